@@ -0,0 +1,210 @@
+use anyhow::{anyhow, Result};
+use serde_json::{Map, Value};
+use sha2::{Digest, Sha256};
+
+use crate::models::{DecodedAccount, DiscriminatorMismatch};
+use crate::rent::{parse_array, split_generic, AccountSchemas, Field, TypeDef};
+
+const DISCRIMINATOR_LEN: usize = 8;
+// Guards against recursive type definitions
+const MAX_DEPTH: u32 = 16;
+
+// Anchor's account discriminator: the first 8 bytes of sha256("account:<Name>")
+pub fn account_discriminator(name: &str) -> [u8; DISCRIMINATOR_LEN] {
+    let hash = Sha256::digest(format!("account:{}", name).as_bytes());
+    let mut discriminator = [0u8; DISCRIMINATOR_LEN];
+    discriminator.copy_from_slice(&hash[..DISCRIMINATOR_LEN]);
+    discriminator
+}
+
+// Decode account data with the program's schemas, as the account type its discriminator
+// names or as `account_type`. Decoding stops at the first field it can't read, e.g. one of
+// a type defined outside the repository, and keeps the fields before it.
+pub fn decode_account_data(schemas: &AccountSchemas, data: &[u8], account_type: Option<&str>) -> Result<DecodedAccount> {
+    let discriminator = data.get(..DISCRIMINATOR_LEN)
+        .ok_or_else(|| anyhow!("Account data is {} bytes, too short for a discriminator", data.len()))?;
+    let discriminator_type = schemas.accounts.iter().find(|a| account_discriminator(&a.name) == discriminator);
+    let account = match account_type {
+        Some(name) => Some(schemas.accounts.iter().find(|a| a.name == name).ok_or_else(|| {
+            let names: Vec<&str> = schemas.accounts.iter().map(|a| a.name.as_str()).collect();
+            anyhow!("Unknown account type {}; the program defines: {}", name, names.join(", "))
+        })?),
+        None => discriminator_type,
+    };
+
+    let mut decoded = DecodedAccount {
+        address: None,
+        owner: None,
+        lamports: None,
+        data_len: data.len(),
+        discriminator: hex(discriminator),
+        account_type: account.map(|a| a.name.clone()),
+        fields: None,
+        discriminator_mismatch: None,
+        unused_bytes: data.len() - DISCRIMINATOR_LEN,
+        warnings: Vec::new(),
+    };
+    let Some(account) = account else {
+        decoded.warnings.push("Discriminator matches none of the program's account types".to_string());
+        return Ok(decoded);
+    };
+    if discriminator_type.map(|a| &a.name) != Some(&account.name) {
+        decoded.discriminator_mismatch = Some(DiscriminatorMismatch {
+            expected: hex(&account_discriminator(&account.name)),
+            actual: hex(discriminator),
+            actual_type: discriminator_type.map(|a| a.name.clone()),
+        });
+    }
+    let Some(TypeDef::Struct(fields)) = schemas.types.get(&account.name) else {
+        decoded.warnings.push(format!("No definition found for {}", account.name));
+        return Ok(decoded);
+    };
+
+    let mut decoder = Decoder { schemas, data, pos: DISCRIMINATOR_LEN, zero_copy: account.zero_copy };
+    let mut values = Map::new();
+    if let Err(e) = decoder.fields(fields, &mut values, 0) {
+        decoded.warnings.push(format!("Stopped decoding: {}", e));
+    }
+    decoded.fields = Some(Value::Object(values));
+    decoded.unused_bytes = decoder.remaining();
+    Ok(decoded)
+}
+
+// Reads Borsh for regular accounts and repr(C) for zero-copy ones, mirroring how
+// the rent estimate sizes them
+struct Decoder<'a> {
+    schemas: &'a AccountSchemas,
+    data: &'a [u8],
+    pos: usize,
+    zero_copy: bool,
+}
+
+impl Decoder<'_> {
+    // Decodes into `values` as it goes, so what precedes a failing field is kept
+    fn fields(&mut self, fields: &[Field], values: &mut Map<String, Value>, depth: u32) -> Result<()> {
+        let start = self.pos;
+        for field in fields {
+            if self.zero_copy {
+                let (_, align) = self.schemas.layout(&field.ty, depth + 1)
+                    .ok_or_else(|| anyhow!("field `{}` has type {}, which can't be zero-copy", field.name, field.ty))?;
+                self.skip_to(start + (self.pos - start).next_multiple_of(align as usize))?;
+            }
+            let value = self.value(&field.ty, depth + 1)
+                .map_err(|e| anyhow!("field `{}` at offset {}: {}", field.name, self.pos, e))?;
+            values.insert(field.name.clone(), value);
+        }
+        Ok(())
+    }
+
+    fn value(&mut self, ty: &str, depth: u32) -> Result<Value> {
+        if depth > MAX_DEPTH {
+            return Err(anyhow!("types nest too deeply"));
+        }
+        if let Some((element, len)) = parse_array(ty) {
+            let len = self.schemas.array_len(len).ok_or_else(|| anyhow!("unknown array length {}", len))?;
+            return (0..len).map(|_| self.value(element, depth + 1)).collect::<Result<Vec<_>>>().map(Value::Array);
+        }
+
+        let (name, args) = split_generic(ty);
+        match (name, args.as_slice()) {
+            ("bool", []) => match self.take(1)?[0] {
+                0 => Ok(Value::Bool(false)),
+                1 => Ok(Value::Bool(true)),
+                other => Err(anyhow!("invalid bool {}", other)),
+            },
+            ("u8", []) => Ok(self.take(1)?[0].into()),
+            ("i8", []) => Ok((self.take(1)?[0] as i8).into()),
+            ("u16", []) => Ok(u16::from_le_bytes(self.array()?).into()),
+            ("i16", []) => Ok(i16::from_le_bytes(self.array()?).into()),
+            ("u32", []) => Ok(u32::from_le_bytes(self.array()?).into()),
+            ("i32", []) => Ok(i32::from_le_bytes(self.array()?).into()),
+            ("u64", []) => Ok(u64::from_le_bytes(self.array()?).into()),
+            ("i64", []) => Ok(i64::from_le_bytes(self.array()?).into()),
+            ("f32", []) => Ok(f32::from_le_bytes(self.array()?).into()),
+            ("f64", []) => Ok(f64::from_le_bytes(self.array()?).into()),
+            // Beyond what JSON numbers hold exactly
+            ("u128", []) => Ok(u128::from_le_bytes(self.array()?).to_string().into()),
+            ("i128", []) => Ok(i128::from_le_bytes(self.array()?).to_string().into()),
+            ("Pubkey", []) => Ok(bs58::encode(self.take(32)?).into_string().into()),
+            ("String", []) if !self.zero_copy => {
+                let len = self.len()?;
+                Ok(String::from_utf8(self.take(len)?.to_vec())?.into())
+            },
+            ("Vec", [element]) if !self.zero_copy => {
+                let len = self.len()?;
+                (0..len).map(|_| self.value(element, depth + 1)).collect::<Result<Vec<_>>>().map(Value::Array)
+            },
+            ("Option", [inner]) if !self.zero_copy => match self.take(1)?[0] {
+                0 => Ok(Value::Null),
+                1 => self.value(inner, depth + 1),
+                other => Err(anyhow!("invalid Option tag {}", other)),
+            },
+            ("Box", [inner]) => self.value(inner, depth + 1),
+            _ => match self.schemas.types.get(name) {
+                Some(TypeDef::Struct(fields)) => {
+                    let start = self.pos;
+                    let mut values = Map::new();
+                    self.fields(fields, &mut values, depth + 1)?;
+                    // Trailing padding up to the struct's alignment
+                    if self.zero_copy {
+                        if let Some((size, _)) = self.schemas.layout(ty, depth + 1) {
+                            self.skip_to(start.saturating_add(size as usize))?;
+                        }
+                    }
+                    Ok(Value::Object(values))
+                },
+                Some(TypeDef::Enum(variants)) if !self.zero_copy => {
+                    let index = self.take(1)?[0];
+                    let variant = variants.get(index as usize)
+                        .ok_or_else(|| anyhow!("variant {} of {} doesn't exist", index, name))?;
+                    if variant.fields.is_empty() {
+                        return Ok(variant.name.clone().into());
+                    }
+                    let mut values = Map::new();
+                    self.fields(&variant.fields, &mut values, depth + 1)?;
+                    let mut tagged = Map::new();
+                    tagged.insert(variant.name.clone(), Value::Object(values));
+                    Ok(Value::Object(tagged))
+                },
+                _ => Err(anyhow!("can't decode type {}", ty)),
+            },
+        }
+    }
+
+    fn take(&mut self, len: usize) -> Result<&[u8]> {
+        let bytes = self.data.get(self.pos..self.pos.saturating_add(len))
+            .ok_or_else(|| anyhow!("needs {} bytes, {} left", len, self.remaining()))?;
+        self.pos += len;
+        Ok(bytes)
+    }
+
+    // Past zero-copy padding, which must still be within the data
+    fn skip_to(&mut self, pos: usize) -> Result<()> {
+        if pos > self.data.len() {
+            return Err(anyhow!("padding to offset {} runs past the {} bytes of data", pos, self.data.len()));
+        }
+        self.pos = pos.max(self.pos);
+        Ok(())
+    }
+
+    fn remaining(&self) -> usize {
+        self.data.len().saturating_sub(self.pos)
+    }
+
+    fn array<const N: usize>(&mut self) -> Result<[u8; N]> {
+        Ok(self.take(N)?.try_into()?)
+    }
+
+    // Borsh's u32 length prefix, checked against what's left so a garbage length fails fast
+    fn len(&mut self) -> Result<usize> {
+        let len = u32::from_le_bytes(self.array()?) as usize;
+        if len > self.remaining() {
+            return Err(anyhow!("length {} exceeds the {} bytes left", len, self.remaining()));
+        }
+        Ok(len)
+    }
+}
+
+fn hex(bytes: &[u8]) -> String {
+    bytes.iter().map(|b| format!("{:02x}", b)).collect()
+}
@@ -0,0 +1,403 @@
+use anyhow::Result;
+use regex::Regex;
+use std::collections::{BTreeMap, BTreeSet, HashMap, HashSet};
+use std::fmt::Write;
+use std::path::Path;
+
+use crate::instructions::{brace_depth, paren_body, program_instructions, program_sources, strip_attributes, ProgramSource};
+use crate::models::{AccountGraph, AccountKind, AccountNode, AccountRelation, GraphEdge, InstructionNode};
+use crate::rent::{block_body, split_generic, top_level_split};
+
+// The accounts of every program in the repository, how they relate to each other and which
+// instructions read or write them. Anchor accounts come from #[derive(Accounts)] structs and
+// their constraints; native ones from the numbered account docs of instruction variants.
+pub fn build_account_graph(repo_path: &Path) -> Result<AccountGraph> {
+    let parser = Parser::new();
+    let mut graph = GraphBuilder::default();
+    for program in program_sources(repo_path)? {
+        if program.is_anchor {
+            parser.anchor_accounts(&program, &mut graph);
+        } else {
+            parser.native_accounts(&program, &mut graph);
+        }
+    }
+    Ok(graph.finish())
+}
+
+// Graphviz source for `graph`, one cluster per program. Instructions are boxes, and
+// relations between accounts are dashed.
+pub fn to_dot(graph: &AccountGraph) -> String {
+    let mut dot = String::from("digraph accounts {\n    rankdir=LR;\n    node [fontname=\"Helvetica\"];\n    edge [fontname=\"Helvetica\", fontsize=10];\n");
+    let mut programs: BTreeMap<&str, Vec<String>> = BTreeMap::new();
+    for instruction in &graph.instructions {
+        programs.entry(&instruction.program).or_default().push(format!(
+            "{} [label={}, shape=box, style=filled, fillcolor=\"#e8eef7\"];",
+            quote(&instruction.id), quote(&instruction.name),
+        ));
+    }
+    for account in &graph.accounts {
+        let label = match &account.account_type {
+            Some(ty) if *ty != account.name => format!("{}\n{}", account.name, ty),
+            _ => account.name.clone(),
+        };
+        programs.entry(&account.program).or_default().push(format!(
+            "{} [label={}, shape={}];",
+            quote(&account.id), quote(&label), node_shape(account.kind),
+        ));
+    }
+    for (program, nodes) in programs {
+        let _ = writeln!(dot, "    subgraph {} {{\n        label={};", quote(&format!("cluster_{}", program)), quote(program));
+        for node in nodes {
+            let _ = writeln!(dot, "        {}", node);
+        }
+        dot.push_str("    }\n");
+    }
+    for edge in &graph.edges {
+        let style = if is_instruction_relation(edge.relation) { "solid" } else { "dashed" };
+        let _ = writeln!(dot, "    {} -> {} [label={}, style={}];", quote(&edge.from), quote(&edge.to), quote(relation_label(edge.relation)), style);
+    }
+    dot.push_str("}\n");
+    dot
+}
+
+fn quote(text: &str) -> String {
+    format!("\"{}\"", text.replace('\\', "\\\\").replace('"', "\\\"").replace('\n', "\\n"))
+}
+
+fn node_shape(kind: AccountKind) -> &'static str {
+    match kind {
+        AccountKind::State => "ellipse",
+        AccountKind::Pda => "hexagon",
+        AccountKind::TokenAccount => "cylinder",
+        AccountKind::Mint => "doubleoctagon",
+        AccountKind::Authority => "house",
+        AccountKind::Program => "component",
+        AccountKind::Sysvar => "note",
+        AccountKind::Other => "oval",
+    }
+}
+
+fn is_instruction_relation(relation: AccountRelation) -> bool {
+    matches!(relation, AccountRelation::Reads | AccountRelation::Writes | AccountRelation::Initializes | AccountRelation::Closes | AccountRelation::Signs)
+}
+
+fn relation_label(relation: AccountRelation) -> &'static str {
+    match relation {
+        AccountRelation::Reads => "reads",
+        AccountRelation::Writes => "writes",
+        AccountRelation::Initializes => "initializes",
+        AccountRelation::Closes => "closes",
+        AccountRelation::Signs => "signs",
+        AccountRelation::HasOne => "has_one",
+        AccountRelation::Authority => "authority",
+        AccountRelation::Mint => "mint",
+        AccountRelation::Seed => "seed",
+    }
+}
+
+#[derive(Default)]
+struct GraphBuilder {
+    accounts: BTreeMap<String, AccountNode>,
+    instructions: Vec<InstructionNode>,
+    edges: BTreeSet<(String, String, AccountRelation)>,
+}
+
+impl GraphBuilder {
+    fn instruction(&mut self, program: &str, name: &str) -> String {
+        let id = format!("instruction:{}/{}", program, name);
+        if !self.instructions.iter().any(|i| i.id == id) {
+            self.instructions.push(InstructionNode { id: id.clone(), program: program.to_string(), name: name.to_string() });
+        }
+        id
+    }
+
+    // Accounts are identified by name within a program, so the `vault` of one instruction is
+    // the `vault` of the others. What one instruction leaves unknown another may tell.
+    fn account(&mut self, program: &str, name: &str, kind: AccountKind, account_type: Option<String>, seeds: Option<Vec<String>>) -> String {
+        let id = format!("account:{}/{}", program, name);
+        let node = self.accounts.entry(id.clone()).or_insert_with(|| AccountNode {
+            id: id.clone(),
+            program: program.to_string(),
+            name: name.to_string(),
+            kind,
+            account_type: None,
+            seeds: None,
+        });
+        if node.kind == AccountKind::Other {
+            node.kind = kind;
+        }
+        node.account_type = node.account_type.take().or(account_type);
+        node.seeds = node.seeds.take().or(seeds);
+        id
+    }
+
+    fn edge(&mut self, from: &str, to: &str, relation: AccountRelation) {
+        self.edges.insert((from.to_string(), to.to_string(), relation));
+    }
+
+    fn finish(mut self) -> AccountGraph {
+        // Untyped accounts others name as their authority are authorities themselves
+        for (_, to, relation) in &self.edges {
+            if *relation != AccountRelation::Authority {
+                continue;
+            }
+            if let Some(node) = self.accounts.get_mut(to).filter(|node| node.kind == AccountKind::Other) {
+                node.kind = AccountKind::Authority;
+            }
+        }
+        AccountGraph {
+            accounts: self.accounts.into_values().collect(),
+            instructions: self.instructions,
+            edges: self.edges.into_iter().map(|(from, to, relation)| GraphEdge { from, to, relation }).collect(),
+        }
+    }
+}
+
+// A field of a #[derive(Accounts)] struct with its #[account(...)] constraints
+struct AccountField {
+    name: String,
+    ty: String,
+    // Constraint keys and values in order, as keys like has_one repeat; flags like `mut` have
+    // an empty value
+    constraints: Vec<(String, String)>,
+}
+
+impl AccountField {
+    fn has(&self, key: &str) -> bool {
+        self.constraints.iter().any(|(k, _)| k == key)
+    }
+
+    // The accounts a constraint names, without custom errors (`has_one = owner @ Error`)
+    fn targets<'a>(&'a self, key: &'a str) -> impl Iterator<Item = &'a str> {
+        self.constraints.iter()
+            .filter(move |(k, _)| k == key)
+            .map(|(_, value)| value.split('@').next().unwrap_or_default().trim())
+            .filter(|target| !target.is_empty())
+    }
+
+    fn seeds(&self) -> Option<Vec<String>> {
+        let seeds = self.constraints.iter().find(|(k, _)| k == "seeds")?.1.trim();
+        let seeds = seeds.strip_prefix('[').and_then(|s| s.strip_suffix(']')).unwrap_or(seeds);
+        Some(top_level_split(seeds, ',').into_iter().map(|seed| seed.split_whitespace().collect::<Vec<_>>().join(" ")).collect())
+    }
+
+    fn kind(&self) -> (AccountKind, Option<String>) {
+        let (mut wrapper, mut args) = split_generic(&self.ty);
+        while matches!(wrapper, "Box" | "Option") {
+            let Some(inner) = args.first() else { break };
+            (wrapper, args) = split_generic(inner);
+        }
+        let inner = args.first().map(|arg| split_generic(arg).0.to_string());
+        let is_pda = self.has("seeds");
+        let kind = match wrapper {
+            "Signer" => AccountKind::Authority,
+            "Program" | "Interface" => AccountKind::Program,
+            "Sysvar" => AccountKind::Sysvar,
+            "Account" | "AccountLoader" | "InterfaceAccount" => match inner.as_deref() {
+                Some("TokenAccount") => AccountKind::TokenAccount,
+                Some("Mint") => AccountKind::Mint,
+                _ if is_pda => AccountKind::Pda,
+                _ => AccountKind::State,
+            },
+            _ if self.constraints.iter().any(|(k, _)| k.starts_with("token::") || k.starts_with("associated_token::")) => AccountKind::TokenAccount,
+            _ if self.constraints.iter().any(|(k, _)| k.starts_with("mint::")) => AccountKind::Mint,
+            _ if is_pda => AccountKind::Pda,
+            _ => AccountKind::Other,
+        };
+        let account_type = match wrapper {
+            "Signer" | "UncheckedAccount" | "AccountInfo" | "SystemAccount" => None,
+            _ => inner,
+        };
+        (kind, account_type)
+    }
+
+    fn instruction_relation(&self) -> AccountRelation {
+        if self.has("init") || self.has("init_if_needed") {
+            AccountRelation::Initializes
+        } else if self.has("close") {
+            AccountRelation::Closes
+        } else if self.has("mut") {
+            AccountRelation::Writes
+        } else {
+            AccountRelation::Reads
+        }
+    }
+}
+
+// One of the numbered accounts in a native instruction's docs
+struct DocAccount {
+    name: String,
+    writable: bool,
+    signer: bool,
+}
+
+struct Parser {
+    comment: Regex,
+    accounts_struct: Regex,
+    account_attribute: Regex,
+    field: Regex,
+    key_reference: Regex,
+    instruction_enum: Regex,
+    // `///   0. `[writable, signer]` Payer account`
+    account_doc: Regex,
+    variant: Regex,
+}
+
+impl Parser {
+    fn new() -> Self {
+        Self {
+            comment: Regex::new(r"//[^\n]*").unwrap(),
+            accounts_struct: Regex::new(r"(?m)((?:^[ \t]*#\[[^\n]*\][ \t]*\n)*)^[ \t]*pub(?:\([^)]*\))?\s+struct\s+(\w+)(?:<[^>{]*>)?\s*\{").unwrap(),
+            account_attribute: Regex::new(r"#\[\s*account\s*\(").unwrap(),
+            field: Regex::new(r"(?s)^(?:pub(?:\([^)]*\))?\s+)?(\w+)\s*:\s*(.+)$").unwrap(),
+            key_reference: Regex::new(r"\b([a-z_][a-z0-9_]*)\s*\.\s*key\s*\(").unwrap(),
+            instruction_enum: Regex::new(r"pub\s+enum\s+\w*Instruction\w*\s*\{").unwrap(),
+            account_doc: Regex::new(r"^\s*///\s*\d+\.\s*(?:`\[([^\]]*)\]`)?\s*(.*)$").unwrap(),
+            variant: Regex::new(r"^\s*([A-Z]\w*)\s*(\{|\(|,|$)").unwrap(),
+        }
+    }
+
+    fn anchor_accounts(&self, program: &ProgramSource, graph: &mut GraphBuilder) {
+        let mut structs: HashMap<String, Vec<AccountField>> = HashMap::new();
+        for (_, source) in &program.sources {
+            let source = self.comment.replace_all(source, "");
+            for captures in self.accounts_struct.captures_iter(&source) {
+                if !captures[1].lines().any(|a| a.contains("derive") && a.contains("Accounts")) {
+                    continue;
+                }
+                let open = captures.get(0).unwrap().end() - 1;
+                if let Some(body) = block_body(&source, open) {
+                    structs.insert(captures[2].to_string(), self.account_fields(body));
+                }
+            }
+        }
+
+        for instruction in program_instructions(program) {
+            let Some(fields) = instruction.accounts_struct.as_ref().and_then(|name| structs.get(name)) else {
+                continue;
+            };
+            let instruction_id = graph.instruction(&program.name, &instruction.name);
+            let names: HashSet<&str> = fields.iter().map(|f| f.name.as_str()).collect();
+            let account_id = |graph: &mut GraphBuilder, name: &str| graph.account(&program.name, name, AccountKind::Other, None, None);
+
+            for field in fields {
+                let (kind, account_type) = field.kind();
+                let seeds = field.seeds();
+                let id = graph.account(&program.name, &field.name, kind, account_type, seeds.clone());
+                graph.edge(&instruction_id, &id, field.instruction_relation());
+                if kind == AccountKind::Authority || field.has("signer") {
+                    graph.edge(&instruction_id, &id, AccountRelation::Signs);
+                }
+
+                let related = [
+                    ("has_one", AccountRelation::HasOne),
+                    ("token::authority", AccountRelation::Authority),
+                    ("associated_token::authority", AccountRelation::Authority),
+                    ("mint::authority", AccountRelation::Authority),
+                    ("token::mint", AccountRelation::Mint),
+                    ("associated_token::mint", AccountRelation::Mint),
+                ];
+                for (key, relation) in related {
+                    for target in field.targets(key).filter(|target| names.contains(target)) {
+                        let target = account_id(graph, target);
+                        graph.edge(&id, &target, relation);
+                    }
+                }
+                for seed in seeds.iter().flatten() {
+                    for reference in self.key_reference.captures_iter(seed) {
+                        if names.contains(&reference[1]) && reference[1] != *field.name {
+                            let target = account_id(graph, &reference[1]);
+                            graph.edge(&id, &target, AccountRelation::Seed);
+                        }
+                    }
+                }
+            }
+        }
+    }
+
+    fn account_fields(&self, body: &str) -> Vec<AccountField> {
+        top_level_split(body, ',').into_iter().filter_map(|chunk| {
+            let declaration = strip_attributes(chunk);
+            let captures = self.field.captures(declaration.trim())?;
+            let constraints = self.account_attribute.find(chunk)
+                .and_then(|attribute| paren_body(chunk, attribute.end() - 1))
+                .map(|args| top_level_split(args, ',').into_iter().map(|arg| {
+                    match arg.split_once('=') {
+                        // `==` in a `constraint = a == b` expression belongs to the value
+                        Some((key, value)) if !key.ends_with(['!', '<', '>']) => (key.trim().to_string(), value.trim().to_string()),
+                        _ => (arg.split('@').next().unwrap_or_default().trim().to_string(), String::new()),
+                    }
+                }).collect())
+                .unwrap_or_default();
+            Some(AccountField {
+                name: captures[1].to_string(),
+                ty: captures[2].chars().filter(|c| !c.is_whitespace()).collect(),
+                constraints,
+            })
+        }).collect()
+    }
+
+    fn native_accounts(&self, program: &ProgramSource, graph: &mut GraphBuilder) {
+        for (_, source) in &program.sources {
+            for found in self.instruction_enum.find_iter(source) {
+                let Some(body) = block_body(source, found.end() - 1) else {
+                    continue;
+                };
+                let (mut pending, mut depth) = (Vec::new(), 0);
+                for line in body.lines() {
+                    if depth == 0 {
+                        if let Some(doc) = self.account_doc.captures(line) {
+                            pending.push(doc_account(pending.len(), doc.get(1).map_or("", |f| f.as_str()), &doc[2]));
+                        } else if let Some(variant) = self.variant.captures(line) {
+                            let instruction_id = graph.instruction(&program.name, &variant[1]);
+                            for account in pending.drain(..) {
+                                let kind = doc_account_kind(&account);
+                                let id = graph.account(&program.name, &account.name, kind, None, None);
+                                let relation = if account.writable { AccountRelation::Writes } else { AccountRelation::Reads };
+                                graph.edge(&instruction_id, &id, relation);
+                                if account.signer {
+                                    graph.edge(&instruction_id, &id, AccountRelation::Signs);
+                                }
+                            }
+                        }
+                    }
+                    depth += brace_depth(line);
+                }
+            }
+        }
+    }
+}
+
+// The description names the account: `Payer account.` becomes `payer_account`
+fn doc_account(index: usize, flags: &str, description: &str) -> DocAccount {
+    let flags: Vec<String> = flags.split(',').map(|f| f.trim().to_lowercase()).collect();
+    let words: Vec<String> = description.split(|c: char| !c.is_alphanumeric() && c != '_')
+        .filter(|word| !word.is_empty())
+        .take(4)
+        .map(str::to_lowercase)
+        .collect();
+    DocAccount {
+        name: if words.is_empty() { format!("account_{}", index) } else { words.join("_") },
+        writable: flags.iter().any(|f| f == "writable" || f == "write" || f == "w"),
+        signer: flags.iter().any(|f| f == "signer" || f == "s"),
+    }
+}
+
+fn doc_account_kind(account: &DocAccount) -> AccountKind {
+    let name = &account.name;
+    if account.signer {
+        AccountKind::Authority
+    } else if name.contains("sysvar") {
+        AccountKind::Sysvar
+    } else if name.contains("program") {
+        AccountKind::Program
+    } else if name.contains("mint") {
+        AccountKind::Mint
+    } else if name.contains("token_account") {
+        AccountKind::TokenAccount
+    } else if name.contains("pda") {
+        AccountKind::Pda
+    } else {
+        AccountKind::Other
+    }
+}
@@ -1,168 +1,607 @@
 use anyhow::{anyhow, Result};
 use regex::Regex;
+use sha2::{Digest, Sha256};
+use std::collections::{BTreeSet, HashMap, HashSet};
+use std::env;
+use std::io::{BufRead, BufReader};
 use std::path::Path;
-use std::process::Command;
+use std::process::{Command, Stdio};
+use std::sync::mpsc::{self, RecvTimeoutError};
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+use toml::Table;
 
-use crate::models::{CodeBug, BugSeverity};
+use crate::autofix::{apply_edits, unified_diff};
+use crate::config;
+use crate::cpi::{cpi_surface, CONFIG_FILE as CPI_CONFIG_FILE};
+use crate::msrv::toolchain_risk;
+use crate::models::{AnalysisEvent, AnalysisMode, CodeBug, BugSeverity, ProgressEvent, ProjectType, TextEdit};
+use crate::exclusions::PathExclusions;
+use crate::external::ExternalAnalyzers;
+use crate::rules::{self, current_rule_set, RuleSet};
+use crate::sandbox::Sandbox;
+use crate::stats::line_at;
+use crate::taint::{find_tainted_sinks, tainted_sinks_in, Sink, TaintFinding};
+use crate::vendor::is_vendored_crate;
 
-pub struct CodeAnalyzer;
+// Features generated by `anchor init`; no-entrypoint and friends only strip code for CPI clients
+const ANCHOR_FEATURES: &[&str] = &["no-entrypoint", "no-idl", "no-log-ix-name", "cpi", "idl-build", "anchor-debug", "custom-heap", "custom-panic"];
+
+// Time a quick scan's rule passes get, for interactive use next to a queued full scan
+const QUICK_SCAN_BUDGET: Duration = Duration::from_secs(10);
+// Default for SAFEX_DEEP_SCAN_BUDGET_SECS
+const DEEP_SCAN_BUDGET_SECS: u64 = 30 * 60;
+// Lints on top of clippy's defaults in deep scans
+const DEEP_CLIPPY_GROUPS: &[&str] = &["clippy::pedantic", "clippy::nursery"];
+// How often a running cargo is checked against the scan budget while it's quiet
+const BUDGET_POLL_INTERVAL: Duration = Duration::from_millis(200);
+
+// Addresses a workspace program must never declare as its own
+const WELL_KNOWN_PROGRAM_IDS: &[(&str, &str)] = &[
+    ("11111111111111111111111111111111", "System Program"),
+    ("TokenkegQfeZyiNwAJbNbGKPFXCWuBvf9Ss623VQ5DA", "SPL Token"),
+    ("TokenzQdBNbLqP5VEhdkAS6EPFLC1PHnBqCXEpPxuEb", "SPL Token-2022"),
+    ("ATokenGPvbdGVxr1b2hvZbsiqW5xWH25efTNsLJA8knL", "Associated Token Account"),
+    ("MemoSq4gqABAXKb96qnH8TysNcWxMyWCqXgDLGmfcHr", "SPL Memo"),
+    ("Memo1UhkJRfHyvLMcVucJwxXeuD728EqVDDwQDxFMNo", "SPL Memo v1"),
+    ("metaqbxxUerdq28cj1RbAWkYQm3ybzjb6a8bt518x1s", "Metaplex Token Metadata"),
+    ("BPFLoaderUpgradeab1e11111111111111111111111", "BPF Upgradeable Loader"),
+    ("BPFLoader2111111111111111111111111111111111", "BPF Loader 2"),
+    ("BPFLoader1111111111111111111111111111111111", "BPF Loader"),
+    ("ComputeBudget111111111111111111111111111111", "Compute Budget"),
+    ("AddressLookupTab1e1111111111111111111111111", "Address Lookup Table"),
+    ("Config1111111111111111111111111111111111111", "Config Program"),
+    ("Stake11111111111111111111111111111111111111", "Stake Program"),
+    ("Vote111111111111111111111111111111111111111", "Vote Program"),
+    ("Ed25519SigVerify111111111111111111111111111", "Ed25519 Program"),
+    ("KeccakSecp256k11111111111111111111111111111", "Secp256k1 Program"),
+    ("Fg6PaFpoGXkYsidMpWTK6W2BeZ7FEfcYkg476zPFsLnS", "Anchor's `anchor init` placeholder"),
+];
+
+// Told about an analysis as it runs, e.g. to stream its findings to clients
+pub type AnalysisEventSink = Arc<dyn Fn(&AnalysisEvent) + Send + Sync>;
+
+pub struct CodeAnalyzer {
+    rule_set: RuleSet,
+    // SAFEX_DISABLED_RULES when the analyzer was created; a config reload doesn't change a
+    // running analysis
+    disabled_rules: BTreeSet<String>,
+    // Paths no pass looks at and no finding is reported in
+    exclusions: PathExclusions,
+}
 
 impl CodeAnalyzer {
     pub fn new() -> Self {
-        Self {}
+        Self::with_rule_set(current_rule_set())
+    }
+
+    // Analyze with a pinned rule set instead of the newest
+    pub fn with_rule_set(rule_set: RuleSet) -> Self {
+        Self { rule_set, disabled_rules: config::current().disabled_rules.clone(), exclusions: PathExclusions::default() }
     }
 
-    // Run analysis on the repository
-    pub fn analyze_repo(&self, repo_path: &Path) -> Result<Vec<CodeBug>> {
+    // Leave the excluded paths of the repository out of the analysis
+    pub fn excluding(self, exclusions: PathExclusions) -> Self {
+        Self { exclusions, ..self }
+    }
+
+    fn reports(&self, bug: &CodeBug) -> bool {
+        self.rule_set.includes(bug)
+            && !bug.rule_id.as_ref().is_some_and(|id| self.disabled_rules.contains(id))
+            && !bug.file.as_ref().is_some_and(|file| self.exclusions.excludes(Path::new(file)))
+    }
+
+    // Run analysis on the repository. Each pass's findings go to `events` once it's done, so
+    // they can be shown before the slow passes are; all of them are returned at the end.
+    #[tracing::instrument(name = "analyze_repo", skip(self, external, sandbox, events))]
+    pub fn analyze_repo(&self, repo_path: &Path, project_type: ProjectType, mode: AnalysisMode, external: &ExternalAnalyzers, sandbox: &Sandbox, events: &mut dyn FnMut(AnalysisEvent)) -> Result<Vec<CodeBug>> {
         println!("Analyzing repository at: {}", repo_path.display());
         
-        // Create a default set of bugs in case analysis fails
-        let mut all_bugs = Vec::new();
+        // Quick and deep scans stop starting passes once their budget is spent
+        let mut passes = Passes::new(self, mode, events);
+        let deadline = passes.deadline;
         
-        // Try to run cargo clippy
-        match self.run_cargo_clippy(repo_path) {
-            Ok(clippy_bugs) => all_bugs.extend(clippy_bugs),
-            Err(e) => {
+        // A .safex.toml that can't be read doesn't stop the analysis, but shouldn't go unnoticed
+        if let Some(problem) = self.exclusions.problem() {
+            println!("Warning: Ignoring the repository's exclude_paths: {}", problem);
+            passes.report(vec![CodeBug {
+                bug: format!("Invalid exclude_paths in {}: {}", CPI_CONFIG_FILE, problem),
+                line: 0,
+                file: Some(CPI_CONFIG_FILE.to_string()),
+                severity: BugSeverity::Low,
+                fix: "Make [analysis] exclude_paths a list of globs that use only *, ** and ? as wildcards".to_string(),
+                ..Default::default()
+            }]);
+        }
+        
+        // Try to run cargo clippy; quick scans skip anything that compiles. Each finding is
+        // handed over as soon as its line of output is parsed.
+        if mode != AnalysisMode::Quick && passes.start("clippy") {
+            if let Err(e) = self.run_cargo_clippy(repo_path, sandbox, mode, deadline, &mut |bugs| passes.report(bugs)) {
                 println!("Warning: Cargo clippy analysis failed: {}", e);
                 // Add a placeholder bug to indicate the failure
-                all_bugs.push(CodeBug {
+                passes.report(vec![CodeBug {
                     bug: "Failed to run Cargo clippy analysis".to_string(),
                     line: 0,
                     severity: BugSeverity::Low,
                     fix: "Ensure Cargo and Clippy are installed and the project is a valid Rust project".to_string(),
-                });
+                    ..Default::default()
+                }]);
             }
         }
         
-        // Try to run custom Anchor lints
-        match self.run_anchor_lints(repo_path) {
-            Ok(anchor_bugs) => all_bugs.extend(anchor_bugs),
-            Err(e) => {
-                println!("Warning: Anchor lints analysis failed: {}", e);
-                // Add a placeholder bug to indicate the failure
-                all_bugs.push(CodeBug {
-                    bug: "Failed to run Anchor-specific lints".to_string(),
+        // Try to run the framework-specific lints; the Anchor lints report each file's findings
+        // as they go
+        if passes.start("framework lints") {
+            match project_type {
+                ProjectType::Anchor => if let Err(e) = self.run_anchor_lints(repo_path, &mut |bugs| passes.report(bugs)) {
+                    println!("Warning: Anchor lints analysis failed: {}", e);
+                    // Add a placeholder bug to indicate the failure
+                    passes.report(vec![CodeBug {
+                        bug: "Failed to run Anchor-specific lints".to_string(),
+                        line: 0,
+                        severity: BugSeverity::Low,
+                        fix: "Ensure the project is a valid Anchor project".to_string(),
+                        ..Default::default()
+                    }]);
+                },
+                ProjectType::Native => match self.run_native_lints(repo_path) {
+                    Ok(native_bugs) => passes.report(native_bugs),
+                    Err(e) => {
+                        println!("Warning: Native program lints analysis failed: {}", e);
+                        passes.report(vec![CodeBug {
+                            bug: "Failed to run native Solana program lints".to_string(),
+                            line: 0,
+                            severity: BugSeverity::Low,
+                            fix: "Ensure the project is a valid solana-program crate".to_string(),
+                            ..Default::default()
+                        }]);
+                    }
+                },
+            }
+        }
+        
+        // Build profiles and features the code findings don't see
+        if passes.start("build configuration lints") {
+            match self.run_config_lints(repo_path) {
+                Ok(config_bugs) => passes.report(config_bugs),
+                Err(e) => {
+                    println!("Warning: Build configuration lints analysis failed: {}", e);
+                    passes.report(vec![CodeBug {
+                        bug: "Failed to check Cargo profiles and features".to_string(),
+                        line: 0,
+                        severity: BugSeverity::Low,
+                        fix: "Manually review [profile.*] and [features] in the workspace's Cargo.toml files".to_string(),
+                        ..Default::default()
+                    }]);
+                }
+            }
+        }
+        
+        // Program IDs across the workspace and their Anchor.toml mappings
+        if passes.start("program ID lints") {
+            match self.run_program_id_lints(repo_path) {
+                Ok(program_id_bugs) => passes.report(program_id_bugs),
+                Err(e) => {
+                    println!("Warning: Program ID lints analysis failed: {}", e);
+                    passes.report(vec![CodeBug {
+                        bug: "Failed to check workspace program IDs".to_string(),
+                        line: 0,
+                        severity: BugSeverity::Low,
+                        fix: "Manually compare each program's declare_id! with the others and with Anchor.toml".to_string(),
+                        ..Default::default()
+                    }]);
+                }
+            }
+        }
+        
+        // Programs invoked against the repository's declared CPI allowlist
+        if passes.start("CPI allowlist lints") {
+            match self.run_cpi_lints(repo_path) {
+                Ok(cpi_bugs) => passes.report(cpi_bugs),
+                Err(e) => {
+                    println!("Warning: CPI allowlist lints analysis failed: {}", e);
+                    passes.report(vec![CodeBug {
+                        bug: "Failed to check CPI targets against the allowlist".to_string(),
+                        line: 0,
+                        file: Some(CPI_CONFIG_FILE.to_string()),
+                        severity: BugSeverity::Low,
+                        fix: format!("Check that [cpi] allowed_programs in {} is a list of program IDs", CPI_CONFIG_FILE),
+                        ..Default::default()
+                    }]);
+                }
+            }
+        }
+        
+        // Client code and tests that drive the program
+        if passes.start("TypeScript lints") {
+            match self.run_typescript_lints(repo_path) {
+                Ok(typescript_bugs) => passes.report(typescript_bugs),
+                Err(e) => {
+                    println!("Warning: TypeScript lints analysis failed: {}", e);
+                    passes.report(vec![CodeBug {
+                        bug: "Failed to run TypeScript client and test lints".to_string(),
+                        line: 0,
+                        severity: BugSeverity::Low,
+                        fix: "Manually review tests/ and app/ for hard-coded keys and unawaited transactions".to_string(),
+                        ..Default::default()
+                    }]);
+                }
+            }
+        }
+        
+        // The Rust release the code needs against the pinned one, and nightly features
+        if passes.start("toolchain lints") {
+            match self.run_toolchain_lints(repo_path) {
+                Ok(toolchain_bugs) => passes.report(toolchain_bugs),
+                Err(e) => {
+                    println!("Warning: Toolchain lints analysis failed: {}", e);
+                    passes.report(vec![CodeBug {
+                        bug: "Failed to check the pinned Rust toolchain".to_string(),
+                        line: 0,
+                        severity: BugSeverity::Low,
+                        fix: "Manually compare rust-toolchain.toml with the dependencies' rust-version".to_string(),
+                        ..Default::default()
+                    }]);
+                }
+            }
+        }
+        
+        // Type errors behind features the default build doesn't enable
+        if mode == AnalysisMode::Deep && passes.start("cargo check") {
+            // Whatever the clippy run already reported
+            let seen: HashSet<(Option<String>, u32, String)> = passes.found.iter()
+                .map(|b| (b.file.clone(), b.line, b.bug.clone()))
+                .collect();
+            let checked = self.run_cargo_check(repo_path, sandbox, deadline, &mut |bugs| {
+                passes.report(bugs.into_iter().filter(|b| !seen.contains(&(b.file.clone(), b.line, b.bug.clone()))).collect());
+            });
+            if let Err(e) = checked {
+                println!("Warning: cargo check analysis failed: {}", e);
+                passes.report(vec![CodeBug {
+                    bug: "Failed to run cargo check with all features".to_string(),
                     line: 0,
                     severity: BugSeverity::Low,
-                    fix: "Ensure the project is a valid Anchor project".to_string(),
-                });
+                    fix: "Ensure every feature combination of the workspace builds".to_string(),
+                    ..Default::default()
+                }]);
+            }
+        }
+        
+        // Instruction arguments flowing into arithmetic, indexing and casts
+        if mode == AnalysisMode::Deep && passes.start("taint analysis") {
+            match self.run_taint_lints(repo_path) {
+                Ok(taint_bugs) => passes.report(taint_bugs),
+                Err(e) => {
+                    println!("Warning: Taint analysis failed: {}", e);
+                    passes.report(vec![CodeBug {
+                        bug: "Failed to run taint analysis on instruction arguments".to_string(),
+                        line: 0,
+                        severity: BugSeverity::Low,
+                        fix: "Manually review how instruction arguments reach arithmetic, indexing and casts".to_string(),
+                        ..Default::default()
+                    }]);
+                }
             }
         }
         
+        // Third-party tools configured on this host, too slow for quick scans
+        if mode != AnalysisMode::Quick && passes.start("external analyzers") {
+            passes.report(external.run(repo_path, project_type));
+        }
+        
         // Always return success with whatever bugs we found
-        Ok(all_bugs)
+        Ok(passes.finish())
     }
     
-    // Run cargo clippy and parse its output
-    fn run_cargo_clippy(&self, repo_path: &Path) -> Result<Vec<CodeBug>> {
-        println!("Running cargo clippy...");
-        
-        let output = Command::new("cargo")
-            .args(["clippy", "--message-format=json"])
-            .current_dir(repo_path)
-            .output()?;
-            
-        let stdout = String::from_utf8_lossy(&output.stdout);
-        
-        // Parse clippy JSON output
-        self.parse_clippy_output(&stdout)
+    // Every rule pass that reads the source without building it, failing on the first pass
+    // that can't run. For checking the rules themselves against fixtures.
+    pub fn run_source_rules(&self, repo_path: &Path, project_type: ProjectType) -> Result<Vec<CodeBug>> {
+        let mut bugs = Vec::new();
+        match project_type {
+            ProjectType::Anchor => self.run_anchor_lints(repo_path, &mut |found| bugs.extend(found))?,
+            ProjectType::Native => bugs.extend(self.run_native_lints(repo_path)?),
+        }
+        bugs.extend(self.run_config_lints(repo_path)?);
+        bugs.extend(self.run_program_id_lints(repo_path)?);
+        bugs.extend(self.run_cpi_lints(repo_path)?);
+        bugs.extend(self.run_typescript_lints(repo_path)?);
+        bugs.extend(self.run_taint_lints(repo_path)?);
+        bugs.extend(self.run_toolchain_lints(repo_path)?);
+        Ok(bugs)
     }
     
-    // Parse clippy JSON output to extract warnings
-    fn parse_clippy_output(&self, clippy_output: &str) -> Result<Vec<CodeBug>> {
+    // The rules that can judge a single file on its own, over source held in memory: the
+    // Anchor or native account checks (picked by what the code imports) and taint analysis.
+    // Nothing is written to disk, built or run.
+    pub fn analyze_source(&self, file: &str, source: &str) -> Vec<CodeBug> {
         let mut bugs = Vec::new();
+        if source.contains("anchor_lang") || source.contains("#[program]") {
+            self.missing_signer_attribute_in(file, source, &mut bugs);
+        } else {
+            self.native_lints_in(file, source, &mut bugs);
+        }
+        bugs.extend(tainted_sinks_in(file, source).into_iter().map(taint_bug));
+        self.finalize(bugs, &mut HashMap::new())
+    }
+    
+    // Drop what the rule set or SAFEX_DISABLED_RULES leaves out, then fingerprint and classify
+    // the rest. `seen` carries the occurrence counts across the batches of one analysis.
+    fn finalize(&self, mut bugs: Vec<CodeBug>, seen: &mut HashMap<(String, String), u32>) -> Vec<CodeBug> {
+        bugs.retain(|bug| self.reports(bug));
+        self.assign_fingerprints(&mut bugs, seen);
+        for bug in bugs.iter_mut() {
+            bug.taxonomy = bug.rule_id.as_deref().and_then(rules::rule_taxonomy);
+        }
+        bugs
+    }
+    
+    // Run cargo clippy, reporting each finding to `found` as its line of output is parsed
+    #[tracing::instrument(name = "process.cargo_clippy", skip(self, sandbox, found), fields(exit_code))]
+    fn run_cargo_clippy(&self, repo_path: &Path, sandbox: &Sandbox, mode: AnalysisMode, deadline: Option<Instant>, found: &mut dyn FnMut(Vec<CodeBug>)) -> Result<()> {
+        println!("Running cargo clippy...");
         
-        for line in clippy_output.lines() {
-            if line.trim().is_empty() {
-                continue;
-            }
-            
-            match serde_json::from_str::<serde_json::Value>(line) {
-                Ok(json) => {
-                    if let Some(message) = json.get("message") {
-                        if let (Some(text), Some(level)) = (message.get("message"), message.get("level")) {
-                            if level.as_str() == Some("warning") || level.as_str() == Some("error") {
-                                let bug_text = text.as_str().unwrap_or("Unknown issue").to_string();
-                                
-                                // Extract line number
-                                let line_num = if let Some(spans) = message.get("spans") {
-                                    if let Some(span) = spans.as_array().and_then(|s| s.first()) {
-                                        span.get("line_start").and_then(|l| l.as_u64()).unwrap_or(0) as u32
-                                    } else {
-                                        0
-                                    }
-                                } else {
-                                    0
-                                };
-                                
-                                // Determine severity
-                                let severity = if bug_text.contains("unsafe") {
-                                    BugSeverity::High
-                                } else if bug_text.contains("unused") {
-                                    BugSeverity::Low
-                                } else {
-                                    BugSeverity::Medium
-                                };
-                                
-                                // Generate fix suggestion
-                                let fix = self.suggest_fix(&bug_text);
-                                
-                                bugs.push(CodeBug {
-                                    bug: bug_text,
-                                    line: line_num,
-                                    severity,
-                                    fix,
-                                });
-                            }
-                        }
-                    }
-                },
-                Err(e) => {
-                    println!("Warning: Failed to parse clippy JSON output: {}", e);
-                    // Continue processing other lines
-                }
+        // Build scripts and proc macros run during clippy, so dependencies are put in place first
+        sandbox.prepare(repo_path, None);
+        let mut command = sandbox.command("cargo");
+        command.args(["clippy", "--message-format=json"]).current_dir(repo_path);
+        let Some(packages) = self.package_selection(repo_path, sandbox) else {
+            println!("Every package is under an excluded path, skipping clippy");
+            return Ok(());
+        };
+        command.args(packages);
+        if mode == AnalysisMode::Deep {
+            command.arg("--");
+            for group in DEEP_CLIPPY_GROUPS {
+                command.args(["-W", group]);
             }
         }
+        let (mut output, mut parsed) = (false, false);
+        let exit_code = self.run_lines(command, deadline, &mut |line| {
+            output = true;
+            if let Some(bug) = self.clippy_bug(repo_path, line) {
+                parsed = true;
+                found(vec![bug]);
+            }
+        })?;
+        tracing::Span::current().record("exit_code", exit_code);
         
         // If we didn't find any bugs but there was output, add a default bug
-        if bugs.is_empty() && !clippy_output.trim().is_empty() {
-            bugs.push(CodeBug {
+        if output && !parsed {
+            found(vec![CodeBug {
                 bug: "Clippy output could not be parsed".to_string(),
                 line: 0,
                 severity: BugSeverity::Low,
                 fix: "Check the project structure and ensure it's a valid Rust project".to_string(),
-            });
+                ..Default::default()
+            }]);
         }
+        Ok(())
+    }
+    
+    // `cargo check` with every feature enabled, for type errors in code the default features
+    // leave out. Dependencies were prepared for clippy.
+    #[tracing::instrument(name = "process.cargo_check", skip(self, sandbox, found), fields(exit_code))]
+    fn run_cargo_check(&self, repo_path: &Path, sandbox: &Sandbox, deadline: Option<Instant>, found: &mut dyn FnMut(Vec<CodeBug>)) -> Result<()> {
+        println!("Running cargo check --all-features...");
         
-        Ok(bugs)
+        let mut command = sandbox.command("cargo");
+        command.args(["check", "--all-features", "--all-targets", "--message-format=json"]).current_dir(repo_path);
+        let Some(packages) = self.package_selection(repo_path, sandbox) else {
+            return Ok(());
+        };
+        command.args(packages);
+        // rustc's messages have the same shape as clippy's
+        let exit_code = self.run_lines(command, deadline, &mut |line| {
+            if let Some(bug) = self.clippy_bug(repo_path, line) {
+                found(vec![bug]);
+            }
+        })?;
+        tracing::Span::current().record("exit_code", exit_code);
+        Ok(())
     }
     
-    // Run custom Anchor-specific lints
-    fn run_anchor_lints(&self, repo_path: &Path) -> Result<Vec<CodeBug>> {
-        println!("Running custom Anchor lints...");
+    // The cargo arguments that leave out the packages whose library and binaries all sit under
+    // excluded paths: `--workspace --exclude` each of them, or nothing when none are. None when
+    // every package is excluded. Findings in excluded files of the remaining packages are
+    // dropped when they're reported.
+    fn package_selection(&self, repo_path: &Path, sandbox: &Sandbox) -> Option<Vec<String>> {
+        if self.exclusions.patterns().is_empty() {
+            return Some(Vec::new());
+        }
+        let mut command = sandbox.command("cargo");
+        command.args(["metadata", "--no-deps", "--format-version", "1"]).current_dir(repo_path);
+        let metadata = match command.stderr(Stdio::null()).output() {
+            Ok(output) if output.status.success() => serde_json::from_slice::<serde_json::Value>(&output.stdout).ok(),
+            _ => None,
+        };
+        let Some(packages) = metadata.as_ref().and_then(|metadata| metadata["packages"].as_array()) else {
+            println!("Warning: Failed to read the workspace packages, excluded paths are only filtered from findings");
+            return Some(Vec::new());
+        };
+
+        let excluded: Vec<&str> = packages.iter()
+            .filter(|package| {
+                let sources: Vec<&str> = package["targets"].as_array().into_iter().flatten()
+                    .filter(|target| target["kind"].as_array().into_iter().flatten()
+                        .any(|kind| matches!(kind.as_str(), Some("lib" | "rlib" | "cdylib" | "proc-macro" | "bin"))))
+                    .filter_map(|target| target["src_path"].as_str())
+                    .collect();
+                !sources.is_empty() && sources.iter().all(|source| self.exclusions.excludes(Path::new(source)))
+            })
+            .filter_map(|package| package["name"].as_str())
+            .collect();
+        if excluded.is_empty() {
+            return Some(Vec::new());
+        }
+        if excluded.len() == packages.len() {
+            return None;
+        }
+        println!("Leaving excluded packages out of cargo: {}", excluded.join(", "));
+        let mut args = vec!["--workspace".to_string()];
+        for name in excluded {
+            args.extend(["--exclude".to_string(), name.to_string()]);
+        }
+        Some(args)
+    }
+    
+    // Run `command` to completion, handing each non-empty line of its stdout to `on_line` as
+    // it's written, and return its exit code. It's killed at the deadline. The pipe is drained
+    // on its own thread, so a chatty build never stalls on a full pipe.
+    fn run_lines(&self, mut command: Command, deadline: Option<Instant>, on_line: &mut dyn FnMut(&str)) -> Result<Option<i32>> {
+        let mut child = command
+            .stdin(Stdio::null())
+            .stdout(Stdio::piped())
+            .stderr(Stdio::null())
+            .spawn()?;
+        let stdout = child.stdout.take().ok_or_else(|| anyhow!("No stdout from {:?}", command.get_program()))?;
+        let (sender, lines) = mpsc::channel();
+        std::thread::spawn(move || {
+            for line in BufReader::new(stdout).split(b'\n').map_while(|line| line.ok()) {
+                if sender.send(line).is_err() {
+                    break;
+                }
+            }
+        });
+        loop {
+            match lines.recv_timeout(BUDGET_POLL_INTERVAL) {
+                Ok(line) => {
+                    let line = String::from_utf8_lossy(&line);
+                    if !line.trim().is_empty() {
+                        on_line(&line);
+                    }
+                },
+                Err(RecvTimeoutError::Timeout) => {},
+                // Closed with the child's stdout
+                Err(RecvTimeoutError::Disconnected) => break,
+            }
+            if deadline.is_some_and(|deadline| Instant::now() > deadline) {
+                let _ = child.kill();
+                let _ = child.wait();
+                return Err(anyhow!("Ran out of the scan budget"));
+            }
+        }
+        Ok(child.wait()?.code())
+    }
+    
+    // The finding in one line of clippy or rustc JSON output, if it's a warning or error
+    fn clippy_bug(&self, repo_path: &Path, line: &str) -> Option<CodeBug> {
+        let json = match serde_json::from_str::<serde_json::Value>(line) {
+            Ok(json) => json,
+            Err(e) => {
+                println!("Warning: Failed to parse clippy JSON output: {}", e);
+                return None;
+            }
+        };
+        let message = json.get("message")?;
+        let (text, level) = (message.get("message")?, message.get("level")?);
+        if level.as_str() != Some("warning") && level.as_str() != Some("error") {
+            return None;
+        }
+        let bug_text = text.as_str().unwrap_or("Unknown issue").to_string();
         
-        let mut bugs = Vec::new();
+        // Extract line number and file from the primary span
+        let span = message.get("spans")
+            .and_then(|s| s.as_array())
+            .and_then(|s| s.first());
+        let line_num = span
+            .and_then(|span| span.get("line_start"))
+            .and_then(|l| l.as_u64())
+            .unwrap_or(0) as u32;
+        let file = span
+            .and_then(|span| span.get("file_name"))
+            .and_then(|f| f.as_str())
+            .map(|f| f.to_string());
+        
+        // Determine severity
+        let severity = if bug_text.contains("unsafe") {
+            BugSeverity::High
+        } else if bug_text.contains("unused") {
+            BugSeverity::Low
+        } else {
+            BugSeverity::Medium
+        };
+        
+        // Generate fix suggestion, with the diff when clippy can apply it itself
+        let autofix = file.as_deref().and_then(|f| self.machine_applicable_edits(repo_path, f, message));
+        let fix = match &autofix {
+            Some((_, diff)) => format!("{}\n\n{}", self.suggest_fix(&bug_text), diff),
+            None => self.suggest_fix(&bug_text),
+        };
+        
+        Some(CodeBug {
+            bug: bug_text,
+            line: line_num,
+            file,
+            severity,
+            fix,
+            rule_id: Some(rules::CLIPPY.to_string()),
+            autofix: autofix.map(|(edits, _)| edits),
+            ..Default::default()
+        })
+    }
+    
+    // Replacements clippy marks MachineApplicable, with the resulting diff. Suggestions
+    // touching other files than the finding's are left for a human.
+    fn machine_applicable_edits(&self, repo_path: &Path, file: &str, message: &serde_json::Value) -> Option<(Vec<TextEdit>, String)> {
+        let spans = message.get("spans").and_then(|s| s.as_array()).into_iter().flatten()
+            .chain(message.get("children").and_then(|c| c.as_array()).into_iter().flatten()
+                .flat_map(|child| child.get("spans").and_then(|s| s.as_array()).into_iter().flatten()));
+        let suggestions: Vec<&serde_json::Value> = spans
+            .filter(|span| span.get("suggestion_applicability").and_then(|a| a.as_str()) == Some("MachineApplicable"))
+            .filter(|span| span.get("suggested_replacement").is_some_and(|r| r.is_string()))
+            .collect();
+        if suggestions.is_empty() || suggestions.iter().any(|span| span.get("file_name").and_then(|f| f.as_str()) != Some(file)) {
+            return None;
+        }
+        
+        let content = std::fs::read_to_string(repo_path.join(file)).ok()?;
+        let mut edits: Vec<TextEdit> = Vec::new();
+        for span in suggestions {
+            let byte_start = span.get("byte_start")?.as_u64()? as usize;
+            let byte_end = span.get("byte_end")?.as_u64()? as usize;
+            let edit = TextEdit {
+                byte_start,
+                byte_end,
+                original: content.get(byte_start..byte_end)?.to_string(),
+                replacement: span.get("suggested_replacement")?.as_str()?.to_string(),
+            };
+            // The same suggestion is often repeated for every target clippy checks
+            if !edits.iter().any(|e| e.byte_start == edit.byte_start && e.byte_end == edit.byte_end) {
+                edits.push(edit);
+            }
+        }
+        
+        let patched = apply_edits(&content, &edits).ok()?;
+        let diff = unified_diff(file, &content, &patched);
+        Some((edits, diff))
+    }
+    
+    // Run custom Anchor-specific lints, reporting each file's findings to `found` as it's checked
+    fn run_anchor_lints(&self, repo_path: &Path, found: &mut dyn FnMut(Vec<CodeBug>)) -> Result<()> {
+        println!("Running custom Anchor lints...");
         
         // Check for missing #[account(signer)]
-        match self.check_missing_signer_attribute(repo_path, &mut bugs) {
+        match self.check_missing_signer_attribute(repo_path, found) {
             Ok(_) => {},
             Err(e) => {
                 println!("Warning: Failed to check for missing signer attributes: {}", e);
                 // Add a placeholder bug
-                bugs.push(CodeBug {
+                found(vec![CodeBug {
                     bug: "Failed to check for missing #[account(signer)] attributes".to_string(),
                     line: 0,
                     severity: BugSeverity::Medium,
                     fix: "Manually review your code for missing signer attributes".to_string(),
-                });
+                    ..Default::default()
+                }]);
             }
         }
         
-        Ok(bugs)
+        Ok(())
     }
     
     // Check for missing #[account(signer)] attribute
-    fn check_missing_signer_attribute(&self, repo_path: &Path, bugs: &mut Vec<CodeBug>) -> Result<()> {
+    fn check_missing_signer_attribute(&self, repo_path: &Path, found: &mut dyn FnMut(Vec<CodeBug>)) -> Result<()> {
         // Find all Rust files in the project
         let rust_files = self.find_rust_files(repo_path)?;
         
@@ -174,46 +613,658 @@ impl CodeAnalyzer {
                     continue;
                 }
             };
+            let relative_path = self.relative_path(repo_path, &file_path);
+            let mut bugs = Vec::new();
+            self.missing_signer_attribute_in(&relative_path, &content, &mut bugs);
+            if !bugs.is_empty() {
+                found(bugs);
+            }
+        }
+        
+        Ok(())
+    }
+    
+    fn missing_signer_attribute_in(&self, relative_path: &str, content: &str, bugs: &mut Vec<CodeBug>) {
+        // Look for patterns that might indicate missing signer attribute
+        let re_account_struct = Regex::new(r"pub\s+struct\s+(\w+)\s*\{").unwrap();
+        let re_signer_check = Regex::new(r"#\[account\(.*signer.*\)\]").unwrap();
+        
+        // Find account structs
+        for cap in re_account_struct.captures_iter(content) {
+            let struct_name = &cap[1];
             
-            // Look for patterns that might indicate missing signer attribute
-            let re_account_struct = Regex::new(r"pub\s+struct\s+(\w+)\s*\{").unwrap();
-            let re_signer_check = Regex::new(r"#\[account\(.*signer.*\)\]").unwrap();
+            // Check if the struct is used as a signer in any instruction
+            if content.contains(&format!("{}: &Signer", struct_name)) || 
+               content.contains(&format!("{}: Signer", struct_name)) {
+                
+                // Check if it has the signer attribute
+                if !re_signer_check.is_match(content) {
+                    // Get approximate line number
+                    let line_num = line_at(content, cap.get(0).unwrap().start());
+                        
+                    bugs.push(CodeBug {
+                        bug: format!("Missing #[account(signer)] attribute for {}", struct_name),
+                        line: line_num,
+                        file: Some(relative_path.to_string()),
+                        severity: BugSeverity::High,
+                        fix: format!("Add #[account(signer)] attribute to the {} struct", struct_name),
+                        rule_id: Some(rules::ANCHOR_MISSING_SIGNER.to_string()),
+                        ..Default::default()
+                    });
+                }
+            }
+        }
+    }
+    
+    // Run lints for native (non-Anchor) programs, where account validation is manual
+    fn run_native_lints(&self, repo_path: &Path) -> Result<Vec<CodeBug>> {
+        println!("Running native Solana program lints...");
+        
+        let mut bugs = Vec::new();
+        for file_path in self.find_rust_files(repo_path)? {
+            let content = match std::fs::read_to_string(&file_path) {
+                Ok(content) => content,
+                Err(e) => {
+                    println!("Warning: Failed to read file {}: {}", file_path, e);
+                    continue;
+                }
+            };
+            let relative_path = self.relative_path(repo_path, &file_path);
+            self.native_lints_in(&relative_path, &content, &mut bugs);
+        }
+        
+        Ok(bugs)
+    }
+    
+    fn native_lints_in(&self, relative_path: &str, content: &str, bugs: &mut Vec<CodeBug>) {
+        // Only files that actually walk the instruction's account list
+        if !content.contains("next_account_info") {
+            return;
+        }
+        
+        let re_account_var = Regex::new(r"let\s+(\w+)\s*=\s*next_account_info\s*\(").unwrap();
+        let re_deserialize = Regex::new(r"(try_from_slice|unpack|deserialize)\s*\(\s*&?\s*(\w+)\.(data|try_borrow_data)").unwrap();
+        let re_unchecked = Regex::new(r"\b(try_from_slice_unchecked|unpack_unchecked|unpack_from_slice_unchecked|from_bytes_unchecked)\s*\(").unwrap();
+        let re_privileged = Regex::new(r"(?i)(authority|admin|owner|signer|payer)").unwrap();
+        
+        let line_of = |offset: usize| line_at(content, offset);
+        
+        // Missing signer checks on privileged accounts
+        for cap in re_account_var.captures_iter(content) {
+            let name = &cap[1];
+            if re_privileged.is_match(name) && !content.contains(&format!("{}.is_signer", name)) {
+                bugs.push(CodeBug {
+                    bug: format!("Missing signer check: account `{}` is never checked with is_signer", name),
+                    line: line_of(cap.get(0).unwrap().start()),
+                    file: Some(relative_path.to_string()),
+                    severity: BugSeverity::High,
+                    fix: format!("Return MissingRequiredSignature unless {}.is_signer is true", name),
+                    rule_id: Some(rules::NATIVE_MISSING_SIGNER.to_string()),
+                    ..Default::default()
+                });
+            }
+        }
+        
+        // Missing owner checks before deserializing account data
+        for cap in re_deserialize.captures_iter(content) {
+            let name = &cap[2];
+            if !content.contains(&format!("{}.owner", name)) {
+                bugs.push(CodeBug {
+                    bug: format!("Missing owner check: data of account `{}` is deserialized without verifying its owner", name),
+                    line: line_of(cap.get(0).unwrap().start()),
+                    file: Some(relative_path.to_string()),
+                    severity: BugSeverity::High,
+                    fix: format!("Compare {}.owner against the expected program id before deserializing", name),
+                    rule_id: Some(rules::NATIVE_MISSING_OWNER_CHECK.to_string()),
+                    ..Default::default()
+                });
+            }
+        }
+        
+        // Deserialization that skips length/initialization validation
+        for cap in re_unchecked.captures_iter(content) {
+            bugs.push(CodeBug {
+                bug: format!("Unchecked deserialization via {}", &cap[1]),
+                line: line_of(cap.get(0).unwrap().start()),
+                file: Some(relative_path.to_string()),
+                severity: BugSeverity::Medium,
+                fix: "Use the checked variant and validate the account is initialized and correctly sized".to_string(),
+                rule_id: Some(rules::NATIVE_UNCHECKED_DESERIALIZATION.to_string()),
+                ..Default::default()
+            });
+        }
+    }
+    
+    // Configuration-level risks in Cargo.toml: release profile settings (what cargo build-sbf
+    // deploys) and features that turn validation off
+    fn run_config_lints(&self, repo_path: &Path) -> Result<Vec<CodeBug>> {
+        println!("Running build configuration lints...");
+        
+        let mut bugs = Vec::new();
+        
+        let manifests: Vec<String> = self.find_files(repo_path, &["toml"])?.into_iter()
+            .filter(|path| Path::new(path).file_name().is_some_and(|name| name == "Cargo.toml"))
+            .collect();
+        let rust_files = self.find_rust_files(repo_path)?;
+        let uses_debug_assert = rust_files.iter().any(|file| {
+            std::fs::read_to_string(file).is_ok_and(|content| content.contains("debug_assert"))
+        });
+        
+        let re_check_feature = Regex::new(r#"(?i)(^|[-_])(no|skip|disable|disabled|bypass|unchecked|insecure|unsafe|without)([-_]|$)"#).unwrap();
+        let mut features: HashMap<String, (String, u32)> = HashMap::new();
+        let mut default_features: HashSet<String> = HashSet::new();
+        
+        for manifest_path in &manifests {
+            let content = match std::fs::read_to_string(manifest_path) {
+                Ok(content) => content,
+                Err(e) => {
+                    println!("Warning: Failed to read file {}: {}", manifest_path, e);
+                    continue;
+                }
+            };
+            let manifest: Table = match content.parse() {
+                Ok(manifest) => manifest,
+                Err(e) => {
+                    println!("Warning: Failed to parse {}: {}", manifest_path, e);
+                    continue;
+                }
+            };
+            let relative_path = self.relative_path(repo_path, manifest_path);
+            
+            if let Some(declared) = manifest.get("features").and_then(|f| f.as_table()) {
+                for (name, enables) in declared {
+                    if name == "default" {
+                        default_features.extend(enables.as_array().into_iter().flatten().filter_map(|f| f.as_str()).map(|f| f.to_string()));
+                    } else {
+                        features.entry(name.clone()).or_insert_with(|| (relative_path.clone(), self.toml_line(&content, "features", name)));
+                    }
+                }
+            }
+            
+            // Cargo only honors profiles in the workspace root; the manifest at the repo root is
+            // that root for every Anchor workspace
+            let is_root = Path::new(manifest_path).parent() == Some(repo_path);
+            let profiles = manifest.get("profile").and_then(|p| p.as_table());
+            if profiles.is_none() && !is_root {
+                continue;
+            }
+            let profile = |name: &str| profiles.and_then(|p| p.get(name)).and_then(|p| p.as_table());
+            let setting = |name: &str, key: &str| profile(name).and_then(|p| p.get(key));
             
-            // Find account structs
-            for cap in re_account_struct.captures_iter(&content) {
-                let struct_name = &cap[1];
+            match setting("release", "overflow-checks").and_then(|v| v.as_bool()) {
+                Some(true) => {},
+                Some(false) => bugs.push(CodeBug {
+                    bug: "overflow-checks = false in [profile.release]: arithmetic wraps silently in the deployed program".to_string(),
+                    line: self.toml_line(&content, "profile.release", "overflow-checks"),
+                    file: Some(relative_path.clone()),
+                    severity: BugSeverity::High,
+                    fix: "Set overflow-checks = true in [profile.release]".to_string(),
+                    rule_id: Some(rules::CONFIG_OVERFLOW_CHECKS_DISABLED.to_string()),
+                    ..Default::default()
+                }),
+                None => bugs.push(CodeBug {
+                    bug: "[profile.release] doesn't enable overflow-checks, which are off by default in release builds".to_string(),
+                    line: self.toml_line(&content, "profile.release", "overflow-checks"),
+                    file: Some(relative_path.clone()),
+                    severity: BugSeverity::Medium,
+                    fix: "Add overflow-checks = true to [profile.release]".to_string(),
+                    rule_id: Some(rules::CONFIG_OVERFLOW_CHECKS_DISABLED.to_string()),
+                    ..Default::default()
+                }),
+            }
+            
+            // Both default to unwind
+            let panic = |name: &str| setting(name, "panic").and_then(|v| v.as_str()).unwrap_or("unwind").to_string();
+            if panic("dev") != panic("release") {
+                let key_profile = if setting("release", "panic").is_some() { "profile.release" } else { "profile.dev" };
+                bugs.push(CodeBug {
+                    bug: format!("Panic strategy differs between [profile.dev] ({}) and [profile.release] ({})", panic("dev"), panic("release")),
+                    line: self.toml_line(&content, key_profile, "panic"),
+                    file: Some(relative_path.clone()),
+                    severity: BugSeverity::Medium,
+                    fix: "Use the same panic strategy in both profiles so tests exercise the deployed behavior".to_string(),
+                    rule_id: Some(rules::CONFIG_PANIC_STRATEGY_MISMATCH.to_string()),
+                    ..Default::default()
+                });
+            }
+            
+            if uses_debug_assert && setting("release", "debug-assertions").and_then(|v| v.as_bool()) != Some(true) {
+                bugs.push(CodeBug {
+                    bug: "debug_assert! checks in the program are compiled out of release builds (debug-assertions is off in [profile.release])".to_string(),
+                    line: self.toml_line(&content, "profile.release", "debug-assertions"),
+                    file: Some(relative_path.clone()),
+                    severity: BugSeverity::Medium,
+                    fix: "Turn invariants that must hold on-chain into require!/assert! checks, or enable debug-assertions in [profile.release]".to_string(),
+                    rule_id: Some(rules::CONFIG_DEBUG_ASSERTIONS_STRIPPED.to_string()),
+                    ..Default::default()
+                });
+            }
+        }
+        
+        // Checks compiled in or out by a feature
+        let re_feature_cfg = Regex::new(r#"cfg!?\(\s*(not\(\s*)?feature\s*=\s*"([^"]+)""#).unwrap();
+        let re_security_check = Regex::new(r"\b(require(_keys_eq|_keys_neq|_eq|_neq|_gt|_gte)?!|assert(_eq|_ne)?!|is_signer|\.owner\b|has_one|constraint\s*=|MissingRequiredSignature|IncorrectProgramId)").unwrap();
+        let mut gated: HashSet<String> = HashSet::new();
+        for file_path in &rust_files {
+            let Ok(content) = std::fs::read_to_string(file_path) else { continue };
+            let lines: Vec<&str> = content.lines().collect();
+            for (index, line) in lines.iter().enumerate() {
+                let Some(cap) = re_feature_cfg.captures(line) else { continue };
+                let name = cap[2].to_string();
+                if ANCHOR_FEATURES.contains(&name.as_str()) {
+                    continue;
+                }
+                // The gated item is the cfg line itself or what follows it
+                let window = lines[index..lines.len().min(index + 4)].join("\n");
+                if !re_security_check.is_match(&window) {
+                    continue;
+                }
+                let default = default_features.contains(&name);
+                bugs.push(CodeBug {
+                    bug: format!("Security check is gated behind feature `{}`{}", name, if default { " (enabled by default)" } else { "" }),
+                    line: index as u32 + 1,
+                    file: Some(self.relative_path(repo_path, file_path)),
+                    severity: if default { BugSeverity::High } else { BugSeverity::Medium },
+                    fix: format!("Make the check unconditional instead of depending on feature `{}`", name),
+                    rule_id: Some(rules::CONFIG_FEATURE_GATED_CHECK.to_string()),
+                    ..Default::default()
+                });
+                gated.insert(name);
+            }
+        }
+        
+        // Features whose name says they turn something off, where no gated check was found above
+        let mut named: Vec<(&String, &(String, u32))> = features.iter()
+            .filter(|(name, _)| !gated.contains(*name) && !ANCHOR_FEATURES.contains(&name.as_str()) && re_check_feature.is_match(name))
+            .collect();
+        named.sort();
+        for (name, (file, line)) in named {
+            let default = default_features.contains(name);
+            bugs.push(CodeBug {
+                bug: format!("Feature `{}` looks like it disables a safety check{}", name, if default { " and is enabled by default" } else { "" }),
+                line: *line,
+                file: Some(file.clone()),
+                severity: if default { BugSeverity::High } else { BugSeverity::Medium },
+                fix: format!("Remove feature `{}` or make sure it can never be enabled in a deployed build", name),
+                rule_id: Some(rules::CONFIG_FEATURE_GATED_CHECK.to_string()),
+                ..Default::default()
+            });
+        }
+        
+        Ok(bugs)
+    }
+    
+    // declare_id! values must be unique, must not be a well-known program's, and must match
+    // every [programs.<cluster>] section of Anchor.toml
+    fn run_program_id_lints(&self, repo_path: &Path) -> Result<Vec<CodeBug>> {
+        println!("Running program ID lints...");
+        
+        let mut bugs = Vec::new();
+        
+        // (program name, id, file, line) per declare_id!
+        let re_declare_id = Regex::new(r#"declare_id!\s*\(\s*"([1-9A-HJ-NP-Za-km-z]{32,44})"\s*\)"#).unwrap();
+        let mut programs: Vec<(String, String, String, u32)> = Vec::new();
+        for file_path in self.find_rust_files(repo_path)? {
+            let Ok(content) = std::fs::read_to_string(&file_path) else { continue };
+            for cap in re_declare_id.captures_iter(&content) {
+                let line = line_at(&content, cap.get(0).unwrap().start());
+                let name = self.crate_lib_name(repo_path, Path::new(&file_path))
+                    .unwrap_or_else(|| self.relative_path(repo_path, &file_path));
+                programs.push((name, cap[1].to_string(), self.relative_path(repo_path, &file_path), line));
+            }
+        }
+        
+        let mut first_declared: HashMap<&str, &str> = HashMap::new();
+        for (name, id, file, line) in &programs {
+            if let Some(other) = first_declared.get(id.as_str()).filter(|other| **other != name.as_str()) {
+                bugs.push(CodeBug {
+                    bug: format!("Program `{}` declares the same ID as `{}`: {}", name, other, id),
+                    line: *line,
+                    file: Some(file.clone()),
+                    severity: BugSeverity::Medium,
+                    fix: format!("Generate a new keypair for `{}` and update its declare_id! (anchor keys sync)", name),
+                    rule_id: Some(rules::WORKSPACE_DUPLICATE_PROGRAM_ID.to_string()),
+                    ..Default::default()
+                });
+            } else {
+                first_declared.entry(id).or_insert(name);
+            }
+            
+            if let Some((_, known)) = WELL_KNOWN_PROGRAM_IDS.iter().find(|(known_id, _)| known_id == id) {
+                bugs.push(CodeBug {
+                    bug: format!("Program `{}` declares the ID of {}: {}", name, known, id),
+                    line: *line,
+                    file: Some(file.clone()),
+                    severity: BugSeverity::Medium,
+                    fix: format!("Declare the address of `{}`'s own program keypair (anchor keys sync)", name),
+                    rule_id: Some(rules::WORKSPACE_RESERVED_PROGRAM_ID.to_string()),
+                    ..Default::default()
+                });
+            }
+        }
+        
+        let anchor_toml_path = repo_path.join("Anchor.toml");
+        let Ok(content) = std::fs::read_to_string(&anchor_toml_path) else {
+            return Ok(bugs);
+        };
+        let anchor_toml: Table = match content.parse() {
+            Ok(anchor_toml) => anchor_toml,
+            Err(e) => {
+                println!("Warning: Failed to parse Anchor.toml: {}", e);
+                return Ok(bugs);
+            }
+        };
+        let Some(clusters) = anchor_toml.get("programs").and_then(|p| p.as_table()) else {
+            return Ok(bugs);
+        };
+        
+        for (cluster, mapping) in clusters {
+            let Some(mapping) = mapping.as_table() else { continue };
+            let section = format!("programs.{}", cluster);
+            let mut checked: HashSet<&str> = HashSet::new();
+            for (name, id, _, _) in &programs {
+                if !checked.insert(name) {
+                    continue;
+                }
+                // A program may declare one ID per cfg'd build; any of them is a valid mapping
+                let declared: Vec<&str> = programs.iter().filter(|p| &p.0 == name).map(|p| p.1.as_str()).collect();
+                // Entries are either an address or { address = "...", idl = "..." }
+                let mapped = mapping.get(name).and_then(|entry| entry.as_str().or_else(|| entry.get("address").and_then(|a| a.as_str())));
+                let bug = match mapped {
+                    None => format!("Anchor.toml [{}] has no entry for program `{}`", section, name),
+                    Some(mapped) if !declared.contains(&mapped) => format!("Anchor.toml [{}] maps `{}` to {}, but it declares {}", section, name, mapped, declared.join(" / ")),
+                    Some(_) => continue,
+                };
+                bugs.push(CodeBug {
+                    bug,
+                    line: self.toml_line(&content, &section, name),
+                    file: Some("Anchor.toml".to_string()),
+                    severity: BugSeverity::Medium,
+                    fix: format!("Set {} = \"{}\" under [{}]", name, id, section),
+                    rule_id: Some(rules::ANCHOR_TOML_PROGRAM_MAPPING.to_string()),
+                    ..Default::default()
+                });
+            }
+        }
+        
+        Ok(bugs)
+    }
+    
+    // Only for repositories that declare the programs they intend to invoke in .safex.toml
+    fn run_cpi_lints(&self, repo_path: &Path) -> Result<Vec<CodeBug>> {
+        println!("Running CPI allowlist lints...");
+        
+        let mut bugs = Vec::new();
+        let surface = cpi_surface(repo_path)?;
+        if !surface.declared {
+            return Ok(bugs);
+        }
+        
+        for target in &surface.targets {
+            let Some(location) = target.locations.first() else { continue };
+            match (&target.program_id, target.allowed) {
+                (Some(program_id), Some(false)) => bugs.push(CodeBug {
+                    bug: format!("CPI into {} ({}), which isn't on the allowlist", target.name, program_id),
+                    line: location.line,
+                    file: Some(location.file.clone()),
+                    severity: BugSeverity::Medium,
+                    fix: format!("Review the calls into {} and add {} to [cpi] allowed_programs in {}, or remove them", target.name, program_id, CPI_CONFIG_FILE),
+                    rule_id: Some(rules::CPI_TARGET_NOT_ALLOWED.to_string()),
+                    ..Default::default()
+                }),
+                (None, _) => bugs.push(CodeBug {
+                    bug: format!("CPI target `{}` is defined outside the repository, so it can't be checked against the allowlist", target.name),
+                    line: location.line,
+                    file: Some(location.file.clone()),
+                    severity: BugSeverity::Low,
+                    fix: format!("Confirm which program `{}` refers to and that its ID is in [cpi] allowed_programs", target.name),
+                    rule_id: Some(rules::CPI_TARGET_UNRESOLVED.to_string()),
+                    ..Default::default()
+                }),
+                _ => {},
+            }
+        }
+        
+        let config = std::fs::read_to_string(repo_path.join(CPI_CONFIG_FILE)).unwrap_or_default();
+        for program_id in &surface.unused_entries {
+            bugs.push(CodeBug {
+                bug: format!("Allowlisted program {} is never invoked", program_id),
+                line: config.lines().position(|line| line.contains(program_id.as_str())).map(|i| i as u32 + 1).unwrap_or(0),
+                file: Some(CPI_CONFIG_FILE.to_string()),
+                severity: BugSeverity::Low,
+                fix: format!("Remove {} from [cpi] allowed_programs", program_id),
+                rule_id: Some(rules::CPI_ALLOWLIST_UNUSED.to_string()),
+                ..Default::default()
+            });
+        }
+        
+        Ok(bugs)
+    }
+    
+    // Deep scans only; the rule IDs follow the sink the argument reaches
+    fn run_taint_lints(&self, repo_path: &Path) -> Result<Vec<CodeBug>> {
+        println!("Running taint analysis...");
+        
+        Ok(find_tainted_sinks(repo_path)?.into_iter().map(taint_bug).collect())
+    }
+    
+    // The Rust release the code needs against the one the repository pins, and unstable
+    // features enabled in program crates
+    fn run_toolchain_lints(&self, repo_path: &Path) -> Result<Vec<CodeBug>> {
+        println!("Running toolchain lints...");
+        
+        let mut bugs = Vec::new();
+        // Version 1 of the rule left the dependencies' rust-version out
+        let risk = toolchain_risk(repo_path, self.rule_set.runs_at_least(rules::TOOLCHAIN_PINNED_TOO_OLD, 2))?;
+        
+        if let Some(error) = &risk.pin_error {
+            bugs.push(CodeBug {
+                bug: format!("rust-toolchain.toml can't be parsed: {}", error),
+                line: 0,
+                file: Some("rust-toolchain.toml".to_string()),
+                severity: BugSeverity::Low,
+                fix: "Set the toolchain as `[toolchain] channel = \"<version>\"` in rust-toolchain.toml".to_string(),
+                ..Default::default()
+            });
+        }
+        
+        if let Some((pinned, required)) = risk.pinned_too_old() {
+            let source = match &required.file {
+                Some(file) => format!("{} ({}:{})", required.reason, file, required.line),
+                None => required.reason.clone(),
+            };
+            bugs.push(CodeBug {
+                bug: format!("{} pins Rust {}, but {} needs Rust {}", pinned.file, pinned.channel, source, required.version),
+                line: pinned.line,
+                file: Some(pinned.file.clone()),
+                severity: BugSeverity::Low,
+                fix: format!("Pin Rust {} or newer in {}, or drop the newer feature", required.version, pinned.file),
+                rule_id: Some(rules::TOOLCHAIN_PINNED_TOO_OLD.to_string()),
+                ..Default::default()
+            });
+        }
+        
+        for feature in &risk.nightly_features {
+            bugs.push(CodeBug {
+                bug: format!("Program enables the nightly-only feature `{}`", feature.feature),
+                line: feature.line,
+                file: Some(feature.file.clone()),
+                severity: BugSeverity::Medium,
+                fix: format!("Rewrite the code using `{}` on stable Rust and remove it from #![feature]", feature.feature),
+                rule_id: Some(rules::TOOLCHAIN_NIGHTLY_FEATURE.to_string()),
+                ..Default::default()
+            });
+        }
+        
+        Ok(bugs)
+    }
+    
+    // Library name of the crate containing `file_path` ([lib] name, or the package name with
+    // dashes replaced), which is how Anchor.toml refers to a program
+    fn crate_lib_name(&self, repo_path: &Path, file_path: &Path) -> Option<String> {
+        let manifest_dir = file_path.ancestors().skip(1)
+            .take_while(|dir| dir.starts_with(repo_path))
+            .find(|dir| dir.join("Cargo.toml").is_file())?;
+        let manifest: Table = std::fs::read_to_string(manifest_dir.join("Cargo.toml")).ok()?.parse().ok()?;
+        let name = manifest.get("lib").and_then(|lib| lib.get("name")).and_then(|n| n.as_str())
+            .or_else(|| manifest.get("package").and_then(|package| package.get("name")).and_then(|n| n.as_str()))?;
+        Some(name.replace('-', "_"))
+    }
+    
+    // Line of `key` within the `[section]` table of a manifest, or of the section header when
+    // the key isn't set there (0 when neither is present)
+    fn toml_line(&self, content: &str, section: &str, key: &str) -> u32 {
+        let mut section_line = 0;
+        let mut in_section = false;
+        for (index, line) in content.lines().enumerate() {
+            // A trailing comment can follow a header or a value
+            let trimmed = line.split('#').next().unwrap_or_default().trim();
+            if trimmed.starts_with('[') {
+                in_section = trimmed.trim_matches(|c| c == '[' || c == ']').trim() == section;
+                if in_section {
+                    section_line = index as u32 + 1;
+                }
+            } else if in_section {
+                let name = trimmed.split('=').next().unwrap_or("").trim().trim_matches('"');
+                if name == key {
+                    return index as u32 + 1;
+                }
+            }
+        }
+        section_line
+    }
+    
+    // Line-based pass over the TypeScript/JavaScript in tests/ and app/, the parts of an
+    // Anchor workspace that hold keys and send transactions
+    fn run_typescript_lints(&self, repo_path: &Path) -> Result<Vec<CodeBug>> {
+        println!("Running TypeScript client and test lints...");
+        
+        let mut bugs = Vec::new();
+        
+        // 64-byte secret keys are 86-88 characters in base58
+        let re_base58_secret = Regex::new(r#"["'`]([1-9A-HJ-NP-Za-km-z]{86,88})["'`]"#).unwrap();
+        let re_inline_secret = Regex::new(r"Keypair\.fromSecretKey\(\s*(?:(?:new\s+)?Uint8Array(?:\.from)?\(\s*)?\[").unwrap();
+        let re_skip_preflight = Regex::new(r"\bskipPreflight\s*:\s*true\b").unwrap();
+        let re_confirmation = Regex::new(r"\b(confirmTransaction|sendAndConfirmTransaction|sendAndConfirmRawTransaction|sendAndConfirm|rpc)\s*\(").unwrap();
+        // The promise is handled when awaited, returned, chained or collected on the same line
+        let re_handled = Regex::new(r"\b(await|return|yield|function)\b|=>|Promise\.(all|allSettled|race|any)\b|\.then\(").unwrap();
+        
+        let mut files = Vec::new();
+        for dir in ["tests", "app"] {
+            files.extend(self.find_files(&repo_path.join(dir), &["ts", "tsx", "js", "jsx", "mjs"])?);
+        }
+        
+        for file_path in files {
+            let content = match std::fs::read_to_string(&file_path) {
+                Ok(content) => content,
+                Err(e) => {
+                    println!("Warning: Failed to read file {}: {}", file_path, e);
+                    continue;
+                }
+            };
+            let relative_path = self.relative_path(repo_path, &file_path);
+            
+            let mut line_start = 0;
+            for (index, line) in content.split_inclusive('\n').enumerate() {
+                let offset = line_start;
+                line_start += line.len();
+                let trimmed = line.trim_start();
+                if trimmed.starts_with("//") || trimmed.starts_with('*') || trimmed.starts_with("/*") {
+                    continue;
+                }
+                let line_num = index as u32 + 1;
                 
-                // Check if the struct is used as a signer in any instruction
-                if content.contains(&format!("{}: &Signer", struct_name)) || 
-                   content.contains(&format!("{}: Signer", struct_name)) {
-                    
-                    // Check if it has the signer attribute
-                    if !re_signer_check.is_match(&content) {
-                        // Get approximate line number
-                        let line_num = content[..cap.get(0).unwrap().start()]
-                            .lines()
-                            .count() as u32 + 1;
-                            
+                if let Some(cap) = re_base58_secret.captures(line) {
+                    // Transaction signatures have the same length
+                    if !line.to_lowercase().contains("sig") {
                         bugs.push(CodeBug {
-                            bug: format!("Missing #[account(signer)] attribute for {}", struct_name),
+                            bug: format!("Hard-coded private key: base58 secret key literal {}...", &cap[1][..8]),
                             line: line_num,
+                            file: Some(relative_path.clone()),
                             severity: BugSeverity::High,
-                            fix: format!("Add #[account(signer)] attribute to the {} struct", struct_name),
+                            fix: "Load the key from an environment variable or a keypair file outside the repository, and rotate the exposed key".to_string(),
+                            rule_id: Some(rules::TS_HARDCODED_SECRET_KEY.to_string()),
+                            ..Default::default()
+                        });
+                    }
+                }
+                
+                if re_inline_secret.is_match(line) {
+                    bugs.push(CodeBug {
+                        bug: "Keypair.fromSecretKey called with an inline secret key array".to_string(),
+                        line: line_num,
+                        file: Some(relative_path.clone()),
+                        severity: BugSeverity::High,
+                        fix: "Read the keypair file at runtime (or use Keypair.generate() in tests) instead of embedding its bytes, and rotate the exposed key".to_string(),
+                        rule_id: Some(rules::TS_INLINE_SECRET_KEY_ARRAY.to_string()),
+                        ..Default::default()
+                    });
+                }
+                
+                if re_skip_preflight.is_match(line) {
+                    bugs.push(CodeBug {
+                        bug: "Transaction sent with skipPreflight: true".to_string(),
+                        line: line_num,
+                        file: Some(relative_path.clone()),
+                        severity: BugSeverity::Medium,
+                        fix: "Leave preflight enabled so simulation errors and program logs are reported before sending".to_string(),
+                        rule_id: Some(rules::TS_SKIP_PREFLIGHT.to_string()),
+                        ..Default::default()
+                    });
+                }
+                
+                if let Some(cap) = re_confirmation.captures(line) {
+                    let call = cap.get(1).unwrap();
+                    // `rpc(` only counts as Anchor's method builder call
+                    let is_call = call.as_str() != "rpc" || line[..call.start()].trim_end().ends_with('.');
+                    if is_call && !re_handled.is_match(&self.statement_prefix(&content, offset + call.start())) {
+                        bugs.push(CodeBug {
+                            bug: format!("Result of {}() is not awaited", call.as_str()),
+                            line: line_num,
+                            file: Some(relative_path.clone()),
+                            severity: BugSeverity::Medium,
+                            fix: format!("await the {}() call so the transaction has landed (or failed) before continuing", call.as_str()),
+                            rule_id: Some(rules::TS_UNAWAITED_CONFIRMATION.to_string()),
+                            ..Default::default()
                         });
                     }
                 }
             }
         }
         
-        Ok(())
+        Ok(bugs)
+    }
+    
+    // The top level of the statement leading up to `offset`, so builder chains and arguments
+    // spread over several lines are seen whole. Nested brackets are skipped; the scan stops at
+    // the previous `;` or the opening brace of the enclosing block.
+    fn statement_prefix(&self, content: &str, offset: usize) -> String {
+        let mut depth = 0usize;
+        let mut prefix = Vec::new();
+        for c in content[..offset].chars().rev() {
+            match c {
+                ')' | ']' | '}' => depth += 1,
+                '(' | '[' | '{' if depth > 0 => depth -= 1,
+                '{' => break,
+                ';' if depth == 0 => break,
+                _ if depth == 0 => prefix.push(c),
+                _ => {},
+            }
+        }
+        prefix.iter().rev().collect()
     }
     
     // Find all Rust files in the project
     fn find_rust_files(&self, dir_path: &Path) -> Result<Vec<String>> {
-        let mut rust_files = Vec::new();
+        self.find_files(dir_path, &["rs"])
+    }
+    
+    // Find files with one of `extensions` under `dir_path`
+    fn find_files(&self, dir_path: &Path, extensions: &[&str]) -> Result<Vec<String>> {
+        let mut files = Vec::new();
         
         if !dir_path.is_dir() {
-            return Ok(rust_files);
+            return Ok(files);
         }
         
         for entry in std::fs::read_dir(dir_path)? {
@@ -226,30 +1277,56 @@ impl CodeAnalyzer {
             };
             let path = entry.path();
             
-            // Skip hidden directories and files
+            // Skip hidden directories and files, and installed JavaScript dependencies
             if path.file_name()
                 .and_then(|name| name.to_str())
-                .map(|name| name.starts_with('.'))
-                .unwrap_or(false) {
+                .map(|name| name.starts_with('.') || name == "node_modules")
+                .unwrap_or(false) || self.exclusions.excludes(&path) {
                 continue;
             }
             
             if path.is_dir() {
-                match self.find_rust_files(&path) {
-                    Ok(mut subdir_files) => rust_files.append(&mut subdir_files),
+                // Vendored crates are third-party code, not the project's
+                if is_vendored_crate(&path) {
+                    continue;
+                }
+                match self.find_files(&path, extensions) {
+                    Ok(mut subdir_files) => files.append(&mut subdir_files),
                     Err(e) => {
                         println!("Warning: Failed to search directory {}: {}", path.display(), e);
                         continue;
                     }
                 }
-            } else if let Some(extension) = path.extension() {
-                if extension == "rs" {
-                    rust_files.push(path.to_string_lossy().to_string());
+            } else if let Some(extension) = path.extension().and_then(|e| e.to_str()) {
+                if extensions.contains(&extension) {
+                    files.push(path.to_string_lossy().to_string());
                 }
             }
         }
         
-        Ok(rust_files)
+        Ok(files)
+    }
+    
+    // Path relative to the repository root, so it is stable across clones
+    fn relative_path(&self, repo_path: &Path, file_path: &str) -> String {
+        Path::new(file_path)
+            .strip_prefix(repo_path)
+            .map(|p| p.to_string_lossy().to_string())
+            .unwrap_or_else(|_| file_path.to_string())
+    }
+    
+    // Fingerprints identify a finding across runs independent of line shifts:
+    // hash of file + message, disambiguated by occurrence order within the file
+    fn assign_fingerprints(&self, bugs: &mut [CodeBug], seen: &mut HashMap<(String, String), u32>) {
+        for bug in bugs.iter_mut() {
+            let file = bug.file.clone().unwrap_or_default();
+            let occurrence = seen.entry((file.clone(), bug.bug.clone())).or_insert(0);
+            
+            let mut hasher = Sha256::new();
+            hasher.update(format!("{}|{}|{}", file, bug.bug, occurrence).as_bytes());
+            bug.fingerprint = format!("{:x}", hasher.finalize())[..16].to_string();
+            *occurrence += 1;
+        }
     }
     
     // Suggest fixes based on the bug description
@@ -264,4 +1341,138 @@ impl CodeAnalyzer {
             "Review the code and fix the issue according to best practices".to_string()
         }
     }
-}
\ No newline at end of file
+}
+
+// Tracks the passes of one analysis: reports each as it starts and, for budgeted modes,
+// skips the ones that no longer fit. Findings are finalized as passes hand them over, reported
+// together once the pass is over, and collected for the end.
+struct Passes<'a> {
+    analyzer: &'a CodeAnalyzer,
+    mode: AnalysisMode,
+    started: Instant,
+    budget: Option<Duration>,
+    deadline: Option<Instant>,
+    skipped: Vec<&'static str>,
+    stage: &'static str,
+    found: Vec<CodeBug>,
+    // Findings of the current pass not reported yet
+    pending: Vec<CodeBug>,
+    // Occurrences of each (file, message) so far, for fingerprints
+    seen: HashMap<(String, String), u32>,
+    events: &'a mut dyn FnMut(AnalysisEvent),
+}
+
+impl<'a> Passes<'a> {
+    fn new(analyzer: &'a CodeAnalyzer, mode: AnalysisMode, events: &'a mut dyn FnMut(AnalysisEvent)) -> Self {
+        let budget = match mode {
+            AnalysisMode::Full => None,
+            AnalysisMode::Quick => Some(QUICK_SCAN_BUDGET),
+            AnalysisMode::Deep => {
+                let secs = env::var("SAFEX_DEEP_SCAN_BUDGET_SECS").ok().and_then(|s| s.parse().ok()).unwrap_or(DEEP_SCAN_BUDGET_SECS);
+                Some(Duration::from_secs(secs))
+            },
+        };
+        let started = Instant::now();
+        Self {
+            analyzer,
+            mode,
+            started,
+            budget,
+            deadline: budget.map(|budget| started + budget),
+            skipped: Vec::new(),
+            stage: "setup",
+            found: Vec::new(),
+            pending: Vec::new(),
+            seen: HashMap::new(),
+            events,
+        }
+    }
+    
+    // Whether there's time left for a pass; records the pass as skipped otherwise
+    fn start(&mut self, stage: &'static str) -> bool {
+        self.flush();
+        let skipped = self.deadline.is_some_and(|deadline| Instant::now() >= deadline);
+        if skipped {
+            self.skipped.push(stage);
+        }
+        self.stage = stage;
+        (self.events)(AnalysisEvent::Progress(ProgressEvent {
+            stage: stage.to_string(),
+            elapsed_ms: self.started.elapsed().as_millis() as u64,
+            skipped,
+        }));
+        !skipped
+    }
+    
+    // Findings from the current pass. Fingerprints depend only on the order findings arrive
+    // in, so they're the same as if the whole run were fingerprinted at the end.
+    fn report(&mut self, bugs: Vec<CodeBug>) {
+        let bugs = self.analyzer.finalize(bugs, &mut self.seen);
+        self.pending.extend(bugs);
+    }
+    
+    // One event per pass, so storing and streaming findings costs a write per pass rather
+    // than per file or finding
+    fn flush(&mut self) {
+        if self.pending.is_empty() {
+            return;
+        }
+        let bugs = std::mem::take(&mut self.pending);
+        (self.events)(AnalysisEvent::Findings { stage: self.stage.to_string(), bugs: bugs.clone() });
+        self.found.extend(bugs);
+    }
+    
+    // Everything found, with a finding naming the passes the budget left out, if any
+    fn finish(mut self) -> Vec<CodeBug> {
+        self.flush();
+        if let Some(budget) = self.budget.filter(|_| !self.skipped.is_empty()) {
+            let (scan, fix) = match self.mode {
+                AnalysisMode::Deep => ("Deep scan", "Raise SAFEX_DEEP_SCAN_BUDGET_SECS for complete results"),
+                _ => ("Quick scan", "Run a full analysis for complete results"),
+            };
+            self.stage = "budget";
+            self.report(vec![CodeBug {
+                bug: format!("{} ran out of its {}s budget before the {}", scan, budget.as_secs(), self.skipped.join(", ")),
+                line: 0,
+                severity: BugSeverity::Low,
+                fix: fix.to_string(),
+                ..Default::default()
+            }]);
+            self.flush();
+        }
+        self.found
+    }
+}
+
+// The finding for an instruction argument that reaches a sink; the rule IDs follow the sink
+fn taint_bug(finding: TaintFinding) -> CodeBug {
+    let (bug, severity, fix, rule_id) = match finding.sink {
+        Sink::Arithmetic => (
+            format!("Instruction argument `{}` reaches unchecked arithmetic in `{}`", finding.variable, finding.function),
+            BugSeverity::Medium,
+            format!("Use checked_add/checked_sub/checked_mul on `{}` and return an error on overflow, or bound it with require! first", finding.variable),
+            rules::TAINT_UNCHECKED_ARITHMETIC,
+        ),
+        Sink::Index => (
+            format!("Instruction argument `{}` indexes a buffer without a bounds check in `{}`", finding.variable, finding.function),
+            BugSeverity::Low,
+            "Use .get() and return an error for out-of-range input instead of panicking".to_string(),
+            rules::TAINT_UNCHECKED_INDEX,
+        ),
+        Sink::Cast => (
+            format!("Instruction argument `{}` is truncated by an `as` cast in `{}`", finding.variable, finding.function),
+            BugSeverity::Medium,
+            format!("Convert `{}` with try_from and return an error when it doesn't fit", finding.variable),
+            rules::TAINT_TRUNCATING_CAST,
+        ),
+    };
+    CodeBug {
+        bug,
+        line: finding.line,
+        file: Some(finding.file),
+        severity,
+        fix,
+        rule_id: Some(rule_id.to_string()),
+        ..Default::default()
+    }
+}
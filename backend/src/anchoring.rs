@@ -0,0 +1,493 @@
+use anyhow::{anyhow, Result};
+use async_trait::async_trait;
+use openssl::cms::{CMSOptions, CmsContentInfo};
+use openssl::x509::store::{X509Store, X509StoreBuilder};
+use openssl::x509::{X509PurposeId, X509};
+use reqwest::Client;
+use serde_json::{json, Value};
+use sha2::{Digest, Sha256};
+use std::collections::HashMap;
+use std::env;
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::sync::oneshot;
+use tokio::time::Instant;
+
+use crate::confirmation::track_signature;
+use crate::hashing::{decode_hex, hex};
+use crate::models::{AnchorBackend, ConfirmationStatus, ReportAnchor, ReportSubmission};
+use crate::report_logger::{parse_commit_sha, repo_url_hash, ReportLogger};
+use crate::repo_url::RepoUrl;
+
+// How long an EVM transaction is followed before it's given up on
+const EVM_TRACKING_TIMEOUT: Duration = Duration::from_secs(30 * 60);
+const EVM_POLL_INTERVAL: Duration = Duration::from_secs(5);
+
+// DER of the SHA-256 AlgorithmIdentifier (OID 2.16.840.1.101.3.4.2.1, NULL parameters)
+const SHA256_ALGORITHM: &[u8] = &[0x30, 0x0d, 0x06, 0x09, 0x60, 0x86, 0x48, 0x01, 0x65, 0x03, 0x04, 0x02, 0x01, 0x05, 0x00];
+// Content of the SHA-256 OID alone, which authorities may send without the NULL parameters
+const SHA256_OID: &[u8] = &[0x60, 0x86, 0x48, 0x01, 0x65, 0x03, 0x04, 0x02, 0x01];
+
+// What is anchored: a report's SHA256 with the repository and commit it covers
+pub struct AnchorEntry {
+    pub hash: [u8; 32],
+    pub repo_url: RepoUrl,
+    pub commit_sha: String,
+    pub confidential: bool,
+}
+
+impl AnchorEntry {
+    pub fn new(hash: [u8; 32], submission: &ReportSubmission) -> Self {
+        Self {
+            hash,
+            repo_url: submission.repo_url.clone(),
+            commit_sha: submission.commit_sha.clone(),
+            confidential: submission.confidential,
+        }
+    }
+
+    // The repository hash as anchored. A confidential report's is zeroed: the repository hash
+    // can be matched against any known URL, so it would name what was audited.
+    pub fn repo_hash(&self) -> [u8; 32] {
+        if self.confidential { [0; 32] } else { repo_url_hash(&self.repo_url) }
+    }
+
+    // The commit as anchored, zeroed for a confidential report for the same reason
+    pub fn commit(&self) -> Result<[u8; 20]> {
+        if self.confidential { Ok([0; 20]) } else { parse_commit_sha(&self.commit_sha) }
+    }
+}
+
+pub struct AnchorReceipt {
+    // Transaction signature or hash, or a timestamp token's SHA256
+    pub reference: String,
+    // Proof the backend issued, where it issues one
+    pub receipt: Option<String>,
+    // Whether the tracker will hear about confirmation after anchor() returns
+    pub tracked: bool,
+}
+
+// Hears how an anchor is getting on, so it can be recorded
+pub trait AnchorTracker: Send + Sync {
+    // As soon as the reference is known; on Solana, before the transaction is sent
+    fn reference(&self, reference: &str);
+    fn status(&self, status: ConfirmationStatus, error: Option<String>);
+}
+
+// A place report hashes are anchored so they can't be quietly changed later
+#[async_trait]
+pub trait Anchorer: Send + Sync {
+    // Anchor `entry`, telling `tracker` about its progress. An error means nothing was anchored.
+    async fn anchor(&self, entry: &AnchorEntry, tracker: Arc<dyn AnchorTracker>) -> Result<AnchorReceipt>;
+
+    // Check a recorded anchor of `hash` against the backend itself rather than this server's
+    // record of it. An error says why it couldn't be.
+    async fn verify(&self, anchor: &ReportAnchor, hash: &[u8; 32]) -> Result<()>;
+}
+
+// The configured backends. Solana is always available; the others are enabled by their settings.
+pub struct Anchorers {
+    backends: HashMap<AnchorBackend, Arc<dyn Anchorer>>,
+}
+
+impl Anchorers {
+    pub fn from_env() -> Result<Self> {
+        let mut backends: HashMap<AnchorBackend, Arc<dyn Anchorer>> = HashMap::new();
+        backends.insert(AnchorBackend::Solana, Arc::new(SolanaAnchorer));
+        if let Some(anchorer) = EvmAnchorer::from_env()? {
+            println!("Anchoring reports on the EVM chain at {} is available", anchorer.rpc_url);
+            backends.insert(AnchorBackend::Evm, Arc::new(anchorer));
+        }
+        if let Some(anchorer) = TimestampAnchorer::from_env()? {
+            println!("Anchoring reports with the timestamping authority at {} is available", anchorer.url);
+            backends.insert(AnchorBackend::Rfc3161, Arc::new(anchorer));
+        }
+        Ok(Self { backends })
+    }
+
+    pub fn get(&self, backend: AnchorBackend) -> Result<Arc<dyn Anchorer>> {
+        self.backends.get(&backend).cloned()
+            .ok_or_else(|| anyhow!("The {} anchoring backend is not configured on this server", backend.as_str()))
+    }
+
+    pub async fn verify(&self, anchor: &ReportAnchor, hash: &[u8; 32]) -> Result<()> {
+        self.get(anchor.backend)?.verify(anchor, hash).await
+    }
+}
+
+// The report-logger program, through the payer in SAFEX_REPORT_PAYER_KEYPAIR. Confirmation is
+// followed over the websocket API when it's available and waited for otherwise.
+pub struct SolanaAnchorer;
+
+#[async_trait]
+impl Anchorer for SolanaAnchorer {
+    async fn anchor(&self, entry: &AnchorEntry, tracker: Arc<dyn AnchorTracker>) -> Result<AnchorReceipt> {
+        let logger = Arc::new(ReportLogger::new().map_err(|e| anyhow!("Failed to initialize report logger: {}", e))?);
+
+        // The RPC client blocks, so it runs on the blocking thread pool
+        let transaction = tokio::task::spawn_blocking({
+            let logger = logger.clone();
+            let (hash, repo_hash, commit) = (entry.hash, entry.repo_hash(), entry.commit()?);
+            move || logger.build_transaction(&hash, &repo_hash, &commit)
+        }).await??;
+        let signature = transaction.signatures[0];
+        tracker.reference(&signature.to_string());
+
+        // Subscribe to the signature before sending, so no notification is missed
+        let (ready, subscribed) = oneshot::channel();
+        let follower = tracker.clone();
+        let following = actix_web::rt::spawn(track_signature(signature, ready, move |status, error| follower.status(status, error)));
+        let tracked = match subscribed.await {
+            Ok(Ok(())) => true,
+            Ok(Err(e)) => {
+                println!("Warning: Can't track confirmation, waiting for it instead: {}", e);
+                false
+            },
+            Err(_) => false,
+        };
+
+        let sent = tokio::task::spawn_blocking({
+            let logger = logger.clone();
+            move || if tracked { logger.send(&transaction) } else { logger.send_and_confirm(&transaction) }
+        }).await?;
+        match sent {
+            Ok(reference) => {
+                if !tracked {
+                    tracker.status(ConfirmationStatus::Confirmed, None);
+                }
+                Ok(AnchorReceipt { reference, receipt: None, tracked })
+            },
+            Err(e) => {
+                following.abort();
+                Err(e)
+            }
+        }
+    }
+
+    // Solana reports are read back from the program's accounts, not from anchors
+    async fn verify(&self, _anchor: &ReportAnchor, _hash: &[u8; 32]) -> Result<()> {
+        Err(anyhow!("Solana reports are verified against the report-logger program"))
+    }
+}
+
+// A zero-value transaction carrying the report hash, repository hash and commit (84 bytes of
+// calldata) on an EVM chain. The node at SAFEX_EVM_RPC_URL signs it for SAFEX_EVM_FROM, so
+// the key stays in the node or its signer; it goes to SAFEX_EVM_TO, the sender by default.
+pub struct EvmAnchorer {
+    client: Client,
+    rpc_url: String,
+    from: String,
+    to: String,
+}
+
+impl EvmAnchorer {
+    fn from_env() -> Result<Option<Self>> {
+        let Ok(rpc_url) = env::var("SAFEX_EVM_RPC_URL") else {
+            return Ok(None);
+        };
+        if !rpc_url.starts_with("https://") && !rpc_url.starts_with("http://") {
+            return Err(anyhow!("SAFEX_EVM_RPC_URL must be http or https: {}", rpc_url));
+        }
+        let from = env::var("SAFEX_EVM_FROM").map_err(|_| anyhow!("SAFEX_EVM_FROM must be set when SAFEX_EVM_RPC_URL is configured"))?;
+        let to = env::var("SAFEX_EVM_TO").unwrap_or_else(|_| from.clone());
+        for (name, address) in [("SAFEX_EVM_FROM", &from), ("SAFEX_EVM_TO", &to)] {
+            let digits = address.strip_prefix("0x").unwrap_or_default();
+            if digits.len() != 40 || !digits.chars().all(|c| c.is_ascii_hexdigit()) {
+                return Err(anyhow!("{} is not a 0x-prefixed address: {}", name, address));
+            }
+        }
+        let client = Client::builder()
+            .timeout(Duration::from_secs(30))
+            .build()?;
+        Ok(Some(Self { client, rpc_url, from, to }))
+    }
+
+    async fn call(client: &Client, rpc_url: &str, method: &str, params: Value) -> Result<Value> {
+        let response: Value = client.post(rpc_url)
+            .json(&json!({ "jsonrpc": "2.0", "id": 1, "method": method, "params": params }))
+            .send()
+            .await
+            .map_err(|e| anyhow!("Failed to reach the EVM node: {}", e))?
+            .error_for_status()?
+            .json()
+            .await?;
+        if let Some(error) = response.get("error") {
+            let message = error.get("message").and_then(|m| m.as_str()).unwrap_or("unknown error");
+            return Err(anyhow!("EVM node rejected {}: {}", method, message));
+        }
+        Ok(response.get("result").cloned().unwrap_or(Value::Null))
+    }
+}
+
+#[async_trait]
+impl Anchorer for EvmAnchorer {
+    async fn anchor(&self, entry: &AnchorEntry, tracker: Arc<dyn AnchorTracker>) -> Result<AnchorReceipt> {
+        let mut data = entry.hash.to_vec();
+        data.extend_from_slice(&entry.repo_hash());
+        data.extend_from_slice(&entry.commit()?);
+        let transaction = json!({ "from": self.from, "to": self.to, "value": "0x0", "data": format!("0x{}", hex(&data)) });
+        let reference = Self::call(&self.client, &self.rpc_url, "eth_sendTransaction", json!([transaction])).await?
+            .as_str()
+            .map(str::to_string)
+            .ok_or_else(|| anyhow!("EVM node returned no transaction hash"))?;
+        tracker.reference(&reference);
+
+        let (client, rpc_url, hash) = (self.client.clone(), self.rpc_url.clone(), reference.clone());
+        tokio::spawn(async move {
+            let (status, error) = follow_evm_transaction(&client, &rpc_url, &hash, tracker.as_ref()).await;
+            tracker.status(status, error);
+        });
+        Ok(AnchorReceipt { reference, receipt: None, tracked: true })
+    }
+
+    // The transaction must have succeeded and carry the hash at the start of its calldata
+    async fn verify(&self, anchor: &ReportAnchor, hash: &[u8; 32]) -> Result<()> {
+        let reference = &anchor.transaction_signature;
+        let transaction = Self::call(&self.client, &self.rpc_url, "eth_getTransactionByHash", json!([reference])).await?;
+        if transaction.is_null() {
+            return Err(anyhow!("Transaction {} isn't on the chain", reference));
+        }
+        let data = transaction.get("input").and_then(|input| input.as_str())
+            .and_then(|input| decode_hex(input.trim_start_matches("0x")))
+            .unwrap_or_default();
+        if !data.starts_with(hash) {
+            return Err(anyhow!("Transaction {} doesn't carry the report hash", reference));
+        }
+        let receipt = Self::call(&self.client, &self.rpc_url, "eth_getTransactionReceipt", json!([reference])).await?;
+        match receipt.get("status").and_then(|status| status.as_str()) {
+            Some("0x1") => Ok(()),
+            Some(_) => Err(anyhow!("Transaction {} reverted", reference)),
+            None => Err(anyhow!("Transaction {} hasn't been mined", reference)),
+        }
+    }
+}
+
+// Poll for the receipt, then for the chain's finalized block to pass it. Returns the last
+// status to record: finalized, or failed with the reason.
+async fn follow_evm_transaction(client: &Client, rpc_url: &str, hash: &str, tracker: &dyn AnchorTracker) -> (ConfirmationStatus, Option<String>) {
+    let number = |value: &Value, field: &str| value.get(field).and_then(|n| n.as_str())
+        .and_then(|n| u64::from_str_radix(n.trim_start_matches("0x"), 16).ok());
+    let deadline = Instant::now() + EVM_TRACKING_TIMEOUT;
+    let mut included_in = None;
+    while Instant::now() < deadline {
+        tokio::time::sleep(EVM_POLL_INTERVAL).await;
+        let block = match included_in {
+            None => match EvmAnchorer::call(client, rpc_url, "eth_getTransactionReceipt", json!([hash])).await {
+                Ok(receipt) if receipt.is_null() => continue,
+                Ok(receipt) if receipt.get("status").and_then(|s| s.as_str()) == Some("0x0") => {
+                    return (ConfirmationStatus::Failed, Some("Transaction reverted".to_string()));
+                },
+                Ok(receipt) => {
+                    tracker.status(ConfirmationStatus::Confirmed, None);
+                    included_in = number(&receipt, "blockNumber");
+                    continue;
+                },
+                Err(e) => {
+                    println!("Warning: Failed to poll EVM transaction {}: {}", hash, e);
+                    continue;
+                }
+            },
+            Some(block) => block,
+        };
+        // Nodes without the "finalized" tag (pre-merge chains, some L2s) stop at confirmed
+        match EvmAnchorer::call(client, rpc_url, "eth_getBlockByNumber", json!(["finalized", false])).await {
+            Ok(finalized) if number(&finalized, "number").is_some_and(|finalized| finalized >= block) => {
+                return (ConfirmationStatus::Finalized, None);
+            },
+            Ok(_) => {},
+            Err(e) => {
+                println!("Warning: Can't follow finality of EVM transaction {}, leaving it confirmed: {}", hash, e);
+                return (ConfirmationStatus::Confirmed, None);
+            }
+        }
+    }
+    match included_in {
+        Some(_) => (ConfirmationStatus::Confirmed, None),
+        None => (ConfirmationStatus::Failed, Some("Transaction wasn't mined in time".to_string())),
+    }
+}
+
+// An RFC 3161 timestamping authority at SAFEX_TSA_URL. The token it signs binds the hash to a
+// time and is returned as the receipt; `openssl ts -verify -token_in` checks it against the
+// authority's certificate. Tokens are only accepted when they're signed by a timestamping
+// certificate that chains to SAFEX_TSA_CA_FILE (PEM).
+pub struct TimestampAnchorer {
+    client: Client,
+    url: String,
+    trusted: X509Store,
+}
+
+impl TimestampAnchorer {
+    fn from_env() -> Result<Option<Self>> {
+        let Ok(url) = env::var("SAFEX_TSA_URL") else {
+            return Ok(None);
+        };
+        if !url.starts_with("https://") && !url.starts_with("http://") {
+            return Err(anyhow!("SAFEX_TSA_URL must be http or https: {}", url));
+        }
+        let ca_file = env::var("SAFEX_TSA_CA_FILE").map_err(|_| anyhow!("SAFEX_TSA_CA_FILE must be set when SAFEX_TSA_URL is configured"))?;
+        let pem = std::fs::read(&ca_file).map_err(|e| anyhow!("Failed to read SAFEX_TSA_CA_FILE {}: {}", ca_file, e))?;
+        let mut trusted = X509StoreBuilder::new()?;
+        for certificate in X509::stack_from_pem(&pem)? {
+            trusted.add_cert(certificate)?;
+        }
+        trusted.set_purpose(X509PurposeId::TIMESTAMP_SIGN)?;
+        let client = Client::builder()
+            .timeout(Duration::from_secs(30))
+            .build()?;
+        Ok(Some(Self { client, url, trusted: trusted.build() }))
+    }
+
+    // The hash and nonce `token` was issued for, once its signature and signer check out
+    fn verified_imprint(&self, token: &[u8]) -> Result<(Vec<u8>, Option<Vec<u8>>)> {
+        let mut signed = CmsContentInfo::from_der(token)
+            .map_err(|e| anyhow!("Timestamp token isn't CMS SignedData: {}", e))?;
+        let mut tst_info = Vec::new();
+        signed.verify(None, Some(&self.trusted), None, Some(&mut tst_info), CMSOptions::empty())
+            .map_err(|e| anyhow!("Timestamp token isn't signed by a trusted timestamping authority: {}", e))?;
+        tst_imprint(&tst_info)
+    }
+}
+
+#[async_trait]
+impl Anchorer for TimestampAnchorer {
+    async fn anchor(&self, entry: &AnchorEntry, tracker: Arc<dyn AnchorTracker>) -> Result<AnchorReceipt> {
+        // A positive INTEGER in its minimal encoding, so the authority echoes it byte for byte
+        let mut nonce = uuid::Uuid::new_v4().as_bytes()[..8].to_vec();
+        nonce[0] = (nonce[0] & 0x7f) | 0x40;
+        let response = self.client.post(&self.url)
+            .header("Content-Type", "application/timestamp-query")
+            .header("User-Agent", "Safex-App")
+            .body(timestamp_request(&entry.hash, &nonce))
+            .send()
+            .await
+            .map_err(|e| anyhow!("Failed to reach the timestamping authority: {}", e))?
+            .error_for_status()?
+            .bytes()
+            .await?;
+        let token = timestamp_token(&response)?;
+        let (imprint, echoed) = self.verified_imprint(&token)?;
+        if imprint != entry.hash {
+            return Err(anyhow!("Timestamp token doesn't cover the report hash"));
+        }
+        if echoed.as_deref() != Some(nonce.as_slice()) {
+            return Err(anyhow!("Timestamp token doesn't answer this request's nonce"));
+        }
+
+        let reference = hex(&Sha256::digest(&token));
+        tracker.reference(&reference);
+        // A token is final as issued
+        tracker.status(ConfirmationStatus::Finalized, None);
+        Ok(AnchorReceipt { reference, receipt: Some(base64::encode(&token)), tracked: false })
+    }
+
+    // The receipt is the token itself; its signature must still check out against the trusted
+    // authorities and its imprint match the hash
+    async fn verify(&self, anchor: &ReportAnchor, hash: &[u8; 32]) -> Result<()> {
+        let token = anchor.receipt.as_deref()
+            .and_then(|receipt| base64::decode(receipt).ok())
+            .ok_or_else(|| anyhow!("Timestamp anchor has no token"))?;
+        if hex(&Sha256::digest(&token)) != anchor.transaction_signature {
+            return Err(anyhow!("Timestamp token doesn't match the anchor's reference"));
+        }
+        let (imprint, _) = self.verified_imprint(&token)?;
+        if imprint != hash {
+            return Err(anyhow!("Timestamp token doesn't cover the report hash"));
+        }
+        Ok(())
+    }
+}
+
+// TimeStampReq { version 1, messageImprint { sha256, hash }, nonce, certReq TRUE }
+fn timestamp_request(hash: &[u8; 32], nonce: &[u8]) -> Vec<u8> {
+    let mut imprint = SHA256_ALGORITHM.to_vec();
+    imprint.extend(der(0x04, hash));
+
+    let mut request = der(0x02, &[1]);
+    request.extend(der(0x30, &imprint));
+    request.extend(der(0x02, nonce));
+    request.extend(der(0x01, &[0xff]));
+    der(0x30, &request)
+}
+
+// The token out of a TimeStampResp { status PKIStatusInfo, timeStampToken }, if the
+// authority granted the request
+fn timestamp_token(response: &[u8]) -> Result<Vec<u8>> {
+    let (tag, body, _) = read_der(response)?;
+    if tag != 0x30 {
+        return Err(anyhow!("Timestamping authority returned an invalid response"));
+    }
+    let (_, status_info, rest) = read_der(body)?;
+    let (_, status, _) = read_der(status_info)?;
+    // 0 granted, 1 granted with modifications; the rest are refusals
+    if !matches!(status, [0] | [1]) {
+        return Err(anyhow!("Timestamping authority refused the request (status {:?})", status));
+    }
+    let (_, _, after) = read_der(rest)?;
+    Ok(rest[..rest.len() - after.len()].to_vec())
+}
+
+// (hashed message, nonce) of a SHA-256 TSTInfo { version, policy, messageImprint, serialNumber,
+// genTime, accuracy OPTIONAL, ordering DEFAULT FALSE, nonce OPTIONAL, ... }
+fn tst_imprint(tst_info: &[u8]) -> Result<(Vec<u8>, Option<Vec<u8>>)> {
+    let (_, fields, _) = read_der(tst_info)?;
+    let (_, _, rest) = read_der(fields)?;
+    let (_, _, rest) = read_der(rest)?;
+    let (_, imprint, rest) = read_der(rest)?;
+    let (_, algorithm, hashed) = read_der(imprint)?;
+    let (_, oid, _) = read_der(algorithm)?;
+    if oid != SHA256_OID {
+        return Err(anyhow!("Timestamp token isn't over a SHA-256 hash"));
+    }
+    let (_, hashed, _) = read_der(hashed)?;
+    let (_, _, rest) = read_der(rest)?;
+    let (_, _, mut rest) = read_der(rest)?;
+    let mut nonce = None;
+    while !rest.is_empty() {
+        let (tag, content, after) = read_der(rest)?;
+        match tag {
+            // accuracy and ordering come before the nonce
+            0x30 | 0x01 => rest = after,
+            0x02 => {
+                nonce = Some(content.to_vec());
+                break;
+            },
+            _ => break,
+        }
+    }
+    Ok((hashed.to_vec(), nonce))
+}
+
+fn der(tag: u8, content: &[u8]) -> Vec<u8> {
+    let mut encoded = vec![tag];
+    let len = content.len();
+    if len < 0x80 {
+        encoded.push(len as u8);
+    } else {
+        let bytes: Vec<u8> = len.to_be_bytes().into_iter().skip_while(|b| *b == 0).collect();
+        encoded.push(0x80 | bytes.len() as u8);
+        encoded.extend(bytes);
+    }
+    encoded.extend_from_slice(content);
+    encoded
+}
+
+// (tag, content, what follows) of the first element
+fn read_der(bytes: &[u8]) -> Result<(u8, &[u8], &[u8])> {
+    let invalid = || anyhow!("Timestamping authority returned invalid DER");
+    let (&tag, rest) = bytes.split_first().ok_or_else(invalid)?;
+    let (&first, rest) = rest.split_first().ok_or_else(invalid)?;
+    let (len, rest) = if first < 0x80 {
+        (first as usize, rest)
+    } else {
+        let count = (first & 0x7f) as usize;
+        if count == 0 || count > 4 || rest.len() < count {
+            return Err(invalid());
+        }
+        (rest[..count].iter().fold(0, |len, b| (len << 8) | *b as usize), &rest[count..])
+    };
+    if rest.len() < len {
+        return Err(invalid());
+    }
+    Ok((tag, &rest[..len], &rest[len..]))
+}
@@ -0,0 +1,56 @@
+use anyhow::{anyhow, Result};
+use solana_sdk::pubkey::Pubkey;
+use solana_sdk::signature::Signature;
+use std::env;
+use std::str::FromStr;
+
+// The reviewer keys that approve reports before they're logged, and how many must sign
+pub struct ReportApprovers {
+    pub reviewers: Vec<Pubkey>,
+    pub threshold: usize,
+}
+
+impl ReportApprovers {
+    // SAFEX_REPORT_REVIEWERS is a comma-separated list of base58 public keys, and
+    // SAFEX_REPORT_APPROVAL_THRESHOLD how many of them must approve (all by default).
+    // Without reviewers, reports are logged as soon as they're submitted.
+    pub fn from_env() -> Result<Option<Self>> {
+        let Ok(keys) = env::var("SAFEX_REPORT_REVIEWERS") else {
+            return Ok(None);
+        };
+        let mut reviewers = Vec::new();
+        for key in keys.split(',').map(str::trim).filter(|key| !key.is_empty()) {
+            let reviewer = Pubkey::from_str(key).map_err(|_| anyhow!("Invalid reviewer key in SAFEX_REPORT_REVIEWERS: {}", key))?;
+            if !reviewers.contains(&reviewer) {
+                reviewers.push(reviewer);
+            }
+        }
+        if reviewers.is_empty() {
+            return Ok(None);
+        }
+        let threshold = match env::var("SAFEX_REPORT_APPROVAL_THRESHOLD") {
+            Ok(value) => value.trim().parse().map_err(|_| anyhow!("Invalid SAFEX_REPORT_APPROVAL_THRESHOLD: {}", value))?,
+            Err(_) => reviewers.len(),
+        };
+        if threshold == 0 || threshold > reviewers.len() {
+            return Err(anyhow!("SAFEX_REPORT_APPROVAL_THRESHOLD must be between 1 and {} (the number of reviewers)", reviewers.len()));
+        }
+        Ok(Some(Self { reviewers, threshold }))
+    }
+}
+
+// Check that `signature` (base58) is `reviewer`'s ed25519 signature over the report hash,
+// signed as its 32 raw bytes rather than the hex string
+pub fn verify_approval(reviewer: &str, signature: &str, hash_hex: &str) -> Result<()> {
+    let reviewer = Pubkey::from_str(reviewer.trim()).map_err(|_| anyhow!("reviewer must be a base58 public key"))?;
+    let signature = Signature::from_str(signature.trim()).map_err(|_| anyhow!("signature must be a base58 ed25519 signature"))?;
+    let hash = (0..hash_hex.len())
+        .step_by(2)
+        .map(|i| hash_hex.get(i..i + 2).and_then(|byte| u8::from_str_radix(byte, 16).ok()))
+        .collect::<Option<Vec<u8>>>()
+        .ok_or_else(|| anyhow!("Report hash {} is not hex", hash_hex))?;
+    if !signature.verify(reviewer.as_ref(), &hash) {
+        return Err(anyhow!("signature is not {}'s signature over the report hash", reviewer));
+    }
+    Ok(())
+}
@@ -0,0 +1,140 @@
+use anyhow::{anyhow, Result};
+use solana_client::rpc_client::RpcClient;
+use solana_client::rpc_config::RpcProgramAccountsConfig;
+use solana_client::rpc_filter::{Memcmp, RpcFilterType};
+use solana_sdk::{
+    instruction::{AccountMeta, Instruction},
+    message::Message,
+    pubkey::Pubkey,
+    transaction::Transaction,
+};
+use std::str::FromStr;
+
+use crate::cluster::Cluster;
+use crate::models::{Attestation, AttestationVerdict};
+use crate::report_logger::{account_discriminator, instruction_discriminator, PROGRAM_ID};
+
+// Attestation account: 8-byte discriminator, report, attester, verdict, timestamp, bump
+const ATTESTATION_LEN: usize = 8 + 32 + 32 + 1 + 8 + 1;
+
+// Third-party reviews of logged reports. Attesters sign their own transactions, so the
+// backend only builds them, relays them once signed, and reads attestations back.
+pub struct Attestations {
+    client: RpcClient,
+    program_id: Pubkey,
+}
+
+impl Attestations {
+    pub fn new() -> Result<Self> {
+        // Same cluster as the report logger
+        let client = RpcClient::new(Cluster::registry()?.rpc_url());
+        let program_id = Pubkey::from_str(PROGRAM_ID)?;
+        Ok(Self { client, program_id })
+    }
+
+    // The PDA holding `attester`'s attestation of `report`
+    pub fn address(&self, report: &Pubkey, attester: &Pubkey) -> Pubkey {
+        Pubkey::find_program_address(&[b"attestation", report.as_ref(), attester.as_ref()], &self.program_id).0
+    }
+
+    // An unsigned attest_report transaction, paid for by the attester, as base64 bincode
+    #[tracing::instrument(name = "attestation.build", skip(self))]
+    pub fn build_transaction(&self, report: &Pubkey, attester: &Pubkey, verdict: AttestationVerdict) -> Result<String> {
+        let mut instruction_data = instruction_discriminator("attest_report").to_vec();
+        instruction_data.push(verdict_byte(verdict));
+
+        let instruction = Instruction {
+            program_id: self.program_id,
+            accounts: vec![
+                AccountMeta::new_readonly(*report, false),
+                AccountMeta::new(self.address(report, attester), false),
+                AccountMeta::new(*attester, true),
+                AccountMeta::new_readonly(Pubkey::from_str("11111111111111111111111111111111").unwrap(), false),
+            ],
+            data: instruction_data,
+        };
+
+        let mut message = Message::new(&[instruction], Some(attester));
+        message.recent_blockhash = tracing::info_span!("solana.rpc", rpc.method = "getLatestBlockhash")
+            .in_scope(|| self.client.get_latest_blockhash())?;
+        Ok(base64::encode(bincode::serialize(&Transaction::new_unsigned(message))?))
+    }
+
+    // Decode a signed transaction and check it only attests to reports through this program,
+    // so the endpoint can't be used to relay arbitrary transactions. Returns the attestation address.
+    pub fn decode_signed(&self, transaction: &str) -> Result<(Transaction, Pubkey)> {
+        let bytes = base64::decode(transaction.trim()).map_err(|e| anyhow!("Transaction is not valid base64: {}", e))?;
+        let transaction: Transaction = bincode::deserialize(&bytes).map_err(|e| anyhow!("Transaction could not be decoded: {}", e))?;
+
+        let discriminator = instruction_discriminator("attest_report");
+        let mut attestation = None;
+        for instruction in &transaction.message.instructions {
+            let program = transaction.message.account_keys.get(instruction.program_id_index as usize);
+            if program != Some(&self.program_id) || !instruction.data.starts_with(&discriminator) {
+                return Err(anyhow!("Transaction may only contain attest_report instructions"));
+            }
+            let account = instruction.accounts.get(1).and_then(|i| transaction.message.account_keys.get(*i as usize));
+            attestation = attestation.or(account.copied());
+        }
+        let attestation = attestation.ok_or_else(|| anyhow!("Transaction contains no attest_report instruction"))?;
+        transaction.verify().map_err(|e| anyhow!("Transaction is not signed by the attester: {}", e))?;
+        Ok((transaction, attestation))
+    }
+
+    #[tracing::instrument(name = "attestation.submit", skip_all)]
+    pub fn submit(&self, transaction: &Transaction) -> Result<String> {
+        let signature = tracing::info_span!("solana.rpc", rpc.method = "sendTransaction")
+            .in_scope(|| self.client.send_and_confirm_transaction(transaction))?;
+        Ok(signature.to_string())
+    }
+
+    // Every attestation of `report`, oldest first
+    #[tracing::instrument(name = "attestation.list", skip(self))]
+    pub fn list(&self, report: &Pubkey) -> Result<Vec<Attestation>> {
+        let config = RpcProgramAccountsConfig {
+            filters: Some(vec![
+                RpcFilterType::DataSize(ATTESTATION_LEN as u64),
+                RpcFilterType::Memcmp(Memcmp::new_raw_bytes(0, account_discriminator("Attestation").to_vec())),
+                RpcFilterType::Memcmp(Memcmp::new_raw_bytes(8, report.to_bytes().to_vec())),
+            ]),
+            ..Default::default()
+        };
+        let accounts = tracing::info_span!("solana.rpc", rpc.method = "getProgramAccounts")
+            .in_scope(|| self.client.get_program_accounts_with_config(&self.program_id, config))?;
+
+        let mut attestations: Vec<Attestation> = accounts.iter()
+            .filter_map(|(address, account)| parse_attestation(address, &account.data))
+            .collect();
+        attestations.sort_by_key(|a| a.timestamp);
+        Ok(attestations)
+    }
+}
+
+fn parse_attestation(address: &Pubkey, data: &[u8]) -> Option<Attestation> {
+    if data.len() < ATTESTATION_LEN {
+        return None;
+    }
+    let pubkey = |offset: usize| Pubkey::try_from(&data[offset..offset + 32]).ok();
+    let verdict = match data[72] {
+        0 => AttestationVerdict::Endorse,
+        1 => AttestationVerdict::EndorseWithConcerns,
+        2 => AttestationVerdict::Reject,
+        _ => return None,
+    };
+    Some(Attestation {
+        address: address.to_string(),
+        report: pubkey(8)?.to_string(),
+        attester: pubkey(40)?.to_string(),
+        verdict,
+        timestamp: i64::from_le_bytes(data[73..81].try_into().ok()?),
+    })
+}
+
+// Borsh encodes unit enum variants as their index
+fn verdict_byte(verdict: AttestationVerdict) -> u8 {
+    match verdict {
+        AttestationVerdict::Endorse => 0,
+        AttestationVerdict::EndorseWithConcerns => 1,
+        AttestationVerdict::Reject => 2,
+    }
+}
@@ -0,0 +1,59 @@
+use crate::auth::Caller;
+use crate::db::{now_unix, Database};
+use crate::models::AuditEntry;
+
+// A mutating operation in progress; `finish` appends it to the audit log with its outcome
+pub struct AuditEvent {
+    tenant: String,
+    actor: String,
+    action: String,
+    target: Option<String>,
+    params: serde_json::Value,
+    started_at: i64,
+}
+
+impl AuditEvent {
+    pub fn start(caller: &Caller, action: &str) -> Self {
+        Self::for_actor(&caller.tenant, &caller.actor, action)
+    }
+
+    // For work done outside a request, e.g. queued jobs acting for whoever submitted them
+    pub fn for_actor(tenant: &str, actor: &str, action: &str) -> Self {
+        Self {
+            tenant: tenant.to_string(),
+            actor: actor.to_string(),
+            action: action.to_string(),
+            target: None,
+            params: serde_json::Value::Null,
+            started_at: now_unix(),
+        }
+    }
+
+    pub fn target(mut self, target: impl Into<String>) -> Self {
+        self.target = Some(target.into());
+        self
+    }
+
+    pub fn params(mut self, params: serde_json::Value) -> Self {
+        self.params = params;
+        self
+    }
+
+    // Audit failures are reported but never fail the operation being audited
+    pub fn finish(self, db: &Database, success: bool, detail: impl Into<String>) {
+        let entry = AuditEntry {
+            id: 0,
+            actor: self.actor,
+            action: self.action,
+            target: self.target,
+            params: self.params,
+            outcome: if success { "success" } else { "failure" }.to_string(),
+            detail: Some(detail.into()),
+            started_at: self.started_at,
+            finished_at: now_unix(),
+        };
+        if let Err(e) = db.insert_audit_entry(&self.tenant, &entry) {
+            println!("Warning: Failed to write audit log entry for {}: {}", entry.action, e);
+        }
+    }
+}
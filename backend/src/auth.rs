@@ -0,0 +1,127 @@
+use actix_web::dev::Payload;
+use actix_web::error::InternalError;
+use actix_web::{web, FromRequest, HttpRequest, HttpResponse};
+use serde_json::json;
+use sha2::{Digest, Sha256};
+use std::collections::HashMap;
+use std::env;
+use std::future::{ready, Ready};
+
+// Tenant used for every request when no API keys are configured
+pub const DEFAULT_TENANT: &str = "default";
+pub const ANONYMOUS_ACTOR: &str = "anonymous";
+
+#[derive(Debug, Clone)]
+pub struct ApiKey {
+    pub tenant: String,
+    // Who is acting, for the audit log; never the secret itself
+    pub name: String,
+}
+
+pub struct ApiKeys {
+    keys: HashMap<String, ApiKey>,
+}
+
+impl ApiKeys {
+    // SAFEX_API_KEYS="secret:tenant,secret2:tenant2:name"; unnamed keys are identified by a hash prefix
+    pub fn from_env() -> Self {
+        let mut keys = HashMap::new();
+        let raw = env::var("SAFEX_API_KEYS").unwrap_or_default();
+        for entry in raw.split(',').map(str::trim).filter(|e| !e.is_empty()) {
+            let parts: Vec<&str> = entry.split(':').collect();
+            if parts.len() < 2 || parts[0].is_empty() || parts[1].is_empty() {
+                println!("Warning: Ignoring malformed SAFEX_API_KEYS entry");
+                continue;
+            }
+            let name = match parts.get(2).filter(|n| !n.is_empty()) {
+                Some(name) => name.to_string(),
+                None => format!("key-{}", &format!("{:x}", Sha256::digest(parts[0].as_bytes()))[..8]),
+            };
+            keys.insert(parts[0].to_string(), ApiKey {
+                tenant: parts[1].to_string(),
+                name,
+            });
+        }
+
+        if keys.is_empty() {
+            println!("No API keys configured, all requests use the default tenant");
+        } else {
+            println!("Loaded {} API keys", keys.len());
+        }
+        Self { keys }
+    }
+
+    pub fn is_enabled(&self) -> bool {
+        !self.keys.is_empty()
+    }
+
+    pub fn lookup(&self, secret: &str) -> Option<&ApiKey> {
+        self.keys.get(secret)
+    }
+}
+
+// The authenticated caller of a request, resolved from `Authorization: Bearer <key>` or `X-API-Key`
+#[derive(Debug, Clone)]
+pub struct Caller {
+    pub tenant: String,
+    pub actor: String,
+}
+
+impl Caller {
+    fn anonymous() -> Self {
+        Self {
+            tenant: DEFAULT_TENANT.to_string(),
+            actor: ANONYMOUS_ACTOR.to_string(),
+        }
+    }
+}
+
+// SAFEX_ADMIN_TENANTS is a comma-separated list of the tenants allowed to change
+// instance-wide settings. Without API keys every caller is the default tenant, which is an
+// admin only when listed.
+pub fn is_admin(caller: &Caller) -> bool {
+    env::var("SAFEX_ADMIN_TENANTS").unwrap_or_default()
+        .split(',')
+        .map(str::trim)
+        .any(|tenant| !tenant.is_empty() && tenant == caller.tenant)
+}
+
+// The API key a request presents, if any, whether or not it is valid
+pub fn presented_secret(req: &HttpRequest) -> Option<&str> {
+    let header = |name: &str| req.headers().get(name).and_then(|v| v.to_str().ok());
+    header("Authorization")
+        .and_then(|v| v.strip_prefix("Bearer "))
+        .or_else(|| header("X-API-Key"))
+        .map(str::trim)
+}
+
+fn unauthorized(message: &str) -> actix_web::Error {
+    InternalError::from_response(
+        message.to_string(),
+        HttpResponse::Unauthorized().json(json!({ "success": false, "message": message })),
+    ).into()
+}
+
+impl FromRequest for Caller {
+    type Error = actix_web::Error;
+    type Future = Ready<Result<Self, Self::Error>>;
+
+    fn from_request(req: &HttpRequest, _payload: &mut Payload) -> Self::Future {
+        let Some(keys) = req.app_data::<web::Data<ApiKeys>>() else {
+            return ready(Ok(Caller::anonymous()));
+        };
+        if !keys.is_enabled() {
+            return ready(Ok(Caller::anonymous()));
+        }
+
+        let secret = presented_secret(req);
+        ready(match secret.and_then(|s| keys.lookup(s)) {
+            Some(key) => Ok(Caller {
+                tenant: key.tenant.clone(),
+                actor: key.name.clone(),
+            }),
+            None if secret.is_some() => Err(unauthorized("Invalid API key")),
+            None => Err(unauthorized("Missing API key")),
+        })
+    }
+}
@@ -0,0 +1,63 @@
+use anyhow::{anyhow, Result};
+
+use crate::models::TextEdit;
+
+const DIFF_CONTEXT: usize = 3;
+
+// Apply edits to `content`, refusing if any span no longer holds the text the fix was computed against
+pub fn apply_edits(content: &str, edits: &[TextEdit]) -> Result<String> {
+    let mut edits: Vec<&TextEdit> = edits.iter().collect();
+    edits.sort_by_key(|e| e.byte_start);
+    for pair in edits.windows(2) {
+        if pair[0].byte_end > pair[1].byte_start {
+            return Err(anyhow!("Overlapping edits at byte {}", pair[1].byte_start));
+        }
+    }
+
+    let mut patched = String::with_capacity(content.len());
+    let mut position = 0;
+    for edit in edits {
+        match content.get(edit.byte_start..edit.byte_end) {
+            Some(original) if original == edit.original => {},
+            _ => return Err(anyhow!("File has changed since the fix was suggested")),
+        }
+        patched.push_str(&content[position..edit.byte_start]);
+        patched.push_str(&edit.replacement);
+        position = edit.byte_end;
+    }
+    patched.push_str(&content[position..]);
+    Ok(patched)
+}
+
+// Single-hunk unified diff; fixes touch one region of a file, so everything between
+// the first and last changed line goes into one hunk
+pub fn unified_diff(path: &str, original: &str, patched: &str) -> String {
+    let old: Vec<&str> = original.lines().collect();
+    let new: Vec<&str> = patched.lines().collect();
+    let prefix = old.iter().zip(&new).take_while(|(a, b)| a == b).count();
+    let suffix = old[prefix..].iter().rev().zip(new[prefix..].iter().rev()).take_while(|(a, b)| a == b).count();
+    if prefix == old.len() && prefix == new.len() {
+        return String::new();
+    }
+
+    let start = prefix.saturating_sub(DIFF_CONTEXT);
+    let old_end = (old.len() - suffix + DIFF_CONTEXT).min(old.len());
+    let new_end = (new.len() - suffix + DIFF_CONTEXT).min(new.len());
+    let mut diff = format!(
+        "--- a/{path}\n+++ b/{path}\n@@ -{},{} +{},{} @@\n",
+        start + 1, old_end - start, start + 1, new_end - start,
+    );
+    for line in &old[start..prefix] {
+        diff.push_str(&format!(" {}\n", line));
+    }
+    for line in &old[prefix..old.len() - suffix] {
+        diff.push_str(&format!("-{}\n", line));
+    }
+    for line in &new[prefix..new.len() - suffix] {
+        diff.push_str(&format!("+{}\n", line));
+    }
+    for line in &old[old.len() - suffix..old_end] {
+        diff.push_str(&format!(" {}\n", line));
+    }
+    diff
+}
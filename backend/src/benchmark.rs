@@ -0,0 +1,161 @@
+use anyhow::{anyhow, Result};
+use serde::Deserialize;
+use std::collections::{BTreeMap, HashSet};
+use std::env;
+use std::fs;
+
+use crate::analyzer::CodeAnalyzer;
+use crate::github::GitHubClient;
+use crate::models::{BenchmarkRepo, BenchmarkRun, RuleBaseline, RuleBenchmark};
+use crate::rules::{self, rule_catalog};
+
+const BUILT_IN_CORPUS: &str = include_str!("../benchmark/corpus.toml");
+
+// Earlier runs searched for a rule's previous version
+pub const HISTORY_LIMIT: u32 = 50;
+
+#[derive(Debug, Deserialize)]
+struct Corpus {
+    #[serde(default)]
+    repos: Vec<CorpusRepo>,
+}
+
+#[derive(Debug, Deserialize)]
+struct CorpusRepo {
+    name: String,
+    url: String,
+    commit: Option<String>,
+    cases: Vec<CorpusCase>,
+}
+
+#[derive(Debug, Deserialize)]
+struct CorpusCase {
+    // Directory of the repository the case covers
+    path: String,
+    // Rules that should report something in it; empty for a fixed program
+    #[serde(default)]
+    expect: Vec<String>,
+}
+
+// SAFEX_BENCHMARK_CORPUS is the path of a corpus file replacing the built-in one, read on
+// every run so it can be edited between runs
+fn load_corpus() -> Result<(String, Corpus)> {
+    let (name, content) = match env::var("SAFEX_BENCHMARK_CORPUS") {
+        Ok(path) => {
+            let content = fs::read_to_string(&path).map_err(|e| anyhow!("Failed to read benchmark corpus {}: {}", path, e))?;
+            (path, content)
+        },
+        Err(_) => ("built-in".to_string(), BUILT_IN_CORPUS.to_string()),
+    };
+    let corpus: Corpus = toml::from_str(&content).map_err(|e| anyhow!("Invalid benchmark corpus {}: {}", name, e))?;
+
+    let known: HashSet<String> = rule_catalog().into_iter().map(|rule| rule.id).collect();
+    for repo in &corpus.repos {
+        for case in &repo.cases {
+            if let Some(rule_id) = case.expect.iter().find(|id| !known.contains(*id)) {
+                return Err(anyhow!("Unknown rule {} expected in {}/{}", rule_id, repo.name, case.path));
+            }
+        }
+    }
+    Ok((name, corpus))
+}
+
+// Run the source rules over every case of the corpus and score each rule. `history` holds
+// earlier runs, most recent first, for the baselines. Like the self-test, clippy and the
+// external analyzers aren't run.
+pub fn run_benchmark(run_by: &str, history: &[BenchmarkRun]) -> Result<BenchmarkRun> {
+    let (corpus_name, corpus) = load_corpus()?;
+    let analyzer = CodeAnalyzer::new();
+    let github_client = GitHubClient::new();
+
+    let mut repos = Vec::new();
+    // Rules that fired in each evaluated case, with the rules it expects
+    let mut cases: Vec<(HashSet<String>, &[String])> = Vec::new();
+    for repo in &corpus.repos {
+        let mut benchmarked = BenchmarkRepo {
+            name: repo.name.clone(),
+            url: repo.url.clone(),
+            commit: repo.commit.clone(),
+            cases: repo.cases.len() as u32,
+            error: None,
+        };
+        let temp_dir = tempfile::TempDir::new()?;
+        let analyzed = github_client.clone_repo(&repo.url, temp_dir.path())
+            .and_then(|_| match &repo.commit {
+                Some(commit) => GitHubClient::checkout_commit(temp_dir.path(), commit),
+                None => Ok(()),
+            })
+            .and_then(|_| github_client.detect_project_type(temp_dir.path())?.ok_or_else(|| anyhow!("Not a Solana program")))
+            .and_then(|project_type| analyzer.run_source_rules(temp_dir.path(), project_type));
+        match analyzed {
+            Ok(bugs) => {
+                benchmarked.commit = GitHubClient::head_commit(temp_dir.path());
+                for case in &repo.cases {
+                    let prefix = format!("{}/", case.path.trim_matches('/'));
+                    let fired = bugs.iter()
+                        .filter(|bug| bug.file.as_ref().is_some_and(|file| file.starts_with(&prefix)))
+                        .filter_map(|bug| bug.rule_id.clone())
+                        .collect();
+                    cases.push((fired, &case.expect));
+                }
+            },
+            Err(e) => {
+                println!("Warning: Benchmark of {} failed: {}", repo.url, e);
+                benchmarked.error = Some(e.to_string());
+            },
+        }
+        repos.push(benchmarked);
+    }
+
+    let ratio = |hits: u32, total: u32| (total > 0).then(|| hits as f64 / total as f64);
+    let mut scores: BTreeMap<String, RuleBenchmark> = BTreeMap::new();
+    for rule in rule_catalog().into_iter().filter(|rule| rule.id != rules::CLIPPY) {
+        let mut score = RuleBenchmark {
+            rule_id: rule.id.clone(),
+            version: rule.version,
+            true_positives: 0,
+            false_negatives: 0,
+            false_positives: 0,
+            true_negatives: 0,
+            detection_rate: None,
+            false_positive_rate: None,
+            baseline: None,
+        };
+        for (fired, expect) in &cases {
+            match (expect.contains(&rule.id), fired.contains(&rule.id)) {
+                (true, true) => score.true_positives += 1,
+                (true, false) => score.false_negatives += 1,
+                (false, true) => score.false_positives += 1,
+                (false, false) => score.true_negatives += 1,
+            }
+        }
+        score.detection_rate = ratio(score.true_positives, score.true_positives + score.false_negatives);
+        score.false_positive_rate = ratio(score.false_positives, score.false_positives + score.true_negatives);
+        score.baseline = baseline(&corpus_name, &score, history);
+        scores.insert(rule.id, score);
+    }
+
+    Ok(BenchmarkRun {
+        id: 0,
+        run_at: 0,
+        run_by: run_by.to_string(),
+        corpus: corpus_name,
+        repos,
+        rules: scores.into_values().collect(),
+    })
+}
+
+// The latest earlier run on the same corpus with a different version of the rule, to compare
+// a rule change against
+fn baseline(corpus: &str, score: &RuleBenchmark, history: &[BenchmarkRun]) -> Option<RuleBaseline> {
+    history.iter().filter(|run| run.corpus == corpus).find_map(|run| {
+        run.rules.iter()
+            .find(|rule| rule.rule_id == score.rule_id && rule.version != score.version)
+            .map(|rule| RuleBaseline {
+                run_id: run.id,
+                version: rule.version,
+                detection_rate: rule.detection_rate,
+                false_positive_rate: rule.false_positive_rate,
+            })
+    })
+}
@@ -0,0 +1,239 @@
+use anyhow::{anyhow, Result};
+use regex::Regex;
+use std::fs;
+
+use crate::models::{BinaryFinding, BugSeverity};
+use crate::program_build::{CompiledProgram, ProgramBuild};
+
+// Largest account the runtime allows, which bounds the program data account
+const MAX_PERMITTED_DATA_LENGTH: u64 = 10 * 1024 * 1024;
+// The program data account's header ahead of the ELF: variant tag, slot and optional authority
+const PROGRAM_DATA_METADATA_LEN: u64 = 45;
+
+const SHT_DYNSYM: u32 = 11;
+const SECTION_HEADER_LEN: usize = 64;
+const SYMBOL_LEN: usize = 24;
+
+// Printable runs shorter than this in .rodata are mostly not strings
+const MIN_STRING_LEN: usize = 6;
+
+// Checks on what a build produced rather than on its source: whether each binary fits in a
+// program account, what it calls into the runtime for, and whether its log messages look
+// like they print key material. Stack frames the compiler couldn't fit come from the build log.
+pub fn check_build(build: &ProgramBuild) -> Vec<BinaryFinding> {
+    let mut findings = stack_findings(&build.stack_warnings);
+    for program in &build.programs {
+        match check_program(program) {
+            Ok(program_findings) => findings.extend(program_findings),
+            Err(e) => println!("Warning: Failed to inspect {}: {}", program.path.display(), e),
+        }
+    }
+    findings
+}
+
+fn check_program(program: &CompiledProgram) -> Result<Vec<BinaryFinding>> {
+    let data = fs::read(&program.path)?;
+    let elf = Elf::parse(&data)?;
+    let finding = |check: &str, severity: BugSeverity, message: String, details: Vec<String>| BinaryFinding {
+        program: Some(program.name.clone()),
+        check: check.to_string(),
+        severity,
+        message,
+        details,
+    };
+    let mut findings = Vec::new();
+
+    let limit = MAX_PERMITTED_DATA_LENGTH - PROGRAM_DATA_METADATA_LEN;
+    if program.size > limit {
+        findings.push(finding("size", BugSeverity::High, format!(
+            "Binary is {} KiB, over the {} KiB a program account can hold; it can't be deployed",
+            program.size / 1024, limit / 1024,
+        ), Vec::new()));
+    } else if program.size > limit / 2 {
+        findings.push(finding("size", BugSeverity::Low, format!(
+            "Binary is {} KiB of the {} KiB a program account can hold, leaving little room for upgrades to grow",
+            program.size / 1024, limit / 1024,
+        ), Vec::new()));
+    }
+
+    // Programs built for SBFv1 import syscalls by name; later versions call them by hash and
+    // have none to list
+    let syscalls = elf.imported_symbols()?;
+    if !syscalls.is_empty() {
+        findings.push(finding("syscalls", BugSeverity::Low, format!("Binary uses {} syscalls", syscalls.len()), syscalls));
+    }
+
+    let re_key_words = Regex::new(r"(?i)(private[ _-]?key|secret[ _-]?key|seed[ _-]?phrase|mnemonic|keypair)").unwrap();
+    // A 64-byte keypair as base58, or as the JSON byte array keypair files hold
+    let re_key_material = Regex::new(r"[1-9A-HJ-NP-Za-km-z]{86,88}|\[\s*\d{1,3}(\s*,\s*\d{1,3}){63}\s*\]").unwrap();
+    let strings = elf.rodata_strings();
+    let embedded: Vec<String> = strings.iter().filter(|s| re_key_material.is_match(s)).map(|s| truncate(s)).collect();
+    if !embedded.is_empty() {
+        findings.push(finding("sensitive_logging", BugSeverity::High,
+            "Binary contains what looks like an embedded secret key".to_string(), embedded));
+    }
+    let mentions: Vec<String> = strings.iter().filter(|s| re_key_words.is_match(s)).map(|s| truncate(s)).collect();
+    if !mentions.is_empty() {
+        findings.push(finding("sensitive_logging", BugSeverity::Medium,
+            "Log or error messages mention key material; check they don't print it".to_string(), mentions));
+    }
+
+    Ok(findings)
+}
+
+// e.g. "Error: Function _ZN7program9process17h0123456789abcdefE Stack offset of 4104 exceeded
+// max offset of 4096 by 8 bytes, please minimize large stack variables"
+fn stack_findings(warnings: &[String]) -> Vec<BinaryFinding> {
+    let re_stack = Regex::new(r"Function (\S+) Stack offset of (\d+) exceeded max offset of (\d+) by (\d+) bytes").unwrap();
+    let functions: Vec<String> = warnings.iter()
+        .filter_map(|w| re_stack.captures(w))
+        .map(|c| format!("{} (over by {} bytes)", demangle(&c[1]), &c[4]))
+        .collect();
+    if functions.is_empty() {
+        return Vec::new();
+    }
+    vec![BinaryFinding {
+        program: None,
+        check: "stack_size".to_string(),
+        severity: BugSeverity::Medium,
+        message: format!("{} function(s) exceed the 4 KiB stack frame; they can corrupt memory at runtime", functions.len()),
+        details: functions,
+    }]
+}
+
+// Legacy Rust mangling, `_ZN` then length-prefixed path segments and a trailing hash
+fn demangle(symbol: &str) -> String {
+    let Some(mut rest) = symbol.strip_prefix("_ZN") else {
+        return symbol.to_string();
+    };
+    let mut segments = Vec::new();
+    while let Some(len_end) = rest.find(|c: char| !c.is_ascii_digit()).filter(|&i| i > 0) {
+        let Ok(len) = rest[..len_end].parse::<usize>() else { break };
+        let Some(segment) = rest.get(len_end..len_end + len) else { break };
+        segments.push(segment);
+        rest = &rest[len_end + len..];
+    }
+    if segments.last().is_some_and(|s| s.len() == 17 && s.starts_with('h')) {
+        segments.pop();
+    }
+    if segments.is_empty() {
+        symbol.to_string()
+    } else {
+        segments.join("::")
+    }
+}
+
+fn truncate(s: &str) -> String {
+    if s.chars().count() > 120 {
+        format!("{}...", s.chars().take(120).collect::<String>())
+    } else {
+        s.to_string()
+    }
+}
+
+// Just enough of a 64-bit little-endian ELF, which is all SBF produces, to read section
+// contents and dynamic symbols
+struct Elf<'a> {
+    data: &'a [u8],
+    sections: Vec<Section>,
+}
+
+struct Section {
+    name: String,
+    kind: u32,
+    offset: usize,
+    size: usize,
+    link: u32,
+}
+
+impl<'a> Elf<'a> {
+    fn parse(data: &'a [u8]) -> Result<Self> {
+        if data.get(..4) != Some(b"\x7fELF") || data.get(4) != Some(&2) || data.get(5) != Some(&1) {
+            return Err(anyhow!("Not a 64-bit little-endian ELF"));
+        }
+        let section_offset = read_u64(data, 0x28)? as usize;
+        let section_count = read_u16(data, 0x3c)? as usize;
+        let names_index = read_u16(data, 0x3e)? as usize;
+
+        let mut headers = Vec::with_capacity(section_count);
+        for index in 0..section_count {
+            let at = section_offset + index * SECTION_HEADER_LEN;
+            headers.push((
+                read_u32(data, at)?,
+                Section {
+                    name: String::new(),
+                    kind: read_u32(data, at + 4)?,
+                    offset: read_u64(data, at + 24)? as usize,
+                    size: read_u64(data, at + 32)? as usize,
+                    link: read_u32(data, at + 40)?,
+                },
+            ));
+        }
+        let names = headers.get(names_index).map(|(_, section)| (section.offset, section.size));
+        let mut sections = Vec::with_capacity(section_count);
+        for (name_offset, mut section) in headers {
+            if let Some((offset, size)) = names {
+                let table = data.get(offset..offset + size).unwrap_or_default();
+                section.name = read_cstr(table, name_offset as usize);
+            }
+            sections.push(section);
+        }
+        Ok(Self { data, sections })
+    }
+
+    fn contents(&self, section: &Section) -> Result<&'a [u8]> {
+        self.data.get(section.offset..section.offset + section.size)
+            .ok_or_else(|| anyhow!("Section {} is out of bounds", section.name))
+    }
+
+    // Undefined dynamic symbols, i.e. what the loader resolves against the runtime
+    fn imported_symbols(&self) -> Result<Vec<String>> {
+        let mut symbols = Vec::new();
+        for dynsym in self.sections.iter().filter(|s| s.kind == SHT_DYNSYM) {
+            let table = self.contents(dynsym)?;
+            let names = self.sections.get(dynsym.link as usize)
+                .ok_or_else(|| anyhow!("Dynamic symbol table has no string table"))?;
+            let names = self.contents(names)?;
+            for symbol in table.chunks_exact(SYMBOL_LEN) {
+                let name = read_cstr(names, read_u32(symbol, 0)? as usize);
+                if read_u16(symbol, 6)? == 0 && !name.is_empty() && !symbols.contains(&name) {
+                    symbols.push(name);
+                }
+            }
+        }
+        symbols.sort();
+        Ok(symbols)
+    }
+
+    // Rust doesn't terminate its string literals, so neighbouring literals come out as one run
+    fn rodata_strings(&self) -> Vec<String> {
+        let mut strings = Vec::new();
+        for section in self.sections.iter().filter(|s| s.name.starts_with(".rodata")) {
+            let Ok(contents) = self.contents(section) else { continue };
+            for run in contents.split(|b| !(b.is_ascii_graphic() || *b == b' ')) {
+                if run.len() >= MIN_STRING_LEN {
+                    strings.push(String::from_utf8_lossy(run).into_owned());
+                }
+            }
+        }
+        strings
+    }
+}
+
+fn read_u16(data: &[u8], at: usize) -> Result<u16> {
+    data.get(at..at + 2).map(|b| u16::from_le_bytes([b[0], b[1]])).ok_or_else(|| anyhow!("Truncated ELF"))
+}
+
+fn read_u32(data: &[u8], at: usize) -> Result<u32> {
+    data.get(at..at + 4).map(|b| u32::from_le_bytes([b[0], b[1], b[2], b[3]])).ok_or_else(|| anyhow!("Truncated ELF"))
+}
+
+fn read_u64(data: &[u8], at: usize) -> Result<u64> {
+    data.get(at..at + 8).and_then(|b| b.try_into().ok()).map(u64::from_le_bytes).ok_or_else(|| anyhow!("Truncated ELF"))
+}
+
+fn read_cstr(table: &[u8], at: usize) -> String {
+    let bytes = table.get(at..).unwrap_or_default();
+    let end = bytes.iter().position(|&b| b == 0).unwrap_or(bytes.len());
+    String::from_utf8_lossy(&bytes[..end]).into_owned()
+}
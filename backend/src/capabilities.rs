@@ -0,0 +1,123 @@
+use futures_util::future::join_all;
+use std::env;
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+use tokio::sync::Mutex;
+
+use crate::cluster::Cluster;
+use crate::config::{self, Config};
+use crate::db::now_unix;
+use crate::environment::tool_version;
+use crate::external::ExternalAnalyzers;
+use crate::fuzzer::fuzz_engines;
+use crate::models::{AnalyzerCapability, ClusterCapability, FuzzEngineCapability, SandboxCapability, ToolchainCapabilities};
+use crate::rules::current_rule_set;
+use crate::sandbox::sandbox_levels;
+use crate::toolchain::{ProvisionedToolchains, ToolchainManager};
+
+// How long a cluster's RPC endpoint gets to answer getVersion before it counts as unreachable
+const CLUSTER_PROBE_TIMEOUT: Duration = Duration::from_secs(5);
+
+// Default for SAFEX_CAPABILITIES_TTL_SECS
+const DEFAULT_CAPABILITIES_TTL_SECS: u64 = 300;
+
+// What this deployment can run, probed ahead of time so GET /api/capabilities answers
+// without running anything. An API-only node probes its own host, which is expected to be set
+// up like its workers.
+pub struct Capabilities {
+    pub analyzers: Vec<AnalyzerCapability>,
+    pub fuzz_engines: Vec<FuzzEngineCapability>,
+    pub toolchains: ToolchainCapabilities,
+    pub sandbox_levels: Vec<SandboxCapability>,
+    pub clusters: Vec<ClusterCapability>,
+    pub probed_at: i64,
+}
+
+impl Capabilities {
+    pub async fn probe(toolchain_manager: Arc<ToolchainManager>, external: Arc<ExternalAnalyzers>) -> Self {
+        let local = tokio::task::spawn_blocking(move || {
+            let mut analyzers = builtin_analyzers();
+            analyzers.extend(external.capabilities());
+            (analyzers, fuzz_engines(), toolchain_manager.installed(), sandbox_levels())
+        });
+        let (local, clusters) = tokio::join!(local, probe_clusters());
+        let (analyzers, fuzz_engines, toolchains, sandbox_levels) = local.unwrap_or_else(|e| {
+            println!("Warning: Failed to probe the host's capabilities: {}", e);
+            (Vec::new(), Vec::new(), ToolchainCapabilities::default(), Vec::new())
+        });
+        Self { analyzers, fuzz_engines, toolchains, sandbox_levels, clusters, probed_at: now_unix() }
+    }
+}
+
+// The latest probe, redone once it's older than SAFEX_CAPABILITIES_TTL_SECS (default 5
+// minutes) or the config has been reloaded since, so RPC URL changes and auto-installed
+// toolchains show up
+pub struct CapabilityProbe {
+    toolchain_manager: Arc<ToolchainManager>,
+    external: Arc<ExternalAnalyzers>,
+    ttl: Duration,
+    // Held while probing, so concurrent requests wait for one probe instead of starting their own
+    latest: Mutex<(Arc<Capabilities>, Instant, Arc<Config>)>,
+}
+
+impl CapabilityProbe {
+    pub async fn start(toolchain_manager: Arc<ToolchainManager>, external: Arc<ExternalAnalyzers>) -> Self {
+        let ttl = env::var("SAFEX_CAPABILITIES_TTL_SECS").ok().and_then(|s| s.parse().ok()).unwrap_or(DEFAULT_CAPABILITIES_TTL_SECS);
+        let capabilities = Capabilities::probe(toolchain_manager.clone(), external.clone()).await;
+        Self {
+            toolchain_manager,
+            external,
+            ttl: Duration::from_secs(ttl),
+            latest: Mutex::new((Arc::new(capabilities), Instant::now(), config::current())),
+        }
+    }
+
+    pub async fn current(&self) -> Arc<Capabilities> {
+        let mut latest = self.latest.lock().await;
+        let config = config::current();
+        if latest.1.elapsed() >= self.ttl || !Arc::ptr_eq(&latest.2, &config) {
+            let capabilities = Capabilities::probe(self.toolchain_manager.clone(), self.external.clone()).await;
+            *latest = (Arc::new(capabilities), Instant::now(), config);
+        }
+        latest.0.clone()
+    }
+}
+
+// The rule passes always run; clippy needs cargo-clippy on the host
+fn builtin_analyzers() -> Vec<AnalyzerCapability> {
+    let clippy_version = tool_version(&ProvisionedToolchains::default(), "cargo", &["clippy", "--version"]);
+    vec![
+        AnalyzerCapability {
+            name: "rules".to_string(),
+            external: false,
+            available: true,
+            version: Some(current_rule_set().version),
+            project_types: Vec::new(),
+        },
+        AnalyzerCapability {
+            name: "clippy".to_string(),
+            external: false,
+            available: clippy_version.is_some(),
+            version: clippy_version,
+            project_types: Vec::new(),
+        },
+    ]
+}
+
+async fn probe_clusters() -> Vec<ClusterCapability> {
+    let registry = Cluster::registry().ok();
+    join_all(Cluster::ALL.into_iter().map(|cluster| async move {
+        let version = match tokio::time::timeout(CLUSTER_PROBE_TIMEOUT, cluster.rpc_client().get_version()).await {
+            Ok(Ok(version)) => Some(version.solana_core),
+            Ok(Err(e)) => {
+                println!("Warning: {} RPC endpoint is unreachable: {}", cluster.as_str(), e);
+                None
+            },
+            Err(_) => {
+                println!("Warning: {} RPC endpoint didn't answer within {}s", cluster.as_str(), CLUSTER_PROBE_TIMEOUT.as_secs());
+                None
+            },
+        };
+        ClusterCapability { cluster, reachable: version.is_some(), version, registry: registry == Some(cluster) }
+    })).await
+}
@@ -0,0 +1,178 @@
+use anyhow::{anyhow, Result};
+use sha2::{Digest, Sha256};
+use solana_client::rpc_client::RpcClient;
+use solana_sdk::{
+    instruction::{AccountMeta, Instruction},
+    message::Message,
+    pubkey::Pubkey,
+    signature::{Keypair, Signer},
+    transaction::Transaction,
+};
+use std::str::FromStr;
+
+use crate::cluster::Cluster;
+use crate::models::ReportStatus;
+use crate::report_logger::{assert_report_instruction, configured_payer, parse_report_hash, ReportLogger};
+
+const TOKEN_2022_PROGRAM_ID: &str = "TokenzQdBNbLqP5VEhdkAS6EPFLC1PHnBqCXEpPxuEb";
+const ASSOCIATED_TOKEN_PROGRAM_ID: &str = "ATokenGPvbdGVxr1b2hvZbsiqW5xWH25efTNsLJA8knL";
+const SYSTEM_PROGRAM_ID: &str = "11111111111111111111111111111111";
+
+const CERTIFICATE_NAME: &str = "Safex Audit Certificate";
+const CERTIFICATE_SYMBOL: &str = "SAFEX";
+// Token metadata URIs follow the Metaplex limit
+pub const MAX_METADATA_URI_LEN: usize = 200;
+
+// Base mint padded to the token account size, account type byte, then the NonTransferable
+// (empty) and MetadataPointer (two pubkeys) extensions, each behind a 4-byte TLV header
+const MINT_LEN: usize = 165 + 1 + 4 + (4 + 64);
+
+// Mints audit certificates: a Token-2022 NFT (supply 1, no mint authority left) that is
+// non-transferable, so it stays with the project's wallet, and carries its metadata URI
+// and the report hash in the mint's own token metadata
+pub struct CertificateMinter {
+    client: RpcClient,
+    payer: Keypair,
+}
+
+impl CertificateMinter {
+    pub fn new() -> Result<Self> {
+        // Same cluster as the report logger
+        let client = RpcClient::new(Cluster::registry()?.rpc_url());
+
+        // The report payer funds the mint and is its authorities until they're given up
+        let payer = configured_payer()?.ok_or_else(|| anyhow!("Minting certificates needs a funded payer in SAFEX_REPORT_PAYER_KEYPAIR"))?;
+
+        Ok(Self { client, payer })
+    }
+
+    // Mint a certificate for `report_hash` (hex) to `owner`, returning the mint address
+    // and transaction signature. The report must be logged and active: the transaction starts
+    // with the report-logger asserting so, and mints nothing otherwise.
+    #[tracing::instrument(name = "certificate.mint", skip(self))]
+    pub fn mint(&self, owner: &Pubkey, report_hash: &str, metadata_uri: &str) -> Result<(Pubkey, String)> {
+        if metadata_uri.len() > MAX_METADATA_URI_LEN {
+            return Err(anyhow!("Metadata URI is longer than {} bytes", MAX_METADATA_URI_LEN));
+        }
+        let hash = parse_report_hash(report_hash)?;
+        let report = ReportLogger::new()?.reports_with_hash(&hash)?.into_iter()
+            .rfind(|report| report.status == ReportStatus::Active)
+            .ok_or_else(|| anyhow!("Report {} isn't logged on chain as active", report_hash))?;
+        let report = Pubkey::from_str(&report.address)?;
+        let token_program = Pubkey::from_str(TOKEN_2022_PROGRAM_ID)?;
+        let associated_token_program = Pubkey::from_str(ASSOCIATED_TOKEN_PROGRAM_ID)?;
+        let system_program = Pubkey::from_str(SYSTEM_PROGRAM_ID)?;
+        let payer = self.payer.pubkey();
+        let mint = Keypair::new();
+        let mint_key = mint.pubkey();
+        let token_account = Pubkey::find_program_address(
+            &[owner.as_ref(), token_program.as_ref(), mint_key.as_ref()],
+            &associated_token_program,
+        ).0;
+
+        // The metadata is written into the mint after it's created, growing the account,
+        // so it's funded up front for its final size
+        let metadata_len = 4 + 32 + 32
+            + borsh_string_len(CERTIFICATE_NAME) + borsh_string_len(CERTIFICATE_SYMBOL) + borsh_string_len(metadata_uri)
+            + 4 + borsh_string_len("report_hash") + borsh_string_len(report_hash);
+        let lamports = tracing::info_span!("solana.rpc", rpc.method = "getMinimumBalanceForRentExemption")
+            .in_scope(|| self.client.get_minimum_balance_for_rent_exemption(MINT_LEN + metadata_len))?;
+
+        let mut create_account = 0u32.to_le_bytes().to_vec();
+        create_account.extend_from_slice(&lamports.to_le_bytes());
+        create_account.extend_from_slice(&(MINT_LEN as u64).to_le_bytes());
+        create_account.extend_from_slice(token_program.as_ref());
+
+        // MetadataPointer::Initialize: authority, then the metadata address (the mint itself)
+        let mut metadata_pointer = vec![39, 0];
+        metadata_pointer.extend_from_slice(payer.as_ref());
+        metadata_pointer.extend_from_slice(mint_key.as_ref());
+
+        // InitializeMint2: no decimals, payer as mint authority, no freeze authority
+        let mut initialize_mint = vec![20, 0];
+        initialize_mint.extend_from_slice(payer.as_ref());
+        initialize_mint.push(0);
+
+        let mut initialize_metadata = interface_discriminator("initialize_account").to_vec();
+        push_borsh_string(&mut initialize_metadata, CERTIFICATE_NAME);
+        push_borsh_string(&mut initialize_metadata, CERTIFICATE_SYMBOL);
+        push_borsh_string(&mut initialize_metadata, metadata_uri);
+
+        // UpdateField with Field::Key("report_hash")
+        let mut report_hash_field = interface_discriminator("updating_field").to_vec();
+        report_hash_field.push(3);
+        push_borsh_string(&mut report_hash_field, "report_hash");
+        push_borsh_string(&mut report_hash_field, report_hash);
+
+        let mut mint_to = vec![7];
+        mint_to.extend_from_slice(&1u64.to_le_bytes());
+
+        let token_instruction = |data: Vec<u8>, accounts: Vec<AccountMeta>| Instruction { program_id: token_program, accounts, data };
+        let instructions = [
+            assert_report_instruction(&report, &hash)?,
+            Instruction {
+                program_id: system_program,
+                accounts: vec![AccountMeta::new(payer, true), AccountMeta::new(mint_key, true)],
+                data: create_account,
+            },
+            // Extensions have to be initialized before the mint itself
+            token_instruction(vec![32], vec![AccountMeta::new(mint_key, false)]),
+            token_instruction(metadata_pointer, vec![AccountMeta::new(mint_key, false)]),
+            token_instruction(initialize_mint, vec![AccountMeta::new(mint_key, false)]),
+            token_instruction(initialize_metadata, vec![
+                AccountMeta::new(mint_key, false),
+                AccountMeta::new_readonly(payer, false),
+                AccountMeta::new_readonly(mint_key, false),
+                AccountMeta::new_readonly(payer, true),
+            ]),
+            token_instruction(report_hash_field, vec![AccountMeta::new(mint_key, false), AccountMeta::new_readonly(payer, true)]),
+            Instruction {
+                program_id: associated_token_program,
+                accounts: vec![
+                    AccountMeta::new(payer, true),
+                    AccountMeta::new(token_account, false),
+                    AccountMeta::new_readonly(*owner, false),
+                    AccountMeta::new_readonly(mint_key, false),
+                    AccountMeta::new_readonly(system_program, false),
+                    AccountMeta::new_readonly(token_program, false),
+                ],
+                // CreateIdempotent
+                data: vec![1],
+            },
+            token_instruction(mint_to, vec![
+                AccountMeta::new(mint_key, false),
+                AccountMeta::new(token_account, false),
+                AccountMeta::new_readonly(payer, true),
+            ]),
+            // SetAuthority(MintTokens, None): no second certificate can be minted
+            token_instruction(vec![6, 0, 0], vec![AccountMeta::new(mint_key, false), AccountMeta::new_readonly(payer, true)]),
+        ];
+
+        let message = Message::new(&instructions, Some(&payer));
+        let mut transaction = Transaction::new_unsigned(message);
+        let recent_blockhash = tracing::info_span!("solana.rpc", rpc.method = "getLatestBlockhash")
+            .in_scope(|| self.client.get_latest_blockhash())?;
+        transaction.sign(&[&self.payer, &mint], recent_blockhash);
+
+        let signature = tracing::info_span!("solana.rpc", rpc.method = "sendTransaction")
+            .in_scope(|| self.client.send_and_confirm_transaction(&transaction))?;
+        Ok((mint_key, signature.to_string()))
+    }
+}
+
+// First 8 bytes of sha256("spl_token_metadata_interface:<name>")
+fn interface_discriminator(name: &str) -> [u8; 8] {
+    let digest = Sha256::digest(format!("spl_token_metadata_interface:{}", name).as_bytes());
+    let mut discriminator = [0u8; 8];
+    discriminator.copy_from_slice(&digest[..8]);
+    discriminator
+}
+
+fn borsh_string_len(value: &str) -> usize {
+    4 + value.len()
+}
+
+fn push_borsh_string(data: &mut Vec<u8>, value: &str) {
+    data.extend_from_slice(&(value.len() as u32).to_le_bytes());
+    data.extend_from_slice(value.as_bytes());
+}
@@ -0,0 +1,136 @@
+use anyhow::{anyhow, Result};
+use sha2::{Digest, Sha256};
+use std::collections::HashMap;
+use std::env;
+use std::fs;
+use std::ops::Deref;
+use std::path::{Component, Path, PathBuf};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, SystemTime};
+use tempfile::TempDir;
+
+use crate::github::{GitHubClient, RequestToken};
+use crate::repo_url::RepoUrl;
+
+const FETCHED_MARKER: &str = ".safex-fetched";
+
+// Shallow working copies of recently used repositories, reused by read-only endpoints
+// (file viewer, stats) instead of cloning on every request
+pub struct CloneCache {
+    root: PathBuf,
+    ttl: Duration,
+    // One lock per repository so concurrent requests don't clone the same repo twice
+    locks: Mutex<HashMap<String, Arc<tokio::sync::Mutex<()>>>>,
+}
+
+impl CloneCache {
+    // SAFEX_CLONE_CACHE_DIR (default ./clone-cache), SAFEX_CLONE_CACHE_TTL_SECS (default 600)
+    pub fn from_env() -> Self {
+        let root = PathBuf::from(env::var("SAFEX_CLONE_CACHE_DIR").unwrap_or_else(|_| "clone-cache".to_string()));
+        let ttl = env::var("SAFEX_CLONE_CACHE_TTL_SECS").ok().and_then(|v| v.parse().ok()).unwrap_or(600);
+        println!("Caching clones in {} for {}s", root.display(), ttl);
+        Self {
+            root,
+            ttl: Duration::from_secs(ttl),
+            locks: Mutex::new(HashMap::new()),
+        }
+    }
+
+    // An up-to-date clone, re-cloning when the cached copy is older than the TTL. A request
+    // with its own GitHub token gets a private clone instead: what it can read must not be
+    // served to other callers, and the cache isn't refreshed with it either.
+    pub async fn checkout(&self, repo_url: &RepoUrl, token: &RequestToken) -> Result<Checkout> {
+        if token.as_deref().is_some() {
+            let dir = TempDir::new()?;
+            GitHubClient::new().with_request_token(token).clone_repo(repo_url.clone_url(), dir.path())?;
+            return Ok(Checkout::Private(dir));
+        }
+        self.cached_checkout(repo_url).await.map(Checkout::Cached)
+    }
+
+    async fn cached_checkout(&self, repo_url: &RepoUrl) -> Result<PathBuf> {
+        let key = cache_key(repo_url);
+        let lock = self.locks.lock().unwrap().entry(key.clone()).or_default().clone();
+        let _guard = lock.lock().await;
+
+        let path = self.root.join(&key);
+        if self.is_fresh(&path) {
+            return Ok(path);
+        }
+
+        // Clone next to the old copy and swap it in, so a failed clone keeps the stale one usable
+        fs::create_dir_all(&self.root)?;
+        let staging = self.root.join(format!("{}.{}", key, uuid::Uuid::new_v4()));
+        let github_client = GitHubClient::new();
+        if let Err(e) = github_client.clone_repo(repo_url.clone_url(), &staging) {
+            let _ = fs::remove_dir_all(&staging);
+            if path.exists() {
+                println!("Warning: Refreshing cached clone failed, serving stale copy: {}", e);
+                return Ok(path);
+            }
+            return Err(e);
+        }
+        fs::write(staging.join(FETCHED_MARKER), repo_url.canonical())?;
+
+        if path.exists() {
+            let retired = self.root.join(format!("{}.old.{}", key, uuid::Uuid::new_v4()));
+            fs::rename(&path, &retired)?;
+            let _ = fs::remove_dir_all(&retired);
+        }
+        fs::rename(&staging, &path)?;
+        Ok(path)
+    }
+
+    pub fn root(&self) -> &Path {
+        &self.root
+    }
+
+    fn is_fresh(&self, path: &Path) -> bool {
+        fs::metadata(path.join(FETCHED_MARKER))
+            .and_then(|m| m.modified())
+            .ok()
+            .and_then(|modified| SystemTime::now().duration_since(modified).ok())
+            .is_some_and(|age| age < self.ttl)
+    }
+}
+
+// A clone to read from; private clones are removed when dropped
+pub enum Checkout {
+    Cached(PathBuf),
+    Private(TempDir),
+}
+
+impl Deref for Checkout {
+    type Target = Path;
+
+    fn deref(&self) -> &Path {
+        match self {
+            Checkout::Cached(path) => path,
+            Checkout::Private(dir) => dir.path(),
+        }
+    }
+}
+
+fn cache_key(repo_url: &RepoUrl) -> String {
+    let mut hasher = Sha256::new();
+    hasher.update(repo_url.canonical().as_bytes());
+    format!("{:x}", hasher.finalize())[..16].to_string()
+}
+
+// Resolve a user-supplied path inside a clone, refusing anything outside it or under .git
+pub fn resolve_repo_path(clone_root: &Path, relative: &str) -> Result<PathBuf> {
+    let relative = Path::new(relative.trim_start_matches('/'));
+    let escapes = relative.components().any(|c| !matches!(c, Component::Normal(_)))
+        || relative.components().next().is_some_and(|c| c.as_os_str() == ".git");
+    if escapes || relative.as_os_str().is_empty() {
+        return Err(anyhow!("Invalid path: {}", relative.display()));
+    }
+
+    // Symlinks in the repo could still point elsewhere
+    let resolved = clone_root.join(relative).canonicalize()
+        .map_err(|_| anyhow!("File not found: {}", relative.display()))?;
+    if !resolved.starts_with(clone_root.canonicalize()?) {
+        return Err(anyhow!("Invalid path: {}", relative.display()));
+    }
+    Ok(resolved)
+}
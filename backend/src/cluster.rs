@@ -0,0 +1,99 @@
+use anyhow::{anyhow, Result};
+use serde::{Deserialize, Serialize};
+use solana_client::nonblocking::rpc_client::RpcClient;
+use std::env;
+use std::time::Duration;
+
+use crate::config;
+
+// Solana cluster a request targets for on-chain lookups
+#[derive(Debug, Clone, Copy, PartialEq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum Cluster {
+    MainnetBeta,
+    #[default]
+    Devnet,
+    Testnet,
+    Localnet,
+}
+
+impl Cluster {
+    pub const ALL: [Cluster; 4] = [Cluster::MainnetBeta, Cluster::Devnet, Cluster::Testnet, Cluster::Localnet];
+
+    // The cluster the report registry is used on: SAFEX_REPORT_CLUSTER, devnet by default
+    pub fn registry() -> Result<Cluster> {
+        match env::var("SAFEX_REPORT_CLUSTER") {
+            Ok(name) => Self::parse(&name).ok_or_else(|| anyhow!("Unknown SAFEX_REPORT_CLUSTER: {}", name)),
+            Err(_) => Ok(Cluster::default()),
+        }
+    }
+
+    pub fn parse(value: &str) -> Option<Self> {
+        Self::ALL.into_iter().find(|cluster| cluster.as_str() == value.trim())
+    }
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Cluster::MainnetBeta => "mainnet-beta",
+            Cluster::Devnet => "devnet",
+            Cluster::Testnet => "testnet",
+            Cluster::Localnet => "localnet",
+        }
+    }
+
+    fn rpc_url_var(&self) -> String {
+        format!("SAFEX_RPC_URL_{}", self.as_str().replace('-', "_").to_uppercase())
+    }
+
+    fn ws_url_var(&self) -> String {
+        format!("SAFEX_WS_URL_{}", self.as_str().replace('-', "_").to_uppercase())
+    }
+
+    // SAFEX_RPC_URL_<CLUSTER> (e.g. SAFEX_RPC_URL_MAINNET_BETA) overrides the public endpoint
+    pub fn rpc_url(&self) -> String {
+        config::var(&self.rpc_url_var()).unwrap_or_else(|| match self {
+            Cluster::MainnetBeta => "https://api.mainnet-beta.solana.com".to_string(),
+            Cluster::Devnet => "https://api.devnet.solana.com".to_string(),
+            Cluster::Testnet => "https://api.testnet.solana.com".to_string(),
+            Cluster::Localnet => "http://127.0.0.1:8899".to_string(),
+        })
+    }
+
+    // SAFEX_WS_URL_<CLUSTER> overrides the websocket endpoint; otherwise it's derived from the
+    // RPC URL like the Solana CLI does, with wss for https, ws for http and the next port up
+    pub fn ws_url(&self) -> String {
+        if let Some(url) = config::var(&self.ws_url_var()) {
+            return url;
+        }
+        let rpc_url = self.rpc_url();
+        let (scheme, rest) = match rpc_url.split_once("://") {
+            Some(("https", rest)) => ("wss", rest),
+            Some((_, rest)) => ("ws", rest),
+            None => ("ws", rpc_url.as_str()),
+        };
+        let (host, path) = rest.split_once('/').map(|(host, path)| (host, format!("/{}", path))).unwrap_or((rest, String::new()));
+        let host = match host.rsplit_once(':').map(|(name, port)| (name, port.parse::<u16>())) {
+            Some((name, Ok(port))) => format!("{}:{}", name, port.saturating_add(1)),
+            _ => host.to_string(),
+        };
+        format!("{}://{}{}", scheme, host, path)
+    }
+
+    pub fn rpc_client(&self) -> RpcClient {
+        RpcClient::new_with_timeout(self.rpc_url(), Duration::from_secs(15))
+    }
+}
+
+// The RPC and websocket URL overrides, before a config with them is put in place
+pub fn validate_endpoints(var: &dyn Fn(&str) -> Option<String>) -> Result<()> {
+    for cluster in Cluster::ALL {
+        for (name, schemes) in [(cluster.rpc_url_var(), ["http://", "https://"]), (cluster.ws_url_var(), ["ws://", "wss://"])] {
+            if let Some(url) = var(&name) {
+                if !schemes.iter().any(|scheme| url.starts_with(scheme) && url.len() > scheme.len()) {
+                    return Err(anyhow!("Invalid {}: must be a {} or {} URL", name, schemes[0], schemes[1]));
+                }
+            }
+        }
+    }
+    Ok(())
+}
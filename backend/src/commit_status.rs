@@ -0,0 +1,72 @@
+use std::env;
+
+use crate::github::GitHubClient;
+use crate::models::{BugSeverity, CodeBug, CommitState, CommitStatus};
+use crate::repo_url::RepoUrl;
+
+pub const STATUS_CONTEXT: &str = "safex/analysis";
+
+// GitHub rejects longer status descriptions
+const MAX_DESCRIPTION_LEN: usize = 140;
+
+// SAFEX_COMMIT_STATUS_FAIL_ON is the lowest severity that fails the status (high by default)
+pub fn default_fail_on() -> BugSeverity {
+    env::var("SAFEX_COMMIT_STATUS_FAIL_ON").ok()
+        .and_then(|severity| BugSeverity::parse(severity.trim()))
+        .unwrap_or(BugSeverity::High)
+}
+
+// Whether no finding is at or above `fail_on`
+pub fn gate_passed(bugs: &[CodeBug], fail_on: BugSeverity) -> bool {
+    !bugs.iter().any(|bug| bug.severity >= fail_on)
+}
+
+// Failure when any finding is at or above `fail_on`; the description counts findings by severity
+pub fn summarize(bugs: &[CodeBug], fail_on: BugSeverity) -> (CommitState, String) {
+    let count = |severity: BugSeverity| bugs.iter().filter(|bug| bug.severity == severity).count();
+    let mut counts = format!("{} high, {} medium, {} low", count(BugSeverity::High), count(BugSeverity::Medium), count(BugSeverity::Low));
+    if count(BugSeverity::Critical) > 0 {
+        counts = format!("{} critical, {}", count(BugSeverity::Critical), counts);
+    }
+    let failing = bugs.iter().filter(|bug| bug.severity >= fail_on).count();
+    let (state, mut description) = if failing > 0 {
+        (CommitState::Failure, format!("{} findings at or above {} ({})", failing, fail_on.as_str(), counts))
+    } else {
+        (CommitState::Success, format!("No findings at or above {} ({})", fail_on.as_str(), counts))
+    };
+    description.truncate(MAX_DESCRIPTION_LEN);
+    (state, description)
+}
+
+// Set the `safex/analysis` status on `sha`. Failing to post doesn't fail the analysis,
+// so the outcome (including any error) is returned for the response instead.
+#[tracing::instrument(name = "commit_status.post", skip(github, bugs), fields(repo = %repo_url.canonical()))]
+pub async fn post_commit_status(
+    github: &GitHubClient,
+    repo_url: &RepoUrl,
+    sha: &str,
+    bugs: &[CodeBug],
+    fail_on: BugSeverity,
+    target_url: Option<String>,
+) -> CommitStatus {
+    let (state, description) = summarize(bugs, fail_on);
+    let posted = match repo_url.owner_repo() {
+        Some((owner, repo)) => github
+            .create_commit_status(&format!("{}/{}", owner, repo), sha, state.as_str(), &description, target_url.as_deref(), STATUS_CONTEXT)
+            .await
+            .map_err(|e| e.to_string()),
+        None => Err("Commit statuses are only supported for GitHub repositories".to_string()),
+    };
+    if let Err(e) = &posted {
+        println!("Warning: Failed to set commit status on {}: {}", sha, e);
+    }
+    CommitStatus {
+        context: STATUS_CONTEXT.to_string(),
+        sha: sha.to_string(),
+        state,
+        description,
+        target_url,
+        posted: posted.is_ok(),
+        error: posted.err(),
+    }
+}
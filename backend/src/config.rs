@@ -0,0 +1,155 @@
+use anyhow::{anyhow, Result};
+use std::collections::{BTreeSet, HashMap};
+use std::env;
+use std::fs;
+use std::sync::{Arc, RwLock};
+use toml::Value;
+
+use crate::cluster::validate_endpoints;
+use crate::cors::configured_origins;
+use crate::db::now_unix;
+use crate::rate_limit::RateLimits;
+use crate::rules::rule_catalog;
+
+// Settings SAFEX_CONFIG_FILE may override; everything else is only read from the environment
+// at startup
+const RELOADABLE: &[&str] = &["SAFEX_CORS_ORIGINS", "SAFEX_RATE_LIMITS", "SAFEX_TRUST_PROXY", "SAFEX_DISABLED_RULES"];
+const RELOADABLE_PREFIXES: &[&str] = &["SAFEX_RATE_LIMIT_", "SAFEX_RPC_URL_", "SAFEX_WS_URL_"];
+
+static CURRENT: RwLock<Option<Arc<Config>>> = RwLock::new(None);
+
+// The settings that can change without a restart: CORS origins, rate limits, Solana RPC and
+// websocket URLs and disabled rules. They come from the SAFEX_* environment variables, which
+// SAFEX_CONFIG_FILE (a TOML table of the same names) overrides. The file is read again on
+// SIGHUP or POST /api/admin/reload-config, and the new config only replaces the running one
+// once all of it validates. Requests and jobs already running keep what they started with.
+pub struct Config {
+    file: HashMap<String, String>,
+    pub cors_origins: Vec<String>,
+    // None when SAFEX_RATE_LIMITS=off. Clients' buckets carry over a reload that leaves their
+    // quota alone.
+    pub rate_limits: Option<Arc<RateLimits>>,
+    // Rule IDs whose findings are dropped: SAFEX_DISABLED_RULES, comma-separated
+    pub disabled_rules: BTreeSet<String>,
+    pub loaded_at: i64,
+}
+
+impl Config {
+    fn load(previous: Option<&Config>) -> Result<Self> {
+        let file = read_file()?;
+        let var = |name: &str| file.get(name).cloned().or_else(|| env::var(name).ok());
+
+        let cors_origins = configured_origins(&var)?;
+        let rate_limits = RateLimits::load(&var, previous.and_then(|config| config.rate_limits.as_deref()))?.map(Arc::new);
+        validate_endpoints(&var)?;
+        let catalog: BTreeSet<String> = rule_catalog().into_iter().map(|rule| rule.id).collect();
+        let disabled_rules = var("SAFEX_DISABLED_RULES").unwrap_or_default()
+            .split(',')
+            .map(str::trim)
+            .filter(|id| !id.is_empty())
+            .map(|id| match catalog.contains(id) {
+                true => Ok(id.to_string()),
+                false => Err(anyhow!("Unknown rule in SAFEX_DISABLED_RULES: {}", id)),
+            })
+            .collect::<Result<BTreeSet<_>>>()?;
+
+        Ok(Self { file, cors_origins, rate_limits, disabled_rules, loaded_at: now_unix() })
+    }
+
+    pub fn var(&self, name: &str) -> Option<String> {
+        self.file.get(name).cloned().or_else(|| env::var(name).ok())
+    }
+
+    // The settings SAFEX_CONFIG_FILE sets, without their values (RPC URLs can carry API keys)
+    pub fn overrides(&self) -> Vec<String> {
+        let mut names: Vec<String> = self.file.keys().cloned().collect();
+        names.sort();
+        names
+    }
+}
+
+// Load the config at startup; fails on a config the server shouldn't start with. Logs nothing,
+// since the language server's stdio transport needs stdout to itself.
+pub fn init() -> Result<()> {
+    let config = Config::load(None)?;
+    *CURRENT.write().unwrap() = Some(Arc::new(config));
+    Ok(())
+}
+
+// What init loaded, for the HTTP API's startup log
+pub fn log_loaded() {
+    let config = current();
+    if let Ok(path) = env::var("SAFEX_CONFIG_FILE") {
+        println!("Loaded {} settings from {}", config.file.len(), path);
+    }
+    println!("{}", RateLimits::describe(config.rate_limits.as_deref()));
+}
+
+pub fn current() -> Arc<Config> {
+    CURRENT.read().unwrap().clone().expect("config::init runs at startup")
+}
+
+// A setting from the running config
+pub fn var(name: &str) -> Option<String> {
+    current().var(name)
+}
+
+// Read SAFEX_CONFIG_FILE again and swap the config in; the running one stays on any error
+pub fn reload() -> Result<Arc<Config>> {
+    if env::var("SAFEX_CONFIG_FILE").is_err() {
+        return Err(anyhow!("No SAFEX_CONFIG_FILE configured; the environment can't change without a restart"));
+    }
+    let config = Arc::new(Config::load(Some(&current()))?);
+    *CURRENT.write().unwrap() = Some(config.clone());
+    println!("{}", RateLimits::describe(config.rate_limits.as_deref()));
+    Ok(config)
+}
+
+#[cfg(unix)]
+pub fn spawn_reload_on_sighup() {
+    use tokio::signal::unix::{signal, SignalKind};
+
+    let mut hangups = match signal(SignalKind::hangup()) {
+        Ok(hangups) => hangups,
+        Err(e) => {
+            println!("Warning: Failed to listen for SIGHUP, config reload is only available over the API: {}", e);
+            return;
+        }
+    };
+    tokio::spawn(async move {
+        while hangups.recv().await.is_some() {
+            match reload() {
+                Ok(config) => println!("Reloaded configuration on SIGHUP ({} settings from the file)", config.file.len()),
+                Err(e) => println!("Warning: Config reload failed, keeping the running config: {}", e),
+            }
+        }
+    });
+}
+
+fn read_file() -> Result<HashMap<String, String>> {
+    let Ok(path) = env::var("SAFEX_CONFIG_FILE") else {
+        return Ok(HashMap::new());
+    };
+    let content = fs::read_to_string(&path).map_err(|e| anyhow!("Failed to read {}: {}", path, e))?;
+    let table: toml::Table = content.parse().map_err(|e| anyhow!("Invalid {}: {}", path, e))?;
+
+    let mut settings = HashMap::new();
+    for (name, value) in table {
+        let reloadable = RELOADABLE.contains(&name.as_str()) || RELOADABLE_PREFIXES.iter().any(|prefix| name.starts_with(prefix));
+        if !reloadable {
+            return Err(anyhow!("{} can't be set in {}; it is only read from the environment at startup", name, path));
+        }
+        let value = match value {
+            Value::String(value) => value,
+            Value::Integer(value) => value.to_string(),
+            Value::Boolean(value) => value.to_string(),
+            Value::Array(values) => values.iter()
+                .map(|value| value.as_str().map(str::to_string).ok_or_else(|| anyhow!("{} in {} must be a list of strings", name, path)))
+                .collect::<Result<Vec<_>>>()?
+                .join(","),
+            _ => return Err(anyhow!("{} in {} must be a string, number, boolean or list of strings", name, path)),
+        };
+        settings.insert(name, value);
+    }
+    Ok(settings)
+}
@@ -0,0 +1,102 @@
+use anyhow::{anyhow, Result};
+use futures_util::StreamExt;
+use solana_client::nonblocking::pubsub_client::PubsubClient;
+use solana_client::rpc_config::RpcSignatureSubscribeConfig;
+use solana_client::rpc_response::RpcSignatureResult;
+use solana_commitment_config::CommitmentConfig;
+use solana_sdk::signature::Signature;
+use std::time::Duration;
+use tokio::sync::oneshot;
+use tokio::time::{timeout_at, Instant};
+
+use crate::cluster::Cluster;
+use crate::models::ConfirmationStatus;
+
+// Long enough for the blockhash to expire, after which the transaction can't land
+const TRACKING_TIMEOUT: Duration = Duration::from_secs(120);
+
+// Follow `signature` through processed, confirmed and finalized over the websocket API,
+// calling `on_status` at each step (or with Failed and the reason). The node only notifies
+// about transactions it processes after the subscription, so `ready` fires once all three
+// subscriptions are in place and the transaction can be sent; an error there means
+// tracking is unavailable.
+pub async fn track_signature<F>(signature: Signature, ready: oneshot::Sender<Result<()>>, mut on_status: F)
+where
+    F: FnMut(ConfirmationStatus, Option<String>),
+{
+    let ws_url = match Cluster::registry() {
+        Ok(cluster) => cluster.ws_url(),
+        Err(e) => {
+            let _ = ready.send(Err(e));
+            return;
+        }
+    };
+    let client = match PubsubClient::new(&ws_url).await {
+        Ok(client) => client,
+        Err(e) => {
+            let _ = ready.send(Err(anyhow!("Failed to connect to {}: {}", ws_url, e)));
+            return;
+        }
+    };
+    follow(&client, signature, ready, &mut on_status).await;
+    let _ = client.shutdown().await;
+}
+
+async fn follow<F>(client: &PubsubClient, signature: Signature, ready: oneshot::Sender<Result<()>>, on_status: &mut F)
+where
+    F: FnMut(ConfirmationStatus, Option<String>),
+{
+    let levels = [
+        (ConfirmationStatus::Processed, CommitmentConfig::processed()),
+        (ConfirmationStatus::Confirmed, CommitmentConfig::confirmed()),
+        (ConfirmationStatus::Finalized, CommitmentConfig::finalized()),
+    ];
+    let mut subscriptions = Vec::new();
+    for (status, commitment) in levels {
+        let config = RpcSignatureSubscribeConfig {
+            commitment: Some(commitment),
+            enable_received_notification: Some(false),
+        };
+        match client.signature_subscribe(&signature, Some(config)).await {
+            Ok((stream, _unsubscribe)) => subscriptions.push((status, stream)),
+            Err(e) => {
+                let _ = ready.send(Err(anyhow!("Failed to subscribe to signature {}: {}", signature, e)));
+                return;
+            }
+        }
+    }
+    if ready.send(Ok(())).is_err() {
+        // The transaction was never sent
+        return;
+    }
+
+    // Each subscription notifies once, when its commitment level is reached
+    let deadline = Instant::now() + TRACKING_TIMEOUT;
+    let mut reached = None;
+    for (status, mut stream) in subscriptions {
+        match timeout_at(deadline, stream.next()).await {
+            Ok(Some(response)) => {
+                if let RpcSignatureResult::ProcessedSignature(result) = response.value {
+                    if let Some(err) = result.err {
+                        on_status(ConfirmationStatus::Failed, Some(format!("Transaction failed: {:?}", err)));
+                        return;
+                    }
+                }
+                on_status(status, None);
+                reached = Some(status);
+            },
+            Ok(None) => {
+                on_status(reached.unwrap_or(ConfirmationStatus::Failed), Some("Websocket closed while waiting for confirmation".to_string()));
+                return;
+            },
+            Err(_) => {
+                let message = match reached {
+                    Some(reached) => format!("Stopped tracking at {} after {}s", reached.as_str(), TRACKING_TIMEOUT.as_secs()),
+                    None => format!("Transaction was not processed within {}s", TRACKING_TIMEOUT.as_secs()),
+                };
+                on_status(reached.unwrap_or(ConfirmationStatus::Failed), Some(message));
+                return;
+            }
+        }
+    }
+}
@@ -0,0 +1,113 @@
+use actix_cors::Cors;
+use anyhow::{anyhow, Result};
+use std::collections::BTreeMap;
+use std::sync::{Arc, RwLock};
+
+use crate::config;
+use crate::db::{now_unix, Database};
+use crate::models::AllowedOrigin;
+
+// Used when SAFEX_CORS_ORIGINS isn't set: the frontend's local dev servers
+const DEFAULT_ORIGINS: &str = "http://localhost:3000,http://localhost:3001";
+
+// Allows every origin
+pub const ANY_ORIGIN: &str = "*";
+
+// SAFEX_CORS_ORIGINS (comma-separated, `*` for any), normalized
+pub fn configured_origins(var: &dyn Fn(&str) -> Option<String>) -> Result<Vec<String>> {
+    var("SAFEX_CORS_ORIGINS").unwrap_or_else(|| DEFAULT_ORIGINS.to_string())
+        .split(',')
+        .map(str::trim)
+        .filter(|origin| !origin.is_empty())
+        .map(|origin| normalize_origin(origin).map_err(|e| anyhow!("Invalid SAFEX_CORS_ORIGINS: {}", e)))
+        .collect()
+}
+
+// The origins browsers may call the API from: the configured ones (see configured_origins,
+// reloadable) plus those admins add at runtime, which are persisted
+pub struct AllowedOrigins {
+    added: RwLock<BTreeMap<String, AllowedOrigin>>,
+}
+
+impl AllowedOrigins {
+    pub fn load(db: &Database) -> Result<Self> {
+        let added = db.list_cors_origins()?.into_iter()
+            .map(|origin| (origin.origin.clone(), origin))
+            .collect();
+        Ok(Self { added: RwLock::new(added) })
+    }
+
+    // `origin` as browsers send it in the Origin header
+    pub fn allows(&self, origin: &str) -> bool {
+        let origin = origin.to_ascii_lowercase();
+        config::current().cors_origins.iter().any(|allowed| allowed == ANY_ORIGIN || *allowed == origin)
+            || self.added.read().unwrap().contains_key(&origin)
+    }
+
+    pub fn is_configured(&self, origin: &str) -> bool {
+        config::current().cors_origins.iter().any(|allowed| allowed == origin)
+    }
+
+    // Configured origins first, then added ones alphabetically
+    pub fn list(&self) -> Vec<AllowedOrigin> {
+        let configured = config::current().cors_origins.clone().into_iter().map(|origin| AllowedOrigin {
+            origin,
+            configured: true,
+            added_by: None,
+            added_at: None,
+        });
+        configured.chain(self.added.read().unwrap().values().cloned()).collect()
+    }
+
+    // False when the origin was already allowed. `origin` must be normalized.
+    pub fn add(&self, db: &Database, origin: &str, added_by: &str) -> Result<bool> {
+        if self.is_configured(origin) || !db.insert_cors_origin(origin, added_by)? {
+            return Ok(false);
+        }
+        self.added.write().unwrap().insert(origin.to_string(), AllowedOrigin {
+            origin: origin.to_string(),
+            configured: false,
+            added_by: Some(added_by.to_string()),
+            added_at: Some(now_unix()),
+        });
+        Ok(true)
+    }
+
+    // False when the origin wasn't added at runtime; configured ones stay until the config changes
+    pub fn remove(&self, db: &Database, origin: &str) -> Result<bool> {
+        let removed = db.delete_cors_origin(origin)?;
+        self.added.write().unwrap().remove(origin);
+        Ok(removed)
+    }
+}
+
+// CORS checked against `origins` on every request, so changes apply without a restart
+pub fn cors(origins: Arc<AllowedOrigins>) -> Cors {
+    Cors::permissive().allowed_origin_fn(move |origin, _| origin.to_str().is_ok_and(|origin| origins.allows(origin)))
+}
+
+// An origin is a scheme, host and optional port, e.g. https://app.example.com or
+// http://localhost:3000. A trailing slash is dropped and the result lowercased.
+pub fn normalize_origin(origin: &str) -> Result<String> {
+    let origin = origin.trim();
+    if origin == ANY_ORIGIN {
+        return Ok(origin.to_string());
+    }
+    let lowercase = origin.trim_end_matches('/').to_ascii_lowercase();
+    let Some((scheme, authority)) = lowercase.split_once("://") else {
+        return Err(anyhow!("{} is not an origin (expected e.g. https://app.example.com)", origin));
+    };
+    if scheme != "http" && scheme != "https" {
+        return Err(anyhow!("{} must use http or https", origin));
+    }
+    let (host, port) = match authority.rsplit_once(':') {
+        Some((host, port)) => (host, Some(port)),
+        None => (authority, None),
+    };
+    let valid_host = !host.is_empty() && host.chars().all(|c| c.is_ascii_alphanumeric() || c == '.' || c == '-');
+    let valid_port = port.is_none_or(|port| port.parse::<u16>().is_ok());
+    if !valid_host || !valid_port {
+        return Err(anyhow!("{} is not an origin (expected scheme://host[:port] without a path)", origin));
+    }
+    Ok(lowercase)
+}
@@ -0,0 +1,138 @@
+use anyhow::{anyhow, Result};
+use regex::Regex;
+use std::collections::{BTreeMap, HashSet};
+use std::fs;
+use std::path::Path;
+use toml::Table;
+
+use crate::models::{CpiLocation, CpiSurface, CpiTarget};
+use crate::stats::{collect_files, is_test_path, line_at};
+
+// Repository-level Safex settings
+pub const CONFIG_FILE: &str = ".safex.toml";
+
+const SYSTEM_PROGRAM: (&str, &str) = ("11111111111111111111111111111111", "System Program");
+const TOKEN_PROGRAM: (&str, &str) = ("TokenkegQfeZyiNwAJbNbGKPFXCWuBvf9Ss623VQ5DA", "SPL Token");
+const TOKEN_2022_PROGRAM: (&str, &str) = ("TokenzQdBNbLqP5VEhdkAS6EPFLC1PHnBqCXEpPxuEb", "SPL Token-2022");
+const ASSOCIATED_TOKEN_PROGRAM: (&str, &str) = ("ATokenGPvbdGVxr1b2hvZbsiqW5xWH25efTNsLJA8knL", "Associated Token Account");
+const METADATA_PROGRAM: (&str, &str) = ("metaqbxxUerdq28cj1RbAWkYQm3ybzjb6a8bt518x1s", "Metaplex Token Metadata");
+const MEMO_PROGRAM: (&str, &str) = ("MemoSq4gqABAXKb96qnH8TysNcWxMyWCqXgDLGmfcHr", "SPL Memo");
+
+// The programs a marker in the source invokes: Anchor's `Program<'info, T>` / `Interface<'info, T>`
+// types, anchor-spl CPI modules and the instruction builders of the programs' own crates.
+// An interface stands for every program implementing it.
+fn known_programs(marker: &str) -> &'static [(&'static str, &'static str)] {
+    match marker {
+        "System" | "system_program" | "system_instruction" => &[SYSTEM_PROGRAM],
+        "Token" | "token" | "spl_token" => &[TOKEN_PROGRAM],
+        "Token2022" | "token_2022" | "spl_token_2022" => &[TOKEN_2022_PROGRAM],
+        "TokenInterface" | "token_interface" => &[TOKEN_PROGRAM, TOKEN_2022_PROGRAM],
+        "AssociatedToken" | "associated_token" | "spl_associated_token_account" => &[ASSOCIATED_TOKEN_PROGRAM],
+        "Metadata" | "metadata" | "mpl_token_metadata" => &[METADATA_PROGRAM],
+        "Memo" | "memo" | "spl_memo" => &[MEMO_PROGRAM],
+        _ => &[],
+    }
+}
+
+// The programs `[cpi] allowed_programs` in .safex.toml lists, or None when the repository
+// doesn't declare its CPI targets
+pub fn load_allowlist(repo_path: &Path) -> Result<Option<Vec<String>>> {
+    let Ok(content) = fs::read_to_string(repo_path.join(CONFIG_FILE)) else {
+        return Ok(None);
+    };
+    let config: Table = content.parse().map_err(|e| anyhow!("Failed to parse {}: {}", CONFIG_FILE, e))?;
+    let Some(allowed) = config.get("cpi").and_then(|cpi| cpi.get("allowed_programs")) else {
+        return Ok(None);
+    };
+    let allowed = allowed.as_array().ok_or_else(|| anyhow!("cpi.allowed_programs in {} must be a list of program IDs", CONFIG_FILE))?;
+    allowed.iter()
+        .map(|id| id.as_str().map(|id| id.trim().to_string())
+            .ok_or_else(|| anyhow!("cpi.allowed_programs in {} must be a list of program IDs", CONFIG_FILE)))
+        .collect::<Result<Vec<_>>>()
+        .map(Some)
+}
+
+// Every program the repository's programs invoke, found from the program accounts their
+// instructions take and the CPI helpers they call, checked against the allowlist if declared.
+// Programs passed as unchecked accounts and invoked by a runtime address aren't found.
+pub fn cpi_surface(repo_path: &Path) -> Result<CpiSurface> {
+    let allowlist = load_allowlist(repo_path)?;
+
+    let re_program_type = Regex::new(r"\b(?:Program|Interface)\s*<\s*'\w+\s*,\s*(?:\w+::)*(\w+)\s*>").unwrap();
+    let re_anchor_cpi = Regex::new(r"\b(system_program|token|token_2022|token_interface|associated_token|metadata|memo)::[a-z_0-9]+\s*\(\s*(?:CpiContext|\w*ctx\b)").unwrap();
+    let re_instruction = Regex::new(r"\b(system_instruction|spl_token|spl_token_2022|spl_associated_token_account|mpl_token_metadata|spl_memo)::(?:instruction(?:s)?::)?[a-z_0-9]+\s*\(").unwrap();
+    let re_program_mod = Regex::new(r"#\[program\]\s*pub\s+mod\s+(\w+)").unwrap();
+
+    let mut files = Vec::new();
+    collect_files(repo_path, &mut files)?;
+    let mut sources = Vec::new();
+    for file in files.iter().filter(|f| f.extension().is_some_and(|e| e == "rs") && !is_test_path(repo_path, f)) {
+        let Ok(source) = fs::read_to_string(file) else { continue };
+        let path = file.strip_prefix(repo_path).unwrap_or(file).display().to_string();
+        sources.push((path, source));
+    }
+
+    // Anchor names a program's `Program<'info, T>` type after its module in CamelCase. Calls
+    // between the workspace's own programs aren't external and stay off the surface.
+    let workspace_programs: HashSet<String> = sources.iter()
+        .flat_map(|(_, source)| re_program_mod.captures_iter(source))
+        .map(|module| camel_case(&module[1]))
+        .collect();
+
+    // Keyed by program ID, or by type name for programs that couldn't be resolved
+    let mut targets: BTreeMap<String, CpiTarget> = BTreeMap::new();
+    for (path, source) in &sources {
+        let markers = re_program_type.captures_iter(source)
+            .chain(re_anchor_cpi.captures_iter(source))
+            .chain(re_instruction.captures_iter(source));
+        for captures in markers {
+            let marker = captures.get(1).unwrap().as_str();
+            let location = CpiLocation {
+                file: path.clone(),
+                line: line_at(source, captures.get(0).unwrap().start()),
+            };
+            let programs: Vec<(Option<String>, String)> = match (known_programs(marker), workspace_programs.contains(marker)) {
+                ([], true) => continue,
+                ([], false) => vec![(None, marker.to_string())],
+                (known, _) => known.iter().map(|(id, name)| (Some(id.to_string()), name.to_string())).collect(),
+            };
+            for (program_id, name) in programs {
+                let target = targets.entry(program_id.clone().unwrap_or_else(|| name.clone())).or_insert_with(|| CpiTarget {
+                    allowed: program_id.as_ref().and_then(|id| allowlist.as_ref().map(|allowed| allowed.contains(id))),
+                    program_id,
+                    name,
+                    locations: Vec::new(),
+                });
+                if !target.locations.iter().any(|l| l.file == location.file && l.line == location.line) {
+                    target.locations.push(location.clone());
+                }
+            }
+        }
+    }
+
+    for target in targets.values_mut() {
+        target.locations.sort_by(|a, b| a.file.cmp(&b.file).then(a.line.cmp(&b.line)));
+    }
+    let unused_entries = match &allowlist {
+        Some(allowed) => allowed.iter()
+            .filter(|id| !targets.values().any(|t| t.program_id.as_ref() == Some(id)))
+            .cloned()
+            .collect(),
+        None => Vec::new(),
+    };
+    Ok(CpiSurface {
+        declared: allowlist.is_some(),
+        targets: targets.into_values().collect(),
+        unused_entries,
+    })
+}
+
+fn camel_case(name: &str) -> String {
+    name.split('_').map(|part| {
+        let mut chars = part.chars();
+        match chars.next() {
+            Some(first) => first.to_uppercase().chain(chars).collect(),
+            None => String::new(),
+        }
+    }).collect()
+}
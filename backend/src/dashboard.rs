@@ -0,0 +1,56 @@
+use std::env;
+use std::fs;
+use std::path::Path;
+
+use crate::models::DiskUsage;
+
+// tempfile's prefix; every clone, build and fuzz run works in one of these
+const SCRATCH_PREFIX: &str = ".tmp";
+
+// Everything under `path`, without following symlinks; a missing directory takes no space
+pub fn dir_usage(name: &str, path: &Path, entries: Option<u64>) -> DiskUsage {
+    let (bytes, files) = walk(path);
+    DiskUsage {
+        name: name.to_string(),
+        path: path.display().to_string(),
+        bytes,
+        files,
+        entries,
+    }
+}
+
+// The scratch directories in the system temp dir, counted as entries. Other processes using
+// tempfile in the same temp dir are counted too.
+pub fn temp_dir_usage() -> DiskUsage {
+    let root = env::temp_dir();
+    let (mut bytes, mut files, mut entries) = (0, 0, 0);
+    for entry in fs::read_dir(&root).into_iter().flatten().flatten() {
+        if !entry.file_name().to_string_lossy().starts_with(SCRATCH_PREFIX) {
+            continue;
+        }
+        let (entry_bytes, entry_files) = walk(&entry.path());
+        bytes += entry_bytes;
+        files += entry_files;
+        entries += 1;
+    }
+    DiskUsage {
+        name: "temp".to_string(),
+        path: root.display().to_string(),
+        bytes,
+        files,
+        entries: Some(entries),
+    }
+}
+
+// Bytes and files under `path`; files removed mid-walk (a run finishing) are skipped
+fn walk(path: &Path) -> (u64, u64) {
+    let Ok(metadata) = fs::symlink_metadata(path) else {
+        return (0, 0);
+    };
+    if !metadata.is_dir() {
+        return (metadata.len(), 1);
+    }
+    fs::read_dir(path).into_iter().flatten().flatten()
+        .map(|entry| walk(&entry.path()))
+        .fold((0, 0), |(bytes, files), (entry_bytes, entry_files)| (bytes + entry_bytes, files + entry_files))
+}
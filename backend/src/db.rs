@@ -0,0 +1,1954 @@
+use anyhow::{anyhow, Result};
+use rusqlite::{params, params_from_iter, Connection, OptionalExtension};
+use rusqlite::types::Value;
+use std::collections::BTreeMap;
+use std::env;
+use std::sync::Mutex;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use crate::deploy_keys::SshKeyPair;
+use crate::models::{AllowedOrigin, AnchorBackend, ApprovalStatus, AuditEntry, AuditLogQuery, BenchmarkRun, BugSeverity, Certificate, CertificateStatus, CodeBug, ConfirmationStatus, DeployKey, EcosystemProject, Engagement, EngagementRequest, EngagementRun, EngagementRunKind, EngagementState, FindingAssignment, FindingComment, FindingSort, FindingThread, FindingsCursor, FindingsQuery, SortOrder, JiraSettings, JiraTicket, JobInfo, EmailRecipient, Discrepancy, DiscrepancyKind, LoggedReport, ReportAnchor, ReportApproval, ReportLogResponse, ReportSubmission, ReportStatus, ReportsQuery, ReportTemplate, ReviewerApproval, EmailSettings, EmailSettingsRequest, FindingTriage, TrendPoint, TriageState};
+use crate::rules::rule_taxonomy;
+
+// SQLite-backed store for analysis history; one connection guarded by a mutex
+// is plenty for the write volume of a single backend instance
+pub struct Database {
+    conn: Mutex<Connection>,
+}
+
+pub struct AnalysisRun {
+    pub repo_url: String,
+    pub commit_sha: Option<String>,
+}
+
+impl Database {
+    // Open the database at SAFEX_DB_PATH (defaults to ./safex.db)
+    pub fn open_from_env() -> Result<Self> {
+        let path = env::var("SAFEX_DB_PATH").unwrap_or_else(|_| "safex.db".to_string());
+        println!("Using database at: {}", path);
+        Self::open(&path)
+    }
+
+    pub fn open(path: &str) -> Result<Self> {
+        let conn = Connection::open(path)?;
+        Self::migrate(&conn)?;
+        Ok(Self { conn: Mutex::new(conn) })
+    }
+
+    fn migrate(conn: &Connection) -> Result<()> {
+        conn.execute_batch(
+            "CREATE TABLE IF NOT EXISTS analysis_runs (
+                id TEXT PRIMARY KEY,
+                repo_url TEXT NOT NULL,
+                commit_sha TEXT,
+                created_at INTEGER NOT NULL
+            );
+            CREATE INDEX IF NOT EXISTS idx_analysis_runs_repo ON analysis_runs (repo_url, created_at);
+
+            CREATE TABLE IF NOT EXISTS findings (
+                run_id TEXT NOT NULL REFERENCES analysis_runs (id),
+                fingerprint TEXT NOT NULL,
+                severity TEXT NOT NULL,
+                file TEXT,
+                line INTEGER NOT NULL,
+                bug TEXT NOT NULL,
+                fix TEXT NOT NULL,
+                triage_state TEXT
+            );
+            CREATE INDEX IF NOT EXISTS idx_findings_run ON findings (run_id);
+
+            CREATE TABLE IF NOT EXISTS email_settings (
+                tenant TEXT PRIMARY KEY,
+                subject_template TEXT,
+                body_template TEXT,
+                updated_at INTEGER NOT NULL
+            );
+
+            CREATE TABLE IF NOT EXISTS email_recipients (
+                tenant TEXT NOT NULL,
+                email TEXT NOT NULL,
+                unsubscribe_token TEXT NOT NULL UNIQUE,
+                active INTEGER NOT NULL DEFAULT 1,
+                PRIMARY KEY (tenant, email)
+            );
+
+            CREATE TABLE IF NOT EXISTS audit_log (
+                id INTEGER PRIMARY KEY AUTOINCREMENT,
+                tenant TEXT NOT NULL,
+                actor TEXT NOT NULL,
+                action TEXT NOT NULL,
+                target TEXT,
+                params TEXT NOT NULL,
+                outcome TEXT NOT NULL,
+                detail TEXT,
+                started_at INTEGER NOT NULL,
+                finished_at INTEGER NOT NULL
+            );
+            CREATE INDEX IF NOT EXISTS idx_audit_log_tenant ON audit_log (tenant, id);
+
+            CREATE TABLE IF NOT EXISTS report_logs (
+                id TEXT PRIMARY KEY,
+                tenant TEXT NOT NULL,
+                transaction_signature TEXT NOT NULL,
+                hash TEXT NOT NULL,
+                repo_url TEXT NOT NULL,
+                commit_sha TEXT NOT NULL,
+                certificate_id TEXT,
+                status TEXT NOT NULL,
+                error TEXT,
+                created_at INTEGER NOT NULL,
+                updated_at INTEGER NOT NULL
+            );
+
+            CREATE TABLE IF NOT EXISTS certificates (
+                id TEXT PRIMARY KEY,
+                tenant TEXT NOT NULL,
+                report_hash TEXT NOT NULL,
+                wallet TEXT NOT NULL,
+                metadata_uri TEXT NOT NULL,
+                status TEXT NOT NULL,
+                mint TEXT,
+                transaction_signature TEXT,
+                error TEXT,
+                created_at INTEGER NOT NULL,
+                updated_at INTEGER NOT NULL
+            );
+
+            -- Reports held back until enough reviewers approve them
+            CREATE TABLE IF NOT EXISTS report_approvals (
+                id TEXT PRIMARY KEY,
+                tenant TEXT NOT NULL,
+                request TEXT NOT NULL,
+                hash TEXT NOT NULL,
+                repo_url TEXT NOT NULL,
+                commit_sha TEXT NOT NULL,
+                threshold INTEGER NOT NULL,
+                reviewers TEXT NOT NULL,
+                status TEXT NOT NULL,
+                transaction_signature TEXT,
+                job_id TEXT,
+                certificate_id TEXT,
+                error TEXT,
+                created_at INTEGER NOT NULL,
+                updated_at INTEGER NOT NULL
+            );
+
+            CREATE TABLE IF NOT EXISTS report_approval_signatures (
+                approval_id TEXT NOT NULL REFERENCES report_approvals(id),
+                reviewer TEXT NOT NULL,
+                signature TEXT NOT NULL,
+                approved_at INTEGER NOT NULL,
+                PRIMARY KEY (approval_id, reviewer)
+            );
+
+            -- Report accounts as last seen by the indexer, per cluster
+            CREATE TABLE IF NOT EXISTS indexed_reports (
+                cluster TEXT NOT NULL,
+                address TEXT NOT NULL,
+                authority TEXT NOT NULL,
+                hash TEXT NOT NULL,
+                repo_hash TEXT NOT NULL,
+                commit_sha TEXT NOT NULL,
+                timestamp INTEGER NOT NULL,
+                status TEXT NOT NULL,
+                indexed_at INTEGER NOT NULL,
+                PRIMARY KEY (cluster, address)
+            );
+            CREATE INDEX IF NOT EXISTS idx_indexed_reports_time ON indexed_reports (cluster, timestamp);
+            CREATE INDEX IF NOT EXISTS idx_indexed_reports_repo ON indexed_reports (cluster, repo_hash);
+            CREATE INDEX IF NOT EXISTS idx_indexed_reports_hash ON indexed_reports (cluster, hash);
+
+            -- The report-logger deployment re-verification last saw, to notice upgrades
+            CREATE TABLE IF NOT EXISTS registry_programs (
+                cluster TEXT PRIMARY KEY,
+                deploy_slot INTEGER NOT NULL,
+                checked_at INTEGER NOT NULL
+            );
+
+            -- One SSH deploy key per tenant and repository; the private key is sealed
+            CREATE TABLE IF NOT EXISTS deploy_keys (
+                tenant TEXT NOT NULL,
+                repo_url TEXT NOT NULL,
+                public_key TEXT NOT NULL,
+                fingerprint TEXT NOT NULL,
+                sealed_private_key TEXT NOT NULL,
+                created_by TEXT NOT NULL,
+                created_at INTEGER NOT NULL,
+                PRIMARY KEY (tenant, repo_url)
+            );
+
+            -- Public projects registered for the nightly ecosystem scan, with the outcome of
+            -- the latest successful scan; rule_hits is a JSON object of finding counts per rule
+            CREATE TABLE IF NOT EXISTS ecosystem_projects (
+                repo_url TEXT PRIMARY KEY,
+                registered_by TEXT NOT NULL,
+                registered_at INTEGER NOT NULL,
+                scanned_at INTEGER,
+                score INTEGER,
+                rule_hits TEXT,
+                error TEXT
+            );
+
+            -- Corpus benchmark runs; repos and rules are the JSON of the run's results
+            CREATE TABLE IF NOT EXISTS benchmark_runs (
+                id INTEGER PRIMARY KEY AUTOINCREMENT,
+                run_at INTEGER NOT NULL,
+                run_by TEXT NOT NULL,
+                corpus TEXT NOT NULL,
+                repos TEXT NOT NULL,
+                rules TEXT NOT NULL
+            );
+
+            -- How long fuzz runs took to build their harness and run it, to split later runs' budgets
+            CREATE TABLE IF NOT EXISTS fuzz_timings (
+                id INTEGER PRIMARY KEY AUTOINCREMENT,
+                repo_url TEXT NOT NULL,
+                build_ms INTEGER NOT NULL,
+                run_ms INTEGER NOT NULL,
+                cache_hit INTEGER NOT NULL,
+                recorded_at INTEGER NOT NULL
+            );
+            CREATE INDEX IF NOT EXISTS idx_fuzz_timings_repo ON fuzz_timings (repo_url, id);
+
+            -- CORS origins added through the admin API, on top of SAFEX_CORS_ORIGINS
+            CREATE TABLE IF NOT EXISTS cors_origins (
+                origin TEXT PRIMARY KEY,
+                added_by TEXT NOT NULL,
+                added_at INTEGER NOT NULL
+            );
+
+            -- Discussion on findings: one thread per tenant, repository and finding fingerprint,
+            -- so it carries across runs like triage does
+            CREATE TABLE IF NOT EXISTS finding_comments (
+                id TEXT PRIMARY KEY,
+                tenant TEXT NOT NULL,
+                repo_url TEXT NOT NULL,
+                fingerprint TEXT NOT NULL,
+                author TEXT NOT NULL,
+                body TEXT NOT NULL,
+                created_at INTEGER NOT NULL
+            );
+            CREATE INDEX IF NOT EXISTS idx_finding_comments_thread ON finding_comments (tenant, repo_url, fingerprint, created_at);
+            CREATE TABLE IF NOT EXISTS resolved_threads (
+                tenant TEXT NOT NULL,
+                repo_url TEXT NOT NULL,
+                fingerprint TEXT NOT NULL,
+                resolved_by TEXT NOT NULL,
+                resolved_at INTEGER NOT NULL,
+                PRIMARY KEY (tenant, repo_url, fingerprint)
+            );
+            -- Who on a tenant's team reviews a finding, by fingerprint so it carries across runs
+            CREATE TABLE IF NOT EXISTS finding_assignments (
+                tenant TEXT NOT NULL,
+                repo_url TEXT NOT NULL,
+                fingerprint TEXT NOT NULL,
+                assignee TEXT NOT NULL,
+                assigned_by TEXT NOT NULL,
+                assigned_at INTEGER NOT NULL,
+                PRIMARY KEY (tenant, repo_url, fingerprint)
+            );
+            CREATE INDEX IF NOT EXISTS idx_finding_assignments_assignee ON finding_assignments (tenant, assignee, assigned_at);
+
+            -- The API token is sealed with SAFEX_DEPLOY_KEY_SECRET like deploy keys
+            CREATE TABLE IF NOT EXISTS jira_settings (
+                tenant TEXT PRIMARY KEY,
+                base_url TEXT NOT NULL,
+                project_key TEXT NOT NULL,
+                email TEXT,
+                sealed_token TEXT NOT NULL,
+                issue_type TEXT NOT NULL,
+                priorities TEXT NOT NULL,
+                updated_by TEXT NOT NULL,
+                updated_at INTEGER NOT NULL
+            );
+            CREATE TABLE IF NOT EXISTS jira_tickets (
+                tenant TEXT NOT NULL,
+                repo_url TEXT NOT NULL,
+                fingerprint TEXT NOT NULL,
+                issue_key TEXT NOT NULL,
+                issue_url TEXT NOT NULL,
+                created_by TEXT NOT NULL,
+                created_at INTEGER NOT NULL,
+                PRIMARY KEY (tenant, repo_url, fingerprint)
+            );
+
+            -- Audit engagements: a repository and commit range with the runs made for it and the
+            -- report delivered at the end
+            CREATE TABLE IF NOT EXISTS engagements (
+                id TEXT PRIMARY KEY,
+                tenant TEXT NOT NULL,
+                name TEXT NOT NULL,
+                repo_url TEXT NOT NULL,
+                base_commit TEXT,
+                head_commit TEXT,
+                state TEXT NOT NULL,
+                report_log_id TEXT,
+                created_by TEXT NOT NULL,
+                created_at INTEGER NOT NULL,
+                updated_at INTEGER NOT NULL
+            );
+            CREATE INDEX IF NOT EXISTS idx_engagements_tenant ON engagements (tenant, updated_at);
+            CREATE TABLE IF NOT EXISTS engagement_runs (
+                engagement_id TEXT NOT NULL REFERENCES engagements (id),
+                kind TEXT NOT NULL,
+                run_id TEXT NOT NULL,
+                added_by TEXT NOT NULL,
+                added_at INTEGER NOT NULL,
+                -- A fuzz job's results, kept here since finished jobs expire from the queue
+                result TEXT,
+                PRIMARY KEY (engagement_id, run_id)
+            );
+
+            CREATE TABLE IF NOT EXISTS report_templates (
+                tenant TEXT PRIMARY KEY,
+                template TEXT NOT NULL,
+                updated_by TEXT NOT NULL,
+                updated_at INTEGER NOT NULL
+            );
+            -- The audit trail is append-only
+            CREATE TRIGGER IF NOT EXISTS audit_log_no_update BEFORE UPDATE ON audit_log
+            BEGIN SELECT RAISE(ABORT, 'audit_log is append-only'); END;
+            CREATE TRIGGER IF NOT EXISTS audit_log_no_delete BEFORE DELETE ON audit_log
+            BEGIN SELECT RAISE(ABORT, 'audit_log is append-only'); END;",
+        )?;
+        Self::add_column_if_missing(conn, "findings", "triage_state", "TEXT")?;
+        Self::add_column_if_missing(conn, "findings", "rule_id", "TEXT")?;
+        Self::add_column_if_missing(conn, "findings", "autofix", "TEXT")?;
+        Self::add_column_if_missing(conn, "indexed_reports", "discrepancy", "TEXT")?;
+        Self::add_column_if_missing(conn, "indexed_reports", "discrepancy_detail", "TEXT")?;
+        Self::add_column_if_missing(conn, "indexed_reports", "discrepancy_at", "INTEGER")?;
+        Self::add_column_if_missing(conn, "report_logs", "anchor_backend", "TEXT NOT NULL DEFAULT 'solana'")?;
+        Self::add_column_if_missing(conn, "report_logs", "receipt", "TEXT")?;
+        // running while findings are still being added, then completed or failed
+        Self::add_column_if_missing(conn, "analysis_runs", "status", "TEXT NOT NULL DEFAULT 'completed'")?;
+        // Runs from before tenants were tracked belong to the default tenant
+        Self::add_column_if_missing(conn, "analysis_runs", "tenant", "TEXT NOT NULL DEFAULT 'default'")?;
+        // The queued job a run was made for, so a retry of the job can fail the attempt it replaces
+        Self::add_column_if_missing(conn, "analysis_runs", "job_id", "TEXT")?;
+        Self::add_column_if_missing(conn, "engagement_runs", "result", "TEXT")?;
+        // Triage decisions, per tenant, repository and fingerprint
+        Self::create_tenant_keyed_table(
+            conn,
+            "finding_triage",
+            "CREATE TABLE finding_triage (
+                tenant TEXT NOT NULL,
+                repo_url TEXT NOT NULL,
+                fingerprint TEXT NOT NULL,
+                state TEXT NOT NULL,
+                comment TEXT,
+                updated_at INTEGER NOT NULL,
+                PRIMARY KEY (tenant, repo_url, fingerprint)
+            )",
+            "repo_url, fingerprint, state, comment, updated_at",
+        )?;
+        // GitHub issues opened for findings, one per tenant, repository and fingerprint. The
+        // issue is NULL while it's being opened, so a second request can't open another.
+        Self::create_tenant_keyed_table(
+            conn,
+            "finding_issues",
+            "CREATE TABLE finding_issues (
+                tenant TEXT NOT NULL,
+                repo_url TEXT NOT NULL,
+                fingerprint TEXT NOT NULL,
+                issue_number INTEGER,
+                issue_url TEXT,
+                created_by TEXT NOT NULL,
+                created_at INTEGER NOT NULL,
+                PRIMARY KEY (tenant, repo_url, fingerprint)
+            )",
+            "repo_url, fingerprint, issue_number, issue_url, created_by, created_at",
+        )?;
+        Ok(())
+    }
+
+    // Create `table` with `create`, or rebuild a copy from before tenants were tracked, whose
+    // rows belong to the default tenant. SQLite can't change a primary key in place.
+    fn create_tenant_keyed_table(conn: &Connection, table: &str, create: &str, columns: &str) -> Result<()> {
+        let mut stmt = conn.prepare(&format!("PRAGMA table_info({})", table))?;
+        let existing = stmt.query_map([], |row| row.get::<_, String>(1))?
+            .collect::<rusqlite::Result<Vec<_>>>()?;
+        if existing.is_empty() {
+            conn.execute_batch(create)?;
+        } else if !existing.iter().any(|c| c == "tenant") {
+            conn.execute_batch(&format!(
+                "BEGIN;
+                 ALTER TABLE {table} RENAME TO {table}_untenanted;
+                 {create};
+                 INSERT INTO {table} (tenant, {columns}) SELECT 'default', {columns} FROM {table}_untenanted;
+                 DROP TABLE {table}_untenanted;
+                 COMMIT;",
+            ))?;
+        }
+        Ok(())
+    }
+
+    // CREATE TABLE IF NOT EXISTS won't touch existing tables, so new columns are added here
+    fn add_column_if_missing(conn: &Connection, table: &str, column: &str, definition: &str) -> Result<()> {
+        let mut stmt = conn.prepare(&format!("PRAGMA table_info({})", table))?;
+        let columns = stmt.query_map([], |row| row.get::<_, String>(1))?
+            .collect::<rusqlite::Result<Vec<_>>>()?;
+        if !columns.iter().any(|c| c == column) {
+            conn.execute_batch(&format!("ALTER TABLE {} ADD COLUMN {} {}", table, column, definition))?;
+        }
+        Ok(())
+    }
+
+    fn conn(&self) -> Result<std::sync::MutexGuard<'_, Connection>> {
+        self.conn.lock().map_err(|_| anyhow!("Database connection lock poisoned"))
+    }
+
+    // A run whose findings are added as the analysis finds them, so they can be listed and
+    // triaged before it's done. Only completed runs count towards trends.
+    // A run of a queued job fails whatever earlier attempt of the job was left running by a
+    // worker that died
+    pub fn start_analysis_run(&self, tenant: &str, repo_url: &str, commit_sha: Option<&str>, job_id: Option<&str>) -> Result<String> {
+        let run_id = uuid::Uuid::new_v4().to_string();
+        let mut conn = self.conn()?;
+        let tx = conn.transaction()?;
+        if let Some(job_id) = job_id {
+            let abandoned = tx.execute("UPDATE analysis_runs SET status = 'failed' WHERE job_id = ?1 AND status = 'running'", params![job_id])?;
+            if abandoned > 0 {
+                println!("Marked {} abandoned run(s) of job {} as failed", abandoned, job_id);
+            }
+        }
+        tx.execute(
+            "INSERT INTO analysis_runs (id, tenant, repo_url, commit_sha, created_at, status, job_id) VALUES (?1, ?2, ?3, ?4, ?5, 'running', ?6)",
+            params![run_id, tenant, repo_url, commit_sha, now_unix(), job_id],
+        )?;
+        tx.commit()?;
+        Ok(run_id)
+    }
+
+    pub fn append_findings(&self, run_id: &str, bugs: &[CodeBug]) -> Result<()> {
+        let mut conn = self.conn()?;
+        let tx = conn.transaction()?;
+        Self::insert_findings(&tx, run_id, bugs)?;
+        tx.commit()?;
+        Ok(())
+    }
+
+    // Replace what was added along the way with the run's final findings, in their final order
+    pub fn finish_analysis_run(&self, run_id: &str, bugs: &[CodeBug]) -> Result<()> {
+        let mut conn = self.conn()?;
+        let tx = conn.transaction()?;
+        tx.execute("DELETE FROM findings WHERE run_id = ?1", params![run_id])?;
+        Self::insert_findings(&tx, run_id, bugs)?;
+        tx.execute("UPDATE analysis_runs SET status = 'completed' WHERE id = ?1", params![run_id])?;
+        tx.commit()?;
+        Ok(())
+    }
+
+    // The findings added before the failure stay readable
+    pub fn fail_analysis_run(&self, run_id: &str) -> Result<()> {
+        let conn = self.conn()?;
+        conn.execute("UPDATE analysis_runs SET status = 'failed' WHERE id = ?1", params![run_id])?;
+        Ok(())
+    }
+
+    fn insert_findings(conn: &Connection, run_id: &str, bugs: &[CodeBug]) -> Result<()> {
+        let mut stmt = conn.prepare(
+            "INSERT INTO findings (run_id, fingerprint, severity, file, line, bug, fix, triage_state, rule_id, autofix)
+             VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10)",
+        )?;
+        for bug in bugs {
+            let triage_state = bug.triage_state.map(|state| state.as_str());
+            let autofix = bug.autofix.as_ref().map(serde_json::to_string).transpose()?;
+            stmt.execute(params![run_id, bug.fingerprint, bug.severity.as_str(), bug.file, bug.line, bug.bug, bug.fix, triage_state, bug.rule_id, autofix])?;
+        }
+        Ok(())
+    }
+
+    // A run belonging to another tenant is treated as missing
+    pub fn get_analysis_run(&self, tenant: &str, run_id: &str) -> Result<Option<AnalysisRun>> {
+        let conn = self.conn()?;
+        let run = conn.query_row(
+            "SELECT repo_url, commit_sha FROM analysis_runs WHERE id = ?1 AND tenant = ?2",
+            params![run_id, tenant],
+            |row| Ok(AnalysisRun {
+                repo_url: row.get(0)?,
+                commit_sha: row.get(1)?,
+            }),
+        ).optional()?;
+        Ok(run)
+    }
+
+    pub fn get_run_findings(&self, run_id: &str) -> Result<Vec<CodeBug>> {
+        let conn = self.conn()?;
+        let mut stmt = conn.prepare(&format!("SELECT {} FROM findings WHERE run_id = ?1 ORDER BY rowid", FINDING_COLUMNS))?;
+        let rows = stmt.query_map(params![run_id], finding_from_row)?;
+        Ok(rows.collect::<rusqlite::Result<Vec<_>>>()?)
+    }
+
+    // One page of a run's findings with the total matching the filters. Pages continue from
+    // `cursor` (keyset, stable while runs are appended) or else by page number; one extra row is
+    // read to tell whether another page follows, in which case its cursor is returned.
+    pub fn list_run_findings(&self, run_id: &str, query: &FindingsQuery, cursor: Option<&FindingsCursor>) -> Result<(Vec<CodeBug>, u64, Option<FindingsCursor>)> {
+        const FILTERS: &str = "WHERE run_id = ?1
+               AND (?2 IS NULL OR severity = ?2)
+               AND (?3 IS NULL OR file = ?3 OR substr(file, 1, length(?3) + 1) = ?3 || '/')
+               AND (?4 IS NULL OR rule_id = ?4)";
+        const SEVERITY_RANK: &str = "CASE severity WHEN 'critical' THEN 3 WHEN 'high' THEN 2 WHEN 'medium' THEN 1 ELSE 0 END";
+        let file = query.file.as_deref().map(|file| file.trim().trim_end_matches('/')).filter(|file| !file.is_empty());
+        let filters = vec![
+            Value::from(run_id.to_string()),
+            query.severity.map(|severity| severity.as_str().to_string()).into(),
+            file.map(str::to_string).into(),
+            query.rule_id.clone().into(),
+        ];
+        
+        // Sort keys come first; rowid breaks ties in the analyzer's order
+        let keys: &[&str] = match query.sort {
+            FindingSort::Position => &[],
+            FindingSort::Severity => &[SEVERITY_RANK],
+            FindingSort::File => &["COALESCE(file, '')", "line"],
+        };
+        let order = query.order();
+        let (direction, after) = match order {
+            SortOrder::Asc => ("ASC", ">"),
+            SortOrder::Desc => ("DESC", "<"),
+        };
+        let (rowid_direction, rowid_after) = if keys.is_empty() { (direction, after) } else { ("ASC", ">") };
+        let order_by: Vec<String> = keys.iter().map(|key| format!("{} {}", key, direction))
+            .chain(std::iter::once(format!("rowid {}", rowid_direction)))
+            .collect();
+        
+        let conn = self.conn()?;
+        let total: i64 = conn.query_row(
+            &format!("SELECT COUNT(*) FROM findings {}", FILTERS),
+            params_from_iter(filters.iter()),
+            |row| row.get(0),
+        )?;
+        
+        let per_page = query.per_page();
+        let mut values = filters;
+        let mut keyset = String::new();
+        let offset = match cursor {
+            Some(cursor) => {
+                if cursor.sort != query.sort || cursor.order != order || cursor.keys.len() != keys.len() {
+                    return Err(anyhow!("Cursor was issued for a different sort"));
+                }
+                // Rows strictly after the cursor in sort order
+                let mut alternatives = Vec::new();
+                for i in 0..=keys.len() {
+                    let mut terms: Vec<String> = (0..i).map(|j| format!("{} = ?{}", keys[j], values.len() + j + 1)).collect();
+                    terms.push(match keys.get(i) {
+                        Some(key) => format!("{} {} ?{}", key, after, values.len() + i + 1),
+                        None => format!("rowid {} ?{}", rowid_after, values.len() + i + 1),
+                    });
+                    alternatives.push(format!("({})", terms.join(" AND ")));
+                }
+                keyset = format!("AND ({})", alternatives.join(" OR "));
+                for key in &cursor.keys {
+                    values.push(match key {
+                        serde_json::Value::Number(n) => Value::from(n.as_i64().ok_or_else(|| anyhow!("Invalid cursor"))?),
+                        serde_json::Value::String(s) => Value::from(s.clone()),
+                        _ => return Err(anyhow!("Invalid cursor")),
+                    });
+                }
+                values.push(Value::from(cursor.rowid));
+                0
+            },
+            None => (query.page() - 1) as i64 * per_page as i64,
+        };
+        let limit_param = values.len() + 1;
+        values.push(Value::from(per_page as i64 + 1));
+        values.push(Value::from(offset));
+        
+        let mut stmt = conn.prepare(&format!(
+            "SELECT {}, {}, COALESCE(file, ''), line, rowid FROM findings {} {}
+             ORDER BY {}
+             LIMIT ?{} OFFSET ?{}",
+            FINDING_COLUMNS, SEVERITY_RANK, FILTERS, keyset, order_by.join(", "), limit_param, limit_param + 1,
+        ))?;
+        let rows = stmt.query_map(params_from_iter(values.iter()), |row| {
+            Ok((finding_from_row(row)?, row.get::<_, i64>(10)?, row.get::<_, String>(11)?, row.get::<_, i64>(12)?, row.get::<_, i64>(13)?))
+        })?;
+        let mut rows = rows.collect::<rusqlite::Result<Vec<_>>>()?;
+        
+        let next_cursor = if rows.len() > per_page as usize {
+            rows.truncate(per_page as usize);
+            rows.last().map(|(_, rank, file, line, rowid)| FindingsCursor {
+                sort: query.sort,
+                order,
+                keys: match query.sort {
+                    FindingSort::Position => vec![],
+                    FindingSort::Severity => vec![(*rank).into()],
+                    FindingSort::File => vec![file.clone().into(), (*line).into()],
+                },
+                rowid: *rowid,
+            })
+        } else {
+            None
+        };
+        Ok((rows.into_iter().map(|(finding, ..)| finding).collect(), total as u64, next_cursor))
+    }
+
+    pub fn insert_engagement(&self, tenant: &str, request: &EngagementRequest, created_by: &str) -> Result<Engagement> {
+        let now = now_unix();
+        let engagement = Engagement {
+            id: uuid::Uuid::new_v4().to_string(),
+            name: request.name.trim().to_string(),
+            repo_url: request.repo_url.canonical(),
+            base_commit: request.base_commit.as_deref().map(str::to_lowercase),
+            head_commit: request.head_commit.as_deref().map(str::to_lowercase),
+            state: EngagementState::Scoping,
+            report_log_id: None,
+            created_by: created_by.to_string(),
+            created_at: now,
+            updated_at: now,
+            runs: None,
+            triage: None,
+            report: None,
+        };
+        let conn = self.conn()?;
+        conn.execute(
+            &format!("INSERT INTO engagements (tenant, {}) VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11)", ENGAGEMENT_COLUMNS),
+            params![
+                tenant,
+                engagement.id,
+                engagement.name,
+                engagement.repo_url,
+                engagement.base_commit,
+                engagement.head_commit,
+                engagement.state.as_str(),
+                engagement.report_log_id,
+                engagement.created_by,
+                engagement.created_at,
+                engagement.updated_at,
+            ],
+        )?;
+        Ok(engagement)
+    }
+
+    pub fn get_engagement(&self, tenant: &str, id: &str) -> Result<Option<Engagement>> {
+        let conn = self.conn()?;
+        let engagement = conn.query_row(
+            &format!("SELECT {} FROM engagements WHERE tenant = ?1 AND id = ?2", ENGAGEMENT_COLUMNS),
+            params![tenant, id],
+            engagement_from_row,
+        ).optional()?;
+        Ok(engagement)
+    }
+
+    // The tenant's engagements, most recently updated first
+    pub fn list_engagements(&self, tenant: &str, state: Option<EngagementState>, repo_url: Option<&str>) -> Result<Vec<Engagement>> {
+        let conn = self.conn()?;
+        let mut stmt = conn.prepare(&format!(
+            "SELECT {} FROM engagements
+             WHERE tenant = ?1 AND (?2 IS NULL OR state = ?2) AND (?3 IS NULL OR repo_url = ?3)
+             ORDER BY updated_at DESC, created_at DESC",
+            ENGAGEMENT_COLUMNS,
+        ))?;
+        let rows = stmt.query_map(params![tenant, state.map(|state| state.as_str()), repo_url], engagement_from_row)?;
+        Ok(rows.collect::<rusqlite::Result<Vec<_>>>()?)
+    }
+
+    // Store the engagement's name, commit range, state and report, touching updated_at
+    pub fn update_engagement(&self, tenant: &str, engagement: &mut Engagement) -> Result<()> {
+        engagement.updated_at = now_unix();
+        let conn = self.conn()?;
+        conn.execute(
+            "UPDATE engagements SET name = ?3, base_commit = ?4, head_commit = ?5, state = ?6, report_log_id = ?7, updated_at = ?8
+             WHERE tenant = ?1 AND id = ?2",
+            params![
+                tenant,
+                engagement.id,
+                engagement.name,
+                engagement.base_commit,
+                engagement.head_commit,
+                engagement.state.as_str(),
+                engagement.report_log_id,
+                engagement.updated_at,
+            ],
+        )?;
+        Ok(())
+    }
+
+    // Whether the engagement existed. Its runs, triage and report stay; only the grouping goes.
+    pub fn delete_engagement(&self, tenant: &str, id: &str) -> Result<bool> {
+        let mut conn = self.conn()?;
+        let tx = conn.transaction()?;
+        tx.execute(
+            "DELETE FROM engagement_runs WHERE engagement_id = (SELECT id FROM engagements WHERE tenant = ?1 AND id = ?2)",
+            params![tenant, id],
+        )?;
+        let deleted = tx.execute("DELETE FROM engagements WHERE tenant = ?1 AND id = ?2", params![tenant, id])?;
+        tx.commit()?;
+        Ok(deleted > 0)
+    }
+
+    // False when the run already belongs to the engagement
+    pub fn add_engagement_run(&self, engagement_id: &str, kind: EngagementRunKind, run_id: &str, result: Option<&serde_json::Value>, added_by: &str) -> Result<bool> {
+        let now = now_unix();
+        let conn = self.conn()?;
+        let added = conn.execute(
+            "INSERT OR IGNORE INTO engagement_runs (engagement_id, kind, run_id, added_by, added_at, result) VALUES (?1, ?2, ?3, ?4, ?5, ?6)",
+            params![engagement_id, kind.as_str(), run_id, added_by, now, result.map(|result| result.to_string())],
+        )?;
+        if added > 0 {
+            conn.execute("UPDATE engagements SET updated_at = ?2 WHERE id = ?1", params![engagement_id, now])?;
+        }
+        Ok(added > 0)
+    }
+
+    pub fn remove_engagement_run(&self, engagement_id: &str, run_id: &str) -> Result<bool> {
+        let conn = self.conn()?;
+        let removed = conn.execute(
+            "DELETE FROM engagement_runs WHERE engagement_id = ?1 AND run_id = ?2",
+            params![engagement_id, run_id],
+        )?;
+        if removed > 0 {
+            conn.execute("UPDATE engagements SET updated_at = ?2 WHERE id = ?1", params![engagement_id, now_unix()])?;
+        }
+        Ok(removed > 0)
+    }
+
+    // The engagement's runs in the order they were added, with the commit each analysis ran on
+    pub fn list_engagement_runs(&self, engagement_id: &str) -> Result<Vec<EngagementRun>> {
+        let conn = self.conn()?;
+        let mut stmt = conn.prepare(
+            "SELECT e.kind, e.run_id, r.commit_sha, e.added_by, e.added_at, e.result
+             FROM engagement_runs e LEFT JOIN analysis_runs r ON e.kind = 'analysis' AND r.id = e.run_id
+             WHERE e.engagement_id = ?1 ORDER BY e.added_at, e.rowid",
+        )?;
+        let rows = stmt.query_map(params![engagement_id], |row| {
+            let kind: String = row.get(0)?;
+            Ok(EngagementRun {
+                kind: EngagementRunKind::parse(&kind).unwrap_or(EngagementRunKind::Analysis),
+                run_id: row.get(1)?,
+                commit_sha: row.get(2)?,
+                added_by: row.get(3)?,
+                added_at: row.get(4)?,
+                result: row.get::<_, Option<String>>(5)?.and_then(|result| serde_json::from_str(&result).ok()),
+            })
+        })?;
+        Ok(rows.collect::<rusqlite::Result<Vec<_>>>()?)
+    }
+
+    // Triage decisions on findings of the engagement's analysis runs
+    pub fn list_engagement_triage(&self, tenant: &str, engagement_id: &str, repo_url: &str) -> Result<Vec<FindingTriage>> {
+        let conn = self.conn()?;
+        let mut stmt = conn.prepare(
+            "SELECT repo_url, fingerprint, state, comment, updated_at FROM finding_triage
+             WHERE tenant = ?3 AND repo_url = ?2 AND fingerprint IN (
+                SELECT f.fingerprint FROM findings f JOIN engagement_runs e ON e.run_id = f.run_id
+                WHERE e.engagement_id = ?1 AND e.kind = 'analysis')
+             ORDER BY updated_at DESC",
+        )?;
+        let rows = stmt.query_map(params![engagement_id, repo_url, tenant], |row| {
+            let state: String = row.get(2)?;
+            Ok(FindingTriage {
+                repo_url: row.get(0)?,
+                fingerprint: row.get(1)?,
+                state: TriageState::parse(&state).unwrap_or(TriageState::Open),
+                comment: row.get(3)?,
+                updated_at: row.get(4)?,
+            })
+        })?;
+        Ok(rows.collect::<rusqlite::Result<Vec<_>>>()?)
+    }
+
+    // Set (or replace) the triage decision for a finding fingerprint in a repository
+    pub fn set_triage(&self, tenant: &str, repo_url: &str, fingerprint: &str, state: TriageState, comment: Option<&str>) -> Result<FindingTriage> {
+        let updated_at = now_unix();
+        let conn = self.conn()?;
+        conn.execute(
+            "INSERT INTO finding_triage (tenant, repo_url, fingerprint, state, comment, updated_at)
+             VALUES (?1, ?2, ?3, ?4, ?5, ?6)
+             ON CONFLICT (tenant, repo_url, fingerprint)
+             DO UPDATE SET state = excluded.state, comment = excluded.comment, updated_at = excluded.updated_at",
+            params![tenant, repo_url, fingerprint, state.as_str(), comment, updated_at],
+        )?;
+        Ok(FindingTriage {
+            repo_url: repo_url.to_string(),
+            fingerprint: fingerprint.to_string(),
+            state,
+            comment: comment.map(str::to_string),
+            updated_at,
+        })
+    }
+
+    pub fn list_triage(&self, tenant: &str, repo_url: &str) -> Result<Vec<FindingTriage>> {
+        let conn = self.conn()?;
+        let mut stmt = conn.prepare(
+            "SELECT repo_url, fingerprint, state, comment, updated_at FROM finding_triage
+             WHERE tenant = ?1 AND repo_url = ?2 ORDER BY updated_at DESC",
+        )?;
+        let rows = stmt.query_map(params![tenant, repo_url], |row| {
+            let state: String = row.get(2)?;
+            Ok(FindingTriage {
+                repo_url: row.get(0)?,
+                fingerprint: row.get(1)?,
+                state: TriageState::parse(&state).unwrap_or(TriageState::Open),
+                comment: row.get(3)?,
+                updated_at: row.get(4)?,
+            })
+        })?;
+        Ok(rows.collect::<rusqlite::Result<Vec<_>>>()?)
+    }
+
+    // Claim the finding's issue before opening it; false when it already has one or another
+    // request is opening it
+    pub fn reserve_finding_issue(&self, tenant: &str, repo_url: &str, fingerprint: &str, created_by: &str) -> Result<bool> {
+        let conn = self.conn()?;
+        let inserted = conn.execute(
+            "INSERT OR IGNORE INTO finding_issues (tenant, repo_url, fingerprint, created_by, created_at)
+             VALUES (?1, ?2, ?3, ?4, ?5)",
+            params![tenant, repo_url, fingerprint, created_by, now_unix()],
+        )?;
+        Ok(inserted > 0)
+    }
+
+    pub fn complete_finding_issue(&self, tenant: &str, repo_url: &str, fingerprint: &str, issue_number: u64, issue_url: &str) -> Result<()> {
+        let conn = self.conn()?;
+        conn.execute(
+            "UPDATE finding_issues SET issue_number = ?4, issue_url = ?5
+             WHERE tenant = ?1 AND repo_url = ?2 AND fingerprint = ?3",
+            params![tenant, repo_url, fingerprint, issue_number as i64, issue_url],
+        )?;
+        Ok(())
+    }
+
+    // Give up a reservation whose issue couldn't be opened
+    pub fn release_finding_issue(&self, tenant: &str, repo_url: &str, fingerprint: &str) -> Result<()> {
+        let conn = self.conn()?;
+        conn.execute(
+            "DELETE FROM finding_issues WHERE tenant = ?1 AND repo_url = ?2 AND fingerprint = ?3 AND issue_url IS NULL",
+            params![tenant, repo_url, fingerprint],
+        )?;
+        Ok(())
+    }
+
+    // (fingerprint, issue URL) for every finding of the repository with an issue
+    pub fn list_finding_issues(&self, tenant: &str, repo_url: &str) -> Result<Vec<(String, String)>> {
+        let conn = self.conn()?;
+        let mut stmt = conn.prepare(
+            "SELECT fingerprint, issue_url FROM finding_issues WHERE tenant = ?1 AND repo_url = ?2 AND issue_url IS NOT NULL",
+        )?;
+        let rows = stmt.query_map(params![tenant, repo_url], |row| Ok((row.get(0)?, row.get(1)?)))?;
+        Ok(rows.collect::<rusqlite::Result<Vec<_>>>()?)
+    }
+
+    // Assign a finding to `assignee`, replacing any earlier assignment
+    pub fn assign_finding(&self, tenant: &str, repo_url: &str, fingerprint: &str, assignee: &str, assigned_by: &str) -> Result<FindingAssignment> {
+        let assigned_at = now_unix();
+        let conn = self.conn()?;
+        conn.execute(
+            "INSERT INTO finding_assignments (tenant, repo_url, fingerprint, assignee, assigned_by, assigned_at)
+             VALUES (?1, ?2, ?3, ?4, ?5, ?6)
+             ON CONFLICT (tenant, repo_url, fingerprint)
+             DO UPDATE SET assignee = excluded.assignee, assigned_by = excluded.assigned_by, assigned_at = excluded.assigned_at",
+            params![tenant, repo_url, fingerprint, assignee, assigned_by, assigned_at],
+        )?;
+        Ok(FindingAssignment {
+            repo_url: repo_url.to_string(),
+            fingerprint: fingerprint.to_string(),
+            assignee: assignee.to_string(),
+            assigned_by: assigned_by.to_string(),
+            assigned_at,
+            finding: None,
+        })
+    }
+
+    // Whether the finding was assigned
+    pub fn unassign_finding(&self, tenant: &str, repo_url: &str, fingerprint: &str) -> Result<bool> {
+        let conn = self.conn()?;
+        let deleted = conn.execute(
+            "DELETE FROM finding_assignments WHERE tenant = ?1 AND repo_url = ?2 AND fingerprint = ?3",
+            params![tenant, repo_url, fingerprint],
+        )?;
+        Ok(deleted > 0)
+    }
+
+    // (fingerprint, assignee) for every assigned finding of the repository
+    pub fn list_finding_assignees(&self, tenant: &str, repo_url: &str) -> Result<Vec<(String, String)>> {
+        let conn = self.conn()?;
+        let mut stmt = conn.prepare("SELECT fingerprint, assignee FROM finding_assignments WHERE tenant = ?1 AND repo_url = ?2")?;
+        let rows = stmt.query_map(params![tenant, repo_url], |row| Ok((row.get(0)?, row.get(1)?)))?;
+        Ok(rows.collect::<rusqlite::Result<Vec<_>>>()?)
+    }
+
+    // The findings assigned to `assignee`, newest assignment first, each as the latest completed
+    // run of its repository has it
+    pub fn assigned_findings(&self, tenant: &str, assignee: &str, repo_url: Option<&str>) -> Result<Vec<FindingAssignment>> {
+        let conn = self.conn()?;
+        let mut stmt = conn.prepare(
+            "SELECT repo_url, fingerprint, assignee, assigned_by, assigned_at,
+                (SELECT id FROM analysis_runs r WHERE r.tenant = a.tenant AND r.repo_url = a.repo_url AND r.status = 'completed'
+                    ORDER BY r.created_at DESC, r.rowid DESC LIMIT 1)
+             FROM finding_assignments a
+             WHERE tenant = ?1 AND assignee = ?2 AND (?3 IS NULL OR repo_url = ?3)
+             ORDER BY assigned_at DESC",
+        )?;
+        let rows = stmt.query_map(params![tenant, assignee, repo_url], |row| Ok((
+            FindingAssignment {
+                repo_url: row.get(0)?,
+                fingerprint: row.get(1)?,
+                assignee: row.get(2)?,
+                assigned_by: row.get(3)?,
+                assigned_at: row.get(4)?,
+                finding: None,
+            },
+            row.get::<_, Option<String>>(5)?,
+        )))?.collect::<rusqlite::Result<Vec<_>>>()?;
+
+        let mut finding = conn.prepare(&format!("SELECT {} FROM findings WHERE run_id = ?1 AND fingerprint = ?2", FINDING_COLUMNS))?;
+        let mut assignments = Vec::with_capacity(rows.len());
+        for (mut assignment, run_id) in rows {
+            if let Some(run_id) = run_id {
+                assignment.finding = finding.query_row(params![run_id, assignment.fingerprint], finding_from_row).optional()?
+                    .map(|bug| CodeBug { assignee: Some(assignment.assignee.clone()), ..bug });
+            }
+            assignments.push(assignment);
+        }
+        Ok(assignments)
+    }
+
+    // A new comment reopens a resolved thread
+    pub fn add_finding_comment(&self, tenant: &str, repo_url: &str, fingerprint: &str, author: &str, body: &str) -> Result<FindingComment> {
+        let comment = FindingComment {
+            id: uuid::Uuid::new_v4().to_string(),
+            author: author.to_string(),
+            body: body.to_string(),
+            created_at: now_unix(),
+        };
+        let mut conn = self.conn()?;
+        let tx = conn.transaction()?;
+        tx.execute(
+            "INSERT INTO finding_comments (id, tenant, repo_url, fingerprint, author, body, created_at)
+             VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7)",
+            params![comment.id, tenant, repo_url, fingerprint, comment.author, comment.body, comment.created_at],
+        )?;
+        tx.execute(
+            "DELETE FROM resolved_threads WHERE tenant = ?1 AND repo_url = ?2 AND fingerprint = ?3",
+            params![tenant, repo_url, fingerprint],
+        )?;
+        tx.commit()?;
+        Ok(comment)
+    }
+
+    // Resolve the thread as `resolved_by`, or reopen it with None. False when the finding has
+    // no comments to resolve.
+    pub fn set_thread_resolved(&self, tenant: &str, repo_url: &str, fingerprint: &str, resolved_by: Option<&str>) -> Result<bool> {
+        let conn = self.conn()?;
+        let exists: bool = conn.query_row(
+            "SELECT EXISTS (SELECT 1 FROM finding_comments WHERE tenant = ?1 AND repo_url = ?2 AND fingerprint = ?3)",
+            params![tenant, repo_url, fingerprint],
+            |row| row.get(0),
+        )?;
+        if !exists {
+            return Ok(false);
+        }
+        match resolved_by {
+            Some(resolved_by) => conn.execute(
+                "INSERT OR REPLACE INTO resolved_threads (tenant, repo_url, fingerprint, resolved_by, resolved_at)
+                 VALUES (?1, ?2, ?3, ?4, ?5)",
+                params![tenant, repo_url, fingerprint, resolved_by, now_unix()],
+            )?,
+            None => conn.execute(
+                "DELETE FROM resolved_threads WHERE tenant = ?1 AND repo_url = ?2 AND fingerprint = ?3",
+                params![tenant, repo_url, fingerprint],
+            )?,
+        };
+        Ok(true)
+    }
+
+    // The tenant's threads on a repository's findings (or on one finding), oldest comment first
+    pub fn list_finding_threads(&self, tenant: &str, repo_url: &str, fingerprint: Option<&str>) -> Result<Vec<FindingThread>> {
+        let conn = self.conn()?;
+        let mut stmt = conn.prepare(
+            "SELECT c.fingerprint, c.id, c.author, c.body, c.created_at, r.resolved_by, r.resolved_at
+             FROM finding_comments c
+             LEFT JOIN resolved_threads r ON r.tenant = c.tenant AND r.repo_url = c.repo_url AND r.fingerprint = c.fingerprint
+             WHERE c.tenant = ?1 AND c.repo_url = ?2 AND (?3 IS NULL OR c.fingerprint = ?3)
+             ORDER BY c.fingerprint, c.created_at, c.rowid",
+        )?;
+        let rows = stmt.query_map(params![tenant, repo_url, fingerprint], |row| Ok((
+            row.get::<_, String>(0)?,
+            FindingComment {
+                id: row.get(1)?,
+                author: row.get(2)?,
+                body: row.get(3)?,
+                created_at: row.get(4)?,
+            },
+            row.get::<_, Option<String>>(5)?,
+            row.get::<_, Option<i64>>(6)?,
+        )))?;
+
+        let mut threads: Vec<FindingThread> = Vec::new();
+        for row in rows {
+            let (fingerprint, comment, resolved_by, resolved_at) = row?;
+            match threads.last_mut().filter(|thread| thread.fingerprint == fingerprint) {
+                Some(thread) => thread.comments.push(comment),
+                None => threads.push(FindingThread {
+                    repo_url: repo_url.to_string(),
+                    fingerprint,
+                    comments: vec![comment],
+                    resolved: resolved_at.is_some(),
+                    resolved_by,
+                    resolved_at,
+                }),
+            }
+        }
+        Ok(threads)
+    }
+
+    // Registering again replaces the repository's key
+    pub fn put_deploy_key(&self, tenant: &str, repo_url: &str, key: &SshKeyPair, sealed_private_key: &str, created_by: &str) -> Result<DeployKey> {
+        let created_at = now_unix();
+        let conn = self.conn()?;
+        conn.execute(
+            "INSERT OR REPLACE INTO deploy_keys (tenant, repo_url, public_key, fingerprint, sealed_private_key, created_by, created_at)
+             VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7)",
+            params![tenant, repo_url, key.public_key, key.fingerprint, sealed_private_key, created_by, created_at],
+        )?;
+        Ok(DeployKey {
+            repo_url: repo_url.to_string(),
+            public_key: key.public_key.clone(),
+            fingerprint: key.fingerprint.clone(),
+            created_by: created_by.to_string(),
+            created_at,
+        })
+    }
+
+    pub fn list_deploy_keys(&self, tenant: &str) -> Result<Vec<DeployKey>> {
+        let conn = self.conn()?;
+        let mut stmt = conn.prepare(
+            "SELECT repo_url, public_key, fingerprint, created_by, created_at FROM deploy_keys
+             WHERE tenant = ?1 ORDER BY repo_url",
+        )?;
+        let rows = stmt.query_map(params![tenant], |row| Ok(DeployKey {
+            repo_url: row.get(0)?,
+            public_key: row.get(1)?,
+            fingerprint: row.get(2)?,
+            created_by: row.get(3)?,
+            created_at: row.get(4)?,
+        }))?;
+        Ok(rows.collect::<rusqlite::Result<Vec<_>>>()?)
+    }
+
+    // The public and still-sealed private key used to clone `repo_url` for `tenant`, if any
+    pub fn get_deploy_key_pair(&self, tenant: &str, repo_url: &str) -> Result<Option<(String, String)>> {
+        let conn = self.conn()?;
+        Ok(conn.query_row(
+            "SELECT public_key, sealed_private_key FROM deploy_keys WHERE tenant = ?1 AND repo_url = ?2",
+            params![tenant, repo_url],
+            |row| Ok((row.get(0)?, row.get(1)?)),
+        ).optional()?)
+    }
+
+    pub fn delete_deploy_key(&self, tenant: &str, repo_url: &str) -> Result<bool> {
+        let conn = self.conn()?;
+        Ok(conn.execute("DELETE FROM deploy_keys WHERE tenant = ?1 AND repo_url = ?2", params![tenant, repo_url])? > 0)
+    }
+
+    // Registering is idempotent; false when the project was already registered
+    pub fn register_ecosystem_project(&self, repo_url: &str, registered_by: &str) -> Result<bool> {
+        let conn = self.conn()?;
+        Ok(conn.execute(
+            "INSERT OR IGNORE INTO ecosystem_projects (repo_url, registered_by, registered_at) VALUES (?1, ?2, ?3)",
+            params![repo_url, registered_by, now_unix()],
+        )? > 0)
+    }
+
+    // Only the tenant that registered a project can remove it
+    pub fn delete_ecosystem_project(&self, repo_url: &str, registered_by: &str) -> Result<bool> {
+        let conn = self.conn()?;
+        Ok(conn.execute("DELETE FROM ecosystem_projects WHERE repo_url = ?1 AND registered_by = ?2", params![repo_url, registered_by])? > 0)
+    }
+
+    // Every registered project, or only those `registered_by` registered
+    pub fn list_ecosystem_projects(&self, registered_by: Option<&str>) -> Result<Vec<EcosystemProject>> {
+        let conn = self.conn()?;
+        let mut stmt = conn.prepare(
+            "SELECT repo_url, registered_by, registered_at, scanned_at, score, rule_hits, error
+             FROM ecosystem_projects WHERE ?1 IS NULL OR registered_by = ?1 ORDER BY registered_at, repo_url",
+        )?;
+        let rows = stmt.query_map(params![registered_by], |row| {
+            let rule_hits: Option<String> = row.get(5)?;
+            Ok(EcosystemProject {
+                repo_url: row.get(0)?,
+                registered_by: row.get(1)?,
+                registered_at: row.get(2)?,
+                scanned_at: row.get(3)?,
+                score: row.get(4)?,
+                rule_hits: rule_hits.and_then(|hits| serde_json::from_str(&hits).ok()),
+                error: row.get(6)?,
+            })
+        })?;
+        Ok(rows.collect::<rusqlite::Result<Vec<_>>>()?)
+    }
+
+    pub fn record_ecosystem_scan(&self, repo_url: &str, score: u32, rule_hits: &BTreeMap<String, u32>) -> Result<()> {
+        let conn = self.conn()?;
+        conn.execute(
+            "UPDATE ecosystem_projects SET scanned_at = ?2, score = ?3, rule_hits = ?4, error = NULL WHERE repo_url = ?1",
+            params![repo_url, now_unix(), score, serde_json::to_string(rule_hits)?],
+        )?;
+        Ok(())
+    }
+
+    // A failed scan keeps the previous results in the statistics
+    pub fn record_ecosystem_scan_error(&self, repo_url: &str, error: &str) -> Result<()> {
+        let conn = self.conn()?;
+        conn.execute("UPDATE ecosystem_projects SET error = ?2 WHERE repo_url = ?1", params![repo_url, error])?;
+        Ok(())
+    }
+
+    // Store a finished run, filling in its id and time
+    pub fn insert_benchmark_run(&self, run: &mut BenchmarkRun) -> Result<()> {
+        let conn = self.conn()?;
+        run.run_at = now_unix();
+        conn.execute(
+            "INSERT INTO benchmark_runs (run_at, run_by, corpus, repos, rules) VALUES (?1, ?2, ?3, ?4, ?5)",
+            params![run.run_at, run.run_by, run.corpus, serde_json::to_string(&run.repos)?, serde_json::to_string(&run.rules)?],
+        )?;
+        run.id = conn.last_insert_rowid();
+        Ok(())
+    }
+
+    pub fn record_fuzz_timing(&self, repo_url: &str, build_ms: u64, run_ms: u64, cache_hit: bool) -> Result<()> {
+        let conn = self.conn()?;
+        conn.execute(
+            "INSERT INTO fuzz_timings (repo_url, build_ms, run_ms, cache_hit, recorded_at) VALUES (?1, ?2, ?3, ?4, ?5)",
+            params![repo_url, build_ms as i64, run_ms as i64, cache_hit, now_unix()],
+        )?;
+        Ok(())
+    }
+
+    // The longest of the repository's last few harness builds, in milliseconds; runs that reused
+    // a cached harness didn't build one
+    pub fn fuzz_build_estimate(&self, repo_url: &str) -> Result<Option<u64>> {
+        let conn = self.conn()?;
+        let build_ms: Option<i64> = conn.query_row(
+            "SELECT MAX(build_ms) FROM (SELECT build_ms FROM fuzz_timings WHERE repo_url = ?1 AND cache_hit = 0 ORDER BY id DESC LIMIT 5)",
+            params![repo_url],
+            |row| row.get(0),
+        )?;
+        Ok(build_ms.map(|ms| ms as u64))
+    }
+
+    // False when the origin was already added
+    pub fn insert_cors_origin(&self, origin: &str, added_by: &str) -> Result<bool> {
+        let conn = self.conn()?;
+        Ok(conn.execute(
+            "INSERT OR IGNORE INTO cors_origins (origin, added_by, added_at) VALUES (?1, ?2, ?3)",
+            params![origin, added_by, now_unix()],
+        )? > 0)
+    }
+
+    pub fn delete_cors_origin(&self, origin: &str) -> Result<bool> {
+        let conn = self.conn()?;
+        Ok(conn.execute("DELETE FROM cors_origins WHERE origin = ?1", params![origin])? > 0)
+    }
+
+    pub fn list_cors_origins(&self) -> Result<Vec<AllowedOrigin>> {
+        let conn = self.conn()?;
+        let mut stmt = conn.prepare("SELECT origin, added_by, added_at FROM cors_origins ORDER BY origin")?;
+        let rows = stmt.query_map([], |row| Ok(AllowedOrigin {
+            origin: row.get(0)?,
+            configured: false,
+            added_by: Some(row.get(1)?),
+            added_at: Some(row.get(2)?),
+        }))?;
+        Ok(rows.collect::<rusqlite::Result<Vec<_>>>()?)
+    }
+
+    // Most recent first
+    pub fn list_benchmark_runs(&self, limit: u32) -> Result<Vec<BenchmarkRun>> {
+        let conn = self.conn()?;
+        let mut stmt = conn.prepare(
+            "SELECT id, run_at, run_by, corpus, repos, rules FROM benchmark_runs ORDER BY id DESC LIMIT ?1",
+        )?;
+        let rows = stmt.query_map(params![limit], |row| {
+            let repos: String = row.get(4)?;
+            let rules: String = row.get(5)?;
+            Ok(BenchmarkRun {
+                id: row.get(0)?,
+                run_at: row.get(1)?,
+                run_by: row.get(2)?,
+                corpus: row.get(3)?,
+                repos: serde_json::from_str(&repos).unwrap_or_default(),
+                rules: serde_json::from_str(&rules).unwrap_or_default(),
+            })
+        })?;
+        Ok(rows.collect::<rusqlite::Result<Vec<_>>>()?)
+    }
+
+    pub fn get_email_settings(&self, tenant: &str) -> Result<EmailSettings> {
+        let conn = self.conn()?;
+        let templates = conn.query_row(
+            "SELECT subject_template, body_template FROM email_settings WHERE tenant = ?1",
+            params![tenant],
+            |row| Ok((row.get::<_, Option<String>>(0)?, row.get::<_, Option<String>>(1)?)),
+        ).optional()?;
+        let (subject_template, body_template) = templates.unwrap_or((None, None));
+
+        let mut stmt = conn.prepare("SELECT email, active FROM email_recipients WHERE tenant = ?1 ORDER BY email")?;
+        let recipients = stmt.query_map(params![tenant], |row| Ok(EmailRecipient {
+            email: row.get(0)?,
+            active: row.get(1)?,
+        }))?.collect::<rusqlite::Result<Vec<_>>>()?;
+
+        Ok(EmailSettings { recipients, subject_template, body_template })
+    }
+
+    // Templates are replaced when given; a recipient list replaces the current one, but
+    // addresses that unsubscribed stay unsubscribed
+    pub fn update_email_settings(&self, tenant: &str, request: &EmailSettingsRequest) -> Result<()> {
+        let mut conn = self.conn()?;
+        let tx = conn.transaction()?;
+
+        tx.execute(
+            "INSERT INTO email_settings (tenant, subject_template, body_template, updated_at)
+             VALUES (?1, ?2, ?3, ?4)
+             ON CONFLICT (tenant) DO UPDATE SET
+                subject_template = COALESCE(excluded.subject_template, subject_template),
+                body_template = COALESCE(excluded.body_template, body_template),
+                updated_at = excluded.updated_at",
+            params![tenant, request.subject_template, request.body_template, now_unix()],
+        )?;
+
+        if let Some(recipients) = &request.recipients {
+            let emails: Vec<String> = recipients.iter().map(|e| e.trim().to_lowercase()).collect();
+            let existing: Vec<String> = {
+                let mut stmt = tx.prepare("SELECT email FROM email_recipients WHERE tenant = ?1")?;
+                let rows = stmt.query_map(params![tenant], |row| row.get(0))?;
+                rows.collect::<rusqlite::Result<Vec<_>>>()?
+            };
+            for email in existing.iter().filter(|e| !emails.contains(e)) {
+                tx.execute("DELETE FROM email_recipients WHERE tenant = ?1 AND email = ?2", params![tenant, email])?;
+            }
+            for email in &emails {
+                tx.execute(
+                    "INSERT OR IGNORE INTO email_recipients (tenant, email, unsubscribe_token, active) VALUES (?1, ?2, ?3, 1)",
+                    params![tenant, email, uuid::Uuid::new_v4().simple().to_string()],
+                )?;
+            }
+        }
+
+        tx.commit()?;
+        Ok(())
+    }
+
+    // None means the built-in report layout is used
+    pub fn get_report_template(&self, tenant: &str) -> Result<Option<ReportTemplate>> {
+        let conn = self.conn()?;
+        let template = conn.query_row(
+            "SELECT template, updated_by, updated_at FROM report_templates WHERE tenant = ?1",
+            params![tenant],
+            |row| Ok(ReportTemplate {
+                template: row.get(0)?,
+                updated_by: row.get(1)?,
+                updated_at: row.get(2)?,
+            }),
+        ).optional()?;
+        Ok(template)
+    }
+
+    pub fn put_report_template(&self, tenant: &str, template: &str, updated_by: &str) -> Result<()> {
+        let conn = self.conn()?;
+        conn.execute(
+            "INSERT OR REPLACE INTO report_templates (tenant, template, updated_by, updated_at) VALUES (?1, ?2, ?3, ?4)",
+            params![tenant, template, updated_by, now_unix()],
+        )?;
+        Ok(())
+    }
+
+    // Whether the tenant had a template to remove
+    pub fn delete_report_template(&self, tenant: &str) -> Result<bool> {
+        let conn = self.conn()?;
+        let deleted = conn.execute("DELETE FROM report_templates WHERE tenant = ?1", params![tenant])?;
+        Ok(deleted > 0)
+    }
+
+    pub fn put_jira_settings(&self, tenant: &str, settings: &JiraSettings, sealed_token: &str) -> Result<()> {
+        let conn = self.conn()?;
+        conn.execute(
+            "INSERT OR REPLACE INTO jira_settings (tenant, base_url, project_key, email, sealed_token, issue_type, priorities, updated_by, updated_at)
+             VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9)",
+            params![
+                tenant,
+                settings.base_url,
+                settings.project_key,
+                settings.email,
+                sealed_token,
+                settings.issue_type,
+                serde_json::to_string(&settings.priorities)?,
+                settings.updated_by,
+                settings.updated_at,
+            ],
+        )?;
+        Ok(())
+    }
+
+    // The tenant's Jira connection and its still-sealed token
+    pub fn get_jira_settings(&self, tenant: &str) -> Result<Option<(JiraSettings, String)>> {
+        let conn = self.conn()?;
+        let settings = conn.query_row(
+            "SELECT base_url, project_key, email, sealed_token, issue_type, priorities, updated_by, updated_at
+             FROM jira_settings WHERE tenant = ?1",
+            params![tenant],
+            |row| {
+                let priorities: String = row.get(5)?;
+                Ok((JiraSettings {
+                    base_url: row.get(0)?,
+                    project_key: row.get(1)?,
+                    email: row.get(2)?,
+                    issue_type: row.get(4)?,
+                    priorities: serde_json::from_str(&priorities).unwrap_or_default(),
+                    updated_by: row.get(6)?,
+                    updated_at: row.get(7)?,
+                }, row.get(3)?))
+            },
+        ).optional()?;
+        Ok(settings)
+    }
+
+    pub fn delete_jira_settings(&self, tenant: &str) -> Result<bool> {
+        let conn = self.conn()?;
+        Ok(conn.execute("DELETE FROM jira_settings WHERE tenant = ?1", params![tenant])? > 0)
+    }
+
+    pub fn insert_jira_ticket(&self, tenant: &str, ticket: &JiraTicket) -> Result<()> {
+        let conn = self.conn()?;
+        conn.execute(
+            "INSERT INTO jira_tickets (tenant, repo_url, fingerprint, issue_key, issue_url, created_by, created_at)
+             VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7)",
+            params![tenant, ticket.repo_url, ticket.fingerprint, ticket.key, ticket.url, ticket.created_by, ticket.created_at],
+        )?;
+        Ok(())
+    }
+
+    pub fn list_jira_tickets(&self, tenant: &str, repo_url: &str) -> Result<Vec<JiraTicket>> {
+        let conn = self.conn()?;
+        let mut stmt = conn.prepare(
+            "SELECT repo_url, fingerprint, issue_key, issue_url, created_by, created_at FROM jira_tickets
+             WHERE tenant = ?1 AND repo_url = ?2 ORDER BY created_at DESC",
+        )?;
+        let rows = stmt.query_map(params![tenant, repo_url], |row| Ok(JiraTicket {
+            repo_url: row.get(0)?,
+            fingerprint: row.get(1)?,
+            key: row.get(2)?,
+            url: row.get(3)?,
+            created_by: row.get(4)?,
+            created_at: row.get(5)?,
+        }))?;
+        Ok(rows.collect::<rusqlite::Result<Vec<_>>>()?)
+    }
+
+    // (email, unsubscribe token) pairs that should receive reports
+    pub fn active_email_recipients(&self, tenant: &str) -> Result<Vec<(String, String)>> {
+        let conn = self.conn()?;
+        let mut stmt = conn.prepare(
+            "SELECT email, unsubscribe_token FROM email_recipients WHERE tenant = ?1 AND active = 1",
+        )?;
+        let rows = stmt.query_map(params![tenant], |row| Ok((row.get(0)?, row.get(1)?)))?;
+        Ok(rows.collect::<rusqlite::Result<Vec<_>>>()?)
+    }
+
+    // Returns false when the token doesn't match any recipient
+    pub fn unsubscribe_email(&self, token: &str) -> Result<bool> {
+        let conn = self.conn()?;
+        let updated = conn.execute(
+            "UPDATE email_recipients SET active = 0 WHERE unsubscribe_token = ?1",
+            params![token],
+        )?;
+        Ok(updated > 0)
+    }
+
+    pub fn insert_audit_entry(&self, tenant: &str, entry: &AuditEntry) -> Result<()> {
+        let conn = self.conn()?;
+        conn.execute(
+            "INSERT INTO audit_log (tenant, actor, action, target, params, outcome, detail, started_at, finished_at)
+             VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9)",
+            params![
+                tenant,
+                entry.actor,
+                entry.action,
+                entry.target,
+                entry.params.to_string(),
+                entry.outcome,
+                entry.detail,
+                entry.started_at,
+                entry.finished_at,
+            ],
+        )?;
+        Ok(())
+    }
+
+    // Newest first; every filter is optional
+    pub fn list_audit_entries(&self, tenant: &str, query: &AuditLogQuery) -> Result<Vec<AuditEntry>> {
+        let conn = self.conn()?;
+        let mut stmt = conn.prepare(
+            "SELECT id, actor, action, target, params, outcome, detail, started_at, finished_at FROM audit_log
+             WHERE tenant = ?1
+               AND (?2 IS NULL OR action = ?2)
+               AND (?3 IS NULL OR actor = ?3)
+               AND (?4 IS NULL OR outcome = ?4)
+               AND (?5 IS NULL OR target = ?5)
+               AND (?6 IS NULL OR started_at >= ?6)
+               AND (?7 IS NULL OR started_at < ?7)
+               AND (?8 IS NULL OR id < ?8)
+             ORDER BY id DESC
+             LIMIT ?9",
+        )?;
+        let rows = stmt.query_map(params![
+            tenant,
+            query.action,
+            query.actor,
+            query.outcome,
+            query.target,
+            query.since,
+            query.until,
+            query.before_id,
+            query.limit.unwrap_or(100).min(1000),
+        ], |row| {
+            let params: String = row.get(4)?;
+            Ok(AuditEntry {
+                id: row.get(0)?,
+                actor: row.get(1)?,
+                action: row.get(2)?,
+                target: row.get(3)?,
+                params: serde_json::from_str(&params).unwrap_or(serde_json::Value::Null),
+                outcome: row.get(5)?,
+                detail: row.get(6)?,
+                started_at: row.get(7)?,
+                finished_at: row.get(8)?,
+            })
+        })?;
+        Ok(rows.collect::<rusqlite::Result<Vec<_>>>()?)
+    }
+
+    // Record a report about to be anchored, returning the id its confirmation is tracked under.
+    // The anchor's reference is filled in once the backend knows it.
+    pub fn insert_report_log(&self, tenant: &str, backend: AnchorBackend, hash: &str, repo_url: &str, commit_sha: &str, certificate_id: Option<&str>) -> Result<String> {
+        let id = uuid::Uuid::new_v4().to_string();
+        let now = now_unix();
+        let conn = self.conn()?;
+        conn.execute(
+            "INSERT INTO report_logs (id, tenant, anchor_backend, transaction_signature, hash, repo_url, commit_sha, certificate_id, status, created_at, updated_at)
+             VALUES (?1, ?2, ?3, '', ?4, ?5, ?6, ?7, ?8, ?9, ?9)",
+            params![id, tenant, backend.as_str(), hash, repo_url, commit_sha, certificate_id, ConfirmationStatus::Submitted.as_str(), now],
+        )?;
+        Ok(id)
+    }
+
+    // The anchor's reference (transaction signature or hash) and any receipt the backend issued
+    pub fn set_report_log_anchor(&self, id: &str, reference: &str, receipt: Option<&str>) -> Result<()> {
+        let conn = self.conn()?;
+        conn.execute(
+            "UPDATE report_logs SET transaction_signature = ?2, receipt = COALESCE(?3, receipt), updated_at = ?4 WHERE id = ?1",
+            params![id, reference, receipt, now_unix()],
+        )?;
+        Ok(())
+    }
+
+    // Move a report log to `status`; failed and finalized logs stay as they are
+    pub fn update_report_log(&self, id: &str, status: ConfirmationStatus, error: Option<&str>) -> Result<()> {
+        let conn = self.conn()?;
+        conn.execute(
+            "UPDATE report_logs SET status = ?2, error = COALESCE(?3, error), updated_at = ?4
+             WHERE id = ?1 AND status NOT IN ('failed', 'finalized')",
+            params![id, status.as_str(), error, now_unix()],
+        )?;
+        Ok(())
+    }
+
+    // The tenant's report logs for a repository and commit, on every backend, newest first
+    pub fn list_report_anchors(&self, tenant: &str, repo_url: &str, commit_sha: &str) -> Result<Vec<ReportAnchor>> {
+        let conn = self.conn()?;
+        let mut stmt = conn.prepare(&format!(
+            "SELECT {} FROM report_logs WHERE tenant = ?1 AND repo_url = ?2 AND commit_sha = ?3 ORDER BY created_at DESC",
+            REPORT_ANCHOR_COLUMNS,
+        ))?;
+        let rows = stmt.query_map(params![tenant, repo_url, commit_sha], report_anchor_from_row)?;
+        Ok(rows.collect::<rusqlite::Result<Vec<_>>>()?)
+    }
+
+    // Confirmed anchors of a report hash on the backends other than Solana, whose own record
+    // is the chain, oldest first
+    pub fn confirmed_external_anchors(&self, hash: &str, repo_url: Option<&str>) -> Result<Vec<ReportAnchor>> {
+        let conn = self.conn()?;
+        let mut stmt = conn.prepare(&format!(
+            "SELECT {} FROM report_logs
+             WHERE hash = ?1 AND (?2 IS NULL OR repo_url = ?2) AND anchor_backend != 'solana' AND status IN ('confirmed', 'finalized')
+             ORDER BY created_at",
+            REPORT_ANCHOR_COLUMNS,
+        ))?;
+        let rows = stmt.query_map(params![hash, repo_url], report_anchor_from_row)?;
+        Ok(rows.collect::<rusqlite::Result<Vec<_>>>()?)
+    }
+
+    // A report log in the shape of a queued job, so the jobs API can serve both
+    pub fn get_report_log(&self, id: &str) -> Result<Option<JobInfo>> {
+        let conn = self.conn()?;
+        let job = conn.query_row(
+            "SELECT tenant, transaction_signature, hash, repo_url, commit_sha, certificate_id, status, error, created_at, updated_at, anchor_backend, receipt
+             FROM report_logs WHERE id = ?1",
+            params![id],
+            |row| {
+                let certificate_id: Option<String> = row.get(5)?;
+                Ok(JobInfo {
+                    id: id.to_string(),
+                    kind: "log_report".to_string(),
+                    status: row.get(6)?,
+                    attempts: 1,
+                    worker: None,
+                    created_at: row.get(8)?,
+                    updated_at: row.get(9)?,
+                    result: Some(serde_json::json!({
+                        "transaction_signature": row.get::<_, String>(1)?,
+                        "hash": row.get::<_, String>(2)?,
+                        "repo_url": row.get::<_, String>(3)?,
+                        "commit_sha": row.get::<_, String>(4)?,
+                        "certificate_id": certificate_id,
+                        "anchor_backend": row.get::<_, String>(10)?,
+                        "anchor_receipt": row.get::<_, Option<String>>(11)?,
+                    })),
+                    error: row.get(7)?,
+                    clone_progress: None,
+                    checkpoint: None,
+                    regression: None,
+                    priority: None,
+                    queue_position: None,
+                    tenant: row.get(0)?,
+                })
+            },
+        ).optional()?;
+        Ok(job)
+    }
+
+    // Hold a report until `threshold` of `reviewers` approve it; the submission is kept as
+    // made so it can be logged unchanged
+    pub fn insert_report_approval(&self, tenant: &str, request: &ReportSubmission, hash: &str, threshold: usize, reviewers: &[String]) -> Result<String> {
+        let id = uuid::Uuid::new_v4().to_string();
+        let now = now_unix();
+        let conn = self.conn()?;
+        conn.execute(
+            "INSERT INTO report_approvals (id, tenant, request, hash, repo_url, commit_sha, threshold, reviewers, status, created_at, updated_at)
+             VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?10)",
+            params![
+                id,
+                tenant,
+                serde_json::to_string(request)?,
+                hash,
+                request.repo_url.canonical(),
+                request.commit_sha,
+                threshold as i64,
+                serde_json::to_string(reviewers)?,
+                ApprovalStatus::Pending.as_str(),
+                now,
+            ],
+        )?;
+        Ok(id)
+    }
+
+    pub fn get_report_approval(&self, tenant: &str, id: &str) -> Result<Option<ReportApproval>> {
+        let conn = self.conn()?;
+        let approval = conn.query_row(
+            "SELECT hash, repo_url, commit_sha, status, threshold, reviewers, transaction_signature, job_id, certificate_id, error, created_at, updated_at
+             FROM report_approvals WHERE tenant = ?1 AND id = ?2",
+            params![tenant, id],
+            |row| {
+                let status: String = row.get(3)?;
+                let reviewers: String = row.get(5)?;
+                Ok(ReportApproval {
+                    id: id.to_string(),
+                    hash: row.get(0)?,
+                    repo_url: row.get(1)?,
+                    commit_sha: row.get(2)?,
+                    status: ApprovalStatus::parse(&status).unwrap_or(ApprovalStatus::Pending),
+                    threshold: row.get::<_, i64>(4)? as usize,
+                    reviewers: serde_json::from_str(&reviewers).unwrap_or_default(),
+                    approvals: Vec::new(),
+                    transaction_signature: row.get(6)?,
+                    job_id: row.get(7)?,
+                    certificate_id: row.get(8)?,
+                    error: row.get(9)?,
+                    created_at: row.get(10)?,
+                    updated_at: row.get(11)?,
+                })
+            },
+        ).optional()?;
+        let Some(mut approval) = approval else {
+            return Ok(None);
+        };
+
+        let mut stmt = conn.prepare(
+            "SELECT reviewer, signature, approved_at FROM report_approval_signatures
+             WHERE approval_id = ?1 ORDER BY approved_at, reviewer",
+        )?;
+        let rows = stmt.query_map(params![id], |row| Ok(ReviewerApproval {
+            reviewer: row.get(0)?,
+            signature: row.get(1)?,
+            approved_at: row.get(2)?,
+        }))?;
+        approval.approvals = rows.collect::<rusqlite::Result<Vec<_>>>()?;
+        Ok(Some(approval))
+    }
+
+    // The submission an approval holds back, with the hex hash of its report. Approvals from
+    // before uploads were hashed on arrival also stored the content, which is ignored.
+    pub fn get_report_approval_request(&self, id: &str) -> Result<Option<(ReportSubmission, String)>> {
+        let conn = self.conn()?;
+        let row: Option<(String, String)> = conn.query_row(
+            "SELECT request, hash FROM report_approvals WHERE id = ?1",
+            params![id],
+            |row| Ok((row.get(0)?, row.get(1)?)),
+        ).optional()?;
+        row.map(|(request, hash)| Ok((serde_json::from_str(&request)?, hash))).transpose()
+    }
+
+    // A reviewer approving twice keeps their first approval
+    pub fn add_report_approval_signature(&self, id: &str, reviewer: &str, signature: &str) -> Result<()> {
+        let conn = self.conn()?;
+        conn.execute(
+            "INSERT OR IGNORE INTO report_approval_signatures (approval_id, reviewer, signature, approved_at)
+             VALUES (?1, ?2, ?3, ?4)",
+            params![id, reviewer, signature, now_unix()],
+        )?;
+        Ok(())
+    }
+
+    // Claim a pending approval for submission; false if it isn't pending (someone else claimed it)
+    pub fn begin_report_approval_submission(&self, id: &str) -> Result<bool> {
+        let conn = self.conn()?;
+        let updated = conn.execute(
+            "UPDATE report_approvals SET status = ?2, updated_at = ?3 WHERE id = ?1 AND status = 'pending'",
+            params![id, ApprovalStatus::Submitting.as_str(), now_unix()],
+        )?;
+        Ok(updated > 0)
+    }
+
+    // Record how sending went: submitted, or back to pending with the error so it can be retried
+    pub fn finish_report_approval_submission(&self, id: &str, response: &ReportLogResponse) -> Result<()> {
+        let conn = self.conn()?;
+        let (status, error) = if response.success {
+            (ApprovalStatus::Submitted, None)
+        } else {
+            (ApprovalStatus::Pending, Some(response.message.as_str()))
+        };
+        conn.execute(
+            "UPDATE report_approvals SET status = ?2, transaction_signature = ?3, job_id = ?4, certificate_id = ?5, error = ?6, updated_at = ?7
+             WHERE id = ?1",
+            params![id, status.as_str(), response.transaction_signature, response.job_id, response.certificate_id, error, now_unix()],
+        )?;
+        Ok(())
+    }
+
+    // Record a certificate mint about to be attempted
+    pub fn insert_certificate(&self, tenant: &str, report_hash: &str, wallet: &str, metadata_uri: &str) -> Result<Certificate> {
+        let id = uuid::Uuid::new_v4().to_string();
+        let now = now_unix();
+        let conn = self.conn()?;
+        conn.execute(
+            "INSERT INTO certificates (id, tenant, report_hash, wallet, metadata_uri, status, created_at, updated_at)
+             VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?7)",
+            params![id, tenant, report_hash, wallet, metadata_uri, CertificateStatus::Pending.as_str(), now],
+        )?;
+        Ok(Certificate {
+            id,
+            report_hash: report_hash.to_string(),
+            wallet: wallet.to_string(),
+            metadata_uri: metadata_uri.to_string(),
+            status: CertificateStatus::Pending,
+            mint: None,
+            transaction_signature: None,
+            error: None,
+            created_at: now,
+            updated_at: now,
+        })
+    }
+
+    pub fn finish_certificate(&self, id: &str, outcome: &Result<(String, String)>) -> Result<()> {
+        let conn = self.conn()?;
+        let (status, mint, signature, error) = match outcome {
+            Ok((mint, signature)) => (CertificateStatus::Minted, Some(mint.as_str()), Some(signature.as_str()), None),
+            Err(e) => (CertificateStatus::Failed, None, None, Some(e.to_string())),
+        };
+        conn.execute(
+            "UPDATE certificates SET status = ?2, mint = ?3, transaction_signature = ?4, error = ?5, updated_at = ?6
+             WHERE id = ?1",
+            params![id, status.as_str(), mint, signature, error, now_unix()],
+        )?;
+        Ok(())
+    }
+
+    pub fn get_certificate(&self, tenant: &str, id: &str) -> Result<Option<Certificate>> {
+        let conn = self.conn()?;
+        let certificate = conn.query_row(
+            "SELECT id, report_hash, wallet, metadata_uri, status, mint, transaction_signature, error, created_at, updated_at
+             FROM certificates WHERE tenant = ?1 AND id = ?2",
+            params![tenant, id],
+            |row| {
+                let status: String = row.get(4)?;
+                Ok(Certificate {
+                    id: row.get(0)?,
+                    report_hash: row.get(1)?,
+                    wallet: row.get(2)?,
+                    metadata_uri: row.get(3)?,
+                    status: CertificateStatus::parse(&status).unwrap_or(CertificateStatus::Failed),
+                    mint: row.get(5)?,
+                    transaction_signature: row.get(6)?,
+                    error: row.get(7)?,
+                    created_at: row.get(8)?,
+                    updated_at: row.get(9)?,
+                })
+            },
+        ).optional()?;
+        Ok(certificate)
+    }
+
+    // Store a report read from its account. Only the status of an indexed report is updated:
+    // nothing else changes on-chain, so the first-seen values are what re-verification checks against.
+    pub fn upsert_indexed_report(&self, cluster: &str, report: &LoggedReport) -> Result<()> {
+        let conn = self.conn()?;
+        conn.execute(
+            "INSERT INTO indexed_reports (cluster, address, authority, hash, repo_hash, commit_sha, timestamp, status, indexed_at)
+             VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9)
+             ON CONFLICT (cluster, address) DO UPDATE SET status = excluded.status, indexed_at = excluded.indexed_at",
+            params![cluster, report.address, report.authority, report.hash, report.repo_hash, report.commit_sha, report.timestamp, report.status.as_str(), now_unix()],
+        )?;
+        Ok(())
+    }
+
+    // Store a newly logged report; one already indexed keeps its status, which may have moved on
+    pub fn insert_indexed_report(&self, cluster: &str, report: &LoggedReport) -> Result<()> {
+        let conn = self.conn()?;
+        conn.execute(
+            "INSERT OR IGNORE INTO indexed_reports (cluster, address, authority, hash, repo_hash, commit_sha, timestamp, status, indexed_at)
+             VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9)",
+            params![cluster, report.address, report.authority, report.hash, report.repo_hash, report.commit_sha, report.timestamp, report.status.as_str(), now_unix()],
+        )?;
+        Ok(())
+    }
+
+    // Returns false when the report isn't indexed yet
+    pub fn set_indexed_report_status(&self, cluster: &str, address: &str, status: ReportStatus) -> Result<bool> {
+        let conn = self.conn()?;
+        let updated = conn.execute(
+            "UPDATE indexed_reports SET status = ?3, indexed_at = ?4 WHERE cluster = ?1 AND address = ?2",
+            params![cluster, address, status.as_str(), now_unix()],
+        )?;
+        Ok(updated > 0)
+    }
+
+    // Newest first, along with how many reports match across all pages; every filter is optional
+    pub fn list_indexed_reports(&self, cluster: &str, repo_hash: Option<&str>, query: &ReportsQuery) -> Result<(Vec<LoggedReport>, u64)> {
+        const FILTERS: &str = "WHERE cluster = ?1
+               AND (?2 IS NULL OR repo_hash = ?2)
+               AND (?3 IS NULL OR hash = ?3)
+               AND (?4 IS NULL OR commit_sha LIKE ?4 || '%')
+               AND (?5 IS NULL OR authority = ?5)
+               AND (?6 IS NULL OR status = ?6)
+               AND (?7 IS NULL OR address LIKE '%' || ?7 || '%' OR authority LIKE '%' || ?7 || '%'
+                    OR hash LIKE '%' || ?7 || '%' OR commit_sha LIKE '%' || ?7 || '%')
+               AND (?8 IS NULL OR (discrepancy IS NOT NULL) = ?8)";
+        let hash = query.hash.as_deref().map(|hash| hash.trim().to_lowercase());
+        let commit_sha = query.commit_sha.as_deref().map(|commit_sha| commit_sha.trim().to_lowercase());
+        let search = query.search.as_deref().map(str::trim).filter(|search| !search.is_empty());
+        let status = query.status.map(|status| status.as_str());
+
+        let conn = self.conn()?;
+        let total: i64 = conn.query_row(
+            &format!("SELECT COUNT(*) FROM indexed_reports {}", FILTERS),
+            params![cluster, repo_hash, hash, commit_sha, query.authority, status, search, query.flagged],
+            |row| row.get(0),
+        )?;
+        let mut stmt = conn.prepare(&format!(
+            "SELECT {} FROM indexed_reports {}
+             ORDER BY timestamp DESC, address
+             LIMIT ?9 OFFSET ?10",
+            INDEXED_REPORT_COLUMNS, FILTERS,
+        ))?;
+        let rows = stmt.query_map(params![
+            cluster, repo_hash, hash, commit_sha, query.authority, status, search, query.flagged,
+            query.limit.unwrap_or(50).min(500),
+            query.offset.unwrap_or(0),
+        ], indexed_report_from_row)?;
+        Ok((rows.collect::<rusqlite::Result<Vec<_>>>()?, total as u64))
+    }
+
+    // Every indexed report on the cluster, oldest first
+    pub fn all_indexed_reports(&self, cluster: &str) -> Result<Vec<LoggedReport>> {
+        let conn = self.conn()?;
+        let mut stmt = conn.prepare(&format!(
+            "SELECT {} FROM indexed_reports WHERE cluster = ?1 ORDER BY timestamp, address",
+            INDEXED_REPORT_COLUMNS,
+        ))?;
+        let rows = stmt.query_map(params![cluster], indexed_report_from_row)?;
+        Ok(rows.collect::<rusqlite::Result<Vec<_>>>()?)
+    }
+
+    // Flag a report unless it already carries an equal or more severe discrepancy; flags are
+    // never cleared, so an alert can't quietly disappear. Returns whether the flag was set.
+    pub fn flag_indexed_report(&self, cluster: &str, address: &str, kind: DiscrepancyKind, detail: &str) -> Result<bool> {
+        let conn = self.conn()?;
+        let updated = conn.execute(
+            "UPDATE indexed_reports SET discrepancy = ?3, discrepancy_detail = ?4, discrepancy_at = ?5
+             WHERE cluster = ?1 AND address = ?2
+               AND (discrepancy IS NULL OR (discrepancy = 'program_upgraded' AND ?3 != 'program_upgraded'))",
+            params![cluster, address, kind.as_str(), detail, now_unix()],
+        )?;
+        Ok(updated > 0)
+    }
+
+    // Flag every report on the cluster that isn't flagged yet and was logged before `logged_before`
+    // (all of them without it), returning how many were
+    pub fn flag_unflagged_indexed_reports(&self, cluster: &str, logged_before: Option<i64>, kind: DiscrepancyKind, detail: &str) -> Result<usize> {
+        let conn = self.conn()?;
+        let updated = conn.execute(
+            "UPDATE indexed_reports SET discrepancy = ?3, discrepancy_detail = ?4, discrepancy_at = ?5
+             WHERE cluster = ?1 AND discrepancy IS NULL AND (?2 IS NULL OR timestamp < ?2)",
+            params![cluster, logged_before, kind.as_str(), detail, now_unix()],
+        )?;
+        Ok(updated)
+    }
+
+    // The slot the program was last deployed at, as of the previous re-verification
+    pub fn program_deploy_slot(&self, cluster: &str) -> Result<Option<u64>> {
+        let conn = self.conn()?;
+        let slot: Option<i64> = conn.query_row(
+            "SELECT deploy_slot FROM registry_programs WHERE cluster = ?1",
+            params![cluster],
+            |row| row.get(0),
+        ).optional()?;
+        Ok(slot.map(|slot| slot as u64))
+    }
+
+    pub fn set_program_deploy_slot(&self, cluster: &str, slot: u64) -> Result<()> {
+        let conn = self.conn()?;
+        conn.execute(
+            "INSERT INTO registry_programs (cluster, deploy_slot, checked_at) VALUES (?1, ?2, ?3)
+             ON CONFLICT (cluster) DO UPDATE SET deploy_slot = excluded.deploy_slot, checked_at = excluded.checked_at",
+            params![cluster, slot as i64, now_unix()],
+        )?;
+        Ok(())
+    }
+
+    // Finding counts by severity for every completed run of a repository, oldest first
+    pub fn severity_trend(&self, tenant: &str, repo_url: &str) -> Result<Vec<TrendPoint>> {
+        let conn = self.conn()?;
+        let mut stmt = conn.prepare(
+            "SELECT r.id, r.created_at, r.commit_sha,
+                    COUNT(f.fingerprint),
+                    COALESCE(SUM(f.severity = 'critical'), 0),
+                    COALESCE(SUM(f.severity = 'high'), 0),
+                    COALESCE(SUM(f.severity = 'medium'), 0),
+                    COALESCE(SUM(f.severity = 'low'), 0)
+             FROM analysis_runs r
+             LEFT JOIN findings f ON f.run_id = r.id
+                AND COALESCE(f.triage_state, 'open') NOT IN ('false_positive', 'accepted_risk')
+             WHERE r.tenant = ?1 AND r.repo_url = ?2 AND r.status = 'completed'
+             GROUP BY r.id
+             ORDER BY r.created_at, r.rowid",
+        )?;
+        let rows = stmt.query_map(params![tenant, repo_url], |row| Ok(TrendPoint {
+            run_id: row.get(0)?,
+            created_at: row.get(1)?,
+            commit_sha: row.get(2)?,
+            total: row.get(3)?,
+            critical: row.get(4)?,
+            high: row.get(5)?,
+            medium: row.get(6)?,
+            low: row.get(7)?,
+        }))?;
+        Ok(rows.collect::<rusqlite::Result<Vec<_>>>()?)
+    }
+}
+
+const FINDING_COLUMNS: &str = "fingerprint, severity, file, line, bug, fix, triage_state, rule_id, autofix,
+    (SELECT i.issue_url FROM finding_issues i JOIN analysis_runs r ON r.id = findings.run_id
+        WHERE i.fingerprint = findings.fingerprint AND i.tenant = r.tenant AND i.repo_url = r.repo_url)";
+
+fn finding_from_row(row: &rusqlite::Row) -> rusqlite::Result<CodeBug> {
+    let severity: String = row.get(1)?;
+    let triage_state: Option<String> = row.get(6)?;
+    let autofix: Option<String> = row.get(8)?;
+    let rule_id: Option<String> = row.get(7)?;
+    Ok(CodeBug {
+        fingerprint: row.get(0)?,
+        severity: BugSeverity::parse(&severity).unwrap_or(BugSeverity::Low),
+        file: row.get(2)?,
+        line: row.get(3)?,
+        bug: row.get(4)?,
+        fix: row.get(5)?,
+        taxonomy: rule_id.as_deref().and_then(rule_taxonomy),
+        rule_id,
+        autofix: autofix.and_then(|a| serde_json::from_str(&a).ok()),
+        triage_state: triage_state.as_deref().and_then(TriageState::parse),
+        triage_comment: None,
+        issue_url: row.get(9)?,
+        assignee: None,
+    })
+}
+
+const ENGAGEMENT_COLUMNS: &str = "id, name, repo_url, base_commit, head_commit, state, report_log_id, created_by, created_at, updated_at";
+
+fn engagement_from_row(row: &rusqlite::Row) -> rusqlite::Result<Engagement> {
+    let state: String = row.get(5)?;
+    Ok(Engagement {
+        id: row.get(0)?,
+        name: row.get(1)?,
+        repo_url: row.get(2)?,
+        base_commit: row.get(3)?,
+        head_commit: row.get(4)?,
+        state: EngagementState::parse(&state).unwrap_or(EngagementState::Scoping),
+        report_log_id: row.get(6)?,
+        created_by: row.get(7)?,
+        created_at: row.get(8)?,
+        updated_at: row.get(9)?,
+        runs: None,
+        triage: None,
+        report: None,
+    })
+}
+
+const REPORT_ANCHOR_COLUMNS: &str = "anchor_backend, transaction_signature, hash, status, receipt, created_at";
+
+fn report_anchor_from_row(row: &rusqlite::Row) -> rusqlite::Result<ReportAnchor> {
+    let backend: String = row.get(0)?;
+    Ok(ReportAnchor {
+        backend: AnchorBackend::parse(&backend).unwrap_or_default(),
+        transaction_signature: row.get(1)?,
+        hash: row.get(2)?,
+        status: row.get(3)?,
+        receipt: row.get(4)?,
+        logged_at: row.get(5)?,
+        verified: None,
+        verification_error: None,
+    })
+}
+
+const INDEXED_REPORT_COLUMNS: &str =
+    "address, authority, hash, repo_hash, commit_sha, timestamp, status, discrepancy, discrepancy_detail, discrepancy_at";
+
+fn indexed_report_from_row(row: &rusqlite::Row) -> rusqlite::Result<LoggedReport> {
+    let status: String = row.get(6)?;
+    let discrepancy: Option<String> = row.get(7)?;
+    Ok(LoggedReport {
+        address: row.get(0)?,
+        authority: row.get(1)?,
+        hash: row.get(2)?,
+        repo_hash: row.get(3)?,
+        commit_sha: row.get(4)?,
+        timestamp: row.get(5)?,
+        status: ReportStatus::parse(&status).unwrap_or(ReportStatus::Active),
+        discrepancy: match discrepancy.as_deref().and_then(DiscrepancyKind::parse) {
+            Some(kind) => Some(Discrepancy {
+                kind,
+                detail: row.get::<_, Option<String>>(8)?.unwrap_or_default(),
+                detected_at: row.get::<_, Option<i64>>(9)?.unwrap_or_default(),
+            }),
+            None => None,
+        },
+    })
+}
+
+pub fn now_unix() -> i64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_secs() as i64)
+        .unwrap_or(0)
+}
@@ -0,0 +1,132 @@
+use anyhow::{anyhow, Result};
+use solana_sdk::pubkey::Pubkey;
+use std::str::FromStr;
+
+use crate::cluster::Cluster;
+use crate::models::{BugSeverity, DeploymentInfo};
+
+const BPF_LOADER_UPGRADEABLE: &str = "BPFLoaderUpgradeab1e11111111111111111111111";
+const BPF_LOADER_V2: &str = "BPFLoader2111111111111111111111111111111111";
+const BPF_LOADER_V1: &str = "BPFLoader1111111111111111111111111111111111";
+const LOADER_V4: &str = "LoaderV411111111111111111111111111111111111";
+
+// Programs whose accounts act as multisig wallets
+const MULTISIG_PROGRAMS: &[(&str, &str)] = &[
+    ("SMPLecH534NA9acpos4G6x7uf3LWbCAwZQE9e8ZekMu", "Squads v3"),
+    ("SQDS4ep65T869zMMBKyuUq6SqFZcSUVzw9xCr9VCsbwn", "Squads v4"),
+    ("msigmtwzgXJHj2ext4XJjCDmpbcMuufFb5cHuwg6Xdt", "Serum multisig"),
+    ("GokivDYuQXPZCWRkwMhdH2h91KpDQXBEmpgBgs55bnpH", "Goki smart wallet"),
+    ("GovER5Lthms3bLBqWub97yVrMmEogzX7xNjdXpPPCVZw", "SPL Governance"),
+];
+
+// UpgradeableLoaderState is bincode: a u32 variant tag followed by the variant's fields
+const UPGRADEABLE_PROGRAM_TAG: u32 = 2;
+const UPGRADEABLE_PROGRAM_DATA_TAG: u32 = 3;
+// LoaderV4Status::Finalized, i.e. no further upgrades
+const LOADER_V4_FINALIZED: u64 = 2;
+
+// Who controls upgrades of a deployed program, and how risky that is
+#[tracing::instrument(name = "deployment.check", skip(cluster), fields(cluster = cluster.as_str()))]
+pub async fn check_deployment(cluster: Cluster, program_id: &str) -> Result<DeploymentInfo> {
+    let program_key = Pubkey::from_str(program_id).map_err(|_| anyhow!("Invalid program ID: {}", program_id))?;
+    let client = cluster.rpc_client();
+    let program = client.get_account(&program_key).await
+        .map_err(|e| anyhow!("Failed to fetch program account on {}: {}", cluster.as_str(), e))?;
+    if !program.executable {
+        return Err(anyhow!("Account {} is not an executable program", program_id));
+    }
+
+    let mut info = DeploymentInfo {
+        program_id: program_id.to_string(),
+        cluster,
+        loader: program.owner.to_string(),
+        upgradeable: false,
+        upgrade_authority: None,
+        authority_kind: "none".to_string(),
+        multisig_program: None,
+        last_deploy_slot: None,
+        risk: BugSeverity::Low,
+        notes: Vec::new(),
+    };
+
+    let authority = match program.owner.to_string().as_str() {
+        BPF_LOADER_UPGRADEABLE => {
+            let programdata_key = read_tagged_pubkey(&program.data, UPGRADEABLE_PROGRAM_TAG)
+                .ok_or_else(|| anyhow!("Unexpected program account layout"))?;
+            let programdata = client.get_account(&programdata_key).await
+                .map_err(|e| anyhow!("Failed to fetch program data account: {}", e))?;
+            let (slot, authority) = parse_program_data(&programdata.data)
+                .ok_or_else(|| anyhow!("Unexpected program data account layout"))?;
+            info.last_deploy_slot = Some(slot);
+            authority
+        },
+        LOADER_V4 => {
+            let (slot, authority, status) = parse_loader_v4_state(&program.data)
+                .ok_or_else(|| anyhow!("Unexpected loader v4 program layout"))?;
+            info.last_deploy_slot = Some(slot);
+            (status != LOADER_V4_FINALIZED).then_some(authority)
+        },
+        BPF_LOADER_V2 | BPF_LOADER_V1 => {
+            info.notes.push("Deployed with a legacy loader, the program cannot be upgraded".to_string());
+            None
+        },
+        other => return Err(anyhow!("Program is owned by unknown loader {}", other)),
+    };
+
+    let Some(authority) = authority else {
+        info.notes.push("Upgrade authority is revoked, the deployed code is immutable".to_string());
+        return Ok(info);
+    };
+    info.upgradeable = true;
+    info.upgrade_authority = Some(authority.to_string());
+
+    // Multisig accounts are owned by their wallet program; vaults (e.g. Squads v4) are PDAs
+    let authority_owner = match client.get_account(&authority).await {
+        Ok(account) => Some(account.owner.to_string()),
+        Err(_) => None,
+    };
+    let multisig = authority_owner.as_deref()
+        .and_then(|owner| MULTISIG_PROGRAMS.iter().find(|(id, _)| *id == owner));
+    if let Some((id, name)) = multisig {
+        info.authority_kind = "multisig".to_string();
+        info.multisig_program = Some(id.to_string());
+        info.notes.push(format!("Upgrades require approval through {}", name));
+    } else if !authority.is_on_curve() {
+        info.authority_kind = "program_derived".to_string();
+        info.risk = BugSeverity::Medium;
+        info.notes.push("Upgrade authority is a program-derived address; confirm it belongs to a multisig or governance program".to_string());
+    } else {
+        info.authority_kind = "wallet".to_string();
+        info.risk = BugSeverity::High;
+        info.notes.push("A single key can upgrade the program; move the authority to a multisig or revoke it".to_string());
+    }
+    Ok(info)
+}
+
+fn read_tagged_pubkey(data: &[u8], tag: u32) -> Option<Pubkey> {
+    if u32::from_le_bytes(data.get(0..4)?.try_into().ok()?) != tag {
+        return None;
+    }
+    Some(Pubkey::new_from_array(data.get(4..36)?.try_into().ok()?))
+}
+
+// ProgramData { slot: u64, upgrade_authority_address: Option<Pubkey> }
+fn parse_program_data(data: &[u8]) -> Option<(u64, Option<Pubkey>)> {
+    if u32::from_le_bytes(data.get(0..4)?.try_into().ok()?) != UPGRADEABLE_PROGRAM_DATA_TAG {
+        return None;
+    }
+    let slot = u64::from_le_bytes(data.get(4..12)?.try_into().ok()?);
+    let authority = match data.get(12)? {
+        0 => None,
+        _ => Some(Pubkey::new_from_array(data.get(13..45)?.try_into().ok()?)),
+    };
+    Some((slot, authority))
+}
+
+// LoaderV4State { slot: u64, authority_address_or_next_version: Pubkey, status: u64 }
+fn parse_loader_v4_state(data: &[u8]) -> Option<(u64, Pubkey, u64)> {
+    let slot = u64::from_le_bytes(data.get(0..8)?.try_into().ok()?);
+    let authority = Pubkey::new_from_array(data.get(8..40)?.try_into().ok()?);
+    let status = u64::from_le_bytes(data.get(40..48)?.try_into().ok()?);
+    Some((slot, authority, status))
+}
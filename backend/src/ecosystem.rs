@@ -0,0 +1,160 @@
+use anyhow::{anyhow, Result};
+use std::collections::{BTreeMap, HashMap};
+use std::env;
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::time::Instant;
+
+use crate::analyzer::CodeAnalyzer;
+use crate::db::Database;
+use crate::exclusions::PathExclusions;
+use crate::external::ExternalAnalyzers;
+use crate::github::GitHubClient;
+use crate::models::{AnalysisMode, BugSeverity, CodeBug, EcosystemProject, EcosystemStats, ProjectType, RuleFrequency, ScoreBucket};
+use crate::repo_url::RepoUrl;
+use crate::rules::rule_catalog;
+use crate::sandbox::{NetworkPolicy, Sandbox};
+
+// Fewer scanned projects than this and the breakdowns could point at a single one
+const MIN_PROJECTS_FOR_BREAKDOWN: usize = 5;
+
+// The first scan waits a little after boot, as re-verification does
+const STARTUP_DELAY: Duration = Duration::from_secs(5 * 60);
+
+const SCORE_BUCKET_WIDTH: u32 = 20;
+
+// Nightly scans of registered public Anchor projects for /api/ecosystem-stats. Off unless
+// SAFEX_ECOSYSTEM_SCAN_INTERVAL_SECS is set (86400 for nightly).
+pub struct Ecosystem {
+    pub interval: Duration,
+}
+
+impl Ecosystem {
+    pub fn from_env() -> Result<Option<Self>> {
+        let secs: u64 = match env::var("SAFEX_ECOSYSTEM_SCAN_INTERVAL_SECS") {
+            Ok(value) => value.trim().parse().map_err(|_| anyhow!("Invalid SAFEX_ECOSYSTEM_SCAN_INTERVAL_SECS: {}", value))?,
+            Err(_) => 0,
+        };
+        Ok((secs > 0).then(|| Self { interval: Duration::from_secs(secs) }))
+    }
+
+    pub fn spawn(&self, db: Arc<Database>, external: Arc<ExternalAnalyzers>) {
+        let period = self.interval;
+        tokio::spawn(async move {
+            let mut interval = tokio::time::interval_at(Instant::now() + STARTUP_DELAY.min(period), period);
+            loop {
+                interval.tick().await;
+                match scan_ecosystem(&db, &external).await {
+                    Ok((scanned, failed)) => println!("Ecosystem scan finished: {} projects scanned, {} failed", scanned, failed),
+                    Err(e) => println!("Warning: Ecosystem scan failed: {}", e),
+                }
+            }
+        });
+    }
+}
+
+// Scan every registered project one after another, returning how many were scanned and how
+// many failed
+pub async fn scan_ecosystem(db: &Database, external: &Arc<ExternalAnalyzers>) -> Result<(usize, usize)> {
+    let projects = db.list_ecosystem_projects(None)?;
+    let (mut scanned, mut failed) = (0, 0);
+    for project in projects {
+        let repo_url = project.repo_url.clone();
+        let external = external.clone();
+        let outcome = tokio::task::spawn_blocking(move || scan_project(&repo_url, &external)).await
+            .map_err(|e| anyhow!("Scan task failed: {}", e))
+            .and_then(|outcome| outcome);
+        match outcome {
+            Ok(bugs) => {
+                db.record_ecosystem_scan(&project.repo_url, project_score(&bugs), &rule_hits(&bugs))?;
+                scanned += 1;
+            },
+            Err(e) => {
+                println!("Warning: Ecosystem scan of {} failed: {}", project.repo_url, e);
+                db.record_ecosystem_scan_error(&project.repo_url, &e.to_string())?;
+                failed += 1;
+            },
+        }
+    }
+    Ok((scanned, failed))
+}
+
+// A full analysis of the default branch under the default network policy. The statistics are
+// public, so projects are cloned without credentials; GITHUB_TOKEN could reach private ones.
+fn scan_project(repo_url: &str, external: &ExternalAnalyzers) -> Result<Vec<CodeBug>> {
+    let repo_url = RepoUrl::parse(repo_url)?;
+    let temp_dir = tempfile::TempDir::new()?;
+    let github_client = GitHubClient::new().without_token();
+    github_client.clone_repo(repo_url.clone_url(), temp_dir.path())?;
+    if github_client.detect_project_type(temp_dir.path())? != Some(ProjectType::Anchor) {
+        return Err(anyhow!("Not an Anchor project"));
+    }
+    let sandbox = Sandbox::new(NetworkPolicy::default(), false)?;
+    // Paths the project itself excludes in .safex.toml
+    let exclusions = PathExclusions::load(temp_dir.path(), &[])?;
+    CodeAnalyzer::new().excluding(exclusions).analyze_repo(temp_dir.path(), ProjectType::Anchor, AnalysisMode::Full, external, &sandbox, &mut |_| {})
+}
+
+// 100 less a penalty per finding by severity, floored at 0. Findings without a rule are the
+// analyzer's own failures and don't count against the project.
+pub fn project_score(bugs: &[CodeBug]) -> u32 {
+    let penalty: u32 = bugs.iter().filter(|bug| bug.rule_id.is_some()).map(|bug| match bug.severity {
+        BugSeverity::Critical => 25,
+        BugSeverity::High => 10,
+        BugSeverity::Medium => 3,
+        BugSeverity::Low => 1,
+    }).sum();
+    100u32.saturating_sub(penalty)
+}
+
+fn rule_hits(bugs: &[CodeBug]) -> BTreeMap<String, u32> {
+    let mut hits = BTreeMap::new();
+    for rule_id in bugs.iter().filter_map(|bug| bug.rule_id.as_ref()) {
+        *hits.entry(rule_id.clone()).or_insert(0) += 1;
+    }
+    hits
+}
+
+// Aggregate the latest successful scan of each project
+pub fn ecosystem_stats(projects: &[EcosystemProject]) -> EcosystemStats {
+    let scanned: Vec<&EcosystemProject> = projects.iter().filter(|p| p.score.is_some()).collect();
+    let mut stats = EcosystemStats {
+        registered_projects: projects.len() as u32,
+        scanned_projects: scanned.len() as u32,
+        last_scanned_at: scanned.iter().filter_map(|p| p.scanned_at).max(),
+        average_score: None,
+        score_distribution: Vec::new(),
+        rules: Vec::new(),
+    };
+    if scanned.len() < MIN_PROJECTS_FOR_BREAKDOWN {
+        return stats;
+    }
+
+    let scores: Vec<u32> = scanned.iter().filter_map(|p| p.score).collect();
+    stats.average_score = Some(scores.iter().sum::<u32>() as f64 / scores.len() as f64);
+    stats.score_distribution = (0..100 / SCORE_BUCKET_WIDTH).map(|bucket| {
+        let min = bucket * SCORE_BUCKET_WIDTH;
+        // The top bucket includes a perfect score
+        let max = if min + SCORE_BUCKET_WIDTH == 100 { 100 } else { min + SCORE_BUCKET_WIDTH - 1 };
+        ScoreBucket { min, max, projects: scores.iter().filter(|&&s| s >= min && s <= max).count() as u32 }
+    }).collect();
+
+    let mut frequencies: BTreeMap<&str, (u32, u32)> = BTreeMap::new();
+    for hits in scanned.iter().filter_map(|p| p.rule_hits.as_ref()) {
+        for (rule_id, count) in hits {
+            let entry = frequencies.entry(rule_id).or_insert((0, 0));
+            entry.0 += 1;
+            entry.1 += count;
+        }
+    }
+    let titles: HashMap<String, String> = rule_catalog().into_iter().map(|rule| (rule.id, rule.title)).collect();
+    stats.rules = frequencies.into_iter().map(|(rule_id, (projects, findings))| RuleFrequency {
+        rule_id: rule_id.to_string(),
+        title: titles.get(rule_id).cloned(),
+        projects,
+        project_share: projects as f64 / scanned.len() as f64,
+        findings,
+    }).collect();
+    stats.rules.sort_by(|a, b| b.projects.cmp(&a.projects).then(b.findings.cmp(&a.findings)));
+    stats
+}
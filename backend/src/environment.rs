@@ -0,0 +1,88 @@
+use std::collections::HashMap;
+use std::env;
+use std::process::{Command, Stdio};
+use std::sync::{Arc, Mutex, OnceLock};
+use std::time::Instant;
+
+use crate::models::{ExecutionMeta, PhaseTiming};
+use crate::toolchain::ProvisionedToolchains;
+
+// `--version` output per command and toolchain; installed versions don't change under a
+// running server, and asking rustup costs tens of milliseconds per call
+static VERSIONS: OnceLock<Mutex<HashMap<String, Option<String>>>> = OnceLock::new();
+
+// Told each phase as it starts, e.g. to show a queued job's progress
+pub type PhaseSink = Arc<dyn Fn(&str) + Send + Sync>;
+
+// Times a request's phases back to back: starting one ends the one before
+#[derive(Default)]
+pub struct PhaseTimer {
+    phases: Vec<PhaseTiming>,
+    current: Option<(String, Instant)>,
+    sink: Option<PhaseSink>,
+}
+
+impl PhaseTimer {
+    pub fn reporting_to(sink: PhaseSink) -> Self {
+        Self { sink: Some(sink), ..Self::default() }
+    }
+
+    pub fn start(&mut self, phase: &str) {
+        self.end();
+        self.current = Some((phase.to_string(), Instant::now()));
+        if let Some(sink) = &self.sink {
+            sink(phase);
+        }
+    }
+
+    pub fn end(&mut self) {
+        if let Some((phase, started)) = self.current.take() {
+            self.phases.push(PhaseTiming { phase, elapsed_ms: started.elapsed().as_millis() as u64 });
+        }
+    }
+
+    pub fn finish(mut self) -> Vec<PhaseTiming> {
+        self.end();
+        self.phases
+    }
+}
+
+// The environment a request ran in. Tool versions are read with the job's provisioned
+// toolchains on PATH, or left out when it ran none (`toolchains` is None).
+pub fn execution_meta(toolchains: Option<&ProvisionedToolchains>, rule_set_version: &str, phases: PhaseTimer) -> ExecutionMeta {
+    // Before the version probes, which aren't part of the last phase
+    let phases = phases.finish();
+    let version = |program: &str, args: &[&str]| toolchains.and_then(|toolchains| tool_version(toolchains, program, args));
+    ExecutionMeta {
+        rustc_version: version("rustc", &["--version"]),
+        clippy_version: version("cargo", &["clippy", "--version"]),
+        anchor_version: version("anchor", &["--version"]),
+        solana_version: version("solana", &["--version"]),
+        rule_set_version: rule_set_version.to_string(),
+        sandbox_image_digest: env::var("SAFEX_SANDBOX_IMAGE_DIGEST").ok().filter(|digest| !digest.trim().is_empty()),
+        phases,
+    }
+}
+
+pub fn tool_version(toolchains: &ProvisionedToolchains, program: &str, args: &[&str]) -> Option<String> {
+    let key = format!("{} {}|{:?}", program, args.join(" "), toolchains);
+    let versions = VERSIONS.get_or_init(|| Mutex::new(HashMap::new()));
+    if let Some(version) = versions.lock().unwrap().get(&key) {
+        return version.clone();
+    }
+
+    let mut command = Command::new(program);
+    command.args(args).stdin(Stdio::null());
+    if let Some(channel) = &toolchains.rust_toolchain {
+        command.env("RUSTUP_TOOLCHAIN", channel);
+    }
+    toolchains.apply(&mut command);
+    let version = match command.output() {
+        Ok(output) if output.status.success() => String::from_utf8_lossy(&output.stdout).lines().next()
+            .map(|line| line.trim().to_string())
+            .filter(|line| !line.is_empty()),
+        _ => None,
+    };
+    versions.lock().unwrap().insert(key, version.clone());
+    version
+}
@@ -0,0 +1,107 @@
+use anyhow::{anyhow, Result};
+use futures_util::future::join_all;
+use std::path::Path;
+use toml::Table;
+
+use crate::github::GitHubClient;
+use crate::models::{AnalysisEstimate, EstimatedProgram, GitHubTreeEntry, ProjectType};
+use crate::repo_url::RepoUrl;
+use crate::stats::{is_program_manifest, package_name};
+use crate::vendor::is_vendored_checksum;
+
+// Manifests fetched per estimate; workspaces rarely have more, vendored trees can have hundreds
+const MAX_MANIFESTS: usize = 50;
+
+// Rough cost model for /api/analyze-code: clone and dependency fetch, a clippy build per
+// program (dominated by the Solana dependency tree), then lint passes over the sources
+const BASE_SECS: u64 = 60;
+const PER_PROGRAM_SECS: u64 = 90;
+const RUST_BYTES_PER_SEC: u64 = 20_000;
+
+// Scope of an analysis from the tree listing and Cargo.toml files alone, without cloning
+pub async fn estimate_analysis(github: &GitHubClient, repo_url: &RepoUrl) -> Result<AnalysisEstimate> {
+    let (owner, repo) = repo_url.owner_repo()
+        .ok_or_else(|| anyhow!("Estimates are only available for GitHub repositories"))?;
+    let full_name = format!("{}/{}", owner, repo);
+    let default_branch = github.get_repo(owner, repo).await?.default_branch.unwrap_or_else(|| "HEAD".to_string());
+    let tree = github.get_tree(&full_name, &default_branch).await?;
+    
+    // Same exclusions as a checkout's file walk, including crates vendored with `cargo vendor`
+    let vendored: Vec<&str> = tree.tree.iter()
+        .filter(|entry| is_vendored_checksum(&entry.path))
+        .map(|entry| entry.path.rsplit_once('/').map_or("", |(dir, _)| dir))
+        .collect();
+    let blobs: Vec<&GitHubTreeEntry> = tree.tree.iter()
+        .filter(|entry| entry.entry_type == "blob")
+        .filter(|entry| !entry.path.split('/').any(|c| c.starts_with('.') || c == "target" || c == "node_modules"))
+        .filter(|entry| !vendored.iter().any(|dir| entry.path.strip_prefix(dir).is_some_and(|rest| rest.starts_with('/'))))
+        .collect();
+    let has_extension = |entry: &GitHubTreeEntry, extensions: &[&str]| {
+        Path::new(&entry.path).extension().and_then(|e| e.to_str()).is_some_and(|e| extensions.contains(&e))
+    };
+    let rust: Vec<&&GitHubTreeEntry> = blobs.iter().filter(|entry| has_extension(entry, &["rs"])).collect();
+    let typescript_files = blobs.iter()
+        .filter(|entry| entry.path.starts_with("tests/") || entry.path.starts_with("app/"))
+        .filter(|entry| has_extension(entry, &["ts", "tsx", "js", "jsx", "mjs"]))
+        .count() as u32;
+    let rust_bytes: u64 = rust.iter().map(|entry| entry.size.unwrap_or(0)).sum();
+    
+    let manifest_paths: Vec<&str> = blobs.iter()
+        .map(|entry| entry.path.as_str())
+        .filter(|path| *path == "Cargo.toml" || path.ends_with("/Cargo.toml"))
+        .collect();
+    if manifest_paths.len() > MAX_MANIFESTS {
+        println!("Warning: {} has {} Cargo.toml files, estimating from the first {}", full_name, manifest_paths.len(), MAX_MANIFESTS);
+    }
+    let branch = default_branch.as_str();
+    let downloads = manifest_paths.iter().take(MAX_MANIFESTS).map(|path| async move {
+        let content = github.download_file(repo_url, path, Some(branch), None).await?.text().await?;
+        Ok::<_, anyhow::Error>((*path, content.parse::<Table>()?))
+    });
+    let mut programs = Vec::new();
+    let mut anchor = false;
+    for manifest in join_all(downloads).await {
+        let (path, cargo_toml) = match manifest {
+            Ok(manifest) => manifest,
+            Err(e) => {
+                println!("Warning: Failed to read manifest for estimate: {}", e);
+                continue;
+            }
+        };
+        if !is_program_manifest(&cargo_toml) {
+            continue;
+        }
+        anchor |= cargo_toml.get("dependencies").and_then(|d| d.get("anchor-lang")).is_some();
+        programs.push(EstimatedProgram {
+            name: package_name(&cargo_toml),
+            path: path.trim_end_matches("Cargo.toml").trim_end_matches('/').to_string(),
+        });
+    }
+    
+    let project_type = match (programs.is_empty(), anchor) {
+        (true, _) => None,
+        (false, true) => Some(ProjectType::Anchor),
+        (false, false) => Some(ProjectType::Native),
+    };
+    // The fuzzer builds the workspace from the repository root
+    let fuzzing_note = if programs.is_empty() {
+        Some("No on-chain programs detected".to_string())
+    } else if !manifest_paths.contains(&"Cargo.toml") {
+        Some("The repository root has no Cargo.toml to build the programs from".to_string())
+    } else {
+        None
+    };
+    
+    Ok(AnalysisEstimate {
+        default_branch,
+        project_type,
+        rust_files: rust.len() as u32,
+        rust_bytes,
+        typescript_files,
+        estimated_duration_secs: BASE_SECS + PER_PROGRAM_SECS * programs.len() as u64 + rust_bytes / RUST_BYTES_PER_SEC,
+        programs,
+        fuzzing_feasible: fuzzing_note.is_none(),
+        fuzzing_note,
+        truncated: tree.truncated,
+    })
+}
@@ -0,0 +1,141 @@
+use anyhow::{anyhow, Result};
+use regex::Regex;
+use std::env;
+use std::fs;
+use std::path::{Path, PathBuf};
+use toml::Table;
+
+use crate::cpi::CONFIG_FILE;
+
+// Most patterns a request or .safex.toml may list
+pub const MAX_EXCLUDE_PATTERNS: usize = 64;
+
+// Paths an analysis leaves out: `exclude_paths` from the request plus `[analysis] exclude_paths`
+// from the repository's .safex.toml, as globs relative to the repository root. `*` and `?` stay
+// within one path segment and `**` spans any number of them. A pattern with no `/` except a
+// trailing one matches at any depth, as in .gitignore. A matched directory takes everything
+// under it, so `migrations/` and `**/tests/**` both drop the whole tree.
+//
+// Whoever can push to the repository could exclude their own code from the gate, so
+// .safex.toml is only followed when SAFEX_REPO_EXCLUSIONS=true.
+#[derive(Default)]
+pub struct PathExclusions {
+    root: PathBuf,
+    patterns: Vec<String>,
+    matchers: Vec<Regex>,
+    // .safex.toml patterns left out because the repository isn't trusted to exclude paths
+    ignored: Vec<String>,
+    // Why .safex.toml's exclude_paths couldn't be read; the analysis goes ahead without them
+    problem: Option<String>,
+}
+
+impl PathExclusions {
+    // Only the request's own patterns can fail the load; they were validated with it
+    pub fn load(repo_path: &Path, requested: &[String]) -> Result<Self> {
+        let trusted = env::var("SAFEX_REPO_EXCLUSIONS").is_ok_and(|value| value == "true" || value == "1");
+        let (configured, problem) = match configured(repo_path) {
+            Ok(configured) => (configured, None),
+            Err(e) => (Vec::new(), Some(e.to_string())),
+        };
+        let (configured, ignored) = if trusted { (configured, Vec::new()) } else { (Vec::new(), configured) };
+
+        let mut exclusions = Self { root: repo_path.to_path_buf(), ignored, problem, ..Self::default() };
+        for pattern in requested {
+            exclusions.add(pattern)?;
+        }
+        for pattern in configured {
+            if let Err(e) = exclusions.add(&pattern) {
+                exclusions.problem = Some(format!("{} in {}", e, CONFIG_FILE));
+            }
+        }
+        if !exclusions.patterns.is_empty() {
+            println!("Excluding from analysis: {}", exclusions.patterns.join(", "));
+        }
+        Ok(exclusions)
+    }
+
+    fn add(&mut self, pattern: &str) -> Result<()> {
+        if !self.patterns.iter().any(|existing| existing == pattern) {
+            self.matchers.push(glob_regex(pattern)?);
+            self.patterns.push(pattern.to_string());
+        }
+        Ok(())
+    }
+
+    // The patterns the analysis applies
+    pub fn patterns(&self) -> &[String] {
+        &self.patterns
+    }
+
+    pub fn ignored(&self) -> &[String] {
+        &self.ignored
+    }
+
+    pub fn problem(&self) -> Option<&str> {
+        self.problem.as_deref()
+    }
+
+    // `path` is either under the repository or relative to its root, as findings' files are
+    pub fn excludes(&self, path: &Path) -> bool {
+        if self.matchers.is_empty() {
+            return false;
+        }
+        let relative = path.strip_prefix(&self.root).unwrap_or(path).to_string_lossy().replace('\\', "/");
+        let relative = relative.trim_start_matches("./");
+        self.matchers.iter().any(|matcher| matcher.is_match(relative))
+    }
+}
+
+// `[analysis] exclude_paths` in .safex.toml
+fn configured(repo_path: &Path) -> Result<Vec<String>> {
+    let Ok(content) = fs::read_to_string(repo_path.join(CONFIG_FILE)) else {
+        return Ok(Vec::new());
+    };
+    let config: Table = content.parse().map_err(|e| anyhow!("Failed to parse {}: {}", CONFIG_FILE, e))?;
+    let Some(excluded) = config.get("analysis").and_then(|analysis| analysis.get("exclude_paths")) else {
+        return Ok(Vec::new());
+    };
+    let excluded = excluded.as_array().ok_or_else(|| anyhow!("analysis.exclude_paths in {} must be a list of globs", CONFIG_FILE))?;
+    if excluded.len() > MAX_EXCLUDE_PATTERNS {
+        return Err(anyhow!("analysis.exclude_paths in {} lists more than {} globs", CONFIG_FILE, MAX_EXCLUDE_PATTERNS));
+    }
+    excluded.iter()
+        .map(|pattern| pattern.as_str().map(|pattern| pattern.trim().to_string())
+            .ok_or_else(|| anyhow!("analysis.exclude_paths in {} must be a list of globs", CONFIG_FILE)))
+        .collect()
+}
+
+// The regex a glob translates to, matching the path and everything under it
+pub fn glob_regex(pattern: &str) -> Result<Regex> {
+    let glob = pattern.trim().trim_start_matches("./").trim_end_matches('/');
+    let glob = glob.strip_suffix("/**").unwrap_or(glob);
+    let anchored = glob.contains('/');
+    let glob = glob.trim_start_matches('/');
+    if glob.is_empty() {
+        return Err(anyhow!("Empty exclude pattern: {:?}", pattern));
+    }
+
+    let mut regex = String::from("^");
+    if !anchored {
+        regex.push_str("(?:.*/)?");
+    }
+    let mut chars = glob.chars().peekable();
+    while let Some(c) = chars.next() {
+        match c {
+            '*' if chars.peek() == Some(&'*') => {
+                chars.next();
+                if chars.next_if_eq(&'/').is_some() {
+                    regex.push_str("(?:.*/)?");
+                } else {
+                    regex.push_str(".*");
+                }
+            },
+            '*' => regex.push_str("[^/]*"),
+            '?' => regex.push_str("[^/]"),
+            '[' | ']' | '{' | '}' => return Err(anyhow!("Unsupported exclude pattern {:?}: only *, ** and ? are wildcards", pattern)),
+            c => regex.push_str(&regex::escape(c.encode_utf8(&mut [0; 4]))),
+        }
+    }
+    regex.push_str("(?:/.*)?$");
+    Regex::new(&regex).map_err(|e| anyhow!("Invalid exclude pattern {:?}: {}", pattern, e))
+}
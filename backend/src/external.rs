@@ -0,0 +1,235 @@
+use anyhow::{anyhow, Context, Result};
+use serde::Deserialize;
+use serde_json::Value;
+use std::collections::HashMap;
+use std::env;
+use std::fs::{self, File};
+use std::path::Path;
+use std::process::{Command, Stdio};
+use std::time::{Duration, Instant};
+
+use crate::models::{AnalyzerCapability, BugSeverity, CodeBug, ProjectType};
+
+// A third-party analyzer whose findings are merged into the analysis result
+pub trait ExternalAnalyzer: Send + Sync {
+    fn name(&self) -> &str;
+    fn applies_to(&self, project_type: ProjectType) -> bool;
+    fn analyze(&self, repo_path: &Path) -> Result<Vec<CodeBug>>;
+    fn capability(&self) -> AnalyzerCapability;
+}
+
+#[derive(Debug, Deserialize)]
+struct AnalyzersFile {
+    #[serde(default)]
+    analyzer: Vec<ProcessAnalyzerConfig>,
+}
+
+#[derive(Debug, Deserialize)]
+struct ProcessAnalyzerConfig {
+    name: String,
+    // argv; `{repo_path}` and `{output_file}` are substituted. Without `{output_file}`
+    // the report is read from stdout.
+    command: Vec<String>,
+    #[serde(default = "default_timeout_secs")]
+    timeout_secs: u64,
+    // Empty means every project type
+    #[serde(default)]
+    project_types: Vec<ProjectType>,
+    mapping: OutputMapping,
+}
+
+fn default_timeout_secs() -> u64 {
+    600
+}
+
+// Where to find each CodeBug field in the tool's JSON report, as JSON pointers.
+// `findings` points at the array of findings; the others are relative to one finding.
+#[derive(Debug, Deserialize)]
+struct OutputMapping {
+    #[serde(default)]
+    findings: String,
+    message: String,
+    file: Option<String>,
+    line: Option<String>,
+    severity: Option<String>,
+    fix: Option<String>,
+    rule_id: Option<String>,
+    // Tool severity (case-insensitive) -> low/medium/high, for tools with their own scale
+    #[serde(default)]
+    severity_map: HashMap<String, BugSeverity>,
+    #[serde(default)]
+    default_severity: BugSeverity,
+}
+
+// Runs an analyzer binary installed on the host and maps its JSON report to findings
+pub struct ProcessAnalyzer {
+    config: ProcessAnalyzerConfig,
+}
+
+impl ExternalAnalyzer for ProcessAnalyzer {
+    fn name(&self) -> &str {
+        &self.config.name
+    }
+
+    fn applies_to(&self, project_type: ProjectType) -> bool {
+        self.config.project_types.is_empty() || self.config.project_types.contains(&project_type)
+    }
+
+    // Available when the command's program exists; the tool isn't run to find out
+    fn capability(&self) -> AnalyzerCapability {
+        AnalyzerCapability {
+            name: self.config.name.clone(),
+            external: true,
+            available: installed(&self.config.command[0]),
+            version: None,
+            project_types: self.config.project_types.clone(),
+        }
+    }
+
+    #[tracing::instrument(name = "process.external_analyzer", skip(self, repo_path), fields(analyzer = %self.config.name, exit_code))]
+    fn analyze(&self, repo_path: &Path) -> Result<Vec<CodeBug>> {
+        let work_dir = tempfile::TempDir::new()?;
+        let output_file = work_dir.path().join("report.json");
+        let stdout_file = work_dir.path().join("stdout");
+        let stderr_file = work_dir.path().join("stderr");
+        let substitute = |arg: &String| arg
+            .replace("{repo_path}", &repo_path.display().to_string())
+            .replace("{output_file}", &output_file.display().to_string());
+        let args: Vec<String> = self.config.command.iter().map(substitute).collect();
+        let (program, args) = args.split_first().ok_or_else(|| anyhow!("Empty command"))?;
+
+        // Output goes to files so a large report can't fill a pipe and stall the tool
+        let mut child = Command::new(program)
+            .args(args)
+            .current_dir(repo_path)
+            .stdin(Stdio::null())
+            .stdout(File::create(&stdout_file)?)
+            .stderr(File::create(&stderr_file)?)
+            .spawn()
+            .with_context(|| format!("Failed to start {}", program))?;
+
+        let deadline = Instant::now() + Duration::from_secs(self.config.timeout_secs);
+        let status = loop {
+            if let Some(status) = child.try_wait()? {
+                break status;
+            }
+            if Instant::now() > deadline {
+                let _ = child.kill();
+                let _ = child.wait();
+                return Err(anyhow!("Timed out after {}s", self.config.timeout_secs));
+            }
+            std::thread::sleep(Duration::from_millis(200));
+        };
+        tracing::Span::current().record("exit_code", status.code().unwrap_or(-1));
+
+        // Most tools exit non-zero when they find something, so only an unreadable report is an error
+        let uses_output_file = self.config.command.iter().any(|arg| arg.contains("{output_file}"));
+        let report = fs::read_to_string(if uses_output_file { &output_file } else { &stdout_file }).unwrap_or_default();
+        let report: Value = serde_json::from_str(&report).map_err(|e| {
+            let stderr = fs::read_to_string(&stderr_file).unwrap_or_default();
+            anyhow!("Exited with {} without a JSON report ({}): {}", status, e, stderr.trim())
+        })?;
+        self.map_findings(&report)
+    }
+}
+
+impl ProcessAnalyzer {
+    fn map_findings(&self, report: &Value) -> Result<Vec<CodeBug>> {
+        let mapping = &self.config.mapping;
+        let findings = report.pointer(&mapping.findings)
+            .and_then(|f| f.as_array())
+            .ok_or_else(|| anyhow!("No findings array at `{}`", mapping.findings))?;
+
+        let field = |finding: &Value, pointer: &Option<String>| -> Option<String> {
+            match finding.pointer(pointer.as_deref()?)? {
+                Value::String(s) => Some(s.clone()),
+                Value::Null => None,
+                other => Some(other.to_string()),
+            }
+        };
+
+        Ok(findings.iter().map(|finding| {
+            let message = field(finding, &Some(mapping.message.clone())).unwrap_or_else(|| "Unknown issue".to_string());
+            let severity = field(finding, &mapping.severity)
+                .map(|s| s.to_lowercase())
+                .and_then(|s| mapping.severity_map.iter()
+                    .find(|(key, _)| key.to_lowercase() == s)
+                    .map(|(_, severity)| *severity)
+                    .or_else(|| BugSeverity::parse(&s)))
+                .unwrap_or(mapping.default_severity);
+            CodeBug {
+                bug: format!("[{}] {}", self.config.name, message),
+                line: field(finding, &mapping.line).and_then(|l| l.parse().ok()).unwrap_or(0),
+                file: field(finding, &mapping.file),
+                severity,
+                fix: field(finding, &mapping.fix).unwrap_or_else(|| format!("See the {} documentation for this finding", self.config.name)),
+                rule_id: Some(match field(finding, &mapping.rule_id) {
+                    Some(rule) => format!("{}:{}", self.config.name, rule),
+                    None => self.config.name.clone(),
+                }),
+                ..Default::default()
+            }
+        }).collect())
+    }
+}
+
+// The analyzers configured for this deployment
+pub struct ExternalAnalyzers {
+    analyzers: Vec<Box<dyn ExternalAnalyzer>>,
+}
+
+impl ExternalAnalyzers {
+    // SAFEX_EXTERNAL_ANALYZERS is the path of a TOML file with one [[analyzer]] table per tool
+    pub fn from_env() -> Result<Self> {
+        let Ok(path) = env::var("SAFEX_EXTERNAL_ANALYZERS") else {
+            return Ok(Self { analyzers: Vec::new() });
+        };
+        let config: AnalyzersFile = toml::from_str(&fs::read_to_string(&path)?)
+            .map_err(|e| anyhow!("Invalid external analyzer config {}: {}", path, e))?;
+
+        let mut analyzers: Vec<Box<dyn ExternalAnalyzer>> = Vec::new();
+        for analyzer in config.analyzer {
+            if analyzer.command.is_empty() {
+                return Err(anyhow!("External analyzer {} has an empty command", analyzer.name));
+            }
+            println!("Loaded external analyzer {} ({})", analyzer.name, analyzer.command[0]);
+            analyzers.push(Box::new(ProcessAnalyzer { config: analyzer }));
+        }
+        Ok(Self { analyzers })
+    }
+
+    pub fn capabilities(&self) -> Vec<AnalyzerCapability> {
+        self.analyzers.iter().map(|analyzer| analyzer.capability()).collect()
+    }
+
+    // Findings from every applicable analyzer; a failing tool is reported as a finding
+    // instead of failing the whole analysis, like clippy
+    pub fn run(&self, repo_path: &Path, project_type: ProjectType) -> Vec<CodeBug> {
+        let mut bugs = Vec::new();
+        for analyzer in self.analyzers.iter().filter(|a| a.applies_to(project_type)) {
+            println!("Running external analyzer {}...", analyzer.name());
+            match analyzer.analyze(repo_path) {
+                Ok(found) => bugs.extend(found),
+                Err(e) => {
+                    println!("Warning: External analyzer {} failed: {}", analyzer.name(), e);
+                    bugs.push(CodeBug {
+                        bug: format!("Failed to run external analyzer {}", analyzer.name()),
+                        line: 0,
+                        severity: BugSeverity::Low,
+                        fix: format!("Check that {} is installed and configured on the analysis host: {}", analyzer.name(), e),
+                        ..Default::default()
+                    });
+                }
+            }
+        }
+        bugs
+    }
+}
+
+// Whether `program` names a file, directly or through PATH as Command would find it
+fn installed(program: &str) -> bool {
+    if program.contains('/') {
+        return Path::new(program).is_file();
+    }
+    env::var_os("PATH").is_some_and(|path| env::split_paths(&path).any(|dir| dir.join(program).is_file()))
+}
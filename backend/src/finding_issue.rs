@@ -0,0 +1,126 @@
+use actix_web::http::StatusCode;
+
+use crate::github::GitHubClient;
+use crate::models::CodeBug;
+use crate::repo_url::RepoUrl;
+use crate::rules::rule_catalog;
+
+// Lines shown on either side of the finding's line
+const SNIPPET_CONTEXT: u32 = 3;
+const MAX_TITLE_CHARS: usize = 120;
+
+// An issue opened on the analyzed repository for one finding
+pub struct FindingIssue {
+    pub number: u64,
+    pub url: String,
+}
+
+// Open an issue describing the finding, with the code it points at as of the analyzed commit
+// and how to fix it
+#[tracing::instrument(name = "finding_issue.open", skip(github, finding, labels), fields(repo = %repo_url.canonical(), fingerprint = %finding.fingerprint))]
+pub async fn open_finding_issue(
+    github: &GitHubClient,
+    repo_url: &RepoUrl,
+    run_id: &str,
+    commit_sha: Option<&str>,
+    finding: &CodeBug,
+    labels: &[String],
+) -> Result<FindingIssue, (StatusCode, String)> {
+    let upstream_error = |e: anyhow::Error| (StatusCode::BAD_GATEWAY, e.to_string());
+    if !github.has_token() {
+        return Err((StatusCode::SERVICE_UNAVAILABLE, "Opening issues requires a GitHub token".to_string()));
+    }
+    let (owner, repo) = match repo_url.owner_repo() {
+        Some(owner_repo) if repo_url.is_github() => owner_repo,
+        _ => return Err((StatusCode::BAD_REQUEST, "Issues can only be opened on GitHub repositories".to_string())),
+    };
+    let upstream = github.get_repo(owner, repo).await.map_err(upstream_error)?;
+    let git_ref = commit_sha.map(str::to_string)
+        .or_else(|| upstream.default_branch.clone())
+        .unwrap_or_else(|| "main".to_string());
+
+    let rule = finding.rule_id.as_deref().and_then(|id| rule_catalog().into_iter().find(|rule| rule.id == id));
+    let mut body = format!("**Severity:** {}\n", finding.severity.as_str());
+    if let Some(rule_id) = &finding.rule_id {
+        match &rule {
+            Some(rule) => body.push_str(&format!("**Rule:** `{}` ({})\n", rule_id, rule.title)),
+            None => body.push_str(&format!("**Rule:** `{}`\n", rule_id)),
+        }
+    }
+    if let Some(taxonomy) = finding.taxonomy {
+        body.push_str(&format!("**Class:** {}\n", taxonomy.title()));
+    }
+
+    // The snippet is best-effort: the file may be binary, too large for the contents API or gone
+    let snippet = match &finding.file {
+        Some(file) if finding.line > 0 => match github.get_file(&upstream.full_name, file, &git_ref).await {
+            Ok((content, _)) => Some(snippet(file, &content, finding.line)),
+            Err(e) => {
+                println!("Warning: Failed to fetch {} for the issue snippet: {}", file, e);
+                None
+            }
+        },
+        _ => None,
+    };
+    if let Some(file) = &finding.file {
+        let start = finding.line.saturating_sub(SNIPPET_CONTEXT).max(1);
+        body.push_str(&format!(
+            "**Location:** [`{}:{}`](https://github.com/{}/blob/{}/{}#L{}-L{})\n",
+            file, finding.line, upstream.full_name, git_ref, file, start, finding.line + SNIPPET_CONTEXT,
+        ));
+    }
+
+    body.push_str(&format!("\n### Description\n\n{}\n", finding.bug));
+    if let Some(rule) = &rule {
+        body.push_str(&format!("\n{}\n", rule.description));
+    }
+    if let Some(snippet) = snippet {
+        body.push_str(&format!("\n### Code\n\n{}", snippet));
+    }
+    body.push_str(&format!("\n### Remediation\n\n{}\n", finding.fix));
+    if let Some(rule) = &rule {
+        if !rule.fixed_example.is_empty() {
+            let extension = finding.file.as_deref().and_then(|file| file.rsplit_once('.')).map(|(_, extension)| extension).unwrap_or_default();
+            body.push_str(&format!("\nFor example:\n\n```{}\n{}\n```\n", fence_language(extension), rule.fixed_example));
+        }
+        let references: Vec<String> = rule.references.iter().map(|r| format!("- [{}]({})", r.title, r.url)).collect();
+        if !references.is_empty() {
+            body.push_str(&format!("\n**References:**\n{}\n", references.join("\n")));
+        }
+    }
+    body.push_str(&format!("\n---\nReported by Safex. Analysis run `{}`, finding `{}`.\n", run_id, finding.fingerprint));
+
+    let summary = finding.bug.lines().next().unwrap_or_default();
+    let mut title = format!("[{}] {}", finding.severity.as_str(), summary);
+    if title.chars().count() > MAX_TITLE_CHARS {
+        title = title.chars().take(MAX_TITLE_CHARS - 3).collect::<String>() + "...";
+    }
+    let (number, url) = github.create_issue(&upstream.full_name, &title, &body, labels).await.map_err(upstream_error)?;
+    println!("Opened issue {} for finding {}", url, finding.fingerprint);
+
+    Ok(FindingIssue { number, url })
+}
+
+// A fenced block of the lines around `line`, numbered, with the finding's line marked
+fn snippet(file: &str, content: &str, line: u32) -> String {
+    let start = line.saturating_sub(SNIPPET_CONTEXT).max(1);
+    let end = line + SNIPPET_CONTEXT;
+    let width = end.to_string().len();
+    let lines: Vec<String> = content.lines().enumerate()
+        .map(|(i, text)| (i as u32 + 1, text))
+        .filter(|(number, _)| (start..=end).contains(number))
+        .map(|(number, text)| format!("{} {:>width$} | {}", if number == line { ">" } else { " " }, number, text, width = width))
+        .collect();
+    let extension = file.rsplit_once('.').map(|(_, extension)| extension).unwrap_or_default();
+    format!("```{}\n{}\n```\n", fence_language(extension), lines.join("\n"))
+}
+
+fn fence_language(extension: &str) -> &'static str {
+    match extension {
+        "rs" => "rust",
+        "ts" | "tsx" => "typescript",
+        "js" | "jsx" => "javascript",
+        "toml" => "toml",
+        _ => "",
+    }
+}
@@ -0,0 +1,80 @@
+use actix_web::http::StatusCode;
+
+use crate::autofix::{apply_edits, unified_diff};
+use crate::github::GitHubClient;
+use crate::models::{CodeBug, TextEdit};
+use crate::repo_url::RepoUrl;
+
+// A pull request opened for one finding's machine-applicable fix
+pub struct FixPullRequest {
+    pub url: String,
+    pub branch: String,
+    pub head_repo: String,
+}
+
+// Apply the fix on a new branch and open a pull request against the default branch.
+// The branch goes to the repository itself when the token can push, otherwise to a fork.
+#[tracing::instrument(name = "fix_pr.open", skip(github, finding, edits), fields(repo = %repo_url.canonical(), fingerprint = %finding.fingerprint))]
+pub async fn open_fix_pull_request(
+    github: &GitHubClient,
+    repo_url: &RepoUrl,
+    run_id: &str,
+    finding: &CodeBug,
+    file: &str,
+    edits: &[TextEdit],
+) -> Result<FixPullRequest, (StatusCode, String)> {
+    let upstream_error = |e: anyhow::Error| (StatusCode::BAD_GATEWAY, e.to_string());
+    if !github.has_token() {
+        return Err((StatusCode::SERVICE_UNAVAILABLE, "Opening pull requests requires GITHUB_TOKEN to be configured".to_string()));
+    }
+    let (owner, repo) = match repo_url.owner_repo() {
+        Some(owner_repo) if repo_url.is_github() => owner_repo,
+        _ => return Err((StatusCode::BAD_REQUEST, "Fix pull requests are only supported for GitHub repositories".to_string())),
+    };
+
+    let upstream = github.get_repo(owner, repo).await.map_err(upstream_error)?;
+    let base = upstream.default_branch.clone().unwrap_or_else(|| "main".to_string());
+    let base_sha = github.branch_head(&upstream.full_name, &base).await.map_err(upstream_error)?;
+
+    // Patch the file as it is on the base branch, not the clone the run analyzed
+    let (content, blob_sha) = github.get_file(&upstream.full_name, file, &base_sha).await.map_err(upstream_error)?;
+    let patched = apply_edits(&content, edits).map_err(|e| (StatusCode::CONFLICT, e.to_string()))?;
+
+    let can_push = upstream.permissions.as_ref().is_some_and(|p| p.push);
+    let head_repo = if can_push {
+        upstream.full_name.clone()
+    } else {
+        println!("No push access to {}, forking", upstream.full_name);
+        github.create_fork(owner, repo, &base).await.map_err(upstream_error)?
+    };
+
+    let branch = format!("safex/fix-{}", finding.fingerprint);
+    github.create_branch(&head_repo, &branch, &base_sha).await
+        .map_err(|e| (StatusCode::CONFLICT, format!("Failed to create branch {} in {}: {}", branch, head_repo, e)))?;
+
+    let rule = finding.rule_id.as_deref().unwrap_or("safex");
+    let commit_message = format!("Fix {} in {}\n\n{}", rule, file, finding.bug);
+    github.update_file(&head_repo, file, &branch, &commit_message, &patched, &blob_sha).await.map_err(upstream_error)?;
+
+    let head = if can_push {
+        branch.clone()
+    } else {
+        format!("{}:{}", head_repo.split('/').next().unwrap_or_default(), branch)
+    };
+    let title = format!("Fix {} in {}", rule, file);
+    let body = format!(
+        "Automated fix for a finding reported by Safex.\n\n\
+        **Finding:** {}\n\
+        **Location:** `{}:{}`\n\
+        **Rule:** `{}`\n\
+        **Severity:** {}\n\n\
+        ```diff\n{}```\n\n\
+        Analysis run `{}`, finding `{}`.",
+        finding.bug, file, finding.line, rule, finding.severity.as_str(),
+        unified_diff(file, &content, &patched), run_id, finding.fingerprint,
+    );
+    let url = github.create_pull_request(&upstream.full_name, &title, &head, &base, &body).await.map_err(upstream_error)?;
+    println!("Opened fix pull request {}", url);
+
+    Ok(FixPullRequest { url, branch, head_repo })
+}
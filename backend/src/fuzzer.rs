@@ -1,46 +1,392 @@
 use std::fs::{self, File};
-use std::io::Write;
+use std::io::{Read, Write};
 use std::path::{Path, PathBuf};
-use std::process::Command;
+use std::process::{Command, Output, Stdio};
+use std::sync::mpsc::{self, Receiver};
+use std::sync::{Arc, OnceLock};
+use std::time::{Duration, Instant};
+use std::env;
+use sha2::{Digest, Sha256};
 use anyhow::{anyhow, Result};
 use serde::{Deserialize, Serialize};
+use solana_sdk::account::Account;
+use solana_sdk::pubkey::Pubkey;
+
+use crate::db::now_unix;
+use crate::models::{BugSeverity, CompilerDiagnostic, FuzzCheckpoint, FuzzEngine, FuzzEngineCapability, FuzzFinding, FuzzFindingKind, FuzzStrategy, FuzzSummary, NetworkReport};
+use crate::github::GitHubClient;
+use crate::instructions::InstructionLayout;
+use crate::rent::{parse_array, split_generic};
+use crate::toolchain::ToolchainSelection;
+use crate::sandbox::Sandbox;
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct FuzzingResult {
     pub success: bool,
     pub timed_out: bool,
     pub errors: Vec<String>,
+    pub findings: Vec<FuzzFinding>,
+    // Errors from building the target program; when present nothing was fuzzed
+    pub build_diagnostics: Vec<CompilerDiagnostic>,
     pub execution_time_ms: u64,
+    // Whether the harness binary came from the build cache
+    pub cache_hit: bool,
+    pub build_ms: u64,
+    pub run_ms: u64,
+    // The harness was the one last built for the instruction, from an earlier commit, because
+    // building this commit's wouldn't fit the budget
+    pub stale_harness: bool,
+    // Building the harness took its whole share of the budget, so nothing was fuzzed
+    pub build_timed_out: bool,
+    // Passes of the harness run to the end; continuous runs make as many as the budget allows
+    pub rounds: u32,
+    // Files in the harness's crashes/ directory holding the failing inputs, whichever engine
+    // found them
+    pub crashes: Vec<String>,
+    // Failing cases replayed from earlier runs, when the corpus is reused
+    pub corpus_cases: Option<u32>,
+    pub toolchain: ToolchainSelection,
+}
+
+// Bump when the harness crate's manifest changes; the generated source is hashed into the key
+const HARNESS_TEMPLATE_VERSION: u32 = 3;
+
+// How long a run may take, building the harness included, unless the request says
+pub const DEFAULT_FUZZ_TIMEOUT_SECS: u64 = 120;
+
+// Defaults for SAFEX_FUZZ_MAX_TIMEOUT_SECS and SAFEX_FUZZ_JOB_MAX_TIMEOUT_SECS
+const MAX_FUZZ_TIMEOUT_SECS: u64 = 300;
+const MAX_FUZZ_JOB_TIMEOUT_SECS: u64 = 4 * 60 * 60;
+
+// The honggfuzz harness's binary target, which names its workspace under hfuzz_workspace/
+const HFUZZ_TARGET: &str = "anchor_fuzz_harness";
+
+// How long honggfuzz gets past its --run_time to write its report and exit before it's killed
+const HFUZZ_GRACE: Duration = Duration::from_secs(30);
+
+// `cargo hfuzz version` output, once probed
+static HONGGFUZZ_VERSION: OnceLock<Option<String>> = OnceLock::new();
+
+// Least time between the checkpoints of a continuous run
+const CHECKPOINT_INTERVAL: Duration = Duration::from_secs(30);
+
+// The part of the budget kept for running the harness however long building it takes
+const MIN_RUN_PERCENT: u32 = 25;
+
+// How long to wait for a killed process's pipes to close
+const PIPE_GRACE: Duration = Duration::from_secs(5);
+
+// How the account ordering harness reports an arrangement the program accepted
+const ACCEPTED_ORDERING: &str = "accepted malformed account ordering";
+
+// The compute units an instruction gets unless its transaction asks for more
+pub const DEFAULT_COMPUTE_UNIT_BUDGET: u64 = 200_000;
+
+// How the compute units harness reports inputs past the budget, and past NEAR_BUDGET_PERCENT of it
+const COMPUTE_BUDGET_EXCEEDED: &str = "compute budget exceeded";
+const COMPUTE_BUDGET_NEARLY_EXHAUSTED: &str = "compute budget nearly exhausted";
+const NEAR_BUDGET_PERCENT: u64 = 80;
+
+// How the harnesses report a transaction that left its accounts with more or fewer lamports than
+// they started with, less the fee
+const LAMPORTS_MINTED: &str = "lamports minted";
+const LAMPORTS_DESTROYED: &str = "lamports destroyed";
+
+// Most accounts a run may seed, and the most data each may hold; the copies are compiled into
+// the harness
+pub const MAX_SEED_ACCOUNTS: usize = 16;
+pub const MAX_SEED_ACCOUNT_BYTES: usize = 64 * 1024;
+
+// SPL programs the harnesses add to the program test when SAFEX_SPL_PROGRAMS_DIR has their
+// <name>.so, in place of the copies solana-program-test bundles (when it bundles them): file
+// stem and program id
+const SPL_PROGRAMS: [(&str, &str); 3] = [
+    ("spl_token", TOKEN_PROGRAM_ID),
+    ("spl_token_2022", TOKEN_2022_PROGRAM_ID),
+    ("spl_associated_token_account", ASSOCIATED_TOKEN_PROGRAM_ID),
+];
+const TOKEN_PROGRAM_ID: &str = "TokenkegQfeZyiNwAJbNbGKPFXCWuBvf9Ss623VQ5DA";
+const TOKEN_2022_PROGRAM_ID: &str = "TokenzQdBNbLqP5VEhdkAS6EPFLC1PHnBqCXEpPxuEb";
+const ASSOCIATED_TOKEN_PROGRAM_ID: &str = "ATokenGPvbdGVxr1b9hvZbsxRZAMmQ4fjjHPa5ic4nKz";
+const SYSTEM_PROGRAM_ID: &str = "11111111111111111111111111111111";
+
+// An account copied from a cluster to stand in for the zeroed one at `index` in the
+// instruction's accounts
+pub struct SeedAccount {
+    pub index: usize,
+    pub address: Pubkey,
+    pub account: Account,
+}
+
+// What a fuzz run's harness exercises
+pub enum Harness {
+    Inputs,
+    // Arrangements of the instruction's accounts other than the expected one
+    AccountOrdering(InstructionLayout),
+    // Arguments searched for the most compute units used, against `budget`
+    ComputeUnits { layout: InstructionLayout, budget: u64 },
+    // The instruction's data, mutated by honggfuzz, with its accounts in order
+    Honggfuzz(InstructionLayout),
+}
+
+impl Harness {
+    pub fn strategy(&self) -> FuzzStrategy {
+        match self {
+            Harness::Inputs => FuzzStrategy::Inputs,
+            Harness::AccountOrdering(_) => FuzzStrategy::AccountOrdering,
+            Harness::ComputeUnits { .. } => FuzzStrategy::ComputeUnits,
+            Harness::Honggfuzz(_) => FuzzStrategy::Inputs,
+        }
+    }
+
+    pub fn engine(&self) -> FuzzEngine {
+        match self {
+            Harness::Honggfuzz(_) => FuzzEngine::Honggfuzz,
+            _ => FuzzEngine::Proptest,
+        }
+    }
+
+    pub fn test_file_name(&self, instruction_name: &str) -> String {
+        match self {
+            Harness::Inputs => format!("{}_fuzz_test.rs", instruction_name),
+            Harness::AccountOrdering(_) => format!("{}_account_ordering_fuzz_test.rs", instruction_name),
+            Harness::ComputeUnits { .. } => format!("{}_compute_units_fuzz_test.rs", instruction_name),
+            Harness::Honggfuzz(_) => format!("{}_honggfuzz.rs", instruction_name),
+        }
+    }
+
+    // The instruction's accounts and arguments, for the harnesses that call it with them
+    pub fn layout(&self) -> Option<&InstructionLayout> {
+        match self {
+            Harness::Inputs => None,
+            Harness::AccountOrdering(layout) | Harness::ComputeUnits { layout, .. } | Harness::Honggfuzz(layout) => Some(layout),
+        }
+    }
+
+    // What besides the strategy the generated harness depends on
+    fn cache_tag(&self) -> String {
+        match self {
+            Harness::ComputeUnits { budget, .. } => format!("{}:{}", self.strategy().as_str(), budget),
+            Harness::Honggfuzz(_) => format!("{}:{}", self.strategy().as_str(), self.engine().as_str()),
+            _ => self.strategy().as_str().to_string(),
+        }
+    }
+}
+
+// A run's time limit, split between building the harness and running it. `expected_build`
+// is how long earlier builds for the repository took, when there were any.
+#[derive(Debug, Clone, Copy)]
+pub struct FuzzBudget {
+    pub total: Duration,
+    pub expected_build: Option<Duration>,
 }
 
+impl Default for FuzzBudget {
+    fn default() -> Self {
+        Self { total: Duration::from_secs(DEFAULT_FUZZ_TIMEOUT_SECS), expected_build: None }
+    }
+}
+
+impl FuzzBudget {
+    fn run_reserve(&self) -> Duration {
+        (self.total * MIN_RUN_PERCENT / 100).max(Duration::from_secs(1)).min(self.total)
+    }
+
+    // How long the build may take before it's stopped
+    pub fn build_limit(&self) -> Duration {
+        self.total - self.run_reserve()
+    }
+}
+
+// Receives a continuous run's results so far
+pub type FuzzCheckpointSink = Arc<dyn Fn(&FuzzCheckpoint) + Send + Sync>;
+
 pub struct Fuzzer {
     temp_dir: PathBuf,
+    sandbox: Sandbox,
+    // Where this repository's corpus is kept between runs, when it's reused
+    corpus_dir: Option<PathBuf>,
+    seeds: Vec<SeedAccount>,
+    budget: FuzzBudget,
+    continuous: bool,
+    checkpoints: Option<FuzzCheckpointSink>,
+    // The last checkpoint of an earlier attempt at the same continuous run, carried forward
+    resumed: Option<FuzzCheckpoint>,
+    // The tenant and repository the run is for; a stale harness is only ever one built for both
+    owner: Option<String>,
 }
 
 impl Fuzzer {
-    pub fn new(temp_dir: PathBuf) -> Self {
-        Self { temp_dir }
+    pub fn new(temp_dir: PathBuf, sandbox: Sandbox) -> Self {
+        Self { temp_dir, sandbox, corpus_dir: None, seeds: Vec::new(), budget: FuzzBudget::default(), continuous: false, checkpoints: None, resumed: None, owner: None }
+    }
+    
+    pub fn with_budget(mut self, budget: FuzzBudget) -> Self {
+        self.budget = budget;
+        self
+    }
+    
+    // Run the harness again with fresh cases until the budget is spent, rather than once.
+    // The results so far go to `checkpoints` every CHECKPOINT_INTERVAL or so. An attempt
+    // resuming a run cut short starts from its last checkpoint's rounds and errors.
+    pub fn continuous(mut self, checkpoints: Option<FuzzCheckpointSink>, resumed: Option<FuzzCheckpoint>) -> Self {
+        self.continuous = true;
+        self.checkpoints = checkpoints;
+        self.resumed = resumed;
+        self
+    }
+    
+    // Let the run fall back to the harness last built for the instruction in this tenant's
+    // copy of the repository when there's no time to build one. Without an owner it never does.
+    pub fn for_repository(mut self, tenant: &str, repo_url: &str) -> Self {
+        self.owner = Some(format!("{}\0{}", tenant, repo_url));
+        self
+    }
+    
+    // Start the harness's accounts from these copies rather than zeroed; the harnesses that
+    // call the instruction with its accounts use them
+    pub fn with_seed_accounts(mut self, seeds: Vec<SeedAccount>) -> Self {
+        self.seeds = seeds;
+        self
+    }
+    
+    // Replay the failing cases earlier runs on the repository found, and keep new ones.
+    // The corpus is proptest's failure persistence file, one per instruction.
+    pub fn with_corpus(mut self, repo_url: &str) -> Self {
+        self.corpus_dir = Some(fuzz_corpus_dir().join(&format!("{:x}", Sha256::digest(repo_url.as_bytes()))[..16]));
+        self
+    }
+    
+    pub fn network_report(&self) -> NetworkReport {
+        self.sandbox.report()
     }
 
-    pub fn generate_and_run_fuzz_tests(&self, repo_path: &Path, instruction_name: &str) -> Result<FuzzingResult> {
+    #[tracing::instrument(name = "fuzz", skip(self, harness), fields(strategy = harness.strategy().as_str()))]
+    pub fn generate_and_run_fuzz_tests(&self, repo_path: &Path, instruction_name: &str, harness: &Harness) -> Result<FuzzingResult> {
+        // Match the harness dependencies and toolchain to the target repo
+        let toolchain = ToolchainSelection::detect(repo_path)?;
+        println!("Using anchor-lang {}, solana {}, toolchain {:?}", toolchain.anchor_version, toolchain.solana_version, toolchain.rust_toolchain);
+        
+        // Don't spend the fuzz budget on a program that doesn't compile. The check counts
+        // against the build's share of the budget.
+        let start_time = Instant::now();
+        let (build_diagnostics, preflight_timed_out) = self.preflight_check(repo_path, start_time + self.budget.build_limit())?;
+        if !build_diagnostics.is_empty() || preflight_timed_out {
+            let elapsed_ms = start_time.elapsed().as_millis() as u64;
+            return Ok(FuzzingResult {
+                success: false,
+                timed_out: false,
+                errors: Vec::new(),
+                findings: Vec::new(),
+                build_diagnostics,
+                execution_time_ms: elapsed_ms,
+                cache_hit: false,
+                build_ms: elapsed_ms,
+                run_ms: 0,
+                stale_harness: false,
+                build_timed_out: preflight_timed_out,
+                rounds: 0,
+                crashes: Vec::new(),
+                corpus_cases: None,
+                toolchain,
+            });
+        }
+        
         // Generate test file
-        let test_file_path = self.generate_test_file(repo_path, instruction_name)?;
+        let spl_programs = spl_programs();
+        let test_file_path = self.generate_test_file(repo_path, instruction_name, harness, &spl_programs)?;
+        if harness.engine() == FuzzEngine::Honggfuzz {
+            return self.run_honggfuzz(&test_file_path, toolchain, start_time);
+        }
+        
+        // Built harnesses are reusable while the commit, instruction, strategy, seeded accounts
+        // and generated source stay the same
+        let mut tag = harness.cache_tag();
+        tag.push_str(&format!(":{:x}", Sha256::digest(fs::read(&test_file_path)?)));
+        if harness.layout().is_some() && !spl_programs.is_empty() {
+            tag.push_str(&format!(":{}", spl_programs.join(",")));
+        }
+        if !self.seeds.is_empty() {
+            let mut digest = Sha256::new();
+            for seed in &self.seeds {
+                digest.update(format!("{}:{}:{}:{}:{}:", seed.index, seed.address, seed.account.owner, seed.account.lamports, seed.account.executable));
+                digest.update(&seed.account.data);
+            }
+            tag.push_str(&format!(":{:x}", digest.finalize()));
+        }
+        let cache_key = GitHubClient::head_commit(repo_path).map(|commit| harness_cache_key(&commit, instruction_name, &tag));
+        // The last harness built for the instruction in the owner's repository, whatever the
+        // commit, for when there's no time to build this one
+        let latest_key = self.owner.as_ref().map(|owner| harness_cache_key(owner, instruction_name, &tag));
         
-        // Run the tests with time limit
-        self.run_tests(&test_file_path, 120) // 2 minute limit
+        self.run_tests(&test_file_path, toolchain, cache_key, latest_key, start_time)
     }
 
-    fn generate_test_file(&self, repo_path: &Path, instruction_name: &str) -> Result<PathBuf> {
+    // `cargo check` of the target repo; no diagnostics when it builds, and true when it was
+    // stopped at the deadline. The repo's own rust-toolchain file applies since it runs in the repo.
+    #[tracing::instrument(name = "process.cargo_check", skip(self), fields(exit_code))]
+    fn preflight_check(&self, repo_path: &Path, deadline: Instant) -> Result<(Vec<CompilerDiagnostic>, bool)> {
+        if !repo_path.join("Cargo.toml").exists() {
+            return Ok((Vec::new(), false));
+        }
+        println!("Checking that the program builds...");
+        self.sandbox.prepare(repo_path, None);
+        let (output, timed_out) = output_until(self.sandbox.command("cargo")
+            .args(["check", "--all-targets", "--message-format=json"])
+            .current_dir(repo_path), deadline)
+            .map_err(|e| anyhow!("Failed to run cargo check: {}", e))?;
+        tracing::Span::current().record("exit_code", output.status.code());
+        if timed_out {
+            println!("Checking the program took over the build's share of the fuzz budget");
+            return Ok((Vec::new(), true));
+        }
+        if output.status.success() {
+            return Ok((Vec::new(), false));
+        }
+        
+        let diagnostics = parse_compiler_errors(&String::from_utf8_lossy(&output.stdout));
+        if !diagnostics.is_empty() {
+            return Ok((diagnostics, false));
+        }
+        // Failures before compilation (manifest errors, unresolvable dependencies) only go to stderr
+        let stderr = String::from_utf8_lossy(&output.stderr);
+        Ok((vec![CompilerDiagnostic {
+            level: "error".to_string(),
+            code: None,
+            message: stderr.lines().rev().find(|l| l.starts_with("error")).unwrap_or("cargo check failed").to_string(),
+            file: None,
+            line: None,
+            column: None,
+            rendered: Some(stderr.to_string()),
+        }], false))
+    }
+    
+    fn generate_test_file(&self, _repo_path: &Path, instruction_name: &str, harness: &Harness, spl_programs: &[&'static str]) -> Result<PathBuf> {
         // Create test directory
         let test_dir = self.temp_dir.join("fuzz_tests");
         fs::create_dir_all(&test_dir)?;
         
+        // The harness runs in the test directory, where the program test looks for programs
+        if harness.layout().is_some() && !spl_programs.is_empty() {
+            let dir = spl_programs_dir().unwrap_or_default();
+            for name in spl_programs {
+                fs::copy(dir.join(format!("{}.so", name)), test_dir.join(format!("{}.so", name)))?;
+            }
+            println!("Adding SPL programs to the fuzz harness: {}", spl_programs.join(", "));
+        }
+        
         // Create test file
-        let test_file_path = test_dir.join(format!("{}_fuzz_test.rs", instruction_name));
+        let test_file_path = test_dir.join(harness.test_file_name(instruction_name));
         let mut file = File::create(&test_file_path)?;
         
-        // Write test content based on instruction
-        if instruction_name.to_lowercase() == "increment" {
+        // Write test content based on strategy and instruction
+        if let Harness::AccountOrdering(layout) = harness {
+            self.write_account_ordering_test(&mut file, instruction_name, layout, spl_programs)?;
+        } else if let Harness::ComputeUnits { layout, budget } = harness {
+            self.write_compute_units_test(&mut file, instruction_name, layout, *budget, spl_programs)?;
+        } else if let Harness::Honggfuzz(layout) = harness {
+            self.write_honggfuzz_harness(&mut file, instruction_name, layout, spl_programs)?;
+        } else if instruction_name.to_lowercase() == "increment" {
             self.write_increment_test(&mut file)?;
         } else {
             self.write_generic_test(&mut file, instruction_name)?;
@@ -216,12 +562,253 @@ mod tests {{
         Ok(())
     }
     
-    fn run_tests(&self, test_file_path: &Path, time_limit_secs: u64) -> Result<FuzzingResult> {
+    // Every arrangement of the instruction's accounts but the expected one, shuffled and with some
+    // left out, must fail. An accepted one fails the test with the exact order that got through;
+    // proptest shrinks it to the simplest such arrangement first. Lamports are checked as well.
+    fn write_account_ordering_test(&self, file: &mut File, instruction_name: &str, layout: &InstructionLayout, spl_programs: &[&str]) -> Result<()> {
+        // The arguments are zeroed; whatever the instruction doesn't read is ignored
+        writeln!(file, r#"
+#[cfg(test)]
+mod tests {{
+    use proptest::prelude::*;
+    use solana_program_test::*;
+    use solana_sdk::{{account::{{Account, AccountSharedData}}, instruction::{{AccountMeta, Instruction}}, pubkey::Pubkey, signature::Keypair, signer::Signer, system_program, transaction::Transaction}};
+    use std::cell::RefCell;
+    
+    // (name, signer, writable), in the order the instruction expects
+    const ACCOUNTS: &[(&str, bool, bool)] = &[{accounts}];
+    const SELECTOR: &[u8] = &[{selector}];
+{conservation}{snapshot}
+    // The instruction with its accounts in this arrangement of ACCOUNTS' indices
+    fn run(arrangement: &[usize]) -> Option<Outcome> {{
+        run_case(|program_id, addresses| {{
+            let accounts = arrangement.iter().map(|&i| {{
+                let (_, signer, writable) = ACCOUNTS[i];
+                if writable {{
+                    AccountMeta::new(addresses[i], signer)
+                }} else {{
+                    AccountMeta::new_readonly(addresses[i], signer)
+                }}
+            }}).collect();
+            let mut data = SELECTOR.to_vec();
+            data.resize(SELECTOR.len() + 256, 0);
+            let signers = arrangement.iter().copied().filter(|&i| ACCOUNTS[i].1).collect();
+            (vec![Instruction {{ program_id, accounts, data }}], signers)
+        }})
+    }}
+    
+    fn names(arrangement: &[usize]) -> String {{
+        arrangement.iter().map(|&i| ACCOUNTS[i].0).collect::<Vec<_>>().join(", ")
+    }}
+    
+    // With zeroed accounts and arguments the expected order may fail too, which leaves the
+    // rejections below proving less
+    #[test]
+    fn test_{name}_expected_ordering() {{
+        let expected: Vec<usize> = (0..ACCOUNTS.len()).collect();
+        let outcome = run(&expected);
+        if let Some(imbalance) = outcome.as_ref().and_then(|outcome| outcome.imbalance.as_ref()) {{
+            panic!("{{}} with accounts [{{}}]", imbalance, names(&expected));
+        }}
+        if !outcome.map_or(false, |outcome| outcome.succeeded) {{
+            println!("note: {name} rejects its expected accounts too: [{{}}]", names(&expected));
+        }}
+    }}
+    
+    proptest! {{
+        #![proptest_config(ProptestConfig::with_cases(256))]
+        #[test]
+        fn test_{name}_account_ordering(
+            order in Just((0..ACCOUNTS.len()).collect::<Vec<usize>>()).prop_shuffle(),
+            keep in proptest::collection::vec(any::<bool>(), ACCOUNTS.len()),
+        ) {{
+            let arrangement: Vec<usize> = order.into_iter().zip(keep).filter(|(_, kept)| *kept).map(|(i, _)| i).collect();
+            prop_assume!(arrangement != (0..ACCOUNTS.len()).collect::<Vec<usize>>());
+            let outcome = run(&arrangement);
+            if let Some(imbalance) = outcome.as_ref().and_then(|outcome| outcome.imbalance.as_ref()) {{
+                prop_assert!(false, "{{}} with accounts [{{}}]", imbalance, names(&arrangement));
+            }}
+            prop_assert!(!outcome.map_or(false, |outcome| outcome.succeeded), "{accepted}: [{{}}]", names(&arrangement));
+        }}
+    }}
+}}"#,
+            accounts = account_list(layout),
+            selector = selector(instruction_name, layout),
+            name = instruction_name,
+            accepted = ACCEPTED_ORDERING,
+            conservation = conservation_check(),
+            snapshot = state_snapshot(layout, &self.seeds, spl_programs),
+        )?;
+        
+        Ok(())
+    }
+    
+    // Calls the instruction with its accounts in order and generated arguments, allowing the
+    // transaction maximum so usage past the budget is measured rather than cut off. An input
+    // over the budget fails the test, shrunk to the smallest such input; the first to come
+    // within NEAR_BUDGET_PERCENT of it is printed. Lamports are checked as well.
+    fn write_compute_units_test(&self, file: &mut File, instruction_name: &str, layout: &InstructionLayout, budget: u64, spl_programs: &[&str]) -> Result<()> {
+        let arguments: Vec<String> = layout.args.iter().map(|arg| format!("{:?}", arg.name)).collect();
+        let strategies: Vec<String> = layout.args.iter()
+            .map(|arg| format!("            {}.boxed(),", borsh_strategy(&arg.ty)))
+            .collect();
+        let max_data = max_instruction_data(layout);
+        
+        writeln!(file, r#"
+#[cfg(test)]
+mod tests {{
+    use proptest::prelude::*;
+    use solana_program_test::*;
+    use solana_sdk::{{account::{{Account, AccountSharedData}}, compute_budget::ComputeBudgetInstruction, instruction::{{AccountMeta, Instruction}}, pubkey::Pubkey, signature::Keypair, signer::Signer, system_program, transaction::Transaction}};
+    use std::cell::RefCell;
+    use std::sync::atomic::{{AtomicBool, Ordering}};
+    
+    // (name, signer, writable), in the order the instruction expects
+    const ACCOUNTS: &[(&str, bool, bool)] = &[{accounts}];
+    const SELECTOR: &[u8] = &[{selector}];
+    const ARGUMENTS: &[&str] = &[{arguments}];
+    const BUDGET: u64 = {budget};
+    // Longest string or collection generated, short of what fits in MAX_DATA; inputs that
+    // don't fit all the same are skipped
+    const MAX_LEN: usize = 256;
+    const MAX_DATA: usize = {max_data};
+    static NEAR_REPORTED: AtomicBool = AtomicBool::new(false);
+    
+    // Borsh encodings of each argument, favouring integer extremes and long collections
+    fn arguments() -> Vec<BoxedStrategy<Vec<u8>>> {{
+        vec![
+{strategies}
+        ]
+    }}
+    
+    fn describe(args: &[Vec<u8>]) -> String {{
+        ARGUMENTS.iter().zip(args)
+            .map(|(name, bytes)| format!("{{}} = 0x{{}}", name, bytes.iter().map(|b| format!("{{:02x}}", b)).collect::<String>()))
+            .collect::<Vec<_>>()
+            .join(", ")
+    }}
+{conservation}{snapshot}
+    // The instruction with its accounts in order and this data
+    fn run(data: Vec<u8>) -> Option<Outcome> {{
+        run_case(|program_id, addresses| {{
+            let accounts = ACCOUNTS.iter().zip(addresses).map(|(&(_, signer, writable), &address)| {{
+                if writable {{
+                    AccountMeta::new(address, signer)
+                }} else {{
+                    AccountMeta::new_readonly(address, signer)
+                }}
+            }}).collect();
+            let instructions = vec![
+                ComputeBudgetInstruction::set_compute_unit_limit(1_400_000),
+                Instruction {{ program_id, accounts, data }},
+            ];
+            (instructions, (0..ACCOUNTS.len()).filter(|&i| ACCOUNTS[i].1).collect())
+        }})
+    }}
+    
+    proptest! {{
+        #![proptest_config(ProptestConfig::with_cases(256))]
+        #[test]
+        fn test_{name}_compute_units(args in arguments()) {{
+            let mut data = SELECTOR.to_vec();
+            for arg in &args {{
+                data.extend(arg);
+            }}
+            if data.len() > MAX_DATA {{
+                return Ok(());
+            }}
+            let Some(outcome) = run(data) else {{
+                return Ok(());
+            }};
+            if let Some(imbalance) = &outcome.imbalance {{
+                prop_assert!(false, "{{}} with {{}}", imbalance, describe(&args));
+            }}
+            let Some(units) = outcome.units else {{
+                return Ok(());
+            }};
+            if units * 100 >= BUDGET * {near_percent} && units <= BUDGET && !NEAR_REPORTED.swap(true, Ordering::Relaxed) {{
+                println!("{near}: {{}} of {{}} CU with {{}}", units, BUDGET, describe(&args));
+            }}
+            prop_assert!(units <= BUDGET, "{exceeded}: {{}} of {{}} CU with {{}}", units, BUDGET, describe(&args));
+        }}
+    }}
+}}"#,
+            accounts = account_list(layout),
+            selector = selector(instruction_name, layout),
+            arguments = arguments.join(", "),
+            budget = budget,
+            max_data = max_data,
+            strategies = strategies.join("\n"),
+            name = instruction_name,
+            near_percent = NEAR_BUDGET_PERCENT,
+            near = COMPUTE_BUDGET_NEARLY_EXHAUSTED,
+            exceeded = COMPUTE_BUDGET_EXCEEDED,
+            conservation = conservation_check(),
+            snapshot = state_snapshot(layout, &self.seeds, spl_programs),
+        )?;
+        
+        Ok(())
+    }
+    
+    // A honggfuzz-rs binary in persistent mode: one process takes input after input against the
+    // bank the snapshot restores, each input the instruction's data after its selector, with its
+    // accounts in order. A lamport imbalance panics, which honggfuzz records as a crash.
+    fn write_honggfuzz_harness(&self, file: &mut File, instruction_name: &str, layout: &InstructionLayout, spl_programs: &[&str]) -> Result<()> {
+        writeln!(file, r#"#![allow(warnings)]
+// Honggfuzz harness for {name}
+use honggfuzz::fuzz;
+use solana_program_test::*;
+use solana_sdk::{{account::{{Account, AccountSharedData}}, instruction::{{AccountMeta, Instruction}}, pubkey::Pubkey, signature::Keypair, signer::Signer, system_program, transaction::Transaction}};
+use std::cell::RefCell;
+
+// (name, signer, writable), in the order the instruction expects
+const ACCOUNTS: &[(&str, bool, bool)] = &[{accounts}];
+const SELECTOR: &[u8] = &[{selector}];
+const MAX_DATA: usize = {max_data};
+{conservation}{snapshot}
+fn main() {{
+    loop {{
+        fuzz!(|input: &[u8]| {{
+            if SELECTOR.len() + input.len() > MAX_DATA {{
+                return;
+            }}
+            let data = [SELECTOR, input].concat();
+            let outcome = run_case(|program_id, addresses| {{
+                let accounts = ACCOUNTS.iter().zip(addresses).map(|(&(_, signer, writable), &address)| {{
+                    if writable {{
+                        AccountMeta::new(address, signer)
+                    }} else {{
+                        AccountMeta::new_readonly(address, signer)
+                    }}
+                }}).collect();
+                let signers = (0..ACCOUNTS.len()).filter(|&i| ACCOUNTS[i].1).collect();
+                (vec![Instruction {{ program_id, accounts, data: data.clone() }}], signers)
+            }});
+            if let Some(imbalance) = outcome.and_then(|outcome| outcome.imbalance) {{
+                panic!("{{}} with data 0x{{}}", imbalance, data.iter().map(|b| format!("{{:02x}}", b)).collect::<String>());
+            }}
+        }});
+    }}
+}}"#,
+            name = instruction_name,
+            accounts = account_list(layout),
+            selector = selector(instruction_name, layout),
+            max_data = max_instruction_data(layout),
+            conservation = conservation_check(),
+            snapshot = state_snapshot(layout, &self.seeds, spl_programs),
+        )?;
+        
+        Ok(())
+    }
+    
+    #[tracing::instrument(name = "process.cargo_test", skip(self, toolchain), fields(rust_toolchain = ?toolchain.rust_toolchain, exit_code))]
+    fn run_tests(&self, test_file_path: &Path, toolchain: ToolchainSelection, cache_key: Option<String>, latest_key: Option<String>, build_start: Instant) -> Result<FuzzingResult> {
         // Create Cargo.toml
         let test_dir = test_file_path.parent().ok_or_else(|| anyhow!("Invalid test path"))?;
         let cargo_path = test_dir.join("Cargo.toml");
         let mut cargo_file = File::create(&cargo_path)?;
         
+        let dependencies = harness_dependencies(&toolchain);
         writeln!(cargo_file, r#"
 [package]
 name = "anchor_fuzz_tests"
@@ -229,12 +816,7 @@ version = "0.1.0"
 edition = "2021"
 
 [dependencies]
-solana-program = "1.16"
-solana-program-test = "1.16"
-solana-sdk = "1.16"
-proptest = "1.2"
-anchor-lang = {{ version = "0.28.0", optional = true }}
-
+{}
 [lib]
 name = "anchor_fuzz_tests"
 path = "src/lib.rs"
@@ -243,7 +825,7 @@ path = "src/lib.rs"
 default = ["anchor"]
 anchor = ["anchor-lang"]
 test-sbf = []
-"#)?;
+"#, dependencies)?;
         
         // Create lib.rs
         let src_dir = test_dir.join("src");
@@ -259,41 +841,311 @@ test-sbf = []
         let test_dest = src_dir.join(test_file_path.file_name().unwrap());
         fs::copy(test_file_path, &test_dest)?;
         
-        // Run cargo test with timeout
-        let start_time = std::time::Instant::now();
-        
-        // Use cargo directly instead of timeout command (which may not exist on macOS)
-        let output = Command::new("cargo")
-            .arg("test")
-            .arg("--lib")
-            .arg("--features=anchor")
-            .current_dir(test_dir)
-            .output()
-            .map_err(|e| anyhow!("Failed to run tests: {}", e))?;
+        // Build the harness, or reuse the binary built for the same commit, instruction and template.
+        // The build gets the budget less the run's reserve; when earlier builds took longer than
+        // that, or this one does, the harness last built for the instruction stands in.
+        let budget = self.budget;
+        let cached_binary = cache_key.map(|key| harness_cache_dir().join(key).join("harness"));
+        let latest_binary = latest_key.map(|key| harness_cache_dir().join(key).join("harness"));
+        let stale_binary = latest_binary.as_ref().filter(|binary| binary.is_file());
+        let mut stale_harness = false;
+        let mut build_timed_out = false;
+        let (binary, cache_hit, build_log) = if let Some(binary) = cached_binary.as_ref().filter(|b| b.is_file()) {
+            println!("Reusing cached fuzz harness {}", binary.display());
+            (Some(binary.clone()), true, String::new())
+        } else if let (Some(expected), Some(stale_binary)) = (budget.expected_build.filter(|expected| *expected > budget.build_limit()), stale_binary) {
+            println!("Harness builds for this repository have taken {}s, more than the {}s the budget leaves; reusing the harness last built for the instruction",
+                expected.as_secs(), budget.build_limit().as_secs());
+            stale_harness = true;
+            (Some(stale_binary.clone()), true, String::new())
+        } else {
+            match self.build_harness(test_dir, &toolchain, build_start + budget.build_limit())? {
+                Some((binary, build_log)) => {
+                    if let Some(built) = &binary {
+                        for cached in cached_binary.iter().chain(latest_binary.as_ref()) {
+                            if let Err(e) = store_harness(built, cached) {
+                                println!("Warning: Failed to cache fuzz harness: {}", e);
+                            }
+                        }
+                    }
+                    (binary, false, build_log)
+                },
+                None if stale_binary.is_some() => {
+                    println!("Building the harness took over {}s of the budget; reusing the harness last built for the instruction", budget.build_limit().as_secs());
+                    stale_harness = true;
+                    (stale_binary.cloned(), true, String::new())
+                },
+                None => {
+                    build_timed_out = true;
+                    (None, false, String::new())
+                },
+            }
+        };
+        let build_ms = build_start.elapsed().as_millis() as u64;
         
-        let duration = start_time.elapsed();
-        let timed_out = duration.as_secs() >= time_limit_secs;
+        let Some(binary) = binary else {
+            let errors = if build_timed_out { Vec::new() } else { self.extract_errors("", &build_log) };
+            fs::write(test_dir.join("test_output.log"), format!("BUILD:\n{}", build_log))?;
+            return Ok(FuzzingResult {
+                success: false,
+                timed_out: false,
+                findings: classify_errors(&errors, false),
+                build_diagnostics: Vec::new(),
+                errors,
+                execution_time_ms: build_ms,
+                cache_hit,
+                build_ms,
+                run_ms: 0,
+                stale_harness,
+                build_timed_out,
+                rounds: 0,
+                crashes: Vec::new(),
+                corpus_cases: None,
+                toolchain,
+            });
+        };
         
-        // Parse output
-        let stdout = String::from_utf8_lossy(&output.stdout).to_string();
-        let stderr = String::from_utf8_lossy(&output.stderr).to_string();
+        // proptest looks for persisted failures next to the harness's src directory
+        let stem = test_file_path.file_stem().unwrap().to_string_lossy().to_string();
+        let persisted = test_dir.join("proptest-regressions").join(format!("{}.txt", stem));
+        let corpus_file = self.corpus_dir.as_ref().map(|dir| dir.join(format!("{}.txt", stem)));
+        let corpus_cases = match &corpus_file {
+            Some(corpus_file) => Some(restore_corpus(corpus_file, &persisted)?),
+            None => None,
+        };
         
-        // Extract errors
-        let errors = self.extract_errors(&stdout, &stderr);
+        // Run the test binary directly; cargo would re-check the build first. It has the rest of
+        // the budget, and never less than the reserve.
+        let run_start = Instant::now();
+        let deadline = (build_start + budget.total).max(run_start + budget.run_reserve());
+        let mut errors: Vec<String> = self.resumed.as_ref().map(|resumed| resumed.errors.clone()).unwrap_or_default();
+        let mut rounds = self.resumed.as_ref().map_or(0, |resumed| resumed.rounds);
+        let resumed_ms = self.resumed.as_ref().map_or(0, |resumed| resumed.run_ms);
+        let mut exited_cleanly = true;
+        let mut last_checkpoint = Instant::now();
+        let (stdout, stderr, timed_out) = loop {
+            let (output, killed) = output_until(self.sandbox.command(&binary).current_dir(test_dir), deadline)
+                .map_err(|e| anyhow!("Failed to run tests: {}", e))?;
+            tracing::Span::current().record("exit_code", output.status.code());
+            let stdout = String::from_utf8_lossy(&output.stdout).to_string();
+            let stderr = String::from_utf8_lossy(&output.stderr).to_string();
+            let found = self.extract_errors(&stdout, &stderr);
+            let crashed = !killed && !output.status.success() && found.is_empty();
+            for error in found {
+                if !errors.contains(&error) {
+                    errors.push(error);
+                }
+            }
+            exited_cleanly &= killed || output.status.success();
+            if !killed {
+                rounds += 1;
+            }
+            if !self.continuous {
+                break (stdout, stderr, killed);
+            }
+            // A continuous run stopping at the deadline is how it ends, not a timeout. One that
+            // failed without reporting why would only fail the same way again.
+            if killed || crashed || Instant::now() >= deadline {
+                break (stdout, stderr, false);
+            }
+            if let Some(checkpoints) = self.checkpoints.as_ref().filter(|_| last_checkpoint.elapsed() >= CHECKPOINT_INTERVAL) {
+                let findings = classify_errors(&errors, false);
+                checkpoints(&FuzzCheckpoint {
+                    rounds,
+                    run_ms: resumed_ms + run_start.elapsed().as_millis() as u64,
+                    errors: errors.clone(),
+                    summary: summarize_findings(&findings),
+                    findings,
+                    recorded_at: now_unix(),
+                });
+                // Cases found so far outlive a job that's cut short
+                if let Some(corpus_file) = &corpus_file {
+                    if let Err(e) = save_corpus(&persisted, corpus_file) {
+                        println!("Warning: Failed to save fuzz corpus: {}", e);
+                    }
+                }
+                last_checkpoint = Instant::now();
+            }
+        };
+        let run_duration = run_start.elapsed();
+        if self.continuous {
+            println!("Ran the fuzz harness {} times in {}s", rounds, run_duration.as_secs());
+        }
         
-        // Save test output for debugging
+        // Save build and test output for debugging; a continuous run's is its last round
         let output_path = test_dir.join("test_output.log");
         let mut output_file = File::create(output_path)?;
-        writeln!(output_file, "STDOUT:\n{}\n\nSTDERR:\n{}", stdout, stderr)?;
+        writeln!(output_file, "BUILD:\n{}\n\nSTDOUT:\n{}\n\nSTDERR:\n{}", build_log, stdout, stderr)?;
         
+        if let Some(corpus_file) = &corpus_file {
+            if let Err(e) = save_corpus(&persisted, corpus_file) {
+                println!("Warning: Failed to save fuzz corpus: {}", e);
+            }
+        }
+        
+        // proptest's failing inputs are the seeds it persisted
+        let mut crashes = Vec::new();
+        if !errors.is_empty() && persisted.is_file() {
+            let crashes_dir = test_dir.join("crashes");
+            fs::create_dir_all(&crashes_dir)?;
+            fs::copy(&persisted, crashes_dir.join("proptest-regressions.txt"))?;
+            crashes.push("proptest-regressions.txt".to_string());
+        }
+        
+        let run_ms = run_duration.as_millis() as u64;
+        Ok(FuzzingResult {
+            success: exited_cleanly && !timed_out && errors.is_empty(),
+            timed_out,
+            findings: classify_errors(&errors, timed_out),
+            build_diagnostics: Vec::new(),
+            errors,
+            execution_time_ms: build_ms + run_ms,
+            cache_hit,
+            build_ms,
+            run_ms,
+            stale_harness,
+            build_timed_out,
+            rounds,
+            crashes,
+            corpus_cases,
+            toolchain,
+        })
+    }
+    
+    // Builds the honggfuzz harness with `cargo hfuzz build` and fuzzes with `cargo hfuzz run` for
+    // the rest of the budget, stopping at the first crash. honggfuzz keeps its own target
+    // directory, so the binary isn't cached. The crashing input and honggfuzz's report are
+    // collected into crashes/ as proptest's failures are.
+    #[tracing::instrument(name = "process.cargo_hfuzz", skip(self, toolchain), fields(rust_toolchain = ?toolchain.rust_toolchain, exit_code))]
+    fn run_honggfuzz(&self, test_file_path: &Path, toolchain: ToolchainSelection, build_start: Instant) -> Result<FuzzingResult> {
+        let test_dir = test_file_path.parent().ok_or_else(|| anyhow!("Invalid test path"))?;
+        fs::write(test_dir.join("Cargo.toml"), format!(r#"
+[package]
+name = "anchor_fuzz_tests"
+version = "0.1.0"
+edition = "2021"
+
+[dependencies]
+honggfuzz = "0.5"
+{}
+[[bin]]
+name = "{}"
+path = "src/main.rs"
+
+[features]
+default = ["anchor"]
+anchor = ["anchor-lang"]
+test-sbf = []
+"#, harness_dependencies(&toolchain), HFUZZ_TARGET))?;
+        let src_dir = test_dir.join("src");
+        fs::create_dir_all(&src_dir)?;
+        fs::copy(test_file_path, src_dir.join("main.rs"))?;
+        
+        self.sandbox.prepare(test_dir, toolchain.rust_toolchain.as_deref());
+        let cargo = || {
+            let mut command = self.sandbox.command("cargo");
+            if let Some(channel) = &toolchain.rust_toolchain {
+                command.env("RUSTUP_TOOLCHAIN", channel);
+            }
+            command.current_dir(test_dir);
+            command
+        };
+        let budget = self.budget;
+        let (output, build_timed_out) = output_until(cargo().args(["hfuzz", "build"]), build_start + budget.build_limit())
+            .map_err(|e| anyhow!("Failed to build the honggfuzz harness: {}", e))?;
+        let build_ms = build_start.elapsed().as_millis() as u64;
+        let build_log = String::from_utf8_lossy(&output.stderr).to_string();
+        if build_timed_out || !output.status.success() {
+            tracing::Span::current().record("exit_code", output.status.code());
+            let errors = if build_timed_out { Vec::new() } else { self.extract_errors("", &build_log) };
+            fs::write(test_dir.join("test_output.log"), format!("BUILD:\n{}", build_log))?;
+            return Ok(FuzzingResult {
+                success: false,
+                timed_out: false,
+                findings: classify_errors(&errors, false),
+                build_diagnostics: Vec::new(),
+                errors,
+                execution_time_ms: build_ms,
+                cache_hit: false,
+                build_ms,
+                run_ms: 0,
+                stale_harness: false,
+                build_timed_out,
+                rounds: 0,
+                crashes: Vec::new(),
+                corpus_cases: None,
+                toolchain,
+            });
+        }
+        
+        // honggfuzz stops itself at --run_time; the deadline only catches one that doesn't
+        let run_start = Instant::now();
+        let run_time = (build_start + budget.total).max(run_start + budget.run_reserve()) - run_start;
+        let run_args = format!("--run_time {} --exit_upon_crash --keep_output --verbose", run_time.as_secs().max(1));
+        let (output, timed_out) = output_until(cargo().args(["hfuzz", "run", HFUZZ_TARGET]).env("HFUZZ_RUN_ARGS", run_args), run_start + run_time + HFUZZ_GRACE)
+            .map_err(|e| anyhow!("Failed to run honggfuzz: {}", e))?;
+        tracing::Span::current().record("exit_code", output.status.code());
+        let run_ms = run_start.elapsed().as_millis() as u64;
+        let stdout = String::from_utf8_lossy(&output.stdout).to_string();
+        let stderr = String::from_utf8_lossy(&output.stderr).to_string();
+        fs::write(test_dir.join("test_output.log"), format!("BUILD:\n{}\n\nSTDOUT:\n{}\n\nSTDERR:\n{}", build_log, stdout, stderr))?;
+        
+        let crashes = collect_honggfuzz_crashes(test_dir)?;
+        let mut errors = self.extract_errors(&stdout, &stderr);
+        errors.dedup();
+        // A crash without a panic message, such as an abort in native code, is still a finding
+        if errors.is_empty() {
+            errors.extend(crashes.iter().filter(|name| name.ends_with(".fuzz")).map(|name| format!("honggfuzz crash, input saved as crashes/{}", name)));
+        }
         Ok(FuzzingResult {
-            success: output.status.success() && !timed_out && errors.is_empty(),
+            success: !timed_out && errors.is_empty(),
             timed_out,
+            findings: classify_errors(&errors, timed_out),
+            build_diagnostics: Vec::new(),
             errors,
-            execution_time_ms: duration.as_millis() as u64,
+            execution_time_ms: build_ms + run_ms,
+            cache_hit: false,
+            build_ms,
+            run_ms,
+            stale_harness: false,
+            build_timed_out: false,
+            rounds: 1,
+            crashes,
+            corpus_cases: None,
+            toolchain,
         })
     }
     
+    // Compile the harness tests without running them. Returns the test binary, or None
+    // if the build failed, along with cargo's human-readable diagnostics; None if the build
+    // was stopped at the deadline.
+    #[tracing::instrument(name = "process.cargo_build_harness", skip_all, fields(exit_code))]
+    fn build_harness(&self, test_dir: &Path, toolchain: &ToolchainSelection, deadline: Instant) -> Result<Option<(Option<PathBuf>, String)>> {
+        self.sandbox.prepare(test_dir, toolchain.rust_toolchain.as_deref());
+        let mut command = self.sandbox.command("cargo");
+        if let Some(channel) = &toolchain.rust_toolchain {
+            // The harness lives outside the repo, so its rust-toolchain.toml doesn't apply
+            command.env("RUSTUP_TOOLCHAIN", channel);
+        }
+        command
+            .args(["test", "--lib", "--features=anchor", "--no-run", "--message-format=json-render-diagnostics"])
+            .current_dir(test_dir);
+        let (output, killed) = output_until(&mut command, deadline).map_err(|e| anyhow!("Failed to build tests: {}", e))?;
+        tracing::Span::current().record("exit_code", output.status.code());
+        if killed {
+            return Ok(None);
+        }
+        
+        // Artifact messages go to stdout as JSON, rendered diagnostics to stderr
+        let binary = String::from_utf8_lossy(&output.stdout).lines()
+            .filter_map(|line| serde_json::from_str::<serde_json::Value>(line).ok())
+            .filter(|json| json.get("reason").and_then(|r| r.as_str()) == Some("compiler-artifact"))
+            .filter(|json| json.pointer("/profile/test").and_then(|t| t.as_bool()) == Some(true))
+            .filter_map(|json| json.get("executable").and_then(|e| e.as_str()).map(PathBuf::from))
+            .next_back();
+        Ok(Some((binary.filter(|_| output.status.success()), String::from_utf8_lossy(&output.stderr).to_string())))
+    }
+    
     fn extract_errors(&self, stdout: &str, stderr: &str) -> Vec<String> {
         let mut errors = Vec::new();
         
@@ -301,6 +1153,11 @@ test-sbf = []
         for line in stdout.lines().chain(stderr.lines()) {
             if line.contains("error:") || 
                line.contains("panicked") || 
+               line.contains(ACCEPTED_ORDERING) || 
+               line.contains(COMPUTE_BUDGET_EXCEEDED) || 
+               line.contains(LAMPORTS_MINTED) || 
+               line.contains(LAMPORTS_DESTROYED) || 
+               line.contains(COMPUTE_BUDGET_NEARLY_EXHAUSTED) || 
                line.contains("overflow") || 
                line.contains("underflow") ||
                line.contains("validation failed") ||
@@ -312,4 +1169,590 @@ test-sbf = []
         
         errors
     }
-}
\ No newline at end of file
+}
+
+// Harness code that runs a transaction and checks it conserved lamports: the payer and the
+// instruction's accounts together may only lose the fee, or nothing when the transaction was
+// rejected before it ran. No specification is needed, so every executing harness checks it.
+fn conservation_check() -> String {
+    format!(r#"
+    // What running a transaction did, and the lamports it made or lost beyond the fee
+    struct Outcome {{
+        succeeded: bool,
+        units: Option<u64>,
+        imbalance: Option<String>,
+    }}
+    
+    async fn total_lamports(banks_client: &mut BanksClient, holders: &[Pubkey]) -> u64 {{
+        let mut total = 0u64;
+        for holder in holders {{
+            total += banks_client.get_balance(*holder).await.unwrap_or(0);
+        }}
+        total
+    }}
+    
+    async fn process(banks_client: &mut BanksClient, transaction: Transaction, holders: &[Pubkey]) -> Option<Outcome> {{
+        let fee = banks_client.get_fee_for_message(transaction.message().clone()).await.ok().flatten().unwrap_or(0);
+        let before = total_lamports(banks_client, holders).await;
+        let processed = banks_client.process_transaction_with_metadata(transaction).await.ok()?;
+        let after = total_lamports(banks_client, holders).await;
+        let succeeded = processed.result.is_ok();
+        let expected = before.saturating_sub(fee);
+        let imbalance = if after == expected || (!succeeded && after == before) {{
+            None
+        }} else if after > expected {{
+            Some(format!("{minted}: {{}} more than the {{}} held before, less the {{}} fee", after - expected, before, fee))
+        }} else {{
+            Some(format!("{destroyed}: {{}} fewer than the {{}} held before, less the {{}} fee", expected - after, before, fee))
+        }};
+        Some(Outcome {{
+            succeeded,
+            units: processed.metadata.map(|metadata| metadata.compute_units_consumed),
+            imbalance,
+        }})
+    }}
+"#, minted = LAMPORTS_MINTED, destroyed = LAMPORTS_DESTROYED)
+}
+
+// Harness code that starts the program test once per test thread and snapshots ACCOUNTS as
+// they start out. Each case gets them back as they were, and a fee payer of its own so no two
+// cases send the same transaction, instead of starting the program test over. Accounts start
+// out as the seeded copies, as initialized mints and token accounts where Anchor declares
+// them, as the programs `Program<..>` names, or else zeroed and owned by the program.
+fn state_snapshot(layout: &InstructionLayout, seeds: &[SeedAccount], spl_programs: &[&str]) -> String {
+    let seeded: Vec<String> = seeds.iter().map(|seed| {
+        let data: Vec<String> = seed.account.data.iter().map(|byte| byte.to_string()).collect();
+        format!("        ({}, \"{}\", \"{}\", {}, {}, &[{}]),", seed.index, seed.address, seed.account.owner, seed.account.lamports, seed.account.executable, data.join(", "))
+    }).collect();
+    let (mut programs, mut mints, mut token_accounts) = (Vec::new(), Vec::new(), Vec::new());
+    for (index, slot) in layout.accounts.iter().enumerate() {
+        match slot.ty.as_deref().map(token_slot) {
+            Some(Some(TokenSlot::Program(id))) => programs.push(format!("({}, \"{}\")", index, id)),
+            Some(Some(TokenSlot::Mint(owner))) => mints.push(format!("({}, \"{}\")", index, owner)),
+            Some(Some(TokenSlot::TokenAccount(owner))) => token_accounts.push(format!("({}, \"{}\")", index, owner)),
+            _ => {},
+        }
+    }
+    let added: Vec<String> = SPL_PROGRAMS.iter()
+        .filter(|(name, _)| spl_programs.contains(name))
+        .map(|(name, id)| format!("(\"{}\", \"{}\")", name, id))
+        .collect();
+    format!(r#"
+    const PAYER_LAMPORTS: u64 = 10_000_000_000;
+    // Copies of cluster accounts for ACCOUNTS indices: (index, address, owner, lamports, executable, data)
+    const SEEDED: &[(usize, &str, &str, u64, bool, &[u8])] = &[
+{seeded}
+    ];
+    // ACCOUNTS indices that are programs the bank has, by address
+    const PROGRAMS: &[(usize, &str)] = &[{programs}];
+    // ACCOUNTS indices that are mints and token accounts, by their token program
+    const MINTS: &[(usize, &str)] = &[{mints}];
+    const TOKEN_ACCOUNTS: &[(usize, &str)] = &[{token_accounts}];
+    // SPL program builds to use over solana-program-test's, by file stem and address
+    const SPL_PROGRAMS: &[(&str, &str)] = &[{added}];
+    
+    struct Bank {{
+        runtime: solana_program_test::tokio::runtime::Runtime,
+        context: ProgramTestContext,
+        program_id: Pubkey,
+        keys: Vec<Keypair>,
+        addresses: Vec<Pubkey>,
+        snapshot: Vec<(Pubkey, AccountSharedData)>,
+    }}
+    
+    thread_local! {{
+        static BANK: RefCell<Option<Bank>> = RefCell::new(None);
+    }}
+    
+    // An initialized mint with `authority` as its mint and freeze authority
+    fn mint_account(authority: &Pubkey, token_program: Pubkey) -> Account {{
+        let mut data = Vec::with_capacity(82);
+        data.extend(1u32.to_le_bytes());
+        data.extend(authority.to_bytes());
+        data.extend(1_000_000_000_000u64.to_le_bytes());
+        data.push(6);
+        data.push(1);
+        data.extend(1u32.to_le_bytes());
+        data.extend(authority.to_bytes());
+        Account {{ lamports: 1_461_600, data, owner: token_program, ..Account::default() }}
+    }}
+    
+    // An initialized token account of `mint` held by `owner`, with no delegate or close authority
+    fn token_account(mint: &Pubkey, owner: &Pubkey, token_program: Pubkey) -> Account {{
+        let mut data = Vec::with_capacity(165);
+        data.extend(mint.to_bytes());
+        data.extend(owner.to_bytes());
+        data.extend(1_000_000_000u64.to_le_bytes());
+        data.extend([0u8; 36]);
+        data.push(1);
+        data.extend([0u8; 12]);
+        data.extend(0u64.to_le_bytes());
+        data.extend([0u8; 36]);
+        Account {{ lamports: 2_039_280, data, owner: token_program, ..Account::default() }}
+    }}
+    
+    fn start() -> Bank {{
+        let program_id = Pubkey::new_unique();
+        let mut program_test = ProgramTest::new("{program}", program_id, None);
+        for &(name, address) in SPL_PROGRAMS {{
+            program_test.add_program(name, address.parse().unwrap(), None);
+        }}
+        let keys: Vec<Keypair> = ACCOUNTS.iter().map(|_| Keypair::new()).collect();
+        let addresses: Vec<Pubkey> = keys.iter().enumerate().map(|(i, key)| {{
+            match (PROGRAMS.iter().find(|program| program.0 == i), SEEDED.iter().find(|seed| seed.0 == i)) {{
+                (Some(&(_, address)), _) | (None, Some(&(_, address, ..))) => address.parse().unwrap(),
+                (None, None) => key.pubkey(),
+            }}
+        }}).collect();
+        
+        // Token accounts are of the first mint among ACCOUNTS, and the first signer holds them
+        // and controls the mints
+        let mint = MINTS.first().map_or_else(Pubkey::new_unique, |&(i, _)| addresses[i]);
+        let authority = ACCOUNTS.iter().position(|account| account.1).map_or_else(Pubkey::new_unique, |i| addresses[i]);
+        let mut snapshot = Vec::new();
+        for (i, &address) in addresses.iter().enumerate() {{
+            if PROGRAMS.iter().any(|program| program.0 == i) {{
+                continue;
+            }}
+            let account = if let Some(&(_, _, owner, lamports, executable, data)) = SEEDED.iter().find(|seed| seed.0 == i) {{
+                Account {{ lamports, data: data.to_vec(), owner: owner.parse().unwrap(), executable, rent_epoch: 0 }}
+            }} else if let Some(&(_, token_program)) = MINTS.iter().find(|mint| mint.0 == i) {{
+                mint_account(&authority, token_program.parse().unwrap())
+            }} else if let Some(&(_, token_program)) = TOKEN_ACCOUNTS.iter().find(|account| account.0 == i) {{
+                token_account(&mint, &authority, token_program.parse().unwrap())
+            }} else {{
+                Account {{ lamports: 1_000_000_000, data: vec![0; 1024], owner: program_id, ..Account::default() }}
+            }};
+            program_test.add_account(address, account.clone());
+            snapshot.push((address, AccountSharedData::from(account)));
+        }}
+        let runtime = solana_program_test::tokio::runtime::Runtime::new().unwrap();
+        let context = runtime.block_on(program_test.start_with_context());
+        Bank {{ runtime, context, program_id, keys, addresses, snapshot }}
+    }}
+    
+    // Runs the instructions `build` makes from the program id and account addresses, signed
+    // by the keys at the indices it returns
+    fn run_case(build: impl FnOnce(Pubkey, &[Pubkey]) -> (Vec<Instruction>, Vec<usize>)) -> Option<Outcome> {{
+        BANK.with(|bank| {{
+            let mut bank = bank.borrow_mut();
+            let Bank {{ runtime, context, program_id, keys, addresses, snapshot }} = bank.get_or_insert_with(start);
+            for (address, account) in snapshot.iter() {{
+                context.set_account(address, account);
+            }}
+            let payer = Keypair::new();
+            context.set_account(&payer.pubkey(), &AccountSharedData::new(PAYER_LAMPORTS, 0, &system_program::id()));
+            
+            let (instructions, signer_indices) = build(*program_id, addresses);
+            let mut signers = vec![&payer];
+            signers.extend(signer_indices.into_iter().map(|i| &keys[i]));
+            let holders: Vec<Pubkey> = std::iter::once(payer.pubkey()).chain(addresses.iter().copied()).collect();
+            runtime.block_on(async {{
+                let recent_blockhash = context.banks_client.get_latest_blockhash().await.ok()?;
+                let mut transaction = Transaction::new_with_payer(&instructions, Some(&payer.pubkey()));
+                transaction.sign(&signers, recent_blockhash);
+                process(&mut context.banks_client, transaction, &holders).await
+            }})
+        }})
+    }}
+"#,
+        program = layout.program.replace('-', "_"),
+        seeded = seeded.join("\n"),
+        programs = programs.join(", "),
+        mints = mints.join(", "),
+        token_accounts = token_accounts.join(", "),
+        added = added.join(", "),
+    )
+}
+
+// What an Anchor accounts field of type `ty` needs to be for token CPIs to get past the
+// program's account checks
+enum TokenSlot {
+    // The program at this address
+    Program(&'static str),
+    // A mint or token account owned by this token program
+    Mint(&'static str),
+    TokenAccount(&'static str),
+}
+
+fn token_slot(ty: &str) -> Option<TokenSlot> {
+    let (outer, params) = split_generic(ty.trim());
+    let inner = params.first().copied().unwrap_or_default();
+    match outer {
+        "Box" => token_slot(inner),
+        "Program" => match split_generic(inner).0 {
+            "Token" => Some(TokenSlot::Program(TOKEN_PROGRAM_ID)),
+            "Token2022" => Some(TokenSlot::Program(TOKEN_2022_PROGRAM_ID)),
+            "AssociatedToken" => Some(TokenSlot::Program(ASSOCIATED_TOKEN_PROGRAM_ID)),
+            "System" => Some(TokenSlot::Program(SYSTEM_PROGRAM_ID)),
+            _ => None,
+        },
+        "Interface" if split_generic(inner).0 == "TokenInterface" => Some(TokenSlot::Program(TOKEN_PROGRAM_ID)),
+        "Account" | "InterfaceAccount" => {
+            // token_2022::Mint and the like; token_interface types and the rest go to Token
+            let owner = if inner.contains("token_2022") { TOKEN_2022_PROGRAM_ID } else { TOKEN_PROGRAM_ID };
+            match split_generic(inner).0 {
+                "Mint" => Some(TokenSlot::Mint(owner)),
+                "TokenAccount" => Some(TokenSlot::TokenAccount(owner)),
+                _ => None,
+            }
+        },
+        _ => None,
+    }
+}
+
+// The harness's ACCOUNTS entries: (name, signer, writable)
+fn account_list(layout: &InstructionLayout) -> String {
+    layout.accounts.iter()
+        .map(|slot| format!("({:?}, {}, {})", slot.name, slot.signer, slot.writable))
+        .collect::<Vec<_>>()
+        .join(", ")
+}
+
+// The bytes instruction data starts with: Anchor dispatches on sha256("global:<name>")[..8],
+// native programs here on the variant index
+fn selector(instruction_name: &str, layout: &InstructionLayout) -> String {
+    let selector: Vec<u8> = if layout.is_anchor {
+        Sha256::digest(format!("global:{}", instruction_name).as_bytes())[..8].to_vec()
+    } else {
+        vec![layout.index as u8]
+    };
+    selector.iter().map(|byte| byte.to_string()).collect::<Vec<_>>().join(", ")
+}
+
+// A proptest strategy for the Borsh encoding of an argument of type `ty`, favouring what makes
+// loops over it run longest: integer extremes and collections of MAX_LEN. Types it can't
+// encode (the program's own structs) get raw bytes.
+fn borsh_strategy(ty: &str) -> String {
+    let ty = ty.trim();
+    if let Some((element, length)) = parse_array(ty) {
+        if let Ok(length) = length.trim().parse::<usize>() {
+            return format!("proptest::collection::vec({}, {}).prop_map(|items| items.concat())", borsh_strategy(element), length);
+        }
+    }
+    match split_generic(ty) {
+        ("bool", _) => "any::<bool>().prop_map(|v| vec![v as u8])".to_string(),
+        (integer @ ("u8" | "u16" | "u32" | "u64" | "u128" | "i8" | "i16" | "i32" | "i64" | "i128"), _) => {
+            format!("prop_oneof![Just({ty}::MAX), Just({ty}::MIN), any::<{ty}>()].prop_map(|v| v.to_le_bytes().to_vec())", ty = integer)
+        },
+        ("Pubkey", _) => "any::<[u8; 32]>().prop_map(|v| v.to_vec())".to_string(),
+        ("String", _) => "prop_oneof![3 => Just(MAX_LEN), 1 => 0..=MAX_LEN].prop_map(|n| [(n as u32).to_le_bytes().to_vec(), vec![b'a'; n]].concat())".to_string(),
+        ("Vec", params) if params.len() == 1 => {
+            let max_len = match borsh_size(params[0]) {
+                Some(size) if size > 1 => format!("MAX_LEN.min((MAX_DATA - SELECTOR.len() - 4) / {})", size),
+                _ => "MAX_LEN".to_string(),
+            };
+            let element = borsh_strategy(params[0]);
+            format!("prop_oneof![3 => proptest::collection::vec({element}, {max_len}), 1 => proptest::collection::vec({element}, 0..={max_len})]\
+                .prop_map(|items| [(items.len() as u32).to_le_bytes().to_vec(), items.concat()].concat())", element = element, max_len = max_len)
+        },
+        ("Option", params) if params.len() == 1 => {
+            format!("prop_oneof![Just(vec![0u8]), {}.prop_map(|v| [vec![1u8], v].concat())]", borsh_strategy(params[0]))
+        },
+        ("Box", params) if params.len() == 1 => borsh_strategy(params[0]),
+        _ => "proptest::collection::vec(any::<u8>(), 0..=MAX_LEN)".to_string(),
+    }
+}
+
+// Encoded size of a fixed-size type
+fn borsh_size(ty: &str) -> Option<usize> {
+    let ty = ty.trim();
+    if let Some((element, length)) = parse_array(ty) {
+        return Some(borsh_size(element)? * length.trim().parse::<usize>().ok()?);
+    }
+    match split_generic(ty).0 {
+        "bool" | "u8" | "i8" => Some(1),
+        "u16" | "i16" => Some(2),
+        "u32" | "i32" => Some(4),
+        "u64" | "i64" => Some(8),
+        "u128" | "i128" => Some(16),
+        "Pubkey" => Some(32),
+        _ => None,
+    }
+}
+
+// What each error line means; the first matching rule wins, so "panicked at 'attempt to
+// add with overflow'" is an overflow rather than a plain panic
+fn classify_error(line: &str) -> (FuzzFindingKind, BugSeverity) {
+    let lower = line.to_lowercase();
+    if lower.contains(ACCEPTED_ORDERING) {
+        (FuzzFindingKind::AccountOrderingAccepted, BugSeverity::High)
+    } else if lower.contains(LAMPORTS_MINTED) {
+        (FuzzFindingKind::LamportImbalance, BugSeverity::Critical)
+    } else if lower.contains(LAMPORTS_DESTROYED) {
+        (FuzzFindingKind::LamportImbalance, BugSeverity::High)
+    } else if lower.contains(COMPUTE_BUDGET_EXCEEDED) {
+        (FuzzFindingKind::ComputeExhaustion, BugSeverity::High)
+    } else if lower.contains(COMPUTE_BUDGET_NEARLY_EXHAUSTED) {
+        (FuzzFindingKind::ComputeExhaustion, BugSeverity::Medium)
+    } else if lower.contains("overflow") || lower.contains("underflow") {
+        (FuzzFindingKind::ArithmeticOverflow, BugSeverity::High)
+    } else if lower.contains("validation failed") || lower.contains("constraint") || lower.contains("missingrequiredsignature") {
+        (FuzzFindingKind::ConstraintBypass, BugSeverity::High)
+    } else if lower.contains("timed out") {
+        (FuzzFindingKind::Timeout, BugSeverity::Low)
+    } else if lower.starts_with("error[e") || lower.starts_with("error:") {
+        // rustc and cargo diagnostics: the harness or program didn't build
+        (FuzzFindingKind::BuildError, BugSeverity::Low)
+    } else {
+        (FuzzFindingKind::Panic, BugSeverity::Medium)
+    }
+}
+
+pub fn classify_errors(errors: &[String], timed_out: bool) -> Vec<FuzzFinding> {
+    let mut findings: Vec<FuzzFinding> = errors.iter().map(|line| {
+        let (kind, severity) = classify_error(line);
+        FuzzFinding { kind, severity, message: line.clone() }
+    }).collect();
+    if timed_out && !findings.iter().any(|f| f.kind == FuzzFindingKind::Timeout) {
+        findings.push(FuzzFinding {
+            kind: FuzzFindingKind::Timeout,
+            severity: BugSeverity::Low,
+            message: "Fuzzing run exceeded its time limit".to_string(),
+        });
+    }
+    findings
+}
+
+pub fn summarize_findings(findings: &[FuzzFinding]) -> FuzzSummary {
+    let mut summary = FuzzSummary { total: findings.len() as u32, ..Default::default() };
+    for finding in findings {
+        match finding.severity {
+            BugSeverity::Critical => summary.critical += 1,
+            BugSeverity::High => summary.high += 1,
+            BugSeverity::Medium => summary.medium += 1,
+            BugSeverity::Low => summary.low += 1,
+        }
+        *summary.by_kind.entry(finding.kind).or_default() += 1;
+    }
+    summary
+}
+
+// Error-level compiler messages from `cargo --message-format=json` output
+fn parse_compiler_errors(output: &str) -> Vec<CompilerDiagnostic> {
+    output.lines()
+        .filter_map(|line| serde_json::from_str::<serde_json::Value>(line).ok())
+        .filter(|json| json.get("reason").and_then(|r| r.as_str()) == Some("compiler-message"))
+        .filter_map(|json| {
+            let message = json.get("message")?;
+            let level = message.get("level")?.as_str()?;
+            if !level.starts_with("error") {
+                return None;
+            }
+            let text = message.get("message")?.as_str()?;
+            // rustc's closing "aborting due to N previous errors" isn't a diagnostic of its own
+            if text.starts_with("aborting due to") {
+                return None;
+            }
+            let span = message.get("spans")
+                .and_then(|s| s.as_array())
+                .and_then(|spans| spans.iter().find(|s| s.get("is_primary").and_then(|p| p.as_bool()) == Some(true)));
+            Some(CompilerDiagnostic {
+                level: level.to_string(),
+                code: message.pointer("/code/code").and_then(|c| c.as_str()).map(|c| c.to_string()),
+                message: text.to_string(),
+                file: span.and_then(|s| s.get("file_name")).and_then(|f| f.as_str()).map(|f| f.to_string()),
+                line: span.and_then(|s| s.get("line_start")).and_then(|l| l.as_u64()).map(|l| l as u32),
+                column: span.and_then(|s| s.get("column_start")).and_then(|c| c.as_u64()).map(|c| c as u32),
+                rendered: message.get("rendered").and_then(|r| r.as_str()).map(|r| r.to_string()),
+            })
+        })
+        .collect()
+}
+
+// SAFEX_SPL_PROGRAMS_DIR holds SPL program builds for the harnesses: spl_token.so,
+// spl_token_2022.so and spl_associated_token_account.so. Unset, the program test's own apply.
+pub fn spl_programs_dir() -> Option<PathBuf> {
+    env::var("SAFEX_SPL_PROGRAMS_DIR").ok().map(PathBuf::from)
+}
+
+// The SPL programs SAFEX_SPL_PROGRAMS_DIR has builds of
+fn spl_programs() -> Vec<&'static str> {
+    let Some(dir) = spl_programs_dir() else {
+        return Vec::new();
+    };
+    SPL_PROGRAMS.iter()
+        .map(|(name, _)| *name)
+        .filter(|name| dir.join(format!("{}.so", name)).is_file())
+        .collect()
+}
+
+// The longest timeout_seconds `actor` may ask for: SAFEX_FUZZ_MAX_TIMEOUT_SECS for
+// /api/fuzz-test and SAFEX_FUZZ_JOB_MAX_TIMEOUT_SECS for queued fuzz jobs, lowered for the API
+// keys SAFEX_FUZZ_TIMEOUT_CAPS="<key name>:<seconds>,..." lists
+pub fn max_fuzz_timeout(actor: &str, queued: bool) -> u64 {
+    let (var, default) = match queued {
+        true => ("SAFEX_FUZZ_JOB_MAX_TIMEOUT_SECS", MAX_FUZZ_JOB_TIMEOUT_SECS),
+        false => ("SAFEX_FUZZ_MAX_TIMEOUT_SECS", MAX_FUZZ_TIMEOUT_SECS),
+    };
+    let deployment = env::var(var).ok().and_then(|secs| secs.parse().ok()).unwrap_or(default);
+    let caps = env::var("SAFEX_FUZZ_TIMEOUT_CAPS").unwrap_or_default();
+    let cap = caps.split(',')
+        .filter_map(|entry| entry.trim().split_once(':'))
+        .find(|(name, _)| *name == actor)
+        .and_then(|(_, secs)| match secs.trim().parse::<u64>() {
+            Ok(secs) => Some(secs),
+            Err(_) => {
+                println!("Warning: Ignoring malformed SAFEX_FUZZ_TIMEOUT_CAPS entry for {}", actor);
+                None
+            }
+        });
+    cap.map_or(deployment, |cap| cap.min(deployment))
+}
+
+// What this host can fuzz with. proptest harnesses only need cargo; honggfuzz needs
+// honggfuzz-rs's `cargo hfuzz`, probed the first time it's asked about.
+pub fn fuzz_engines() -> Vec<FuzzEngineCapability> {
+    FuzzEngine::ALL.iter().map(|&engine| {
+        let version = match engine {
+            FuzzEngine::Proptest => None,
+            FuzzEngine::Honggfuzz => honggfuzz_version(),
+        };
+        FuzzEngineCapability {
+            engine,
+            available: engine == FuzzEngine::Proptest || version.is_some(),
+            version,
+            strategies: engine.strategies().to_vec(),
+        }
+    }).collect()
+}
+
+pub fn engine_available(engine: FuzzEngine) -> bool {
+    match engine {
+        FuzzEngine::Proptest => true,
+        FuzzEngine::Honggfuzz => honggfuzz_version().is_some(),
+    }
+}
+
+fn honggfuzz_version() -> Option<String> {
+    HONGGFUZZ_VERSION.get_or_init(|| {
+        let output = Command::new("cargo").args(["hfuzz", "version"]).stdin(Stdio::null()).output().ok()?;
+        let version = String::from_utf8_lossy(&output.stdout).lines().next().map(|line| line.trim().to_string());
+        version.filter(|line| output.status.success() && !line.is_empty())
+    }).clone()
+}
+
+// The harness's dependencies, pinned to the target repo's resolved versions. Requirements come
+// from the repository, so they're written as TOML strings rather than pasted in.
+fn harness_dependencies(toolchain: &ToolchainSelection) -> String {
+    let mut dependencies = String::new();
+    for (name, requirement) in &toolchain.harness_dependencies {
+        let requirement = toml::Value::String(requirement.clone());
+        if name == "anchor-lang" {
+            dependencies.push_str(&format!("{} = {{ version = {}, optional = true }}\n", name, requirement));
+        } else {
+            dependencies.push_str(&format!("{} = {}\n", name, requirement));
+        }
+    }
+    dependencies
+}
+
+// The most instruction data that fits in a transaction with the instruction's accounts. A
+// transaction is at most 1232 bytes: the payer's and other signatures, the payer's, the
+// accounts' and both programs' keys, the blockhash and some framing.
+fn max_instruction_data(layout: &InstructionLayout) -> usize {
+    let signers = 1 + layout.accounts.iter().filter(|slot| slot.signer).count();
+    1232usize.saturating_sub(64 * signers + 32 * (layout.accounts.len() + 3) + 32 + 32)
+}
+
+// Copy honggfuzz's crashing inputs and report from its workspace into crashes/
+fn collect_honggfuzz_crashes(test_dir: &Path) -> Result<Vec<String>> {
+    let Ok(entries) = fs::read_dir(test_dir.join("hfuzz_workspace").join(HFUZZ_TARGET)) else {
+        return Ok(Vec::new());
+    };
+    let crashes_dir = test_dir.join("crashes");
+    let mut crashes = Vec::new();
+    for entry in entries.flatten() {
+        let name = entry.file_name().to_string_lossy().to_string();
+        if !name.ends_with(".fuzz") && !name.starts_with("HONGGFUZZ.REPORT") {
+            continue;
+        }
+        fs::create_dir_all(&crashes_dir)?;
+        fs::copy(entry.path(), crashes_dir.join(&name))?;
+        crashes.push(name);
+    }
+    crashes.sort();
+    Ok(crashes)
+}
+
+// Like Command::output, but the process is killed at the deadline; also returns whether it was
+fn output_until(command: &mut Command, deadline: Instant) -> std::io::Result<(Output, bool)> {
+    let mut child = command.stdin(Stdio::null()).stdout(Stdio::piped()).stderr(Stdio::piped()).spawn()?;
+    // Read as it runs so a full pipe can't stall it
+    let (stdout, stderr) = (drain(child.stdout.take()), drain(child.stderr.take()));
+    let mut killed = false;
+    let status = loop {
+        if let Some(status) = child.try_wait()? {
+            break status;
+        }
+        if Instant::now() > deadline {
+            let _ = child.kill();
+            killed = true;
+            break child.wait()?;
+        }
+        std::thread::sleep(Duration::from_millis(200));
+    };
+    // Processes the killed one started may still hold its pipes
+    let collect = |output: Receiver<Vec<u8>>| if killed { output.recv_timeout(PIPE_GRACE).unwrap_or_default() } else { output.recv().unwrap_or_default() };
+    Ok((Output { status, stdout: collect(stdout), stderr: collect(stderr) }, killed))
+}
+
+fn drain(pipe: Option<impl Read + Send + 'static>) -> Receiver<Vec<u8>> {
+    let (sender, output) = mpsc::channel();
+    std::thread::spawn(move || {
+        let mut buffer = Vec::new();
+        if let Some(mut pipe) = pipe {
+            let _ = pipe.read_to_end(&mut buffer);
+        }
+        let _ = sender.send(buffer);
+    });
+    output
+}
+
+// SAFEX_HARNESS_CACHE_DIR holds compiled harness binaries (default ./harness-cache)
+pub fn harness_cache_dir() -> PathBuf {
+    PathBuf::from(env::var("SAFEX_HARNESS_CACHE_DIR").unwrap_or_else(|_| "harness-cache".to_string()))
+}
+
+fn harness_cache_key(commit: &str, instruction_name: &str, harness: &str) -> String {
+    let key = format!("{}\0{}\0{}\0{}", commit, instruction_name, harness, HARNESS_TEMPLATE_VERSION);
+    format!("{:x}", Sha256::digest(key.as_bytes()))[..16].to_string()
+}
+
+// Copy then rename, so a concurrent run never executes a partially written binary
+fn store_harness(built: &Path, cached: &Path) -> Result<()> {
+    let dir = cached.parent().ok_or_else(|| anyhow!("Invalid cache path"))?;
+    fs::create_dir_all(dir)?;
+    let staging = dir.join(format!(".harness.{}", uuid::Uuid::new_v4()));
+    fs::copy(built, &staging)?;
+    fs::rename(&staging, cached)?;
+    Ok(())
+}
+
+// SAFEX_FUZZ_CORPUS_DIR keeps fuzz corpora between runs (default ./fuzz-corpus)
+pub fn fuzz_corpus_dir() -> PathBuf {
+    PathBuf::from(env::var("SAFEX_FUZZ_CORPUS_DIR").unwrap_or_else(|_| "fuzz-corpus".to_string()))
+}
+
+// Put the kept corpus where the harness reads it; returns how many cases it holds
+fn restore_corpus(corpus_file: &Path, persisted: &Path) -> Result<u32> {
+    let Ok(corpus) = fs::read_to_string(corpus_file) else {
+        return Ok(0);
+    };
+    fs::create_dir_all(persisted.parent().ok_or_else(|| anyhow!("Invalid corpus path"))?)?;
+    fs::write(persisted, &corpus)?;
+    Ok(corpus.lines().filter(|line| line.starts_with("cc ")).count() as u32)
+}
+
+// Keep the harness's persisted failures, old and new, for the next run; staged like harnesses
+fn save_corpus(persisted: &Path, corpus_file: &Path) -> Result<()> {
+    if !persisted.is_file() {
+        return Ok(());
+    }
+    let dir = corpus_file.parent().ok_or_else(|| anyhow!("Invalid corpus path"))?;
+    fs::create_dir_all(dir)?;
+    let staging = dir.join(format!(".corpus.{}", uuid::Uuid::new_v4()));
+    fs::copy(persisted, &staging)?;
+    fs::rename(&staging, corpus_file)?;
+    Ok(())
+}
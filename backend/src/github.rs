@@ -1,19 +1,62 @@
+use actix_web::dev::Payload;
+use actix_web::{FromRequest, HttpRequest};
 use anyhow::{anyhow, Result};
 use reqwest::{Client, StatusCode};
 use std::time::Duration;
 use std::env;
 use std::fs;
-use std::path::Path;
-use base64;
-use git2::{Repository, FetchOptions};
+use std::future::{ready, Ready};
+use std::path::{Component, Path, PathBuf};
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
+use std::sync::Arc;
+use git2::{FetchOptions, Repository};
+use git2::build::RepoBuilder;
 use tempfile::TempDir;
 use toml::Table;
 
-use crate::models::{GitHubRepo, GitHubContent};
+use crate::models::{CloneProgress, GitHubQuota, GitHubRepo, GitHubContent, GitHubTree, ProjectType};
+use crate::metadata_cache::{EntryKind, MetadataCache};
+use crate::repo_url::RepoUrl;
+use crate::vendor::is_vendored_crate;
 
 pub struct GitHubClient {
     client: Client,
     token: Option<String>,
+    cache: Option<Arc<MetadataCache>>,
+    // The token came with the request rather than from GITHUB_TOKEN
+    request_token: bool,
+    // Public and private OpenSSH key clones authenticate with instead of the ssh-agent
+    deploy_key: Option<(String, String)>,
+    clone_progress: Option<CloneProgressSink>,
+}
+
+// Told how far a clone (or submodule fetch) has got, as git2 reports it
+pub type CloneProgressSink = Arc<dyn Fn(&CloneProgress) + Send + Sync>;
+
+// A GitHub token a request brings for itself in the X-GitHub-Token header, so multi-user
+// frontends can act with each user's permissions. It lives only as long as the request:
+// it is never stored, and what it fetches stays out of the shared caches.
+pub struct RequestToken(pub Option<String>);
+
+impl RequestToken {
+    pub const HEADER: &'static str = "X-GitHub-Token";
+    
+    pub fn as_deref(&self) -> Option<&str> {
+        self.0.as_deref()
+    }
+}
+
+impl FromRequest for RequestToken {
+    type Error = actix_web::Error;
+    type Future = Ready<Result<Self, Self::Error>>;
+    
+    fn from_request(req: &HttpRequest, _payload: &mut Payload) -> Self::Future {
+        let token = req.headers().get(Self::HEADER)
+            .and_then(|value| value.to_str().ok())
+            .map(|value| value.trim().to_string())
+            .filter(|value| !value.is_empty());
+        ready(Ok(RequestToken(token)))
+    }
 }
 
 impl GitHubClient {
@@ -31,56 +74,266 @@ impl GitHubClient {
             println!("No GitHub token found, using unauthenticated requests (rate limited)");
         }
         
-        Self { client, token }
+        Self { client, token, cache: None, request_token: false, deploy_key: None, clone_progress: None }
+    }
+    
+    // Serve repository metadata and directory listings from `cache` while fresh. Not for
+    // request tokens, which may see private repositories other callers can't.
+    pub fn with_cache(mut self, cache: Arc<MetadataCache>) -> Self {
+        if !self.request_token {
+            self.cache = Some(cache);
+        }
+        self
+    }
+    
+    // Clone over ssh with a repository deploy key
+    pub fn with_deploy_key(mut self, public_key: String, private_key: String) -> Self {
+        self.deploy_key = Some((public_key, private_key));
+        self
+    }
+    
+    // Report the transfer progress of clones to `sink`
+    pub fn with_clone_progress(mut self, sink: CloneProgressSink) -> Self {
+        self.clone_progress = Some(sink);
+        self
+    }
+    
+    // Where to clone `repo_url` from; deploy keys only authenticate over ssh
+    pub fn remote_url(&self, repo_url: &RepoUrl) -> String {
+        match self.deploy_key {
+            Some(_) => repo_url.ssh_url(),
+            None => repo_url.clone_url().to_string(),
+        }
+    }
+    
+    // Act with the request's own token, when it brought one, instead of GITHUB_TOKEN
+    pub fn with_request_token(mut self, token: &RequestToken) -> Self {
+        if let Some(token) = token.as_deref() {
+            self.token = Some(token.to_string());
+            self.request_token = true;
+            self.cache = None;
+        }
+        self
+    }
+    
+    // Act without any token, so only public repositories are reachable
+    pub fn without_token(mut self) -> Self {
+        self.token = None;
+        self.request_token = false;
+        self
+    }
+    
+    pub fn has_token(&self) -> bool {
+        self.token.is_some()
     }
     
     // Clone a repository to a specific path
+    #[tracing::instrument(name = "git.clone", skip(self))]
     pub fn clone_repo(&self, repo_url: &str, target_path: &Path) -> Result<()> {
         println!("Cloning repository: {} to {}", repo_url, target_path.display());
         
-        // Set up fetch options (use token if available)
-        let mut fetch_opts = FetchOptions::new();
-        if let Some(_token) = &self.token {
-            // For authenticated cloning if needed
-            fetch_opts.remote_callbacks(git2::RemoteCallbacks::new());
-        }
+        Self::validate_clone_url(repo_url)?;
         
-        // Clone the repository
-        let _repo = match Repository::clone(repo_url, target_path) {
+        // Clone the repository. SAFEX_MAX_REPO_BYTES caps it and its submodules together.
+        let downloaded = Arc::new(AtomicU64::new(0));
+        let oversized = Arc::new(AtomicBool::new(false));
+        let repo = match RepoBuilder::new().fetch_options(self.fetch_options(&downloaded, &oversized)).clone(repo_url, target_path) {
             Ok(repo) => repo,
+            Err(_) if oversized.load(Ordering::Relaxed) => {
+                return Err(anyhow!("Repository is larger than the {} bytes allowed (SAFEX_MAX_REPO_BYTES)", max_repo_bytes()));
+            }
             Err(e) => {
                 return Err(anyhow!("Failed to clone repository: {}", e));
             }
         };
         
+        // Anchor projects often vendor shared libraries as submodules
+        self.update_submodules(&repo, &downloaded)?;
+        
+        // Path dependencies that escape the clone can't be satisfied as-is
+        let unresolved = self.resolve_path_dependencies(target_path)?;
+        for dependency in &unresolved {
+            println!("Warning: Unresolved path dependency: {}", dependency);
+        }
+        
         Ok(())
     }
     
-    // Clone a repository and check if it's an Anchor project
-    pub fn clone_and_validate_anchor_project(&self, repo_url: &str) -> Result<bool> {
+    // Set up fetch options (token for https GitHub URLs, ssh-agent for ssh remotes). The transfer adds
+    // what it receives to `downloaded`, and `oversized` is set when it's cut off for taking
+    // that past SAFEX_MAX_REPO_BYTES.
+    fn fetch_options(&self, downloaded: &Arc<AtomicU64>, oversized: &Arc<AtomicBool>) -> FetchOptions<'static> {
+        let token = self.token.clone();
+        let deploy_key = self.deploy_key.clone();
+        let mut callbacks = git2::RemoteCallbacks::new();
+        let mut attempts = 0;
+        callbacks.credentials(move |url, username_from_url, allowed_types| {
+            // libgit2 keeps asking while credentials are rejected; give up after a few tries
+            attempts += 1;
+            if attempts > 3 {
+                return Err(git2::Error::from_str("Authentication failed for repository"));
+            }
+            if let (true, Some((public_key, private_key))) = (allowed_types.contains(git2::CredentialType::SSH_KEY), &deploy_key) {
+                git2::Cred::ssh_key_from_memory(username_from_url.unwrap_or("git"), Some(public_key), private_key, None)
+            } else if allowed_types.contains(git2::CredentialType::SSH_KEY) {
+                git2::Cred::ssh_key_from_agent(username_from_url.unwrap_or("git"))
+            } else if let (true, Some(token)) = (Self::is_github_https_url(url), &token) {
+                git2::Cred::userpass_plaintext(token, "x-oauth-basic")
+            } else {
+                git2::Cred::default()
+            }
+        });
+        // Oversized repositories are cut off as soon as they pass the cap rather than after
+        // they're fully downloaded. git2 reports every few KB; only changes in the
+        // percentage, and the end of indexing, are passed on.
+        let max_bytes = max_repo_bytes();
+        let downloaded = downloaded.clone();
+        let oversized = oversized.clone();
+        let sink = self.clone_progress.clone();
+        let mut reported = None;
+        let mut received = 0;
+        callbacks.transfer_progress(move |stats| {
+            let delta = (stats.received_bytes() as u64).saturating_sub(received);
+            received += delta;
+            let total = downloaded.fetch_add(delta, Ordering::Relaxed) + delta;
+            if total > max_bytes {
+                oversized.store(true, Ordering::Relaxed);
+                return false;
+            }
+            if let Some(sink) = &sink {
+                let progress = CloneProgress {
+                    received_objects: stats.received_objects(),
+                    total_objects: stats.total_objects(),
+                    indexed_objects: stats.indexed_objects(),
+                    received_bytes: stats.received_bytes() as u64,
+                    percent: (stats.received_objects() * 100).checked_div(stats.total_objects()).unwrap_or(0) as u8,
+                };
+                let state = (progress.percent, progress.indexed_objects == progress.total_objects);
+                if reported != Some(state) {
+                    reported = Some(state);
+                    sink(&progress);
+                }
+            }
+            true
+        });
+        let mut fetch_opts = FetchOptions::new();
+        fetch_opts.remote_callbacks(callbacks);
+        fetch_opts
+    }
+    
+    // Initialize and update submodules recursively
+    #[tracing::instrument(name = "git.update_submodules", skip_all)]
+    fn update_submodules(&self, repo: &Repository, downloaded: &Arc<AtomicU64>) -> Result<()> {
+        for mut submodule in repo.submodules()? {
+            let name = submodule.name().unwrap_or("<unnamed>").to_string();
+            
+            // Relative URLs resolve against the parent remote; anything else must be a remote transport
+            let url = submodule.url().unwrap_or("").to_string();
+            if !url.starts_with("../") && !url.starts_with("./") && Self::validate_clone_url(&url).is_err() {
+                println!("Warning: Skipping submodule {} with unsupported URL: {}", name, url);
+                continue;
+            }
+            
+            println!("Updating submodule: {}", name);
+            let mut update_opts = git2::SubmoduleUpdateOptions::new();
+            let oversized = Arc::new(AtomicBool::new(false));
+            update_opts.fetch(self.fetch_options(downloaded, &oversized));
+            if let Err(e) = submodule.update(true, Some(&mut update_opts)) {
+                if oversized.load(Ordering::Relaxed) {
+                    return Err(anyhow!("Repository and its submodules are larger than the {} bytes allowed (SAFEX_MAX_REPO_BYTES)", max_repo_bytes()));
+                }
+                println!("Warning: Failed to update submodule {}: {}", name, e);
+                continue;
+            }
+            
+            if let Ok(sub_repo) = submodule.open() {
+                self.update_submodules(&sub_repo, downloaded)?;
+            }
+        }
+        
+        Ok(())
+    }
+    
+    // Rewrite path dependencies that point outside the clone (or at missing directories)
+    // to their registry version when one is declared; returns the ones that can't be resolved
+    pub fn resolve_path_dependencies(&self, repo_root: &Path) -> Result<Vec<String>> {
+        let root = repo_root.canonicalize()?;
+        let mut unresolved = Vec::new();
+        
+        for cargo_path in self.find_cargo_toml_files(&root)? {
+            let cargo_path = Path::new(&cargo_path);
+            let manifest_dir = cargo_path.parent().unwrap_or(&root).to_path_buf();
+            let content = fs::read_to_string(cargo_path)?;
+            let mut cargo_toml: Table = match content.parse() {
+                Ok(toml) => toml,
+                Err(_) => continue,
+            };
+            
+            let mut changed = false;
+            let mut sections: Vec<&mut Table> = Vec::new();
+            for (key, value) in cargo_toml.iter_mut() {
+                match (key.as_str(), value.as_table_mut()) {
+                    ("dependencies" | "dev-dependencies" | "build-dependencies", Some(table)) => sections.push(table),
+                    ("workspace", Some(workspace)) => {
+                        if let Some(table) = workspace.get_mut("dependencies").and_then(|d| d.as_table_mut()) {
+                            sections.push(table);
+                        }
+                    },
+                    _ => {}
+                }
+            }
+            
+            for deps in sections {
+                for (name, spec) in deps.iter_mut() {
+                    let Some(spec) = spec.as_table_mut() else { continue };
+                    let Some(dep_path) = spec.get("path").and_then(|p| p.as_str()) else { continue };
+                    
+                    let resolved = normalize_path(&manifest_dir.join(dep_path));
+                    if resolved.starts_with(&root) && resolved.exists() {
+                        continue;
+                    }
+                    
+                    let relative_manifest = cargo_path.strip_prefix(&root).unwrap_or(cargo_path).display().to_string();
+                    if spec.contains_key("version") {
+                        println!("Falling back to registry version for {} in {}", name, relative_manifest);
+                        spec.remove("path");
+                        changed = true;
+                    } else {
+                        unresolved.push(format!("{}: {} -> {}", relative_manifest, name, dep_path));
+                    }
+                }
+            }
+            
+            if changed {
+                fs::write(cargo_path, toml::to_string(&cargo_toml)?)?;
+            }
+        }
+        
+        Ok(unresolved)
+    }
+    
+    // Clone a repository and detect whether it's an Anchor or native Solana program
+    pub fn clone_and_detect_project_type(&self, repo_url: &str) -> Result<Option<ProjectType>> {
         println!("Cloning repository: {}", repo_url);
         
         // Create a temporary directory for the clone
         let temp_dir = TempDir::new()?;
         let temp_path = temp_dir.path();
         
-        // Set up fetch options (use token if available)
-        let mut fetch_opts = FetchOptions::new();
-        if let Some(_token) = &self.token {
-            // For authenticated cloning if needed
-            fetch_opts.remote_callbacks(git2::RemoteCallbacks::new());
-        }
+        self.clone_repo(repo_url, temp_path)?;
         
-        // Clone the repository
-        let _repo = match Repository::clone(repo_url, temp_path) {
-            Ok(repo) => repo,
-            Err(e) => {
-                return Err(anyhow!("Failed to clone repository: {}", e));
-            }
-        };
-        
-        // Check if it's an Anchor project by looking for Cargo.toml with anchor-lang dependency
-        self.is_anchor_project(temp_path)
+        self.detect_project_type(temp_path)
+    }
+    
+    // Anchor takes precedence: Anchor programs also depend on solana-program transitively
+    pub fn detect_project_type(&self, repo_path: &Path) -> Result<Option<ProjectType>> {
+        if self.is_anchor_project(repo_path)? {
+            Ok(Some(ProjectType::Anchor))
+        } else if self.is_native_solana_project(repo_path)? {
+            Ok(Some(ProjectType::Native))
+        } else {
+            Ok(None)
+        }
     }
     
     // Check if a repository is an Anchor project
@@ -90,7 +343,7 @@ impl GitHubClient {
         
         // Check each Cargo.toml for anchor-lang dependency
         for cargo_path in cargo_paths {
-            if self.has_anchor_dependency(&cargo_path)? {
+            if self.has_dependency(&cargo_path, "anchor-lang")? {
                 return Ok(true);
             }
         }
@@ -98,6 +351,47 @@ impl GitHubClient {
         Ok(false)
     }
     
+    // Check for a plain solana-program crate that declares its own entrypoint
+    fn is_native_solana_project(&self, repo_path: &Path) -> Result<bool> {
+        let mut has_solana_program = false;
+        for cargo_path in self.find_cargo_toml_files(repo_path)? {
+            if self.has_dependency(&cargo_path, "solana-program")? {
+                has_solana_program = true;
+                break;
+            }
+        }
+        if !has_solana_program {
+            return Ok(false);
+        }
+        
+        self.contains_entrypoint_macro(repo_path)
+    }
+    
+    // Recursively look for an entrypoint!(...) invocation in Rust sources
+    fn contains_entrypoint_macro(&self, dir_path: &Path) -> Result<bool> {
+        for entry in fs::read_dir(dir_path)? {
+            let path = entry?.path();
+            if path.file_name()
+                .and_then(|name| name.to_str())
+                .is_some_and(|name| name.starts_with('.') || name == "target") {
+                continue;
+            }
+            
+            if path.is_dir() {
+                if !is_vendored_crate(&path) && self.contains_entrypoint_macro(&path)? {
+                    return Ok(true);
+                }
+            } else if path.extension().is_some_and(|ext| ext == "rs") {
+                let content = fs::read_to_string(&path).unwrap_or_default();
+                if content.contains("entrypoint!(") {
+                    return Ok(true);
+                }
+            }
+        }
+        
+        Ok(false)
+    }
+    
     // Find all Cargo.toml files in the repository recursively
     fn find_cargo_toml_files(&self, repo_path: &Path) -> Result<Vec<String>> {
         let mut cargo_files = Vec::new();
@@ -132,7 +426,8 @@ impl GitHubClient {
                 continue;
             }
             
-            if path.is_dir() {
+            // Vendored crates' manifests belong to their upstream packages
+            if path.is_dir() && !is_vendored_crate(&path) {
                 self.find_cargo_toml_recursive(&path, cargo_files)?;
             }
         }
@@ -140,8 +435,8 @@ impl GitHubClient {
         Ok(())
     }
     
-    // Check if a Cargo.toml file has anchor-lang dependency
-    fn has_anchor_dependency(&self, cargo_path: &str) -> Result<bool> {
+    // Check if a Cargo.toml file declares the given dependency
+    fn has_dependency(&self, cargo_path: &str, crate_name: &str) -> Result<bool> {
         let content = fs::read_to_string(cargo_path)?;
         
         // Parse TOML
@@ -153,10 +448,10 @@ impl GitHubClient {
             }
         };
         
-        // Check for anchor-lang in dependencies
+        // Check for the crate in dependencies
         if let Some(deps) = cargo_toml.get("dependencies") {
             if let Some(deps_table) = deps.as_table() {
-                if deps_table.contains_key("anchor-lang") {
+                if deps_table.contains_key(crate_name) {
                     return Ok(true);
                 }
             }
@@ -165,14 +460,22 @@ impl GitHubClient {
         Ok(false)
     }
 
-    pub async fn get_repo_from_url(&self, repo_url: &str) -> Result<GitHubRepo> {
+    pub async fn get_repo_from_url(&self, repo_url: &RepoUrl) -> Result<GitHubRepo> {
         // Extract owner and repo name from URL
-        let (owner, repo) = self.extract_owner_repo(repo_url)?;
+        let (owner, repo) = repo_url.owner_repo()
+            .ok_or_else(|| anyhow!("Not a GitHub repository URL: {}", repo_url))?;
         println!("Fetching repo: owner={}, repo={}", owner, repo);
         self.get_repo(owner, repo).await
     }
 
+    #[tracing::instrument(name = "github.get_repo", skip(self))]
     pub async fn get_repo(&self, owner: &str, repo: &str) -> Result<GitHubRepo> {
+        let full_name = format!("{}/{}", owner, repo);
+        if let Some(cached) = self.cache.as_ref().and_then(|c| c.get(&full_name, EntryKind::Repo, "repo")) {
+            println!("Using cached metadata for {}", full_name);
+            return Ok(cached);
+        }
+        
         let url = format!("https://api.github.com/repos/{}/{}", owner, repo);
         println!("Making API request to: {}", url);
         
@@ -211,7 +514,12 @@ impl GitHubClient {
         }
 
         match response.json::<GitHubRepo>().await {
-            Ok(repo_data) => Ok(repo_data),
+            Ok(repo_data) => {
+                if let Some(cache) = &self.cache {
+                    cache.put(&full_name, "repo", &repo_data);
+                }
+                Ok(repo_data)
+            },
             Err(e) => {
                 println!("Failed to parse GitHub response: {}", e);
                 Err(anyhow!("Failed to parse GitHub repository data: {}", e))
@@ -219,15 +527,22 @@ impl GitHubClient {
         }
     }
 
-    pub async fn get_repo_contents(&self, repo_url: &str, path: Option<&str>) -> Result<Vec<GitHubContent>> {
-        let (owner, repo) = self.extract_owner_repo(repo_url)?;
+    #[tracing::instrument(name = "github.get_repo_contents", skip(self, repo_url), fields(repo_url = %repo_url))]
+    pub async fn get_repo_contents(&self, repo_url: &RepoUrl, path: Option<&str>) -> Result<Vec<GitHubContent>> {
+        let (owner, repo) = repo_url.owner_repo()
+            .ok_or_else(|| anyhow!("Not a GitHub repository URL: {}", repo_url))?;
         let path = path.unwrap_or("");
+        let full_name = format!("{}/{}", owner, repo);
+        let cache_key = format!("contents:{}", path.trim_matches('/'));
+        if let Some(cached) = self.cache.as_ref().and_then(|c| c.get(&full_name, EntryKind::Listing, &cache_key)) {
+            return Ok(cached);
+        }
         
-        let url = format!("https://api.github.com/repos/{}/{}/contents/{}", owner, repo, path);
+        let url = Self::contents_url(&full_name, path)?;
         println!("Fetching repo contents: {}", url);
         
         let mut request = self.client
-            .get(&url)
+            .get(url)
             .header("User-Agent", "Safex-App")
             .header("Accept", "application/vnd.github.v3+json");
         
@@ -250,12 +565,16 @@ impl GitHubClient {
         }
         
         // GitHub API returns either an array (for directories) or a single object (for files)
-        if response.headers().get("content-type").map_or(false, |ct| ct.to_str().unwrap_or("").contains("application/json")) {
+        if response.headers().get("content-type").is_some_and(|ct| ct.to_str().unwrap_or("").contains("application/json")) {
             let text = response.text().await?;
             
             // Try to parse as array first
             match serde_json::from_str::<Vec<GitHubContent>>(&text) {
                 Ok(contents) => {
+                    // Only directory listings are cached; file bodies can be large
+                    if let Some(cache) = &self.cache {
+                        cache.put(&full_name, &cache_key, &contents);
+                    }
                     return Ok(contents);
                 },
                 Err(_) => {
@@ -269,16 +588,21 @@ impl GitHubClient {
                                     // Remove whitespace and newlines from base64 content
                                     let clean_content = content.replace("\n", "");
                                     match base64::decode(&clean_content) {
-                                        Ok(decoded) => {
-                                            match String::from_utf8(decoded) {
-                                                Ok(text) => file.content = Some(text),
-                                                Err(_) => println!("Content is not valid UTF-8")
-                                            }
-                                        },
+                                        Ok(decoded) => set_file_content(&mut file, decoded),
                                         Err(e) => println!("Failed to decode base64: {}", e)
                                     }
                                 }
                             }
+                            // Files over 1 MB come back with empty content (encoding "none"); fetch them raw
+                            let truncated = file.content.as_deref().unwrap_or("").is_empty() && file.size.unwrap_or(0) > 0;
+                            if truncated && file.content_type == "file" {
+                                let max_bytes = max_file_bytes();
+                                if file.size.unwrap_or(0) > max_bytes {
+                                    return Err(anyhow!("File is {} bytes, over the {} byte limit; use /api/repo-file to download it", file.size.unwrap_or(0), max_bytes));
+                                }
+                                let raw = self.download_file(repo_url, &file.path, None, None).await?.bytes().await?;
+                                set_file_content(&mut file, raw.to_vec());
+                            }
                             return Ok(vec![file]);
                         },
                         Err(e) => return Err(anyhow!("Failed to parse GitHub content: {}", e)),
@@ -290,27 +614,373 @@ impl GitHubClient {
         Err(anyhow!("Unexpected response format from GitHub API"))
     }
     
-    fn extract_owner_repo<'a>(&self, repo_url: &'a str) -> Result<(&'a str, &'a str)> {
-        // Extract owner and repo name from URL
-        // Example: https://github.com/owner/repo
-        let url = repo_url.trim_end_matches('/');
-        let parts: Vec<&str> = url.split('/').collect();
-        
-        println!("URL parts: {:?}", parts);
-        
-        // Handle different URL formats
-        // Format 1: https://github.com/owner/repo
-        // Format 2: http://github.com/owner/repo
-        // Format 3: github.com/owner/repo
-        
-        if parts.len() >= 5 && (parts[2] == "github.com" || parts[2].contains("github")) {
-            // Full URL with https://
-            Ok((parts[3], parts[4]))
-        } else if parts.len() >= 3 && (parts[0] == "github.com" || parts[0].contains("github")) {
-            // URL without protocol
-            Ok((parts[1], parts[2]))
+    // Every path at `git_ref` with blob sizes, in one request
+    #[tracing::instrument(name = "github.get_tree", skip(self))]
+    pub async fn get_tree(&self, full_name: &str, git_ref: &str) -> Result<GitHubTree> {
+        let cache_key = format!("tree:{}", git_ref);
+        if let Some(cached) = self.cache.as_ref().and_then(|c| c.get(full_name, EntryKind::Listing, &cache_key)) {
+            return Ok(cached);
+        }
+        
+        let url = format!("https://api.github.com/repos/{}/git/trees/{}", full_name, git_ref);
+        let mut request = self.client
+            .get(&url)
+            .query(&[("recursive", "1")])
+            .header("User-Agent", "Safex-App")
+            .header("Accept", "application/vnd.github.v3+json");
+        if let Some(token) = &self.token {
+            request = request.header("Authorization", format!("token {}", token));
+        }
+        
+        let response = request.send().await
+            .map_err(|e| anyhow!("Failed to connect to GitHub API: {}", e))?;
+        let status = response.status();
+        if !status.is_success() {
+            let error_text = response.text().await.unwrap_or_else(|_| "Could not read error response".to_string());
+            return Err(anyhow!("GitHub API error: {} - {}", status, error_text));
+        }
+        let tree: GitHubTree = response.json().await
+            .map_err(|e| anyhow!("Failed to parse GitHub tree: {}", e))?;
+        if let Some(cache) = &self.cache {
+            cache.put(full_name, &cache_key, &tree);
+        }
+        Ok(tree)
+    }
+    
+    // The core API quota left for this client's token; checking it doesn't count against it
+    #[tracing::instrument(name = "github.rate_limit", skip(self))]
+    pub async fn rate_limit(&self) -> Result<GitHubQuota> {
+        let mut request = self.client
+            .get("https://api.github.com/rate_limit")
+            .header("User-Agent", "Safex-App")
+            .header("Accept", "application/vnd.github.v3+json");
+        if let Some(token) = &self.token {
+            request = request.header("Authorization", format!("token {}", token));
+        }
+        
+        let response = request.send().await
+            .map_err(|e| anyhow!("Failed to connect to GitHub API: {}", e))?;
+        let status = response.status();
+        if !status.is_success() {
+            let error_text = response.text().await.unwrap_or_else(|_| "Could not read error response".to_string());
+            return Err(anyhow!("GitHub API error: {} - {}", status, error_text));
+        }
+        let value: serde_json::Value = response.json().await
+            .map_err(|e| anyhow!("Failed to parse GitHub rate limit: {}", e))?;
+        let core = value.pointer("/resources/core").ok_or_else(|| anyhow!("Unexpected response format from GitHub API"))?;
+        let number = |name: &str| core.get(name).and_then(|v| v.as_u64()).unwrap_or(0);
+        Ok(GitHubQuota {
+            authenticated: self.token.is_some(),
+            limit: number("limit"),
+            remaining: number("remaining"),
+            used: number("used"),
+            resets_at: number("reset") as i64,
+        })
+    }
+    
+    // The contents API URL of `path` in `full_name`, each segment escaped. Dot and empty
+    // segments are refused, as they would resolve to other API endpoints.
+    fn contents_url(full_name: &str, path: &str) -> Result<reqwest::Url> {
+        // The root listing is `contents/`
+        let path = path.trim_matches('/');
+        let segments: Vec<&str> = path.split('/').collect();
+        if !path.is_empty() && segments.iter().any(|segment| matches!(*segment, "" | "." | "..")) {
+            return Err(anyhow!("Invalid repository path: {}", path));
+        }
+        let mut url = reqwest::Url::parse("https://api.github.com/repos")?;
+        url.path_segments_mut()
+            .map_err(|_| anyhow!("GitHub API URL can't take a path"))?
+            .extend(full_name.split('/'))
+            .push("contents")
+            .extend(segments);
+        Ok(url)
+    }
+    
+    // Raw file download via the contents API, which serves files up to 100 MB (the JSON
+    // form stops including content at 1 MB). `range` is forwarded as a Range header.
+    #[tracing::instrument(name = "github.download_file", skip(self, repo_url), fields(repo_url = %repo_url))]
+    pub async fn download_file(&self, repo_url: &RepoUrl, path: &str, git_ref: Option<&str>, range: Option<&str>) -> Result<reqwest::Response> {
+        let (owner, repo) = repo_url.owner_repo()
+            .ok_or_else(|| anyhow!("Not a GitHub repository URL: {}", repo_url))?;
+        let url = Self::contents_url(&format!("{}/{}", owner, repo), path)?;
+        println!("Downloading raw file: {}", url);
+        
+        let mut request = self.client
+            .get(url)
+            .timeout(Duration::from_secs(300))
+            .header("User-Agent", "Safex-App")
+            .header("Accept", "application/vnd.github.raw");
+        if let Some(git_ref) = git_ref {
+            request = request.query(&[("ref", git_ref)]);
+        }
+        if let Some(range) = range {
+            request = request.header("Range", range);
+        }
+        if let Some(token) = &self.token {
+            request = request.header("Authorization", format!("token {}", token));
+        }
+        
+        let response = request.send().await
+            .map_err(|e| anyhow!("Failed to connect to GitHub API: {}", e))?;
+        let status = response.status();
+        if !status.is_success() {
+            let error_text = response.text().await.unwrap_or_else(|_| "Could not read error response".to_string());
+            return Err(anyhow!("GitHub API error: {} - {}", status, error_text));
+        }
+        Ok(response)
+    }
+    
+    // Authenticated JSON call for the write endpoints used to open fix pull requests
+    async fn api_json(&self, method: reqwest::Method, path: &str, body: Option<serde_json::Value>) -> Result<serde_json::Value> {
+        let token = self.token.as_ref().ok_or_else(|| anyhow!("GITHUB_TOKEN is required to write to GitHub"))?;
+        let url = format!("https://api.github.com/{}", path);
+        let mut request = self.client
+            .request(method, &url)
+            .header("User-Agent", "Safex-App")
+            .header("Accept", "application/vnd.github.v3+json")
+            .header("Authorization", format!("token {}", token));
+        if let Some(body) = body {
+            request = request.json(&body);
+        }
+        
+        let response = request.send().await
+            .map_err(|e| anyhow!("Failed to connect to GitHub API: {}", e))?;
+        let status = response.status();
+        let value: serde_json::Value = response.json().await.unwrap_or(serde_json::Value::Null);
+        if !status.is_success() {
+            let message = value.get("message").and_then(|m| m.as_str()).unwrap_or("unknown error");
+            return Err(anyhow!("GitHub API error: {} - {}", status, message));
+        }
+        Ok(value)
+    }
+    
+    // Fork `owner/repo` into the token's account, waiting until `branch` is readable in the fork
+    #[tracing::instrument(name = "github.create_fork", skip(self))]
+    pub async fn create_fork(&self, owner: &str, repo: &str, branch: &str) -> Result<String> {
+        let fork = self.api_json(reqwest::Method::POST, &format!("repos/{}/{}/forks", owner, repo), Some(serde_json::json!({}))).await?;
+        let full_name = fork.get("full_name").and_then(|n| n.as_str())
+            .ok_or_else(|| anyhow!("GitHub did not return the fork name"))?
+            .to_string();
+        
+        // Forks are created asynchronously
+        for _ in 0..15 {
+            if self.branch_head(&full_name, branch).await.is_ok() {
+                // Best-effort: bring an existing, older fork up to date
+                let _ = self.api_json(reqwest::Method::POST, &format!("repos/{}/merge-upstream", full_name), Some(serde_json::json!({ "branch": branch }))).await;
+                return Ok(full_name);
+            }
+            tokio::time::sleep(Duration::from_secs(2)).await;
+        }
+        Err(anyhow!("Fork {} was not ready in time", full_name))
+    }
+    
+    pub async fn branch_head(&self, full_name: &str, branch: &str) -> Result<String> {
+        let reference = self.api_json(reqwest::Method::GET, &format!("repos/{}/git/ref/heads/{}", full_name, branch), None).await?;
+        reference.pointer("/object/sha").and_then(|s| s.as_str())
+            .map(|s| s.to_string())
+            .ok_or_else(|| anyhow!("Branch {} not found in {}", branch, full_name))
+    }
+    
+    // Whether `commit` is in base..head of `full_name`, either end included; a missing end
+    // doesn't bound the range
+    pub async fn commit_in_range(&self, full_name: &str, base: Option<&str>, head: Option<&str>, commit: &str) -> Result<bool> {
+        for (from, to) in [(base, commit), (Some(commit), head.unwrap_or(commit))] {
+            let Some(from) = from else { continue };
+            if from == to {
+                continue;
+            }
+            let comparison = self.api_json(reqwest::Method::GET, &format!("repos/{}/compare/{}...{}", full_name, from, to), None).await?;
+            if !matches!(comparison.get("status").and_then(|s| s.as_str()), Some("ahead") | Some("identical")) {
+                return Ok(false);
+            }
+        }
+        Ok(true)
+    }
+    
+    pub async fn create_branch(&self, full_name: &str, branch: &str, sha: &str) -> Result<()> {
+        self.api_json(reqwest::Method::POST, &format!("repos/{}/git/refs", full_name), Some(serde_json::json!({
+            "ref": format!("refs/heads/{}", branch),
+            "sha": sha,
+        }))).await?;
+        Ok(())
+    }
+    
+    // Text content and blob sha of a file at `git_ref`
+    pub async fn get_file(&self, full_name: &str, path: &str, git_ref: &str) -> Result<(String, String)> {
+        let file = self.api_json(reqwest::Method::GET, &format!("{}?ref={}", &Self::contents_url(full_name, path)?.path()[1..], git_ref), None).await?;
+        let sha = file.get("sha").and_then(|s| s.as_str()).ok_or_else(|| anyhow!("{} is not a file", path))?;
+        let content = file.get("content").and_then(|c| c.as_str()).unwrap_or("").replace('\n', "");
+        if content.is_empty() {
+            return Err(anyhow!("{} is too large to edit through the GitHub API", path));
+        }
+        let content = String::from_utf8(base64::decode(&content)?)
+            .map_err(|_| anyhow!("{} is not a text file", path))?;
+        Ok((content, sha.to_string()))
+    }
+    
+    // Commit new content for one file on `branch`; `sha` is the blob being replaced
+    #[tracing::instrument(name = "github.update_file", skip(self, content))]
+    pub async fn update_file(&self, full_name: &str, path: &str, branch: &str, message: &str, content: &str, sha: &str) -> Result<()> {
+        self.api_json(reqwest::Method::PUT, &Self::contents_url(full_name, path)?.path()[1..], Some(serde_json::json!({
+            "message": message,
+            "content": base64::encode(content),
+            "sha": sha,
+            "branch": branch,
+        }))).await?;
+        Ok(())
+    }
+    
+    // Open a pull request against `full_name`; `head` is "branch" or "fork_owner:branch"
+    #[tracing::instrument(name = "github.create_pull_request", skip(self, body))]
+    pub async fn create_pull_request(&self, full_name: &str, title: &str, head: &str, base: &str, body: &str) -> Result<String> {
+        let pull = self.api_json(reqwest::Method::POST, &format!("repos/{}/pulls", full_name), Some(serde_json::json!({
+            "title": title,
+            "head": head,
+            "base": base,
+            "body": body,
+            "maintainer_can_modify": true,
+        }))).await?;
+        pull.get("html_url").and_then(|u| u.as_str())
+            .map(|u| u.to_string())
+            .ok_or_else(|| anyhow!("GitHub did not return the pull request URL"))
+    }
+    
+    // Open an issue on `full_name`, returning its number and URL
+    #[tracing::instrument(name = "github.create_issue", skip(self, body))]
+    pub async fn create_issue(&self, full_name: &str, title: &str, body: &str, labels: &[String]) -> Result<(u64, String)> {
+        let issue = self.api_json(reqwest::Method::POST, &format!("repos/{}/issues", full_name), Some(serde_json::json!({
+            "title": title,
+            "body": body,
+            "labels": labels,
+        }))).await?;
+        let number = issue.get("number").and_then(|n| n.as_u64())
+            .ok_or_else(|| anyhow!("GitHub did not return the issue number"))?;
+        let url = issue.get("html_url").and_then(|u| u.as_str())
+            .ok_or_else(|| anyhow!("GitHub did not return the issue URL"))?;
+        Ok((number, url.to_string()))
+    }
+    
+    // Set a commit status; `state` is one of error, failure, pending or success
+    #[tracing::instrument(name = "github.create_commit_status", skip(self, description, target_url))]
+    pub async fn create_commit_status(&self, full_name: &str, sha: &str, state: &str, description: &str, target_url: Option<&str>, context: &str) -> Result<()> {
+        self.api_json(reqwest::Method::POST, &format!("repos/{}/statuses/{}", full_name, sha), Some(serde_json::json!({
+            "state": state,
+            "description": description,
+            "target_url": target_url,
+            "context": context,
+        }))).await?;
+        Ok(())
+    }
+    
+    // Only remote transports are accepted; local paths and file:// URLs would
+    // let a request read arbitrary directories on the server
+    pub fn validate_clone_url(repo_url: &str) -> Result<()> {
+        let url = repo_url.trim();
+        let is_scp_like = !url.contains("://") && url.contains('@') && url.contains(':');
+        if url.starts_with("https://") || url.starts_with("http://") || url.starts_with("ssh://") || is_scp_like {
+            if Self::url_host(url).is_none_or(|host| host.is_empty()) {
+                return Err(anyhow!("Repository URL has no host: {}", repo_url));
+            }
+            Ok(())
+        } else {
+            Err(anyhow!("Unsupported repository URL (expected https:// or ssh://): {}", repo_url))
+        }
+    }
+    
+    // Extract the host from https://, ssh:// or scp-like (git@host:path) URLs
+    pub fn url_host(repo_url: &str) -> Option<&str> {
+        let url = repo_url.trim();
+        let (rest, scp_like) = match url.find("://") {
+            Some(idx) => (&url[idx + 3..], false),
+            None => (url, true),
+        };
+        let authority = if scp_like {
+            rest.split(':').next()?
         } else {
-            Err(anyhow!("Invalid GitHub repository URL: {}", repo_url))
+            rest.split('/').next()?
+        };
+        // Strip user info and port
+        let host = authority.rsplit('@').next()?;
+        let host = if scp_like { host } else { host.split(':').next()? };
+        Some(host)
+    }
+    
+    // Where git may send the token: submodules of a GitHub repository can live anywhere, and
+    // are fetched with the same credentials callback as their parent
+    fn is_github_https_url(url: &str) -> bool {
+        reqwest::Url::parse(url).is_ok_and(|url| {
+            url.scheme() == "https" && matches!(url.host_str(), Some("github.com") | Some("www.github.com"))
+        })
+    }
+    
+    // Commit SHA checked out in a local clone
+    pub fn head_commit(repo_path: &Path) -> Option<String> {
+        let repo = Repository::open(repo_path).ok()?;
+        let commit = repo.head().ok()?.peel_to_commit().ok()?;
+        Some(commit.id().to_string())
+    }
+
+    // Detach a local clone's HEAD at a commit it already has
+    pub fn checkout_commit(repo_path: &Path, commit: &str) -> Result<()> {
+        let repo = Repository::open(repo_path)?;
+        let target = repo.revparse_single(commit)
+            .map_err(|e| anyhow!("Commit {} not found: {}", commit, e))?
+            .peel_to_commit()?;
+        repo.checkout_tree(target.as_object(), Some(git2::build::CheckoutBuilder::new().force()))?;
+        repo.set_head_detached(target.id())?;
+        Ok(())
+    }
+}
+
+// SAFEX_MAX_REPO_BYTES caps what a clone may download (default 2 GB)
+pub fn max_repo_bytes() -> u64 {
+    env::var("SAFEX_MAX_REPO_BYTES").ok().and_then(|v| v.parse().ok()).unwrap_or(2 * 1024 * 1024 * 1024)
+}
+
+// SAFEX_MAX_FILE_BYTES caps single-file downloads (default 50 MB)
+pub fn max_file_bytes() -> u64 {
+    env::var("SAFEX_MAX_FILE_BYTES").ok().and_then(|v| v.parse().ok()).unwrap_or(50 * 1024 * 1024)
+}
+
+// Text files are returned as UTF-8 ("utf-8" encoding); binaries stay base64
+fn set_file_content(file: &mut GitHubContent, bytes: Vec<u8>) {
+    match String::from_utf8(bytes) {
+        Ok(text) => {
+            file.content = Some(text);
+            file.encoding = Some("utf-8".to_string());
+        },
+        Err(e) => {
+            file.content = Some(base64::encode(e.into_bytes()));
+            file.encoding = Some("base64".to_string());
+        }
+    }
+}
+
+// Lexically resolve `.` and `..` components (the target may not exist, so no canonicalize)
+fn normalize_path(path: &Path) -> PathBuf {
+    let mut normalized = PathBuf::new();
+    for component in path.components() {
+        match component {
+            Component::ParentDir => { normalized.pop(); },
+            Component::CurDir => {},
+            other => normalized.push(other.as_os_str()),
         }
     }
-}
\ No newline at end of file
+    normalized
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn contents_url_stays_within_the_repository() {
+        let url = |path| GitHubClient::contents_url("owner/repo", path).map(|url| url.to_string());
+        assert_eq!(url("").unwrap(), "https://api.github.com/repos/owner/repo/contents/");
+        assert_eq!(url("/src/lib.rs").unwrap(), "https://api.github.com/repos/owner/repo/contents/src/lib.rs");
+        assert_eq!(url("a b/%2e%2e/c?d#e").unwrap(), "https://api.github.com/repos/owner/repo/contents/a%20b/%252e%252e/c%3Fd%23e");
+        for escaping in ["../../../../user", "src/../../other", "./x", "a//b"] {
+            assert!(url(escaping).is_err(), "{} was accepted", escaping);
+        }
+    }
+}
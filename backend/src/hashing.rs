@@ -0,0 +1,112 @@
+use anyhow::{anyhow, Result};
+use ring::rand::{SecureRandom, SystemRandom};
+use sha2::{Digest, Sha256};
+use sha3::Keccak256;
+use std::collections::BTreeMap;
+
+use crate::models::HashAlgorithm;
+
+pub const SALT_LEN: usize = 32;
+
+// A report's digests, computed in one pass over its content
+pub struct ReportDigest {
+    // What's logged on-chain and looked up by verification
+    pub sha256: [u8; 32],
+    // Hex digests by the additional algorithms asked for
+    pub alternatives: BTreeMap<HashAlgorithm, String>,
+    // Set when the report was hashed for confidential logging
+    pub commitment: Option<Commitment>,
+}
+
+// SHA256(salt || report), logged in place of a confidential report's hash. Without the salt it
+// can't be matched to the report, so publishing the report doesn't reveal it was logged.
+pub struct Commitment {
+    pub salt: [u8; SALT_LEN],
+    pub hash: [u8; 32],
+}
+
+// Hashes a report as it arrives, so a streamed upload is never held in memory
+pub struct ReportHasher {
+    sha256: Sha256,
+    blake3: Option<blake3::Hasher>,
+    keccak256: Option<Keccak256>,
+    salted: Option<([u8; SALT_LEN], Sha256)>,
+}
+
+impl ReportHasher {
+    pub fn new(algorithms: &[HashAlgorithm]) -> Self {
+        Self {
+            sha256: Sha256::new(),
+            blake3: algorithms.contains(&HashAlgorithm::Blake3).then(blake3::Hasher::new),
+            keccak256: algorithms.contains(&HashAlgorithm::Keccak256).then(Keccak256::new),
+            salted: None,
+        }
+    }
+
+    // Also compute a commitment under a fresh random salt
+    pub fn salted(mut self) -> Result<Self> {
+        let mut salt = [0u8; SALT_LEN];
+        SystemRandom::new().fill(&mut salt).map_err(|_| anyhow!("Failed to generate a salt"))?;
+        let mut hasher = Sha256::new();
+        hasher.update(salt);
+        self.salted = Some((salt, hasher));
+        Ok(self)
+    }
+
+    pub fn update(&mut self, bytes: &[u8]) {
+        self.sha256.update(bytes);
+        if let Some((_, hasher)) = &mut self.salted {
+            hasher.update(bytes);
+        }
+        if let Some(hasher) = &mut self.blake3 {
+            hasher.update(bytes);
+        }
+        if let Some(hasher) = &mut self.keccak256 {
+            hasher.update(bytes);
+        }
+    }
+
+    pub fn finish(self) -> ReportDigest {
+        let mut alternatives = BTreeMap::new();
+        if let Some(hasher) = self.blake3 {
+            alternatives.insert(HashAlgorithm::Blake3, hex(blake3::Hasher::finalize(&hasher).as_bytes()));
+        }
+        if let Some(hasher) = self.keccak256 {
+            alternatives.insert(HashAlgorithm::Keccak256, hex(&hasher.finalize()));
+        }
+        ReportDigest {
+            sha256: self.sha256.finalize().into(),
+            alternatives,
+            commitment: self.salted.map(|(salt, hasher)| Commitment { salt, hash: hasher.finalize().into() }),
+        }
+    }
+}
+
+// The commitment a confidential report was logged under, from the salt it was given
+pub fn commit_report(salt: &[u8], content: &[u8]) -> [u8; 32] {
+    let mut hasher = Sha256::new();
+    hasher.update(salt);
+    hasher.update(content);
+    hasher.finalize().into()
+}
+
+pub fn hash_report(content: &[u8], algorithms: &[HashAlgorithm]) -> ReportDigest {
+    let mut hasher = ReportHasher::new(algorithms);
+    hasher.update(content);
+    hasher.finish()
+}
+
+// Lowercase hex, as hashes appear in responses and the database
+pub fn hex(bytes: &[u8]) -> String {
+    bytes.iter().map(|b| format!("{:02x}", b)).collect()
+}
+
+pub fn decode_hex(hex: &str) -> Option<Vec<u8>> {
+    if !hex.len().is_multiple_of(2) {
+        return None;
+    }
+    (0..hex.len())
+        .step_by(2)
+        .map(|i| u8::from_str_radix(hex.get(i..i + 2)?, 16).ok())
+        .collect()
+}
@@ -0,0 +1,142 @@
+use anyhow::{anyhow, Result};
+use futures_util::StreamExt;
+use solana_client::nonblocking::pubsub_client::PubsubClient;
+use solana_client::rpc_config::{RpcProgramAccountsConfig, RpcTransactionLogsConfig, RpcTransactionLogsFilter};
+use solana_commitment_config::CommitmentConfig;
+use solana_sdk::pubkey::Pubkey;
+use std::str::FromStr;
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::time::Instant;
+
+use crate::cluster::Cluster;
+use crate::db::Database;
+use crate::report_logger::{parse_event, parse_report, report_filters, RegistryEvent, PROGRAM_ID};
+
+const MIN_BACKOFF: Duration = Duration::from_secs(1);
+const MAX_BACKOFF: Duration = Duration::from_secs(300);
+
+// Keeps the indexed_reports table in step with the report-logger program on the registry's
+// cluster, so report listings don't need an RPC scan per request. Program logs are followed
+// over the websocket API, and every (re)connect backfills from the report accounts to cover
+// whatever was missed while disconnected.
+pub fn spawn_indexer(db: Arc<Database>) -> Result<()> {
+    let cluster = Cluster::registry()?;
+    let program_id = Pubkey::from_str(PROGRAM_ID)?;
+    tokio::spawn(async move {
+        let mut backoff = MIN_BACKOFF;
+        loop {
+            let started = Instant::now();
+            let e = match run(&db, cluster, &program_id).await {
+                Ok(()) => anyhow!("Log subscription closed"),
+                Err(e) => e,
+            };
+            // A connection that held up for a while starts over with a short delay
+            if started.elapsed() > MAX_BACKOFF {
+                backoff = MIN_BACKOFF;
+            }
+            println!("Warning: report indexer disconnected from {}: {}; retrying in {}s", cluster.as_str(), e, backoff.as_secs());
+            tokio::time::sleep(backoff).await;
+            backoff = (backoff * 2).min(MAX_BACKOFF);
+        }
+    });
+    Ok(())
+}
+
+async fn run(db: &Database, cluster: Cluster, program_id: &Pubkey) -> Result<()> {
+    let ws_url = cluster.ws_url();
+    let client = PubsubClient::new(&ws_url).await.map_err(|e| anyhow!("Failed to connect to {}: {}", ws_url, e))?;
+    let result = follow(&client, db, cluster, program_id).await;
+    let _ = client.shutdown().await;
+    result
+}
+
+async fn follow(client: &PubsubClient, db: &Database, cluster: Cluster, program_id: &Pubkey) -> Result<()> {
+    let config = RpcTransactionLogsConfig { commitment: Some(CommitmentConfig::confirmed()) };
+    let (mut logs, _unsubscribe) = client
+        .logs_subscribe(RpcTransactionLogsFilter::Mentions(vec![program_id.to_string()]), config)
+        .await
+        .map_err(|e| anyhow!("Failed to subscribe to program logs: {}", e))?;
+
+    // Subscribed first, so nothing logged during the backfill is missed. Events queued
+    // meanwhile are replayed on top of it, which is harmless: a logged report that's already
+    // indexed is left alone, and status changes arrive in order.
+    let backfilled = backfill(db, cluster, program_id).await?;
+    println!("Report indexer following {} after backfilling {} reports", cluster.as_str(), backfilled);
+
+    while let Some(response) = logs.next().await {
+        // A failed transaction's events were rolled back with it
+        if response.value.err.is_some() {
+            continue;
+        }
+        for data in program_data(&response.value.logs, program_id) {
+            let Some(event) = base64::decode(data).ok().and_then(|data| parse_event(&data)) else {
+                continue;
+            };
+            if let Err(e) = apply(db, cluster, event) {
+                println!("Warning: failed to index event from {}: {}", response.value.signature, e);
+            }
+        }
+    }
+    Ok(())
+}
+
+// The `Program data:` lines the program logged itself. Any program in a transaction that
+// mentions it can log a line shaped like its events, so the invoke stack decides whose a line
+// is. Past a truncated log the stack is unknown, and nothing more is taken.
+fn program_data<'a>(logs: &'a [String], program_id: &Pubkey) -> Vec<&'a str> {
+    let program_id = program_id.to_string();
+    let mut stack: Vec<&str> = Vec::new();
+    let mut data = Vec::new();
+    for line in logs {
+        if line == "Log truncated" {
+            break;
+        }
+        if let Some(data_line) = line.strip_prefix("Program data: ") {
+            if stack.last() == Some(&program_id.as_str()) {
+                data.push(data_line);
+            }
+            continue;
+        }
+        let Some(rest) = line.strip_prefix("Program ") else { continue };
+        let mut words = rest.split_whitespace();
+        match (words.next(), words.next()) {
+            (Some(program), Some("invoke")) => stack.push(program),
+            (Some(program), Some("success" | "failed:")) if stack.last() == Some(&program) => {
+                stack.pop();
+            },
+            _ => {},
+        }
+    }
+    data
+}
+
+async fn backfill(db: &Database, cluster: Cluster, program_id: &Pubkey) -> Result<usize> {
+    let config = RpcProgramAccountsConfig {
+        filters: Some(report_filters()),
+        ..Default::default()
+    };
+    let accounts = cluster.rpc_client().get_program_accounts_with_config(program_id, config).await
+        .map_err(|e| anyhow!("Failed to load report accounts: {}", e))?;
+    let mut indexed = 0;
+    for (address, account) in &accounts {
+        if let Some(report) = parse_report(address, &account.data) {
+            db.upsert_indexed_report(cluster.as_str(), &report)?;
+            indexed += 1;
+        }
+    }
+    Ok(indexed)
+}
+
+fn apply(db: &Database, cluster: Cluster, event: RegistryEvent) -> Result<()> {
+    match event {
+        RegistryEvent::ReportLogged(report) => db.insert_indexed_report(cluster.as_str(), &report),
+        RegistryEvent::ReportStatusChanged { report, status } => {
+            // The next backfill picks up reports this instance hasn't seen yet
+            if !db.set_indexed_report_status(cluster.as_str(), &report, status)? {
+                println!("Warning: status change for unindexed report {}", report);
+            }
+            Ok(())
+        }
+    }
+}
@@ -0,0 +1,452 @@
+use anyhow::Result;
+use regex::Regex;
+use std::collections::HashMap;
+use std::fs;
+use std::path::Path;
+use toml::Table;
+
+use crate::models::{InstructionArg, InstructionInfo, ProgramInstructions, ProjectType};
+use crate::rent::{block_body, split_generic, top_level_split};
+use crate::stats::{collect_files, is_program_manifest, is_test_path, package_name};
+
+// A program crate's non-test Rust sources, as (path relative to the repository, content)
+pub struct ProgramSource {
+    pub name: String,
+    pub path: String,
+    pub is_anchor: bool,
+    pub sources: Vec<(String, String)>,
+}
+
+// Every program crate in the repository
+pub fn program_sources(repo_path: &Path) -> Result<Vec<ProgramSource>> {
+    let mut files = Vec::new();
+    collect_files(repo_path, &mut files)?;
+
+    let mut programs = Vec::new();
+    for manifest in files.iter().filter(|f| f.file_name().is_some_and(|n| n == "Cargo.toml")) {
+        let Ok(cargo_toml) = fs::read_to_string(manifest).unwrap_or_default().parse::<Table>() else {
+            continue;
+        };
+        let Some(program_dir) = manifest.parent().filter(|_| is_program_manifest(&cargo_toml)) else {
+            continue;
+        };
+        let is_anchor = cargo_toml.get("dependencies")
+            .and_then(|d| d.as_table())
+            .is_some_and(|d| d.contains_key("anchor-lang"));
+
+        let mut sources = Vec::new();
+        for file in files.iter().filter(|f| f.starts_with(program_dir) && f.extension().is_some_and(|e| e == "rs")) {
+            if is_test_path(repo_path, file) {
+                continue;
+            }
+            if let Ok(source) = fs::read_to_string(file) {
+                sources.push((file.strip_prefix(repo_path).unwrap_or(file).display().to_string(), source));
+            }
+        }
+
+        programs.push(ProgramSource {
+            name: package_name(&cargo_toml),
+            path: program_dir.strip_prefix(repo_path).unwrap_or(program_dir).display().to_string(),
+            is_anchor,
+            sources,
+        });
+    }
+    Ok(programs)
+}
+
+// Instructions of every program crate in the repository, found statically:
+// Anchor handlers in the #[program] module, or variants of native `*Instruction` enums
+pub fn extract_instructions(repo_path: &Path) -> Result<Vec<ProgramInstructions>> {
+    let parser = Parser::new();
+    Ok(program_sources(repo_path)?.into_iter().map(|program| ProgramInstructions {
+        instructions: parser.instructions(&program),
+        name: program.name,
+        path: program.path,
+        project_type: if program.is_anchor { ProjectType::Anchor } else { ProjectType::Native },
+    }).collect())
+}
+
+pub fn program_instructions(program: &ProgramSource) -> Vec<InstructionInfo> {
+    Parser::new().instructions(program)
+}
+
+// The instruction `name` refers to, ignoring case and underscores so `initialize_mint`
+// finds a native `InitializeMint` variant
+pub fn find_instruction<'a>(programs: &'a [ProgramInstructions], name: &str) -> Option<&'a InstructionInfo> {
+    let normalize = |n: &str| n.replace('_', "").to_lowercase();
+    let wanted = normalize(name);
+    programs.iter()
+        .flat_map(|p| &p.instructions)
+        .find(|i| i.name == name || normalize(&i.name) == wanted)
+}
+
+// An account an instruction expects, at its position in the account list
+#[derive(Debug, Clone)]
+pub struct AccountSlot {
+    pub name: String,
+    pub signer: bool,
+    pub writable: bool,
+    // The field's type in an Anchor accounts struct, `Account<'info, Mint>`
+    pub ty: Option<String>,
+}
+
+// How an instruction is called from outside its program: the program crate, the instruction's
+// variant index for native programs (Anchor dispatches by name), its accounts in order and
+// its arguments
+#[derive(Debug, Clone)]
+pub struct InstructionLayout {
+    pub program: String,
+    pub is_anchor: bool,
+    pub index: usize,
+    pub accounts: Vec<AccountSlot>,
+    pub args: Vec<InstructionArg>,
+}
+
+// The layout of the instruction `name` refers to, as find_instruction matches it; None when
+// its accounts can't be found statically. A composite Anchor accounts field counts as one.
+pub fn instruction_layout(repo_path: &Path, name: &str) -> Result<Option<InstructionLayout>> {
+    let parser = Parser::new();
+    let normalize = |n: &str| n.replace('_', "").to_lowercase();
+    let wanted = normalize(name);
+    for program in program_sources(repo_path)? {
+        let instructions = parser.instructions(&program);
+        let Some(instruction) = instructions.iter().find(|i| i.name == name || normalize(&i.name) == wanted) else {
+            continue;
+        };
+        let accounts = match (&instruction.accounts_struct, program.is_anchor) {
+            (Some(accounts_struct), true) => parser.anchor_accounts(&program.sources, accounts_struct).map(|accounts| (0, accounts)),
+            (_, false) => parser.native_accounts(&program.sources, &instruction.name),
+            (None, true) => None,
+        };
+        return Ok(accounts.filter(|(_, accounts)| !accounts.is_empty()).map(|(index, accounts)| InstructionLayout {
+            program: program.name,
+            is_anchor: program.is_anchor,
+            index,
+            accounts,
+            args: instruction.args.clone(),
+        }));
+    }
+    Ok(None)
+}
+
+// The code an instruction's behaviour depends on, as far as it can be found statically, for
+// telling whether it changed between commits. For Anchor that is the handler and its accounts
+// struct, plus the module the handler usually delegates to (`instructions/deposit.rs`). Native
+// processors are found by name (`process_initialize_mint` for `InitializeMint`); when there
+// is none, every source of the program stands in, so any change counts.
+pub fn instruction_source(program: &ProgramSource, instruction: &InstructionInfo) -> String {
+    let normalize = |n: &str| n.replace('_', "").to_lowercase();
+    let wanted = normalize(&instruction.name);
+    let re_fn = Regex::new(r"\bfn\s+(\w+)\s*(?:<[^(]*>)?\s*\(").unwrap();
+    let re_struct = Regex::new(r"\bstruct\s+(\w+)").unwrap();
+
+    let mut parts = Vec::new();
+    for (path, source) in &program.sources {
+        let stem = Path::new(path).file_stem().and_then(|s| s.to_str()).unwrap_or_default();
+        if program.is_anchor && normalize(stem) == wanted {
+            parts.push(source.clone());
+            continue;
+        }
+        for captures in re_fn.captures_iter(source) {
+            let name = normalize(&captures[1]);
+            let handler = if program.is_anchor {
+                *path == instruction.path && name == wanted
+            } else {
+                name == wanted || name == format!("process{}", wanted)
+            };
+            let Some(open) = handler.then(|| source[captures.get(0).unwrap().end()..].find('{')).flatten() else {
+                continue;
+            };
+            let open = captures.get(0).unwrap().end() + open;
+            parts.extend(block_body(source, open).map(|body| format!("{}{}", &captures[0], body)));
+        }
+        if let Some(accounts) = &instruction.accounts_struct {
+            for captures in re_struct.captures_iter(source).filter(|c| c[1] == **accounts) {
+                let Some(open) = source[captures.get(0).unwrap().end()..].find('{') else {
+                    continue;
+                };
+                let open = captures.get(0).unwrap().end() + open;
+                // The constraints are in the attributes just above the struct
+                let declared = captures.get(0).unwrap().start();
+                let start = source[..declared].rfind("#[derive")
+                    .filter(|&derive| !source[derive..declared].contains(['}', ';']))
+                    .unwrap_or(declared);
+                parts.extend(block_body(source, open).map(|body| format!("{}{}", &source[start..open], body)));
+            }
+        }
+    }
+    if parts.is_empty() {
+        return program.sources.iter().map(|(_, source)| source.as_str()).collect::<Vec<_>>().join("\n");
+    }
+    parts.join("\n")
+}
+
+struct Parser {
+    comment: Regex,
+    field: Regex,
+    program_module: Regex,
+    handler: Regex,
+    accounts_struct: Regex,
+    instruction_enum: Regex,
+    // `///   0. `[writable, signer]` Payer` in native instruction docs
+    account_doc: Regex,
+    // The same, capturing the flags and description
+    account_doc_flags: Regex,
+    variant: Regex,
+}
+
+impl Parser {
+    fn new() -> Self {
+        Self {
+            comment: Regex::new(r"//[^\n]*").unwrap(),
+            field: Regex::new(r"(?s)^(?:pub(?:\([^)]*\))?\s+)?(?:mut\s+)?(\w+)\s*:\s*(.+)$").unwrap(),
+            program_module: Regex::new(r"#\[program\]\s*(?:pub\s+)?mod\s+\w+\s*\{").unwrap(),
+            handler: Regex::new(r"\bpub\s+fn\s+(\w+)\s*(?:<[^(]*>)?\s*\(").unwrap(),
+            accounts_struct: Regex::new(r"(?m)((?:^[ \t]*#\[[^\n]*\][ \t]*\n)*)^[ \t]*pub(?:\([^)]*\))?\s+struct\s+(\w+)(?:<[^>{]*>)?\s*\{").unwrap(),
+            instruction_enum: Regex::new(r"pub\s+enum\s+\w*Instruction\w*\s*\{").unwrap(),
+            account_doc: Regex::new(r"^\s*///\s*\d+\.").unwrap(),
+            account_doc_flags: Regex::new(r"^\s*///\s*\d+\.\s*(?:`\[([^\]]*)\]`)?(.*)$").unwrap(),
+            variant: Regex::new(r"^\s*([A-Z]\w*)\s*(\{|\(|,|$)").unwrap(),
+        }
+    }
+
+    fn instructions(&self, program: &ProgramSource) -> Vec<InstructionInfo> {
+        if program.is_anchor {
+            self.anchor_instructions(&program.sources)
+        } else {
+            self.native_instructions(&program.sources)
+        }
+    }
+
+    fn anchor_instructions(&self, sources: &[(String, String)]) -> Vec<InstructionInfo> {
+        // Field count of every #[derive(Accounts)] struct in the crate
+        let mut accounts: HashMap<String, u32> = HashMap::new();
+        for (_, source) in sources {
+            for captures in self.accounts_struct.captures_iter(source) {
+                if !captures[1].lines().any(|a| a.contains("derive") && a.contains("Accounts")) {
+                    continue;
+                }
+                let open = captures.get(0).unwrap().end() - 1;
+                if let Some(body) = block_body(source, open) {
+                    let count = self.fields(body).len() as u32;
+                    accounts.insert(captures[2].to_string(), count);
+                }
+            }
+        }
+
+        let mut instructions = Vec::new();
+        for (path, source) in sources {
+            let source = self.comment.replace_all(source, "");
+            let Some(module) = self.program_module.find(&source) else {
+                continue;
+            };
+            let Some(body) = block_body(&source, module.end() - 1) else {
+                continue;
+            };
+            for captures in self.handler.captures_iter(body) {
+                let start = captures.get(0).unwrap().start();
+                // Only handlers directly in the module, not fns nested in their bodies
+                if brace_depth(&body[..start]) != 0 {
+                    continue;
+                }
+                let Some(params) = paren_body(body, captures.get(0).unwrap().end() - 1) else {
+                    continue;
+                };
+                let mut params = self.fields(params).into_iter();
+                let accounts_struct = params.next()
+                    .map(|ctx| split_generic(&ctx.ty).1.last().map(|a| split_generic(a).0.to_string()).unwrap_or_default())
+                    .filter(|name| !name.is_empty());
+                instructions.push(InstructionInfo {
+                    name: captures[1].to_string(),
+                    path: path.clone(),
+                    args: params.collect(),
+                    account_count: accounts_struct.as_ref().and_then(|name| accounts.get(name).copied()),
+                    accounts_struct,
+                });
+            }
+        }
+        instructions
+    }
+
+    fn native_instructions(&self, sources: &[(String, String)]) -> Vec<InstructionInfo> {
+        let mut instructions = Vec::new();
+        for (path, source) in sources {
+            for found in self.instruction_enum.find_iter(source) {
+                let Some(body) = block_body(source, found.end() - 1) else {
+                    continue;
+                };
+                // Account docs precede their variant, so count them from the raw text
+                let mut account_counts = HashMap::new();
+                let (mut pending, mut depth) = (0, 0);
+                for line in body.lines() {
+                    if depth == 0 {
+                        if self.account_doc.is_match(line) {
+                            pending += 1;
+                        } else if let Some(variant) = self.variant.captures(line) {
+                            account_counts.insert(variant[1].to_string(), pending);
+                            pending = 0;
+                        }
+                    }
+                    depth += brace_depth(line);
+                }
+
+                let body = self.comment.replace_all(body, "");
+                for chunk in top_level_split(&body, ',') {
+                    let variant = strip_attributes(chunk);
+                    let variant = variant.trim();
+                    let name: String = variant.chars().take_while(|c| c.is_alphanumeric() || *c == '_').collect();
+                    if name.is_empty() {
+                        continue;
+                    }
+                    let args = if let Some(open) = variant.find('{') {
+                        block_body(variant, open).map(|fields| self.fields(fields)).unwrap_or_default()
+                    } else if let Some(fields) = variant.find('(').and_then(|open| paren_body(variant, open)) {
+                        top_level_split(fields, ',').into_iter().enumerate().map(|(i, ty)| InstructionArg {
+                            name: i.to_string(),
+                            ty: ty.chars().filter(|c| !c.is_whitespace()).collect(),
+                        }).collect()
+                    } else {
+                        Vec::new()
+                    };
+                    instructions.push(InstructionInfo {
+                        account_count: account_counts.get(&name).copied().filter(|count| *count > 0),
+                        name,
+                        path: path.clone(),
+                        args,
+                        accounts_struct: None,
+                    });
+                }
+            }
+        }
+        instructions
+    }
+
+    // Fields of the #[derive(Accounts)] struct `name`: signers are `Signer` fields or marked
+    // `signer`, and `mut`, `init` and `close` fields are written
+    fn anchor_accounts(&self, sources: &[(String, String)], name: &str) -> Option<Vec<AccountSlot>> {
+        for (_, source) in sources {
+            for captures in self.accounts_struct.captures_iter(source) {
+                if &captures[2] != name || !captures[1].lines().any(|a| a.contains("derive") && a.contains("Accounts")) {
+                    continue;
+                }
+                let body = block_body(source, captures.get(0).unwrap().end() - 1)?;
+                let body = self.comment.replace_all(body, "");
+                return Some(top_level_split(&body, ',').into_iter().filter_map(|chunk| {
+                    let declaration = strip_attributes(chunk);
+                    let field = self.field.captures(declaration.trim())?;
+                    let constraints: Vec<String> = chunk.find("#[account(")
+                        .and_then(|start| paren_body(chunk, start + "#[account".len()))
+                        .map(|body| top_level_split(body, ',').into_iter().map(|c| c.trim().to_string()).collect())
+                        .unwrap_or_default();
+                    let has = |wanted: &str| constraints.iter().any(|c| c == wanted || c.starts_with(&format!("{} ", wanted)) || c.starts_with(&format!("{}=", wanted)));
+                    Some(AccountSlot {
+                        name: field[1].to_string(),
+                        signer: field[2].trim_start().starts_with("Signer") || has("signer"),
+                        writable: has("mut") || has("init") || has("init_if_needed") || has("close"),
+                        ty: Some(field[2].trim().to_string()),
+                    })
+                }).collect());
+            }
+        }
+        None
+    }
+
+    // The native instruction variant `name`'s index in its enum, and the numbered accounts
+    // documented above it: `///   0. `[writable, signer]` Payer`
+    fn native_accounts(&self, sources: &[(String, String)], name: &str) -> Option<(usize, Vec<AccountSlot>)> {
+        for (_, source) in sources {
+            for found in self.instruction_enum.find_iter(source) {
+                let Some(body) = block_body(source, found.end() - 1) else {
+                    continue;
+                };
+                let (mut pending, mut depth, mut index) = (Vec::new(), 0, 0);
+                for line in body.lines() {
+                    if depth == 0 {
+                        if let Some(captures) = self.account_doc_flags.captures(line) {
+                            let flags = captures.get(1).map_or("", |flags| flags.as_str());
+                            let description = captures[2].trim();
+                            pending.push(AccountSlot {
+                                name: match description.split(['.', ',', ';']).next().map(str::trim) {
+                                    Some(description) if !description.is_empty() => description.to_string(),
+                                    _ => format!("account {}", pending.len()),
+                                },
+                                signer: flags.contains("signer"),
+                                writable: flags.contains("writable") || flags.contains("write"),
+                                ty: None,
+                            });
+                        } else if let Some(variant) = self.variant.captures(line) {
+                            if &variant[1] == name {
+                                return Some((index, pending));
+                            }
+                            pending.clear();
+                            index += 1;
+                        }
+                    }
+                    depth += brace_depth(line);
+                }
+            }
+        }
+        None
+    }
+
+    // `name: Type` pairs of a struct body or parameter list
+    fn fields(&self, body: &str) -> Vec<InstructionArg> {
+        let body = self.comment.replace_all(body, "");
+        top_level_split(&body, ',').into_iter().filter_map(|chunk| {
+            let declaration = strip_attributes(chunk);
+            let captures = self.field.captures(declaration.trim())?;
+            Some(InstructionArg {
+                name: captures[1].to_string(),
+                ty: captures[2].chars().filter(|c| !c.is_whitespace()).collect(),
+            })
+        }).collect()
+    }
+}
+
+// `text` without `#[...]` attributes; brackets inside them (`seeds = [...]`) are balanced
+pub fn strip_attributes(text: &str) -> String {
+    let mut stripped = String::with_capacity(text.len());
+    let mut rest = text;
+    while let Some(start) = rest.find("#[") {
+        stripped.push_str(&rest[..start]);
+        let mut depth = 0;
+        let end = rest[start + 1..].char_indices().find_map(|(i, c)| {
+            match c {
+                '[' => depth += 1,
+                ']' => depth -= 1,
+                _ => {},
+            }
+            (depth == 0).then_some(start + 1 + i + 1)
+        });
+        rest = &rest[end.unwrap_or(rest.len())..];
+    }
+    stripped.push_str(rest);
+    stripped
+}
+
+// Net `{` minus `}` in `text`
+pub fn brace_depth(text: &str) -> i32 {
+    text.chars().map(|c| match c {
+        '{' => 1,
+        '}' => -1,
+        _ => 0,
+    }).sum()
+}
+
+// Contents of the `( ... )` group opening at `open`
+pub fn paren_body(source: &str, open: usize) -> Option<&str> {
+    let mut depth = 0;
+    for (i, c) in source[open..].char_indices() {
+        match c {
+            '(' => depth += 1,
+            ')' => {
+                depth -= 1;
+                if depth == 0 {
+                    return Some(&source[open + 1..open + i]);
+                }
+            },
+            _ => {},
+        }
+    }
+    None
+}
@@ -0,0 +1,202 @@
+use anyhow::{anyhow, Result};
+use reqwest::{Client, Url};
+use serde_json::{json, Value};
+use std::collections::BTreeMap;
+use std::net::{IpAddr, SocketAddr};
+use std::time::Duration;
+
+use crate::db::now_unix;
+use crate::models::{BugSeverity, CodeBug, JiraSettings, JiraSettingsRequest};
+use crate::repo_url::RepoUrl;
+
+const DEFAULT_ISSUE_TYPE: &str = "Bug";
+// Jira rejects longer summaries
+const MAX_SUMMARY_CHARS: usize = 255;
+
+pub fn default_priorities() -> BTreeMap<BugSeverity, String> {
+    BTreeMap::from([
+        (BugSeverity::Critical, "Highest".to_string()),
+        (BugSeverity::High, "High".to_string()),
+        (BugSeverity::Medium, "Medium".to_string()),
+        (BugSeverity::Low, "Low".to_string()),
+    ])
+}
+
+pub fn settings_from_request(request: &JiraSettingsRequest, updated_by: &str) -> JiraSettings {
+    JiraSettings {
+        base_url: request.base_url.trim().trim_end_matches('/').to_string(),
+        project_key: request.project_key.clone(),
+        email: request.email.clone().filter(|email| !email.trim().is_empty()),
+        issue_type: request.issue_type.clone()
+            .filter(|issue_type| !issue_type.trim().is_empty())
+            .unwrap_or_else(|| DEFAULT_ISSUE_TYPE.to_string()),
+        priorities: request.priorities.clone().unwrap_or_else(default_priorities),
+        updated_by: updated_by.to_string(),
+        updated_at: now_unix(),
+    }
+}
+
+// Whether `ip` is reachable from the internet, rather than loopback, private, link-local or
+// otherwise reserved
+fn is_public(ip: IpAddr) -> bool {
+    match ip {
+        IpAddr::V4(ip) => !(ip.is_loopback()
+            || ip.is_private()
+            || ip.is_link_local()
+            || ip.is_unspecified()
+            || ip.is_broadcast()
+            || ip.is_documentation()
+            // carrier-grade NAT, 100.64.0.0/10
+            || (ip.octets()[0] == 100 && ip.octets()[1] & 0xc0 == 64)),
+        IpAddr::V6(ip) => match ip.to_ipv4_mapped() {
+            Some(mapped) => is_public(IpAddr::V4(mapped)),
+            None => !(ip.is_loopback()
+                || ip.is_unspecified()
+                // unique local, fc00::/7
+                || (ip.segments()[0] & 0xfe00) == 0xfc00
+                // link-local, fe80::/10
+                || (ip.segments()[0] & 0xffc0) == 0xfe80),
+        },
+    }
+}
+
+// A finding being pushed, with where it was found and where Safex reports on it
+pub struct TicketSource<'a> {
+    pub finding: &'a CodeBug,
+    pub repo_url: &'a RepoUrl,
+    pub commit_sha: Option<&'a str>,
+    pub run_id: &'a str,
+    pub report_url: &'a str,
+}
+
+// Jira REST API v2, which Jira Cloud and Data Center both serve and which takes descriptions
+// as wiki markup
+pub struct JiraClient {
+    client: Client,
+    settings: JiraSettings,
+    token: String,
+}
+
+impl JiraClient {
+    // The base URL is tenant-supplied, so it must be https and resolve to a public address. The
+    // client is pinned to the address checked here, so a second lookup can't point it elsewhere.
+    pub async fn connect(settings: JiraSettings, token: String) -> Result<Self> {
+        let url = Url::parse(&settings.base_url)
+            .map_err(|e| anyhow!("Invalid Jira base URL {}: {}", settings.base_url, e))?;
+        if url.scheme() != "https" {
+            return Err(anyhow!("Jira base URL must be https: {}", settings.base_url));
+        }
+        let host = url.host_str().ok_or_else(|| anyhow!("Jira base URL has no host: {}", settings.base_url))?;
+        let port = url.port_or_known_default().unwrap_or(443);
+        let addresses: Vec<SocketAddr> = tokio::net::lookup_host((host.trim_matches(|c| c == '[' || c == ']'), port)).await
+            .map_err(|e| anyhow!("Failed to resolve Jira host {}: {}", host, e))?
+            .collect();
+        if let Some(address) = addresses.iter().find(|address| !is_public(address.ip())) {
+            return Err(anyhow!("Jira host {} resolves to non-public address {}", host, address.ip()));
+        }
+        let address = *addresses.first().ok_or_else(|| anyhow!("Jira host {} has no addresses", host))?;
+        let client = Client::builder()
+            .timeout(Duration::from_secs(30))
+            .redirect(reqwest::redirect::Policy::none())
+            .resolve(host, address)
+            .build()?;
+        Ok(Self { client, settings, token })
+    }
+
+    fn request(&self, method: reqwest::Method, path: &str) -> reqwest::RequestBuilder {
+        let request = self.client
+            .request(method, format!("{}/rest/api/2/{}", self.settings.base_url, path))
+            .header("User-Agent", "Safex-App")
+            .header("Accept", "application/json");
+        match &self.settings.email {
+            Some(email) => request.basic_auth(email, Some(&self.token)),
+            None => request.bearer_auth(&self.token),
+        }
+    }
+
+    async fn send(&self, request: reqwest::RequestBuilder) -> Result<Value> {
+        let response = request.send().await
+            .map_err(|e| anyhow!("Failed to connect to Jira: {}", e))?;
+        let status = response.status();
+        let value: Value = response.json().await.unwrap_or(Value::Null);
+        if !status.is_success() {
+            // Jira reports field problems under `errors` and the rest under `errorMessages`
+            let mut messages: Vec<String> = value.get("errorMessages").and_then(|m| m.as_array())
+                .map(|m| m.iter().filter_map(|m| m.as_str()).map(str::to_string).collect())
+                .unwrap_or_default();
+            if let Some(errors) = value.get("errors").and_then(|e| e.as_object()) {
+                messages.extend(errors.iter().map(|(field, message)| format!("{}: {}", field, message.as_str().unwrap_or_default())));
+            }
+            let detail = if messages.is_empty() { "unknown error".to_string() } else { messages.join("; ") };
+            return Err(anyhow!("Jira API error: {} - {}", status, detail));
+        }
+        Ok(value)
+    }
+
+    // Create the ticket and link it back to the Safex report; returns its key and URL
+    #[tracing::instrument(name = "jira.create_ticket", skip(self, source), fields(fingerprint = %source.finding.fingerprint))]
+    pub async fn create_ticket(&self, source: &TicketSource<'_>) -> Result<(String, String)> {
+        let finding = source.finding;
+        let mut summary = format!("[Safex] {}", finding.bug.lines().next().unwrap_or_default());
+        if summary.chars().count() > MAX_SUMMARY_CHARS {
+            summary = summary.chars().take(MAX_SUMMARY_CHARS - 3).collect::<String>() + "...";
+        }
+        let mut fields = json!({
+            "project": { "key": self.settings.project_key },
+            "issuetype": { "name": self.settings.issue_type },
+            "summary": summary,
+            "description": description(source),
+            "labels": ["safex", format!("safex-{}", finding.severity.as_str())],
+        });
+        if let Some(priority) = self.settings.priorities.get(&finding.severity) {
+            fields["priority"] = json!({ "name": priority });
+        }
+
+        let issue = self.send(self.request(reqwest::Method::POST, "issue").json(&json!({ "fields": fields }))).await?;
+        let key = issue.get("key").and_then(|k| k.as_str())
+            .ok_or_else(|| anyhow!("Jira did not return the issue key"))?
+            .to_string();
+        let url = format!("{}/browse/{}", self.settings.base_url, key);
+
+        // The description links back too, so a failed remote link only loses the sidebar entry
+        let link = json!({
+            "globalId": format!("safex:{}:{}", source.run_id, finding.fingerprint),
+            "object": {
+                "url": source.report_url,
+                "title": format!("Safex report, finding {}", finding.fingerprint),
+            },
+        });
+        if let Err(e) = self.send(self.request(reqwest::Method::POST, &format!("issue/{}/remotelink", key)).json(&link)).await {
+            println!("Warning: Failed to link {} back to the Safex report: {}", key, e);
+        }
+        Ok((key, url))
+    }
+}
+
+fn description(source: &TicketSource<'_>) -> String {
+    let finding = source.finding;
+    let mut description = format!("h3. Finding\n\n{}\n\n*Severity:* {}\n", finding.bug, finding.severity.as_str());
+    if let Some(rule_id) = &finding.rule_id {
+        description.push_str(&format!("*Rule:* {{{{{}}}}}\n", rule_id));
+    }
+    if let Some(taxonomy) = finding.taxonomy {
+        description.push_str(&format!("*Class:* {}\n", taxonomy.title()));
+    }
+    if let Some(file) = &finding.file {
+        let location = format!("{}:{}", file, finding.line);
+        match (source.repo_url.owner_repo(), source.commit_sha) {
+            (Some((owner, repo)), Some(sha)) => description.push_str(&format!(
+                "*Location:* [{}|https://github.com/{}/{}/blob/{}/{}#L{}]\n",
+                location, owner, repo, sha, file, finding.line,
+            )),
+            _ => description.push_str(&format!("*Location:* {{{{{}}}}}\n", location)),
+        }
+    }
+    description.push_str(&format!("*Repository:* {}\n", source.repo_url));
+    description.push_str(&format!("\nh3. Remediation\n\n{}\n", finding.fix));
+    description.push_str(&format!(
+        "\n----\nReported by [Safex|{}]. Analysis run {{{{{}}}}}, finding {{{{{}}}}}.\n",
+        source.report_url, source.run_id, finding.fingerprint,
+    ));
+    description
+}
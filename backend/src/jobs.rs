@@ -0,0 +1,695 @@
+use anyhow::{anyhow, Result};
+use redis::aio::ConnectionManager;
+use redis::{AsyncCommands, Commands, Script};
+use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, HashSet};
+use std::env;
+use std::future::Future;
+use std::sync::{Arc, Mutex};
+use std::thread;
+use std::time::{Duration, Instant};
+use tracing::Instrument;
+
+use crate::analyzer::AnalysisEventSink;
+use crate::db::now_unix;
+use crate::environment::PhaseSink;
+use crate::fuzzer::FuzzCheckpointSink;
+use crate::github::CloneProgressSink;
+use crate::models::{ActiveJob, AnalysisEvent, CloneProgress, FuzzCheckpoint, JobInfo, JobPriority, QueueDepth, RegressionFuzz, WorkerInfo};
+
+// Jobs queued before priority classes existed; leased after all of the classes
+const LEGACY_QUEUE_KEY: &str = "safex:jobs:queued";
+// A sorted set of the tenants with jobs in a class per class (`<prefix><class>`), scored by
+// when they were last served, and a list of jobs per tenant and class (`<prefix><class>:<tenant>`)
+const QUEUE_PREFIX: &str = "safex:jobs:queue:";
+// Counter the tenant sets are scored by
+const SERVED_KEY: &str = "safex:jobs:served";
+const LEASES_KEY: &str = "safex:jobs:leased";
+const WORKERS_KEY: &str = "safex:workers";
+const JOB_KEY_PREFIX: &str = "safex:job:";
+// A list of a running analysis's events, as JSON, after the job's key
+const EVENTS_SUFFIX: &str = ":events";
+const WORKER_KEY_PREFIX: &str = "safex:worker:";
+
+// Finished jobs are kept around for a week so clients can still fetch results
+const FINISHED_JOB_TTL_SECS: i64 = 7 * 24 * 60 * 60;
+const HEARTBEAT_INTERVAL_SECS: u64 = 10;
+const HEARTBEAT_TTL_SECS: u64 = 30;
+// How often a running clone's progress is written to its job
+const CLONE_PROGRESS_INTERVAL: Duration = Duration::from_secs(1);
+
+// Pop the next job and lease it to a worker until ARGV[1]. The classes from ARGV[6] on are
+// tried in order; within one, the tenant served longest ago gives up its oldest job and goes
+// to the back. A tenant that had nothing queued comes in at the front. A job leased again
+// after a lease ran out starts over, so the phase its last run reached and the events it
+// streamed are cleared.
+const LEASE_SCRIPT: &str = r#"
+local function pop(tenants)
+  while true do
+    local tenant = redis.call('ZRANGE', tenants, 0, 0)[1]
+    if not tenant then return false end
+    local queue = tenants .. ':' .. tenant
+    local id = redis.call('RPOP', queue)
+    if redis.call('LLEN', queue) == 0 then
+      redis.call('ZREM', tenants, tenant)
+    else
+      redis.call('ZADD', tenants, redis.call('INCR', KEYS[3]), tenant)
+    end
+    if id then return id end
+  end
+end
+
+local id = false
+for i = 6, #ARGV do
+  id = pop(ARGV[5] .. ARGV[i])
+  if id then break end
+end
+if not id then id = redis.call('RPOP', KEYS[1]) end
+if not id then return false end
+local job = ARGV[4] .. id
+redis.call('ZADD', KEYS[2], ARGV[1], id)
+redis.call('HSET', job, 'status', 'running', 'worker', ARGV[2], 'updated_at', ARGV[3], 'started_at', ARGV[3])
+redis.call('HDEL', job, 'phase', 'phase_started_at')
+redis.call('DEL', job .. ':events')
+redis.call('HINCRBY', job, 'attempts', 1)
+return id
+"#;
+
+// Extend a lease, but only while the worker still holds it
+const RENEW_SCRIPT: &str = r#"
+if redis.call('HGET', ARGV[4] .. ARGV[1], 'worker') ~= ARGV[2] then return 0 end
+return redis.call('ZADD', KEYS[1], 'XX', 'CH', ARGV[3], ARGV[1])
+"#;
+
+// Record the outcome of a job; ignored if the lease expired and the job moved on
+const FINISH_SCRIPT: &str = r#"
+local job = ARGV[6] .. ARGV[1]
+if redis.call('HGET', job, 'worker') ~= ARGV[2] or redis.call('ZREM', KEYS[1], ARGV[1]) == 0 then return 0 end
+redis.call('HSET', job, 'status', ARGV[3], 'updated_at', ARGV[5])
+redis.call('HSET', job, ARGV[3] == 'completed' and 'result' or 'error', ARGV[4])
+redis.call('HDEL', job, 'worker')
+redis.call('EXPIRE', job, ARGV[7])
+return 1
+"#;
+
+// Requeue jobs whose lease ran out (their worker died or stalled), failing them after too many
+// attempts. They go back to the front of their tenant's queue in their class (ARGV[7] for jobs
+// from before classes).
+const REAP_SCRIPT: &str = r#"
+local expired = redis.call('ZRANGEBYSCORE', KEYS[1], '-inf', ARGV[1])
+local requeued = 0
+for _, id in ipairs(expired) do
+  redis.call('ZREM', KEYS[1], id)
+  local job = ARGV[4] .. id
+  local attempts = tonumber(redis.call('HGET', job, 'attempts') or '0')
+  redis.call('HDEL', job, 'worker')
+  if attempts >= tonumber(ARGV[2]) then
+    redis.call('HSET', job, 'status', 'failed', 'error', 'Worker lease expired too many times', 'updated_at', ARGV[3])
+    redis.call('EXPIRE', job, ARGV[5])
+  else
+    local tenants = ARGV[6] .. (redis.call('HGET', job, 'priority') or ARGV[7])
+    local tenant = redis.call('HGET', job, 'tenant') or ''
+    redis.call('HSET', job, 'status', 'queued', 'updated_at', ARGV[3])
+    redis.call('RPUSH', tenants .. ':' .. tenant, id)
+    redis.call('ZADD', tenants, 'NX', 0, tenant)
+    requeued = requeued + 1
+  end
+end
+return requeued
+"#;
+
+fn tenants_key(priority: JobPriority) -> String {
+    format!("{}{}", QUEUE_PREFIX, priority.as_str())
+}
+
+fn tenant_queue_key(priority: JobPriority, tenant: &str) -> String {
+    format!("{}:{}", tenants_key(priority), tenant)
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum JobKind {
+    Analyze,
+    Fuzz,
+    // Find the instructions changed between two commits and queue a fuzz job for each
+    RegressionFuzz,
+}
+
+impl JobKind {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            JobKind::Analyze => "analyze",
+            JobKind::Fuzz => "fuzz",
+            JobKind::RegressionFuzz => "regression_fuzz",
+        }
+    }
+
+    pub fn parse(value: &str) -> Option<Self> {
+        match value {
+            "analyze" => Some(JobKind::Analyze),
+            "fuzz" => Some(JobKind::Fuzz),
+            "regression_fuzz" => Some(JobKind::RegressionFuzz),
+            _ => None,
+        }
+    }
+}
+
+// A unit of clone/clippy/fuzz work handed from API nodes to workers
+#[derive(Debug, Clone)]
+pub struct Job {
+    pub id: String,
+    pub kind: JobKind,
+    pub priority: JobPriority,
+    pub tenant: String,
+    // Who submitted the job, for the audit log
+    pub actor: String,
+    pub payload: serde_json::Value,
+}
+
+// Which parts of the service this process runs
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Role {
+    Api,
+    Worker,
+    All,
+}
+
+impl Role {
+    // SAFEX_ROLE: "api", "worker" or "all" (default)
+    pub fn from_env() -> Result<Self> {
+        match env::var("SAFEX_ROLE").unwrap_or_else(|_| "all".to_string()).as_str() {
+            "api" => Ok(Role::Api),
+            "worker" => Ok(Role::Worker),
+            "all" => Ok(Role::All),
+            other => Err(anyhow!("Unknown SAFEX_ROLE: {}", other)),
+        }
+    }
+
+    pub fn serves_http(&self) -> bool {
+        *self != Role::Worker
+    }
+
+    pub fn runs_workers(&self) -> bool {
+        *self != Role::Api
+    }
+}
+
+pub struct JobQueue {
+    client: redis::Client,
+    conn: ConnectionManager,
+    lease_secs: i64,
+    max_attempts: u32,
+}
+
+impl JobQueue {
+    // Queueing is enabled by SAFEX_REDIS_URL; without it every request runs inline
+    pub async fn from_env() -> Result<Option<Self>> {
+        let Ok(url) = env::var("SAFEX_REDIS_URL") else {
+            println!("No SAFEX_REDIS_URL configured, jobs run inline on the API node");
+            return Ok(None);
+        };
+
+        let client = redis::Client::open(url.as_str())?;
+        let conn = tokio::time::timeout(Duration::from_secs(10), ConnectionManager::new(client.clone()))
+            .await
+            .map_err(|_| anyhow!("Timed out connecting to Redis"))??;
+        let lease_secs = env::var("SAFEX_JOB_LEASE_SECS").ok().and_then(|v| v.parse().ok()).unwrap_or(300);
+        let max_attempts = env::var("SAFEX_JOB_MAX_ATTEMPTS").ok().and_then(|v| v.parse().ok()).unwrap_or(3);
+
+        println!("Using Redis job queue (lease {}s, max {} attempts)", lease_secs, max_attempts);
+        Ok(Some(Self { client, conn, lease_secs, max_attempts }))
+    }
+
+    pub async fn enqueue(&self, kind: JobKind, priority: JobPriority, tenant: &str, actor: &str, payload: serde_json::Value) -> Result<String> {
+        self.enqueue_with(kind, priority, tenant, actor, payload, None).await
+    }
+
+    // A fuzz job for an instruction that changed between two commits, marked as such
+    pub async fn enqueue_regression_fuzz(&self, priority: JobPriority, tenant: &str, actor: &str, payload: serde_json::Value, regression: &RegressionFuzz) -> Result<String> {
+        self.enqueue_with(JobKind::Fuzz, priority, tenant, actor, payload, Some(regression)).await
+    }
+
+    async fn enqueue_with(&self, kind: JobKind, priority: JobPriority, tenant: &str, actor: &str, payload: serde_json::Value, regression: Option<&RegressionFuzz>) -> Result<String> {
+        let id = uuid::Uuid::new_v4().to_string();
+        let now = now_unix();
+        let mut conn = self.conn.clone();
+
+        let mut fields = vec![
+            ("kind", kind.as_str().to_string()),
+            ("priority", priority.as_str().to_string()),
+            ("tenant", tenant.to_string()),
+            ("actor", actor.to_string()),
+            ("payload", payload.to_string()),
+            ("status", "queued".to_string()),
+            ("attempts", "0".to_string()),
+            ("created_at", now.to_string()),
+            ("updated_at", now.to_string()),
+        ];
+        if let Some(regression) = regression {
+            fields.push(("regression", serde_json::to_string(regression)?));
+        }
+        redis::pipe()
+            .atomic()
+            .hset_multiple(format!("{}{}", JOB_KEY_PREFIX, id), &fields)
+            .lpush(tenant_queue_key(priority, tenant), &id)
+            .cmd("ZADD").arg(tenants_key(priority)).arg("NX").arg(0).arg(tenant)
+            .query_async::<()>(&mut conn)
+            .await?;
+        Ok(id)
+    }
+
+    pub async fn get(&self, id: &str) -> Result<Option<JobInfo>> {
+        let mut conn = self.conn.clone();
+        let fields: HashMap<String, String> = conn.hgetall(format!("{}{}", JOB_KEY_PREFIX, id)).await?;
+        if fields.is_empty() {
+            return Ok(None);
+        }
+
+        let field = |name: &str| fields.get(name).cloned();
+        let number = |name: &str| fields.get(name).and_then(|v| v.parse().ok()).unwrap_or(0);
+        let priority = field("priority").and_then(|p| JobPriority::parse(&p));
+        let tenant = field("tenant").unwrap_or_default();
+        let queue_position = match priority {
+            Some(priority) if field("status").as_deref() == Some("queued") => self.queue_position(id, priority, &tenant).await?,
+            _ => None,
+        };
+        Ok(Some(JobInfo {
+            id: id.to_string(),
+            kind: field("kind").unwrap_or_default(),
+            status: field("status").unwrap_or_default(),
+            attempts: number("attempts") as u32,
+            worker: field("worker"),
+            created_at: number("created_at"),
+            updated_at: number("updated_at"),
+            result: field("result").and_then(|r| serde_json::from_str(&r).ok()),
+            error: field("error"),
+            clone_progress: field("clone_progress").and_then(|p| serde_json::from_str(&p).ok()),
+            checkpoint: field("checkpoint").and_then(|c| serde_json::from_str(&c).ok()),
+            regression: field("regression").and_then(|r| serde_json::from_str(&r).ok()),
+            priority,
+            queue_position,
+            tenant,
+        }))
+    }
+
+    // Jobs leased before this one if nothing more urgent arrives: everything queued in more
+    // urgent classes, then the turns the other tenants of its class get until its own comes
+    async fn queue_position(&self, id: &str, priority: JobPriority, tenant: &str) -> Result<Option<u64>> {
+        let mut conn = self.conn.clone();
+        let own_queue = tenant_queue_key(priority, tenant);
+        let index: Option<u64> = redis::cmd("LPOS").arg(&own_queue).arg(id).query_async(&mut conn).await?;
+        let Some(index) = index else {
+            return Ok(None);
+        };
+
+        let mut position = 0;
+        for class in JobPriority::ALL.into_iter().take_while(|class| *class != priority) {
+            let tenants: Vec<String> = conn.zrange(tenants_key(class), 0, -1).await?;
+            for other in tenants {
+                position += conn.llen::<_, u64>(tenant_queue_key(class, &other)).await?;
+            }
+        }
+
+        // Jobs are pushed on the left and leased from the right
+        let length: u64 = conn.llen(&own_queue).await?;
+        let turns = length.saturating_sub(index + 1);
+        position += turns;
+        let tenants: Vec<String> = conn.zrange(tenants_key(priority), 0, -1).await?;
+        let own_turn = tenants.iter().position(|other| other == tenant);
+        for (turn, other) in tenants.iter().enumerate().filter(|(_, other)| *other != tenant) {
+            let queued: u64 = conn.llen(tenant_queue_key(priority, other)).await?;
+            // Tenants ahead in the rotation get one more turn before this job's comes round
+            let ahead = own_turn.is_some_and(|own| turn < own);
+            position += queued.min(turns + ahead as u64);
+        }
+        Ok(Some(position))
+    }
+
+    // Records a job's clone progress on it, at most once per interval plus the end of the
+    // clone. git2 reports progress from inside the clone, which may be on a runtime thread, so
+    // the sink only hands it to a task that does the writing, in order, and ends with the sink.
+    pub fn clone_progress_sink(&self, job_id: &str) -> CloneProgressSink {
+        let mut conn = self.conn.clone();
+        let key = format!("{}{}", JOB_KEY_PREFIX, job_id);
+        let (sender, mut receiver) = tokio::sync::mpsc::unbounded_channel::<CloneProgress>();
+        tokio::spawn(async move {
+            while let Some(progress) = receiver.recv().await {
+                let fields = [
+                    ("clone_progress", serde_json::to_string(&progress).unwrap_or_default()),
+                    ("updated_at", now_unix().to_string()),
+                ];
+                if let Err(e) = conn.hset_multiple::<_, _, _, ()>(&key, &fields).await {
+                    println!("Warning: Failed to record clone progress: {}", e);
+                }
+            }
+        });
+        let last_sent: Mutex<Option<Instant>> = Mutex::new(None);
+        Arc::new(move |progress: &CloneProgress| {
+            let mut last_sent = last_sent.lock().unwrap();
+            let done = progress.indexed_objects == progress.total_objects;
+            if !done && last_sent.is_some_and(|at| at.elapsed() < CLONE_PROGRESS_INTERVAL) {
+                return;
+            }
+            *last_sent = Some(Instant::now());
+            let _ = sender.send(progress.clone());
+        })
+    }
+
+    // Records a continuous fuzz job's results so far on it, from the blocking fuzzer like clone
+    // progress. An attempt after a lost lease resumes from the last checkpoint of the one
+    // before, so what it writes includes the earlier attempt's results.
+    pub fn fuzz_checkpoint_sink(&self, job_id: &str) -> FuzzCheckpointSink {
+        let client = self.client.clone();
+        let key = format!("{}{}", JOB_KEY_PREFIX, job_id);
+        let conn: Mutex<Option<redis::Connection>> = Mutex::new(None);
+        Arc::new(move |checkpoint: &FuzzCheckpoint| {
+            let mut conn = conn.lock().unwrap();
+            if conn.is_none() {
+                match client.get_connection_with_timeout(Duration::from_secs(5)) {
+                    Ok(opened) => *conn = Some(opened),
+                    Err(e) => {
+                        println!("Warning: Failed to connect to record fuzz checkpoint: {}", e);
+                        return;
+                    }
+                }
+            }
+            let Some(connection) = conn.as_mut() else { return };
+            let fields = [
+                ("checkpoint", serde_json::to_string(checkpoint).unwrap_or_default()),
+                ("updated_at", now_unix().to_string()),
+            ];
+            if let Err(e) = connection.hset_multiple::<_, _, _, ()>(&key, &fields) {
+                println!("Warning: Failed to record fuzz checkpoint: {}", e);
+                *conn = None;
+            }
+        })
+    }
+
+    // Appends a running analysis's events to its job, for /api/jobs/{id}/events. Like clone
+    // progress they come from the blocking analysis, so they go over their own synchronous
+    // connection. The list lives as long as a finished job.
+    pub fn analysis_event_sink(&self, job_id: &str) -> AnalysisEventSink {
+        let client = self.client.clone();
+        let key = format!("{}{}{}", JOB_KEY_PREFIX, job_id, EVENTS_SUFFIX);
+        let conn: Mutex<Option<redis::Connection>> = Mutex::new(None);
+        Arc::new(move |event: &AnalysisEvent| {
+            let mut conn = conn.lock().unwrap();
+            if conn.is_none() {
+                match client.get_connection_with_timeout(Duration::from_secs(5)) {
+                    Ok(opened) => *conn = Some(opened),
+                    Err(e) => {
+                        println!("Warning: Failed to connect to record analysis events: {}", e);
+                        return;
+                    }
+                }
+            }
+            let Some(connection) = conn.as_mut() else { return };
+            let event = serde_json::to_string(event).unwrap_or_default();
+            let recorded = redis::pipe()
+                .rpush(&key, event).ignore()
+                .expire(&key, FINISHED_JOB_TTL_SECS).ignore()
+                .query::<()>(connection);
+            if let Err(e) = recorded {
+                println!("Warning: Failed to record analysis event: {}", e);
+                *conn = None;
+            }
+        })
+    }
+
+    // A job's analysis events from position `from` on, as JSON
+    pub async fn events(&self, job_id: &str, from: usize) -> Result<Vec<String>> {
+        let mut conn = self.conn.clone();
+        Ok(conn.lrange(format!("{}{}{}", JOB_KEY_PREFIX, job_id, EVENTS_SUFFIX), from as isize, -1).await?)
+    }
+
+    // Records the phase a running job is in. Writes are sent off in the background so timing
+    // the phases doesn't wait on Redis; a lost one only leaves the previous phase showing.
+    pub fn phase_sink(&self, job_id: &str) -> PhaseSink {
+        let conn = self.conn.clone();
+        let key = format!("{}{}", JOB_KEY_PREFIX, job_id);
+        let runtime = tokio::runtime::Handle::current();
+        Arc::new(move |phase: &str| {
+            let (mut conn, key) = (conn.clone(), key.clone());
+            let fields = [("phase", phase.to_string()), ("phase_started_at", now_unix().to_string())];
+            runtime.spawn(async move {
+                if let Err(e) = conn.hset_multiple::<_, _, _, ()>(&key, &fields).await {
+                    println!("Warning: Failed to record job phase: {}", e);
+                }
+            });
+        })
+    }
+
+    // Take the next job, if any, leasing it to `worker_id`
+    async fn lease(&self, worker_id: &str) -> Result<Option<Job>> {
+        let mut conn = self.conn.clone();
+        let now = now_unix();
+        let script = Script::new(LEASE_SCRIPT);
+        let mut invocation = script.key(LEGACY_QUEUE_KEY);
+        invocation.key(LEASES_KEY)
+            .key(SERVED_KEY)
+            .arg(now + self.lease_secs)
+            .arg(worker_id)
+            .arg(now)
+            .arg(JOB_KEY_PREFIX)
+            .arg(QUEUE_PREFIX);
+        for priority in JobPriority::ALL {
+            invocation.arg(priority.as_str());
+        }
+        let id: Option<String> = invocation.invoke_async(&mut conn).await?;
+        let Some(id) = id else {
+            return Ok(None);
+        };
+
+        let fields: HashMap<String, String> = conn.hgetall(format!("{}{}", JOB_KEY_PREFIX, id)).await?;
+        let kind = fields.get("kind").and_then(|k| JobKind::parse(k));
+        let payload = fields.get("payload").and_then(|p| serde_json::from_str(p).ok());
+        match (kind, payload) {
+            (Some(kind), Some(payload)) => Ok(Some(Job {
+                id,
+                kind,
+                priority: fields.get("priority").and_then(|p| JobPriority::parse(p)).unwrap_or_default(),
+                tenant: fields.get("tenant").cloned().unwrap_or_default(),
+                actor: fields.get("actor").cloned().unwrap_or_default(),
+                payload,
+            })),
+            _ => {
+                self.finish(worker_id, &id, &Err(anyhow!("Malformed job"))).await?;
+                Err(anyhow!("Job {} is malformed", id))
+            }
+        }
+    }
+
+    async fn renew(&self, worker_id: &str, job_id: &str) -> Result<()> {
+        let mut conn = self.conn.clone();
+        Script::new(RENEW_SCRIPT)
+            .key(LEASES_KEY)
+            .arg(job_id)
+            .arg(worker_id)
+            .arg(now_unix() + self.lease_secs)
+            .arg(JOB_KEY_PREFIX)
+            .invoke_async::<i64>(&mut conn)
+            .await?;
+        Ok(())
+    }
+
+    // Store the result of a completed job, or the error of a failed one
+    async fn finish(&self, worker_id: &str, job_id: &str, outcome: &Result<serde_json::Value>) -> Result<bool> {
+        let (status, value) = match outcome {
+            Ok(result) => ("completed", result.to_string()),
+            Err(e) => ("failed", e.to_string()),
+        };
+        let mut conn = self.conn.clone();
+        let recorded: i64 = Script::new(FINISH_SCRIPT)
+            .key(LEASES_KEY)
+            .arg(job_id)
+            .arg(worker_id)
+            .arg(status)
+            .arg(value)
+            .arg(now_unix())
+            .arg(JOB_KEY_PREFIX)
+            .arg(FINISHED_JOB_TTL_SECS)
+            .invoke_async(&mut conn)
+            .await?;
+        Ok(recorded == 1)
+    }
+
+    // Put jobs from dead workers back on the queue; safe to run from every worker
+    async fn requeue_expired(&self) -> Result<i64> {
+        let mut conn = self.conn.clone();
+        let now = now_unix();
+        Ok(Script::new(REAP_SCRIPT)
+            .key(LEASES_KEY)
+            .arg(now)
+            .arg(self.max_attempts)
+            .arg(now)
+            .arg(JOB_KEY_PREFIX)
+            .arg(FINISHED_JOB_TTL_SECS)
+            .arg(QUEUE_PREFIX)
+            .arg(JobPriority::default().as_str())
+            .invoke_async(&mut conn)
+            .await?)
+    }
+
+    async fn heartbeat(&self, worker_id: &str, active_jobs: usize, slots: usize) -> Result<()> {
+        let mut conn = self.conn.clone();
+        redis::pipe()
+            .set_ex(format!("{}{}", WORKER_KEY_PREFIX, worker_id), format!("{}:{}:{}", now_unix(), active_jobs, slots), HEARTBEAT_TTL_SECS)
+            .sadd(WORKERS_KEY, worker_id)
+            .query_async::<()>(&mut conn)
+            .await?;
+        Ok(())
+    }
+
+    // Workers with a live heartbeat; stale entries are pruned as a side effect
+    pub async fn workers(&self) -> Result<Vec<WorkerInfo>> {
+        let mut conn = self.conn.clone();
+        let ids: Vec<String> = conn.smembers(WORKERS_KEY).await?;
+        let mut workers = Vec::new();
+        for id in ids {
+            let heartbeat: Option<String> = conn.get(format!("{}{}", WORKER_KEY_PREFIX, id)).await?;
+            let Some(heartbeat) = heartbeat else {
+                conn.srem::<_, _, ()>(WORKERS_KEY, &id).await?;
+                continue;
+            };
+            // "<timestamp>:<active jobs>:<slots>"; workers from before slots were reported leave them off
+            let mut parts = heartbeat.split(':');
+            let mut number = || parts.next().and_then(|v| v.parse().ok());
+            workers.push(WorkerInfo {
+                id,
+                last_heartbeat: number().unwrap_or(0),
+                active_jobs: number().unwrap_or(0) as u32,
+                slots: number().map(|slots| slots as u32),
+            });
+        }
+        workers.sort_by(|a, b| a.id.cmp(&b.id));
+        Ok(workers)
+    }
+
+    // Jobs leased to a worker, longest running first
+    pub async fn active_jobs(&self) -> Result<Vec<ActiveJob>> {
+        let mut conn = self.conn.clone();
+        let leases: Vec<(String, i64)> = conn.zrange_withscores(LEASES_KEY, 0, -1).await?;
+        let now = now_unix();
+        let mut jobs = Vec::new();
+        for (id, lease_expires_at) in leases {
+            let fields: HashMap<String, String> = conn.hgetall(format!("{}{}", JOB_KEY_PREFIX, id)).await?;
+            if fields.is_empty() {
+                continue;
+            }
+            let field = |name: &str| fields.get(name).cloned();
+            let number = |name: &str| fields.get(name).and_then(|v| v.parse::<i64>().ok());
+            jobs.push(ActiveJob {
+                id,
+                kind: field("kind").unwrap_or_default(),
+                tenant: field("tenant").unwrap_or_default(),
+                priority: field("priority").and_then(|p| JobPriority::parse(&p)),
+                worker: field("worker"),
+                phase: field("phase"),
+                phase_elapsed_secs: number("phase_started_at").map(|at| now - at),
+                started_at: number("started_at"),
+                elapsed_secs: number("started_at").map(|at| now - at),
+                lease_expires_at,
+                attempts: number("attempts").unwrap_or(0) as u32,
+                clone_progress: field("clone_progress").and_then(|p| serde_json::from_str(&p).ok()),
+            });
+        }
+        jobs.sort_by_key(|job| job.started_at.unwrap_or(i64::MAX));
+        Ok(jobs)
+    }
+
+    // Jobs waiting in each class, and how many tenants they belong to
+    pub async fn queue_depths(&self) -> Result<Vec<QueueDepth>> {
+        let mut conn = self.conn.clone();
+        let mut depths = Vec::new();
+        for priority in JobPriority::ALL {
+            let tenants: Vec<String> = conn.zrange(tenants_key(priority), 0, -1).await?;
+            let mut queued = 0;
+            for tenant in &tenants {
+                queued += conn.llen::<_, u64>(tenant_queue_key(priority, tenant)).await?;
+            }
+            depths.push(QueueDepth { priority, queued, tenants: tenants.len() as u64 });
+        }
+        Ok(depths)
+    }
+}
+
+// Run `concurrency` job loops plus a heartbeat loop that renews leases and requeues jobs from dead workers.
+// Jobs clone, build and fuzz synchronously for up to hours, so each loop gets its own thread and
+// runtime; the heartbeat stays on the current (actix) runtime, where nothing holds it up past
+// the lease. The handler's futures don't need to be Send.
+pub fn spawn_workers<F, Fut>(queue: Arc<JobQueue>, concurrency: usize, handler: F) -> Result<String>
+where
+    F: Fn(Job) -> Fut + Clone + Send + 'static,
+    Fut: Future<Output = Result<serde_json::Value>> + 'static,
+{
+    let worker_id = format!("{}-{}", hostname(), &uuid::Uuid::new_v4().to_string()[..8]);
+    let active: Arc<Mutex<HashSet<String>>> = Arc::new(Mutex::new(HashSet::new()));
+
+    {
+        let (queue, worker_id, active) = (queue.clone(), worker_id.clone(), active.clone());
+        actix_web::rt::spawn(async move {
+            loop {
+                let jobs: Vec<String> = active.lock().unwrap().iter().cloned().collect();
+                if let Err(e) = queue.heartbeat(&worker_id, jobs.len(), concurrency.max(1)).await {
+                    println!("Warning: Worker heartbeat failed: {}", e);
+                }
+                for job_id in &jobs {
+                    if let Err(e) = queue.renew(&worker_id, job_id).await {
+                        println!("Warning: Failed to renew lease on job {}: {}", job_id, e);
+                    }
+                }
+                match queue.requeue_expired().await {
+                    Ok(requeued) if requeued > 0 => println!("Requeued {} jobs from expired leases", requeued),
+                    Ok(_) => {},
+                    Err(e) => println!("Warning: Failed to requeue expired jobs: {}", e),
+                }
+                actix_web::rt::time::sleep(Duration::from_secs(HEARTBEAT_INTERVAL_SECS)).await;
+            }
+        });
+    }
+
+    for slot in 0..concurrency.max(1) {
+        let (queue, worker_id, active, handler) = (queue.clone(), worker_id.clone(), active.clone(), handler.clone());
+        let job_loop = move || actix_web::rt::System::new().block_on(async move {
+            loop {
+                let job = match queue.lease(&worker_id).await {
+                    Ok(Some(job)) => job,
+                    Ok(None) => {
+                        actix_web::rt::time::sleep(Duration::from_secs(1)).await;
+                        continue;
+                    },
+                    Err(e) => {
+                        println!("Warning: Failed to lease job: {}", e);
+                        actix_web::rt::time::sleep(Duration::from_secs(5)).await;
+                        continue;
+                    }
+                };
+
+                println!("Worker {} running {} job {}", worker_id, job.kind.as_str(), job.id);
+                active.lock().unwrap().insert(job.id.clone());
+                let job_id = job.id.clone();
+                let span = tracing::info_span!("job", job.id = %job_id, job.kind = job.kind.as_str(), worker.id = %worker_id);
+                let outcome = handler(job).instrument(span).await;
+                active.lock().unwrap().remove(&job_id);
+
+                match queue.finish(&worker_id, &job_id, &outcome).await {
+                    Ok(true) => println!("Job {} {}", job_id, if outcome.is_ok() { "completed" } else { "failed" }),
+                    Ok(false) => println!("Warning: Dropped result of job {}, its lease had expired", job_id),
+                    Err(e) => println!("Warning: Failed to record result of job {}: {}", job_id, e),
+                }
+            }
+        });
+        thread::Builder::new()
+            .name(format!("job-slot-{}", slot))
+            .spawn(job_loop)
+            .map_err(|e| anyhow!("Failed to start job slot {}: {}", slot, e))?;
+    }
+
+    Ok(worker_id)
+}
+
+fn hostname() -> String {
+    env::var("HOSTNAME").ok()
+        .filter(|h| !h.is_empty())
+        .unwrap_or_else(|| "worker".to_string())
+}
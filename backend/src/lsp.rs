@@ -0,0 +1,171 @@
+use anyhow::{anyhow, Result};
+use lsp_server::{Connection, ErrorCode, Message, Notification, Response};
+use lsp_types::notification::{DidChangeTextDocument, DidCloseTextDocument, DidOpenTextDocument, Notification as _, PublishDiagnostics};
+use lsp_types::{
+    CodeDescription, Diagnostic, DiagnosticSeverity, DidChangeTextDocumentParams, DidCloseTextDocumentParams,
+    DidOpenTextDocumentParams, InitializeParams, NumberOrString, Position, PublishDiagnosticsParams, Range,
+    ServerCapabilities, TextDocumentSyncCapability, TextDocumentSyncKind, Uri,
+};
+use std::collections::HashMap;
+use std::str::FromStr;
+
+use crate::analyzer::CodeAnalyzer;
+use crate::models::{BugSeverity, CodeBug, Rule};
+use crate::rules::rule_catalog;
+
+// How an editor reaches the server
+pub enum Transport {
+    Stdio,
+    // Listen on this address for a single editor connection
+    Tcp(String),
+}
+
+impl Transport {
+    // SAFEX_LSP = "stdio" or "<host>:<port>"; unset runs the HTTP API as usual
+    pub fn from_env() -> Option<Self> {
+        match std::env::var("SAFEX_LSP").ok()?.trim() {
+            "" => None,
+            "stdio" => Some(Transport::Stdio),
+            address => Some(Transport::Tcp(address.to_string())),
+        }
+    }
+}
+
+// Serve the single-file rules as diagnostics on the Rust documents the editor has open, until
+// it shuts the server down. Logs go to stderr: with the stdio transport stdout carries the
+// protocol.
+pub fn serve(transport: Transport) -> Result<()> {
+    let (connection, io_threads) = match transport {
+        Transport::Stdio => Connection::stdio(),
+        Transport::Tcp(address) => {
+            eprintln!("Waiting for an editor to connect on {}", address);
+            Connection::listen(address.as_str())?
+        }
+    };
+
+    let capabilities = ServerCapabilities {
+        text_document_sync: Some(TextDocumentSyncCapability::Kind(TextDocumentSyncKind::FULL)),
+        ..Default::default()
+    };
+    let params = connection.initialize(serde_json::to_value(capabilities)?)
+        .map_err(|e| anyhow!("LSP initialization failed: {}", e))?;
+    let params: InitializeParams = serde_json::from_value(params)?;
+    #[allow(deprecated)]
+    let root = params.workspace_folders.as_ref()
+        .and_then(|folders| folders.first())
+        .map(|folder| folder.uri.as_str().to_string())
+        .or_else(|| params.root_uri.as_ref().map(|uri| uri.as_str().to_string()));
+    eprintln!("Safex language server started for {}", root.as_deref().unwrap_or("a workspace without a root"));
+
+    let server = Server {
+        analyzer: CodeAnalyzer::new(),
+        rules: rule_catalog().into_iter().map(|rule| (rule.id.clone(), rule)).collect(),
+        root: root.map(|root| format!("{}/", root.trim_end_matches('/'))),
+    };
+    for message in &connection.receiver {
+        match message {
+            Message::Request(request) => {
+                if connection.handle_shutdown(&request)? {
+                    break;
+                }
+                let response = Response::new_err(request.id, ErrorCode::MethodNotFound as i32, format!("Unsupported request: {}", request.method));
+                connection.sender.send(Message::Response(response))?;
+            },
+            Message::Notification(notification) => {
+                if let Some(published) = server.handle(notification)? {
+                    let notification = Notification::new(PublishDiagnostics::METHOD.to_string(), published);
+                    connection.sender.send(Message::Notification(notification))?;
+                }
+            },
+            Message::Response(_) => {},
+        }
+    }
+
+    drop(connection);
+    io_threads.join()?;
+    eprintln!("Safex language server stopped");
+    Ok(())
+}
+
+struct Server {
+    analyzer: CodeAnalyzer,
+    rules: HashMap<String, Rule>,
+    // Workspace root URI with a trailing slash, which findings are reported relative to
+    root: Option<String>,
+}
+
+impl Server {
+    // Documents are analyzed whole on every change; the rules take milliseconds per file
+    fn handle(&self, notification: Notification) -> Result<Option<PublishDiagnosticsParams>> {
+        let (uri, text, version) = match notification.method.as_str() {
+            DidOpenTextDocument::METHOD => {
+                let params: DidOpenTextDocumentParams = serde_json::from_value(notification.params)?;
+                (params.text_document.uri, Some(params.text_document.text), Some(params.text_document.version))
+            },
+            DidChangeTextDocument::METHOD => {
+                let mut params: DidChangeTextDocumentParams = serde_json::from_value(notification.params)?;
+                // Full sync: the last change holds the whole document
+                let Some(change) = params.content_changes.pop() else { return Ok(None) };
+                (params.text_document.uri, Some(change.text), Some(params.text_document.version))
+            },
+            // Diagnostics of closed documents are cleared
+            DidCloseTextDocument::METHOD => {
+                let params: DidCloseTextDocumentParams = serde_json::from_value(notification.params)?;
+                (params.text_document.uri, None, None)
+            },
+            _ => return Ok(None),
+        };
+        if !uri.as_str().ends_with(".rs") {
+            return Ok(None);
+        }
+
+        let diagnostics = match text {
+            Some(text) => {
+                let file = self.relative_path(&uri);
+                self.analyzer.analyze_source(&file, &text).iter()
+                    .map(|bug| self.diagnostic(bug, &text))
+                    .collect()
+            },
+            None => Vec::new(),
+        };
+        Ok(Some(PublishDiagnosticsParams { uri, diagnostics, version }))
+    }
+
+    fn relative_path(&self, uri: &Uri) -> String {
+        let uri = uri.as_str();
+        self.root.as_deref()
+            .and_then(|root| uri.strip_prefix(root))
+            .unwrap_or(uri)
+            .to_string()
+    }
+
+    fn diagnostic(&self, bug: &CodeBug, text: &str) -> Diagnostic {
+        let rule = bug.rule_id.as_deref().and_then(|id| self.rules.get(id));
+        Diagnostic {
+            range: line_range(text, bug.line),
+            severity: Some(match bug.severity {
+                BugSeverity::Critical | BugSeverity::High => DiagnosticSeverity::ERROR,
+                BugSeverity::Medium => DiagnosticSeverity::WARNING,
+                BugSeverity::Low => DiagnosticSeverity::INFORMATION,
+            }),
+            code: bug.rule_id.clone().map(NumberOrString::String),
+            code_description: rule
+                .and_then(|rule| rule.references.first())
+                .and_then(|reference| Uri::from_str(&reference.url).ok())
+                .map(|href| CodeDescription { href }),
+            source: Some("safex".to_string()),
+            message: format!("{}\nFix: {}", bug.bug, bug.fix),
+            ..Default::default()
+        }
+    }
+}
+
+// The finding's 1-based line without its indentation, in the UTF-16 columns editors count in
+fn line_range(text: &str, line: u32) -> Range {
+    let index = line.saturating_sub(1);
+    let content = text.lines().nth(index as usize).unwrap_or_default();
+    let indent = content.len() - content.trim_start().len();
+    let start = content[..indent].encode_utf16().count() as u32;
+    let end = content.trim_end().encode_utf16().count() as u32;
+    Range::new(Position::new(index, start), Position::new(index, end.max(start)))
+}
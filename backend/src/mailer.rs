@@ -0,0 +1,138 @@
+use anyhow::{anyhow, Result};
+use lettre::message::header::ContentType;
+use lettre::message::{Attachment, Mailbox, MultiPart, SinglePart};
+use lettre::transport::smtp::authentication::Credentials;
+use lettre::{AsyncSmtpTransport, AsyncTransport, Message, Tokio1Executor};
+use std::env;
+
+use crate::db::Database;
+use crate::models::BugSeverity;
+use crate::report::{escape_html, render_report, tenant_template, ReportContext};
+
+pub const DEFAULT_SUBJECT_TEMPLATE: &str = "Safex report for {{repo_url}}: {{total}} findings ({{high}} high)";
+pub const DEFAULT_BODY_TEMPLATE: &str = "<p>The Safex analysis of <strong>{{repo_url}}</strong> has completed.</p>\
+<p>{{total}} findings: {{critical}} critical, {{high}} high, {{medium}} medium, {{low}} low. The full report is attached.</p>\
+{{report}}\
+<p style=\"font-size:small\"><a href=\"{{unsubscribe_url}}\">Unsubscribe</a> from these reports.</p>";
+
+// SMTP delivery of finished reports; disabled unless SMTP_HOST is set
+pub struct Mailer {
+    transport: Option<AsyncSmtpTransport<Tokio1Executor>>,
+    from: Option<Mailbox>,
+    public_url: String,
+}
+
+impl Mailer {
+    pub fn from_env() -> Result<Self> {
+        let public_url = env::var("SAFEX_PUBLIC_URL").unwrap_or_else(|_| "http://localhost:8080".to_string());
+        let Ok(host) = env::var("SMTP_HOST") else {
+            println!("No SMTP_HOST configured, email report delivery disabled");
+            return Ok(Self { transport: None, from: None, public_url });
+        };
+
+        let from: Mailbox = env::var("SMTP_FROM")
+            .map_err(|_| anyhow!("SMTP_FROM must be set when SMTP_HOST is configured"))?
+            .parse()?;
+
+        // SMTP_TLS: "starttls" (default), "tls" for implicit TLS, or "none" for local relays
+        let mut builder = match env::var("SMTP_TLS").unwrap_or_default().as_str() {
+            "tls" => AsyncSmtpTransport::<Tokio1Executor>::relay(&host)?,
+            "none" => AsyncSmtpTransport::<Tokio1Executor>::builder_dangerous(&host),
+            _ => AsyncSmtpTransport::<Tokio1Executor>::starttls_relay(&host)?,
+        };
+        if let Some(port) = env::var("SMTP_PORT").ok().and_then(|p| p.parse().ok()) {
+            builder = builder.port(port);
+        }
+        if let (Ok(username), Ok(password)) = (env::var("SMTP_USERNAME"), env::var("SMTP_PASSWORD")) {
+            builder = builder.credentials(Credentials::new(username, password));
+        }
+
+        println!("Email report delivery enabled via {}", host);
+        Ok(Self {
+            transport: Some(builder.build()),
+            from: Some(from),
+            public_url,
+        })
+    }
+
+    pub fn is_configured(&self) -> bool {
+        self.transport.is_some()
+    }
+
+    // SAFEX_PUBLIC_URL without a trailing slash, for links back to this server
+    pub fn public_url(&self) -> &str {
+        self.public_url.trim_end_matches('/')
+    }
+
+    // Send the rendered report to every active recipient of the tenant; returns how many were
+    // sent. The report goes out as HTML only, as nothing here renders PDF.
+    pub async fn deliver_report(&self, db: &Database, tenant: &str, context: &ReportContext) -> Result<usize> {
+        let (Some(transport), Some(from)) = (&self.transport, &self.from) else {
+            return Ok(0);
+        };
+
+        let settings = db.get_email_settings(tenant)?;
+        let recipients = db.active_email_recipients(tenant)?;
+        if recipients.is_empty() {
+            return Ok(0);
+        }
+
+        let report_html = render_report(context, tenant_template(db, tenant).as_deref());
+        let subject_template = settings.subject_template.as_deref().unwrap_or(DEFAULT_SUBJECT_TEMPLATE);
+        let body_template = settings.body_template.as_deref().unwrap_or(DEFAULT_BODY_TEMPLATE);
+
+        let mut sent = 0;
+        for (email, unsubscribe_token) in recipients {
+            let unsubscribe_url = format!("{}/api/email/unsubscribe?token={}", self.public_url(), unsubscribe_token);
+            let values = |escape: fn(&str) -> String| [
+                ("repo_url", escape(&context.repo_url)),
+                ("run_id", escape(context.run_id.as_deref().unwrap_or(""))),
+                ("commit_sha", escape(context.commit_sha.as_deref().unwrap_or(""))),
+                ("total", context.bugs.len().to_string()),
+                ("critical", context.count(BugSeverity::Critical).to_string()),
+                ("high", context.count(BugSeverity::High).to_string()),
+                ("medium", context.count(BugSeverity::Medium).to_string()),
+                ("low", context.count(BugSeverity::Low).to_string()),
+                ("unsubscribe_url", escape(&unsubscribe_url)),
+                ("report", report_html.clone()),
+            ];
+            // Subjects are plain text; only the HTML body gets escaped values
+            let subject = render_template(subject_template, &values(|v| v.to_string()));
+            let body = render_template(body_template, &values(escape_html));
+
+            let to: Mailbox = match email.parse() {
+                Ok(to) => to,
+                Err(e) => {
+                    println!("Warning: Skipping invalid recipient {}: {}", email, e);
+                    continue;
+                }
+            };
+            let message = Message::builder()
+                .from(from.clone())
+                .to(to)
+                .subject(subject)
+                .multipart(
+                    MultiPart::mixed()
+                        .singlepart(SinglePart::html(body))
+                        .singlepart(Attachment::new("safex-report.html".to_string())
+                            .body(report_html.clone(), ContentType::TEXT_HTML)),
+                )?;
+
+            match transport.send(message).await {
+                Ok(_) => sent += 1,
+                Err(e) => println!("Warning: Failed to send report email to {}: {}", email, e),
+            }
+        }
+
+        Ok(sent)
+    }
+}
+
+// Replace {{name}} placeholders; unknown placeholders are left as-is
+fn render_template(template: &str, values: &[(&str, String)]) -> String {
+    let mut rendered = template.to_string();
+    for (name, value) in values {
+        rendered = rendered.replace(&format!("{{{{{}}}}}", name), value);
+    }
+    rendered
+}
@@ -1,83 +1,252 @@
 mod models;
 mod github;
 mod analyzer;
+mod auth;
+mod db;
+mod mailer;
+mod report;
 mod fuzzer;
 mod report_logger;
+mod toolchain;
+mod storage;
+mod jobs;
+mod repo_url;
+mod clone_cache;
+mod stats;
+mod telemetry;
+mod audit;
+mod rate_limit;
+mod cluster;
+mod rent;
+mod deployment;
+mod rules;
+mod external;
+mod autofix;
+mod finding_issue;
+mod jira;
+mod fix_pr;
+mod metadata_cache;
+mod webhook;
+mod instructions;
+mod attestation;
+mod certificate;
+mod confirmation;
+mod indexer;
+mod reverify;
+mod approvals;
+mod commit_status;
+mod estimate;
+mod deploy_keys;
+mod sandbox;
+mod vendor;
+mod program_build;
+mod binary_checks;
+mod account_decoder;
+mod cpi;
+mod taint;
+mod msrv;
+mod ecosystem;
+mod self_test;
+mod benchmark;
+mod validation;
+mod hashing;
+mod signing;
+mod cors;
+mod tls;
+mod account_graph;
+mod pda;
+mod regression;
+mod config;
+mod environment;
+mod dashboard;
+mod payer_monitor;
+mod anchoring;
+mod exclusions;
+mod capabilities;
+#[cfg(feature = "lsp")]
+mod lsp;
 
-use actix_web::{get, post, web, App, HttpResponse, HttpServer, Responder};
-use actix_web::middleware::Logger;
-use actix_web::http::header;
-use actix_cors::Cors;
-use models::{RepoIngestionRequest, RepoIngestionResponse, RepoContentsRequest, RepoContentsResponse, CodeAnalysisRequest, CodeAnalysisResponse, FuzzingRequest, FuzzingResponse, ReportLogRequest, ReportLogResponse};
-use github::GitHubClient;
-use analyzer::CodeAnalyzer;
-use fuzzer::Fuzzer;
-use report_logger::ReportLogger;
+use actix_web::{delete, error, get, patch, post, put, web, App, HttpMessage, HttpRequest, HttpResponse, Responder};
+use actix_web::http::{header, StatusCode};
+use futures_util::StreamExt;
+use actix_multipart::Multipart;
+use sha2::{Digest, Sha256};
+use actix_web::middleware::{from_fn, Compress, Logger};
+use tracing_actix_web::TracingLogger;
+use models::{RepoIngestionRequest, RepoIngestionResponse, RepoContentsRequest, RepoContentsResponse, RepoFileQuery, RepoFilesRequest, RepoFilesResponse, RepoFile, RepoStatsRequest, RepoStatsResponse, EstimateRequest, EstimateResponse, InstructionsRequest, InstructionsResponse, AccountGraphRequest, AccountGraphResponse, GraphFormat, RentEstimateRequest, RentEstimateResponse, DeploymentCheckRequest, DeploymentCheckResponse, RulesResponse, AutofixPreviewRequest, AutofixPreviewResponse, CreateFixPrRequest, CreateFixPrResponse, CreateIssueRequest, CreateIssueResponse, JiraSettingsRequest, JiraSettingsResponse, JiraPushRequest, JiraPushResponse, JiraPushResult, JiraTicket, JiraTicketsQuery, JiraTicketsResponse, TextEdit, CodeAnalysisRequest, CodeAnalysisResponse, SnippetAnalysisRequest, SnippetAnalysisResponse, AnalysisMode, AnalysisEvent, FuzzingRequest, FuzzingResponse, FuzzingMetadata, FuzzStatus, FuzzStrategy, FuzzEngine, FuzzCheckpoint, CapabilitiesResponse, RegressionFuzzRequest, RegressionFuzz, RegressionFuzzPlan, RegressionFuzzJob, ReportLogRequest, ReportLogResponse, ReportSubmission, AnchorBackend, HashAlgorithm, ApprovalRequest, ApprovalResponse, ApprovalStatus, CertificateRequest, CertificateResponse, CertificateTarget, Certificate, ConfirmationStatus, ReportsQuery, ReportsResponse, ReverifyReportsResponse, ReportStatus, ReportVerifyRequest, ReportRevealRequest, ReportVerifyResponse, ReportAnchor, ResponseSignature, SigningKeyResponse, AttestationBuildRequest, AttestationBuildResponse, AttestationSubmitRequest, AttestationSubmitResponse, AttestationsQuery, AttestationsResponse, ProjectType, CodeBug, TrendsQuery, TrendsResponse, CompareQuery, CompareResponse, FindingsQuery, FindingsCursor, FindingsResponse, FindingTriage, TriageRequest, TriageListQuery, TriageResponse, FindingCommentRequest, FindingCommentsQuery, ResolveThreadRequest, FindingThreadsResponse, Engagement, EngagementRequest, EngagementUpdateRequest, EngagementRunRequest, EngagementRunKind, EngagementState, EngagementsQuery, EngagementResponse, EngagementsResponse, AssignFindingRequest, AssignedFindingsQuery, FindingAssignmentsResponse, TaxonomyResponse, ReportTemplateRequest, ReportTemplateResponse, EmailSettingsRequest, EmailSettingsResponse, UnsubscribeQuery, JobPriority, JobSubmitQuery, JobSubmitResponse, JobStatusResponse, WorkersResponse, AdminDashboardResponse, JobsOverview, WorkerUtilization, HealthResponse, AuditLogQuery, AuditLogResponse, GitHubWebhookPayload, WebhookResponse, DeployKeyRequest, DeployKeyResponse, BuildProgramRequest, BuildProgramResponse, BuiltProgram, DecodeAccountRequest, DecodeAccountResponse, DerivePdaRequest, DerivePdaResponse, EcosystemProjectRequest, EcosystemProjectsResponse, EcosystemStatsResponse, SelfTestResponse, BenchmarkResponse, BenchmarkRunsResponse, CorsOriginRequest, CorsOriginsResponse, ConfigReloadResponse};
+use github::{max_file_bytes, GitHubClient, RequestToken};
+use analyzer::{AnalysisEventSink, CodeAnalyzer};
+use fuzzer::{engine_available, fuzz_corpus_dir, harness_cache_dir, max_fuzz_timeout, summarize_findings, FuzzBudget, FuzzCheckpointSink, Fuzzer, Harness, SeedAccount, DEFAULT_COMPUTE_UNIT_BUDGET, DEFAULT_FUZZ_TIMEOUT_SECS, MAX_SEED_ACCOUNT_BYTES};
+use report_logger::{max_report_bytes, parse_report_hash, repo_url_hash, ReportLogger};
+use attestation::Attestations;
+use certificate::CertificateMinter;
+use indexer::spawn_indexer;
+use reverify::{reverify_reports, spawn_reverification};
+use ecosystem::{ecosystem_stats, Ecosystem};
+use self_test::run_self_test;
+use benchmark::{run_benchmark, HISTORY_LIMIT};
+use validation::{commit_sha, invalid_field, parse_valid, Valid};
+use hashing::{commit_report, decode_hex, hash_report, hex, ReportDigest, ReportHasher};
+use signing::ResponseSigner;
+use approvals::{verify_approval, ReportApprovers};
+use commit_status::{default_fail_on, gate_passed, post_commit_status};
+use estimate::estimate_analysis;
+use deploy_keys::DeployKeys;
+use sandbox::{NetworkPolicy, Sandbox};
+use program_build::build_programs;
+use binary_checks::check_build;
+use account_decoder::decode_account_data;
+use cpi::cpi_surface;
+use toolchain::{ProvisionedToolchains, ToolchainManager};
+use environment::{execution_meta, PhaseTimer};
+use dashboard::{dir_usage, temp_dir_usage};
+use payer_monitor::{spawn_payer_monitor, PayerMonitor};
+use anchoring::{AnchorEntry, AnchorTracker, Anchorers};
+use exclusions::PathExclusions;
+use capabilities::CapabilityProbe;
+use cluster::Cluster;
+use solana_sdk::pubkey::Pubkey;
+use std::str::FromStr;
+use db::{now_unix, Database};
+use auth::{is_admin, ApiKeys, Caller};
+use cors::{cors, normalize_origin, AllowedOrigins};
+use mailer::Mailer;
+use report::{render_report, tenant_template, ReportContext};
+use clone_cache::{resolve_repo_path, CloneCache};
+use stats::compute_repo_stats;
+use instructions::{extract_instructions, find_instruction, instruction_layout};
+use regression::changed_instructions;
+use account_graph::{build_account_graph, to_dot};
+use pda::{derive_pda, discover_pdas};
+use deployment::check_deployment;
+use rules::{current_rule_set, resolve_rule_set, rule_catalog, rule_sets, taxonomy_counts};
+use external::ExternalAnalyzers;
+use autofix::{apply_edits, unified_diff};
+use finding_issue::open_finding_issue;
+use fix_pr::open_fix_pull_request;
+use jira::{JiraClient, TicketSource};
+use metadata_cache::MetadataCache;
+use webhook::{WebhookSecret, WEBHOOK_ACTOR};
+use repo_url::RepoUrl;
+use rent::{default_rent_exempt_minimum, estimate_account_sizes, parse_account_schemas, rent_exempt_minimums};
+use telemetry::Telemetry;
+use audit::AuditEvent;
+use rate_limit::{rate_limit, RateLimits};
+use jobs::{Job, JobKind, JobQueue, Role};
+use storage::{content_type_for_key, owned_by, storage_from_env, tenant_key, validate_key, Storage};
+use serde_json::json;
 use tempfile::TempDir;
-use git2::Repository;
-use std::time::Instant;
+use std::time::{Duration, Instant};
+use std::collections::{HashMap, HashSet};
 use std::path::Path;
+use std::sync::Arc;
 
 #[get("/")]
 async fn hello() -> impl Responder {
     HttpResponse::Ok().body("Hello world!")
 }
 
+// For load balancers and uptime checks: 200 while the server is up, "degraded" when
+// something will need an operator soon. Reads only state the background checks keep.
+#[get("/api/health")]
+async fn health(payer_monitor: Option<web::Data<PayerMonitor>>) -> impl Responder {
+    let payer = payer_monitor.and_then(|payer_monitor| payer_monitor.latest());
+    let (status, message) = match &payer {
+        Some(payer) if payer.low => ("degraded", format!("Report payer balance is low ({} SOL)", payer.sol)),
+        _ => ("ok", "Healthy".to_string()),
+    };
+    HttpResponse::Ok().json(HealthResponse {
+        success: true,
+        message,
+        status: status.to_string(),
+        payer,
+    })
+}
+
 #[post("/api/ingest-repo")]
-async fn ingest_repo(repo_request: web::Json<RepoIngestionRequest>) -> impl Responder {
-    let github_client = GitHubClient::new();
+async fn ingest_repo(repo_request: Valid<RepoIngestionRequest>, token: RequestToken, metadata_cache: web::Data<MetadataCache>) -> impl Responder {
+    let github_client = GitHubClient::new().with_request_token(&token).with_cache(metadata_cache.into_inner());
     
-    match github_client.get_repo_from_url(&repo_request.repo_url).await {
-        Ok(repo) => {
-            // Check if it's an Anchor project
-            let is_anchor_project = match github_client.clone_and_validate_anchor_project(&repo_request.repo_url) {
-                Ok(is_anchor) => {
-                    if !is_anchor {
-                        // If not an Anchor project, return error
-                        let response = RepoIngestionResponse {
-                            success: false,
-                            message: "Repository is not an Anchor project. Please provide a valid Solana Anchor project.".to_string(),
-                            repo: Some(repo),
-                            is_anchor_project: Some(false),
-                        };
-                        return HttpResponse::BadRequest().json(response);
-                    }
-                    Some(true)
-                },
-                Err(e) => {
-                    let response = RepoIngestionResponse {
-                        success: false,
-                        message: format!("Failed to validate Anchor project: {}", e),
-                        repo: Some(repo),
-                        is_anchor_project: None,
-                    };
-                    return HttpResponse::BadRequest().json(response);
-                }
-            };
-            
+    // Only fetch metadata from hosts we know; self-hosted servers (Gitea, Gerrit,
+    // bare git) go straight to the clone-based validation
+    let repo = if repo_request.repo_url.is_github() {
+        match github_client.get_repo_from_url(&repo_request.repo_url).await {
+            Ok(repo) => Some(repo),
+            Err(e) => {
+                let response = RepoIngestionResponse {
+                    success: false,
+                    message: format!("Failed to ingest repository: {}", e),
+                    repo: None,
+                    repo_name: None,
+                    is_anchor_project: None,
+                    project_type: None,
+                };
+                return HttpResponse::BadRequest().json(response);
+            }
+        }
+    } else {
+        println!("Skipping metadata lookup for non-GitHub host: {}", repo_request.repo_url);
+        None
+    };
+    let repo_name = repo.as_ref()
+        .map(|r| r.name.clone())
+        .or_else(|| Some(repo_request.repo_url.name().to_string()));
+    
+    // Check if it's an Anchor (or native Solana) project
+    let project_type = match github_client.clone_and_detect_project_type(repo_request.repo_url.clone_url()) {
+        Ok(Some(project_type)) => project_type,
+        Ok(None) => {
+            // If not a Solana program, return error
             let response = RepoIngestionResponse {
-                success: true,
-                message: "Anchor project successfully ingested".to_string(),
-                repo: Some(repo),
-                is_anchor_project,
+                success: false,
+                message: "Repository is not a Solana program. Please provide a valid Anchor or native Solana project.".to_string(),
+                repo,
+                repo_name,
+                is_anchor_project: Some(false),
+                project_type: None,
             };
-            HttpResponse::Ok().json(response)
+            return HttpResponse::BadRequest().json(response);
         },
         Err(e) => {
             let response = RepoIngestionResponse {
                 success: false,
-                message: format!("Failed to ingest repository: {}", e),
-                repo: None,
+                message: format!("Failed to validate Anchor project: {}", e),
+                repo,
+                repo_name,
                 is_anchor_project: None,
+                project_type: None,
             };
-            HttpResponse::BadRequest().json(response)
+            return HttpResponse::BadRequest().json(response);
         }
-    }
+    };
+    
+    let response = RepoIngestionResponse {
+        success: true,
+        message: match project_type {
+            ProjectType::Anchor => "Anchor project successfully ingested".to_string(),
+            ProjectType::Native => "Native Solana program successfully ingested".to_string(),
+        },
+        repo,
+        repo_name,
+        is_anchor_project: Some(project_type == ProjectType::Anchor),
+        project_type: Some(project_type),
+    };
+    HttpResponse::Ok().json(response)
 }
 
 #[post("/api/repo-contents")]
-async fn repo_contents(contents_request: web::Json<RepoContentsRequest>) -> impl Responder {
-    let github_client = GitHubClient::new();
+async fn repo_contents(contents_request: Valid<RepoContentsRequest>, req: HttpRequest, token: RequestToken, metadata_cache: web::Data<MetadataCache>) -> impl Responder {
+    fetch_repo_contents(&contents_request, &req, &token, &metadata_cache).await
+}
+
+// Same as the POST form, but cacheable by browsers and proxies
+#[get("/api/repo-contents")]
+async fn repo_contents_query(contents_request: web::Query<RepoContentsRequest>, req: HttpRequest, token: RequestToken, metadata_cache: web::Data<MetadataCache>) -> impl Responder {
+    fetch_repo_contents(&contents_request, &req, &token, &metadata_cache).await
+}
+
+async fn fetch_repo_contents(contents_request: &RepoContentsRequest, req: &HttpRequest, token: &RequestToken, metadata_cache: &web::Data<MetadataCache>) -> HttpResponse {
+    let github_client = GitHubClient::new().with_request_token(token).with_cache(metadata_cache.clone().into_inner());
     let path_str = contents_request.path.as_deref();
     
     match github_client.get_repo_contents(&contents_request.repo_url, path_str).await {
@@ -87,10 +256,11 @@ async fn repo_contents(contents_request: web::Json<RepoContentsRequest>) -> impl
                 message: "Repository contents fetched successfully".to_string(),
                 contents: Some(contents),
                 file_content: None,
-                repo_url: contents_request.repo_url.clone(),
+                repo_url: contents_request.repo_url.to_string(),
                 path: path_str.unwrap_or("").to_string(),
             };
-            HttpResponse::Ok().json(response)
+            // Branch heads move, so only cache briefly
+            cached_json(req, &response, "private, max-age=60")
         },
         Err(e) => {
             let response = RepoContentsResponse {
@@ -98,7 +268,7 @@ async fn repo_contents(contents_request: web::Json<RepoContentsRequest>) -> impl
                 message: format!("Failed to fetch repository contents: {}", e),
                 contents: None,
                 file_content: None,
-                repo_url: contents_request.repo_url.clone(),
+                repo_url: contents_request.repo_url.to_string(),
                 path: path_str.unwrap_or("").to_string(),
             };
             HttpResponse::BadRequest().json(response)
@@ -106,214 +276,5319 @@ async fn repo_contents(contents_request: web::Json<RepoContentsRequest>) -> impl
     }
 }
 
-#[post("/api/fuzz-test")]
-async fn fuzz_test(fuzzing_request: web::Json<FuzzingRequest>) -> impl Responder {
-    let start_time = Instant::now();
-    let github_client = GitHubClient::new();
+// GitHub webhook: a push makes cached metadata and listings for the repository stale. With
+// SAFEX_REGRESSION_FUZZ_TENANT set, the instructions the push changed are also fuzzed, as
+// jobs of that tenant.
+#[post("/api/webhooks/github")]
+async fn github_webhook(req: HttpRequest, body: web::Bytes, metadata_cache: web::Data<MetadataCache>, secret: Option<web::Data<WebhookSecret>>, queue: Option<web::Data<JobQueue>>) -> impl Responder {
+    let webhook_response = |success: bool, message: String, invalidated: Option<usize>| WebhookResponse { success, message, invalidated, job_id: None };
+    let Some(secret) = secret else {
+        return HttpResponse::ServiceUnavailable().json(webhook_response(false, "GITHUB_WEBHOOK_SECRET is not configured".to_string(), None));
+    };
+    let signature = req.headers().get("X-Hub-Signature-256").and_then(|v| v.to_str().ok()).unwrap_or("");
+    if !secret.verify(&body, signature) {
+        return HttpResponse::Unauthorized().json(webhook_response(false, "Invalid webhook signature".to_string(), None));
+    }
     
-    // Create temp directory for cloning and testing
-    let temp_dir = match TempDir::new() {
-        Ok(dir) => dir,
+    let event = req.headers().get("X-GitHub-Event").and_then(|v| v.to_str().ok()).unwrap_or("");
+    if event != "push" {
+        return HttpResponse::Ok().json(webhook_response(true, format!("Ignored {} event", event), None));
+    }
+    let (repository, before, after) = match serde_json::from_slice::<GitHubWebhookPayload>(&body) {
+        Ok(GitHubWebhookPayload { repository: Some(repository), before, after }) => (repository, before, after),
+        _ => return HttpResponse::BadRequest().json(webhook_response(false, "Push event without a repository".to_string(), None)),
+    };
+    
+    let invalidated = metadata_cache.invalidate_repo(&repository.full_name);
+    println!("Push to {}, dropped {} cached GitHub responses", repository.full_name, invalidated);
+    let mut response = webhook_response(true, format!("Invalidated cache for {}", repository.full_name), Some(invalidated));
+    
+    // Created and deleted branches have no before or after to compare
+    let tenant = std::env::var("SAFEX_REGRESSION_FUZZ_TENANT").ok().filter(|tenant| !tenant.is_empty());
+    let commits = before.zip(after).filter(|(before, after)| [before, after].iter().all(|commit| commit_sha(commit).is_ok() && commit.chars().any(|c| c != '0')));
+    if let (Some(tenant), Some(queue), Some((base_commit, head_commit))) = (tenant, queue, commits) {
+        let repo_url = match RepoUrl::parse(&format!("https://github.com/{}", repository.full_name)) {
+            Ok(repo_url) => repo_url,
+            Err(e) => return HttpResponse::BadRequest().json(webhook_response(false, format!("Invalid repository: {}", e), Some(invalidated))),
+        };
+        let request = RegressionFuzzRequest {
+            repo_url,
+            base_commit,
+            head_commit,
+            timeout_seconds: fuzz_timeout(None, WEBHOOK_ACTOR, true).ok(),
+            network_policy: NetworkPolicy::default(),
+            vendor_dependencies: false,
+        };
+        match queue.enqueue(JobKind::RegressionFuzz, JobPriority::Ci, &tenant, WEBHOOK_ACTOR, json!(request)).await {
+            Ok(job_id) => {
+                println!("Queued regression fuzzing of {} as job {}", repository.full_name, job_id);
+                response.message = format!("Invalidated cache for {} and queued regression fuzzing", repository.full_name);
+                response.job_id = Some(job_id);
+            },
+            Err(e) => println!("Warning: Failed to queue regression fuzzing of {}: {}", repository.full_name, e),
+        }
+    }
+    HttpResponse::Ok().json(response)
+}
+
+#[post("/api/repo-files")]
+async fn repo_files(files_request: Valid<RepoFilesRequest>, token: RequestToken, clone_cache: web::Data<CloneCache>) -> impl Responder {
+    const MAX_PATHS: usize = 50;
+    if files_request.paths.is_empty() || files_request.paths.len() > MAX_PATHS {
+        return HttpResponse::UnprocessableEntity().json(RepoFilesResponse {
+            success: false,
+            message: format!("Request between 1 and {} paths", MAX_PATHS),
+            files: None,
+        });
+    }
+    
+    let clone_root = match clone_cache.checkout(&files_request.repo_url, &token).await {
+        Ok(path) => path,
         Err(e) => {
-            return HttpResponse::InternalServerError().json(FuzzingResponse {
+            return HttpResponse::BadRequest().json(RepoFilesResponse {
                 success: false,
-                message: format!("Failed to create temporary directory: {}", e),
-                errors: None,
-                test_file: None,
-                execution_time_ms: None,
+                message: format!("Failed to clone repository: {}", e),
+                files: None,
             });
         }
     };
     
-    // Clone the repository
-    let repo_path = temp_dir.path().join("repo");
-    match github_client.clone_repo(&fuzzing_request.repo_url, &repo_path) {
-        Ok(_) => {},
+    let max_bytes = max_file_bytes();
+    let files: Vec<RepoFile> = files_request.paths.iter().map(|path| {
+        let read = resolve_repo_path(&clone_root, path).and_then(|resolved| {
+            let size = std::fs::metadata(&resolved)?.len();
+            if size > max_bytes {
+                return Err(anyhow::anyhow!("File is {} bytes, over the {} byte limit", size, max_bytes));
+            }
+            Ok((size, std::fs::read(&resolved)?))
+        });
+        match read {
+            Ok((size, bytes)) => {
+                let (content, encoding) = match String::from_utf8(bytes) {
+                    Ok(text) => (text, "utf-8"),
+                    Err(e) => (base64::encode(e.into_bytes()), "base64"),
+                };
+                RepoFile {
+                    path: path.clone(),
+                    size: Some(size),
+                    content: Some(content),
+                    encoding: Some(encoding.to_string()),
+                    error: None,
+                }
+            },
+            Err(e) => RepoFile {
+                path: path.clone(),
+                size: None,
+                content: None,
+                encoding: None,
+                error: Some(e.to_string()),
+            },
+        }
+    }).collect();
+    
+    let failed = files.iter().filter(|f| f.error.is_some()).count();
+    HttpResponse::Ok().json(RepoFilesResponse {
+        success: failed == 0,
+        message: format!("Fetched {} of {} files", files.len() - failed, files.len()),
+        files: Some(files),
+    })
+}
+
+#[post("/api/repo-stats")]
+async fn repo_stats(stats_request: Valid<RepoStatsRequest>, token: RequestToken, clone_cache: web::Data<CloneCache>) -> impl Responder {
+    let clone_root = match clone_cache.checkout(&stats_request.repo_url, &token).await {
+        Ok(path) => path,
         Err(e) => {
-            return HttpResponse::BadRequest().json(FuzzingResponse {
+            return HttpResponse::BadRequest().json(RepoStatsResponse {
                 success: false,
                 message: format!("Failed to clone repository: {}", e),
-                errors: None,
-                test_file: None,
-                execution_time_ms: None,
+                stats: None,
             });
         }
     };
     
-    // Initialize fuzzer
-    let fuzzer = Fuzzer::new(temp_dir.path().to_path_buf());
-    
-    // Get instruction name or use default
-    let instruction_name = fuzzing_request.instruction_name.clone().unwrap_or_else(|| "increment".to_string());
-    
-    // Set timeout (default to 120 seconds if not specified)
-    let timeout = fuzzing_request.timeout_seconds.unwrap_or(120);
-    if timeout > 120 {
-        return HttpResponse::BadRequest().json(FuzzingResponse {
-            success: false,
-            message: "Timeout cannot exceed 120 seconds".to_string(),
-            errors: None,
-            test_file: None,
-            execution_time_ms: None,
-        });
+    match compute_repo_stats(&clone_root) {
+        Ok(stats) => {
+            HttpResponse::Ok().json(RepoStatsResponse {
+                success: true,
+                message: format!("Found {} programs with {} instructions", stats.program_count, stats.instruction_count),
+                stats: Some(stats),
+            })
+        },
+        Err(e) => {
+            HttpResponse::InternalServerError().json(RepoStatsResponse {
+                success: false,
+                message: format!("Failed to compute repository stats: {}", e),
+                stats: None,
+            })
+        }
     }
-    
-    // Generate and run fuzz tests
-    match fuzzer.generate_and_run_fuzz_tests(&repo_path, &instruction_name) {
-        Ok(result) => {
-            let execution_time = start_time.elapsed().as_millis() as u64;
-            
-            // Get the test file content
-            let test_file_path = temp_dir.path().join("fuzz_tests").join(format!("{}_fuzz_test.rs", instruction_name));
-            let test_file_content = match std::fs::read_to_string(&test_file_path) {
-                Ok(content) => Some(content),
-                Err(_) => None,
-            };
-            
-            HttpResponse::Ok().json(FuzzingResponse {
-                success: !result.timed_out && result.errors.is_empty(),
-                message: if result.timed_out {
-                    "Fuzzing tests timed out".to_string()
-                } else if result.errors.is_empty() {
-                    "Fuzzing tests completed successfully".to_string()
-                } else {
-                    "Fuzzing tests found potential issues".to_string()
-                },
-                errors: if result.errors.is_empty() { None } else { Some(result.errors) },
-                test_file: test_file_content,
-                execution_time_ms: Some(execution_time),
+}
+
+// Predicted scope of /api/analyze-code from repository metadata, so frontends can warn before
+// starting a long analysis
+#[post("/api/estimate")]
+async fn estimate_scope(estimate_request: Valid<EstimateRequest>, token: RequestToken, metadata_cache: web::Data<MetadataCache>) -> impl Responder {
+    let github_client = GitHubClient::new().with_request_token(&token).with_cache(metadata_cache.into_inner());
+    match estimate_analysis(&github_client, &estimate_request.repo_url).await {
+        Ok(estimate) => {
+            HttpResponse::Ok().json(EstimateResponse {
+                success: true,
+                message: format!("{} programs, {} Rust files, about {} minutes", estimate.programs.len(), estimate.rust_files, estimate.estimated_duration_secs.div_ceil(60)),
+                estimate: Some(estimate),
             })
         },
         Err(e) => {
-            HttpResponse::InternalServerError().json(FuzzingResponse {
+            HttpResponse::BadRequest().json(EstimateResponse {
                 success: false,
-                message: format!("Failed to run fuzzing tests: {}", e),
-                errors: None,
-                test_file: None,
-                execution_time_ms: Some(start_time.elapsed().as_millis() as u64),
+                message: format!("Failed to estimate analysis: {}", e),
+                estimate: None,
             })
         }
     }
 }
 
-#[post("/api/analyze-code")]
-async fn analyze_code(analysis_request: web::Json<CodeAnalysisRequest>) -> impl Responder {
-    println!("Received code analysis request for: {}", analysis_request.repo_url);
-    
-    // Create a temporary directory for cloning
-    let temp_dir = match TempDir::new() {
-        Ok(dir) => dir,
+// Instruction names, arguments and account counts per program, for the fuzzing UI
+#[post("/api/instructions")]
+async fn list_instructions(instructions_request: Valid<InstructionsRequest>, token: RequestToken, clone_cache: web::Data<CloneCache>) -> impl Responder {
+    let clone_root = match clone_cache.checkout(&instructions_request.repo_url, &token).await {
+        Ok(path) => path,
         Err(e) => {
-            return HttpResponse::InternalServerError().json(CodeAnalysisResponse {
+            return HttpResponse::BadRequest().json(InstructionsResponse {
                 success: false,
-                message: format!("Failed to create temporary directory: {}", e),
-                bugs: None,
+                message: format!("Failed to clone repository: {}", e),
+                programs: None,
             });
         }
     };
     
-    // Clone the repository
-    println!("Cloning repository to: {}", temp_dir.path().display());
-    let _repo = match Repository::clone(&analysis_request.repo_url, temp_dir.path()) {
-        Ok(repo) => repo,
+    match extract_instructions(&clone_root) {
+        Ok(programs) => {
+            let count: usize = programs.iter().map(|p| p.instructions.len()).sum();
+            HttpResponse::Ok().json(InstructionsResponse {
+                success: true,
+                message: format!("Found {} instructions in {} programs", count, programs.len()),
+                programs: Some(programs),
+            })
+        },
+        Err(e) => {
+            HttpResponse::InternalServerError().json(InstructionsResponse {
+                success: false,
+                message: format!("Failed to extract instructions: {}", e),
+                programs: None,
+            })
+        }
+    }
+}
+
+// Accounts, the instructions that touch them and how they relate, as JSON or Graphviz DOT
+#[post("/api/account-graph")]
+async fn export_account_graph(graph_request: Valid<AccountGraphRequest>, token: RequestToken, clone_cache: web::Data<CloneCache>) -> impl Responder {
+    let clone_root = match clone_cache.checkout(&graph_request.repo_url, &token).await {
+        Ok(path) => path,
         Err(e) => {
-            return HttpResponse::BadRequest().json(CodeAnalysisResponse {
+            return HttpResponse::BadRequest().json(AccountGraphResponse {
                 success: false,
                 message: format!("Failed to clone repository: {}", e),
-                bugs: None,
+                graph: None,
             });
         }
     };
     
-    // Run code analysis
-    let analyzer = CodeAnalyzer::new();
-    match analyzer.analyze_repo(temp_dir.path()) {
-        Ok(bugs) => {
-            HttpResponse::Ok().json(CodeAnalysisResponse {
+    match build_account_graph(&clone_root) {
+        Ok(graph) if graph_request.format == GraphFormat::Dot => {
+            HttpResponse::Ok().content_type("text/vnd.graphviz; charset=utf-8").body(to_dot(&graph))
+        },
+        Ok(graph) => {
+            HttpResponse::Ok().json(AccountGraphResponse {
                 success: true,
-                message: format!("Analysis completed. Found {} issues.", bugs.len()),
-                bugs: Some(bugs),
+                message: format!("Found {} accounts used by {} instructions", graph.accounts.len(), graph.instructions.len()),
+                graph: Some(graph),
             })
         },
         Err(e) => {
-            HttpResponse::InternalServerError().json(CodeAnalysisResponse {
+            HttpResponse::InternalServerError().json(AccountGraphResponse {
                 success: false,
-                message: format!("Analysis failed: {}", e),
-                bugs: None,
+                message: format!("Failed to build account graph: {}", e),
+                graph: None,
             })
         }
     }
 }
 
-#[post("/api/log-report")]
-async fn log_report(report_request: web::Json<ReportLogRequest>) -> impl Responder {
-    println!("Received report logging request");
+#[post("/api/estimate-rent")]
+async fn estimate_rent(rent_request: Valid<RentEstimateRequest>, token: RequestToken, clone_cache: web::Data<CloneCache>) -> impl Responder {
+    let cluster = rent_request.cluster;
+    let clone_root = match clone_cache.checkout(&rent_request.repo_url, &token).await {
+        Ok(path) => path,
+        Err(e) => {
+            return HttpResponse::BadRequest().json(RentEstimateResponse {
+                success: false,
+                message: format!("Failed to clone repository: {}", e),
+                cluster,
+                rent_source: None,
+                accounts: None,
+            });
+        }
+    };
     
-    // Create SHA256 hash of the report content
-    use sha2::{Sha256, Digest};
-    let mut hasher = Sha256::new();
-    hasher.update(report_request.report_content.as_bytes());
-    let hash = hasher.finalize();
-    let hash_hex = format!("{:x}", hash);
-    
-    // Initialize the report logger
-    match ReportLogger::new() {
-        Ok(logger) => {
-            // Log the report to the blockchain
-            match logger.log_report(&report_request.report_content) {
-                Ok(signature) => {
-                    HttpResponse::Ok().json(ReportLogResponse {
-                        success: true,
-                        message: "Report successfully logged to Solana blockchain".to_string(),
-                        transaction_signature: Some(signature),
-                        hash: Some(hash_hex),
-                    })
-                },
-                Err(e) => {
-                    HttpResponse::InternalServerError().json(ReportLogResponse {
-                        success: false,
-                        message: format!("Failed to log report: {}", e),
-                        transaction_signature: None,
-                        hash: Some(hash_hex),
-                    })
-                }
+    let mut accounts = match estimate_account_sizes(&clone_root) {
+        Ok(accounts) => accounts,
+        Err(e) => {
+            return HttpResponse::InternalServerError().json(RentEstimateResponse {
+                success: false,
+                message: format!("Failed to estimate account sizes: {}", e),
+                cluster,
+                rent_source: None,
+                accounts: None,
+            });
+        }
+    };
+    
+    // Fall back to the default rent parameters, which all public clusters currently use
+    let sizes = accounts.iter().map(|a| a.size).collect();
+    let minimums = rent_exempt_minimums(cluster, &sizes).await;
+    for account in &mut accounts {
+        account.rent_exempt_lamports = Some(match &minimums {
+            Some(minimums) => minimums[&account.size],
+            None => default_rent_exempt_minimum(account.size),
+        });
+    }
+    
+    let warnings = accounts.iter().flat_map(|a| &a.declared_space).filter(|d| d.warning.is_some()).count();
+    HttpResponse::Ok().json(RentEstimateResponse {
+        success: true,
+        message: format!("Estimated {} account types ({} space warnings)", accounts.len(), warnings),
+        cluster,
+        rent_source: Some(if minimums.is_some() { "rpc" } else { "default" }.to_string()),
+        accounts: Some(accounts),
+    })
+}
+
+#[post("/api/decode-account")]
+async fn decode_account(decode_request: Valid<DecodeAccountRequest>, token: RequestToken, clone_cache: web::Data<CloneCache>) -> impl Responder {
+    let failure = |message: String| DecodeAccountResponse { success: false, message, account: None };
+    
+    // Raw data, or the account as it is on the cluster now
+    let (data, fetched) = match (&decode_request.data, &decode_request.address) {
+        (Some(data), _) => match base64::decode(data.trim()) {
+            Ok(data) => (data, None),
+            Err(e) => return HttpResponse::BadRequest().json(failure(format!("data is not valid base64: {}", e))),
+        },
+        (None, Some(address)) => {
+            let Ok(key) = Pubkey::from_str(address) else {
+                return HttpResponse::BadRequest().json(failure(format!("Invalid account address: {}", address)));
+            };
+            match decode_request.cluster.rpc_client().get_account(&key).await {
+                Ok(account) => (account.data.clone(), Some(account)),
+                Err(e) => return HttpResponse::BadRequest().json(failure(format!("Failed to fetch account on {}: {}", decode_request.cluster.as_str(), e))),
+            }
+        },
+        (None, None) => return HttpResponse::BadRequest().json(failure("Either address or data is required".to_string())),
+    };
+    
+    let clone_root = match clone_cache.checkout(&decode_request.repo_url, &token).await {
+        Ok(path) => path,
+        Err(e) => return HttpResponse::BadRequest().json(failure(format!("Failed to clone repository: {}", e))),
+    };
+    let schemas = match parse_account_schemas(&clone_root) {
+        Ok(schemas) => schemas,
+        Err(e) => return HttpResponse::InternalServerError().json(failure(format!("Failed to parse account types: {}", e))),
+    };
+    
+    match decode_account_data(&schemas, &data, decode_request.account_type.as_deref()) {
+        Ok(mut account) => {
+            if let Some(fetched) = fetched {
+                account.address = decode_request.address.clone();
+                account.owner = Some(fetched.owner.to_string());
+                account.lamports = Some(fetched.lamports);
+            }
+            let message = match (&account.account_type, &account.discriminator_mismatch) {
+                (Some(name), Some(_)) => format!("Decoded as {}, but the discriminator doesn't match", name),
+                (Some(name), None) => format!("Decoded {} account", name),
+                (None, _) => "Account type not recognized".to_string(),
+            };
+            HttpResponse::Ok().json(DecodeAccountResponse {
+                success: account.account_type.is_some(),
+                message,
+                account: Some(account),
+            })
+        },
+        Err(e) => HttpResponse::BadRequest().json(failure(format!("Failed to decode account: {}", e))),
+    }
+}
+
+// A PDA from a program ID and seeds, or every PDA the repository's programs declare, for
+// checking seed schemes the PDA lints flag
+#[post("/api/derive-pda")]
+async fn derive_pda_address(derive_request: Valid<DerivePdaRequest>, token: RequestToken, clone_cache: web::Data<CloneCache>) -> impl Responder {
+    let failure = |message: String| DerivePdaResponse { success: false, message, pdas: None };
+    
+    let Some(repo_url) = &derive_request.repo_url else {
+        let (Some(program_id), Some(seeds)) = (&derive_request.program_id, &derive_request.seeds) else {
+            return HttpResponse::BadRequest().json(failure("Either program_id and seeds, or repo_url is required".to_string()));
+        };
+        return match derive_pda(program_id, seeds) {
+            Ok(pda) => HttpResponse::Ok().json(DerivePdaResponse {
+                success: true,
+                message: format!("Derived {} with bump {}", pda.address.as_deref().unwrap_or_default(), pda.bump.unwrap_or_default()),
+                pdas: Some(vec![pda]),
+            }),
+            Err(e) => HttpResponse::BadRequest().json(failure(format!("Failed to derive address: {}", e))),
+        };
+    };
+    
+    let clone_root = match clone_cache.checkout(repo_url, &token).await {
+        Ok(path) => path,
+        Err(e) => return HttpResponse::BadRequest().json(failure(format!("Failed to clone repository: {}", e))),
+    };
+    match discover_pdas(&clone_root, derive_request.program_id.as_deref(), &derive_request.values) {
+        Ok(pdas) => {
+            let derived = pdas.iter().filter(|pda| pda.address.is_some()).count();
+            HttpResponse::Ok().json(DerivePdaResponse {
+                success: true,
+                message: format!("Derived {} of {} PDAs", derived, pdas.len()),
+                pdas: Some(pdas),
+            })
+        },
+        Err(e) => HttpResponse::BadRequest().json(failure(format!("Failed to derive PDAs: {}", e))),
+    }
+}
+
+// Catalog of the analyzer's rules, for rendering detail on a finding's rule_id
+#[get("/api/rules")]
+async fn list_rules(req: HttpRequest) -> impl Responder {
+    let rules = rule_catalog();
+    cached_json(&req, &RulesResponse {
+        success: true,
+        message: format!("Found {} rules", rules.len()),
+        rules,
+        rule_sets: rule_sets(),
+    }, "public, max-age=3600")
+}
+
+// Run the rules against the built-in vulnerable fixtures and report how precisely they fire
+#[get("/api/self-test")]
+async fn run_rule_self_test(_caller: Caller) -> impl Responder {
+    match web::block(run_self_test).await.map_err(anyhow::Error::from).and_then(|report| report) {
+        Ok(report) => {
+            let message = if report.passed {
+                format!("All rules behaved as expected on {} fixtures", report.fixtures.len())
+            } else {
+                format!("{} mismatches across {} fixtures", report.mismatches.len(), report.fixtures.len())
+            };
+            HttpResponse::Ok().json(SelfTestResponse {
+                success: true,
+                message,
+                report: Some(report),
+            })
+        },
+        Err(e) => {
+            HttpResponse::InternalServerError().json(SelfTestResponse {
+                success: false,
+                message: format!("Self-test failed to run: {}", e),
+                report: None,
+            })
+        }
+    }
+}
+
+// Run the rules against the corpus of public vulnerable programs and record per-rule detection
+// and false-positive rates, with the previous version of each rule for comparison
+#[post("/api/benchmark")]
+async fn run_corpus_benchmark(caller: Caller, db: web::Data<Database>) -> impl Responder {
+    let audit = AuditEvent::start(&caller, "benchmark.run");
+    let run_by = caller.actor.clone();
+    let store = db.clone();
+    // Cloning and analyzing the corpus takes a while, so it runs on the blocking thread pool
+    let run = web::block(move || {
+        let history = store.list_benchmark_runs(HISTORY_LIMIT)?;
+        let mut run = run_benchmark(&run_by, &history)?;
+        store.insert_benchmark_run(&mut run)?;
+        Ok(run)
+    }).await.unwrap_or_else(|e| Err(anyhow::anyhow!(e.to_string())));
+    match run {
+        Ok(run) => {
+            let failed = run.repos.iter().filter(|repo| repo.error.is_some()).count();
+            let mut message = format!("Benchmark run {} covered {} repositories", run.id, run.repos.len() - failed);
+            if failed > 0 {
+                message.push_str(&format!(", {} could not be analyzed", failed));
             }
+            audit.target(run.id.to_string()).finish(&db, true, &message);
+            HttpResponse::Ok().json(BenchmarkResponse {
+                success: true,
+                message,
+                run: Some(run),
+            })
         },
         Err(e) => {
-            HttpResponse::InternalServerError().json(ReportLogResponse {
+            let message = format!("Benchmark failed: {}", e);
+            audit.finish(&db, false, &message);
+            HttpResponse::InternalServerError().json(BenchmarkResponse {
                 success: false,
-                message: format!("Failed to initialize report logger: {}", e),
-                transaction_signature: None,
-                hash: None,
+                message,
+                run: None,
             })
         }
     }
 }
 
-#[actix_web::main]
-async fn main() -> std::io::Result<()> {
-    let port: u16 = std::env::var("PORT").unwrap_or_else(|_| "8080".to_string()).parse().unwrap_or(8080);
-    println!("Starting Safex backend server at http://0.0.0.0:{port}");
-    actix_web::HttpServer::new(|| {
-        // let cors = Cors::default()
-        //     .allowed_origin("http://localhost:3000")
-        //     .allowed_origin("http://localhost:3001")
-        //     .allowed_methods(vec!["GET", "POST"])
-        //     .allowed_headers(vec![header::AUTHORIZATION, header::ACCEPT, header::CONTENT_TYPE])
-        //     .max_age(3600);
-        let cors = Cors::permissive();
-            
-        App::new()
-            .wrap(cors)
-            .wrap(Logger::default())
-            .service(hello)
-            .service(ingest_repo)
-            .service(repo_contents)
-            .service(analyze_code)
-            .service(fuzz_test)
-            .service(log_report)
+#[get("/api/benchmark/runs")]
+async fn list_benchmark_runs(_caller: Caller, db: web::Data<Database>) -> impl Responder {
+    match db.list_benchmark_runs(HISTORY_LIMIT) {
+        Ok(runs) => HttpResponse::Ok().json(BenchmarkRunsResponse {
+            success: true,
+            message: format!("Found {} benchmark runs", runs.len()),
+            runs: Some(runs),
+        }),
+        Err(e) => HttpResponse::InternalServerError().json(BenchmarkRunsResponse {
+            success: false,
+            message: format!("Failed to list benchmark runs: {}", e),
+            runs: None,
+        }),
+    }
+}
+
+// A stored finding that carries machine-applicable edits
+struct FixableFinding {
+    repo_url: RepoUrl,
+    finding: CodeBug,
+    file: String,
+    edits: Vec<TextEdit>,
+}
+
+fn load_fixable_finding(db: &Database, tenant: &str, run_id: &str, fingerprint: &str) -> Result<FixableFinding, (StatusCode, String)> {
+    let run = match db.get_analysis_run(tenant, run_id) {
+        Ok(Some(run)) => run,
+        Ok(None) => return Err((StatusCode::NOT_FOUND, format!("Analysis run not found: {}", run_id))),
+        Err(e) => return Err((StatusCode::INTERNAL_SERVER_ERROR, format!("Failed to load analysis run: {}", e))),
+    };
+    let finding = db.get_run_findings(run_id)
+        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, format!("Failed to load findings: {}", e)))?
+        .into_iter()
+        .find(|f| f.fingerprint == fingerprint)
+        .ok_or_else(|| (StatusCode::NOT_FOUND, format!("Finding not found: {}", fingerprint)))?;
+    let (Some(file), Some(edits)) = (finding.file.clone(), finding.autofix.clone()) else {
+        return Err((StatusCode::UNPROCESSABLE_ENTITY, "Finding has no machine-applicable fix".to_string()));
+    };
+    let repo_url = RepoUrl::parse(&run.repo_url).map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?;
+    Ok(FixableFinding { repo_url, finding, file, edits })
+}
+
+// A finding's machine-applicable fix applied to the current content of its file
+struct PreparedFix {
+    file: String,
+    diff: String,
+    patched_content: String,
+}
+
+async fn prepare_autofix(db: &Database, clone_cache: &CloneCache, token: &RequestToken, tenant: &str, run_id: &str, fingerprint: &str) -> Result<PreparedFix, (StatusCode, String)> {
+    let FixableFinding { repo_url, file, edits, .. } = load_fixable_finding(db, tenant, run_id, fingerprint)?;
+    let clone_root = clone_cache.checkout(&repo_url, token).await
+        .map_err(|e| (StatusCode::BAD_REQUEST, format!("Failed to clone repository: {}", e)))?;
+    let content = resolve_repo_path(&clone_root, &file)
+        .and_then(|path| Ok(std::fs::read_to_string(path)?))
+        .map_err(|e| (StatusCode::NOT_FOUND, e.to_string()))?;
+    // The edits carry the text they replace, so a file that moved on since the run is refused
+    let patched_content = apply_edits(&content, &edits).map_err(|e| (StatusCode::CONFLICT, e.to_string()))?;
+    
+    Ok(PreparedFix {
+        diff: unified_diff(&file, &content, &patched_content),
+        file,
+        patched_content,
     })
-    .bind(("0.0.0.0", port))?
-    .run()
-    .await
+}
+
+#[post("/api/autofix-preview")]
+async fn autofix_preview(preview_request: Valid<AutofixPreviewRequest>, caller: Caller, token: RequestToken, db: web::Data<Database>, clone_cache: web::Data<CloneCache>) -> impl Responder {
+    match prepare_autofix(&db, &clone_cache, &token, &caller.tenant, &preview_request.run_id, &preview_request.fingerprint).await {
+        Ok(fix) => {
+            HttpResponse::Ok().json(AutofixPreviewResponse {
+                success: true,
+                message: format!("Fix for {} applies cleanly", fix.file),
+                file: Some(fix.file),
+                diff: Some(fix.diff),
+                patched_content: Some(fix.patched_content),
+            })
+        },
+        Err((status, message)) => {
+            HttpResponse::build(status).json(AutofixPreviewResponse {
+                success: false,
+                message,
+                file: None,
+                diff: None,
+                patched_content: None,
+            })
+        }
+    }
+}
+
+#[post("/api/create-fix-pr")]
+async fn create_fix_pr(fix_request: Valid<CreateFixPrRequest>, caller: Caller, token: RequestToken, db: web::Data<Database>) -> impl Responder {
+    let audit = AuditEvent::start(&caller, "fix_pr.create")
+        .target(fix_request.run_id.clone())
+        .params(json!({ "fingerprint": fix_request.fingerprint }));
+    
+    // Branches are pushed and pull requests opened as the caller, never with the server's GITHUB_TOKEN
+    if token.as_deref().is_none() {
+        let message = format!("{} is required to open pull requests", RequestToken::HEADER);
+        audit.finish(&db, false, &message);
+        return HttpResponse::Unauthorized().json(CreateFixPrResponse {
+            success: false,
+            message,
+            pull_request_url: None,
+            branch: None,
+            head_repo: None,
+        });
+    }
+    let opened = match load_fixable_finding(&db, &caller.tenant, &fix_request.run_id, &fix_request.fingerprint) {
+        Ok(fixable) => {
+            let github_client = GitHubClient::new().with_request_token(&token);
+            open_fix_pull_request(&github_client, &fixable.repo_url, &fix_request.run_id, &fixable.finding, &fixable.file, &fixable.edits).await
+        },
+        Err(e) => Err(e),
+    };
+    
+    match opened {
+        Ok(pull_request) => {
+            let message = format!("Opened pull request {}", pull_request.url);
+            audit.finish(&db, true, &message);
+            HttpResponse::Ok().json(CreateFixPrResponse {
+                success: true,
+                message,
+                pull_request_url: Some(pull_request.url),
+                branch: Some(pull_request.branch),
+                head_repo: Some(pull_request.head_repo),
+            })
+        },
+        Err((status, message)) => {
+            audit.finish(&db, false, &message);
+            HttpResponse::build(status).json(CreateFixPrResponse {
+                success: false,
+                message,
+                pull_request_url: None,
+                branch: None,
+                head_repo: None,
+            })
+        }
+    }
+}
+
+// Track a finding as a GitHub issue on the analyzed repository; one issue per finding, which
+// later runs report through the finding's issue_url
+#[post("/api/findings/{fingerprint}/create-issue")]
+async fn create_finding_issue(
+    path: web::Path<String>,
+    issue_request: Valid<CreateIssueRequest>,
+    caller: Caller,
+    token: RequestToken,
+    db: web::Data<Database>,
+) -> impl Responder {
+    let fingerprint = path.into_inner();
+    let audit = AuditEvent::start(&caller, "finding.create_issue")
+        .target(fingerprint.clone())
+        .params(json!({ "run_id": issue_request.run_id, "labels": issue_request.labels }));
+    let failure = |message: String| CreateIssueResponse { success: false, message, issue_url: None, issue_number: None };
+    
+    // Issues are opened as the caller, never with the server's GITHUB_TOKEN
+    if token.as_deref().is_none() {
+        let message = format!("{} is required to open issues", RequestToken::HEADER);
+        audit.finish(&db, false, &message);
+        return HttpResponse::Unauthorized().json(failure(message));
+    }
+    let run = match db.get_analysis_run(&caller.tenant, &issue_request.run_id) {
+        Ok(Some(run)) => run,
+        Ok(None) => {
+            let message = format!("Analysis run not found: {}", issue_request.run_id);
+            audit.finish(&db, false, &message);
+            return HttpResponse::NotFound().json(failure(message));
+        },
+        Err(e) => {
+            let message = format!("Failed to load analysis run: {}", e);
+            audit.finish(&db, false, &message);
+            return HttpResponse::InternalServerError().json(failure(message));
+        }
+    };
+    let finding = match db.get_run_findings(&issue_request.run_id) {
+        Ok(findings) => match findings.into_iter().find(|f| f.fingerprint == fingerprint) {
+            Some(finding) => finding,
+            None => {
+                let message = format!("Finding not found: {}", fingerprint);
+                audit.finish(&db, false, &message);
+                return HttpResponse::NotFound().json(failure(message));
+            }
+        },
+        Err(e) => {
+            let message = format!("Failed to load findings: {}", e);
+            audit.finish(&db, false, &message);
+            return HttpResponse::InternalServerError().json(failure(message));
+        }
+    };
+    if let Some(issue_url) = finding.issue_url {
+        let message = format!("Finding {} is already tracked in {}", fingerprint, issue_url);
+        audit.finish(&db, false, &message);
+        return HttpResponse::Conflict().json(CreateIssueResponse {
+            success: false,
+            message,
+            issue_url: Some(issue_url),
+            issue_number: None,
+        });
+    }
+    let repo_url = match RepoUrl::parse(&run.repo_url) {
+        Ok(repo_url) => repo_url,
+        Err(e) => {
+            let message = e.to_string();
+            audit.finish(&db, false, &message);
+            return HttpResponse::InternalServerError().json(failure(message));
+        }
+    };
+    
+    match db.reserve_finding_issue(&caller.tenant, &run.repo_url, &fingerprint, &caller.actor) {
+        Ok(true) => {},
+        Ok(false) => {
+            let message = format!("An issue for finding {} is already being opened", fingerprint);
+            audit.finish(&db, false, &message);
+            return HttpResponse::Conflict().json(failure(message));
+        },
+        Err(e) => {
+            let message = format!("Failed to reserve the finding's issue: {}", e);
+            audit.finish(&db, false, &message);
+            return HttpResponse::InternalServerError().json(failure(message));
+        }
+    }
+    
+    let github_client = GitHubClient::new().with_request_token(&token);
+    match open_finding_issue(&github_client, &repo_url, &issue_request.run_id, run.commit_sha.as_deref(), &finding, &issue_request.labels).await {
+        Ok(issue) => {
+            if let Err(e) = db.complete_finding_issue(&caller.tenant, &run.repo_url, &fingerprint, issue.number, &issue.url) {
+                println!("Warning: Failed to record issue {} for finding {}: {}", issue.url, fingerprint, e);
+            }
+            let message = format!("Opened issue {}", issue.url);
+            audit.finish(&db, true, &message);
+            HttpResponse::Ok().json(CreateIssueResponse {
+                success: true,
+                message,
+                issue_url: Some(issue.url),
+                issue_number: Some(issue.number),
+            })
+        },
+        Err((status, message)) => {
+            if let Err(e) = db.release_finding_issue(&caller.tenant, &run.repo_url, &fingerprint) {
+                println!("Warning: Failed to release the issue reservation for finding {}: {}", fingerprint, e);
+            }
+            audit.finish(&db, false, &message);
+            HttpResponse::build(status).json(failure(message))
+        }
+    }
+}
+
+#[get("/api/jira-settings")]
+async fn get_jira_settings(caller: Caller, db: web::Data<Database>) -> impl Responder {
+    match db.get_jira_settings(&caller.tenant) {
+        Ok(settings) => HttpResponse::Ok().json(JiraSettingsResponse {
+            success: true,
+            message: match settings {
+                Some(_) => "Jira settings fetched successfully".to_string(),
+                None => "Jira is not configured".to_string(),
+            },
+            settings: settings.map(|(settings, _)| settings),
+        }),
+        Err(e) => HttpResponse::InternalServerError().json(JiraSettingsResponse {
+            success: false,
+            message: format!("Failed to load Jira settings: {}", e),
+            settings: None,
+        }),
+    }
+}
+
+// The token is sealed with the deploy key secret, so storing one requires it to be configured
+#[put("/api/jira-settings")]
+async fn update_jira_settings(
+    settings_request: Valid<JiraSettingsRequest>,
+    caller: Caller,
+    db: web::Data<Database>,
+    deploy_keys: Option<web::Data<DeployKeys>>,
+) -> impl Responder {
+    let failure = |message: String| JiraSettingsResponse { success: false, message, settings: None };
+    let Some(deploy_keys) = deploy_keys else {
+        return HttpResponse::ServiceUnavailable().json(failure("SAFEX_DEPLOY_KEY_SECRET is not configured".to_string()));
+    };
+    let settings = jira::settings_from_request(&settings_request, &caller.actor);
+    let audit = AuditEvent::start(&caller, "jira_settings.update")
+        .params(json!({
+            "base_url": settings.base_url,
+            "project_key": settings.project_key,
+            "email": settings.email,
+            "issue_type": settings.issue_type,
+            "priorities": settings.priorities,
+        }));
+    
+    match deploy_keys.seal(&settings_request.token).and_then(|sealed| db.put_jira_settings(&caller.tenant, &settings, &sealed)) {
+        Ok(()) => {
+            audit.finish(&db, true, "Jira settings updated");
+            HttpResponse::Ok().json(JiraSettingsResponse {
+                success: true,
+                message: "Jira settings updated successfully".to_string(),
+                settings: Some(settings),
+            })
+        },
+        Err(e) => {
+            let message = format!("Failed to update Jira settings: {}", e);
+            audit.finish(&db, false, &message);
+            HttpResponse::InternalServerError().json(failure(message))
+        }
+    }
+}
+
+#[delete("/api/jira-settings")]
+async fn delete_jira_settings(caller: Caller, db: web::Data<Database>) -> impl Responder {
+    let audit = AuditEvent::start(&caller, "jira_settings.delete");
+    let failure = |message: String| JiraSettingsResponse { success: false, message, settings: None };
+    match db.delete_jira_settings(&caller.tenant) {
+        Ok(true) => {
+            audit.finish(&db, true, "Jira settings removed");
+            HttpResponse::Ok().json(JiraSettingsResponse {
+                success: true,
+                message: "Jira settings removed".to_string(),
+                settings: None,
+            })
+        },
+        Ok(false) => {
+            let message = "Jira is not configured".to_string();
+            audit.finish(&db, false, &message);
+            HttpResponse::NotFound().json(failure(message))
+        },
+        Err(e) => {
+            let message = format!("Failed to remove Jira settings: {}", e);
+            audit.finish(&db, false, &message);
+            HttpResponse::InternalServerError().json(failure(message))
+        }
+    }
+}
+
+// Create a Jira ticket for each selected finding of a run. Findings that already have a
+// ticket are returned with it rather than filed twice; one failing doesn't stop the rest.
+#[post("/api/jira/push")]
+async fn push_to_jira(
+    push_request: Valid<JiraPushRequest>,
+    caller: Caller,
+    db: web::Data<Database>,
+    mailer: web::Data<Mailer>,
+    deploy_keys: Option<web::Data<DeployKeys>>,
+) -> impl Responder {
+    let audit = AuditEvent::start(&caller, "jira.push")
+        .target(push_request.run_id.clone())
+        .params(json!({ "fingerprints": push_request.fingerprints }));
+    let failure = |message: String| JiraPushResponse { success: false, message, results: None };
+    
+    let Some(deploy_keys) = deploy_keys else {
+        let message = "SAFEX_DEPLOY_KEY_SECRET is not configured".to_string();
+        audit.finish(&db, false, &message);
+        return HttpResponse::ServiceUnavailable().json(failure(message));
+    };
+    let client = match db.get_jira_settings(&caller.tenant) {
+        Ok(Some((settings, sealed_token))) => match deploy_keys.open(&sealed_token) {
+            Ok(token) => JiraClient::connect(settings, token).await,
+            Err(e) => Err(e),
+        },
+        Ok(None) => {
+            let message = "Jira is not configured; set it up with PUT /api/jira-settings".to_string();
+            audit.finish(&db, false, &message);
+            return HttpResponse::BadRequest().json(failure(message));
+        },
+        Err(e) => Err(e),
+    };
+    let client = match client {
+        Ok(client) => client,
+        Err(e) => {
+            let message = format!("Failed to load Jira settings: {}", e);
+            audit.finish(&db, false, &message);
+            return HttpResponse::InternalServerError().json(failure(message));
+        }
+    };
+    let run = match db.get_analysis_run(&caller.tenant, &push_request.run_id) {
+        Ok(Some(run)) => run,
+        Ok(None) => {
+            let message = format!("Analysis run not found: {}", push_request.run_id);
+            audit.finish(&db, false, &message);
+            return HttpResponse::NotFound().json(failure(message));
+        },
+        Err(e) => {
+            let message = format!("Failed to load analysis run: {}", e);
+            audit.finish(&db, false, &message);
+            return HttpResponse::InternalServerError().json(failure(message));
+        }
+    };
+    let loaded = RepoUrl::parse(&run.repo_url)
+        .and_then(|repo_url| Ok((repo_url, db.get_run_findings(&push_request.run_id)?, db.list_jira_tickets(&caller.tenant, &run.repo_url)?)));
+    let (repo_url, findings, tickets) = match loaded {
+        Ok(loaded) => loaded,
+        Err(e) => {
+            let message = format!("Failed to load findings: {}", e);
+            audit.finish(&db, false, &message);
+            return HttpResponse::InternalServerError().json(failure(message));
+        }
+    };
+    let report_url = format!("{}/api/analyses/{}/report", mailer.public_url(), push_request.run_id);
+    
+    let mut results = Vec::new();
+    for fingerprint in &push_request.fingerprints {
+        let mut result = JiraPushResult { fingerprint: fingerprint.clone(), ticket: None, created: false, error: None };
+        if let Some(ticket) = tickets.iter().find(|ticket| &ticket.fingerprint == fingerprint) {
+            result.ticket = Some(ticket.clone());
+            results.push(result);
+            continue;
+        }
+        let Some(finding) = findings.iter().find(|finding| &finding.fingerprint == fingerprint) else {
+            result.error = Some("Finding not found in this run".to_string());
+            results.push(result);
+            continue;
+        };
+        let source = TicketSource {
+            finding,
+            repo_url: &repo_url,
+            commit_sha: run.commit_sha.as_deref(),
+            run_id: &push_request.run_id,
+            report_url: &report_url,
+        };
+        match client.create_ticket(&source).await {
+            Ok((key, url)) => {
+                let ticket = JiraTicket {
+                    repo_url: run.repo_url.clone(),
+                    fingerprint: fingerprint.clone(),
+                    key,
+                    url,
+                    created_by: caller.actor.clone(),
+                    created_at: chrono::Utc::now().timestamp(),
+                };
+                if let Err(e) = db.insert_jira_ticket(&caller.tenant, &ticket) {
+                    println!("Warning: Failed to record Jira ticket {} for finding {}: {}", ticket.key, fingerprint, e);
+                }
+                result.ticket = Some(ticket);
+                result.created = true;
+            },
+            Err(e) => result.error = Some(e.to_string()),
+        }
+        results.push(result);
+    }
+    
+    let created = results.iter().filter(|r| r.created).count();
+    let failed = results.iter().filter(|r| r.error.is_some()).count();
+    let message = format!("Created {} Jira tickets, {} already tracked, {} failed", created, results.len() - created - failed, failed);
+    audit.finish(&db, failed == 0, &message);
+    HttpResponse::Ok().json(JiraPushResponse {
+        success: failed == 0,
+        message,
+        results: Some(results),
+    })
+}
+
+#[get("/api/jira/tickets")]
+async fn list_jira_tickets(query: web::Query<JiraTicketsQuery>, caller: Caller, db: web::Data<Database>) -> impl Responder {
+    match db.list_jira_tickets(&caller.tenant, &query.repo_url.canonical()) {
+        Ok(tickets) => HttpResponse::Ok().json(JiraTicketsResponse {
+            success: true,
+            message: format!("Found {} Jira tickets", tickets.len()),
+            tickets: Some(tickets),
+        }),
+        Err(e) => HttpResponse::InternalServerError().json(JiraTicketsResponse {
+            success: false,
+            message: format!("Failed to load Jira tickets: {}", e),
+            tickets: None,
+        }),
+    }
+}
+
+#[post("/api/deployment-check")]
+async fn deployment_check(check_request: Valid<DeploymentCheckRequest>) -> impl Responder {
+    match check_deployment(check_request.cluster, &check_request.program_id).await {
+        Ok(deployment) => {
+            HttpResponse::Ok().json(DeploymentCheckResponse {
+                success: true,
+                message: format!("Program is {} ({} risk)", if deployment.upgradeable { "upgradeable" } else { "immutable" }, deployment.risk.as_str()),
+                deployment: Some(deployment),
+            })
+        },
+        Err(e) => {
+            HttpResponse::BadRequest().json(DeploymentCheckResponse {
+                success: false,
+                message: format!("Failed to check deployment: {}", e),
+                deployment: None,
+            })
+        }
+    }
+}
+
+#[get("/api/repo-file")]
+async fn repo_file(query: web::Query<RepoFileQuery>, req: HttpRequest, token: RequestToken) -> impl Responder {
+    let github_client = GitHubClient::new().with_request_token(&token);
+    let range = req.headers().get(header::RANGE).and_then(|r| r.to_str().ok());
+    let max_bytes = max_file_bytes();
+    
+    let upstream = match github_client.download_file(&query.repo_url, &query.path, query.git_ref.as_deref(), range).await {
+        Ok(response) => response,
+        Err(e) => {
+            return HttpResponse::BadRequest().json(json!({ "success": false, "message": format!("Failed to download file: {}", e) }));
+        }
+    };
+    
+    // Honour Range ourselves when GitHub answers with the whole file
+    let upstream_len = upstream.content_length();
+    let (status, skip, length) = if upstream.status() == reqwest::StatusCode::PARTIAL_CONTENT {
+        (StatusCode::PARTIAL_CONTENT, 0, upstream_len)
+    } else if let Some(range) = range {
+        match upstream_len.and_then(|total| parse_byte_range(range, total)) {
+            Some((start, end)) => (StatusCode::PARTIAL_CONTENT, start, Some(end - start + 1)),
+            None => {
+                let total = upstream_len.map(|t| t.to_string()).unwrap_or_else(|| "*".to_string());
+                return HttpResponse::RangeNotSatisfiable()
+                    .insert_header((header::CONTENT_RANGE, format!("bytes */{}", total)))
+                    .json(json!({ "success": false, "message": format!("Unsatisfiable range: {}", range) }));
+            }
+        }
+    } else {
+        (StatusCode::OK, 0, upstream_len)
+    };
+    
+    if length.is_some_and(|len| len > max_bytes) {
+        return HttpResponse::PayloadTooLarge().json(json!({
+            "success": false,
+            "message": format!("File exceeds the {} byte limit; request a smaller Range", max_bytes),
+        }));
+    }
+    
+    let mut response = HttpResponse::build(status);
+    response
+        .content_type(raw_content_type(&query.path))
+        .insert_header((header::ACCEPT_RANGES, "bytes"))
+        .insert_header((header::X_CONTENT_TYPE_OPTIONS, "nosniff"))
+        // Ranges refer to the raw bytes, so never let the compression middleware re-encode them
+        .insert_header((header::CONTENT_ENCODING, "identity"));
+    if let Some(length) = length {
+        response.insert_header((header::CONTENT_LENGTH, length));
+    }
+    if status == StatusCode::PARTIAL_CONTENT {
+        match upstream.headers().get(reqwest::header::CONTENT_RANGE).and_then(|r| r.to_str().ok()) {
+            Some(content_range) => response.insert_header((header::CONTENT_RANGE, content_range.to_string())),
+            None => response.insert_header((header::CONTENT_RANGE, format!(
+                "bytes {}-{}/{}", skip, skip + length.unwrap_or(1) - 1, upstream_len.unwrap_or(0)
+            ))),
+        };
+    }
+    
+    // Stream through without buffering, skipping/truncating for locally applied ranges
+    // and aborting if an upstream without Content-Length goes over the cap
+    let (mut to_skip, mut to_send, mut sent) = (skip, length, 0u64);
+    let body = upstream.bytes_stream().map(move |chunk| {
+        let mut chunk = chunk.map_err(error::ErrorBadGateway)?;
+        let skipped = to_skip.min(chunk.len() as u64);
+        to_skip -= skipped;
+        let _ = chunk.split_to(skipped as usize);
+        if let Some(remaining) = to_send.as_mut() {
+            chunk.truncate((*remaining).min(chunk.len() as u64) as usize);
+            *remaining -= chunk.len() as u64;
+        }
+        sent += chunk.len() as u64;
+        if sent > max_bytes {
+            return Err(error::ErrorPayloadTooLarge("File exceeds the download size limit"));
+        }
+        Ok::<_, actix_web::Error>(chunk)
+    });
+    response.streaming(body)
+}
+
+// Resolve a single `bytes=` range against the file size into inclusive offsets
+fn parse_byte_range(header: &str, total: u64) -> Option<(u64, u64)> {
+    let spec = header.trim().strip_prefix("bytes=")?;
+    if spec.contains(',') || total == 0 {
+        return None;
+    }
+    let (start, end) = spec.split_once('-')?;
+    let (start, end) = match (start.trim(), end.trim()) {
+        ("", suffix) => {
+            let suffix: u64 = suffix.parse().ok()?;
+            (total.saturating_sub(suffix), total - 1)
+        },
+        (start, "") => (start.parse().ok()?, total - 1),
+        (start, end) => (start.parse().ok()?, end.parse::<u64>().ok()?.min(total - 1)),
+    };
+    if start > end || start >= total {
+        return None;
+    }
+    Some((start, end))
+}
+
+// Repository files are never served as HTML so a malicious repo can't script our origin
+fn raw_content_type(path: &str) -> &'static str {
+    let text_extensions = ["rs", "toml", "lock", "json", "md", "txt", "ts", "js", "yml", "yaml", "sh", "html", "css", "gitignore"];
+    let extension = std::path::Path::new(path).extension().and_then(|e| e.to_str()).unwrap_or("");
+    if text_extensions.contains(&extension) {
+        "text/plain; charset=utf-8"
+    } else {
+        "application/octet-stream"
+    }
+}
+
+// What this deployment supports, as last probed, so clients can offer only options that will run
+#[get("/api/capabilities")]
+async fn get_capabilities(_caller: Caller, probe: web::Data<CapabilityProbe>) -> impl Responder {
+    let capabilities = probe.current().await;
+    HttpResponse::Ok().json(CapabilitiesResponse {
+        success: true,
+        message: format!("{} analyzers, {} of {} fuzz engines and {} of {} clusters available",
+            capabilities.analyzers.iter().filter(|analyzer| analyzer.available).count(),
+            capabilities.fuzz_engines.iter().filter(|engine| engine.available).count(), capabilities.fuzz_engines.len(),
+            capabilities.clusters.iter().filter(|cluster| cluster.reachable).count(), capabilities.clusters.len()),
+        analyzers: capabilities.analyzers.clone(),
+        fuzz_engines: capabilities.fuzz_engines.clone(),
+        toolchains: capabilities.toolchains.clone(),
+        sandbox_levels: capabilities.sandbox_levels.clone(),
+        clusters: capabilities.clusters.clone(),
+        probed_at: capabilities.probed_at,
+    })
+}
+
+#[post("/api/fuzz-test")]
+async fn fuzz_test(
+    fuzzing_request: Valid<FuzzingRequest>,
+    caller: Caller,
+    token: RequestToken,
+    db: web::Data<Database>,
+    storage: web::Data<dyn Storage>,
+    toolchain_manager: web::Data<ToolchainManager>,
+) -> impl Responder {
+    let mut fuzzing_request = fuzzing_request.into_inner();
+    match fuzz_timeout(fuzzing_request.timeout_seconds, &caller.actor, false) {
+        Ok(timeout) => fuzzing_request.timeout_seconds = Some(timeout),
+        Err(e) => return HttpResponse::from_error(e),
+    }
+    if let Err(e) = check_fuzz_engine(&fuzzing_request) {
+        return HttpResponse::from_error(e);
+    }
+    let audit = AuditEvent::start(&caller, "fuzz.run")
+        .target(fuzzing_request.repo_url.canonical())
+        .params(fuzz_audit_params(&fuzzing_request));
+    let (status, response) = run_fuzz_test(&fuzzing_request, &caller.tenant, GitHubClient::new().with_request_token(&token), &toolchain_manager, &db, storage.get_ref(), PhaseTimer::default(), None).await;
+    audit.finish(&db, response.success, &response.message);
+    HttpResponse::build(status).json(response)
+}
+
+// The timeout a fuzz run gets: the requested one or the default, within what the caller's API
+// key may ask for on the endpoint
+fn fuzz_timeout(requested: Option<u64>, actor: &str, queued: bool) -> Result<u64, actix_web::Error> {
+    let max = max_fuzz_timeout(actor, queued);
+    match requested {
+        Some(seconds) if seconds > max && !queued && seconds <= max_fuzz_timeout(actor, true) => {
+            Err(invalid_field("timeout_seconds", format!("must be at most {} seconds; queue longer runs with POST /api/jobs/fuzz", max)))
+        },
+        Some(seconds) if seconds > max => Err(invalid_field("timeout_seconds", format!("must be at most {} seconds", max))),
+        Some(seconds) => Ok(seconds),
+        None => Ok(DEFAULT_FUZZ_TIMEOUT_SECS.min(max)),
+    }
+}
+
+// Whether the requested engine can run the requested strategy. Whether the host has the engine
+// is checked when the run starts, as a queued run may land on another worker.
+fn check_fuzz_engine(request: &FuzzingRequest) -> Result<(), actix_web::Error> {
+    if request.engine.strategies().contains(&request.strategy) {
+        return Ok(());
+    }
+    let supported: Vec<&str> = request.engine.strategies().iter().map(|strategy| strategy.as_str()).collect();
+    Err(invalid_field("engine", format!("{} doesn't support the {} strategy; it supports: {}", request.engine.as_str(), request.strategy.as_str(), supported.join(", "))))
+}
+
+fn fuzz_audit_params(request: &FuzzingRequest) -> serde_json::Value {
+    json!({
+        "repo_url": request.repo_url.canonical(),
+        "instruction_name": request.instruction_name,
+        "timeout_seconds": request.timeout_seconds,
+        "continuous": request.continuous,
+        "commit": request.commit,
+        "strategy": request.strategy,
+        "engine": request.engine,
+        "compute_unit_budget": request.compute_unit_budget,
+        "seed_accounts": request.seed_accounts,
+        "cluster": request.cluster,
+    })
+}
+
+// Copies of the accounts the request seeds, fetched from its cluster now so the harness
+// doesn't depend on the cluster or its state changing mid-run
+async fn fetch_seed_accounts(fuzzing_request: &FuzzingRequest, instruction_name: &str, harness: &Harness) -> Result<Vec<SeedAccount>, (StatusCode, String)> {
+    if fuzzing_request.seed_accounts.is_empty() {
+        return Ok(Vec::new());
+    }
+    let Some(layout) = harness.layout() else {
+        return Err((StatusCode::UNPROCESSABLE_ENTITY, "seed_accounts apply to the account_ordering and compute_units strategies".to_string()));
+    };
+    let cluster = fuzzing_request.cluster;
+    let client = cluster.rpc_client();
+    let mut seeds = Vec::new();
+    for (name, address) in &fuzzing_request.seed_accounts {
+        let Some(index) = layout.accounts.iter().position(|slot| &slot.name == name) else {
+            let names: Vec<&str> = layout.accounts.iter().map(|slot| slot.name.as_str()).collect();
+            return Err((StatusCode::UNPROCESSABLE_ENTITY, format!("{} has no account {}; its accounts are {}", instruction_name, name, names.join(", "))));
+        };
+        if layout.accounts[index].signer {
+            return Err((StatusCode::UNPROCESSABLE_ENTITY, format!("{} signs {} and can't be seeded", name, instruction_name)));
+        }
+        let Ok(address) = Pubkey::from_str(address) else {
+            return Err((StatusCode::BAD_REQUEST, format!("Invalid account address: {}", address)));
+        };
+        let account = client.get_account(&address).await
+            .map_err(|e| (StatusCode::BAD_REQUEST, format!("Failed to fetch {} ({}) on {}: {}", name, address, cluster.as_str(), e)))?;
+        if account.data.len() > MAX_SEED_ACCOUNT_BYTES {
+            return Err((StatusCode::UNPROCESSABLE_ENTITY, format!("{} holds {} bytes of data, more than the {} a seeded account may", name, account.data.len(), MAX_SEED_ACCOUNT_BYTES)));
+        }
+        seeds.push(SeedAccount { index, address, account });
+    }
+    println!("Seeded {} accounts of {} from {}", seeds.len(), instruction_name, cluster.as_str());
+    Ok(seeds)
+}
+
+// Shared by the HTTP handler and queue workers
+#[tracing::instrument(name = "run_fuzz_test", skip_all, fields(repo_url = %fuzzing_request.repo_url))]
+#[allow(clippy::too_many_arguments)]
+async fn run_fuzz_test(fuzzing_request: &FuzzingRequest, tenant: &str, github_client: GitHubClient, toolchain_manager: &ToolchainManager, db: &Database, storage: &dyn Storage, mut phases: PhaseTimer, checkpoints: Option<(FuzzCheckpointSink, Option<FuzzCheckpoint>)>) -> (StatusCode, FuzzingResponse) {
+    let start_time = Instant::now();
+    
+    let engine = fuzzing_request.engine;
+    if !web::block(move || engine_available(engine)).await.unwrap_or(false) {
+        let available: Vec<&str> = FuzzEngine::ALL.into_iter().filter(|&engine| engine_available(engine)).map(|engine| engine.as_str()).collect();
+        return (StatusCode::UNPROCESSABLE_ENTITY, FuzzingResponse {
+            success: false,
+            message: format!("The {} fuzz engine isn't installed on this host; available engines: {}", engine.as_str(), available.join(", ")),
+            errors: None,
+            test_file: None,
+            execution_time_ms: None,
+            cache_hit: None,
+            build_ms: None,
+            run_ms: None,
+            stale_harness: None,
+            rounds: None,
+            corpus_cases: None,
+            metadata: None,
+            artifacts: None,
+            available_instructions: None,
+            findings: None,
+            summary: None,
+            status: None,
+            build_diagnostics: None,
+            network: None,
+            toolchains: None,
+            meta: None,
+        });
+    }
+    
+    // Create temp directory for cloning and testing
+    let temp_dir = match TempDir::new() {
+        Ok(dir) => dir,
+        Err(e) => {
+            return (StatusCode::INTERNAL_SERVER_ERROR, FuzzingResponse {
+                success: false,
+                message: format!("Failed to create temporary directory: {}", e),
+                errors: None,
+                test_file: None,
+                execution_time_ms: None,
+                cache_hit: None,
+                build_ms: None,
+                run_ms: None,
+                stale_harness: None,
+                rounds: None,
+                corpus_cases: None,
+                metadata: None,
+                artifacts: None,
+                available_instructions: None,
+                findings: None,
+                summary: None,
+                status: None,
+                build_diagnostics: None,
+                network: None,
+                toolchains: None,
+                meta: None,
+            });
+        }
+    };
+    
+    // Clone the repository
+    phases.start("clone");
+    let repo_path = temp_dir.path().join("repo");
+    match github_client.clone_repo(&github_client.remote_url(&fuzzing_request.repo_url), &repo_path) {
+        Ok(_) => {},
+        Err(e) => {
+            return (StatusCode::BAD_REQUEST, FuzzingResponse {
+                success: false,
+                message: format!("Failed to clone repository: {}", e),
+                errors: None,
+                test_file: None,
+                execution_time_ms: None,
+                cache_hit: None,
+                build_ms: None,
+                run_ms: None,
+                stale_harness: None,
+                rounds: None,
+                corpus_cases: None,
+                metadata: None,
+                artifacts: None,
+                available_instructions: None,
+                findings: None,
+                summary: None,
+                status: None,
+                build_diagnostics: None,
+                network: None,
+                toolchains: None,
+                meta: None,
+            });
+        }
+    };
+    
+    if let Some(commit) = &fuzzing_request.commit {
+        if let Err(e) = GitHubClient::checkout_commit(&repo_path, commit) {
+            return (StatusCode::BAD_REQUEST, FuzzingResponse {
+                success: false,
+                message: format!("Failed to check out commit: {}", e),
+                errors: None,
+                test_file: None,
+                execution_time_ms: None,
+                cache_hit: None,
+                build_ms: None,
+                run_ms: None,
+                stale_harness: None,
+                rounds: None,
+                corpus_cases: None,
+                metadata: None,
+                artifacts: None,
+                available_instructions: None,
+                findings: None,
+                summary: None,
+                status: None,
+                build_diagnostics: None,
+                network: None,
+                toolchains: None,
+                meta: None,
+            });
+        }
+    }
+    
+    // Initialize fuzzer
+    phases.start("toolchains");
+    let toolchains = provision_toolchains(toolchain_manager, &repo_path, true);
+    let sandbox = match Sandbox::new(fuzzing_request.network_policy, fuzzing_request.vendor_dependencies) {
+        Ok(sandbox) => sandbox.for_tenant(tenant),
+        Err(e) => {
+            return (StatusCode::INTERNAL_SERVER_ERROR, FuzzingResponse {
+                success: false,
+                message: format!("Failed to set up the build sandbox: {}", e),
+                errors: None,
+                test_file: None,
+                execution_time_ms: None,
+                cache_hit: None,
+                build_ms: None,
+                run_ms: None,
+                stale_harness: None,
+                rounds: None,
+                corpus_cases: None,
+                metadata: None,
+                artifacts: None,
+                available_instructions: None,
+                findings: None,
+                summary: None,
+                status: None,
+                build_diagnostics: None,
+                network: None,
+                toolchains: None,
+                meta: None,
+            });
+        }
+    };
+    // The timeout covers building the harness as well as running it; how long the repository's
+    // builds took before decides whether there's time to build this one
+    let repo_url = fuzzing_request.repo_url.canonical();
+    let expected_build = db.fuzz_build_estimate(&repo_url).unwrap_or_else(|e| {
+        println!("Warning: Failed to read earlier fuzz build times: {}", e);
+        None
+    });
+    let mut budget = FuzzBudget {
+        total: Duration::from_secs(fuzzing_request.timeout_seconds.unwrap_or(DEFAULT_FUZZ_TIMEOUT_SECS)),
+        expected_build: expected_build.map(Duration::from_millis),
+    };
+    // A continuous run resumed after a lost lease only fuzzes for what's left of its timeout,
+    // though never less than the default
+    let (checkpoints, resumed) = checkpoints.unzip();
+    let resumed = resumed.flatten().filter(|_| fuzzing_request.continuous);
+    if let Some(resumed) = &resumed {
+        println!("Resuming the continuous fuzz run after {} rounds", resumed.rounds);
+        budget.total = budget.total.saturating_sub(Duration::from_millis(resumed.run_ms))
+            .max(Duration::from_secs(DEFAULT_FUZZ_TIMEOUT_SECS).min(budget.total));
+    }
+    let mut fuzzer = Fuzzer::new(temp_dir.path().to_path_buf(), sandbox.with_toolchains(toolchains.clone()))
+        .with_budget(budget)
+        .for_repository(tenant, &repo_url);
+    if fuzzing_request.reuse_corpus {
+        fuzzer = fuzzer.with_corpus(&repo_url);
+    }
+    if fuzzing_request.continuous {
+        fuzzer = fuzzer.continuous(checkpoints, resumed);
+    }
+    
+    // Fuzz a real instruction of the program: the requested one, or the first found
+    phases.start("instructions");
+    let programs = extract_instructions(&repo_path).unwrap_or_default();
+    let available: Vec<String> = programs.iter().flat_map(|p| &p.instructions).map(|i| i.name.clone()).collect();
+    let instruction = match &fuzzing_request.instruction_name {
+        Some(name) => find_instruction(&programs, name),
+        None => programs.iter().flat_map(|p| &p.instructions).next(),
+    };
+    let Some(instruction) = instruction else {
+        return (StatusCode::UNPROCESSABLE_ENTITY, FuzzingResponse {
+            success: false,
+            message: if available.is_empty() {
+                "No instructions found in the repository's programs".to_string()
+            } else {
+                format!("Instruction {} not found; available instructions: {}", fuzzing_request.instruction_name.as_deref().unwrap_or_default(), available.join(", "))
+            },
+            errors: None,
+            test_file: None,
+            execution_time_ms: None,
+            cache_hit: None,
+            build_ms: None,
+            run_ms: None,
+            stale_harness: None,
+            rounds: None,
+            corpus_cases: None,
+            metadata: None,
+            artifacts: None,
+            available_instructions: Some(available),
+            findings: None,
+            summary: None,
+            status: None,
+            build_diagnostics: None,
+            network: None,
+            toolchains: None,
+            meta: None,
+        });
+    };
+    let instruction_name = instruction.name.clone();
+    
+    // The other harnesses call the instruction properly: they need the accounts it expects, in order
+    let layout = match (fuzzing_request.strategy, fuzzing_request.engine) {
+        (FuzzStrategy::Inputs, FuzzEngine::Proptest) => None,
+        _ => instruction_layout(&repo_path, &instruction_name).unwrap_or_else(|e| {
+            println!("Warning: Failed to read the accounts of {}: {}", instruction_name, e);
+            None
+        }),
+    };
+    let harness = match (fuzzing_request.strategy, layout) {
+        (FuzzStrategy::Inputs, Some(layout)) if fuzzing_request.engine == FuzzEngine::Honggfuzz => Harness::Honggfuzz(layout),
+        (FuzzStrategy::Inputs, None) if fuzzing_request.engine == FuzzEngine::Honggfuzz => {
+                return (StatusCode::UNPROCESSABLE_ENTITY, FuzzingResponse {
+                    success: false,
+                    message: format!("Couldn't determine the accounts {} expects; honggfuzz harnesses need an Anchor accounts struct or numbered account docs on the instruction", instruction_name),
+                    errors: None,
+                    test_file: None,
+                    execution_time_ms: None,
+                    cache_hit: None,
+                    build_ms: None,
+                    run_ms: None,
+                    stale_harness: None,
+                    rounds: None,
+                    corpus_cases: None,
+                    metadata: None,
+                    artifacts: None,
+                    available_instructions: None,
+                    findings: None,
+                    summary: None,
+                    status: None,
+                    build_diagnostics: None,
+                    network: None,
+                    toolchains: None,
+                    meta: None,
+                });
+        },
+        (FuzzStrategy::Inputs, _) => Harness::Inputs,
+        (FuzzStrategy::AccountOrdering, Some(layout)) => Harness::AccountOrdering(layout),
+        (FuzzStrategy::ComputeUnits, Some(layout)) => Harness::ComputeUnits {
+            layout,
+            budget: fuzzing_request.compute_unit_budget.map_or(DEFAULT_COMPUTE_UNIT_BUDGET, u64::from),
+        },
+        (strategy, None) => {
+                return (StatusCode::UNPROCESSABLE_ENTITY, FuzzingResponse {
+                    success: false,
+                    message: format!("Couldn't determine the accounts {} expects; {} fuzzing needs an Anchor accounts struct or numbered account docs on the instruction", instruction_name, strategy.as_str().replace('_', " ")),
+                    errors: None,
+                    test_file: None,
+                    execution_time_ms: None,
+                    cache_hit: None,
+                    build_ms: None,
+                    run_ms: None,
+                    stale_harness: None,
+                    rounds: None,
+                    corpus_cases: None,
+                    metadata: None,
+                    artifacts: None,
+                    available_instructions: None,
+                    findings: None,
+                    summary: None,
+                    status: None,
+                    build_diagnostics: None,
+                    network: None,
+                    toolchains: None,
+                    meta: None,
+                });
+        },
+    };
+    
+    match fetch_seed_accounts(fuzzing_request, &instruction_name, &harness).await {
+        Ok(seeds) => fuzzer = fuzzer.with_seed_accounts(seeds),
+        Err((status, message)) => {
+            return (status, FuzzingResponse {
+                success: false,
+                message,
+                errors: None,
+                test_file: None,
+                execution_time_ms: None,
+                cache_hit: None,
+                build_ms: None,
+                run_ms: None,
+                stale_harness: None,
+                rounds: None,
+                corpus_cases: None,
+                metadata: None,
+                artifacts: None,
+                available_instructions: None,
+                findings: None,
+                summary: None,
+                status: None,
+                build_diagnostics: None,
+                network: None,
+                toolchains: None,
+                meta: None,
+            });
+        }
+    }
+    
+    // Generate and run fuzz tests
+    phases.start("fuzz");
+    let result = fuzzer.generate_and_run_fuzz_tests(&repo_path, &instruction_name, &harness);
+    phases.end();
+    if let Some(result) = result.as_ref().ok().filter(|result| result.build_diagnostics.is_empty()) {
+        if let Err(e) = db.record_fuzz_timing(&repo_url, result.build_ms, result.run_ms, result.cache_hit) {
+            println!("Warning: Failed to record fuzz timings: {}", e);
+        }
+    }
+    match result {
+        Ok(result) if !result.build_diagnostics.is_empty() => {
+            (StatusCode::UNPROCESSABLE_ENTITY, FuzzingResponse {
+                success: false,
+                message: format!("Program failed to build with {} errors; fix them before fuzzing", result.build_diagnostics.len()),
+                errors: None,
+                test_file: None,
+                execution_time_ms: Some(start_time.elapsed().as_millis() as u64),
+                cache_hit: None,
+                build_ms: None,
+                run_ms: None,
+                stale_harness: None,
+                rounds: None,
+                corpus_cases: None,
+                metadata: None,
+                artifacts: None,
+                available_instructions: None,
+                findings: None,
+                summary: None,
+                status: Some(FuzzStatus::BuildFailed),
+                build_diagnostics: Some(result.build_diagnostics),
+                network: Some(fuzzer.network_report()),
+                meta: Some(execution_meta(Some(&toolchains), &current_rule_set().version, phases)),
+                toolchains: Some(toolchains),
+            })
+        },
+        Ok(result) => {
+            let execution_time = start_time.elapsed().as_millis() as u64;
+            let status = if result.build_timed_out {
+                FuzzStatus::BuildTimedOut
+            } else if result.timed_out {
+                FuzzStatus::TimedOut
+            } else if result.errors.is_empty() {
+                FuzzStatus::Passed
+            } else {
+                FuzzStatus::IssuesFound
+            };
+            
+            // Get the test file content
+            let test_file_name = harness.test_file_name(&instruction_name);
+            let test_file_path = temp_dir.path().join("fuzz_tests").join(&test_file_name);
+            let test_file_content = std::fs::read_to_string(&test_file_path).ok();
+            
+            // Keep the harness and its output so any instance can serve them after the temp dir is gone
+            phases.start("artifacts");
+            let artifact_prefix = tenant_key(tenant, &format!("fuzz/{}", uuid::Uuid::new_v4()));
+            let mut artifacts = Vec::new();
+            for file_name in [test_file_name, "Cargo.toml".to_string(), "test_output.log".to_string()] {
+                let Ok(data) = std::fs::read(temp_dir.path().join("fuzz_tests").join(&file_name)) else {
+                    continue;
+                };
+                let key = format!("{}/{}", artifact_prefix, file_name);
+                match storage.put(&key, data, content_type_for_key(&key)).await {
+                    Ok(_) => artifacts.push(key),
+                    Err(e) => println!("Warning: Failed to store fuzz artifact {}: {}", key, e),
+                }
+            }
+            // The inputs that failed, whichever engine found them
+            for file_name in &result.crashes {
+                let Ok(data) = std::fs::read(temp_dir.path().join("fuzz_tests").join("crashes").join(file_name)) else {
+                    continue;
+                };
+                let key = format!("{}/crashes/{}", artifact_prefix, file_name);
+                match storage.put(&key, data, content_type_for_key(&key)).await {
+                    Ok(_) => artifacts.push(key),
+                    Err(e) => println!("Warning: Failed to store fuzz artifact {}: {}", key, e),
+                }
+            }
+            
+            (StatusCode::OK, FuzzingResponse {
+                success: !result.build_timed_out && !result.timed_out && result.errors.is_empty(),
+                message: if result.build_timed_out {
+                    format!("Building the fuzz harness took longer than the {}s of the {}s budget it may use, so nothing was fuzzed; raise timeout_seconds",
+                        budget.build_limit().as_secs(), budget.total.as_secs())
+                } else if result.timed_out {
+                    "Fuzzing tests timed out".to_string()
+                } else if result.errors.is_empty() {
+                    "Fuzzing tests completed successfully".to_string()
+                } else {
+                    "Fuzzing tests found potential issues".to_string()
+                },
+                errors: if result.errors.is_empty() { None } else { Some(result.errors) },
+                test_file: test_file_content,
+                execution_time_ms: Some(execution_time),
+                cache_hit: Some(result.cache_hit),
+                build_ms: Some(result.build_ms),
+                run_ms: Some(result.run_ms),
+                stale_harness: Some(result.stale_harness),
+                rounds: Some(result.rounds),
+                corpus_cases: result.corpus_cases,
+                metadata: Some(FuzzingMetadata {
+                    anchor_version: result.toolchain.anchor_version,
+                    solana_version: result.toolchain.solana_version,
+                    rust_toolchain: result.toolchain.rust_toolchain,
+                    dependencies: result.toolchain.harness_dependencies,
+                    from_lockfile: result.toolchain.from_lockfile,
+                }),
+                artifacts: Some(artifacts),
+                available_instructions: None,
+                summary: Some(summarize_findings(&result.findings)),
+                findings: Some(result.findings),
+                status: Some(status),
+                build_diagnostics: None,
+                network: Some(fuzzer.network_report()),
+                meta: Some(execution_meta(Some(&toolchains), &current_rule_set().version, phases)),
+                toolchains: Some(toolchains),
+            })
+        },
+        Err(e) => {
+            (StatusCode::INTERNAL_SERVER_ERROR, FuzzingResponse {
+                success: false,
+                message: format!("Failed to run fuzzing tests: {}", e),
+                errors: None,
+                test_file: None,
+                execution_time_ms: Some(start_time.elapsed().as_millis() as u64),
+                cache_hit: None,
+                build_ms: None,
+                run_ms: None,
+                stale_harness: None,
+                rounds: None,
+                corpus_cases: None,
+                metadata: None,
+                artifacts: None,
+                available_instructions: None,
+                findings: None,
+                summary: None,
+                status: None,
+                build_diagnostics: None,
+                network: Some(fuzzer.network_report()),
+                meta: Some(execution_meta(Some(&toolchains), &current_rule_set().version, phases)),
+                toolchains: Some(toolchains),
+            })
+        }
+    }
+}
+
+#[post("/api/build-program")]
+async fn build_program(
+    build_request: Valid<BuildProgramRequest>,
+    caller: Caller,
+    token: RequestToken,
+    db: web::Data<Database>,
+    storage: web::Data<dyn Storage>,
+    toolchain_manager: web::Data<ToolchainManager>,
+) -> impl Responder {
+    let audit = AuditEvent::start(&caller, "program.build")
+        .target(build_request.repo_url.canonical())
+        .params(json!({ "repo_url": build_request.repo_url.canonical(), "include_artifacts": build_request.include_artifacts }));
+    let (status, response) = run_program_build(&build_request, &caller.tenant, GitHubClient::new().with_request_token(&token), &toolchain_manager, storage.get_ref()).await;
+    audit.finish(&db, response.success, &response.message);
+    HttpResponse::build(status).json(response)
+}
+
+async fn run_program_build(build_request: &BuildProgramRequest, tenant: &str, github_client: GitHubClient, toolchain_manager: &ToolchainManager, storage: &dyn Storage) -> (StatusCode, BuildProgramResponse) {
+    let start_time = Instant::now();
+    let failure = |message: String| BuildProgramResponse {
+        success: false,
+        message,
+        programs: None,
+        build_log: None,
+        execution_time_ms: None,
+        network: None,
+        toolchains: None,
+    };
+
+    let temp_dir = match TempDir::new() {
+        Ok(dir) => dir,
+        Err(e) => return (StatusCode::INTERNAL_SERVER_ERROR, failure(format!("Failed to create temporary directory: {}", e))),
+    };
+    let repo_path = temp_dir.path().join("repo");
+    if let Err(e) = github_client.clone_repo(&github_client.remote_url(&build_request.repo_url), &repo_path) {
+        return (StatusCode::BAD_REQUEST, failure(format!("Failed to clone repository: {}", e)));
+    }
+
+    let toolchains = provision_toolchains(toolchain_manager, &repo_path, false);
+    let sandbox = match Sandbox::new(build_request.network_policy, build_request.vendor_dependencies) {
+        Ok(sandbox) => sandbox.for_tenant(tenant).with_toolchains(toolchains.clone()),
+        Err(e) => return (StatusCode::INTERNAL_SERVER_ERROR, failure(format!("Failed to set up the build sandbox: {}", e))),
+    };
+
+    // Builds take minutes, so they run on the blocking thread pool
+    let built = web::block(move || {
+        let build = build_programs(&repo_path, &sandbox);
+        (build, sandbox.report())
+    }).await;
+    let (build, network) = match built {
+        Ok(built) => built,
+        Err(e) => return (StatusCode::INTERNAL_SERVER_ERROR, failure(format!("Failed to build programs: {}", e))),
+    };
+    let build = match build {
+        Ok(build) => build,
+        Err(e) => {
+            return (StatusCode::UNPROCESSABLE_ENTITY, BuildProgramResponse {
+                execution_time_ms: Some(start_time.elapsed().as_millis() as u64),
+                network: Some(network),
+                toolchains: Some(toolchains),
+                ..failure(format!("Failed to build programs: {}", e))
+            });
+        }
+    };
+
+    let build_id = uuid::Uuid::new_v4();
+    let mut programs = Vec::new();
+    for program in build.programs {
+        let mut artifact = None;
+        if build_request.include_artifacts {
+            let key = tenant_key(tenant, &format!("builds/{}/{}.so", build_id, program.name));
+            match std::fs::read(&program.path) {
+                Ok(data) => match storage.put(&key, data, content_type_for_key(&key)).await {
+                    Ok(_) => artifact = Some(key),
+                    Err(e) => println!("Warning: Failed to store program binary {}: {}", key, e),
+                },
+                Err(e) => println!("Warning: Failed to read program binary {}: {}", program.path.display(), e),
+            }
+        }
+        programs.push(BuiltProgram { name: program.name, sha256: program.sha256, size: program.size, artifact });
+    }
+
+    let message = if !build.success {
+        "Build failed".to_string()
+    } else if programs.is_empty() {
+        "Build succeeded but produced no program binaries".to_string()
+    } else {
+        format!("Built {} program(s)", programs.len())
+    };
+    (StatusCode::OK, BuildProgramResponse {
+        success: build.success && !programs.is_empty(),
+        message,
+        programs: Some(programs),
+        build_log: Some(build.log),
+        execution_time_ms: Some(start_time.elapsed().as_millis() as u64),
+        network: Some(network),
+        toolchains: Some(toolchains),
+    })
+}
+
+#[post("/api/analyze-code")]
+#[allow(clippy::too_many_arguments)]
+async fn analyze_code(
+    analysis_request: Valid<CodeAnalysisRequest>,
+    caller: Caller,
+    token: RequestToken,
+    db: web::Data<Database>,
+    mailer: web::Data<Mailer>,
+    storage: web::Data<dyn Storage>,
+    external: web::Data<ExternalAnalyzers>,
+    toolchain_manager: web::Data<ToolchainManager>,
+) -> impl Responder {
+    let audit = AuditEvent::start(&caller, "analysis.run")
+        .target(analysis_request.repo_url.canonical())
+        .params(json!({ "repo_url": analysis_request.repo_url.canonical() }));
+    let (status, response) = run_code_analysis(&analysis_request, GitHubClient::new().with_request_token(&token), &caller.tenant, &db, &mailer, storage.get_ref(), &external, &toolchain_manager, PhaseTimer::default(), None).await;
+    audit.finish(&db, response.success, &response.message);
+    HttpResponse::build(status).json(response)
+}
+
+// The single-file rules over pasted source, for editor and playground integrations. Nothing
+// is cloned, built or recorded.
+#[post("/api/analyze-snippet")]
+async fn analyze_snippet(snippet_request: Valid<SnippetAnalysisRequest>, _caller: Caller) -> impl Responder {
+    let start_time = Instant::now();
+    let snippet_request = snippet_request.into_inner();
+    let file_name = snippet_request.file_name.unwrap_or_else(|| "snippet.rs".to_string());
+    let source = snippet_request.source;
+    let rule_set = match snippet_request.ruleset_version.as_deref().map(resolve_rule_set).transpose() {
+        Ok(rule_set) => rule_set.unwrap_or_else(current_rule_set),
+        Err(e) => return HttpResponse::UnprocessableEntity().json(SnippetAnalysisResponse {
+            success: false,
+            message: e.to_string(),
+            bugs: None,
+            elapsed_ms: None,
+            meta: None,
+        }),
+    };
+    let rule_set_version = rule_set.version.clone();
+    let mut phases = PhaseTimer::default();
+    phases.start("analysis");
+    match web::block(move || CodeAnalyzer::with_rule_set(rule_set).analyze_source(&file_name, &source)).await {
+        Ok(bugs) => HttpResponse::Ok().json(SnippetAnalysisResponse {
+            success: true,
+            message: format!("Found {} issues", bugs.len()),
+            bugs: Some(bugs),
+            elapsed_ms: Some(start_time.elapsed().as_millis() as u64),
+            // No compiler or CLI runs on a snippet
+            meta: Some(execution_meta(None, &rule_set_version, phases)),
+        }),
+        Err(e) => HttpResponse::InternalServerError().json(SnippetAnalysisResponse {
+            success: false,
+            message: format!("Failed to analyze snippet: {}", e),
+            bugs: None,
+            elapsed_ms: None,
+            meta: None,
+        }),
+    }
+}
+
+// Shared by the HTTP handler and queue workers
+#[tracing::instrument(name = "run_code_analysis", skip_all, fields(repo_url = %analysis_request.repo_url, tenant = %tenant))]
+#[allow(clippy::too_many_arguments)]
+async fn run_code_analysis(
+    analysis_request: &CodeAnalysisRequest,
+    github_client: GitHubClient,
+    tenant: &str,
+    db: &web::Data<Database>,
+    mailer: &web::Data<Mailer>,
+    storage: &dyn Storage,
+    external: &ExternalAnalyzers,
+    toolchain_manager: &ToolchainManager,
+    mut phases: PhaseTimer,
+    // The queued job running the analysis, and where its events go
+    job: Option<(&str, AnalysisEventSink)>,
+) -> (StatusCode, CodeAnalysisResponse) {
+    println!("Received code analysis request for: {}", analysis_request.repo_url);
+    
+    // A queued job can outlive the rule set it pinned
+    let rule_set = match analysis_request.ruleset_version.as_deref().map(resolve_rule_set).transpose() {
+        Ok(rule_set) => rule_set.unwrap_or_else(current_rule_set),
+        Err(e) => {
+            return (StatusCode::UNPROCESSABLE_ENTITY, CodeAnalysisResponse {
+                success: false,
+                message: e.to_string(),
+                gate_passed: false,
+                bugs: None,
+                suppressed_bugs: None,
+                run_id: None,
+                report_artifact: None,
+                deployment: None,
+                commit_status: None,
+                network: None,
+                toolchains: None,
+                binary_findings: None,
+                cpi_surface: None,
+                exclusions: None,
+                progress: None,
+                meta: None,
+            });
+        }
+    };
+    
+    // Create a temporary directory for cloning
+    let temp_dir = match TempDir::new() {
+        Ok(dir) => dir,
+        Err(e) => {
+            return (StatusCode::INTERNAL_SERVER_ERROR, CodeAnalysisResponse {
+                success: false,
+                message: format!("Failed to create temporary directory: {}", e),
+                gate_passed: false,
+                bugs: None,
+                suppressed_bugs: None,
+                run_id: None,
+                report_artifact: None,
+                deployment: None,
+                commit_status: None,
+                network: None,
+                toolchains: None,
+                binary_findings: None,
+                cpi_surface: None,
+                exclusions: None,
+                progress: None,
+                meta: None,
+            });
+        }
+    };
+    
+    // Clone the repository
+    phases.start("clone");
+    println!("Cloning repository to: {}", temp_dir.path().display());
+    match github_client.clone_repo(&github_client.remote_url(&analysis_request.repo_url), temp_dir.path()) {
+        Ok(_) => {},
+        Err(e) => {
+            return (StatusCode::BAD_REQUEST, CodeAnalysisResponse {
+                success: false,
+                message: format!("Failed to clone repository: {}", e),
+                gate_passed: false,
+                bugs: None,
+                suppressed_bugs: None,
+                run_id: None,
+                report_artifact: None,
+                deployment: None,
+                commit_status: None,
+                network: None,
+                toolchains: None,
+                binary_findings: None,
+                cpi_surface: None,
+                exclusions: None,
+                progress: None,
+                meta: None,
+            });
+        }
+    };
+    
+    // The request's excluded paths and the repository's own
+    let exclusions = match PathExclusions::load(temp_dir.path(), &analysis_request.exclude_paths) {
+        Ok(exclusions) => exclusions,
+        Err(e) => {
+            return (StatusCode::UNPROCESSABLE_ENTITY, CodeAnalysisResponse {
+                success: false,
+                message: e.to_string(),
+                gate_passed: false,
+                bugs: None,
+                suppressed_bugs: None,
+                run_id: None,
+                report_artifact: None,
+                deployment: None,
+                commit_status: None,
+                network: None,
+                toolchains: None,
+                binary_findings: None,
+                cpi_surface: None,
+                exclusions: None,
+                progress: None,
+                meta: None,
+            });
+        }
+    };
+    
+    // Native programs get their own rule set instead of the Anchor lints
+    let project_type = match github_client.detect_project_type(temp_dir.path()) {
+        Ok(project_type) => project_type.unwrap_or(ProjectType::Anchor),
+        Err(e) => {
+            println!("Warning: Failed to detect project type: {}", e);
+            ProjectType::Anchor
+        }
+    };
+    
+    // Run code analysis
+    phases.start("toolchains");
+    let quick = analysis_request.mode == AnalysisMode::Quick;
+    let toolchains = if quick { ProvisionedToolchains::default() } else { provision_toolchains(toolchain_manager, temp_dir.path(), false) };
+    let sandbox = match Sandbox::new(analysis_request.network_policy, analysis_request.vendor_dependencies) {
+        Ok(sandbox) => sandbox.for_tenant(tenant),
+        Err(e) => {
+            return (StatusCode::INTERNAL_SERVER_ERROR, CodeAnalysisResponse {
+                success: false,
+                message: format!("Failed to set up the build sandbox: {}", e),
+                gate_passed: false,
+                bugs: None,
+                suppressed_bugs: None,
+                run_id: None,
+                report_artifact: None,
+                deployment: None,
+                commit_status: None,
+                network: None,
+                toolchains: None,
+                binary_findings: None,
+                cpi_surface: None,
+                exclusions: None,
+                progress: None,
+                meta: None,
+            });
+        }
+    };
+    let sandbox = sandbox.with_toolchains(toolchains.clone());
+    let repo_url = analysis_request.repo_url.canonical();
+    let commit_sha = GitHubClient::head_commit(temp_dir.path());
+    
+    // Earlier triage decisions, filed issues and assignees carry forward by fingerprint, to findings as
+    // they're found as well as to the final list
+    let triage: HashMap<String, FindingTriage> = match db.list_triage(tenant, &repo_url) {
+        Ok(triage) => triage.into_iter().map(|t| (t.fingerprint.clone(), t)).collect(),
+        Err(e) => {
+            println!("Warning: Failed to load triage state: {}", e);
+            HashMap::new()
+        }
+    };
+    let issues: HashMap<String, String> = match db.list_finding_issues(tenant, &repo_url) {
+        Ok(issues) => issues.into_iter().collect(),
+        Err(e) => {
+            println!("Warning: Failed to load finding issues: {}", e);
+            HashMap::new()
+        }
+    };
+    let assignees: HashMap<String, String> = match db.list_finding_assignees(tenant, &repo_url) {
+        Ok(assignees) => assignees.into_iter().collect(),
+        Err(e) => {
+            println!("Warning: Failed to load finding assignees: {}", e);
+            HashMap::new()
+        }
+    };
+    
+    // Keep the run for trend/compare queries; history is best-effort. Quick scans would
+    // show up there as spurious drops in findings. Each pass's findings are stored when it's
+    // done, so they can be listed and triaged while the slower passes run.
+    let run_id = match quick {
+        true => None,
+        false => match db.start_analysis_run(tenant, &repo_url, commit_sha.as_deref(), job.as_ref().map(|(job_id, _)| *job_id)) {
+            Ok(run_id) => Some(run_id),
+            Err(e) => {
+                println!("Warning: Failed to store analysis run: {}", e);
+                None
+            }
+        },
+    };
+    
+    phases.start("analysis");
+    let excluded_paths = exclusions.patterns().to_vec();
+    let ignored_exclusions = exclusions.ignored().len();
+    let analyzer = CodeAnalyzer::with_rule_set(rule_set.clone()).excluding(exclusions);
+    let mut progress = Vec::new();
+    let analysis = analyzer.analyze_repo(temp_dir.path(), project_type, analysis_request.mode, external, &sandbox, &mut |mut event: AnalysisEvent| {
+        match &mut event {
+            AnalysisEvent::Progress(stage) => {
+                if stage.skipped {
+                    println!("Analysis progress: skipped {} at {}ms", stage.stage, stage.elapsed_ms);
+                } else {
+                    println!("Analysis progress: {} at {}ms", stage.stage, stage.elapsed_ms);
+                }
+                progress.push(stage.clone());
+            },
+            AnalysisEvent::Findings { bugs, .. } => {
+                carry_forward_triage(bugs, &triage, &issues, &assignees);
+                if let Some(run_id) = &run_id {
+                    if let Err(e) = db.append_findings(run_id, bugs) {
+                        println!("Warning: Failed to store findings: {}", e);
+                    }
+                }
+            },
+        }
+        if let Some((_, events)) = &job {
+            events(&event);
+        }
+    });
+    match analysis {
+        Ok(mut bugs) => {
+            phases.start("triage");
+            carry_forward_triage(&mut bugs, &triage, &issues, &assignees);
+            let run_id = run_id.filter(|run_id| match db.finish_analysis_run(run_id, &bugs) {
+                Ok(()) => true,
+                Err(e) => {
+                    println!("Warning: Failed to store analysis run: {}", e);
+                    false
+                }
+            });
+            
+            let (suppressed_bugs, bugs): (Vec<CodeBug>, Vec<CodeBug>) = bugs.into_iter()
+                .partition(|bug| bug.triage_state.is_some_and(|state| state.is_suppressed()));
+            
+            // Governance risk of the deployed program sits alongside the code findings
+            phases.start("deployment");
+            let mut deployment_error = None;
+            let deployment = match analysis_request.program_id.as_ref().filter(|_| !quick) {
+                Some(program_id) => match check_deployment(analysis_request.cluster, program_id).await {
+                    Ok(deployment) => Some(deployment),
+                    Err(e) => {
+                        println!("Warning: Deployment check failed: {}", e);
+                        deployment_error = Some(e.to_string());
+                        None
+                    }
+                },
+                None => None,
+            };
+            
+            // Checks on the compiled binaries, which catch what the source doesn't show
+            phases.start("build");
+            let mut build_error = None;
+            let binary_findings = if analysis_request.build_program && !quick {
+                match build_programs(temp_dir.path(), &sandbox) {
+                    Ok(build) if build.success => Some(check_build(&build)),
+                    Ok(_) => {
+                        build_error = Some("the build failed".to_string());
+                        None
+                    },
+                    Err(e) => {
+                        println!("Warning: Program build failed: {}", e);
+                        build_error = Some(e.to_string());
+                        None
+                    }
+                }
+            } else {
+                None
+            };
+            
+            // The reviewed CPI surface, alongside the allowlist findings the analyzer raised
+            phases.start("cpi");
+            let cpi_surface = match cpi_surface(temp_dir.path()) {
+                Ok(surface) => Some(surface),
+                Err(e) => {
+                    println!("Warning: Failed to map CPI targets: {}", e);
+                    None
+                }
+            };
+            
+            phases.start("report");
+            let context = ReportContext {
+                repo_url: repo_url.clone(),
+                commit_sha: commit_sha.clone(),
+                run_id: run_id.clone(),
+                bugs: bugs.clone(),
+                deployment: deployment.clone(),
+                cpi_surface: cpi_surface.clone(),
+                discussion: db.list_finding_threads(tenant, &repo_url, None).unwrap_or_else(|e| {
+                    println!("Warning: Failed to load finding discussion: {}", e);
+                    Vec::new()
+                }),
+                anchors: match &commit_sha {
+                    Some(sha) => db.list_report_anchors(tenant, &repo_url, sha).unwrap_or_else(|e| {
+                        println!("Warning: Failed to load report anchors: {}", e);
+                        Vec::new()
+                    }),
+                    None => Vec::new(),
+                },
+            };
+            
+            // Store the rendered report so it can be downloaded later from any instance; a quick
+            // scan's partial results aren't a deliverable
+            let report_key = tenant_key(tenant, &format!("reports/{}.html", run_id.clone().unwrap_or_else(|| uuid::Uuid::new_v4().to_string())));
+            let report_artifact = match quick {
+                true => None,
+                false => match storage.put(&report_key, render_report(&context, tenant_template(db, tenant).as_deref()).into_bytes(), content_type_for_key(&report_key)).await {
+                    Ok(_) => Some(report_key),
+                    Err(e) => {
+                        println!("Warning: Failed to store report: {}", e);
+                        None
+                    }
+                },
+            };
+            
+            // Pass/fail signal for CI, from the same threshold as the commit status
+            let fail_on = analysis_request.fail_on.unwrap_or_else(default_fail_on);
+            let gate_passed = gate_passed(&bugs, fail_on);
+            
+            // Pass/fail signal on the analyzed commit for repos without Checks integration
+            phases.start("commit_status");
+            let commit_status = match (&commit_sha, analysis_request.commit_status && !quick) {
+                (Some(sha), true) => {
+                    let target_url = report_artifact.as_ref().map(|key| format!("{}/api/artifacts/{}", mailer.public_url(), key));
+                    Some(post_commit_status(&github_client, &analysis_request.repo_url, sha, &bugs, fail_on, target_url).await)
+                },
+                (None, true) => {
+                    println!("Warning: Commit status requested but the analyzed commit is unknown");
+                    None
+                },
+                _ => None,
+            };
+            
+            // Email the report to the tenant's recipients without holding up the response
+            if mailer.is_configured() && !quick {
+                let (db, mailer, tenant) = (db.clone(), mailer.clone(), tenant.to_string());
+                actix_web::rt::spawn(async move {
+                    match mailer.deliver_report(&db, &tenant, &context).await {
+                        Ok(sent) if sent > 0 => println!("Emailed report to {} recipients", sent),
+                        Ok(_) => {},
+                        Err(e) => println!("Warning: Failed to email report: {}", e),
+                    }
+                });
+            }
+            
+            let scan = match analysis_request.mode {
+                AnalysisMode::Quick => "Quick scan",
+                AnalysisMode::Deep => "Deep scan",
+                AnalysisMode::Full => "Analysis",
+            };
+            let mut message = format!("{} completed. Found {} issues ({} suppressed by triage).", scan, bugs.len(), suppressed_bugs.len());
+            if let Some(e) = deployment_error {
+                message.push_str(&format!(" Deployment check failed: {}", e));
+            }
+            if let Some(e) = build_error {
+                message.push_str(&format!(" Program build failed: {}", e));
+            }
+            if !gate_passed {
+                message.push_str(&format!(" Gate failed: findings at or above {}.", fail_on.as_str()));
+            }
+            if ignored_exclusions > 0 {
+                message.push_str(&format!(" Ignored {} exclude_paths from .safex.toml; set SAFEX_REPO_EXCLUSIONS=true to trust them.", ignored_exclusions));
+            }
+            if let Some(until) = &rule_set.supported_until {
+                message.push_str(&format!(" Rule set {} is deprecated and can be pinned until {}; the newest is {}.", rule_set.version, until, current_rule_set().version));
+            }
+            (StatusCode::OK, CodeAnalysisResponse {
+                success: true,
+                message,
+                gate_passed,
+                bugs: Some(bugs),
+                suppressed_bugs: Some(suppressed_bugs),
+                run_id,
+                report_artifact,
+                deployment,
+                commit_status,
+                network: (!quick).then(|| sandbox.report()),
+                toolchains: (!quick).then(|| toolchains.clone()),
+                binary_findings,
+                cpi_surface,
+                exclusions: Some(excluded_paths),
+                progress: Some(progress),
+                meta: Some(execution_meta(Some(&toolchains), &rule_set.version, phases)),
+            })
+        },
+        Err(e) => {
+            if let Some(run_id) = &run_id {
+                if let Err(e) = db.fail_analysis_run(run_id) {
+                    println!("Warning: Failed to mark analysis run failed: {}", e);
+                }
+            }
+            (StatusCode::INTERNAL_SERVER_ERROR, CodeAnalysisResponse {
+                success: false,
+                message: format!("Analysis failed: {}", e),
+                gate_passed: false,
+                bugs: None,
+                suppressed_bugs: None,
+                run_id: None,
+                report_artifact: None,
+                deployment: None,
+                commit_status: None,
+                network: Some(sandbox.report()),
+                toolchains: Some(toolchains.clone()),
+                binary_findings: None,
+                cpi_surface: None,
+                exclusions: None,
+                progress: None,
+                meta: Some(execution_meta(Some(&toolchains), &rule_set.version, phases)),
+            })
+        }
+    }
+}
+
+// Earlier triage decisions, filed issues and assignees, by fingerprint
+fn carry_forward_triage(bugs: &mut [CodeBug], triage: &HashMap<String, FindingTriage>, issues: &HashMap<String, String>, assignees: &HashMap<String, String>) {
+    for bug in bugs.iter_mut() {
+        bug.assignee = assignees.get(&bug.fingerprint).cloned();
+        if let Some(decision) = triage.get(&bug.fingerprint) {
+            bug.triage_state = Some(decision.state);
+            bug.triage_comment = decision.comment.clone();
+        }
+        bug.issue_url = issues.get(&bug.fingerprint).cloned();
+    }
+}
+
+#[get("/api/trends")]
+async fn trends(query: web::Query<TrendsQuery>, caller: Caller, db: web::Data<Database>) -> impl Responder {
+    let repo_url = query.repo_url.canonical();
+    
+    match db.severity_trend(&caller.tenant, &repo_url) {
+        Ok(points) => {
+            HttpResponse::Ok().json(TrendsResponse {
+                success: true,
+                message: format!("Found {} analysis runs", points.len()),
+                repo_url,
+                points: Some(points),
+            })
+        },
+        Err(e) => {
+            HttpResponse::InternalServerError().json(TrendsResponse {
+                success: false,
+                message: format!("Failed to load analysis history: {}", e),
+                repo_url,
+                points: None,
+            })
+        }
+    }
+}
+
+#[get("/api/compare")]
+async fn compare_runs(query: web::Query<CompareQuery>, caller: Caller, db: web::Data<Database>) -> impl Responder {
+    let error_response = |message: String| CompareResponse {
+        success: false,
+        message,
+        run_a: query.run_a.clone(),
+        run_b: query.run_b.clone(),
+        commit_a: None,
+        commit_b: None,
+        new_findings: None,
+        resolved_findings: None,
+        unchanged_count: None,
+    };
+    
+    let mut runs = Vec::new();
+    for run_id in [&query.run_a, &query.run_b] {
+        match db.get_analysis_run(&caller.tenant, run_id) {
+            Ok(Some(run)) => runs.push(run),
+            Ok(None) => return HttpResponse::NotFound().json(error_response(format!("Analysis run not found: {}", run_id))),
+            Err(e) => return HttpResponse::InternalServerError().json(error_response(format!("Failed to load analysis run: {}", e))),
+        }
+    }
+    if runs[0].repo_url != runs[1].repo_url {
+        return HttpResponse::BadRequest().json(error_response("Runs belong to different repositories".to_string()));
+    }
+    
+    let (findings_a, findings_b) = match (db.get_run_findings(&query.run_a), db.get_run_findings(&query.run_b)) {
+        (Ok(a), Ok(b)) => (a, b),
+        (Err(e), _) | (_, Err(e)) => {
+            return HttpResponse::InternalServerError().json(error_response(format!("Failed to load findings: {}", e)));
+        }
+    };
+    
+    // Findings are matched across runs by fingerprint
+    let fingerprints_a: HashSet<&str> = findings_a.iter().map(|f| f.fingerprint.as_str()).collect();
+    let fingerprints_b: HashSet<&str> = findings_b.iter().map(|f| f.fingerprint.as_str()).collect();
+    let new_findings: Vec<CodeBug> = findings_b.iter()
+        .filter(|f| !fingerprints_a.contains(f.fingerprint.as_str()))
+        .cloned()
+        .collect();
+    let resolved_findings: Vec<CodeBug> = findings_a.iter()
+        .filter(|f| !fingerprints_b.contains(f.fingerprint.as_str()))
+        .cloned()
+        .collect();
+    let unchanged_count = fingerprints_a.intersection(&fingerprints_b).count();
+    
+    HttpResponse::Ok().json(CompareResponse {
+        success: true,
+        message: format!("{} new, {} resolved, {} unchanged", new_findings.len(), resolved_findings.len(), unchanged_count),
+        run_a: query.run_a.clone(),
+        run_b: query.run_b.clone(),
+        commit_a: runs[0].commit_sha.clone(),
+        commit_b: runs[1].commit_sha.clone(),
+        new_findings: Some(new_findings),
+        resolved_findings: Some(resolved_findings),
+        unchanged_count: Some(unchanged_count),
+    })
+}
+
+// A run's findings a page at a time, for analyses too large to render from one response
+#[get("/api/analyses/{run_id}/findings")]
+async fn list_findings(path: web::Path<String>, query: web::Query<FindingsQuery>, caller: Caller, db: web::Data<Database>) -> impl Responder {
+    let run_id = path.into_inner();
+    let error_response = |message: String| FindingsResponse {
+        success: false,
+        message,
+        run_id: run_id.clone(),
+        findings: None,
+        total: None,
+        page: None,
+        per_page: None,
+        next_cursor: None,
+    };
+    
+    let run = match db.get_analysis_run(&caller.tenant, &run_id) {
+        Ok(Some(run)) => run,
+        Ok(None) => return HttpResponse::NotFound().json(error_response(format!("Analysis run not found: {}", run_id))),
+        Err(e) => return HttpResponse::InternalServerError().json(error_response(format!("Failed to load analysis run: {}", e))),
+    };
+    let cursor = match query.cursor.as_deref().map(FindingsCursor::decode) {
+        Some(None) => return HttpResponse::BadRequest().json(error_response("Invalid cursor".to_string())),
+        Some(Some(cursor)) if cursor.sort != query.sort || cursor.order != query.order() => {
+            return HttpResponse::BadRequest().json(error_response("Cursor was issued for a different sort".to_string()));
+        },
+        Some(cursor) => cursor,
+        None => None,
+    };
+    
+    let listed = db.list_run_findings(&run_id, &query, cursor.as_ref())
+        .and_then(|listed| Ok((listed, db.list_finding_assignees(&caller.tenant, &run.repo_url)?)));
+    match listed {
+        Ok(((mut findings, total, next_cursor), assignees)) => {
+            let assignees: HashMap<String, String> = assignees.into_iter().collect();
+            for finding in findings.iter_mut() {
+                finding.assignee = assignees.get(&finding.fingerprint).cloned();
+            }
+            HttpResponse::Ok().json(FindingsResponse {
+                success: true,
+                message: format!("Found {} findings", total),
+                run_id: run_id.clone(),
+                findings: Some(findings),
+                total: Some(total),
+                page: if cursor.is_none() { Some(query.page()) } else { None },
+                per_page: Some(query.per_page()),
+                next_cursor: next_cursor.map(|cursor| cursor.encode()),
+            })
+        },
+        Err(e) => HttpResponse::InternalServerError().json(error_response(format!("Failed to load findings: {}", e))),
+    }
+}
+
+#[post("/api/triage")]
+async fn set_triage(triage_request: Valid<TriageRequest>, caller: Caller, db: web::Data<Database>) -> impl Responder {
+    let repo_url = triage_request.repo_url.canonical();
+    let audit = AuditEvent::start(&caller, "triage.set")
+        .target(repo_url.clone())
+        .params(json!({
+            "fingerprint": triage_request.fingerprint,
+            "state": triage_request.state,
+            "comment": triage_request.comment,
+        }));
+    
+    match db.set_triage(&caller.tenant, &repo_url, &triage_request.fingerprint, triage_request.state, triage_request.comment.as_deref()) {
+        Ok(triage) => {
+            let message = format!("Finding {} marked as {}", triage.fingerprint, triage.state.as_str());
+            audit.finish(&db, true, &message);
+            HttpResponse::Ok().json(TriageResponse {
+                success: true,
+                message,
+                triage: Some(vec![triage]),
+            })
+        },
+        Err(e) => {
+            let message = format!("Failed to save triage state: {}", e);
+            audit.finish(&db, false, &message);
+            HttpResponse::InternalServerError().json(TriageResponse {
+                success: false,
+                message,
+                triage: None,
+            })
+        }
+    }
+}
+
+#[get("/api/triage")]
+async fn list_triage(query: web::Query<TriageListQuery>, caller: Caller, db: web::Data<Database>) -> impl Responder {
+    let repo_url = query.repo_url.canonical();
+    
+    match db.list_triage(&caller.tenant, &repo_url) {
+        Ok(triage) => {
+            HttpResponse::Ok().json(TriageResponse {
+                success: true,
+                message: format!("Found {} triaged findings", triage.len()),
+                triage: Some(triage),
+            })
+        },
+        Err(e) => {
+            HttpResponse::InternalServerError().json(TriageResponse {
+                success: false,
+                message: format!("Failed to load triage state: {}", e),
+                triage: None,
+            })
+        }
+    }
+}
+
+#[post("/api/findings/comments")]
+async fn add_finding_comment(comment_request: Valid<FindingCommentRequest>, caller: Caller, db: web::Data<Database>) -> impl Responder {
+    let repo_url = comment_request.repo_url.canonical();
+    let audit = AuditEvent::start(&caller, "finding.comment")
+        .target(repo_url.clone())
+        .params(json!({ "fingerprint": comment_request.fingerprint }));
+    
+    let added = db.add_finding_comment(&caller.tenant, &repo_url, &comment_request.fingerprint, &caller.actor, &comment_request.body)
+        .and_then(|_| db.list_finding_threads(&caller.tenant, &repo_url, Some(&comment_request.fingerprint)));
+    match added {
+        Ok(threads) => {
+            let message = format!("Commented on finding {}", comment_request.fingerprint);
+            audit.finish(&db, true, &message);
+            HttpResponse::Ok().json(FindingThreadsResponse {
+                success: true,
+                message,
+                threads: Some(threads),
+            })
+        },
+        Err(e) => {
+            let message = format!("Failed to save comment: {}", e);
+            audit.finish(&db, false, &message);
+            HttpResponse::InternalServerError().json(FindingThreadsResponse {
+                success: false,
+                message,
+                threads: None,
+            })
+        }
+    }
+}
+
+#[get("/api/findings/comments")]
+async fn list_finding_comments(query: web::Query<FindingCommentsQuery>, caller: Caller, db: web::Data<Database>) -> impl Responder {
+    let repo_url = query.repo_url.canonical();
+    
+    match db.list_finding_threads(&caller.tenant, &repo_url, query.fingerprint.as_deref()) {
+        Ok(threads) => {
+            let open = threads.iter().filter(|thread| !thread.resolved).count();
+            HttpResponse::Ok().json(FindingThreadsResponse {
+                success: true,
+                message: format!("Found {} threads ({} open)", threads.len(), open),
+                threads: Some(threads),
+            })
+        },
+        Err(e) => {
+            HttpResponse::InternalServerError().json(FindingThreadsResponse {
+                success: false,
+                message: format!("Failed to load comments: {}", e),
+                threads: None,
+            })
+        }
+    }
+}
+
+#[post("/api/findings/comments/resolve")]
+async fn resolve_finding_thread(resolve_request: Valid<ResolveThreadRequest>, caller: Caller, db: web::Data<Database>) -> impl Responder {
+    let repo_url = resolve_request.repo_url.canonical();
+    let action = if resolve_request.reopen { "finding.reopen" } else { "finding.resolve" };
+    let audit = AuditEvent::start(&caller, action)
+        .target(repo_url.clone())
+        .params(json!({ "fingerprint": resolve_request.fingerprint }));
+    let failure = |message: String| FindingThreadsResponse { success: false, message, threads: None };
+    
+    let resolved_by = (!resolve_request.reopen).then_some(caller.actor.as_str());
+    match db.set_thread_resolved(&caller.tenant, &repo_url, &resolve_request.fingerprint, resolved_by) {
+        Ok(true) => {},
+        Ok(false) => {
+            let message = format!("Finding {} has no comments", resolve_request.fingerprint);
+            audit.finish(&db, false, &message);
+            return HttpResponse::NotFound().json(failure(message));
+        },
+        Err(e) => {
+            let message = format!("Failed to update thread: {}", e);
+            audit.finish(&db, false, &message);
+            return HttpResponse::InternalServerError().json(failure(message));
+        }
+    }
+    let message = format!("Thread on finding {} {}", resolve_request.fingerprint, if resolve_request.reopen { "reopened" } else { "resolved" });
+    audit.finish(&db, true, &message);
+    match db.list_finding_threads(&caller.tenant, &repo_url, Some(&resolve_request.fingerprint)) {
+        Ok(threads) => HttpResponse::Ok().json(FindingThreadsResponse {
+            success: true,
+            message,
+            threads: Some(threads),
+        }),
+        Err(e) => HttpResponse::InternalServerError().json(failure(format!("Failed to load comments: {}", e))),
+    }
+}
+
+#[post("/api/findings/assign")]
+async fn assign_finding(assign_request: Valid<AssignFindingRequest>, caller: Caller, db: web::Data<Database>) -> impl Responder {
+    let repo_url = assign_request.repo_url.canonical();
+    let assignee = assign_request.assignee.as_deref().map(str::trim).filter(|assignee| !assignee.is_empty());
+    let action = if assignee.is_some() { "finding.assign" } else { "finding.unassign" };
+    let audit = AuditEvent::start(&caller, action)
+        .target(repo_url.clone())
+        .params(json!({
+            "fingerprint": assign_request.fingerprint,
+            "assignee": assignee,
+        }));
+    let failure = |message: String| FindingAssignmentsResponse { success: false, message, assignments: None };
+    
+    let Some(assignee) = assignee else {
+        return match db.unassign_finding(&caller.tenant, &repo_url, &assign_request.fingerprint) {
+            Ok(true) => {
+                let message = format!("Finding {} unassigned", assign_request.fingerprint);
+                audit.finish(&db, true, &message);
+                HttpResponse::Ok().json(FindingAssignmentsResponse {
+                    success: true,
+                    message,
+                    assignments: Some(Vec::new()),
+                })
+            },
+            Ok(false) => {
+                let message = format!("Finding {} isn't assigned", assign_request.fingerprint);
+                audit.finish(&db, false, &message);
+                HttpResponse::NotFound().json(failure(message))
+            },
+            Err(e) => {
+                let message = format!("Failed to unassign finding: {}", e);
+                audit.finish(&db, false, &message);
+                HttpResponse::InternalServerError().json(failure(message))
+            }
+        };
+    };
+    match db.assign_finding(&caller.tenant, &repo_url, &assign_request.fingerprint, assignee, &caller.actor) {
+        Ok(assignment) => {
+            let message = format!("Finding {} assigned to {}", assignment.fingerprint, assignment.assignee);
+            audit.finish(&db, true, &message);
+            HttpResponse::Ok().json(FindingAssignmentsResponse {
+                success: true,
+                message,
+                assignments: Some(vec![assignment]),
+            })
+        },
+        Err(e) => {
+            let message = format!("Failed to assign finding: {}", e);
+            audit.finish(&db, false, &message);
+            HttpResponse::InternalServerError().json(failure(message))
+        }
+    }
+}
+
+// The findings assigned to a team member, the caller by default
+#[get("/api/findings/assigned")]
+async fn list_assigned_findings(query: web::Query<AssignedFindingsQuery>, caller: Caller, db: web::Data<Database>) -> impl Responder {
+    let assignee = query.assignee.as_deref().map(str::trim).filter(|assignee| !assignee.is_empty()).unwrap_or(&caller.actor);
+    let repo_url = query.repo_url.as_ref().map(RepoUrl::canonical);
+    
+    match db.assigned_findings(&caller.tenant, assignee, repo_url.as_deref()) {
+        Ok(assignments) => {
+            let open = assignments.iter().filter(|assignment| assignment.finding.is_some()).count();
+            HttpResponse::Ok().json(FindingAssignmentsResponse {
+                success: true,
+                message: format!("Found {} findings assigned to {} ({} still reported)", assignments.len(), assignee, open),
+                assignments: Some(assignments),
+            })
+        },
+        Err(e) => {
+            HttpResponse::InternalServerError().json(FindingAssignmentsResponse {
+                success: false,
+                message: format!("Failed to load assigned findings: {}", e),
+                assignments: None,
+            })
+        }
+    }
+}
+
+// A run's report rendered again from its stored findings, with the discussion and assignees as
+// they stand now
+#[get("/api/analyses/{run_id}/report")]
+async fn export_run_report(path: web::Path<String>, caller: Caller, db: web::Data<Database>) -> impl Responder {
+    let run_id = path.into_inner();
+    let run = match db.get_analysis_run(&caller.tenant, &run_id) {
+        Ok(Some(run)) => run,
+        Ok(None) => return HttpResponse::NotFound().json(json!({ "success": false, "message": format!("Analysis run not found: {}", run_id) })),
+        Err(e) => return HttpResponse::InternalServerError().json(json!({ "success": false, "message": format!("Failed to load analysis run: {}", e) })),
+    };
+    let loaded = db.get_run_findings(&run_id).and_then(|mut bugs| {
+        let assignees: HashMap<String, String> = db.list_finding_assignees(&caller.tenant, &run.repo_url)?.into_iter().collect();
+        for bug in bugs.iter_mut() {
+            bug.assignee = assignees.get(&bug.fingerprint).cloned();
+        }
+        let discussion = db.list_finding_threads(&caller.tenant, &run.repo_url, None)?;
+        let anchors = match &run.commit_sha {
+            Some(sha) => db.list_report_anchors(&caller.tenant, &run.repo_url, sha)?,
+            None => Vec::new(),
+        };
+        Ok((bugs, discussion, anchors))
+    });
+    match loaded {
+        Ok((bugs, discussion, anchors)) => {
+            let context = ReportContext {
+                repo_url: run.repo_url,
+                commit_sha: run.commit_sha,
+                run_id: Some(run_id),
+                bugs,
+                deployment: None,
+                cpi_surface: None,
+                discussion,
+                anchors,
+            };
+            let template = tenant_template(&db, &caller.tenant);
+            HttpResponse::Ok().content_type("text/html; charset=utf-8").body(render_report(&context, template.as_deref()))
+        },
+        Err(e) => HttpResponse::InternalServerError().json(json!({ "success": false, "message": format!("Failed to load findings: {}", e) })),
+    }
+}
+
+// Findings of a run per vulnerability class, for the report's charts
+#[get("/api/analyses/{run_id}/taxonomy")]
+async fn run_taxonomy(path: web::Path<String>, caller: Caller, db: web::Data<Database>) -> impl Responder {
+    let run_id = path.into_inner();
+    let failure = |message: String| TaxonomyResponse { success: false, message, categories: None, uncategorized: None };
+    match db.get_analysis_run(&caller.tenant, &run_id) {
+        Ok(Some(_)) => {},
+        Ok(None) => return HttpResponse::NotFound().json(failure(format!("Analysis run not found: {}", run_id))),
+        Err(e) => return HttpResponse::InternalServerError().json(failure(format!("Failed to load analysis run: {}", e))),
+    }
+    match db.get_run_findings(&run_id) {
+        Ok(bugs) => {
+            let (categories, uncategorized) = taxonomy_counts(&bugs);
+            let total: u32 = categories.iter().map(|c| c.total).sum();
+            HttpResponse::Ok().json(TaxonomyResponse {
+                success: true,
+                message: format!("{} findings across {} vulnerability classes", total, categories.iter().filter(|c| c.total > 0).count()),
+                categories: Some(categories),
+                uncategorized: Some(uncategorized),
+            })
+        },
+        Err(e) => HttpResponse::InternalServerError().json(failure(format!("Failed to load findings: {}", e))),
+    }
+}
+
+#[get("/api/report-template")]
+async fn get_report_template(caller: Caller, db: web::Data<Database>) -> impl Responder {
+    match db.get_report_template(&caller.tenant) {
+        Ok(template) => HttpResponse::Ok().json(ReportTemplateResponse {
+            success: true,
+            message: match template {
+                Some(_) => "Report template fetched successfully".to_string(),
+                None => "No report template set; reports use the built-in layout".to_string(),
+            },
+            template,
+        }),
+        Err(e) => HttpResponse::InternalServerError().json(ReportTemplateResponse {
+            success: false,
+            message: format!("Failed to load report template: {}", e),
+            template: None,
+        }),
+    }
+}
+
+// The template is checked against a sample report before it's stored, so a broken one is
+// rejected here rather than at the end of an analysis
+#[put("/api/report-template")]
+async fn update_report_template(
+    template_request: Valid<ReportTemplateRequest>,
+    caller: Caller,
+    db: web::Data<Database>,
+) -> impl Responder {
+    let audit = AuditEvent::start(&caller, "report_template.update")
+        .params(json!({ "template_bytes": template_request.template.len() }));
+    match db.put_report_template(&caller.tenant, &template_request.template, &caller.actor)
+        .and_then(|_| db.get_report_template(&caller.tenant))
+    {
+        Ok(template) => {
+            audit.finish(&db, true, "Report template updated");
+            HttpResponse::Ok().json(ReportTemplateResponse {
+                success: true,
+                message: "Report template updated successfully".to_string(),
+                template,
+            })
+        },
+        Err(e) => {
+            let message = format!("Failed to update report template: {}", e);
+            audit.finish(&db, false, &message);
+            HttpResponse::InternalServerError().json(ReportTemplateResponse {
+                success: false,
+                message,
+                template: None,
+            })
+        }
+    }
+}
+
+// Back to the built-in report layout
+#[delete("/api/report-template")]
+async fn delete_report_template(caller: Caller, db: web::Data<Database>) -> impl Responder {
+    let audit = AuditEvent::start(&caller, "report_template.delete");
+    let failure = |message: String| ReportTemplateResponse { success: false, message, template: None };
+    match db.delete_report_template(&caller.tenant) {
+        Ok(true) => {
+            audit.finish(&db, true, "Report template removed");
+            HttpResponse::Ok().json(ReportTemplateResponse {
+                success: true,
+                message: "Report template removed; reports use the built-in layout".to_string(),
+                template: None,
+            })
+        },
+        Ok(false) => {
+            let message = "No report template set".to_string();
+            audit.finish(&db, false, &message);
+            HttpResponse::NotFound().json(failure(message))
+        },
+        Err(e) => {
+            let message = format!("Failed to remove report template: {}", e);
+            audit.finish(&db, false, &message);
+            HttpResponse::InternalServerError().json(failure(message))
+        }
+    }
+}
+
+#[post("/api/engagements")]
+async fn create_engagement(engagement_request: Valid<EngagementRequest>, caller: Caller, db: web::Data<Database>) -> impl Responder {
+    let audit = AuditEvent::start(&caller, "engagement.create")
+        .target(engagement_request.repo_url.canonical())
+        .params(json!({
+            "name": engagement_request.name,
+            "base_commit": engagement_request.base_commit,
+            "head_commit": engagement_request.head_commit,
+        }));
+    
+    match db.insert_engagement(&caller.tenant, &engagement_request, &caller.actor).and_then(|engagement| engagement_detail(&db, &caller.tenant, engagement)) {
+        Ok(engagement) => {
+            let message = format!("Engagement {} created", engagement.id);
+            audit.finish(&db, true, &message);
+            HttpResponse::Ok().json(EngagementResponse {
+                success: true,
+                message,
+                engagement: Some(engagement),
+            })
+        },
+        Err(e) => {
+            let message = format!("Failed to create engagement: {}", e);
+            audit.finish(&db, false, &message);
+            HttpResponse::InternalServerError().json(EngagementResponse {
+                success: false,
+                message,
+                engagement: None,
+            })
+        }
+    }
+}
+
+#[get("/api/engagements")]
+async fn list_engagements(query: web::Query<EngagementsQuery>, caller: Caller, db: web::Data<Database>) -> impl Responder {
+    let repo_url = query.repo_url.as_ref().map(RepoUrl::canonical);
+    match db.list_engagements(&caller.tenant, query.state, repo_url.as_deref()) {
+        Ok(engagements) => {
+            HttpResponse::Ok().json(EngagementsResponse {
+                success: true,
+                message: format!("Found {} engagements", engagements.len()),
+                engagements: Some(engagements),
+            })
+        },
+        Err(e) => {
+            HttpResponse::InternalServerError().json(EngagementsResponse {
+                success: false,
+                message: format!("Failed to load engagements: {}", e),
+                engagements: None,
+            })
+        }
+    }
+}
+
+// The engagement with its runs, their triage decisions and the final report's log
+fn engagement_detail(db: &Database, tenant: &str, mut engagement: Engagement) -> anyhow::Result<Engagement> {
+    engagement.runs = Some(db.list_engagement_runs(&engagement.id)?);
+    engagement.triage = Some(db.list_engagement_triage(tenant, &engagement.id, &engagement.repo_url)?);
+    engagement.report = match &engagement.report_log_id {
+        Some(id) => db.get_report_log(id)?.filter(|log| log.tenant == tenant),
+        None => None,
+    };
+    Ok(engagement)
+}
+
+#[get("/api/engagements/{engagement_id}")]
+async fn get_engagement(path: web::Path<String>, caller: Caller, db: web::Data<Database>) -> impl Responder {
+    let engagement_id = path.into_inner();
+    let failure = |message: String| EngagementResponse { success: false, message, engagement: None };
+    
+    match db.get_engagement(&caller.tenant, &engagement_id).and_then(|engagement| engagement.map(|e| engagement_detail(&db, &caller.tenant, e)).transpose()) {
+        Ok(Some(engagement)) => {
+            HttpResponse::Ok().json(EngagementResponse {
+                success: true,
+                message: format!("Engagement is {}", engagement.state.as_str()),
+                engagement: Some(engagement),
+            })
+        },
+        Ok(None) => HttpResponse::NotFound().json(failure(format!("Engagement not found: {}", engagement_id))),
+        Err(e) => HttpResponse::InternalServerError().json(failure(format!("Failed to load engagement: {}", e))),
+    }
+}
+
+// Rename it, change its commit range, link the final report or move it to another state.
+// Delivery needs a linked report, and anchoring needs that report's log confirmed on chain.
+#[patch("/api/engagements/{engagement_id}")]
+async fn update_engagement(path: web::Path<String>, update_request: Valid<EngagementUpdateRequest>, caller: Caller, db: web::Data<Database>) -> impl Responder {
+    let engagement_id = path.into_inner();
+    let audit = AuditEvent::start(&caller, "engagement.update")
+        .target(engagement_id.clone())
+        .params(json!({
+            "name": update_request.name,
+            "base_commit": update_request.base_commit,
+            "head_commit": update_request.head_commit,
+            "state": update_request.state,
+            "report_log_id": update_request.report_log_id,
+        }));
+    let failure = |message: String| EngagementResponse { success: false, message, engagement: None };
+    
+    let mut engagement = match db.get_engagement(&caller.tenant, &engagement_id) {
+        Ok(Some(engagement)) => engagement,
+        Ok(None) => {
+            let message = format!("Engagement not found: {}", engagement_id);
+            audit.finish(&db, false, &message);
+            return HttpResponse::NotFound().json(failure(message));
+        },
+        Err(e) => {
+            let message = format!("Failed to load engagement: {}", e);
+            audit.finish(&db, false, &message);
+            return HttpResponse::InternalServerError().json(failure(message));
+        }
+    };
+    let delivered = matches!(engagement.state, EngagementState::Delivered | EngagementState::AnchoredOnChain);
+    
+    if let Some(name) = &update_request.name {
+        engagement.name = name.trim().to_string();
+    }
+    if let Some(base_commit) = &update_request.base_commit {
+        engagement.base_commit = Some(base_commit.to_lowercase());
+    }
+    if let Some(head_commit) = &update_request.head_commit {
+        engagement.head_commit = Some(head_commit.to_lowercase());
+    }
+    
+    // The report has to be this tenant's, for the engagement's repository
+    let mut report = None;
+    if let Some(report_log_id) = update_request.report_log_id.as_deref().filter(|id| engagement.report_log_id.as_deref() != Some(*id)) {
+        if delivered {
+            let message = format!("Engagement is already {}; its report can't change", engagement.state.as_str());
+            audit.finish(&db, false, &message);
+            return HttpResponse::Conflict().json(failure(message));
+        }
+        match db.get_report_log(report_log_id) {
+            Ok(Some(log)) if log.tenant == caller.tenant => {
+                let repo_url = log.result.as_ref().and_then(|result| result["repo_url"].as_str());
+                if repo_url != Some(engagement.repo_url.as_str()) {
+                    let message = format!("Report log {} is for another repository", report_log_id);
+                    audit.finish(&db, false, &message);
+                    return HttpResponse::UnprocessableEntity().json(failure(message));
+                }
+                engagement.report_log_id = Some(report_log_id.to_string());
+                report = Some(log);
+            },
+            Ok(_) => {
+                let message = format!("Report log not found: {}", report_log_id);
+                audit.finish(&db, false, &message);
+                return HttpResponse::NotFound().json(failure(message));
+            },
+            Err(e) => {
+                let message = format!("Failed to load report log: {}", e);
+                audit.finish(&db, false, &message);
+                return HttpResponse::InternalServerError().json(failure(message));
+            }
+        }
+    }
+    
+    if let Some(state) = update_request.state.filter(|state| *state != engagement.state) {
+        if !engagement.state.can_move_to(state) {
+            let message = format!("Engagement can't move from {} to {}", engagement.state.as_str(), state.as_str());
+            audit.finish(&db, false, &message);
+            return HttpResponse::Conflict().json(failure(message));
+        }
+        if report.is_none() {
+            report = match engagement.report_log_id.as_deref().map(|id| db.get_report_log(id)).transpose() {
+                Ok(log) => log.flatten(),
+                Err(e) => {
+                    let message = format!("Failed to load report log: {}", e);
+                    audit.finish(&db, false, &message);
+                    return HttpResponse::InternalServerError().json(failure(message));
+                }
+            };
+        }
+        let unmet = match (state, &report) {
+            (EngagementState::Delivered, None) => Some("a final report must be linked before delivery".to_string()),
+            (EngagementState::AnchoredOnChain, None) => Some("no final report is linked".to_string()),
+            (EngagementState::AnchoredOnChain, Some(log)) if !matches!(log.status.as_str(), "confirmed" | "finalized") => {
+                Some(format!("the report's log is {}, not yet confirmed on chain", log.status))
+            },
+            _ => None,
+        };
+        if let Some(unmet) = unmet {
+            let message = format!("Engagement can't be {}: {}", state.as_str(), unmet);
+            audit.finish(&db, false, &message);
+            return HttpResponse::Conflict().json(failure(message));
+        }
+        engagement.state = state;
+    }
+    
+    match db.update_engagement(&caller.tenant, &mut engagement).and_then(|_| engagement_detail(&db, &caller.tenant, engagement)) {
+        Ok(engagement) => {
+            let message = format!("Engagement {} updated; it is {}", engagement.id, engagement.state.as_str());
+            audit.finish(&db, true, &message);
+            HttpResponse::Ok().json(EngagementResponse {
+                success: true,
+                message,
+                engagement: Some(engagement),
+            })
+        },
+        Err(e) => {
+            let message = format!("Failed to update engagement: {}", e);
+            audit.finish(&db, false, &message);
+            HttpResponse::InternalServerError().json(failure(message))
+        }
+    }
+}
+
+#[delete("/api/engagements/{engagement_id}")]
+async fn delete_engagement(path: web::Path<String>, caller: Caller, db: web::Data<Database>) -> impl Responder {
+    let engagement_id = path.into_inner();
+    let audit = AuditEvent::start(&caller, "engagement.delete").target(engagement_id.clone());
+    let failure = |message: String| EngagementResponse { success: false, message, engagement: None };
+    
+    match db.delete_engagement(&caller.tenant, &engagement_id) {
+        Ok(true) => {
+            let message = format!("Engagement {} deleted", engagement_id);
+            audit.finish(&db, true, &message);
+            HttpResponse::Ok().json(EngagementResponse {
+                success: true,
+                message,
+                engagement: None,
+            })
+        },
+        Ok(false) => {
+            let message = format!("Engagement not found: {}", engagement_id);
+            audit.finish(&db, false, &message);
+            HttpResponse::NotFound().json(failure(message))
+        },
+        Err(e) => {
+            let message = format!("Failed to delete engagement: {}", e);
+            audit.finish(&db, false, &message);
+            HttpResponse::InternalServerError().json(failure(message))
+        }
+    }
+}
+
+// Add an analysis run of the engagement's repository, or one of the tenant's queued fuzz jobs
+#[post("/api/engagements/{engagement_id}/runs")]
+async fn add_engagement_run(
+    path: web::Path<String>,
+    run_request: Valid<EngagementRunRequest>,
+    caller: Caller,
+    token: RequestToken,
+    queue: Option<web::Data<JobQueue>>,
+    db: web::Data<Database>,
+) -> impl Responder {
+    let engagement_id = path.into_inner();
+    let audit = AuditEvent::start(&caller, "engagement.add_run")
+        .target(engagement_id.clone())
+        .params(json!({ "kind": run_request.kind, "run_id": run_request.run_id }));
+    let failure = |message: String| EngagementResponse { success: false, message, engagement: None };
+    
+    let engagement = match db.get_engagement(&caller.tenant, &engagement_id) {
+        Ok(Some(engagement)) => engagement,
+        Ok(None) => {
+            let message = format!("Engagement not found: {}", engagement_id);
+            audit.finish(&db, false, &message);
+            return HttpResponse::NotFound().json(failure(message));
+        },
+        Err(e) => {
+            let message = format!("Failed to load engagement: {}", e);
+            audit.finish(&db, false, &message);
+            return HttpResponse::InternalServerError().json(failure(message));
+        }
+    };
+    if matches!(engagement.state, EngagementState::Delivered | EngagementState::AnchoredOnChain) {
+        let message = format!("Engagement is already {}; its runs can't change", engagement.state.as_str());
+        audit.finish(&db, false, &message);
+        return HttpResponse::Conflict().json(failure(message));
+    }
+    
+    // Analysis runs must be of the engagement's repository and within its commit range. Fuzz
+    // runs must be finished, and keep their results here since the job itself expires.
+    let mut analyzed_commit = None;
+    let mut fuzz_result = None;
+    let found = match run_request.kind {
+        EngagementRunKind::Analysis => db.get_analysis_run(&caller.tenant, &run_request.run_id)
+            .map(|run| run.map(|run| {
+                analyzed_commit = run.commit_sha;
+                match run.repo_url == engagement.repo_url {
+                    true => None,
+                    false => Some(format!("Analysis run {} is of another repository", run_request.run_id)),
+                }
+            })),
+        EngagementRunKind::Fuzz => {
+            let Some(queue) = &queue else {
+                let message = "Job queue is not configured; fuzz runs are linked by job id".to_string();
+                audit.finish(&db, false, &message);
+                return HttpResponse::ServiceUnavailable().json(failure(message));
+            };
+            queue.get(&run_request.run_id).await.map(|job| job
+                .filter(|job| job.tenant == caller.tenant)
+                .map(|job| {
+                    if !matches!(JobKind::parse(&job.kind), Some(JobKind::Fuzz | JobKind::RegressionFuzz)) {
+                        return Some(format!("Job {} isn't a fuzz job", run_request.run_id));
+                    }
+                    if job.status != "completed" {
+                        return Some(format!("Fuzz job {} is {}; only completed runs can be added", run_request.run_id, job.status));
+                    }
+                    fuzz_result = job.result;
+                    None
+                }))
+        },
+    };
+    match found {
+        Ok(Some(None)) => {},
+        Ok(Some(Some(message))) => {
+            audit.finish(&db, false, &message);
+            return HttpResponse::UnprocessableEntity().json(failure(message));
+        },
+        Ok(None) => {
+            let message = format!("Run not found: {}", run_request.run_id);
+            audit.finish(&db, false, &message);
+            return HttpResponse::NotFound().json(failure(message));
+        },
+        Err(e) => {
+            let message = format!("Failed to load run: {}", e);
+            audit.finish(&db, false, &message);
+            return HttpResponse::InternalServerError().json(failure(message));
+        }
+    }
+    
+    if run_request.kind == EngagementRunKind::Analysis && (engagement.base_commit.is_some() || engagement.head_commit.is_some()) {
+        let full_name = RepoUrl::parse(&engagement.repo_url).ok()
+            .filter(|url| url.is_github())
+            .and_then(|url| url.owner_repo().map(|(owner, repo)| format!("{}/{}", owner, repo)));
+        let problem = match (analyzed_commit.as_deref(), full_name) {
+            (None, _) => Some(format!("Analysis run {} has no commit to check against the engagement's range", run_request.run_id)),
+            (Some(commit), _) if engagement.base_commit.as_deref() == Some(commit) || engagement.head_commit.as_deref() == Some(commit) => None,
+            (Some(_), None) => Some("Commit ranges can only be checked on GitHub; add runs of the base or head commit".to_string()),
+            (Some(commit), Some(full_name)) => {
+                let github_client = GitHubClient::new().with_request_token(&token);
+                match github_client.commit_in_range(&full_name, engagement.base_commit.as_deref(), engagement.head_commit.as_deref(), commit).await {
+                    Ok(true) => None,
+                    Ok(false) => Some(format!(
+                        "Analysis run {} is of commit {}, outside the engagement's range {}..{}",
+                        run_request.run_id,
+                        commit,
+                        engagement.base_commit.as_deref().unwrap_or(""),
+                        engagement.head_commit.as_deref().unwrap_or(""),
+                    )),
+                    Err(e) => Some(format!("Failed to compare commits: {}", e)),
+                }
+            },
+        };
+        if let Some(message) = problem {
+            audit.finish(&db, false, &message);
+            return HttpResponse::UnprocessableEntity().json(failure(message));
+        }
+    }
+    
+    let added = db.add_engagement_run(&engagement.id, run_request.kind, &run_request.run_id, fuzz_result.as_ref(), &caller.actor)
+        .and_then(|added| Ok((added, engagement_detail(&db, &caller.tenant, engagement)?)));
+    match added {
+        Ok((added, engagement)) => {
+            let message = match added {
+                true => format!("Run {} added to engagement {}", run_request.run_id, engagement.id),
+                false => format!("Run {} is already part of engagement {}", run_request.run_id, engagement.id),
+            };
+            audit.finish(&db, true, &message);
+            HttpResponse::Ok().json(EngagementResponse {
+                success: true,
+                message,
+                engagement: Some(engagement),
+            })
+        },
+        Err(e) => {
+            let message = format!("Failed to add run: {}", e);
+            audit.finish(&db, false, &message);
+            HttpResponse::InternalServerError().json(failure(message))
+        }
+    }
+}
+
+#[delete("/api/engagements/{engagement_id}/runs/{run_id}")]
+async fn remove_engagement_run(path: web::Path<(String, String)>, caller: Caller, db: web::Data<Database>) -> impl Responder {
+    let (engagement_id, run_id) = path.into_inner();
+    let audit = AuditEvent::start(&caller, "engagement.remove_run")
+        .target(engagement_id.clone())
+        .params(json!({ "run_id": run_id }));
+    let failure = |message: String| EngagementResponse { success: false, message, engagement: None };
+    
+    let engagement = match db.get_engagement(&caller.tenant, &engagement_id) {
+        Ok(Some(engagement)) => engagement,
+        Ok(None) => {
+            let message = format!("Engagement not found: {}", engagement_id);
+            audit.finish(&db, false, &message);
+            return HttpResponse::NotFound().json(failure(message));
+        },
+        Err(e) => {
+            let message = format!("Failed to load engagement: {}", e);
+            audit.finish(&db, false, &message);
+            return HttpResponse::InternalServerError().json(failure(message));
+        }
+    };
+    if matches!(engagement.state, EngagementState::Delivered | EngagementState::AnchoredOnChain) {
+        let message = format!("Engagement is already {}; its runs can't change", engagement.state.as_str());
+        audit.finish(&db, false, &message);
+        return HttpResponse::Conflict().json(failure(message));
+    }
+    
+    let removed = db.remove_engagement_run(&engagement.id, &run_id)
+        .and_then(|removed| Ok((removed, engagement_detail(&db, &caller.tenant, engagement)?)));
+    match removed {
+        Ok((true, engagement)) => {
+            let message = format!("Run {} removed from engagement {}", run_id, engagement.id);
+            audit.finish(&db, true, &message);
+            HttpResponse::Ok().json(EngagementResponse {
+                success: true,
+                message,
+                engagement: Some(engagement),
+            })
+        },
+        Ok((false, engagement)) => {
+            let message = format!("Run {} isn't part of engagement {}", run_id, engagement.id);
+            audit.finish(&db, false, &message);
+            HttpResponse::NotFound().json(failure(message))
+        },
+        Err(e) => {
+            let message = format!("Failed to remove run: {}", e);
+            audit.finish(&db, false, &message);
+            HttpResponse::InternalServerError().json(failure(message))
+        }
+    }
+}
+
+#[get("/api/email-settings")]
+async fn get_email_settings(caller: Caller, db: web::Data<Database>) -> impl Responder {
+    match db.get_email_settings(&caller.tenant) {
+        Ok(settings) => {
+            HttpResponse::Ok().json(EmailSettingsResponse {
+                success: true,
+                message: "Email settings fetched successfully".to_string(),
+                settings: Some(settings),
+            })
+        },
+        Err(e) => {
+            HttpResponse::InternalServerError().json(EmailSettingsResponse {
+                success: false,
+                message: format!("Failed to load email settings: {}", e),
+                settings: None,
+            })
+        }
+    }
+}
+
+#[put("/api/email-settings")]
+async fn update_email_settings(
+    settings_request: Valid<EmailSettingsRequest>,
+    caller: Caller,
+    db: web::Data<Database>,
+) -> impl Responder {
+    let audit = AuditEvent::start(&caller, "email_settings.update")
+        .params(json!({
+            "recipients": settings_request.recipients,
+            "subject_template": settings_request.subject_template,
+            "body_template": settings_request.body_template,
+        }));
+    for email in settings_request.recipients.iter().flatten() {
+        if email.trim().parse::<lettre::message::Mailbox>().is_err() {
+            let message = format!("Invalid email address: {}", email);
+            audit.finish(&db, false, &message);
+            return HttpResponse::BadRequest().json(EmailSettingsResponse {
+                success: false,
+                message,
+                settings: None,
+            });
+        }
+    }
+    
+    match db.update_email_settings(&caller.tenant, &settings_request).and_then(|_| db.get_email_settings(&caller.tenant)) {
+        Ok(settings) => {
+            audit.finish(&db, true, "Email settings updated");
+            HttpResponse::Ok().json(EmailSettingsResponse {
+                success: true,
+                message: "Email settings updated successfully".to_string(),
+                settings: Some(settings),
+            })
+        },
+        Err(e) => {
+            let message = format!("Failed to update email settings: {}", e);
+            audit.finish(&db, false, &message);
+            HttpResponse::InternalServerError().json(EmailSettingsResponse {
+                success: false,
+                message,
+                settings: None,
+            })
+        }
+    }
+}
+
+// Linked from report emails, so it answers with a small HTML page rather than JSON
+#[get("/api/email/unsubscribe")]
+async fn unsubscribe_email(query: web::Query<UnsubscribeQuery>, db: web::Data<Database>) -> impl Responder {
+    match db.unsubscribe_email(&query.token) {
+        Ok(true) => HttpResponse::Ok()
+            .content_type("text/html; charset=utf-8")
+            .body("<p>You have been unsubscribed from Safex report emails.</p>"),
+        Ok(false) => HttpResponse::NotFound()
+            .content_type("text/html; charset=utf-8")
+            .body("<p>This unsubscribe link is invalid or has already been used.</p>"),
+        Err(e) => HttpResponse::InternalServerError()
+            .content_type("text/html; charset=utf-8")
+            .body(format!("<p>Failed to unsubscribe: {}</p>", report::escape_html(&e.to_string()))),
+    }
+}
+
+// Generates an SSH key for the repository; the public half is returned for the user to add
+// as a read-only deploy key on GitHub, and queued scans clone with it from then on
+#[post("/api/deploy-keys")]
+async fn create_deploy_key(
+    key_request: Valid<DeployKeyRequest>,
+    caller: Caller,
+    db: web::Data<Database>,
+    deploy_keys: Option<web::Data<DeployKeys>>,
+) -> impl Responder {
+    let Some(deploy_keys) = deploy_keys else {
+        return HttpResponse::ServiceUnavailable().json(DeployKeyResponse {
+            success: false,
+            message: "SAFEX_DEPLOY_KEY_SECRET is not configured".to_string(),
+            deploy_keys: None,
+        });
+    };
+    let repo_url = key_request.repo_url.canonical();
+    let audit = AuditEvent::start(&caller, "deploy_key.create").target(repo_url.clone());
+    
+    let stored = deploy_keys.generate(&format!("safex@{}", key_request.repo_url.name()))
+        .and_then(|key| Ok((deploy_keys.seal(&key.private_key)?, key)))
+        .and_then(|(sealed, key)| db.put_deploy_key(&caller.tenant, &repo_url, &key, &sealed, &caller.actor));
+    match stored {
+        Ok(deploy_key) => {
+            audit.finish(&db, true, format!("Registered deploy key {}", deploy_key.fingerprint));
+            HttpResponse::Created().json(DeployKeyResponse {
+                success: true,
+                message: format!("Add this public key as a deploy key on {}", key_request.repo_url),
+                deploy_keys: Some(vec![deploy_key]),
+            })
+        },
+        Err(e) => {
+            let message = format!("Failed to create deploy key: {}", e);
+            audit.finish(&db, false, &message);
+            HttpResponse::InternalServerError().json(DeployKeyResponse {
+                success: false,
+                message,
+                deploy_keys: None,
+            })
+        }
+    }
+}
+
+#[get("/api/deploy-keys")]
+async fn list_deploy_keys(caller: Caller, db: web::Data<Database>) -> impl Responder {
+    match db.list_deploy_keys(&caller.tenant) {
+        Ok(keys) => {
+            HttpResponse::Ok().json(DeployKeyResponse {
+                success: true,
+                message: format!("Found {} deploy keys", keys.len()),
+                deploy_keys: Some(keys),
+            })
+        },
+        Err(e) => {
+            HttpResponse::InternalServerError().json(DeployKeyResponse {
+                success: false,
+                message: format!("Failed to list deploy keys: {}", e),
+                deploy_keys: None,
+            })
+        }
+    }
+}
+
+#[delete("/api/deploy-keys")]
+async fn delete_deploy_key(query: web::Query<DeployKeyRequest>, caller: Caller, db: web::Data<Database>) -> impl Responder {
+    let repo_url = query.repo_url.canonical();
+    let audit = AuditEvent::start(&caller, "deploy_key.delete").target(repo_url.clone());
+    match db.delete_deploy_key(&caller.tenant, &repo_url) {
+        Ok(true) => {
+            audit.finish(&db, true, "Deleted deploy key");
+            HttpResponse::Ok().json(DeployKeyResponse {
+                success: true,
+                message: format!("Deleted deploy key for {}; remove it on GitHub as well", repo_url),
+                deploy_keys: None,
+            })
+        },
+        Ok(false) => {
+            audit.finish(&db, false, "No deploy key registered");
+            HttpResponse::NotFound().json(DeployKeyResponse {
+                success: false,
+                message: format!("No deploy key registered for {}", repo_url),
+                deploy_keys: None,
+            })
+        },
+        Err(e) => {
+            let message = format!("Failed to delete deploy key: {}", e);
+            audit.finish(&db, false, &message);
+            HttpResponse::InternalServerError().json(DeployKeyResponse {
+                success: false,
+                message,
+                deploy_keys: None,
+            })
+        }
+    }
+}
+
+#[post("/api/ecosystem/projects")]
+async fn register_ecosystem_project(
+    project_request: Valid<EcosystemProjectRequest>,
+    caller: Caller,
+    db: web::Data<Database>,
+    ecosystem: Option<web::Data<Ecosystem>>,
+) -> impl Responder {
+    if ecosystem.is_none() {
+        return HttpResponse::ServiceUnavailable().json(EcosystemProjectsResponse {
+            success: false,
+            message: "SAFEX_ECOSYSTEM_SCAN_INTERVAL_SECS is not configured".to_string(),
+            projects: None,
+        });
+    }
+    let repo_url = project_request.repo_url.canonical();
+    let audit = AuditEvent::start(&caller, "ecosystem.register").target(repo_url.clone());
+    
+    // Scans clone without credentials, and the statistics are public
+    if !project_request.repo_url.is_github() {
+        audit.finish(&db, false, "Not a GitHub repository");
+        return HttpResponse::BadRequest().json(EcosystemProjectsResponse {
+            success: false,
+            message: "Only public GitHub repositories can be registered".to_string(),
+            projects: None,
+        });
+    }
+    
+    match db.register_ecosystem_project(&repo_url, &caller.tenant) {
+        Ok(registered) => {
+            let message = if registered { format!("Registered {} for the ecosystem scan", repo_url) } else { format!("{} is already registered", repo_url) };
+            audit.finish(&db, true, &message);
+            let status = if registered { StatusCode::CREATED } else { StatusCode::OK };
+            HttpResponse::build(status).json(EcosystemProjectsResponse {
+                success: true,
+                message,
+                projects: None,
+            })
+        },
+        Err(e) => {
+            let message = format!("Failed to register project: {}", e);
+            audit.finish(&db, false, &message);
+            HttpResponse::InternalServerError().json(EcosystemProjectsResponse {
+                success: false,
+                message,
+                projects: None,
+            })
+        }
+    }
+}
+
+#[get("/api/ecosystem/projects")]
+async fn list_ecosystem_projects(caller: Caller, db: web::Data<Database>) -> impl Responder {
+    match db.list_ecosystem_projects(Some(&caller.tenant)) {
+        Ok(projects) => {
+            HttpResponse::Ok().json(EcosystemProjectsResponse {
+                success: true,
+                message: format!("Found {} registered projects", projects.len()),
+                projects: Some(projects),
+            })
+        },
+        Err(e) => {
+            HttpResponse::InternalServerError().json(EcosystemProjectsResponse {
+                success: false,
+                message: format!("Failed to list projects: {}", e),
+                projects: None,
+            })
+        }
+    }
+}
+
+#[delete("/api/ecosystem/projects")]
+async fn delete_ecosystem_project(query: web::Query<EcosystemProjectRequest>, caller: Caller, db: web::Data<Database>) -> impl Responder {
+    let repo_url = query.repo_url.canonical();
+    let audit = AuditEvent::start(&caller, "ecosystem.unregister").target(repo_url.clone());
+    match db.delete_ecosystem_project(&repo_url, &caller.tenant) {
+        Ok(true) => {
+            audit.finish(&db, true, "Unregistered project");
+            HttpResponse::Ok().json(EcosystemProjectsResponse {
+                success: true,
+                message: format!("Removed {} from the ecosystem scan", repo_url),
+                projects: None,
+            })
+        },
+        Ok(false) => {
+            audit.finish(&db, false, "Project not registered by this tenant");
+            HttpResponse::NotFound().json(EcosystemProjectsResponse {
+                success: false,
+                message: format!("{} isn't registered by this tenant", repo_url),
+                projects: None,
+            })
+        },
+        Err(e) => {
+            let message = format!("Failed to unregister project: {}", e);
+            audit.finish(&db, false, &message);
+            HttpResponse::InternalServerError().json(EcosystemProjectsResponse {
+                success: false,
+                message,
+                projects: None,
+            })
+        }
+    }
+}
+
+// Anonymized aggregates over the registered projects' latest scans; public like /api/rules
+#[get("/api/ecosystem-stats")]
+async fn get_ecosystem_stats(req: HttpRequest, db: web::Data<Database>) -> impl Responder {
+    match db.list_ecosystem_projects(None) {
+        Ok(projects) => {
+            let stats = ecosystem_stats(&projects);
+            cached_json(&req, &EcosystemStatsResponse {
+                success: true,
+                message: format!("Statistics over {} scanned projects", stats.scanned_projects),
+                stats: Some(stats),
+            }, "public, max-age=3600")
+        },
+        Err(e) => {
+            HttpResponse::InternalServerError().json(EcosystemStatsResponse {
+                success: false,
+                message: format!("Failed to compute statistics: {}", e),
+                stats: None,
+            })
+        }
+    }
+}
+
+#[get("/api/artifacts/{key:.*}")]
+async fn download_artifact(path: web::Path<String>, caller: Caller, storage: web::Data<dyn Storage>, req: HttpRequest) -> impl Responder {
+    let key = path.into_inner();
+    if let Err(e) = validate_key(&key) {
+        return HttpResponse::BadRequest().json(json!({ "success": false, "message": e.to_string() }));
+    }
+    // Other tenants' artifacts are answered like missing ones
+    if !owned_by(&key, &caller.tenant) {
+        return HttpResponse::NotFound().json(json!({ "success": false, "message": "Artifact not found" }));
+    }
+    
+    match storage.get(&key).await {
+        // Artifact keys embed a run or fuzz id and are never rewritten, but belong to a tenant,
+        // so shared caches mustn't keep them
+        Ok(Some(data)) => cached_body(&req, content_type_for_key(&key), data, "private, max-age=31536000, immutable"),
+        Ok(None) => HttpResponse::NotFound().json(json!({ "success": false, "message": "Artifact not found" })),
+        Err(e) => HttpResponse::InternalServerError().json(json!({ "success": false, "message": format!("Failed to read artifact: {}", e) })),
+    }
+}
+
+#[post("/api/jobs/analyze")]
+async fn submit_analysis_job(
+    analysis_request: Valid<CodeAnalysisRequest>,
+    query: web::Query<JobSubmitQuery>,
+    caller: Caller,
+    token: RequestToken,
+    queue: Option<web::Data<JobQueue>>,
+) -> impl Responder {
+    submit_job(queue, JobKind::Analyze, query.priority, &caller, &token, json!(analysis_request.into_inner())).await
+}
+
+#[post("/api/jobs/fuzz")]
+async fn submit_fuzz_job(
+    fuzzing_request: Valid<FuzzingRequest>,
+    query: web::Query<JobSubmitQuery>,
+    caller: Caller,
+    token: RequestToken,
+    queue: Option<web::Data<JobQueue>>,
+) -> impl Responder {
+    let mut fuzzing_request = fuzzing_request.into_inner();
+    match fuzz_timeout(fuzzing_request.timeout_seconds, &caller.actor, true) {
+        Ok(timeout) => fuzzing_request.timeout_seconds = Some(timeout),
+        Err(e) => return HttpResponse::from_error(e),
+    }
+    if let Err(e) = check_fuzz_engine(&fuzzing_request) {
+        return HttpResponse::from_error(e);
+    }
+    submit_job(queue, JobKind::Fuzz, query.priority, &caller, &token, json!(fuzzing_request)).await
+}
+
+// Fuzz only what changed between two commits, e.g. the two sides of /api/compare
+#[post("/api/jobs/regression-fuzz")]
+async fn submit_regression_fuzz_job(
+    regression_request: Valid<RegressionFuzzRequest>,
+    query: web::Query<JobSubmitQuery>,
+    caller: Caller,
+    token: RequestToken,
+    queue: Option<web::Data<JobQueue>>,
+) -> impl Responder {
+    let mut regression_request = regression_request.into_inner();
+    match fuzz_timeout(regression_request.timeout_seconds, &caller.actor, true) {
+        Ok(timeout) => regression_request.timeout_seconds = Some(timeout),
+        Err(e) => return HttpResponse::from_error(e),
+    }
+    submit_job(queue, JobKind::RegressionFuzz, query.priority, &caller, &token, json!(regression_request)).await
+}
+
+async fn submit_job(queue: Option<web::Data<JobQueue>>, kind: JobKind, priority: JobPriority, caller: &Caller, token: &RequestToken, payload: serde_json::Value) -> HttpResponse {
+    // Nobody waits on a submitted job, so it mustn't jump ahead of those someone does
+    if priority == JobPriority::Interactive {
+        return HttpResponse::BadRequest().json(JobSubmitResponse {
+            success: false,
+            message: "Submitted jobs can't be interactive; use ci or scheduled, or run the request directly instead".to_string(),
+            job_id: None,
+        });
+    }
+    // Workers would need the token after this request ends, and it must never be stored
+    if token.as_deref().is_some() {
+        return HttpResponse::BadRequest().json(JobSubmitResponse {
+            success: false,
+            message: format!("{} can't be used with queued jobs; run the request directly instead", RequestToken::HEADER),
+            job_id: None,
+        });
+    }
+    let Some(queue) = queue else {
+        return HttpResponse::ServiceUnavailable().json(JobSubmitResponse {
+            success: false,
+            message: "Job queue is not configured".to_string(),
+            job_id: None,
+        });
+    };
+    
+    match queue.enqueue(kind, priority, &caller.tenant, &caller.actor, payload).await {
+        Ok(job_id) => {
+            HttpResponse::Accepted().json(JobSubmitResponse {
+                success: true,
+                message: format!("Queued {} job as {}", kind.as_str(), priority.as_str()),
+                job_id: Some(job_id),
+            })
+        },
+        Err(e) => {
+            HttpResponse::InternalServerError().json(JobSubmitResponse {
+                success: false,
+                message: format!("Failed to queue job: {}", e),
+                job_id: None,
+            })
+        }
+    }
+}
+
+#[get("/api/jobs/{job_id}")]
+async fn get_job(path: web::Path<String>, caller: Caller, queue: Option<web::Data<JobQueue>>, db: web::Data<Database>) -> impl Responder {
+    let job_id = path.into_inner();
+    
+    // Report logs are tracked in the database rather than the queue
+    match db.get_report_log(&job_id) {
+        Ok(Some(job)) if job.tenant == caller.tenant => {
+            return HttpResponse::Ok().json(JobStatusResponse {
+                success: true,
+                message: format!("Job is {}", job.status),
+                job: Some(job),
+            });
+        },
+        Ok(_) => {},
+        Err(e) => println!("Warning: Failed to look up report log {}: {}", job_id, e),
+    }
+    
+    let Some(queue) = queue else {
+        return HttpResponse::ServiceUnavailable().json(JobStatusResponse {
+            success: false,
+            message: "Job queue is not configured".to_string(),
+            job: None,
+        });
+    };
+    
+    match queue.get(&job_id).await {
+        // Jobs of other tenants are reported as missing
+        Ok(Some(job)) if job.tenant == caller.tenant => {
+            HttpResponse::Ok().json(JobStatusResponse {
+                success: true,
+                message: format!("Job is {}", job.status),
+                job: Some(job),
+            })
+        },
+        Ok(_) => {
+            HttpResponse::NotFound().json(JobStatusResponse {
+                success: false,
+                message: "Job not found".to_string(),
+                job: None,
+            })
+        },
+        Err(e) => {
+            HttpResponse::InternalServerError().json(JobStatusResponse {
+                success: false,
+                message: format!("Failed to load job: {}", e),
+                job: None,
+            })
+        }
+    }
+}
+
+// A queued job as server-sent events: an analysis's passes as they start (`progress`) and its
+// findings as each pass finishes (`findings`), then `done` with the job once it has finished. Event
+// IDs are positions, so an EventSource reconnecting with Last-Event-ID carries on after the
+// last one it saw. A retried job starts over, announced by `restarted`.
+#[get("/api/jobs/{job_id}/events")]
+async fn job_events(path: web::Path<String>, req: HttpRequest, caller: Caller, queue: Option<web::Data<JobQueue>>) -> impl Responder {
+    let job_id = path.into_inner();
+    let Some(queue) = queue else {
+        return HttpResponse::ServiceUnavailable().json(JobStatusResponse {
+            success: false,
+            message: "Job queue is not configured".to_string(),
+            job: None,
+        });
+    };
+    let attempts = match queue.get(&job_id).await {
+        Ok(Some(job)) if job.tenant == caller.tenant => job.attempts,
+        Ok(_) => {
+            return HttpResponse::NotFound().json(JobStatusResponse {
+                success: false,
+                message: "Job not found".to_string(),
+                job: None,
+            });
+        },
+        Err(e) => {
+            return HttpResponse::InternalServerError().json(JobStatusResponse {
+                success: false,
+                message: format!("Failed to load job: {}", e),
+                job: None,
+            });
+        }
+    };
+    let cursor = req.headers().get("Last-Event-ID")
+        .and_then(|id| id.to_str().ok())
+        .and_then(|id| id.parse::<usize>().ok())
+        .map_or(0, |id| id + 1);
+    let events = JobEvents { queue: queue.into_inner(), job_id, cursor, attempts, quiet_polls: 0, done: false };
+    let stream = futures_util::stream::unfold(events, |mut events| async move {
+        let chunk = events.next().await?;
+        Some((Ok::<_, actix_web::Error>(web::Bytes::from(chunk)), events))
+    });
+    HttpResponse::Ok()
+        .content_type("text/event-stream")
+        .insert_header((header::CACHE_CONTROL, "no-cache"))
+        // Compression would hold events back until enough of them pile up
+        .insert_header((header::CONTENT_ENCODING, "identity"))
+        .streaming(stream)
+}
+
+const JOB_EVENTS_POLL_INTERVAL: Duration = Duration::from_secs(1);
+// Quiet polls before a comment goes out, so proxies don't close an idle stream
+const JOB_EVENTS_KEEPALIVE_POLLS: u32 = 15;
+
+// Where a job's event stream has got to
+struct JobEvents {
+    queue: Arc<JobQueue>,
+    job_id: String,
+    cursor: usize,
+    attempts: u32,
+    quiet_polls: u32,
+    done: bool,
+}
+
+impl JobEvents {
+    // The next chunk of the stream; None once the finished job has been sent
+    async fn next(&mut self) -> Option<String> {
+        if self.done {
+            return None;
+        }
+        loop {
+            // The job is read before its events: the last events are written before it
+            // finishes, so a finished job's events are all there
+            let job = match self.queue.get(&self.job_id).await {
+                Ok(Some(job)) => job,
+                // Expired
+                Ok(None) => return None,
+                Err(e) => return Some(self.fail(e)),
+            };
+            if job.attempts != self.attempts {
+                self.attempts = job.attempts;
+                self.cursor = 0;
+                return Some(format!("event: restarted\ndata: {}\n\n", json!({ "attempts": job.attempts })));
+            }
+            match self.queue.events(&self.job_id, self.cursor).await {
+                Ok(events) if !events.is_empty() => {
+                    let mut chunk = String::new();
+                    for event in &events {
+                        let kind = serde_json::from_str::<serde_json::Value>(event).ok()
+                            .and_then(|event| event.get("type").and_then(|kind| kind.as_str()).map(str::to_string))
+                            .unwrap_or_else(|| "message".to_string());
+                        chunk.push_str(&format!("id: {}\nevent: {}\ndata: {}\n\n", self.cursor, kind, event));
+                        self.cursor += 1;
+                    }
+                    self.quiet_polls = 0;
+                    return Some(chunk);
+                },
+                Ok(_) => {},
+                Err(e) => return Some(self.fail(e)),
+            }
+            if job.status == "completed" || job.status == "failed" {
+                self.done = true;
+                return Some(format!("event: done\ndata: {}\n\n", serde_json::to_string(&job).unwrap_or_default()));
+            }
+            actix_web::rt::time::sleep(JOB_EVENTS_POLL_INTERVAL).await;
+            self.quiet_polls += 1;
+            if self.quiet_polls >= JOB_EVENTS_KEEPALIVE_POLLS {
+                self.quiet_polls = 0;
+                return Some(": keep-alive\n\n".to_string());
+            }
+        }
+    }
+
+    // Ends the stream; the client reconnects to carry on
+    fn fail(&mut self, e: anyhow::Error) -> String {
+        println!("Warning: Failed to stream events of job {}: {}", self.job_id, e);
+        self.done = true;
+        format!("event: error\ndata: {}\n\n", json!({ "message": format!("Failed to load job events: {}", e) }))
+    }
+}
+
+#[get("/api/workers")]
+async fn list_workers(queue: Option<web::Data<JobQueue>>) -> impl Responder {
+    let Some(queue) = queue else {
+        return HttpResponse::ServiceUnavailable().json(WorkersResponse {
+            success: false,
+            message: "Job queue is not configured".to_string(),
+            workers: None,
+        });
+    };
+    
+    match queue.workers().await {
+        Ok(workers) => {
+            HttpResponse::Ok().json(WorkersResponse {
+                success: true,
+                message: format!("{} live workers", workers.len()),
+                workers: Some(workers),
+            })
+        },
+        Err(e) => {
+            HttpResponse::InternalServerError().json(WorkersResponse {
+                success: false,
+                message: format!("Failed to list workers: {}", e),
+                workers: None,
+            })
+        }
+    }
+}
+
+// Run a leased job with the same code paths as the synchronous endpoints
+#[allow(clippy::too_many_arguments)]
+async fn execute_job(
+    job: Job,
+    db: web::Data<Database>,
+    mailer: web::Data<Mailer>,
+    storage: web::Data<dyn Storage>,
+    external: web::Data<ExternalAnalyzers>,
+    deploy_keys: Option<web::Data<DeployKeys>>,
+    toolchain_manager: web::Data<ToolchainManager>,
+    queue: Arc<JobQueue>,
+) -> anyhow::Result<serde_json::Value> {
+    // Clients polling the job see how far its clone has got, and operators the phase it's in
+    let clone_progress = queue.clone_progress_sink(&job.id);
+    let phases = PhaseTimer::reporting_to(queue.phase_sink(&job.id));
+    let events = queue.analysis_event_sink(&job.id);
+    match job.kind {
+        JobKind::Analyze => {
+            let request: CodeAnalysisRequest = serde_json::from_value(job.payload)?;
+            let audit = AuditEvent::for_actor(&job.tenant, &job.actor, "analysis.run")
+                .target(request.repo_url.canonical())
+                .params(json!({ "repo_url": request.repo_url.canonical(), "job_id": job.id }));
+            let github_client = job_github_client(&db, deploy_keys.as_ref().map(|keys| keys.get_ref()), &job.tenant, &request.repo_url).with_clone_progress(clone_progress);
+            let (status, response) = run_code_analysis(&request, github_client, &job.tenant, &db, &mailer, storage.get_ref(), &external, &toolchain_manager, phases, Some((&job.id, events))).await;
+            audit.finish(&db, response.success, &response.message);
+            if !status.is_success() {
+                return Err(anyhow::anyhow!(response.message));
+            }
+            Ok(serde_json::to_value(response)?)
+        },
+        JobKind::Fuzz => {
+            let request: FuzzingRequest = serde_json::from_value(job.payload)?;
+            let mut params = fuzz_audit_params(&request);
+            params["job_id"] = json!(job.id);
+            let audit = AuditEvent::for_actor(&job.tenant, &job.actor, "fuzz.run")
+                .target(request.repo_url.canonical())
+                .params(params);
+            let github_client = job_github_client(&db, deploy_keys.as_ref().map(|keys| keys.get_ref()), &job.tenant, &request.repo_url).with_clone_progress(clone_progress);
+            let checkpoints = queue.fuzz_checkpoint_sink(&job.id);
+            // Set when an earlier attempt at the job lost its lease after a checkpoint
+            let resumed = queue.get(&job.id).await?.and_then(|info| info.checkpoint);
+            let (status, response) = run_fuzz_test(&request, &job.tenant, github_client, &toolchain_manager, &db, storage.get_ref(), phases, Some((checkpoints, resumed))).await;
+            audit.finish(&db, response.success, &response.message);
+            if !status.is_success() {
+                return Err(anyhow::anyhow!(response.message));
+            }
+            Ok(serde_json::to_value(response)?)
+        },
+        JobKind::RegressionFuzz => {
+            let request: RegressionFuzzRequest = serde_json::from_value(job.payload.clone())?;
+            let audit = AuditEvent::for_actor(&job.tenant, &job.actor, "fuzz.regression")
+                .target(request.repo_url.canonical())
+                .params(json!({
+                    "repo_url": request.repo_url.canonical(),
+                    "base_commit": request.base_commit,
+                    "head_commit": request.head_commit,
+                    "job_id": job.id,
+                }));
+            let github_client = job_github_client(&db, deploy_keys.as_ref().map(|keys| keys.get_ref()), &job.tenant, &request.repo_url).with_clone_progress(clone_progress);
+            let plan = plan_regression_fuzz(&request, &job, github_client, &queue).await;
+            match &plan {
+                Ok(plan) => audit.finish(&db, true, format!("Queued fuzzing for {} changed instructions", plan.jobs.len())),
+                Err(e) => audit.finish(&db, false, e.to_string()),
+            }
+            Ok(serde_json::to_value(plan?)?)
+        }
+    }
+}
+
+// Queue a fuzz job, reusing the corpus, for each instruction whose code changed between the
+// two commits. The fuzz jobs carry the commits, so their results read as regression fuzzing.
+async fn plan_regression_fuzz(request: &RegressionFuzzRequest, job: &Job, github_client: GitHubClient, queue: &JobQueue) -> anyhow::Result<RegressionFuzzPlan> {
+    let temp_dir = TempDir::new()?;
+    github_client.clone_repo(&github_client.remote_url(&request.repo_url), temp_dir.path())?;
+    let changed = changed_instructions(temp_dir.path(), &request.base_commit, &request.head_commit)?;
+    println!("{} instructions changed in {} between {} and {}", changed.len(), request.repo_url, request.base_commit, request.head_commit);
+    
+    let regression = RegressionFuzz {
+        base_commit: request.base_commit.clone(),
+        head_commit: request.head_commit.clone(),
+        planned_by: job.id.clone(),
+    };
+    let mut jobs = Vec::new();
+    for instruction_name in &changed {
+        let fuzzing_request = FuzzingRequest {
+            repo_url: request.repo_url.clone(),
+            instruction_name: Some(instruction_name.clone()),
+            timeout_seconds: request.timeout_seconds,
+            continuous: false,
+            network_policy: request.network_policy,
+            vendor_dependencies: request.vendor_dependencies,
+            commit: Some(request.head_commit.clone()),
+            reuse_corpus: true,
+            strategy: FuzzStrategy::Inputs,
+            engine: FuzzEngine::Proptest,
+            compute_unit_budget: None,
+            seed_accounts: Default::default(),
+            cluster: Cluster::default(),
+        };
+        let job_id = queue.enqueue_regression_fuzz(job.priority, &job.tenant, &job.actor, json!(fuzzing_request), &regression).await?;
+        jobs.push(RegressionFuzzJob { instruction_name: instruction_name.clone(), job_id });
+    }
+    Ok(RegressionFuzzPlan {
+        base_commit: request.base_commit.clone(),
+        head_commit: request.head_commit.clone(),
+        changed_instructions: changed,
+        jobs,
+    })
+}
+
+// A pinned toolchain that can't be installed leaves the build to the host's toolchains;
+// the response then shows nothing was provisioned
+fn provision_toolchains(toolchain_manager: &ToolchainManager, repo_path: &Path, harness: bool) -> ProvisionedToolchains {
+    match toolchain_manager.provision(repo_path, harness) {
+        Ok(toolchains) => toolchains,
+        Err(e) => {
+            println!("Warning: Failed to provision toolchains: {}", e);
+            ProvisionedToolchains::default()
+        }
+    }
+}
+
+// Jobs run without the submitter's credentials, so private repositories are cloned with the
+// deploy key registered for them, if any
+fn job_github_client(db: &Database, deploy_keys: Option<&DeployKeys>, tenant: &str, repo_url: &RepoUrl) -> GitHubClient {
+    let github_client = GitHubClient::new();
+    let Some(deploy_keys) = deploy_keys else {
+        return github_client;
+    };
+    let key_pair = db.get_deploy_key_pair(tenant, &repo_url.canonical())
+        .and_then(|pair| pair.map(|(public_key, sealed)| deploy_keys.open(&sealed).map(|private_key| (public_key, private_key))).transpose());
+    match key_pair {
+        Ok(Some((public_key, private_key))) => github_client.with_deploy_key(public_key, private_key),
+        Ok(None) => github_client,
+        Err(e) => {
+            println!("Warning: Failed to load deploy key for {}: {}", repo_url, e);
+            github_client
+        }
+    }
+}
+
+// The origins browsers may call the API from. Managing them is limited to admin tenants.
+#[get("/api/admin/cors-origins")]
+async fn list_cors_origins(caller: Caller, origins: web::Data<AllowedOrigins>) -> impl Responder {
+    if !is_admin(&caller) {
+        return cors_forbidden();
+    }
+    let origins = origins.list();
+    HttpResponse::Ok().json(CorsOriginsResponse {
+        success: true,
+        message: format!("{} allowed origins", origins.len()),
+        origins: Some(origins),
+    })
+}
+
+// Takes effect immediately and survives restarts
+#[post("/api/admin/cors-origins")]
+async fn add_cors_origin(request: Valid<CorsOriginRequest>, caller: Caller, db: web::Data<Database>, origins: web::Data<AllowedOrigins>) -> impl Responder {
+    if !is_admin(&caller) {
+        return cors_forbidden();
+    }
+    let origin = normalize_origin(&request.origin).unwrap_or_else(|_| request.origin.clone());
+    let audit = AuditEvent::start(&caller, "cors.add").target(origin.clone());
+    match origins.add(&db, &origin, &caller.actor) {
+        Ok(added) => {
+            let message = if added { format!("Allowed requests from {}", origin) } else { format!("{} is already allowed", origin) };
+            audit.finish(&db, true, &message);
+            let status = if added { StatusCode::CREATED } else { StatusCode::OK };
+            HttpResponse::build(status).json(CorsOriginsResponse {
+                success: true,
+                message,
+                origins: Some(origins.list()),
+            })
+        },
+        Err(e) => {
+            let message = format!("Failed to add origin: {}", e);
+            audit.finish(&db, false, &message);
+            HttpResponse::InternalServerError().json(CorsOriginsResponse {
+                success: false,
+                message,
+                origins: None,
+            })
+        }
+    }
+}
+
+#[delete("/api/admin/cors-origins")]
+async fn delete_cors_origin(query: web::Query<CorsOriginRequest>, caller: Caller, db: web::Data<Database>, origins: web::Data<AllowedOrigins>) -> impl Responder {
+    if !is_admin(&caller) {
+        return cors_forbidden();
+    }
+    let origin = normalize_origin(&query.origin).unwrap_or_else(|_| query.origin.clone());
+    let audit = AuditEvent::start(&caller, "cors.remove").target(origin.clone());
+    if origins.is_configured(&origin) {
+        let message = format!("{} is set by SAFEX_CORS_ORIGINS and can only be removed there", origin);
+        audit.finish(&db, false, &message);
+        return HttpResponse::Conflict().json(CorsOriginsResponse {
+            success: false,
+            message,
+            origins: None,
+        });
+    }
+    match origins.remove(&db, &origin) {
+        Ok(true) => {
+            let message = format!("Stopped allowing requests from {}", origin);
+            audit.finish(&db, true, &message);
+            HttpResponse::Ok().json(CorsOriginsResponse {
+                success: true,
+                message,
+                origins: Some(origins.list()),
+            })
+        },
+        Ok(false) => {
+            audit.finish(&db, false, "Origin not allowed");
+            HttpResponse::NotFound().json(CorsOriginsResponse {
+                success: false,
+                message: format!("{} isn't an allowed origin", origin),
+                origins: None,
+            })
+        },
+        Err(e) => {
+            let message = format!("Failed to remove origin: {}", e);
+            audit.finish(&db, false, &message);
+            HttpResponse::InternalServerError().json(CorsOriginsResponse {
+                success: false,
+                message,
+                origins: None,
+            })
+        }
+    }
+}
+
+// Same as SIGHUP: reads SAFEX_CONFIG_FILE again and swaps in the new settings if they all
+// validate. Running requests and jobs aren't interrupted.
+#[post("/api/admin/reload-config")]
+async fn reload_config(caller: Caller, db: web::Data<Database>) -> impl Responder {
+    if !is_admin(&caller) {
+        return HttpResponse::Forbidden().json(ConfigReloadResponse {
+            success: false,
+            message: "Only admin tenants (SAFEX_ADMIN_TENANTS) can reload the configuration".to_string(),
+            loaded_at: None,
+            overrides: None,
+        });
+    }
+    let audit = AuditEvent::start(&caller, "config.reload");
+    match config::reload() {
+        Ok(config) => {
+            let message = format!("Reloaded configuration with {} settings from the config file", config.overrides().len());
+            audit.finish(&db, true, &message);
+            HttpResponse::Ok().json(ConfigReloadResponse {
+                success: true,
+                message,
+                loaded_at: Some(config.loaded_at),
+                overrides: Some(config.overrides()),
+            })
+        },
+        Err(e) => {
+            let message = format!("Config reload failed, keeping the running config: {}", e);
+            audit.finish(&db, false, &message);
+            HttpResponse::UnprocessableEntity().json(ConfigReloadResponse {
+                success: false,
+                message,
+                loaded_at: Some(config::current().loaded_at),
+                overrides: None,
+            })
+        }
+    }
+}
+
+// Live operational state for an operator dashboard. Each section is read on its own; one that
+// can't be (no queue, GitHub or the RPC node unreachable) is left out with a warning rather
+// than failing the rest.
+#[get("/api/admin/dashboard")]
+async fn admin_dashboard(
+    caller: Caller,
+    queue: Option<web::Data<JobQueue>>,
+    clone_cache: web::Data<CloneCache>,
+    metadata_cache: web::Data<MetadataCache>,
+    toolchain_manager: web::Data<ToolchainManager>,
+    payer_monitor: Option<web::Data<PayerMonitor>>,
+) -> impl Responder {
+    if !is_admin(&caller) {
+        return HttpResponse::Forbidden().json(AdminDashboardResponse {
+            success: false,
+            message: "Only admin tenants (SAFEX_ADMIN_TENANTS) can view the dashboard".to_string(),
+            generated_at: now_unix(),
+            jobs: None,
+            workers: None,
+            temp_dir: None,
+            caches: None,
+            github: None,
+            payer: None,
+            warnings: Vec::new(),
+        });
+    }
+    let mut warnings = Vec::new();
+    
+    let (jobs, workers) = match &queue {
+        Some(queue) => {
+            let jobs = match (queue.active_jobs().await, queue.queue_depths().await) {
+                (Ok(active), Ok(queued)) => Some(JobsOverview { active, queued }),
+                (Err(e), _) | (_, Err(e)) => {
+                    warnings.push(format!("Failed to read jobs: {}", e));
+                    None
+                }
+            };
+            let workers = match queue.workers().await {
+                Ok(workers) => {
+                    let active_jobs = workers.iter().map(|worker| worker.active_jobs).sum();
+                    // Workers that don't report their slots run the default one
+                    let slots: u32 = workers.iter().map(|worker| worker.slots.unwrap_or(1)).sum();
+                    Some(WorkerUtilization {
+                        utilization: (slots > 0).then(|| active_jobs as f64 / slots as f64),
+                        workers,
+                        active_jobs,
+                        slots,
+                    })
+                },
+                Err(e) => {
+                    warnings.push(format!("Failed to list workers: {}", e));
+                    None
+                }
+            };
+            (jobs, workers)
+        },
+        None => {
+            warnings.push("Job queue is not configured, so jobs run inline and aren't tracked".to_string());
+            (None, None)
+        }
+    };
+    
+    // Walking large caches takes a while, so it's kept off the request threads
+    let disk = web::block(move || {
+        let mut caches = vec![
+            dir_usage("clone_cache", clone_cache.root(), None),
+            dir_usage("github_metadata", metadata_cache.root(), Some(metadata_cache.entry_count() as u64)),
+            dir_usage("harness_cache", &harness_cache_dir(), None),
+            dir_usage("fuzz_corpus", &fuzz_corpus_dir(), None),
+            dir_usage("toolchains", toolchain_manager.root(), None),
+        ];
+        if let Some(vendor_dir) = std::env::var("SAFEX_VENDOR_DIR").ok().filter(|dir| !dir.is_empty()) {
+            caches.push(dir_usage("vendor", Path::new(&vendor_dir), None));
+        }
+        (temp_dir_usage(), caches)
+    }).await;
+    let (temp_dir, caches) = match disk {
+        Ok((temp_dir, caches)) => (Some(temp_dir), Some(caches)),
+        Err(e) => {
+            warnings.push(format!("Failed to measure disk usage: {}", e));
+            (None, None)
+        }
+    };
+    
+    let github = match GitHubClient::new().rate_limit().await {
+        Ok(quota) => Some(quota),
+        Err(e) => {
+            warnings.push(format!("Failed to read GitHub quota: {}", e));
+            None
+        }
+    };
+    
+    // A fresh check, which alerts like the periodic ones if the balance crossed the threshold
+    let payer = match &payer_monitor {
+        Some(payer_monitor) => match payer_monitor.check().await {
+            Ok(balance) => Some(balance),
+            Err(e) => {
+                warnings.push(format!("Failed to read payer balance: {}", e));
+                None
+            }
+        },
+        None => {
+            warnings.push("No SAFEX_REPORT_PAYER_KEYPAIR configured, reports are paid by a throwaway keypair".to_string());
+            None
+        }
+    };
+    
+    HttpResponse::Ok().json(AdminDashboardResponse {
+        success: true,
+        message: match warnings.len() {
+            0 => "Operational state".to_string(),
+            count => format!("Operational state with {} warnings", count),
+        },
+        generated_at: now_unix(),
+        jobs,
+        workers,
+        temp_dir,
+        caches,
+        github,
+        payer,
+        warnings,
+    })
+}
+
+fn cors_forbidden() -> HttpResponse {
+    HttpResponse::Forbidden().json(CorsOriginsResponse {
+        success: false,
+        message: "Only admin tenants (SAFEX_ADMIN_TENANTS) can manage CORS origins".to_string(),
+        origins: None,
+    })
+}
+
+#[get("/api/audit-log")]
+async fn audit_log(query: web::Query<AuditLogQuery>, caller: Caller, db: web::Data<Database>) -> impl Responder {
+    match db.list_audit_entries(&caller.tenant, &query) {
+        Ok(entries) => {
+            HttpResponse::Ok().json(AuditLogResponse {
+                success: true,
+                message: format!("Found {} audit log entries", entries.len()),
+                entries: Some(entries),
+            })
+        },
+        Err(e) => {
+            HttpResponse::InternalServerError().json(AuditLogResponse {
+                success: false,
+                message: format!("Failed to load audit log: {}", e),
+                entries: None,
+            })
+        }
+    }
+}
+
+#[post("/api/log-report")]
+async fn log_report(report_request: Valid<ReportLogRequest>, caller: Caller, db: web::Data<Database>, anchorers: web::Data<Anchorers>, approvers: Option<web::Data<ReportApprovers>>) -> Result<HttpResponse, actix_web::Error> {
+    println!("Received report logging request");
+    let mut hasher = ReportHasher::new(&report_request.hash_algorithms);
+    if report_request.confidential {
+        hasher = hasher.salted().map_err(|e| request_error(StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?;
+    }
+    hasher.update(report_request.report_content.as_bytes());
+    Ok(log_report_hash(hasher.finish(), report_request.submission(), &caller, &db, &anchorers, approvers).await)
+}
+
+// Upload of a report too large for a JSON body: a multipart form with a `metadata` part, the
+// JSON of a ReportSubmission, and a `report` part hashed as it streams in. The report is capped
+// at SAFEX_MAX_REPORT_BYTES and never held in memory.
+#[post("/api/log-report/stream")]
+async fn log_report_stream(mut payload: Multipart, caller: Caller, db: web::Data<Database>, anchorers: web::Data<Anchorers>, approvers: Option<web::Data<ReportApprovers>>) -> Result<HttpResponse, actix_web::Error> {
+    println!("Received streamed report logging request");
+    let (digest, submission) = read_report_upload(&mut payload).await?;
+    Ok(log_report_hash(digest, submission, &caller, &db, &anchorers, approvers).await)
+}
+
+// Largest `metadata` part of a streamed upload
+const MAX_UPLOAD_METADATA_BYTES: usize = 64 * 1024;
+
+async fn read_report_upload(payload: &mut Multipart) -> Result<(ReportDigest, ReportSubmission), actix_web::Error> {
+    let max_bytes = max_report_bytes();
+    let (mut digest, mut submission) = (None::<ReportDigest>, None::<ReportSubmission>);
+    while let Some(field) = payload.next().await {
+        let mut field = field.map_err(|e| request_error(StatusCode::BAD_REQUEST, format!("Invalid multipart body: {}", e)))?;
+        match field.name() {
+            Some("metadata") => {
+                let mut bytes = Vec::new();
+                while let Some(chunk) = field.next().await {
+                    let chunk = chunk.map_err(|e| request_error(StatusCode::BAD_REQUEST, format!("Failed to read metadata: {}", e)))?;
+                    if bytes.len() + chunk.len() > MAX_UPLOAD_METADATA_BYTES {
+                        return Err(request_error(StatusCode::PAYLOAD_TOO_LARGE, format!("Metadata is larger than {} bytes", MAX_UPLOAD_METADATA_BYTES)));
+                    }
+                    bytes.extend_from_slice(&chunk);
+                }
+                let value = serde_json::from_slice(&bytes)
+                    .map_err(|e| request_error(StatusCode::BAD_REQUEST, format!("Metadata is not JSON: {}", e)))?;
+                submission = Some(parse_valid::<ReportSubmission>(value)?);
+            },
+            Some("report") => {
+                // A report sent before its metadata is hashed by every algorithm and salted, as
+                // what's asked for isn't known yet
+                let algorithms = submission.as_ref().map_or(HashAlgorithm::ALL.as_slice(), |submission| &submission.hash_algorithms);
+                let mut hasher = ReportHasher::new(algorithms);
+                if submission.as_ref().is_none_or(|submission| submission.confidential) {
+                    hasher = hasher.salted().map_err(|e| request_error(StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?;
+                }
+                let mut len = 0;
+                while let Some(chunk) = field.next().await {
+                    let chunk = chunk.map_err(|e| request_error(StatusCode::BAD_REQUEST, format!("Failed to read report: {}", e)))?;
+                    len += chunk.len();
+                    if len > max_bytes {
+                        return Err(request_error(StatusCode::PAYLOAD_TOO_LARGE, format!("Report is larger than the {} bytes allowed", max_bytes)));
+                    }
+                    hasher.update(&chunk);
+                }
+                if len == 0 {
+                    return Err(request_error(StatusCode::UNPROCESSABLE_ENTITY, "Report is empty".to_string()));
+                }
+                digest = Some(hasher.finish());
+            },
+            // Other parts are skipped
+            _ => {},
+        }
+    }
+    match (digest, submission) {
+        (Some(mut digest), Some(submission)) => {
+            digest.alternatives.retain(|algorithm, _| submission.hash_algorithms.contains(algorithm));
+            if !submission.confidential {
+                digest.commitment = None;
+            }
+            Ok((digest, submission))
+        },
+        (None, _) => Err(request_error(StatusCode::UNPROCESSABLE_ENTITY, "Missing the `report` part".to_string())),
+        (_, None) => Err(request_error(StatusCode::UNPROCESSABLE_ENTITY, "Missing the `metadata` part".to_string())),
+    }
+}
+
+// Log a report by its SHA256 hash, or hold it for reviewer approval when that's configured.
+// Alternative digests are only reported back; the chain and the approval keep the SHA256.
+// A confidential report is logged and held by its commitment instead, and its salt returned.
+async fn log_report_hash(digest: ReportDigest, submission: ReportSubmission, caller: &Caller, db: &web::Data<Database>, anchorers: &Anchorers, approvers: Option<web::Data<ReportApprovers>>) -> HttpResponse {
+    let (hash, salt) = match &digest.commitment {
+        Some(commitment) => (commitment.hash, Some(hex(&commitment.salt))),
+        None => (digest.sha256, None),
+    };
+    let hash_hex = hex(&hash);
+    let repo_hash_hex = hex(&AnchorEntry::new(hash, &submission).repo_hash());
+    let alternative_hashes = (!digest.alternatives.is_empty()).then_some(digest.alternatives);
+    let audit = AuditEvent::start(caller, "report.log")
+        .target(submission.repo_url.canonical())
+        .params(json!({ "hash": hash_hex, "commit_sha": submission.commit_sha }));
+    let failure = |message: String, hash: Option<String>, repo_hash: Option<String>| ReportLogResponse {
+        success: false,
+        message,
+        transaction_signature: None,
+        anchor_backend: None,
+        anchor_receipt: None,
+        hash,
+        repo_hash,
+        certificate_id: None,
+        job_id: None,
+        approval_id: None,
+        alternative_hashes: None,
+        salt: None,
+    };
+    
+    let certificate_wallet = match submission.certificate.as_ref().map(validate_certificate_target).transpose() {
+        Ok(wallet) => wallet,
+        Err(message) => {
+            audit.finish(db, false, &message);
+            return HttpResponse::BadRequest().json(failure(message, Some(hash_hex), None));
+        }
+    };
+    // Checked up front so a held report doesn't fail once it's approved
+    if let Err(e) = anchorers.get(submission.anchor) {
+        let message = e.to_string();
+        audit.finish(db, false, &message);
+        return HttpResponse::UnprocessableEntity().json(failure(message, Some(hash_hex), None));
+    }
+    if submission.certificate.is_some() && submission.anchor != AnchorBackend::Solana {
+        let message = "Certificates are only minted for reports anchored on Solana".to_string();
+        audit.finish(db, false, &message);
+        return HttpResponse::BadRequest().json(failure(message, Some(hash_hex), None));
+    }
+    if submission.confidential && !submission.hash_algorithms.is_empty() {
+        let message = "hash_algorithms can't be used with confidential reports; their digests would identify the report".to_string();
+        audit.finish(db, false, &message);
+        return HttpResponse::BadRequest().json(failure(message, Some(hash_hex), None));
+    }
+    
+    // With reviewers configured, the report waits for their approvals instead of going out now
+    if let Some(approvers) = approvers {
+        let reviewers: Vec<String> = approvers.reviewers.iter().map(|reviewer| reviewer.to_string()).collect();
+        return match db.insert_report_approval(&caller.tenant, &submission, &hash_hex, approvers.threshold, &reviewers) {
+            Ok(approval_id) => {
+                let message = format!(
+                    "Report awaits approval by {} of {} reviewers; follow it at /api/approvals/{}",
+                    approvers.threshold, reviewers.len(), approval_id,
+                );
+                audit.finish(db, true, &message);
+                HttpResponse::Accepted().json(ReportLogResponse {
+                    success: true,
+                    message,
+                    transaction_signature: None,
+                    anchor_backend: None,
+                    anchor_receipt: None,
+                    hash: Some(hash_hex),
+                    repo_hash: Some(repo_hash_hex),
+                    certificate_id: None,
+                    job_id: None,
+                    approval_id: Some(approval_id),
+                    alternative_hashes,
+                    salt,
+                })
+            },
+            Err(e) => {
+                let message = format!("Failed to record report for approval: {}", e);
+                audit.finish(db, false, &message);
+                HttpResponse::InternalServerError().json(failure(message, Some(hash_hex), Some(repo_hash_hex)))
+            }
+        };
+    }
+    
+    let (status, mut response) = submit_report(db, anchorers, &caller.tenant, &hash, &submission, certificate_wallet).await;
+    response.alternative_hashes = alternative_hashes;
+    response.salt = salt;
+    match &response.transaction_signature {
+        Some(signature) if response.success => audit.finish(db, true, format!("Sent in transaction {}", signature)),
+        _ => audit.finish(db, response.success, &response.message),
+    }
+    HttpResponse::build(status).json(response)
+}
+
+// Anchor a validated report on the backend it asks for and record it. Solana confirmation is
+// followed over the websocket API when available (202) and otherwise waited for (200); EVM
+// transactions are followed by polling (202); timestamp tokens are final as issued (200).
+async fn submit_report(db: &web::Data<Database>, anchorers: &Anchorers, tenant: &str, hash: &[u8; 32], submission: &ReportSubmission, certificate_wallet: Option<Pubkey>) -> (StatusCode, ReportLogResponse) {
+    let entry = AnchorEntry::new(*hash, submission);
+    let hash_hex = hex(hash);
+    let repo_hash_hex = hex(&entry.repo_hash());
+    let failure = |message: String, hash: Option<String>, repo_hash: Option<String>| ReportLogResponse {
+        success: false,
+        message,
+        transaction_signature: None,
+        anchor_backend: None,
+        anchor_receipt: None,
+        hash,
+        repo_hash,
+        certificate_id: None,
+        job_id: None,
+        approval_id: None,
+        alternative_hashes: None,
+        salt: None,
+    };
+    
+    let anchorer = match anchorers.get(submission.anchor) {
+        Ok(anchorer) => anchorer,
+        Err(e) => return (StatusCode::UNPROCESSABLE_ENTITY, failure(e.to_string(), Some(hash_hex), Some(repo_hash_hex))),
+    };
+    
+    // The certificate is minted once the report is confirmed
+    let certificate = match (&submission.certificate, certificate_wallet) {
+        (Some(target), Some(wallet)) => match db.insert_certificate(tenant, &hash_hex, &wallet.to_string(), &target.metadata_uri) {
+            Ok(certificate) => Some((certificate, wallet)),
+            Err(e) => {
+                println!("Warning: Failed to record certificate: {}", e);
+                None
+            }
+        },
+        _ => None,
+    };
+    let certificate_id = certificate.as_ref().map(|(certificate, _)| certificate.id.clone());
+    let job_id = match db.insert_report_log(tenant, submission.anchor, &hash_hex, &submission.repo_url.canonical(), &submission.commit_sha, certificate_id.as_deref()) {
+        Ok(job_id) => job_id,
+        Err(e) => {
+            let message = format!("Failed to record report log: {}", e);
+            return (StatusCode::INTERNAL_SERVER_ERROR, failure(message, Some(hash_hex), Some(repo_hash_hex)));
+        }
+    };
+    let tracker = Arc::new(ReportLogTracker {
+        db: db.clone(),
+        job_id: job_id.clone(),
+        certificate: std::sync::Mutex::new(certificate),
+    });
+    
+    match anchorer.anchor(&entry, tracker.clone()).await {
+        Ok(receipt) => {
+            if let Some(proof) = &receipt.receipt {
+                if let Err(e) = db.set_report_log_anchor(&job_id, &receipt.reference, Some(proof)) {
+                    println!("Warning: Failed to record receipt of report log {}: {}", job_id, e);
+                }
+            }
+            let message = match (submission.anchor, receipt.tracked) {
+                (backend, true) => format!("Report submitted to {}; follow confirmation at /api/jobs/{}", anchor_destination(backend), job_id),
+                (AnchorBackend::Rfc3161, false) => "Report hash timestamped; the signed token is in anchor_receipt".to_string(),
+                (backend, false) => format!("Report successfully logged to {}", anchor_destination(backend)),
+            };
+            let response = ReportLogResponse {
+                success: true,
+                message,
+                transaction_signature: Some(receipt.reference),
+                anchor_backend: Some(submission.anchor),
+                anchor_receipt: receipt.receipt,
+                hash: Some(hash_hex),
+                repo_hash: Some(repo_hash_hex),
+                certificate_id,
+                job_id: Some(job_id),
+                approval_id: None,
+                alternative_hashes: None,
+                salt: None,
+            };
+            if receipt.tracked {
+                (StatusCode::ACCEPTED, response)
+            } else {
+                (StatusCode::OK, response)
+            }
+        },
+        Err(e) => {
+            let message = format!("Failed to log report: {}", e);
+            tracker.status(ConfirmationStatus::Failed, Some(message.clone()));
+            (StatusCode::INTERNAL_SERVER_ERROR, failure(message, Some(hash_hex), Some(repo_hash_hex)))
+        }
+    }
+}
+
+fn anchor_destination(backend: AnchorBackend) -> &'static str {
+    match backend {
+        AnchorBackend::Solana => "Solana blockchain",
+        AnchorBackend::Evm => "the EVM chain",
+        AnchorBackend::Rfc3161 => "the timestamping authority",
+    }
+}
+
+// Records an anchor's progress on its report log, and mints the report's certificate once
+// it's confirmed
+struct ReportLogTracker {
+    db: web::Data<Database>,
+    job_id: String,
+    certificate: std::sync::Mutex<Option<(Certificate, Pubkey)>>,
+}
+
+impl AnchorTracker for ReportLogTracker {
+    fn reference(&self, reference: &str) {
+        if let Err(e) = self.db.set_report_log_anchor(&self.job_id, reference, None) {
+            println!("Warning: Failed to record anchor of report log {}: {}", self.job_id, e);
+        }
+    }
+    
+    fn status(&self, status: ConfirmationStatus, error: Option<String>) {
+        println!("Report log {} is {}", self.job_id, status.as_str());
+        if let Err(e) = self.db.update_report_log(&self.job_id, status, error.as_deref()) {
+            println!("Warning: Failed to record status of report log {}: {}", self.job_id, e);
+        }
+        let mut certificate = self.certificate.lock().unwrap();
+        match (status, certificate.take()) {
+            (ConfirmationStatus::Confirmed | ConfirmationStatus::Finalized, Some((certificate, wallet))) => spawn_certificate_mint(self.db.clone(), &certificate, wallet),
+            (ConfirmationStatus::Failed, Some((certificate, _))) => {
+                let _ = self.db.finish_certificate(&certificate.id, &Err(anyhow::anyhow!("Report transaction failed")));
+            },
+            (_, pending) => *certificate = pending,
+        }
+    }
+}
+
+// A reviewer's approval of a held-back report. The approval that reaches the threshold
+// sends the report; if sending fails, any reviewer who already approved can retry it.
+#[post("/api/approvals/{approval_id}")]
+async fn approve_report(path: web::Path<String>, request: Valid<ApprovalRequest>, caller: Caller, db: web::Data<Database>, anchorers: web::Data<Anchorers>) -> impl Responder {
+    let approval_id = path.into_inner();
+    println!("Received approval of report {} by {}", approval_id, request.reviewer);
+    let audit = AuditEvent::start(&caller, "report.approve")
+        .target(approval_id.clone())
+        .params(json!({ "reviewer": request.reviewer }));
+    let failure = |message: String| ApprovalResponse {
+        success: false,
+        message,
+        approval: None,
+    };
+    
+    let approval = match db.get_report_approval(&caller.tenant, &approval_id) {
+        Ok(Some(approval)) => approval,
+        Ok(None) => {
+            let message = format!("Approval {} not found", approval_id);
+            audit.finish(&db, false, &message);
+            return HttpResponse::NotFound().json(failure(message));
+        },
+        Err(e) => {
+            let message = format!("Failed to load approval: {}", e);
+            audit.finish(&db, false, &message);
+            return HttpResponse::InternalServerError().json(failure(message));
+        }
+    };
+    if approval.status != ApprovalStatus::Pending {
+        let message = format!("Report is already {}", approval.status.as_str());
+        audit.finish(&db, false, &message);
+        return HttpResponse::Conflict().json(failure(message));
+    }
+    let reviewer = request.reviewer.trim();
+    if !approval.reviewers.iter().any(|r| r == reviewer) {
+        let message = format!("{} is not a reviewer for this report", reviewer);
+        audit.finish(&db, false, &message);
+        return HttpResponse::Forbidden().json(failure(message));
+    }
+    if let Err(e) = verify_approval(reviewer, &request.signature, &approval.hash) {
+        let message = e.to_string();
+        audit.finish(&db, false, &message);
+        return HttpResponse::BadRequest().json(failure(message));
+    }
+    if let Err(e) = db.add_report_approval_signature(&approval_id, reviewer, request.signature.trim()) {
+        let message = format!("Failed to record approval: {}", e);
+        audit.finish(&db, false, &message);
+        return HttpResponse::InternalServerError().json(failure(message));
+    }
+    
+    let approved = match db.get_report_approval(&caller.tenant, &approval_id) {
+        Ok(Some(approval)) => approval.approvals.len(),
+        _ => approval.approvals.len(),
+    };
+    let mut message = format!("Approval recorded ({} of {} needed)", approved.min(approval.threshold), approval.threshold);
+    if approved >= approval.threshold {
+        // Only one request gets to send the report
+        match db.begin_report_approval_submission(&approval_id) {
+            Ok(true) => {
+                let held = db.get_report_approval_request(&approval_id)
+                    .and_then(|held| held.ok_or_else(|| anyhow::anyhow!("approval {} holds no report", approval_id)))
+                    .and_then(|(submission, hash)| Ok((submission, parse_report_hash(&hash)?)));
+                let submitted = match held {
+                    Ok((submission, hash)) => {
+                        let wallet = submission.certificate.as_ref().and_then(|target| validate_certificate_target(target).ok());
+                        submit_report(&db, &anchorers, &caller.tenant, &hash, &submission, wallet).await.1
+                    },
+                    Err(e) => ReportLogResponse {
+                        success: false,
+                        message: format!("Failed to load the approved report: {}", e),
+                        transaction_signature: None,
+                        anchor_backend: None,
+                        anchor_receipt: None,
+                        hash: None,
+                        repo_hash: None,
+                        certificate_id: None,
+                        job_id: None,
+                        approval_id: None,
+                        alternative_hashes: None,
+                        salt: None,
+                    },
+                };
+                if let Err(e) = db.finish_report_approval_submission(&approval_id, &submitted) {
+                    println!("Warning: Failed to record submission of approval {}: {}", approval_id, e);
+                }
+                message = match (&submitted.transaction_signature, &submitted.job_id) {
+                    (Some(signature), Some(job_id)) if submitted.success => format!(
+                        "Approval threshold reached; report sent in transaction {}, follow confirmation at /api/jobs/{}", signature, job_id,
+                    ),
+                    _ => format!("Approval threshold reached, but sending the report failed ({}); approve again to retry", submitted.message),
+                };
+            },
+            Ok(false) => message = "Approval recorded; the report is already being sent".to_string(),
+            Err(e) => println!("Warning: Failed to claim approval {} for submission: {}", approval_id, e),
+        }
+    }
+    audit.finish(&db, true, &message);
+    
+    match db.get_report_approval(&caller.tenant, &approval_id) {
+        Ok(approval) => HttpResponse::Ok().json(ApprovalResponse {
+            success: true,
+            message,
+            approval,
+        }),
+        Err(e) => HttpResponse::InternalServerError().json(failure(format!("Failed to load approval: {}", e))),
+    }
+}
+
+#[get("/api/approvals/{approval_id}")]
+async fn get_approval(path: web::Path<String>, caller: Caller, db: web::Data<Database>) -> impl Responder {
+    let approval_id = path.into_inner();
+    match db.get_report_approval(&caller.tenant, &approval_id) {
+        Ok(Some(approval)) => {
+            HttpResponse::Ok().json(ApprovalResponse {
+                success: true,
+                message: format!("{} of {} approvals, report {}", approval.approvals.len(), approval.threshold, approval.status.as_str()),
+                approval: Some(approval),
+            })
+        },
+        Ok(None) => {
+            HttpResponse::NotFound().json(ApprovalResponse {
+                success: false,
+                message: format!("Approval {} not found", approval_id),
+                approval: None,
+            })
+        },
+        Err(e) => {
+            HttpResponse::InternalServerError().json(ApprovalResponse {
+                success: false,
+                message: format!("Failed to load approval: {}", e),
+                approval: None,
+            })
+        }
+    }
+}
+
+// The wallet a certificate goes to, if the target is usable
+fn validate_certificate_target(target: &CertificateTarget) -> Result<Pubkey, String> {
+    Pubkey::from_str(&target.wallet).map_err(|_| "wallet must be a base58 public key".to_string())
+}
+
+// Record the certificate and mint it in the background; progress is tracked on the record
+fn start_certificate_mint(db: &web::Data<Database>, tenant: &str, report_hash: &str, wallet: Pubkey, metadata_uri: &str) -> anyhow::Result<Certificate> {
+    let certificate = db.insert_certificate(tenant, report_hash, &wallet.to_string(), metadata_uri)?;
+    spawn_certificate_mint(db.clone(), &certificate, wallet);
+    Ok(certificate)
+}
+
+fn spawn_certificate_mint(db: web::Data<Database>, certificate: &Certificate, wallet: Pubkey) {
+    let (id, report_hash, metadata_uri) = (certificate.id.clone(), certificate.report_hash.clone(), certificate.metadata_uri.clone());
+    actix_web::rt::spawn(async move {
+        let outcome = web::block(move || {
+            CertificateMinter::new()?
+                .mint(&wallet, &report_hash, &metadata_uri)
+                .map(|(mint, signature)| (mint.to_string(), signature))
+        }).await.unwrap_or_else(|e| Err(anyhow::anyhow!(e.to_string())));
+        match &outcome {
+            Ok((mint, _)) => println!("Minted certificate {} as {}", id, mint),
+            Err(e) => println!("Warning: Failed to mint certificate {}: {}", id, e),
+        }
+        if let Err(e) = db.finish_certificate(&id, &outcome) {
+            println!("Warning: Failed to record certificate {}: {}", id, e);
+        }
+    });
+}
+
+#[post("/api/certificates")]
+async fn mint_certificate(request: Valid<CertificateRequest>, caller: Caller, db: web::Data<Database>) -> impl Responder {
+    println!("Received certificate mint request for report {}", request.report_hash);
+    let audit = AuditEvent::start(&caller, "certificate.mint")
+        .target(request.wallet.clone())
+        .params(json!({ "report_hash": request.report_hash, "metadata_uri": request.metadata_uri }));
+    let target = CertificateTarget { wallet: request.wallet.clone(), metadata_uri: request.metadata_uri.clone() };
+    let report_hash = request.report_hash.trim().to_lowercase();
+    
+    let wallet = match validate_certificate_target(&target) {
+        Ok(wallet) => wallet,
+        Err(message) => {
+            audit.finish(&db, false, &message);
+            return HttpResponse::BadRequest().json(CertificateResponse {
+                success: false,
+                message,
+                certificate: None,
+            });
+        }
+    };
+    
+    match start_certificate_mint(&db, &caller.tenant, &report_hash, wallet, &target.metadata_uri) {
+        Ok(certificate) => {
+            audit.finish(&db, true, format!("Started certificate {}", certificate.id));
+            HttpResponse::Accepted().json(CertificateResponse {
+                success: true,
+                message: "Certificate mint started".to_string(),
+                certificate: Some(certificate),
+            })
+        },
+        Err(e) => {
+            let message = format!("Failed to start certificate mint: {}", e);
+            audit.finish(&db, false, &message);
+            HttpResponse::InternalServerError().json(CertificateResponse {
+                success: false,
+                message,
+                certificate: None,
+            })
+        }
+    }
+}
+
+#[get("/api/certificates/{certificate_id}")]
+async fn get_certificate(path: web::Path<String>, caller: Caller, db: web::Data<Database>) -> impl Responder {
+    match db.get_certificate(&caller.tenant, &path.into_inner()) {
+        Ok(Some(certificate)) => {
+            HttpResponse::Ok().json(CertificateResponse {
+                success: true,
+                message: format!("Certificate is {}", certificate.status.as_str()),
+                certificate: Some(certificate),
+            })
+        },
+        Ok(None) => {
+            HttpResponse::NotFound().json(CertificateResponse {
+                success: false,
+                message: "Certificate not found".to_string(),
+                certificate: None,
+            })
+        },
+        Err(e) => {
+            HttpResponse::InternalServerError().json(CertificateResponse {
+                success: false,
+                message: format!("Failed to load certificate: {}", e),
+                certificate: None,
+            })
+        }
+    }
+}
+
+// Served from the indexer's copy of the registry, which may trail the chain by a few seconds
+#[get("/api/reports")]
+async fn list_reports(query: web::Query<ReportsQuery>, db: web::Data<Database>, signer: Option<web::Data<ResponseSigner>>) -> impl Responder {
+    let failure = |message: String| ReportsResponse {
+        success: false,
+        message,
+        reports: None,
+        total: None,
+        signature: None,
+    };
+    let (status, mut response) = match Cluster::registry() {
+        Err(e) => (StatusCode::INTERNAL_SERVER_ERROR, failure(e.to_string())),
+        Ok(cluster) => {
+            let repo_hash = query.repo_url.as_ref()
+                .map(|repo_url| hex(&repo_url_hash(repo_url)));
+            match db.list_indexed_reports(cluster.as_str(), repo_hash.as_deref(), &query) {
+                Ok((reports, total)) => (StatusCode::OK, ReportsResponse {
+                    success: true,
+                    message: format!("Found {} logged reports", total),
+                    reports: Some(reports),
+                    total: Some(total),
+                    signature: None,
+                }),
+                Err(e) => (StatusCode::INTERNAL_SERVER_ERROR, failure(format!("Failed to load logged reports: {}", e))),
+            }
+        },
+    };
+    response.signature = sign_response(signer.as_ref(), &response);
+    HttpResponse::build(status).json(response)
+}
+
+// Re-checks every indexed report against its account right away, rather than waiting for the
+// periodic run. Newly found discrepancies are returned and flagged on the reports.
+#[post("/api/reverify-reports")]
+async fn reverify_logged_reports(caller: Caller, db: web::Data<Database>) -> impl Responder {
+    let audit = AuditEvent::start(&caller, "reports.reverify");
+    match reverify_reports(&db).await {
+        Ok(run) => {
+            let message = format!("Re-verified {} reports, {} newly flagged", run.checked, run.flagged.len());
+            audit.finish(&db, true, &message);
+            HttpResponse::Ok().json(ReverifyReportsResponse {
+                success: true,
+                message,
+                checked: Some(run.checked),
+                program_upgraded: Some(run.program_upgraded),
+                flagged: Some(run.flagged),
+            })
+        },
+        Err(e) => {
+            let message = format!("Failed to re-verify reports: {}", e);
+            audit.finish(&db, false, &message);
+            HttpResponse::BadGateway().json(ReverifyReportsResponse {
+                success: false,
+                message,
+                checked: None,
+                program_upgraded: None,
+                flagged: None,
+            })
+        }
+    }
+}
+
+// Checks report content against the hashes on-chain and the anchors made on the other
+// backends. Only an active report or a confirmed anchor that checks out against its backend
+// verifies; disputed, superseded and revoked reports, and anchors only this server has a record
+// of, are listed but don't count.
+#[post("/api/verify-report")]
+async fn verify_report(request: Valid<ReportVerifyRequest>, db: web::Data<Database>, anchorers: web::Data<Anchorers>, signer: Option<web::Data<ResponseSigner>>) -> impl Responder {
+    let hash = hash_report(request.report_content.as_bytes(), &[]).sha256;
+    verify_report_hash(hash, request.repo_url.as_ref(), false, &db, &anchorers, signer).await
+}
+
+// Checks a confidential report against the commitment it was logged under, once its salt is
+// revealed
+#[post("/api/verify-report/reveal")]
+async fn reveal_report(request: Valid<ReportRevealRequest>, db: web::Data<Database>, anchorers: web::Data<Anchorers>, signer: Option<web::Data<ResponseSigner>>) -> impl Responder {
+    let salt = decode_hex(&request.salt).unwrap_or_default();
+    let commitment = commit_report(&salt, request.report_content.as_bytes());
+    verify_report_hash(commitment, request.repo_url.as_ref(), true, &db, &anchorers, signer).await
+}
+
+// A revealed report may have been logged confidentially, without its repository on-chain
+async fn verify_report_hash(hash: [u8; 32], repo_url: Option<&RepoUrl>, revealed: bool, db: &Database, anchorers: &Anchorers, signer: Option<web::Data<ResponseSigner>>) -> HttpResponse {
+    let hash_hex = hex(&hash);
+    let recorded = match db.confirmed_external_anchors(&hash_hex, repo_url.map(|url| url.canonical()).as_deref()) {
+        Ok(anchors) => anchors,
+        Err(e) => {
+            println!("Warning: Failed to look up report anchors: {}", e);
+            Vec::new()
+        }
+    };
+    // This server's record of an anchor isn't proof by itself, so each is checked against the
+    // chain or the timestamping authority's signature
+    let anchors: Vec<ReportAnchor> = futures_util::future::join_all(recorded.into_iter().map(|mut anchor| async move {
+        let checked = anchorers.verify(&anchor, &hash).await;
+        anchor.verified = Some(checked.is_ok());
+        anchor.verification_error = checked.err().map(|e| e.to_string());
+        anchor
+    })).await;
+    let checked_anchor = anchors.iter().find(|anchor| anchor.verified == Some(true)).map(|anchor| anchor.backend);
+
+    let found = web::block(move || ReportLogger::new()?.reports_with_hash(&hash)).await
+        .unwrap_or_else(|e| Err(anyhow::anyhow!(e.to_string())));
+    let (status, mut response) = match found {
+        Ok(mut reports) => {
+            if let Some(repo_url) = repo_url {
+                let repo_hash = hex(&repo_url_hash(repo_url));
+                let withheld = hex(&[0; 32]);
+                reports.retain(|r| r.repo_hash == repo_hash || (revealed && r.repo_hash == withheld));
+            }
+            let verified = reports.iter().any(|r| r.status == ReportStatus::Active);
+            let message = if verified {
+                "Report matches an active on-chain report".to_string()
+            } else if let Some(backend) = checked_anchor {
+                format!("Report matches a confirmed {} anchor", backend.as_str())
+            } else if let Some(anchor) = anchors.first() {
+                format!("Report matches a {} anchor recorded by this server, which couldn't be checked against the backend", anchor.backend.as_str())
+            } else if reports.is_empty() {
+                "No on-chain report matches this content".to_string()
+            } else {
+                "Report was logged, but is no longer active".to_string()
+            };
+            (StatusCode::OK, ReportVerifyResponse {
+                success: true,
+                message,
+                hash: hash_hex,
+                verified: verified || checked_anchor.is_some(),
+                reports: Some(reports),
+                anchors: Some(anchors),
+                signature: None,
+            })
+        },
+        // An anchor elsewhere still verifies while the Solana RPC node is unreachable
+        Err(e) if checked_anchor.is_some() || !anchors.is_empty() => (StatusCode::OK, ReportVerifyResponse {
+            success: true,
+            message: match checked_anchor {
+                Some(backend) => format!("Report matches a confirmed {} anchor; on-chain reports couldn't be checked: {}", backend.as_str(), e),
+                None => format!("Report matches a {} anchor recorded by this server, which couldn't be checked against the backend; on-chain reports couldn't be checked: {}", anchors[0].backend.as_str(), e),
+            },
+            hash: hash_hex,
+            verified: checked_anchor.is_some(),
+            reports: None,
+            anchors: Some(anchors),
+            signature: None,
+        }),
+        Err(e) => (StatusCode::BAD_GATEWAY, ReportVerifyResponse {
+            success: false,
+            message: format!("Failed to look up logged reports: {}", e),
+            hash: hash_hex,
+            verified: false,
+            reports: None,
+            anchors: None,
+            signature: None,
+        }),
+    };
+    response.signature = sign_response(signer.as_ref(), &response);
+    HttpResponse::build(status).json(response)
+}
+
+// The key verification responses are signed with, to check their `signature` against
+#[get("/api/signing-key")]
+async fn signing_key(signer: Option<web::Data<ResponseSigner>>) -> impl Responder {
+    match signer {
+        Some(signer) => HttpResponse::Ok().json(SigningKeyResponse {
+            success: true,
+            message: "Verification responses are signed with this key".to_string(),
+            algorithm: Some(signing::ALGORITHM.to_string()),
+            public_key: Some(signer.public_key().to_string()),
+        }),
+        None => HttpResponse::ServiceUnavailable().json(SigningKeyResponse {
+            success: false,
+            message: "Response signing is not configured (set SAFEX_RESPONSE_SIGNING_KEYPAIR)".to_string(),
+            algorithm: None,
+            public_key: None,
+        }),
+    }
+}
+
+// A signature for a response when a signing key is configured. A response that can't be signed
+// goes out unsigned rather than failing.
+fn sign_response<T: serde::Serialize>(signer: Option<&web::Data<ResponseSigner>>, response: &T) -> Option<ResponseSignature> {
+    match signer?.sign(response) {
+        Ok(signature) => Some(signature),
+        Err(e) => {
+            println!("Warning: Failed to sign response: {}", e);
+            None
+        }
+    }
+}
+
+#[post("/api/attestations/build")]
+async fn build_attestation(request: Valid<AttestationBuildRequest>) -> impl Responder {
+    println!("Received attestation build request for report {}", request.report);
+    let (report, attester) = match (Pubkey::from_str(&request.report), Pubkey::from_str(&request.attester)) {
+        (Ok(report), Ok(attester)) => (report, attester),
+        _ => {
+            return HttpResponse::BadRequest().json(AttestationBuildResponse {
+                success: false,
+                message: "report and attester must be base58 public keys".to_string(),
+                transaction: None,
+                attestation: None,
+            });
+        }
+    };
+
+    let attestations = match Attestations::new() {
+        Ok(attestations) => attestations,
+        Err(e) => {
+            return HttpResponse::InternalServerError().json(AttestationBuildResponse {
+                success: false,
+                message: format!("Failed to initialize attestations: {}", e),
+                transaction: None,
+                attestation: None,
+            });
+        }
+    };
+    let address = attestations.address(&report, &attester).to_string();
+    // The RPC client blocks, so it runs on the blocking thread pool
+    let verdict = request.verdict;
+    let built = web::block(move || attestations.build_transaction(&report, &attester, verdict)).await
+        .unwrap_or_else(|e| Err(anyhow::anyhow!(e.to_string())));
+    match built {
+        Ok(transaction) => {
+            HttpResponse::Ok().json(AttestationBuildResponse {
+                success: true,
+                message: "Sign the transaction as the attester and submit it to /api/attestations".to_string(),
+                transaction: Some(transaction),
+                attestation: Some(address),
+            })
+        },
+        Err(e) => {
+            HttpResponse::BadGateway().json(AttestationBuildResponse {
+                success: false,
+                message: format!("Failed to build attestation transaction: {}", e),
+                transaction: None,
+                attestation: Some(address),
+            })
+        }
+    }
+}
+
+#[post("/api/attestations")]
+async fn submit_attestation(request: Valid<AttestationSubmitRequest>, caller: Caller, db: web::Data<Database>) -> impl Responder {
+    println!("Received attestation submission");
+    let audit = AuditEvent::start(&caller, "attestation.submit");
+    let failure = |message: String| AttestationSubmitResponse {
+        success: false,
+        message,
+        transaction_signature: None,
+        attestation: None,
+    };
+
+    let attestations = match Attestations::new() {
+        Ok(attestations) => attestations,
+        Err(e) => {
+            let message = format!("Failed to initialize attestations: {}", e);
+            audit.finish(&db, false, &message);
+            return HttpResponse::InternalServerError().json(failure(message));
+        }
+    };
+    let (transaction, address) = match attestations.decode_signed(&request.transaction) {
+        Ok(decoded) => decoded,
+        Err(e) => {
+            let message = e.to_string();
+            audit.finish(&db, false, &message);
+            return HttpResponse::BadRequest().json(failure(message));
+        }
+    };
+
+    let audit = audit.target(address.to_string());
+    let submitted = web::block(move || attestations.submit(&transaction)).await
+        .unwrap_or_else(|e| Err(anyhow::anyhow!(e.to_string())));
+    match submitted {
+        Ok(signature) => {
+            audit.finish(&db, true, format!("Attested in transaction {}", signature));
+            HttpResponse::Ok().json(AttestationSubmitResponse {
+                success: true,
+                message: "Attestation recorded on Solana blockchain".to_string(),
+                transaction_signature: Some(signature),
+                attestation: Some(address.to_string()),
+            })
+        },
+        Err(e) => {
+            let message = format!("Failed to submit attestation: {}", e);
+            audit.finish(&db, false, &message);
+            HttpResponse::BadGateway().json(failure(message))
+        }
+    }
+}
+
+#[get("/api/attestations")]
+async fn list_attestations(query: web::Query<AttestationsQuery>) -> impl Responder {
+    let Ok(report) = Pubkey::from_str(&query.report) else {
+        return HttpResponse::BadRequest().json(AttestationsResponse {
+            success: false,
+            message: "report must be a base58 public key".to_string(),
+            attestations: None,
+        });
+    };
+
+    let listed = web::block(move || Attestations::new()?.list(&report)).await
+        .unwrap_or_else(|e| Err(anyhow::anyhow!(e.to_string())));
+    match listed {
+        Ok(attestations) => {
+            HttpResponse::Ok().json(AttestationsResponse {
+                success: true,
+                message: format!("Found {} attestations", attestations.len()),
+                attestations: Some(attestations),
+            })
+        },
+        Err(e) => {
+            HttpResponse::BadGateway().json(AttestationsResponse {
+                success: false,
+                message: format!("Failed to load attestations: {}", e),
+                attestations: None,
+            })
+        }
+    }
+}
+
+// Well-formed bodies/queries that fail validation (bad repo URLs, unknown enum values)
+// get a 422 with the reason; anything unparseable stays a 400
+fn json_error_handler(err: error::JsonPayloadError, _req: &HttpRequest) -> actix_web::Error {
+    let status = match err {
+        error::JsonPayloadError::Deserialize(ref e) if e.is_data() => StatusCode::UNPROCESSABLE_ENTITY,
+        _ => StatusCode::BAD_REQUEST,
+    };
+    request_error(status, format!("Invalid request body: {}", err))
+}
+
+fn query_error_handler(err: error::QueryPayloadError, _req: &HttpRequest) -> actix_web::Error {
+    request_error(StatusCode::UNPROCESSABLE_ENTITY, format!("Invalid query parameters: {}", err))
+}
+
+// Successful response with a content-hash ETag; answers 304 when the client already has this body.
+// The tag is weak so it stays valid across the gzip/brotli encodings Compress may apply.
+fn cached_body(req: &HttpRequest, content_type: &str, body: Vec<u8>, cache_control: &str) -> HttpResponse {
+    let etag = header::EntityTag::new_weak(format!("{:x}", Sha256::digest(&body))[..32].to_string());
+    let not_modified = match req.get_header::<header::IfNoneMatch>() {
+        Some(header::IfNoneMatch::Any) => true,
+        Some(header::IfNoneMatch::Items(tags)) => tags.iter().any(|tag| tag.weak_eq(&etag)),
+        None => false,
+    };
+    
+    let mut response = if not_modified { HttpResponse::NotModified() } else { HttpResponse::Ok() };
+    response
+        .insert_header(header::ETag(etag))
+        .insert_header((header::CACHE_CONTROL, cache_control));
+    if not_modified {
+        response.finish()
+    } else {
+        response.content_type(content_type).body(body)
+    }
+}
+
+fn cached_json<T: serde::Serialize>(req: &HttpRequest, value: &T, cache_control: &str) -> HttpResponse {
+    match serde_json::to_vec(value) {
+        Ok(body) => cached_body(req, "application/json", body, cache_control),
+        Err(e) => HttpResponse::InternalServerError().json(json!({ "success": false, "message": format!("Failed to serialize response: {}", e) })),
+    }
+}
+
+fn request_error(status: StatusCode, message: String) -> actix_web::Error {
+    let response = HttpResponse::build(status).json(json!({ "success": false, "message": message }));
+    error::InternalError::from_response(message, response).into()
+}
+
+#[actix_web::main]
+async fn main() -> std::io::Result<()> {
+    config::init().map_err(|e| std::io::Error::other(e.to_string()))?;
+    
+    // Editors start the binary as a language server instead of the HTTP API
+    #[cfg(feature = "lsp")]
+    if let Some(transport) = lsp::Transport::from_env() {
+        return lsp::serve(transport).map_err(|e| std::io::Error::other(e.to_string()));
+    }
+    config::log_loaded();
+    
+    let telemetry = Telemetry::init().map_err(|e| std::io::Error::other(e.to_string()))?;
+    let port: u16 = std::env::var("PORT").unwrap_or_else(|_| "8080".to_string()).parse().unwrap_or(8080);
+    let db = web::Data::new(Database::open_from_env().map_err(|e| std::io::Error::other(e.to_string()))?);
+    let api_keys = web::Data::new(ApiKeys::from_env());
+    let allowed_origins = web::Data::new(AllowedOrigins::load(&db).map_err(|e| std::io::Error::other(e.to_string()))?);
+    let mailer = web::Data::new(Mailer::from_env().map_err(|e| std::io::Error::other(e.to_string()))?);
+    let storage: web::Data<dyn Storage> = web::Data::from(storage_from_env().map_err(|e| std::io::Error::other(e.to_string()))?);
+    let clone_cache = web::Data::new(CloneCache::from_env());
+    let metadata_cache = web::Data::new(MetadataCache::from_env());
+    let webhook_secret = WebhookSecret::from_env().map(web::Data::new);
+    let approvers = ReportApprovers::from_env().map_err(|e| std::io::Error::other(e.to_string()))?.map(web::Data::new);
+    if let Some(approvers) = &approvers {
+        println!("Reports need approval by {} of {} reviewers before they are logged", approvers.threshold, approvers.reviewers.len());
+    }
+    let toolchain_manager = web::Data::new(ToolchainManager::from_env());
+    let external = web::Data::new(ExternalAnalyzers::from_env().map_err(|e| std::io::Error::other(e.to_string()))?);
+    let role = Role::from_env().map_err(|e| std::io::Error::other(e.to_string()))?;
+    RateLimits::spawn_pruning();
+    #[cfg(unix)]
+    config::spawn_reload_on_sighup();
+    let deploy_keys = DeployKeys::from_env().map(web::Data::new);
+    if deploy_keys.is_none() {
+        println!("No SAFEX_DEPLOY_KEY_SECRET configured, deploy keys disabled");
+    }
+    let ecosystem = Ecosystem::from_env().map_err(|e| std::io::Error::other(e.to_string()))?.map(web::Data::new);
+    let signer = ResponseSigner::from_env().map_err(|e| std::io::Error::other(e.to_string()))?.map(web::Data::new);
+    let queue = JobQueue::from_env().await.map_err(|e| std::io::Error::other(e.to_string()))?.map(web::Data::new);
+    let anchorers = web::Data::new(Anchorers::from_env().map_err(|e| std::io::Error::other(e.to_string()))?);
+    let payer_monitor = PayerMonitor::from_env().map_err(|e| std::io::Error::other(e.to_string()))?.map(web::Data::new);
+    
+    if role.runs_workers() {
+        if let Some(queue) = &queue {
+            let concurrency = std::env::var("SAFEX_WORKER_CONCURRENCY").ok().and_then(|c| c.parse().ok()).unwrap_or(1);
+            let (db, mailer, storage, external, deploy_keys, toolchain_manager) = (db.clone(), mailer.clone(), storage.clone(), external.clone(), deploy_keys.clone(), toolchain_manager.clone());
+            let job_queue = queue.clone().into_inner();
+            let worker_id = jobs::spawn_workers(queue.clone().into_inner(), concurrency, move |job| {
+                execute_job(job, db.clone(), mailer.clone(), storage.clone(), external.clone(), deploy_keys.clone(), toolchain_manager.clone(), job_queue.clone())
+            }).map_err(|e| std::io::Error::other(e.to_string()))?;
+            println!("Started worker {} with {} job slots", worker_id, concurrency);
+        } else if role == Role::Worker {
+            return Err(std::io::Error::other("SAFEX_ROLE=worker requires SAFEX_REDIS_URL"));
+        }
+    }
+    
+    // Worker-only nodes just process jobs until they are stopped
+    if !role.serves_http() {
+        std::future::pending::<()>().await;
+    }
+    
+    let capabilities = web::Data::new(CapabilityProbe::start(toolchain_manager.clone().into_inner(), external.clone().into_inner()).await);
+    
+    // Report listings are served from the index this keeps current
+    spawn_indexer(db.clone().into_inner()).map_err(|e| std::io::Error::other(e.to_string()))?;
+    spawn_reverification(db.clone().into_inner()).map_err(|e| std::io::Error::other(e.to_string()))?;
+    if let Some(payer_monitor) = &payer_monitor {
+        spawn_payer_monitor(payer_monitor.clone().into_inner()).map_err(|e| std::io::Error::other(e.to_string()))?;
+    }
+    if let Some(ecosystem) = &ecosystem {
+        ecosystem.spawn(db.clone().into_inner(), external.clone().into_inner());
+    }
+    
+    let tls = tls::server_config_from_env().map_err(|e| std::io::Error::other(e.to_string()))?;
+    let scheme = if tls.is_some() { "https" } else { "http" };
+    println!("Starting Safex backend server at {scheme}://0.0.0.0:{port}");
+    let server = actix_web::HttpServer::new(move || {
+        App::new()
+            .wrap(from_fn(rate_limit))
+            .wrap(cors(allowed_origins.clone().into_inner()))
+            .wrap(Compress::default())
+            .wrap(Logger::default())
+            .wrap(TracingLogger::default())
+            .app_data(db.clone())
+            .app_data(allowed_origins.clone())
+            .app_data(api_keys.clone())
+            .app_data(mailer.clone())
+            .app_data(storage.clone())
+            .app_data(clone_cache.clone())
+            .app_data(metadata_cache.clone())
+            .app_data(external.clone())
+            .app_data(toolchain_manager.clone())
+            .app_data(capabilities.clone())
+            .app_data(anchorers.clone())
+            .app_data(web::JsonConfig::default().error_handler(json_error_handler))
+            .app_data(web::QueryConfig::default().error_handler(query_error_handler))
+            .configure(|cfg| {
+                if let Some(queue) = &queue {
+                    cfg.app_data(queue.clone());
+                }
+                if let Some(webhook_secret) = &webhook_secret {
+                    cfg.app_data(webhook_secret.clone());
+                }
+                if let Some(approvers) = &approvers {
+                    cfg.app_data(approvers.clone());
+                }
+                if let Some(deploy_keys) = &deploy_keys {
+                    cfg.app_data(deploy_keys.clone());
+                }
+                if let Some(ecosystem) = &ecosystem {
+                    cfg.app_data(ecosystem.clone());
+                }
+                if let Some(signer) = &signer {
+                    cfg.app_data(signer.clone());
+                }
+                if let Some(payer_monitor) = &payer_monitor {
+                    cfg.app_data(payer_monitor.clone());
+                }
+            })
+            .service(hello)
+            .service(health)
+            .service(ingest_repo)
+            .service(repo_contents)
+            .service(repo_contents_query)
+            .service(repo_file)
+            .service(repo_files)
+            .service(github_webhook)
+            .service(repo_stats)
+            .service(estimate_scope)
+            .service(estimate_rent)
+            .service(decode_account)
+            .service(derive_pda_address)
+            .service(list_instructions)
+            .service(export_account_graph)
+            .service(deployment_check)
+            .service(list_rules)
+            .service(run_rule_self_test)
+            .service(run_corpus_benchmark)
+            .service(list_benchmark_runs)
+            .service(get_ecosystem_stats)
+            .service(register_ecosystem_project)
+            .service(list_ecosystem_projects)
+            .service(delete_ecosystem_project)
+            .service(list_cors_origins)
+            .service(add_cors_origin)
+            .service(delete_cors_origin)
+            .service(reload_config)
+            .service(admin_dashboard)
+            .service(autofix_preview)
+            .service(create_fix_pr)
+            .service(create_finding_issue)
+            .service(get_jira_settings)
+            .service(update_jira_settings)
+            .service(delete_jira_settings)
+            .service(push_to_jira)
+            .service(list_jira_tickets)
+            .service(analyze_code)
+            .service(analyze_snippet)
+            .service(fuzz_test)
+            .service(get_capabilities)
+            .service(build_program)
+            .service(log_report)
+            .service(log_report_stream)
+            .service(approve_report)
+            .service(get_approval)
+            .service(list_reports)
+            .service(verify_report)
+            .service(reveal_report)
+            .service(signing_key)
+            .service(reverify_logged_reports)
+            .service(mint_certificate)
+            .service(get_certificate)
+            .service(build_attestation)
+            .service(submit_attestation)
+            .service(list_attestations)
+            .service(trends)
+            .service(compare_runs)
+            .service(list_findings)
+            .service(set_triage)
+            .service(list_triage)
+            .service(add_finding_comment)
+            .service(list_finding_comments)
+            .service(resolve_finding_thread)
+            .service(assign_finding)
+            .service(list_assigned_findings)
+            .service(create_engagement)
+            .service(list_engagements)
+            .service(get_engagement)
+            .service(update_engagement)
+            .service(delete_engagement)
+            .service(add_engagement_run)
+            .service(remove_engagement_run)
+            .service(export_run_report)
+            .service(run_taxonomy)
+            .service(get_report_template)
+            .service(update_report_template)
+            .service(delete_report_template)
+            .service(get_email_settings)
+            .service(update_email_settings)
+            .service(unsubscribe_email)
+            .service(download_artifact)
+            .service(submit_analysis_job)
+            .service(submit_fuzz_job)
+            .service(submit_regression_fuzz_job)
+            .service(get_job)
+            .service(job_events)
+            .service(create_deploy_key)
+            .service(list_deploy_keys)
+            .service(delete_deploy_key)
+            .service(list_workers)
+            .service(audit_log)
+    });
+    let server = match tls {
+        Some(config) => server.bind_rustls_0_22(("0.0.0.0", port), config)?,
+        None => server.bind(("0.0.0.0", port))?,
+    };
+    let result = server.run().await;
+    
+    telemetry.shutdown();
+    result
 }
@@ -0,0 +1,151 @@
+use anyhow::Result;
+use serde::de::DeserializeOwned;
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::collections::HashMap;
+use std::env;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+// What a cached response is, which decides its TTL
+#[derive(Debug, Clone, Copy)]
+pub enum EntryKind {
+    Repo,
+    Listing,
+}
+
+#[derive(Serialize, Deserialize)]
+struct Entry<T> {
+    fetched_at: u64,
+    value: T,
+}
+
+// GitHub API responses (repository metadata, directory listings) kept on disk so repeated
+// ingests of the same repository don't spend API quota. One directory per repository,
+// so a push webhook can drop everything for that repository at once.
+pub struct MetadataCache {
+    root: PathBuf,
+    repo_ttl: Duration,
+    listing_ttl: Duration,
+    max_entries: usize,
+    // Entry path -> last use, for LRU eviction; seeded from file mtimes at startup
+    index: Mutex<HashMap<PathBuf, SystemTime>>,
+}
+
+impl MetadataCache {
+    // SAFEX_GITHUB_CACHE_DIR (default ./github-cache), SAFEX_GITHUB_CACHE_REPO_TTL_SECS (default 3600),
+    // SAFEX_GITHUB_CACHE_LISTING_TTL_SECS (default 300), SAFEX_GITHUB_CACHE_MAX_ENTRIES (default 5000)
+    pub fn from_env() -> Self {
+        let root = PathBuf::from(env::var("SAFEX_GITHUB_CACHE_DIR").unwrap_or_else(|_| "github-cache".to_string()));
+        let secs = |name: &str, default: u64| env::var(name).ok().and_then(|v| v.parse().ok()).unwrap_or(default);
+        let cache = Self {
+            repo_ttl: Duration::from_secs(secs("SAFEX_GITHUB_CACHE_REPO_TTL_SECS", 3600)),
+            listing_ttl: Duration::from_secs(secs("SAFEX_GITHUB_CACHE_LISTING_TTL_SECS", 300)),
+            max_entries: secs("SAFEX_GITHUB_CACHE_MAX_ENTRIES", 5000) as usize,
+            index: Mutex::new(scan_entries(&root)),
+            root,
+        };
+        println!("Caching GitHub metadata in {} ({} entries)", cache.root.display(), cache.index.lock().unwrap().len());
+        cache
+    }
+
+    pub fn get<T: DeserializeOwned>(&self, repo: &str, kind: EntryKind, key: &str) -> Option<T> {
+        let path = self.entry_path(repo, key);
+        let entry: Entry<T> = serde_json::from_slice(&fs::read(&path).ok()?).ok()?;
+        let age = now_secs().saturating_sub(entry.fetched_at);
+        if age >= self.ttl(kind).as_secs() {
+            return None;
+        }
+        // Persist recency so LRU order survives a restart
+        let now = SystemTime::now();
+        if let Ok(file) = fs::File::options().write(true).open(&path) {
+            let _ = file.set_modified(now);
+        }
+        self.index.lock().unwrap().insert(path, now);
+        Some(entry.value)
+    }
+
+    // Best effort: a cache that can't be written only costs API calls
+    pub fn put<T: Serialize>(&self, repo: &str, key: &str, value: &T) {
+        if let Err(e) = self.write(repo, key, value) {
+            println!("Warning: Failed to cache GitHub response for {}: {}", repo, e);
+        }
+    }
+
+    fn write<T: Serialize>(&self, repo: &str, key: &str, value: &T) -> Result<()> {
+        let path = self.entry_path(repo, key);
+        let entry = Entry { fetched_at: now_secs(), value };
+        fs::create_dir_all(path.parent().unwrap_or(&self.root))?;
+        // Write then rename, so a concurrent reader never sees half an entry
+        let staging = path.with_extension(format!("{}.tmp", uuid::Uuid::new_v4()));
+        fs::write(&staging, serde_json::to_vec(&entry)?)?;
+        fs::rename(&staging, &path)?;
+
+        let mut index = self.index.lock().unwrap();
+        index.insert(path, SystemTime::now());
+        while index.len() > self.max_entries {
+            let Some(oldest) = index.iter().min_by_key(|(_, used)| **used).map(|(path, _)| path.clone()) else {
+                break;
+            };
+            let _ = fs::remove_file(&oldest);
+            index.remove(&oldest);
+        }
+        Ok(())
+    }
+
+    // Drop every cached response for `repo` ("owner/name"), e.g. after a push
+    pub fn invalidate_repo(&self, repo: &str) -> usize {
+        let dir = self.root.join(hash_key(&repo.to_lowercase()));
+        let mut index = self.index.lock().unwrap();
+        let before = index.len();
+        index.retain(|path, _| !path.starts_with(&dir));
+        let _ = fs::remove_dir_all(&dir);
+        before - index.len()
+    }
+
+    pub fn root(&self) -> &Path {
+        &self.root
+    }
+
+    // Cached responses, as tracked for eviction
+    pub fn entry_count(&self) -> usize {
+        self.index.lock().unwrap().len()
+    }
+
+    fn ttl(&self, kind: EntryKind) -> Duration {
+        match kind {
+            EntryKind::Repo => self.repo_ttl,
+            EntryKind::Listing => self.listing_ttl,
+        }
+    }
+
+    // GitHub owner and repository names are case-insensitive
+    fn entry_path(&self, repo: &str, key: &str) -> PathBuf {
+        self.root.join(hash_key(&repo.to_lowercase())).join(format!("{}.json", hash_key(key)))
+    }
+}
+
+fn hash_key(input: &str) -> String {
+    format!("{:x}", Sha256::digest(input.as_bytes()))[..16].to_string()
+}
+
+fn now_secs() -> u64 {
+    SystemTime::now().duration_since(UNIX_EPOCH).map(|d| d.as_secs()).unwrap_or(0)
+}
+
+fn scan_entries(root: &Path) -> HashMap<PathBuf, SystemTime> {
+    let mut entries = HashMap::new();
+    let Ok(repos) = fs::read_dir(root) else {
+        return entries;
+    };
+    for file in repos.flatten().filter_map(|repo| fs::read_dir(repo.path()).ok()).flatten().flatten() {
+        let path = file.path();
+        if path.extension().is_some_and(|ext| ext == "json") {
+            let used = file.metadata().and_then(|m| m.modified()).unwrap_or(UNIX_EPOCH);
+            entries.insert(path, used);
+        }
+    }
+    entries
+}
@@ -1,25 +1,666 @@
 use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use validator::Validate;
+
+use crate::cluster::Cluster;
+use crate::sandbox::NetworkPolicy;
+use crate::toolchain::ProvisionedToolchains;
+use crate::repo_url::RepoUrl;
 
 // Report Logging Models
-#[derive(Debug, Serialize, Deserialize)]
+#[derive(Debug, Serialize, Deserialize, Validate)]
 pub struct ReportLogRequest {
+    #[validate(custom(function = "crate::validation::report_content"))]
     pub report_content: String,
+    // The repository and revision the report covers, recorded alongside the hash
+    pub repo_url: RepoUrl,
+    #[validate(custom(function = "crate::validation::commit_sha"))]
+    pub commit_sha: String,
+    // Mint a certificate NFT to the project's wallet once the report is logged
+    #[validate(nested)]
+    pub certificate: Option<CertificateTarget>,
+    // Also hash the report with these, for the response
+    #[serde(default)]
+    pub hash_algorithms: Vec<HashAlgorithm>,
+    // Where the hash is anchored; Solana unless asked otherwise
+    #[serde(default)]
+    pub anchor: AnchorBackend,
+    // Log a salted commitment instead of the hash, with the repository hash and commit zeroed;
+    // see ReportLogResponse::salt
+    #[serde(default)]
+    pub confidential: bool,
+}
+
+impl ReportLogRequest {
+    pub fn submission(&self) -> ReportSubmission {
+        ReportSubmission {
+            repo_url: self.repo_url.clone(),
+            commit_sha: self.commit_sha.clone(),
+            certificate: self.certificate.clone(),
+            hash_algorithms: self.hash_algorithms.clone(),
+            anchor: self.anchor,
+            confidential: self.confidential,
+        }
+    }
+}
+
+// Everything about a report that's logged besides its hash. The `metadata` part of a streamed
+// upload, and what a report awaiting approval holds on to; the content itself is never kept.
+#[derive(Debug, Clone, Serialize, Deserialize, Validate)]
+pub struct ReportSubmission {
+    pub repo_url: RepoUrl,
+    #[validate(custom(function = "crate::validation::commit_sha"))]
+    pub commit_sha: String,
+    #[validate(nested)]
+    pub certificate: Option<CertificateTarget>,
+    #[serde(default)]
+    pub hash_algorithms: Vec<HashAlgorithm>,
+    #[serde(default)]
+    pub anchor: AnchorBackend,
+    #[serde(default)]
+    pub confidential: bool,
+}
+
+// Where a report's hash is anchored to make it tamper-evident
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum AnchorBackend {
+    // The report-logger program on the registry's cluster
+    #[default]
+    Solana,
+    // A transaction on an EVM chain, sent through a node that holds the sending account's key
+    Evm,
+    // A token from an RFC 3161 timestamping authority, signed proof the hash existed at a time
+    Rfc3161,
+}
+
+impl AnchorBackend {
+    pub const ALL: [AnchorBackend; 3] = [AnchorBackend::Solana, AnchorBackend::Evm, AnchorBackend::Rfc3161];
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            AnchorBackend::Solana => "solana",
+            AnchorBackend::Evm => "evm",
+            AnchorBackend::Rfc3161 => "rfc3161",
+        }
+    }
+
+    pub fn parse(value: &str) -> Option<Self> {
+        Self::ALL.into_iter().find(|backend| backend.as_str() == value)
+    }
+}
+
+// Digests reported alongside the SHA256 that's logged on-chain
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum HashAlgorithm {
+    Blake3,
+    Keccak256,
+}
+
+impl HashAlgorithm {
+    pub const ALL: [HashAlgorithm; 2] = [HashAlgorithm::Blake3, HashAlgorithm::Keccak256];
 }
 
 #[derive(Debug, Serialize, Deserialize)]
 pub struct ReportLogResponse {
     pub success: bool,
     pub message: String,
+    // The anchor's reference: a transaction signature or hash, or a timestamp token's SHA256
     pub transaction_signature: Option<String>,
+    pub anchor_backend: Option<AnchorBackend>,
+    // Proof issued by the backend itself, where it issues one (base64 RFC 3161 token)
+    pub anchor_receipt: Option<String>,
     pub hash: Option<String>,
+    // Hex SHA256 of the canonical repository URL, as stored on-chain
+    pub repo_hash: Option<String>,
+    // Track the certificate mint via /api/certificates/{id}
+    pub certificate_id: Option<String>,
+    // Track confirmation of the transaction via /api/jobs/{id}
+    pub job_id: Option<String>,
+    // Set instead of the above when reviewers must approve first; follow via /api/approvals/{id}
+    pub approval_id: Option<String>,
+    // Hex digests by the hash_algorithms asked for
+    pub alternative_hashes: Option<BTreeMap<HashAlgorithm, String>>,
+    // A confidential report's hex salt; `hash` is then SHA256(salt || report). It is only ever
+    // returned here, and revealing it with the report at /api/verify-report/reveal proves the
+    // report was logged.
+    pub salt: Option<String>,
+}
+
+// Approval Models
+// A report waiting on reviewer approvals is pending until enough have signed, then
+// submitting while its transaction is sent. A failed send returns it to pending.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ApprovalStatus {
+    Pending,
+    Submitting,
+    Submitted,
+}
+
+impl ApprovalStatus {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            ApprovalStatus::Pending => "pending",
+            ApprovalStatus::Submitting => "submitting",
+            ApprovalStatus::Submitted => "submitted",
+        }
+    }
+
+    pub fn parse(value: &str) -> Option<Self> {
+        match value {
+            "pending" => Some(ApprovalStatus::Pending),
+            "submitting" => Some(ApprovalStatus::Submitting),
+            "submitted" => Some(ApprovalStatus::Submitted),
+            _ => None,
+        }
+    }
 }
 
-// Fuzzing Models
 #[derive(Debug, Serialize, Deserialize)]
-pub struct FuzzingRequest {
+pub struct ReviewerApproval {
+    pub reviewer: String,
+    // Base58 ed25519 signature over the raw 32-byte report hash
+    pub signature: String,
+    pub approved_at: i64,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct ReportApproval {
+    pub id: String,
+    pub hash: String,
     pub repo_url: String,
+    pub commit_sha: String,
+    pub status: ApprovalStatus,
+    // How many of `reviewers` must approve; both as configured when the report was submitted
+    pub threshold: usize,
+    pub reviewers: Vec<String>,
+    pub approvals: Vec<ReviewerApproval>,
+    // Set once the report transaction is sent, as in ReportLogResponse
+    pub transaction_signature: Option<String>,
+    pub job_id: Option<String>,
+    pub certificate_id: Option<String>,
+    // Why the last attempt to send the report failed
+    pub error: Option<String>,
+    pub created_at: i64,
+    pub updated_at: i64,
+}
+
+#[derive(Debug, Serialize, Deserialize, Validate)]
+pub struct ApprovalRequest {
+    pub reviewer: String,
+    pub signature: String,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct ApprovalResponse {
+    pub success: bool,
+    pub message: String,
+    pub approval: Option<ReportApproval>,
+}
+
+// How far a submitted report transaction has got, tracked over the websocket API
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ConfirmationStatus {
+    Submitted,
+    Processed,
+    Confirmed,
+    Finalized,
+    Failed,
+}
+
+impl ConfirmationStatus {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            ConfirmationStatus::Submitted => "submitted",
+            ConfirmationStatus::Processed => "processed",
+            ConfirmationStatus::Confirmed => "confirmed",
+            ConfirmationStatus::Finalized => "finalized",
+            ConfirmationStatus::Failed => "failed",
+        }
+    }
+}
+
+// Certificate Models
+#[derive(Debug, Clone, Serialize, Deserialize, Validate)]
+pub struct CertificateTarget {
+    // Base58 address of the project's wallet
+    pub wallet: String,
+    #[validate(custom(function = "crate::validation::metadata_uri"))]
+    pub metadata_uri: String,
+}
+
+#[derive(Debug, Serialize, Deserialize, Validate)]
+pub struct CertificateRequest {
+    // Hex SHA256 of a logged report
+    #[validate(custom(function = "crate::validation::hex_hash"))]
+    pub report_hash: String,
+    pub wallet: String,
+    #[validate(custom(function = "crate::validation::metadata_uri"))]
+    pub metadata_uri: String,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum CertificateStatus {
+    Pending,
+    Minted,
+    Failed,
+}
+
+impl CertificateStatus {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            CertificateStatus::Pending => "pending",
+            CertificateStatus::Minted => "minted",
+            CertificateStatus::Failed => "failed",
+        }
+    }
+
+    pub fn parse(value: &str) -> Option<Self> {
+        match value {
+            "pending" => Some(CertificateStatus::Pending),
+            "minted" => Some(CertificateStatus::Minted),
+            "failed" => Some(CertificateStatus::Failed),
+            _ => None,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Certificate {
+    pub id: String,
+    pub report_hash: String,
+    pub wallet: String,
+    pub metadata_uri: String,
+    pub status: CertificateStatus,
+    // Address of the certificate's mint, once minted
+    pub mint: Option<String>,
+    pub transaction_signature: Option<String>,
+    pub error: Option<String>,
+    pub created_at: i64,
+    pub updated_at: i64,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct CertificateResponse {
+    pub success: bool,
+    pub message: String,
+    pub certificate: Option<Certificate>,
+}
+
+// Lifecycle of a logged report, in the program's enum order
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ReportStatus {
+    Active,
+    Disputed,
+    Superseded,
+    Revoked,
+}
+
+impl ReportStatus {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            ReportStatus::Active => "active",
+            ReportStatus::Disputed => "disputed",
+            ReportStatus::Superseded => "superseded",
+            ReportStatus::Revoked => "revoked",
+        }
+    }
+
+    pub fn parse(value: &str) -> Option<Self> {
+        match value {
+            "active" => Some(ReportStatus::Active),
+            "disputed" => Some(ReportStatus::Disputed),
+            "superseded" => Some(ReportStatus::Superseded),
+            "revoked" => Some(ReportStatus::Revoked),
+            _ => None,
+        }
+    }
+}
+
+// A report account as stored by the report-logger program
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct LoggedReport {
+    pub address: String,
+    pub authority: String,
+    pub hash: String,
+    pub repo_hash: String,
+    pub commit_sha: String,
+    pub timestamp: i64,
+    pub status: ReportStatus,
+    // Set by re-verification when the on-chain trail no longer matches what was indexed
+    pub discrepancy: Option<Discrepancy>,
+}
+
+// Ways a logged report's on-chain trail can change after the fact, most severe first
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum DiscrepancyKind {
+    // The report account no longer exists (or no longer holds a report)
+    AccountClosed,
+    // The account's hash, repository, commit, authority or timestamp changed
+    HashMismatch,
+    // The report-logger program was redeployed since the report was indexed
+    ProgramUpgraded,
+}
+
+impl DiscrepancyKind {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            DiscrepancyKind::AccountClosed => "account_closed",
+            DiscrepancyKind::HashMismatch => "hash_mismatch",
+            DiscrepancyKind::ProgramUpgraded => "program_upgraded",
+        }
+    }
+
+    pub fn parse(value: &str) -> Option<Self> {
+        match value {
+            "account_closed" => Some(DiscrepancyKind::AccountClosed),
+            "hash_mismatch" => Some(DiscrepancyKind::HashMismatch),
+            "program_upgraded" => Some(DiscrepancyKind::ProgramUpgraded),
+            _ => None,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Discrepancy {
+    pub kind: DiscrepancyKind,
+    pub detail: String,
+    pub detected_at: i64,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct ReportsQuery {
+    pub repo_url: Option<RepoUrl>,
+    pub hash: Option<String>,
+    // Matches abbreviated SHAs too
+    pub commit_sha: Option<String>,
+    pub authority: Option<String>,
+    pub status: Option<ReportStatus>,
+    // Free text matched against the address, authority, hash and commit
+    pub search: Option<String>,
+    // Only reports re-verification has (true) or hasn't (false) flagged
+    pub flagged: Option<bool>,
+    pub limit: Option<u32>,
+    pub offset: Option<u32>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct ReportsResponse {
+    pub success: bool,
+    pub message: String,
+    pub reports: Option<Vec<LoggedReport>>,
+    // Reports matching the filters across all pages
+    pub total: Option<u64>,
+    pub signature: Option<ResponseSignature>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct ReverifyReportsResponse {
+    pub success: bool,
+    pub message: String,
+    // Indexed reports whose accounts were re-fetched
+    pub checked: Option<usize>,
+    // Whether the program was redeployed since the previous run
+    pub program_upgraded: Option<bool>,
+    // Reports newly flagged as closed or mismatched by this run
+    pub flagged: Option<Vec<LoggedReport>>,
+}
+
+#[derive(Debug, Serialize, Deserialize, Validate)]
+pub struct ReportVerifyRequest {
+    #[validate(custom(function = "crate::validation::report_content"))]
+    pub report_content: String,
+    // Only consider reports logged for this repository
+    pub repo_url: Option<RepoUrl>,
+}
+
+#[derive(Debug, Serialize, Deserialize, Validate)]
+pub struct ReportRevealRequest {
+    #[validate(custom(function = "crate::validation::report_content"))]
+    pub report_content: String,
+    // The salt returned when the report was logged confidentially
+    #[validate(custom(function = "crate::validation::salt"))]
+    pub salt: String,
+    pub repo_url: Option<RepoUrl>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct ReportVerifyResponse {
+    pub success: bool,
+    pub message: String,
+    pub hash: String,
+    // Whether an active report with this content is on-chain, or an anchor elsewhere checked
+    // against its backend
+    pub verified: bool,
+    pub reports: Option<Vec<LoggedReport>>,
+    // Anchors on the other backends, as recorded when they were made, with their receipts
+    pub anchors: Option<Vec<ReportAnchor>>,
+    pub signature: Option<ResponseSignature>,
+}
+
+// Detached signature over a response by the instance's signing key, present when one is
+// configured
+#[derive(Debug, Serialize, Deserialize)]
+pub struct ResponseSignature {
+    pub algorithm: String,
+    // Base58, as returned by /api/signing-key
+    pub public_key: String,
+    pub signed_at: i64,
+    // Base58 signature of the response's canonical JSON without this field
+    pub value: String,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct SigningKeyResponse {
+    pub success: bool,
+    pub message: String,
+    pub algorithm: Option<String>,
+    pub public_key: Option<String>,
+}
+
+// Attestation Models
+// A reviewer's verdict on a logged report, in the program's enum order
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum AttestationVerdict {
+    Endorse,
+    EndorseWithConcerns,
+    Reject,
+}
+
+#[derive(Debug, Serialize, Deserialize, Validate)]
+pub struct AttestationBuildRequest {
+    // Base58 address of the report account
+    pub report: String,
+    // Base58 public key of the reviewer, who signs and pays for the attestation
+    pub attester: String,
+    pub verdict: AttestationVerdict,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct AttestationBuildResponse {
+    pub success: bool,
+    pub message: String,
+    // Base64 bincode transaction for the attester to sign and submit
+    pub transaction: Option<String>,
+    // Address the attestation will be created at
+    pub attestation: Option<String>,
+}
+
+#[derive(Debug, Serialize, Deserialize, Validate)]
+pub struct AttestationSubmitRequest {
+    // Base64 bincode transaction from /api/attestations/build, signed by the attester
+    pub transaction: String,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct AttestationSubmitResponse {
+    pub success: bool,
+    pub message: String,
+    pub transaction_signature: Option<String>,
+    pub attestation: Option<String>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct AttestationsQuery {
+    pub report: String,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct Attestation {
+    pub address: String,
+    pub report: String,
+    pub attester: String,
+    pub verdict: AttestationVerdict,
+    pub timestamp: i64,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct AttestationsResponse {
+    pub success: bool,
+    pub message: String,
+    pub attestations: Option<Vec<Attestation>>,
+}
+
+// Fuzzing Models
+#[derive(Debug, Serialize, Deserialize, Validate)]
+pub struct FuzzingRequest {
+    pub repo_url: RepoUrl,
+    #[validate(custom(function = "crate::validation::identifier"))]
     pub instruction_name: Option<String>,
+    // Building the harness and running it; 120 by default. How long it may be depends on the
+    // deployment and API key, and is longer for queued jobs.
+    #[validate(range(min = 1, message = "must be at least 1 second"))]
+    pub timeout_seconds: Option<u64>,
+    // Keep running the harness with fresh cases until timeout_seconds is up, rather than once.
+    // Queued jobs record their results so far as they go.
+    #[serde(default)]
+    pub continuous: bool,
+    #[serde(default)]
+    pub network_policy: NetworkPolicy,
+    #[serde(default)]
+    pub vendor_dependencies: bool,
+    // Fuzz the program as of this commit rather than the default branch
+    #[validate(custom(function = "crate::validation::commit_sha"))]
+    pub commit: Option<String>,
+    // Replay failing cases that earlier runs on the repository kept (SAFEX_FUZZ_CORPUS_DIR)
+    #[serde(default)]
+    pub reuse_corpus: bool,
+    #[serde(default)]
+    pub strategy: FuzzStrategy,
+    #[serde(default)]
+    pub engine: FuzzEngine,
+    // Compute units the instruction may use under the compute_units strategy; 200,000 by default
+    #[validate(range(min = 1, max = 1_400_000, message = "must be between 1 and 1400000"))]
+    pub compute_unit_budget: Option<u32>,
+    // Accounts of the instruction, by name, to copy from `cluster` instead of zeroing: the mints,
+    // pools and the like the program expects to exist. They're fetched once when the run starts
+    // and the harness runs against that copy. Signers can't be seeded.
+    #[serde(default)]
+    #[validate(custom(function = "crate::validation::seed_accounts"))]
+    pub seed_accounts: BTreeMap<String, String>,
+    #[serde(default)]
+    pub cluster: Cluster,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum FuzzStrategy {
+    // Random instruction data
+    #[default]
+    Inputs,
+    // The instruction's accounts reordered and with some left out; the program has to reject
+    // every arrangement but the expected one
+    AccountOrdering,
+    // Arguments chosen to make the instruction run longest: integer extremes and the longest
+    // collections that fit. Inputs that exceed the compute budget, or come close, are reported.
+    ComputeUnits,
+}
+
+impl FuzzStrategy {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            FuzzStrategy::Inputs => "inputs",
+            FuzzStrategy::AccountOrdering => "account_ordering",
+            FuzzStrategy::ComputeUnits => "compute_units",
+        }
+    }
+}
+
+// What generates and runs the cases
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum FuzzEngine {
+    // proptest harnesses run as cargo tests, for every strategy
+    #[default]
+    Proptest,
+    // A persistent-mode honggfuzz-rs harness mutating the instruction's data under coverage
+    // guidance, for the inputs strategy; needs `cargo hfuzz` on the host
+    Honggfuzz,
+}
+
+impl FuzzEngine {
+    pub const ALL: [FuzzEngine; 2] = [FuzzEngine::Proptest, FuzzEngine::Honggfuzz];
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            FuzzEngine::Proptest => "proptest",
+            FuzzEngine::Honggfuzz => "honggfuzz",
+        }
+    }
+
+    pub fn strategies(&self) -> &'static [FuzzStrategy] {
+        match self {
+            FuzzEngine::Proptest => &[FuzzStrategy::Inputs, FuzzStrategy::AccountOrdering, FuzzStrategy::ComputeUnits],
+            FuzzEngine::Honggfuzz => &[FuzzStrategy::Inputs],
+        }
+    }
+}
+
+// Fuzz only the instructions whose code changed from base_commit to head_commit
+#[derive(Debug, Serialize, Deserialize, Validate)]
+pub struct RegressionFuzzRequest {
+    pub repo_url: RepoUrl,
+    #[validate(custom(function = "crate::validation::commit_sha"))]
+    pub base_commit: String,
+    #[validate(custom(function = "crate::validation::commit_sha"))]
+    pub head_commit: String,
+    #[validate(range(min = 1, message = "must be at least 1 second"))]
     pub timeout_seconds: Option<u64>,
+    #[serde(default)]
+    pub network_policy: NetworkPolicy,
+    #[serde(default)]
+    pub vendor_dependencies: bool,
+}
+
+// Marks a fuzz job queued because its instruction changed between two commits
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RegressionFuzz {
+    pub base_commit: String,
+    pub head_commit: String,
+    // The regression_fuzz job that found the change
+    pub planned_by: String,
+}
+
+// Result of a regression_fuzz job: what changed, and the fuzz job queued for each
+#[derive(Debug, Serialize, Deserialize)]
+pub struct RegressionFuzzPlan {
+    pub base_commit: String,
+    pub head_commit: String,
+    pub changed_instructions: Vec<String>,
+    pub jobs: Vec<RegressionFuzzJob>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct RegressionFuzzJob {
+    pub instruction_name: String,
+    pub job_id: String,
 }
 
 #[derive(Debug, Serialize, Deserialize)]
@@ -29,101 +670,2305 @@ pub struct FuzzingResponse {
     pub errors: Option<Vec<String>>,
     pub test_file: Option<String>,
     pub execution_time_ms: Option<u64>,
+    // Whether the compiled harness was reused, and time spent building and running it
+    pub cache_hit: Option<bool>,
+    pub build_ms: Option<u64>,
+    pub run_ms: Option<u64>,
+    // Whether the harness was the one last built for the instruction at an earlier commit,
+    // because this commit's wouldn't build within the budget
+    pub stale_harness: Option<bool>,
+    // Passes of the harness run; more than one for continuous runs
+    pub rounds: Option<u32>,
+    // Failing cases replayed from the kept corpus, with reuse_corpus
+    pub corpus_cases: Option<u32>,
+    pub metadata: Option<FuzzingMetadata>,
+    // Storage keys of the harness, manifest and output log, downloadable via /api/artifacts
+    pub artifacts: Option<Vec<String>>,
+    // Set when instruction_name doesn't match any instruction in the program
+    pub available_instructions: Option<Vec<String>>,
+    // The errors above, classified
+    pub findings: Option<Vec<FuzzFinding>>,
+    pub summary: Option<FuzzSummary>,
+    pub status: Option<FuzzStatus>,
+    // Compiler errors from the pre-flight build of the target program, when status is build_failed
+    pub build_diagnostics: Option<Vec<CompilerDiagnostic>>,
+    pub network: Option<NetworkReport>,
+    pub toolchains: Option<ProvisionedToolchains>,
+    pub meta: Option<ExecutionMeta>,
 }
 
-// Code Analysis Models
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum FuzzStatus {
+    Passed,
+    IssuesFound,
+    TimedOut,
+    // The target program doesn't compile, so nothing was fuzzed
+    BuildFailed,
+    // Building the harness took the budget the run left for it, so nothing was fuzzed
+    BuildTimedOut,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CompilerDiagnostic {
+    pub level: String,
+    // e.g. "E0425"
+    pub code: Option<String>,
+    pub message: String,
+    pub file: Option<String>,
+    pub line: Option<u32>,
+    pub column: Option<u32>,
+    // rustc's human-readable rendering, with source context
+    pub rendered: Option<String>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum FuzzFindingKind {
+    Panic,
+    ArithmeticOverflow,
+    ConstraintBypass,
+    Timeout,
+    BuildError,
+    // The program accepted its accounts in an order or number it doesn't expect
+    AccountOrderingAccepted,
+    // An input drove the instruction past its compute budget, or close to it
+    ComputeExhaustion,
+    // A transaction left its accounts with more or fewer lamports than it started with, less the fee
+    LamportImbalance,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct FuzzFinding {
+    pub kind: FuzzFindingKind,
+    pub severity: BugSeverity,
+    // The output line the failure was recognized from
+    pub message: String,
+}
+
+// A fuzz engine and whether this host can run it
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct FuzzEngineCapability {
+    pub engine: FuzzEngine,
+    pub available: bool,
+    pub version: Option<String>,
+    pub strategies: Vec<FuzzStrategy>,
+}
+
+// An analysis pass: the built-in rules and clippy, or a SAFEX_EXTERNAL_ANALYZERS tool
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct AnalyzerCapability {
+    pub name: String,
+    pub external: bool,
+    pub available: bool,
+    pub version: Option<String>,
+    // Empty means every project type
+    pub project_types: Vec<ProjectType>,
+}
+
+// The toolchains already installed for jobs to pin. With auto_install, other versions are
+// installed the first time a job asks for them.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct ToolchainCapabilities {
+    pub rust: Vec<String>,
+    pub solana: Vec<String>,
+    pub anchor: Vec<String>,
+    pub auto_install: bool,
+}
+
+// A network policy builds can run under, and whether they also run without network
+// interfaces rather than only behind the egress proxy
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SandboxCapability {
+    pub network_policy: NetworkPolicy,
+    pub isolated: bool,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ClusterCapability {
+    pub cluster: Cluster,
+    // Whether its RPC endpoint answered when the server started, and the node's version
+    pub reachable: bool,
+    pub version: Option<String>,
+    // The cluster reports are logged on
+    pub registry: bool,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct CapabilitiesResponse {
+    pub success: bool,
+    pub message: String,
+    pub analyzers: Vec<AnalyzerCapability>,
+    pub fuzz_engines: Vec<FuzzEngineCapability>,
+    pub toolchains: ToolchainCapabilities,
+    pub sandbox_levels: Vec<SandboxCapability>,
+    pub clusters: Vec<ClusterCapability>,
+    pub probed_at: i64,
+}
+
+// The results of a continuous fuzz job so far, recorded on the job while it runs
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct FuzzCheckpoint {
+    pub rounds: u32,
+    pub run_ms: u64,
+    pub errors: Vec<String>,
+    pub findings: Vec<FuzzFinding>,
+    pub summary: FuzzSummary,
+    pub recorded_at: i64,
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct FuzzSummary {
+    pub total: u32,
+    pub critical: u32,
+    pub high: u32,
+    pub medium: u32,
+    pub low: u32,
+    pub by_kind: BTreeMap<FuzzFindingKind, u32>,
+}
+
+// Versions used to build the harness, so a run can be reproduced later
 #[derive(Debug, Serialize, Deserialize)]
+pub struct FuzzingMetadata {
+    pub anchor_version: String,
+    pub solana_version: String,
+    pub rust_toolchain: Option<String>,
+    pub dependencies: BTreeMap<String, String>,
+    pub from_lockfile: bool,
+}
+
+// Code Analysis Models
+// Variants are in increasing order of severity
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Default, Serialize, Deserialize)]
 pub enum BugSeverity {
+    #[default]
     #[serde(rename = "low")]
     Low,
     #[serde(rename = "medium")]
     Medium,
     #[serde(rename = "high")]
     High,
+    // Nothing built in reports it; external analyzers can map their top severity here
+    #[serde(rename = "critical")]
+    Critical,
 }
 
-#[derive(Debug, Serialize, Deserialize)]
+impl BugSeverity {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            BugSeverity::Low => "low",
+            BugSeverity::Medium => "medium",
+            BugSeverity::High => "high",
+            BugSeverity::Critical => "critical",
+        }
+    }
+    
+    pub fn parse(value: &str) -> Option<Self> {
+        match value {
+            "low" => Some(BugSeverity::Low),
+            "medium" => Some(BugSeverity::Medium),
+            "high" => Some(BugSeverity::High),
+            "critical" => Some(BugSeverity::Critical),
+            _ => None,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
 pub struct CodeBug {
     pub bug: String,
     pub line: u32,
+    pub file: Option<String>,
     pub severity: BugSeverity,
     pub fix: String,
+    pub fingerprint: String,
+    // Catalog entry from /api/rules; None for tool failures
+    pub rule_id: Option<String>,
+    // The rule's vulnerability class; None for tool failures and external analyzers
+    pub taxonomy: Option<Taxonomy>,
+    // Machine-applicable edits to `file` that resolve the finding
+    pub autofix: Option<Vec<TextEdit>>,
+    pub triage_state: Option<TriageState>,
+    pub triage_comment: Option<String>,
+    // GitHub issue opened for the finding, carried across runs like triage
+    pub issue_url: Option<String>,
+    // Team member reviewing the finding, for the tenant asking
+    pub assignee: Option<String>,
 }
 
-#[derive(Debug, Serialize, Deserialize)]
-pub struct CodeAnalysisRequest {
-    pub repo_url: String,
+// Vulnerability classes of the Sealevel attacks catalog, in its order, followed by the classes
+// the rules cover that it doesn't
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Taxonomy {
+    MissingSignerCheck,
+    AccountDataMatching,
+    MissingOwnerCheck,
+    TypeCosplay,
+    Reinitialization,
+    ArbitraryCpi,
+    DuplicateMutableAccounts,
+    BumpSeedCanonicalization,
+    PdaSharing,
+    ClosingAccounts,
+    SysvarAddressChecking,
+    IntegerOverflow,
+    UncheckedInput,
+    SecretExposure,
+    TransactionHandling,
+    BuildConfiguration,
+    DeploymentConfiguration,
+    Toolchain,
+    CodeQuality,
+}
+
+impl Taxonomy {
+    pub const ALL: &'static [Taxonomy] = &[
+        Taxonomy::MissingSignerCheck,
+        Taxonomy::AccountDataMatching,
+        Taxonomy::MissingOwnerCheck,
+        Taxonomy::TypeCosplay,
+        Taxonomy::Reinitialization,
+        Taxonomy::ArbitraryCpi,
+        Taxonomy::DuplicateMutableAccounts,
+        Taxonomy::BumpSeedCanonicalization,
+        Taxonomy::PdaSharing,
+        Taxonomy::ClosingAccounts,
+        Taxonomy::SysvarAddressChecking,
+        Taxonomy::IntegerOverflow,
+        Taxonomy::UncheckedInput,
+        Taxonomy::SecretExposure,
+        Taxonomy::TransactionHandling,
+        Taxonomy::BuildConfiguration,
+        Taxonomy::DeploymentConfiguration,
+        Taxonomy::Toolchain,
+        Taxonomy::CodeQuality,
+    ];
+
+    // Chart label
+    pub fn title(&self) -> &'static str {
+        match self {
+            Taxonomy::MissingSignerCheck => "Missing signer check",
+            Taxonomy::AccountDataMatching => "Account data matching",
+            Taxonomy::MissingOwnerCheck => "Missing owner check",
+            Taxonomy::TypeCosplay => "Type cosplay",
+            Taxonomy::Reinitialization => "Reinitialization",
+            Taxonomy::ArbitraryCpi => "Arbitrary CPI",
+            Taxonomy::DuplicateMutableAccounts => "Duplicate mutable accounts",
+            Taxonomy::BumpSeedCanonicalization => "Bump seed canonicalization",
+            Taxonomy::PdaSharing => "PDA sharing",
+            Taxonomy::ClosingAccounts => "Closing accounts",
+            Taxonomy::SysvarAddressChecking => "Sysvar address checking",
+            Taxonomy::IntegerOverflow => "Integer overflow",
+            Taxonomy::UncheckedInput => "Unchecked input",
+            Taxonomy::SecretExposure => "Secret exposure",
+            Taxonomy::TransactionHandling => "Transaction handling",
+            Taxonomy::BuildConfiguration => "Build configuration",
+            Taxonomy::DeploymentConfiguration => "Deployment configuration",
+            Taxonomy::Toolchain => "Toolchain",
+            Taxonomy::CodeQuality => "Code quality",
+        }
+    }
+}
+
+// Findings of one vulnerability class, by severity; suppressed findings aren't counted
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TaxonomyCount {
+    pub category: Taxonomy,
+    pub title: String,
+    pub total: u32,
+    pub critical: u32,
+    pub high: u32,
+    pub medium: u32,
+    pub low: u32,
 }
 
 #[derive(Debug, Serialize, Deserialize)]
-pub struct CodeAnalysisResponse {
+pub struct TaxonomyResponse {
     pub success: bool,
     pub message: String,
-    pub bugs: Option<Vec<CodeBug>>,
+    // Every class, including those with no findings, so charts keep the same axes
+    pub categories: Option<Vec<TaxonomyCount>>,
+    // Findings without a class: tool failures and external analyzers' findings
+    pub uncategorized: Option<u32>,
 }
 
-#[derive(Debug, Serialize, Deserialize)]
-pub struct GitHubRepo {
-    pub id: u64,
-    pub name: String,
-    pub full_name: String,
-    pub description: Option<String>,
-    pub html_url: String,
-    pub stargazers_count: u32,
-    pub forks_count: u32,
-    pub open_issues_count: u32,
-    pub owner: GitHubOwner,
-    pub language: Option<String>,
-    pub created_at: String,
-    pub updated_at: String,
+// Replace bytes [byte_start, byte_end) of a file, which must still read `original`
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TextEdit {
+    pub byte_start: usize,
+    pub byte_end: usize,
+    pub original: String,
+    pub replacement: String,
 }
 
-#[derive(Debug, Serialize, Deserialize)]
-pub struct GitHubOwner {
-    pub login: String,
-    pub avatar_url: Option<String>,
+#[derive(Debug, Serialize, Deserialize, Validate)]
+pub struct AutofixPreviewRequest {
+    pub run_id: String,
+    pub fingerprint: String,
 }
 
 #[derive(Debug, Serialize, Deserialize)]
-pub struct GitHubContent {
-    pub name: String,
-    pub path: String,
-    pub sha: String,
-    pub size: Option<u64>,
-    #[serde(rename = "type")]
-    pub content_type: String,  // "file", "dir", "symlink", etc.
-    pub download_url: Option<String>,
-    pub html_url: String,
-    pub content: Option<String>,
-    pub encoding: Option<String>,
-    pub url: String,
+pub struct AutofixPreviewResponse {
+    pub success: bool,
+    pub message: String,
+    pub file: Option<String>,
+    pub diff: Option<String>,
+    // Full content of the file with the fix applied
+    pub patched_content: Option<String>,
 }
 
-#[derive(Debug, Serialize, Deserialize)]
-pub struct RepoIngestionRequest {
-    pub repo_url: String,
+#[derive(Debug, Serialize, Deserialize, Validate)]
+pub struct CreateFixPrRequest {
+    pub run_id: String,
+    pub fingerprint: String,
 }
 
 #[derive(Debug, Serialize, Deserialize)]
-pub struct RepoIngestionResponse {
+pub struct CreateFixPrResponse {
     pub success: bool,
     pub message: String,
-    pub repo: Option<GitHubRepo>,
-    pub is_anchor_project: Option<bool>,
+    pub pull_request_url: Option<String>,
+    pub branch: Option<String>,
+    // Repository the branch was pushed to: the original, or a fork when we can't write to it
+    pub head_repo: Option<String>,
+}
+
+#[derive(Debug, Serialize, Deserialize, Validate)]
+pub struct CreateIssueRequest {
+    pub run_id: String,
+    #[serde(default)]
+    pub labels: Vec<String>,
 }
 
 #[derive(Debug, Serialize, Deserialize)]
-pub struct RepoContentsRequest {
-    pub repo_url: String,
-    pub path: Option<String>,
+pub struct CreateIssueResponse {
+    pub success: bool,
+    pub message: String,
+    pub issue_url: Option<String>,
+    pub issue_number: Option<u64>,
+}
+
+#[derive(Debug, Serialize, Deserialize, Validate)]
+pub struct CodeAnalysisRequest {
+    pub repo_url: RepoUrl,
+    // Deployed program to include an upgrade-authority check for in the report
+    pub program_id: Option<String>,
+    #[serde(default)]
+    pub cluster: Cluster,
+    // Set a `safex/analysis` commit status on the analyzed commit (GitHub repositories only)
+    #[serde(default)]
+    pub commit_status: bool,
+    // Lowest severity that fails the gate and the commit status; SAFEX_COMMIT_STATUS_FAIL_ON
+    // (high) by default
+    pub fail_on: Option<BugSeverity>,
+    // What clippy and the program's build scripts may reach; crates.io only by default
+    #[serde(default)]
+    pub network_policy: NetworkPolicy,
+    // `cargo vendor` the dependencies before the offline build (the tenant's directory in SAFEX_VENDOR_DIR collects them)
+    #[serde(default)]
+    pub vendor_dependencies: bool,
+    // Also build the programs and check the binaries: size, stack frames, syscalls and
+    // key material in log messages
+    #[serde(default)]
+    pub build_program: bool,
+    #[serde(default)]
+    pub mode: AnalysisMode,
+    // Rule set to analyze with, e.g. "1.0.0" or "1" for the newest 1.x; the newest by default
+    #[validate(custom(function = "crate::validation::ruleset_version"))]
+    pub ruleset_version: Option<String>,
+    // Globs of paths whose findings are left out, e.g. "**/tests/**" or "migrations/"; added
+    // to `[analysis] exclude_paths` in the repository's .safex.toml when the server trusts it
+    // (SAFEX_REPO_EXCLUSIONS)
+    #[serde(default)]
+    #[validate(custom(function = "crate::validation::exclude_paths"))]
+    pub exclude_paths: Vec<String>,
+}
+
+// Source pasted into an editor or playground, checked without a repository
+#[derive(Debug, Serialize, Deserialize, Validate)]
+pub struct SnippetAnalysisRequest {
+    #[validate(custom(function = "crate::validation::snippet_source"))]
+    pub source: String,
+    // Path the findings are reported against; snippet.rs by default
+    #[validate(length(min = 1, max = 256, message = "must be between 1 and 256 characters"))]
+    pub file_name: Option<String>,
+    #[validate(custom(function = "crate::validation::ruleset_version"))]
+    pub ruleset_version: Option<String>,
 }
 
 #[derive(Debug, Serialize, Deserialize)]
-pub struct RepoContentsResponse {
+pub struct SnippetAnalysisResponse {
     pub success: bool,
     pub message: String,
-    pub contents: Option<Vec<GitHubContent>>,
-    pub file_content: Option<GitHubContent>,
+    pub bugs: Option<Vec<CodeBug>>,
+    pub elapsed_ms: Option<u64>,
+    pub meta: Option<ExecutionMeta>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum AnalysisMode {
+    #[default]
+    Full,
+    // Only the regex rule passes, within a 10 second budget. Nothing is compiled or fetched,
+    // and the run isn't recorded, reported or posted as a commit status.
+    Quick,
+    // A full analysis plus `cargo check --all-features`, pedantic and nursery clippy lints
+    // and the taint passes, within SAFEX_DEEP_SCAN_BUDGET_SECS
+    Deep,
+}
+
+// What produced a result, so it can be audited and reproduced later
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ExecutionMeta {
+    // `--version` output of the tools as the job ran them; None when a tool isn't installed
+    // or the job didn't use any
+    pub rustc_version: Option<String>,
+    pub clippy_version: Option<String>,
+    pub anchor_version: Option<String>,
+    pub solana_version: Option<String>,
+    pub rule_set_version: String,
+    // Digest of the image the service and its sandboxed builds run in, from SAFEX_SANDBOX_IMAGE_DIGEST
+    pub sandbox_image_digest: Option<String>,
+    // The request's phases in the order they ran
+    pub phases: Vec<PhaseTiming>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PhaseTiming {
+    pub phase: String,
+    pub elapsed_ms: u64,
+}
+
+// A rule pass starting, or skipped for lack of budget, with the time since analysis began
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ProgressEvent {
+    pub stage: String,
+    pub elapsed_ms: u64,
+    pub skipped: bool,
+}
+
+// What a running analysis reports as it goes: each pass as it starts, and its findings once
+// it's done, already fingerprinted. Streamed to clients at /api/jobs/{id}/events.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum AnalysisEvent {
+    Progress(ProgressEvent),
+    Findings { stage: String, bugs: Vec<CodeBug> },
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum CommitState {
+    Success,
+    Failure,
+}
+
+impl CommitState {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            CommitState::Success => "success",
+            CommitState::Failure => "failure",
+        }
+    }
+}
+
+// The commit status set for an analysis, or why it couldn't be
+#[derive(Debug, Serialize, Deserialize)]
+pub struct CommitStatus {
+    pub context: String,
+    pub sha: String,
+    pub state: CommitState,
+    pub description: String,
+    pub target_url: Option<String>,
+    pub posted: bool,
+    pub error: Option<String>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct CodeAnalysisResponse {
+    pub success: bool,
+    pub message: String,
+    // No unsuppressed finding at or above fail_on; false when the analysis didn't complete
+    pub gate_passed: bool,
+    pub bugs: Option<Vec<CodeBug>>,
+    // Findings triaged as false positive / accepted risk in earlier runs
+    pub suppressed_bugs: Option<Vec<CodeBug>>,
+    pub run_id: Option<String>,
+    // Storage key of the rendered HTML report, downloadable via /api/artifacts
+    pub report_artifact: Option<String>,
+    pub deployment: Option<DeploymentInfo>,
+    pub commit_status: Option<CommitStatus>,
+    pub network: Option<NetworkReport>,
+    // Toolchains provisioned for what the repository pins in rust-toolchain.toml and Anchor.toml
+    pub toolchains: Option<ProvisionedToolchains>,
+    // Checks on the built binaries, when build_program was set and the build succeeded
+    pub binary_findings: Option<Vec<BinaryFinding>>,
+    // The programs the code invokes, checked against the .safex.toml allowlist
+    pub cpi_surface: Option<CpiSurface>,
+    // The path globs the analysis left out, from the request and a trusted .safex.toml
+    pub exclusions: Option<Vec<String>>,
+    // The analysis passes in the order they ran
+    pub progress: Option<Vec<ProgressEvent>>,
+    pub meta: Option<ExecutionMeta>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CpiLocation {
+    pub file: String,
+    pub line: u32,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CpiTarget {
+    // None when the program type is defined outside the repository
+    pub program_id: Option<String>,
+    pub name: String,
+    // Whether the allowlist lists it; None without an allowlist or a program_id
+    pub allowed: Option<bool>,
+    pub locations: Vec<CpiLocation>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CpiSurface {
+    // Whether .safex.toml declares `[cpi] allowed_programs`
+    pub declared: bool,
+    pub targets: Vec<CpiTarget>,
+    // Allowlisted programs the code never invokes
+    pub unused_entries: Vec<String>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct BinaryFinding {
+    // None for stack frame warnings, which the build reports for the whole workspace
+    pub program: Option<String>,
+    // "size", "stack_size", "syscalls" or "sensitive_logging"
+    pub check: String,
+    pub severity: BugSeverity,
+    pub message: String,
+    // The syscalls, functions or strings the finding is about
+    pub details: Vec<String>,
+}
+
+// Sandbox Models
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct BlockedConnection {
+    pub host: String,
+    pub port: u16,
+    pub attempts: u32,
+}
+
+// The network policy a run's builds used and the connections it refused
+#[derive(Debug, Serialize, Deserialize)]
+pub struct NetworkReport {
+    pub policy: NetworkPolicy,
+    // Whether builds ran in their own network namespace; otherwise only clients honouring
+    // the proxy settings were held back
+    pub isolated: bool,
+    // Whether builds used vendored dependencies, the repository's own or vendored for the run
+    pub vendored: bool,
+    // Destinations refused by the sandbox proxy. Inside a namespace, connections that
+    // bypass the proxy fail outright and aren't listed.
+    pub blocked: Vec<BlockedConnection>,
+}
+
+// Rule Catalog Models
+#[derive(Debug, Serialize, Deserialize)]
+pub struct RuleReference {
+    pub title: String,
+    pub url: String,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct Rule {
+    pub id: String,
+    pub version: u32,
+    pub title: String,
+    pub category: String,
+    pub taxonomy: Taxonomy,
+    pub severity: BugSeverity,
+    // "anchor", "native" or "all"
+    pub applies_to: String,
+    pub description: String,
+    pub vulnerable_example: String,
+    pub fixed_example: String,
+    pub references: Vec<RuleReference>,
+}
+
+// A released rule set and the version of each rule it runs
+#[derive(Debug, Serialize, Deserialize)]
+pub struct RuleSetInfo {
+    pub version: String,
+    pub released: String,
+    // Set once a newer rule set is released; pinning fails after this date
+    pub supported_until: Option<String>,
+    pub rules: BTreeMap<String, u32>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct RulesResponse {
+    pub success: bool,
+    pub message: String,
+    pub rules: Vec<Rule>,
+    // Newest first; analyses run the first unless they pin ruleset_version
+    pub rule_sets: Vec<RuleSetInfo>,
+}
+
+// Triage Models
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub enum TriageState {
+    #[serde(rename = "open")]
+    Open,
+    #[serde(rename = "false_positive")]
+    FalsePositive,
+    #[serde(rename = "accepted_risk")]
+    AcceptedRisk,
+    #[serde(rename = "fixed")]
+    Fixed,
+}
+
+impl TriageState {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            TriageState::Open => "open",
+            TriageState::FalsePositive => "false_positive",
+            TriageState::AcceptedRisk => "accepted_risk",
+            TriageState::Fixed => "fixed",
+        }
+    }
+    
+    pub fn parse(value: &str) -> Option<Self> {
+        match value {
+            "open" => Some(TriageState::Open),
+            "false_positive" => Some(TriageState::FalsePositive),
+            "accepted_risk" => Some(TriageState::AcceptedRisk),
+            "fixed" => Some(TriageState::Fixed),
+            _ => None,
+        }
+    }
+    
+    // Suppressed findings are reported separately instead of in the main list
+    pub fn is_suppressed(&self) -> bool {
+        matches!(self, TriageState::FalsePositive | TriageState::AcceptedRisk)
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct FindingTriage {
     pub repo_url: String,
+    pub fingerprint: String,
+    pub state: TriageState,
+    pub comment: Option<String>,
+    pub updated_at: i64,
+}
+
+#[derive(Debug, Serialize, Deserialize, Validate)]
+pub struct TriageRequest {
+    pub repo_url: RepoUrl,
+    pub fingerprint: String,
+    pub state: TriageState,
+    pub comment: Option<String>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct TriageListQuery {
+    pub repo_url: RepoUrl,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct TriageResponse {
+    pub success: bool,
+    pub message: String,
+    pub triage: Option<Vec<FindingTriage>>,
+}
+
+// Finding Discussion Models
+#[derive(Debug, Serialize, Deserialize, Validate)]
+pub struct FindingCommentRequest {
+    pub repo_url: RepoUrl,
+    pub fingerprint: String,
+    #[validate(custom(function = "crate::validation::comment_body"))]
+    pub body: String,
+}
+
+#[derive(Debug, Serialize, Deserialize, Validate)]
+pub struct ResolveThreadRequest {
+    pub repo_url: RepoUrl,
+    pub fingerprint: String,
+    // Reopen a resolved thread instead
+    #[serde(default)]
+    pub reopen: bool,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct FindingCommentsQuery {
+    pub repo_url: RepoUrl,
+    // Only this finding's thread
+    pub fingerprint: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct FindingComment {
+    pub id: String,
+    pub author: String,
+    pub body: String,
+    pub created_at: i64,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct FindingThread {
+    pub repo_url: String,
+    pub fingerprint: String,
+    pub comments: Vec<FindingComment>,
+    pub resolved: bool,
+    pub resolved_by: Option<String>,
+    pub resolved_at: Option<i64>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct FindingThreadsResponse {
+    pub success: bool,
+    pub message: String,
+    pub threads: Option<Vec<FindingThread>>,
+}
+
+// Finding Assignment Models
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct FindingAssignment {
+    pub repo_url: String,
+    pub fingerprint: String,
+    pub assignee: String,
+    pub assigned_by: String,
+    pub assigned_at: i64,
+    // The finding in the repository's latest completed run; None once that run no longer has it
+    pub finding: Option<CodeBug>,
+}
+
+#[derive(Debug, Serialize, Deserialize, Validate)]
+pub struct AssignFindingRequest {
+    pub repo_url: RepoUrl,
+    pub fingerprint: String,
+    // Team member to assign, e.g. the actor of their API key; None unassigns the finding
+    #[validate(length(min = 1, max = 128, message = "must be between 1 and 128 characters"))]
+    pub assignee: Option<String>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct AssignedFindingsQuery {
+    // The caller by default
+    pub assignee: Option<String>,
+    pub repo_url: Option<RepoUrl>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct FindingAssignmentsResponse {
+    pub success: bool,
+    pub message: String,
+    pub assignments: Option<Vec<FindingAssignment>>,
+}
+
+// Engagement Models
+// Where an audit engagement stands. It moves forward a step at a time, and from review back to
+// in progress when findings need another look.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum EngagementState {
+    Scoping,
+    InProgress,
+    Review,
+    // The final report is linked and handed over
+    Delivered,
+    // The final report's log is confirmed on chain
+    AnchoredOnChain,
+}
+
+impl EngagementState {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            EngagementState::Scoping => "scoping",
+            EngagementState::InProgress => "in_progress",
+            EngagementState::Review => "review",
+            EngagementState::Delivered => "delivered",
+            EngagementState::AnchoredOnChain => "anchored_on_chain",
+        }
+    }
+
+    pub fn parse(value: &str) -> Option<Self> {
+        match value {
+            "scoping" => Some(EngagementState::Scoping),
+            "in_progress" => Some(EngagementState::InProgress),
+            "review" => Some(EngagementState::Review),
+            "delivered" => Some(EngagementState::Delivered),
+            "anchored_on_chain" => Some(EngagementState::AnchoredOnChain),
+            _ => None,
+        }
+    }
+
+    pub fn can_move_to(&self, next: EngagementState) -> bool {
+        use EngagementState::*;
+        matches!(
+            (self, next),
+            (Scoping, InProgress) | (InProgress, Review) | (Review, InProgress) | (Review, Delivered) | (Delivered, AnchoredOnChain)
+        )
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum EngagementRunKind {
+    // An analysis run, as in /api/analyses/{run_id}
+    Analysis,
+    // A queued fuzz or regression fuzz job
+    Fuzz,
+}
+
+impl EngagementRunKind {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            EngagementRunKind::Analysis => "analysis",
+            EngagementRunKind::Fuzz => "fuzz",
+        }
+    }
+
+    pub fn parse(value: &str) -> Option<Self> {
+        match value {
+            "analysis" => Some(EngagementRunKind::Analysis),
+            "fuzz" => Some(EngagementRunKind::Fuzz),
+            _ => None,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct EngagementRun {
+    pub kind: EngagementRunKind,
+    pub run_id: String,
+    // The analyzed commit; None for fuzz runs
+    pub commit_sha: Option<String>,
+    pub added_by: String,
+    pub added_at: i64,
+    // A fuzz run's results as they were when it was added
+    pub result: Option<serde_json::Value>,
+}
+
+// One audit of a repository: the commit range in scope, the runs made for it, the triage of
+// their findings and the report delivered at the end
+#[derive(Debug, Serialize, Deserialize)]
+pub struct Engagement {
+    pub id: String,
+    pub name: String,
+    pub repo_url: String,
+    pub base_commit: Option<String>,
+    pub head_commit: Option<String>,
+    pub state: EngagementState,
+    // The final report's log, as in /api/jobs/{job_id}
+    pub report_log_id: Option<String>,
+    pub created_by: String,
+    pub created_at: i64,
+    pub updated_at: i64,
+    // Set when a single engagement is returned
+    pub runs: Option<Vec<EngagementRun>>,
+    // Triage decisions on the findings of the engagement's analysis runs
+    pub triage: Option<Vec<FindingTriage>>,
+    pub report: Option<JobInfo>,
+}
+
+#[derive(Debug, Serialize, Deserialize, Validate)]
+pub struct EngagementRequest {
+    #[validate(length(min = 1, max = 200, message = "must be between 1 and 200 characters"))]
+    pub name: String,
+    pub repo_url: RepoUrl,
+    #[validate(custom(function = "crate::validation::commit_sha"))]
+    pub base_commit: Option<String>,
+    #[validate(custom(function = "crate::validation::commit_sha"))]
+    pub head_commit: Option<String>,
+}
+
+// Fields left out stay as they are
+#[derive(Debug, Serialize, Deserialize, Validate)]
+pub struct EngagementUpdateRequest {
+    #[validate(length(min = 1, max = 200, message = "must be between 1 and 200 characters"))]
+    pub name: Option<String>,
+    #[validate(custom(function = "crate::validation::commit_sha"))]
+    pub base_commit: Option<String>,
+    #[validate(custom(function = "crate::validation::commit_sha"))]
+    pub head_commit: Option<String>,
+    pub state: Option<EngagementState>,
+    pub report_log_id: Option<String>,
+}
+
+#[derive(Debug, Serialize, Deserialize, Validate)]
+pub struct EngagementRunRequest {
+    pub kind: EngagementRunKind,
+    #[validate(length(min = 1, max = 64, message = "must be between 1 and 64 characters"))]
+    pub run_id: String,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct EngagementsQuery {
+    pub state: Option<EngagementState>,
+    pub repo_url: Option<RepoUrl>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct EngagementResponse {
+    pub success: bool,
+    pub message: String,
+    pub engagement: Option<Engagement>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct EngagementsResponse {
+    pub success: bool,
+    pub message: String,
+    pub engagements: Option<Vec<Engagement>>,
+}
+
+// Email Delivery Models
+#[derive(Debug, Serialize, Deserialize)]
+pub struct EmailRecipient {
+    pub email: String,
+    pub active: bool,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct EmailSettings {
+    pub recipients: Vec<EmailRecipient>,
+    // None means the built-in template is used
+    pub subject_template: Option<String>,
+    pub body_template: Option<String>,
+}
+
+#[derive(Debug, Serialize, Deserialize, Validate)]
+pub struct EmailSettingsRequest {
+    pub recipients: Option<Vec<String>>,
+    pub subject_template: Option<String>,
+    pub body_template: Option<String>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct EmailSettingsResponse {
+    pub success: bool,
+    pub message: String,
+    pub settings: Option<EmailSettings>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct UnsubscribeQuery {
+    pub token: String,
+}
+
+// Report Template Models
+// An on-chain record of a report for the same repository and commit
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ReportAnchor {
+    pub backend: AnchorBackend,
+    pub transaction_signature: String,
+    pub hash: String,
+    pub status: String,
+    pub receipt: Option<String>,
+    pub logged_at: i64,
+    // Set when verifying a report: whether the anchor was checked against the backend itself,
+    // and why it couldn't be. Otherwise it's only this server's record of it.
+    pub verified: Option<bool>,
+    pub verification_error: Option<String>,
+}
+
+#[derive(Debug, Serialize, Deserialize, Validate)]
+pub struct ReportTemplateRequest {
+    // Handlebars; see report::template_data for the placeholders it can use
+    #[validate(custom(function = "crate::validation::report_template"))]
+    pub template: String,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct ReportTemplate {
+    pub template: String,
+    pub updated_by: String,
+    pub updated_at: i64,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct ReportTemplateResponse {
+    pub success: bool,
+    pub message: String,
+    pub template: Option<ReportTemplate>,
+}
+
+// Analysis History Models
+#[derive(Debug, Serialize, Deserialize)]
+pub struct TrendsQuery {
+    pub repo_url: RepoUrl,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct TrendPoint {
+    pub run_id: String,
+    pub created_at: i64,
+    pub commit_sha: Option<String>,
+    pub total: u32,
+    pub critical: u32,
+    pub high: u32,
+    pub medium: u32,
+    pub low: u32,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct TrendsResponse {
+    pub success: bool,
+    pub message: String,
+    pub repo_url: String,
+    pub points: Option<Vec<TrendPoint>>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct CompareQuery {
+    pub run_a: String,
+    pub run_b: String,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct CompareResponse {
+    pub success: bool,
+    pub message: String,
+    pub run_a: String,
+    pub run_b: String,
+    pub commit_a: Option<String>,
+    pub commit_b: Option<String>,
+    // Present in run_b but not run_a
+    pub new_findings: Option<Vec<CodeBug>>,
+    // Present in run_a but gone from run_b
+    pub resolved_findings: Option<Vec<CodeBug>>,
+    pub unchanged_count: Option<usize>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum FindingSort {
+    // The order the analyzer reported them in
+    #[default]
+    Position,
+    Severity,
+    // By file, then line
+    File,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum SortOrder {
+    Asc,
+    Desc,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct FindingsQuery {
+    pub severity: Option<BugSeverity>,
+    // Matches a file or everything under a directory
+    pub file: Option<String>,
+    pub rule_id: Option<String>,
+    #[serde(default)]
+    pub sort: FindingSort,
+    // Descending by default for severity (high first), ascending otherwise
+    pub order: Option<SortOrder>,
+    // 1-based; ignored when a cursor is given
+    pub page: Option<u32>,
+    pub per_page: Option<u32>,
+    // next_cursor from the previous page
+    pub cursor: Option<String>,
+}
+
+impl FindingsQuery {
+    pub fn order(&self) -> SortOrder {
+        self.order.unwrap_or(match self.sort {
+            FindingSort::Severity => SortOrder::Desc,
+            _ => SortOrder::Asc,
+        })
+    }
+    
+    pub fn page(&self) -> u32 {
+        self.page.unwrap_or(1).max(1)
+    }
+    
+    pub fn per_page(&self) -> u32 {
+        self.per_page.unwrap_or(50).clamp(1, 500)
+    }
+}
+
+// Where a findings page left off: the sort key values and rowid of its last finding. Sent to
+// clients as an opaque token; the sort it was made for is kept so it can't be reused with another.
+#[derive(Debug, Serialize, Deserialize)]
+pub struct FindingsCursor {
+    pub sort: FindingSort,
+    pub order: SortOrder,
+    pub keys: Vec<serde_json::Value>,
+    pub rowid: i64,
+}
+
+impl FindingsCursor {
+    pub fn encode(&self) -> String {
+        base64::encode_config(serde_json::to_vec(self).unwrap_or_default(), base64::URL_SAFE_NO_PAD)
+    }
+    
+    pub fn decode(token: &str) -> Option<Self> {
+        let json = base64::decode_config(token, base64::URL_SAFE_NO_PAD).ok()?;
+        serde_json::from_slice(&json).ok()
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct FindingsResponse {
+    pub success: bool,
+    pub message: String,
+    pub run_id: String,
+    pub findings: Option<Vec<CodeBug>>,
+    // Findings matching the filters across all pages
+    pub total: Option<u64>,
+    // Set when paging by page number
+    pub page: Option<u32>,
+    pub per_page: Option<u32>,
+    // Pass as `cursor` for the next page; None on the last one
+    pub next_cursor: Option<String>,
+}
+
+// Job Queue Models
+#[derive(Debug, Serialize, Deserialize)]
+pub struct JobInfo {
+    pub id: String,
+    pub kind: String,
+    // queued, running, completed or failed; report logs go submitted, processed,
+    // confirmed, finalized (or failed)
+    pub status: String,
+    pub attempts: u32,
+    pub worker: Option<String>,
+    pub created_at: i64,
+    pub updated_at: i64,
+    // The analyze/fuzz response body once completed; for report logs, the transaction
+    pub result: Option<serde_json::Value>,
+    pub error: Option<String>,
+    // How far the job's clone has got, while it runs
+    pub clone_progress: Option<CloneProgress>,
+    // A continuous fuzz job's latest results, while it runs
+    pub checkpoint: Option<FuzzCheckpoint>,
+    // Set on fuzz jobs queued for an instruction that changed between two commits
+    pub regression: Option<RegressionFuzz>,
+    // Queued jobs only; report logs have neither
+    pub priority: Option<JobPriority>,
+    // Jobs that will be leased before this one while it's queued, unless more urgent ones arrive
+    pub queue_position: Option<u64>,
+    // Only used for access checks, never returned to clients
+    #[serde(skip)]
+    pub tenant: String,
+}
+
+// Transfer progress of a clone, as git reports it
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct CloneProgress {
+    pub received_objects: usize,
+    pub total_objects: usize,
+    // Objects unpacked after they arrive; the clone is done when all are indexed
+    pub indexed_objects: usize,
+    pub received_bytes: u64,
+    // Objects received out of the total
+    pub percent: u8,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct WorkerInfo {
+    pub id: String,
+    pub last_heartbeat: i64,
+    pub active_jobs: u32,
+    // SAFEX_WORKER_CONCURRENCY; None for workers that don't report it yet
+    pub slots: Option<u32>,
+}
+
+// Queued jobs are leased class by class, most urgent first, and round-robin across tenants
+// within a class
+#[derive(Debug, Clone, Copy, PartialEq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum JobPriority {
+    // Someone is waiting on the result; not for jobs submitted through /api/jobs
+    Interactive,
+    // Pushes and pipelines
+    Ci,
+    // Batches and periodic scans, and what submitted jobs get unless they ask for ci
+    #[default]
+    Scheduled,
+}
+
+impl JobPriority {
+    // In the order they are leased
+    pub const ALL: [JobPriority; 3] = [JobPriority::Interactive, JobPriority::Ci, JobPriority::Scheduled];
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            JobPriority::Interactive => "interactive",
+            JobPriority::Ci => "ci",
+            JobPriority::Scheduled => "scheduled",
+        }
+    }
+
+    pub fn parse(value: &str) -> Option<Self> {
+        Self::ALL.into_iter().find(|priority| priority.as_str() == value)
+    }
+}
+
+#[derive(Debug, Deserialize)]
+pub struct JobSubmitQuery {
+    #[serde(default)]
+    pub priority: JobPriority,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct JobSubmitResponse {
+    pub success: bool,
+    pub message: String,
+    pub job_id: Option<String>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct JobStatusResponse {
+    pub success: bool,
+    pub message: String,
+    pub job: Option<JobInfo>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct WorkersResponse {
+    pub success: bool,
+    pub message: String,
+    pub workers: Option<Vec<WorkerInfo>>,
+}
+
+// Admin Dashboard Models
+// A job a worker holds a lease on
+#[derive(Debug, Serialize, Deserialize)]
+pub struct ActiveJob {
+    pub id: String,
+    pub kind: String,
+    pub tenant: String,
+    pub priority: Option<JobPriority>,
+    pub worker: Option<String>,
+    // The phase it's in (clone, toolchains, fuzz, ...), once it has reported one
+    pub phase: Option<String>,
+    pub phase_elapsed_secs: Option<i64>,
+    pub started_at: Option<i64>,
+    pub elapsed_secs: Option<i64>,
+    pub lease_expires_at: i64,
+    pub attempts: u32,
+    pub clone_progress: Option<CloneProgress>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct QueueDepth {
+    pub priority: JobPriority,
+    pub queued: u64,
+    // Tenants with jobs waiting in the class
+    pub tenants: u64,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct JobsOverview {
+    pub active: Vec<ActiveJob>,
+    pub queued: Vec<QueueDepth>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct WorkerUtilization {
+    pub workers: Vec<WorkerInfo>,
+    pub active_jobs: u32,
+    pub slots: u32,
+    // Busy slots out of all slots; None with no live workers
+    pub utilization: Option<f64>,
+}
+
+// Space a directory the service writes to takes up
+#[derive(Debug, Serialize, Deserialize)]
+pub struct DiskUsage {
+    pub name: String,
+    pub path: String,
+    pub bytes: u64,
+    pub files: u64,
+    // Entries of caches that keep an index, or scratch directories in the temp dir
+    pub entries: Option<u64>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct GitHubQuota {
+    // Whether the numbers are for GITHUB_TOKEN or the server's unauthenticated address
+    pub authenticated: bool,
+    pub limit: u64,
+    pub remaining: u64,
+    pub used: u64,
+    pub resets_at: i64,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PayerBalance {
+    pub cluster: String,
+    pub pubkey: String,
+    pub lamports: u64,
+    pub sol: f64,
+    // SAFEX_PAYER_MIN_LAMPORTS
+    pub min_lamports: u64,
+    pub low: bool,
+    pub checked_at: i64,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct HealthResponse {
+    pub success: bool,
+    pub message: String,
+    // "ok", or "degraded" while something needs an operator soon
+    pub status: String,
+    // The last periodic check; None without a configured payer or before the first check
+    pub payer: Option<PayerBalance>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct AdminDashboardResponse {
+    pub success: bool,
+    pub message: String,
+    pub generated_at: i64,
+    // Sections that couldn't be read are left out and explained in `warnings`
+    pub jobs: Option<JobsOverview>,
+    pub workers: Option<WorkerUtilization>,
+    pub temp_dir: Option<DiskUsage>,
+    pub caches: Option<Vec<DiskUsage>>,
+    pub github: Option<GitHubQuota>,
+    pub payer: Option<PayerBalance>,
+    pub warnings: Vec<String>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct GitHubRepo {
+    pub id: u64,
+    pub name: String,
+    pub full_name: String,
+    pub description: Option<String>,
+    pub html_url: String,
+    pub stargazers_count: u32,
+    pub forks_count: u32,
+    pub open_issues_count: u32,
+    pub owner: GitHubOwner,
+    pub language: Option<String>,
+    pub created_at: String,
+    pub updated_at: String,
+    pub default_branch: Option<String>,
+    // Only present on authenticated requests
+    pub permissions: Option<GitHubPermissions>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct GitHubPermissions {
+    #[serde(default)]
+    pub push: bool,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct GitHubOwner {
+    pub login: String,
+    pub avatar_url: Option<String>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct GitHubContent {
+    pub name: String,
+    pub path: String,
+    pub sha: String,
+    pub size: Option<u64>,
+    #[serde(rename = "type")]
+    pub content_type: String,  // "file", "dir", "symlink", etc.
+    pub download_url: Option<String>,
+    pub html_url: String,
+    pub content: Option<String>,
+    pub encoding: Option<String>,
+    pub url: String,
+}
+
+// Recursive tree listing from the git trees API
+#[derive(Debug, Serialize, Deserialize)]
+pub struct GitHubTree {
+    pub sha: String,
+    pub tree: Vec<GitHubTreeEntry>,
+    // GitHub stops listing past 100,000 entries
+    pub truncated: bool,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct GitHubTreeEntry {
+    pub path: String,
+    #[serde(rename = "type")]
+    pub entry_type: String,  // "blob", "tree" or "commit" (submodule)
+    // Blobs only
+    pub size: Option<u64>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub enum ProjectType {
+    #[serde(rename = "anchor")]
+    Anchor,
+    #[serde(rename = "native")]
+    Native,
+}
+
+#[derive(Debug, Serialize, Deserialize, Validate)]
+pub struct RepoIngestionRequest {
+    pub repo_url: RepoUrl,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct RepoIngestionResponse {
+    pub success: bool,
+    pub message: String,
+    pub repo: Option<GitHubRepo>,
+    pub repo_name: Option<String>,
+    pub is_anchor_project: Option<bool>,
+    pub project_type: Option<ProjectType>,
+}
+
+#[derive(Debug, Serialize, Deserialize, Validate)]
+pub struct RepoContentsRequest {
+    pub repo_url: RepoUrl,
+    pub path: Option<String>,
+}
+
+// Raw download of a single file; `ref` is a branch, tag or commit (default branch if omitted)
+#[derive(Debug, Serialize, Deserialize)]
+pub struct RepoFileQuery {
+    pub repo_url: RepoUrl,
+    pub path: String,
+    #[serde(rename = "ref")]
+    pub git_ref: Option<String>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct RepoContentsResponse {
+    pub success: bool,
+    pub message: String,
+    pub contents: Option<Vec<GitHubContent>>,
+    pub file_content: Option<GitHubContent>,
+    pub repo_url: String,
+    pub path: String,
+}
+#[derive(Debug, Serialize, Deserialize, Validate)]
+pub struct RepoFilesRequest {
+    pub repo_url: RepoUrl,
+    pub paths: Vec<String>,
+}
+
+// One requested file; `error` is set instead of `content` when it couldn't be read
+#[derive(Debug, Serialize, Deserialize)]
+pub struct RepoFile {
+    pub path: String,
+    pub size: Option<u64>,
+    pub content: Option<String>,
+    // "utf-8" for text, "base64" for binary files
+    pub encoding: Option<String>,
+    pub error: Option<String>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct RepoFilesResponse {
+    pub success: bool,
+    pub message: String,
+    pub files: Option<Vec<RepoFile>>,
+}
+
+#[derive(Debug, Serialize, Deserialize, Validate)]
+pub struct RepoStatsRequest {
+    pub repo_url: RepoUrl,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct ProgramStats {
+    pub name: String,
+    pub path: String,
+    pub instructions: u32,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct RepoStats {
+    // Bytes per language, by file extension
+    pub languages: BTreeMap<String, u64>,
+    pub program_count: u32,
+    pub instruction_count: u32,
+    pub programs: Vec<ProgramStats>,
+    // Non-blank lines of Rust in program crates, excluding their tests
+    pub program_rust_lines: u64,
+    // Non-blank lines of Rust/TypeScript/JavaScript tests
+    pub test_lines: u64,
+    // Distinct crates across all manifests
+    pub dependency_count: u32,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct RepoStatsResponse {
+    pub success: bool,
+    pub message: String,
+    pub stats: Option<RepoStats>,
+}
+
+// Analysis Estimate Models
+#[derive(Debug, Serialize, Deserialize, Validate)]
+pub struct EstimateRequest {
+    pub repo_url: RepoUrl,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct EstimatedProgram {
+    pub name: String,
     pub path: String,
-}
\ No newline at end of file
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct AnalysisEstimate {
+    pub default_branch: String,
+    pub project_type: Option<ProjectType>,
+    pub programs: Vec<EstimatedProgram>,
+    pub rust_files: u32,
+    pub rust_bytes: u64,
+    // TypeScript/JavaScript under tests/ and app/, which the client lints cover
+    pub typescript_files: u32,
+    pub estimated_duration_secs: u64,
+    pub fuzzing_feasible: bool,
+    // Why fuzzing isn't feasible
+    pub fuzzing_note: Option<String>,
+    // The listing was cut short by GitHub, so counts are lower bounds
+    pub truncated: bool,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct EstimateResponse {
+    pub success: bool,
+    pub message: String,
+    pub estimate: Option<AnalysisEstimate>,
+}
+
+// Instruction Inventory Models
+#[derive(Debug, Serialize, Deserialize, Validate)]
+pub struct InstructionsRequest {
+    pub repo_url: RepoUrl,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct InstructionArg {
+    pub name: String,
+    #[serde(rename = "type")]
+    pub ty: String,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct InstructionInfo {
+    pub name: String,
+    // File the handler or instruction enum is declared in
+    pub path: String,
+    // Handler arguments after the context, or the enum variant's fields
+    pub args: Vec<InstructionArg>,
+    // Anchor: the #[derive(Accounts)] struct; native instructions have none
+    pub accounts_struct: Option<String>,
+    // Fields of the accounts struct, or the numbered accounts in the variant's doc comment;
+    // None when neither could be found
+    pub account_count: Option<u32>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ProgramInstructions {
+    pub name: String,
+    pub path: String,
+    pub project_type: ProjectType,
+    pub instructions: Vec<InstructionInfo>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct InstructionsResponse {
+    pub success: bool,
+    pub message: String,
+    pub programs: Option<Vec<ProgramInstructions>>,
+}
+
+// Account Graph Models
+#[derive(Debug, Clone, Copy, PartialEq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum GraphFormat {
+    #[default]
+    Json,
+    // Graphviz source, returned as text/vnd.graphviz
+    Dot,
+}
+
+#[derive(Debug, Serialize, Deserialize, Validate)]
+pub struct AccountGraphRequest {
+    pub repo_url: RepoUrl,
+    #[serde(default)]
+    pub format: GraphFormat,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum AccountKind {
+    // Program-owned data, e.g. Account<'info, Vault>
+    State,
+    // An address derived from seeds
+    Pda,
+    TokenAccount,
+    Mint,
+    // Signers and the accounts others name as their authority or owner
+    Authority,
+    Program,
+    Sysvar,
+    Other,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct AccountNode {
+    pub id: String,
+    pub program: String,
+    // The field name in Anchor accounts structs, or the description in native instruction docs
+    pub name: String,
+    pub kind: AccountKind,
+    // The data type for typed accounts, e.g. Vault
+    pub account_type: Option<String>,
+    // Seed expressions as written in the source
+    pub seeds: Option<Vec<String>>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct InstructionNode {
+    pub id: String,
+    pub program: String,
+    pub name: String,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum AccountRelation {
+    // Instruction to account
+    Reads,
+    Writes,
+    Initializes,
+    Closes,
+    Signs,
+    // Account to account: a has_one constraint, a token or mint authority, a token account's
+    // mint, or an account whose key is part of the PDA's seeds
+    HasOne,
+    Authority,
+    Mint,
+    Seed,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct GraphEdge {
+    pub from: String,
+    pub to: String,
+    pub relation: AccountRelation,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct AccountGraph {
+    pub accounts: Vec<AccountNode>,
+    pub instructions: Vec<InstructionNode>,
+    pub edges: Vec<GraphEdge>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct AccountGraphResponse {
+    pub success: bool,
+    pub message: String,
+    pub graph: Option<AccountGraph>,
+}
+
+// Rent Estimation Models
+#[derive(Debug, Serialize, Deserialize, Validate)]
+pub struct RentEstimateRequest {
+    pub repo_url: RepoUrl,
+    #[serde(default)]
+    pub cluster: Cluster,
+}
+
+// A `space = ...` value given where the account is initialized
+#[derive(Debug, Serialize, Deserialize)]
+pub struct DeclaredSpace {
+    pub path: String,
+    pub expression: String,
+    // None when the expression couldn't be evaluated
+    pub bytes: Option<u64>,
+    pub warning: Option<String>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct AccountSpaceEstimate {
+    pub name: String,
+    pub path: String,
+    pub zero_copy: bool,
+    // Serialized size including the 8-byte discriminator; a lower bound unless `exact`
+    pub size: u64,
+    pub exact: bool,
+    // Fields whose size couldn't be bounded, e.g. a String or Vec without #[max_len]
+    pub unbounded_fields: Vec<String>,
+    pub rent_exempt_lamports: Option<u64>,
+    pub declared_space: Vec<DeclaredSpace>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct RentEstimateResponse {
+    pub success: bool,
+    pub message: String,
+    pub cluster: Cluster,
+    // "rpc", or "default" when the cluster couldn't be reached and the default rent was used
+    pub rent_source: Option<String>,
+    pub accounts: Option<Vec<AccountSpaceEstimate>>,
+}
+
+// Deployment Check Models
+#[derive(Debug, Serialize, Deserialize, Validate)]
+pub struct DeploymentCheckRequest {
+    pub program_id: String,
+    #[serde(default)]
+    pub cluster: Cluster,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DeploymentInfo {
+    pub program_id: String,
+    pub cluster: Cluster,
+    // Owning loader program ID
+    pub loader: String,
+    pub upgradeable: bool,
+    pub upgrade_authority: Option<String>,
+    // "none", "wallet", "multisig" or "program_derived"
+    pub authority_kind: String,
+    pub multisig_program: Option<String>,
+    pub last_deploy_slot: Option<u64>,
+    pub risk: BugSeverity,
+    pub notes: Vec<String>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct DeploymentCheckResponse {
+    pub success: bool,
+    pub message: String,
+    pub deployment: Option<DeploymentInfo>,
+}
+
+// Audit Log Models
+#[derive(Debug, Serialize, Deserialize)]
+pub struct AuditEntry {
+    pub id: i64,
+    // API key name, or "anonymous" when keys are disabled
+    pub actor: String,
+    pub action: String,
+    // Usually the canonical repository URL
+    pub target: Option<String>,
+    pub params: serde_json::Value,
+    // "success" or "failure"
+    pub outcome: String,
+    pub detail: Option<String>,
+    pub started_at: i64,
+    pub finished_at: i64,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct AuditLogQuery {
+    pub action: Option<String>,
+    pub actor: Option<String>,
+    pub outcome: Option<String>,
+    pub target: Option<String>,
+    pub since: Option<i64>,
+    pub until: Option<i64>,
+    // Page backwards by passing the smallest id of the previous page
+    pub before_id: Option<i64>,
+    pub limit: Option<u32>,
+}
+
+// The part of a GitHub webhook delivery we act on; present on push and most repository events
+#[derive(Debug, Deserialize)]
+pub struct GitHubWebhookPayload {
+    pub repository: Option<GitHubWebhookRepository>,
+    // Push events: the branch's commit before and after the push; all zeros when the branch
+    // was created or deleted
+    pub before: Option<String>,
+    pub after: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct GitHubWebhookRepository {
+    pub full_name: String,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct WebhookResponse {
+    pub success: bool,
+    pub message: String,
+    // Cached GitHub responses dropped for the repository
+    pub invalidated: Option<usize>,
+    // The regression_fuzz job queued for the push, with SAFEX_REGRESSION_FUZZ_TENANT
+    pub job_id: Option<String>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct AuditLogResponse {
+    pub success: bool,
+    pub message: String,
+    pub entries: Option<Vec<AuditEntry>>,
+}
+
+// Jira Models
+// The API token is sealed at rest and never returned
+#[derive(Debug, Serialize, Deserialize, Validate)]
+pub struct JiraSettingsRequest {
+    // e.g. https://example.atlassian.net
+    #[validate(custom(function = "crate::validation::jira_base_url"))]
+    pub base_url: String,
+    #[validate(custom(function = "crate::validation::jira_project_key"))]
+    pub project_key: String,
+    // Jira Cloud authenticates with the account's email and an API token; without an email the
+    // token is sent as a Data Center personal access token
+    pub email: Option<String>,
+    #[validate(length(min = 1, message = "must not be empty"))]
+    pub token: String,
+    // Defaults to "Bug"
+    pub issue_type: Option<String>,
+    // Jira priority per severity; defaults to Highest, High, Medium and Low. Severities left out
+    // of a given map are created without a priority.
+    pub priorities: Option<BTreeMap<BugSeverity, String>>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct JiraSettings {
+    pub base_url: String,
+    pub project_key: String,
+    pub email: Option<String>,
+    pub issue_type: String,
+    pub priorities: BTreeMap<BugSeverity, String>,
+    pub updated_by: String,
+    pub updated_at: i64,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct JiraSettingsResponse {
+    pub success: bool,
+    pub message: String,
+    pub settings: Option<JiraSettings>,
+}
+
+#[derive(Debug, Serialize, Deserialize, Validate)]
+pub struct JiraPushRequest {
+    pub run_id: String,
+    #[validate(length(min = 1, max = 100, message = "must list between 1 and 100 findings"))]
+    pub fingerprints: Vec<String>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct JiraTicketsQuery {
+    pub repo_url: RepoUrl,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct JiraTicket {
+    pub repo_url: String,
+    pub fingerprint: String,
+    // Issue key, e.g. SEC-123
+    pub key: String,
+    pub url: String,
+    pub created_by: String,
+    pub created_at: i64,
+}
+
+// One requested finding: its ticket, whether this request created it, or why it has none
+#[derive(Debug, Serialize, Deserialize)]
+pub struct JiraPushResult {
+    pub fingerprint: String,
+    pub ticket: Option<JiraTicket>,
+    pub created: bool,
+    pub error: Option<String>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct JiraPushResponse {
+    pub success: bool,
+    pub message: String,
+    pub results: Option<Vec<JiraPushResult>>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct JiraTicketsResponse {
+    pub success: bool,
+    pub message: String,
+    pub tickets: Option<Vec<JiraTicket>>,
+}
+
+// Deploy Key Models
+#[derive(Debug, Serialize, Deserialize, Validate)]
+pub struct DeployKeyRequest {
+    pub repo_url: RepoUrl,
+}
+
+// The private half never leaves the server; only what the user adds on GitHub is returned
+#[derive(Debug, Serialize, Deserialize)]
+pub struct DeployKey {
+    pub repo_url: String,
+    pub public_key: String,
+    pub fingerprint: String,
+    pub created_by: String,
+    pub created_at: i64,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct DeployKeyResponse {
+    pub success: bool,
+    pub message: String,
+    pub deploy_keys: Option<Vec<DeployKey>>,
+}
+
+// Program Build Models
+#[derive(Debug, Serialize, Deserialize, Validate)]
+pub struct BuildProgramRequest {
+    pub repo_url: RepoUrl,
+    #[serde(default)]
+    pub network_policy: NetworkPolicy,
+    #[serde(default)]
+    pub vendor_dependencies: bool,
+    // Store each compiled .so, downloadable via /api/artifacts
+    #[serde(default)]
+    pub include_artifacts: bool,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct BuiltProgram {
+    pub name: String,
+    // Hex SHA-256 of the .so, comparable with a deployed program's data
+    pub sha256: String,
+    pub size: u64,
+    // Storage key of the .so, when include_artifacts was set
+    pub artifact: Option<String>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct BuildProgramResponse {
+    pub success: bool,
+    pub message: String,
+    pub programs: Option<Vec<BuiltProgram>>,
+    // Tail of the build output
+    pub build_log: Option<String>,
+    pub execution_time_ms: Option<u64>,
+    pub network: Option<NetworkReport>,
+    pub toolchains: Option<ProvisionedToolchains>,
+}
+
+// Account Decoder Models
+#[derive(Debug, Serialize, Deserialize, Validate)]
+pub struct DecodeAccountRequest {
+    pub repo_url: RepoUrl,
+    #[serde(default)]
+    pub cluster: Cluster,
+    // Account to fetch from the cluster...
+    pub address: Option<String>,
+    // ...or its data as base64, decoded without a cluster lookup
+    pub data: Option<String>,
+    // Decode as this account type rather than the one the discriminator names
+    #[validate(custom(function = "crate::validation::identifier"))]
+    pub account_type: Option<String>,
+}
+
+// The data's discriminator isn't the one of the account type it was decoded as
+#[derive(Debug, Serialize, Deserialize)]
+pub struct DiscriminatorMismatch {
+    pub expected: String,
+    pub actual: String,
+    // The program's account type the actual discriminator belongs to, if any
+    pub actual_type: Option<String>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct DecodedAccount {
+    pub address: Option<String>,
+    pub owner: Option<String>,
+    pub lamports: Option<u64>,
+    pub data_len: usize,
+    // Hex of the first 8 bytes
+    pub discriminator: String,
+    pub account_type: Option<String>,
+    // Named fields, as far as decoding got
+    pub fields: Option<serde_json::Value>,
+    pub discriminator_mismatch: Option<DiscriminatorMismatch>,
+    // Bytes after the decoded fields, e.g. space allocated for growth
+    pub unused_bytes: usize,
+    pub warnings: Vec<String>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct DecodeAccountResponse {
+    pub success: bool,
+    pub message: String,
+    pub account: Option<DecodedAccount>,
+}
+
+// PDA Derivation Models
+// A seed's value; integers are little-endian, as `to_le_bytes()` encodes them
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(tag = "type", content = "value", rename_all = "snake_case")]
+pub enum SeedValue {
+    // UTF-8 text, e.g. b"vault"
+    String(String),
+    // Hex bytes
+    Bytes(String),
+    Pubkey(String),
+    U8(u8),
+    U16(u16),
+    U32(u32),
+    U64(u64),
+}
+
+#[derive(Debug, Serialize, Deserialize, Validate)]
+pub struct DerivePdaRequest {
+    // Required with `seeds`; in batch mode it defaults to each program's declare_id!
+    pub program_id: Option<String>,
+    // Derive one address from these seeds...
+    pub seeds: Option<Vec<SeedValue>>,
+    // ...or every PDA whose seeds the repository's programs declare
+    pub repo_url: Option<RepoUrl>,
+    // Batch mode: values of seeds that aren't constants, by the expression they're taken from
+    // (`owner` for `owner.key().as_ref()`, `args.id` or `id` for `args.id.to_le_bytes()`)
+    #[serde(default)]
+    pub values: BTreeMap<String, SeedValue>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct DerivedPda {
+    // Batch mode: the program and the account, or the file:line of a find_program_address call
+    pub program: Option<String>,
+    pub account: Option<String>,
+    pub program_id: Option<String>,
+    // Seed expressions as written in the source, or the given seeds' values
+    pub seeds: Vec<String>,
+    pub address: Option<String>,
+    pub bump: Option<u8>,
+    // Seeds that need a value in `values` before the address can be derived
+    pub unresolved_seeds: Vec<String>,
+    pub error: Option<String>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct DerivePdaResponse {
+    pub success: bool,
+    pub message: String,
+    pub pdas: Option<Vec<DerivedPda>>,
+}
+
+// Ecosystem Models
+#[derive(Debug, Serialize, Deserialize, Validate)]
+pub struct EcosystemProjectRequest {
+    pub repo_url: RepoUrl,
+}
+
+// A registered project and its latest nightly scan; only shown to authenticated callers
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct EcosystemProject {
+    pub repo_url: String,
+    pub registered_by: String,
+    pub registered_at: i64,
+    pub scanned_at: Option<i64>,
+    pub score: Option<u32>,
+    // Findings per rule ID in the latest scan
+    pub rule_hits: Option<BTreeMap<String, u32>>,
+    // Why the latest scan failed, if it did
+    pub error: Option<String>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct EcosystemProjectsResponse {
+    pub success: bool,
+    pub message: String,
+    pub projects: Option<Vec<EcosystemProject>>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct RuleFrequency {
+    pub rule_id: String,
+    pub title: Option<String>,
+    // Scanned projects with at least one finding of the rule, and their share of all scanned
+    pub projects: u32,
+    pub project_share: f64,
+    pub findings: u32,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct ScoreBucket {
+    pub min: u32,
+    pub max: u32,
+    pub projects: u32,
+}
+
+// Aggregates over the latest scan of every project, with nothing naming a project
+#[derive(Debug, Serialize, Deserialize)]
+pub struct EcosystemStats {
+    pub registered_projects: u32,
+    pub scanned_projects: u32,
+    pub last_scanned_at: Option<i64>,
+    // Withheld, like the breakdowns below, until enough projects are scanned that they
+    // can't single one out
+    pub average_score: Option<f64>,
+    pub score_distribution: Vec<ScoreBucket>,
+    pub rules: Vec<RuleFrequency>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct EcosystemStatsResponse {
+    pub success: bool,
+    pub message: String,
+    pub stats: Option<EcosystemStats>,
+}
+
+// Self-test Models
+#[derive(Debug, Serialize, Deserialize)]
+pub struct RulePrecision {
+    pub rule_id: String,
+    pub true_positives: u32,
+    pub false_positives: u32,
+    pub false_negatives: u32,
+    // None when the rule reported nothing / nothing was expected of it
+    pub precision: Option<f64>,
+    pub recall: Option<f64>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum SelfTestMismatchKind {
+    // A marked finding the rule didn't report
+    Missed,
+    // A finding on a line with no marker for it
+    Unexpected,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct SelfTestMismatch {
+    pub fixture: String,
+    pub rule_id: String,
+    pub file: String,
+    pub line: u32,
+    pub kind: SelfTestMismatchKind,
+    // The finding's text, for unexpected findings
+    pub message: Option<String>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct SelfTestReport {
+    pub fixtures: Vec<String>,
+    pub passed: bool,
+    pub rules: Vec<RulePrecision>,
+    pub mismatches: Vec<SelfTestMismatch>,
+    // Catalog rules no fixture exercises
+    pub uncovered_rules: Vec<String>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct SelfTestResponse {
+    pub success: bool,
+    pub message: String,
+    pub report: Option<SelfTestReport>,
+}
+
+// Benchmark Models
+// One repository of the corpus as it was benchmarked
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct BenchmarkRepo {
+    pub name: String,
+    pub url: String,
+    // Commit analyzed, pinned by the corpus or the default branch's head at the time
+    pub commit: Option<String>,
+    pub cases: u32,
+    // Why the repository couldn't be analyzed; its cases are left out of the rates
+    pub error: Option<String>,
+}
+
+// The same rule's results in the latest earlier run of another version of it
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RuleBaseline {
+    pub run_id: i64,
+    pub version: u32,
+    pub detection_rate: Option<f64>,
+    pub false_positive_rate: Option<f64>,
+}
+
+// Outcome of one rule over every case: a case counts as detected when the rule reports
+// anything inside it
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RuleBenchmark {
+    pub rule_id: String,
+    pub version: u32,
+    pub true_positives: u32,
+    pub false_negatives: u32,
+    pub false_positives: u32,
+    pub true_negatives: u32,
+    // None when no case expects the rule / every case does
+    pub detection_rate: Option<f64>,
+    pub false_positive_rate: Option<f64>,
+    pub baseline: Option<RuleBaseline>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct BenchmarkRun {
+    pub id: i64,
+    pub run_at: i64,
+    pub run_by: String,
+    // "built-in", or the path of the corpus SAFEX_BENCHMARK_CORPUS named
+    pub corpus: String,
+    pub repos: Vec<BenchmarkRepo>,
+    pub rules: Vec<RuleBenchmark>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct BenchmarkResponse {
+    pub success: bool,
+    pub message: String,
+    pub run: Option<BenchmarkRun>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct BenchmarkRunsResponse {
+    pub success: bool,
+    pub message: String,
+    pub runs: Option<Vec<BenchmarkRun>>,
+}
+
+// CORS Models
+#[derive(Debug, Serialize, Deserialize, Validate)]
+pub struct CorsOriginRequest {
+    #[validate(custom(function = "crate::validation::origin"))]
+    pub origin: String,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct AllowedOrigin {
+    pub origin: String,
+    // Set by SAFEX_CORS_ORIGINS rather than through the API, so it can't be removed there
+    pub configured: bool,
+    pub added_by: Option<String>,
+    pub added_at: Option<i64>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct CorsOriginsResponse {
+    pub success: bool,
+    pub message: String,
+    pub origins: Option<Vec<AllowedOrigin>>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct ConfigReloadResponse {
+    pub success: bool,
+    pub message: String,
+    pub loaded_at: Option<i64>,
+    // The settings SAFEX_CONFIG_FILE sets, by name
+    pub overrides: Option<Vec<String>>,
+}
+
+// Validation Models
+// A request body field that failed to deserialize or broke one of its model's constraints
+#[derive(Debug, Serialize, Deserialize)]
+pub struct FieldError {
+    // Dotted path of the field, e.g. `certificate.metadata_uri`
+    pub field: String,
+    pub message: String,
+}
@@ -0,0 +1,257 @@
+use anyhow::Result;
+use regex::Regex;
+use std::collections::HashMap;
+use std::env;
+use std::fs;
+use std::path::{Path, PathBuf};
+use toml::Table;
+
+use crate::stats::{collect_files, is_test_path, line_at};
+use crate::toolchain::{anchor_rust_version, detect_anchor_version, detect_rust_toolchain, version_key};
+
+// Language and standard library features by the Rust release that stabilized them, as
+// (pattern, release, what it is). Only features common in programs are listed, so this is a
+// lower bound of what the code needs.
+const LANGUAGE_FEATURES: &[(&str, &str, &str)] = &[
+    (r"\blet\b[^;{=]*=[^;{]*\belse\s*\{", "1.65", "`let ... else`"),
+    (r"\bOnceLock\b", "1.70", "std::sync::OnceLock"),
+    (r"\.is_(?:some|ok)_and\(", "1.70", "Option::is_some_and / Result::is_ok_and"),
+    (r"\.div_ceil\(", "1.73", "unsigned div_ceil"),
+    (r#"(?m)(?:^|[ \t(,=\[])c"[^"]*""#, "1.77", "C string literals"),
+    (r"\.(?:split_)?(?:first|last)_chunk\(", "1.77", "slice first_chunk / last_chunk"),
+    (r"#\[diagnostic::", "1.78", "#[diagnostic] attributes"),
+    (r"\[\s*const\s*\{", "1.79", "inline `const` blocks"),
+    (r"\bLazy(?:Lock|Cell)\b", "1.80", "std::sync::LazyLock / std::cell::LazyCell"),
+    (r"#\[expect\(", "1.81", "#[expect] lint attributes"),
+    (r"\.is_none_or\(", "1.82", "Option::is_none_or"),
+    (r"&raw\s+(?:const|mut)\b", "1.82", "`&raw const` / `&raw mut`"),
+    (r#"\bunsafe\s+extern\s+""#, "1.82", "`unsafe extern` blocks"),
+    (r"\bis_multiple_of\(", "1.87", "unsigned is_multiple_of"),
+    (r"\bif\s+let\b[^{;]*&&\s*let\b", "1.88", "let chains"),
+];
+
+// Release that introduced each edition
+const EDITIONS: &[(&str, &str)] = &[("2018", "1.31"), ("2021", "1.56"), ("2024", "1.85")];
+
+// Something in the repository that needs at least `version` of Rust
+pub struct RustRequirement {
+    pub version: String,
+    pub reason: String,
+    // Where it's used or locked; None for the Anchor release's requirement
+    pub file: Option<String>,
+    pub line: u32,
+}
+
+// The channel a rust-toolchain file pins
+pub struct PinnedToolchain {
+    pub channel: String,
+    pub file: String,
+    pub line: u32,
+}
+
+// A `#![feature]` gate in a program crate
+pub struct NightlyFeature {
+    pub feature: String,
+    pub file: String,
+    pub line: u32,
+}
+
+pub struct ToolchainRisk {
+    pub pinned: Option<PinnedToolchain>,
+    // The newest release anything in the repository needs
+    pub required: Option<RustRequirement>,
+    pub nightly_features: Vec<NightlyFeature>,
+    // Why the rust-toolchain file couldn't be read, when it couldn't
+    pub pin_error: Option<String>,
+}
+
+impl ToolchainRisk {
+    // The required release when the pinned one is a version older than it. Channels like
+    // `stable` or a dated nightly can't be compared and never count as too old.
+    pub fn pinned_too_old(&self) -> Option<(&PinnedToolchain, &RustRequirement)> {
+        let pinned = self.pinned.as_ref()?;
+        let required = self.required.as_ref()?;
+        if !pinned.channel.starts_with(|c: char| c.is_ascii_digit()) {
+            return None;
+        }
+        let pinned_version = pinned.channel.split('-').next().unwrap_or_default();
+        (version_key(&required.version) > version_key(pinned_version)).then_some((pinned, required))
+    }
+}
+
+// The minimum Rust the code and, with `dependencies`, its locked dependencies imply, the
+// toolchain the repository pins, and the nightly-only features its on-chain programs enable
+pub fn toolchain_risk(repo_path: &Path, dependencies: bool) -> Result<ToolchainRisk> {
+    let mut files = Vec::new();
+    collect_files(repo_path, &mut files)?;
+    let relative = |file: &Path| file.strip_prefix(repo_path).unwrap_or(file).display().to_string();
+
+    let mut required: Option<RustRequirement> = None;
+    let mut require = |requirement: RustRequirement| {
+        if required.as_ref().is_none_or(|current| version_key(&requirement.version) > version_key(&current.version)) {
+            required = Some(requirement);
+        }
+    };
+
+    for manifest in files.iter().filter(|f| f.file_name().is_some_and(|name| name == "Cargo.toml")) {
+        let Ok(content) = fs::read_to_string(manifest) else { continue };
+        let Ok(table) = content.parse::<Table>() else { continue };
+        let package = table.get("package").or_else(|| table.get("workspace").and_then(|w| w.get("package")));
+        if let Some(version) = package.and_then(|p| p.get("rust-version")).and_then(|v| v.as_str()) {
+            require(RustRequirement {
+                version: version.to_string(),
+                reason: "The declared rust-version".to_string(),
+                file: Some(relative(manifest)),
+                line: key_line(&content, "rust-version"),
+            });
+        }
+        let edition = package.and_then(|p| p.get("edition")).and_then(|e| e.as_str());
+        if let Some((edition, version)) = EDITIONS.iter().find(|(name, _)| Some(*name) == edition) {
+            require(RustRequirement {
+                version: version.to_string(),
+                reason: format!("Edition {}", edition),
+                file: Some(relative(manifest)),
+                line: key_line(&content, "edition"),
+            });
+        }
+    }
+
+    let registries = if dependencies { registry_sources() } else { Vec::new() };
+    for lockfile in files.iter().filter(|f| dependencies && f.file_name().is_some_and(|name| name == "Cargo.lock")) {
+        for requirement in dependency_requirements(lockfile, &relative(lockfile), &registries) {
+            require(requirement);
+        }
+    }
+
+    // Anchor releases are built and tested against one toolchain; older ones can't compile them
+    if let Some(anchor_version) = detect_anchor_version(repo_path)? {
+        if let Some(version) = anchor_rust_version(&anchor_version) {
+            require(RustRequirement {
+                version: version.to_string(),
+                reason: format!("anchor-lang {}", anchor_version),
+                file: None,
+                line: 0,
+            });
+        }
+    }
+
+    let features: Vec<(Regex, &str, &str)> = LANGUAGE_FEATURES.iter()
+        .map(|(pattern, version, name)| (Regex::new(pattern).unwrap(), *version, *name))
+        .collect();
+    let re_feature_gate = Regex::new(r"#!\[\s*feature\s*\(([^)]*)\)\s*\]").unwrap();
+    let mut program_crates: HashMap<PathBuf, bool> = HashMap::new();
+    let mut nightly_features = Vec::new();
+    for file in files.iter().filter(|f| f.extension().is_some_and(|e| e == "rs")) {
+        let Ok(source) = fs::read_to_string(file) else { continue };
+        for (re, version, name) in &features {
+            if let Some(found) = re.find_iter(&source).find(|m| !in_comment(&source, m.start())) {
+                require(RustRequirement {
+                    version: version.to_string(),
+                    reason: name.to_string(),
+                    file: Some(relative(file)),
+                    line: line_at(&source, found.start()),
+                });
+            }
+        }
+
+        if is_test_path(repo_path, file) || !is_program_crate(repo_path, file, &mut program_crates) {
+            continue;
+        }
+        for gate in re_feature_gate.captures_iter(&source) {
+            let start = gate.get(0).unwrap().start();
+            if in_comment(&source, start) {
+                continue;
+            }
+            for feature in gate[1].split(',').map(str::trim).filter(|f| !f.is_empty()) {
+                nightly_features.push(NightlyFeature {
+                    feature: feature.to_string(),
+                    file: relative(file),
+                    line: line_at(&source, start),
+                });
+            }
+        }
+    }
+
+    let (pinned, pin_error) = match detect_rust_toolchain(repo_path) {
+        Ok(channel) => (channel, None),
+        Err(e) => (None, Some(e.to_string())),
+    };
+    let pinned = pinned.map(|channel| {
+        match fs::read_to_string(repo_path.join("rust-toolchain.toml")) {
+            Ok(content) => PinnedToolchain { channel, file: "rust-toolchain.toml".to_string(), line: key_line(&content, "channel") },
+            Err(_) => PinnedToolchain { channel, file: "rust-toolchain".to_string(), line: 1 },
+        }
+    });
+
+    Ok(ToolchainRisk { pinned, required, nightly_features, pin_error })
+}
+
+// Where cargo unpacks downloaded crates, one directory per registry
+fn registry_sources() -> Vec<PathBuf> {
+    let cargo_home = env::var_os("CARGO_HOME").map(PathBuf::from)
+        .or_else(|| env::var_os("HOME").map(|home| PathBuf::from(home).join(".cargo")));
+    cargo_home.and_then(|home| fs::read_dir(home.join("registry").join("src")).ok())
+        .map(|entries| entries.filter_map(|entry| entry.ok()).map(|entry| entry.path()).collect())
+        .unwrap_or_default()
+}
+
+// The rust-version declared by each registry package in a Cargo.lock. Only packages cargo has
+// already unpacked on this host can be read, so this too is a lower bound.
+fn dependency_requirements(lockfile: &Path, lockfile_name: &str, registries: &[PathBuf]) -> Vec<RustRequirement> {
+    let Ok(content) = fs::read_to_string(lockfile) else { return Vec::new() };
+    let Ok(lock) = content.parse::<Table>() else { return Vec::new() };
+    let packages = lock.get("package").and_then(|p| p.as_array()).cloned().unwrap_or_default();
+
+    let mut requirements = Vec::new();
+    for package in &packages {
+        let field = |key: &str| package.get(key).and_then(|v| v.as_str());
+        let (Some(name), Some(version), Some(source)) = (field("name"), field("version"), field("source")) else { continue };
+        if !source.starts_with("registry+") && !source.starts_with("sparse+") {
+            continue;
+        }
+        let rust_version = registries.iter()
+            .filter_map(|registry| fs::read_to_string(registry.join(format!("{}-{}", name, version)).join("Cargo.toml")).ok())
+            .find_map(|manifest| {
+                let manifest = manifest.parse::<Table>().ok()?;
+                manifest.get("package")?.get("rust-version")?.as_str().map(str::to_string)
+            });
+        let Some(rust_version) = rust_version else { continue };
+        let entry = format!("name = \"{}\"\nversion = \"{}\"", name, version);
+        requirements.push(RustRequirement {
+            version: rust_version,
+            reason: format!("Dependency {} {}", name, version),
+            file: Some(lockfile_name.to_string()),
+            line: content.find(&entry).map(|offset| line_at(&content, offset)).unwrap_or(0),
+        });
+    }
+    requirements
+}
+
+// Programs are built as cdylib crates; a file belongs to the crate of the closest manifest
+fn is_program_crate(repo_path: &Path, file: &Path, cache: &mut HashMap<PathBuf, bool>) -> bool {
+    let Some(crate_dir) = file.ancestors().skip(1)
+        .take_while(|dir| dir.starts_with(repo_path))
+        .find(|dir| dir.join("Cargo.toml").is_file()) else {
+        return false;
+    };
+    *cache.entry(crate_dir.to_path_buf()).or_insert_with(|| {
+        fs::read_to_string(crate_dir.join("Cargo.toml")).ok()
+            .and_then(|content| content.parse::<Table>().ok())
+            .and_then(|manifest| manifest.get("lib")?.get("crate-type")?.as_array().cloned())
+            .is_some_and(|types| types.iter().any(|t| t.as_str() == Some("cdylib")))
+    })
+}
+
+// First line setting `key`, wherever in the file (0 when it isn't set)
+fn key_line(content: &str, key: &str) -> u32 {
+    content.lines()
+        .position(|line| line.split('=').next().is_some_and(|name| name.trim() == key))
+        .map(|i| i as u32 + 1)
+        .unwrap_or(0)
+}
+
+// Whether `offset` follows a `//` on its line
+fn in_comment(source: &str, offset: usize) -> bool {
+    let line_start = source[..offset].rfind('\n').map(|i| i + 1).unwrap_or(0);
+    source[line_start..offset].contains("//")
+}
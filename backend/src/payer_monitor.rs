@@ -0,0 +1,141 @@
+use anyhow::{anyhow, Result};
+use reqwest::Client;
+use serde_json::json;
+use solana_sdk::native_token::LAMPORTS_PER_SOL;
+use std::env;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+use crate::cluster::Cluster;
+use crate::db::now_unix;
+use crate::models::PayerBalance;
+use crate::report_logger::ReportLogger;
+
+// 0.1 SOL covers rent and fees for a few dozen reports at the default registry fee
+const DEFAULT_MIN_LAMPORTS: u64 = 100_000_000;
+const DEFAULT_INTERVAL_SECS: u64 = 300;
+
+// Watches the balance of the keypair that pays for logged reports, so it can be topped up
+// before logging starts failing with insufficient funds
+pub struct PayerMonitor {
+    cluster: Cluster,
+    // SAFEX_PAYER_MIN_LAMPORTS: below this the balance is low
+    min_lamports: u64,
+    // SAFEX_PAYER_ALERT_WEBHOOK_URL is posted {"text": ...} (the Slack and Mattermost incoming
+    // webhook format) when the balance drops below the threshold and when it recovers
+    alert_webhook: Option<String>,
+    // SAFEX_PAYER_AIRDROP_LAMPORTS is requested from the faucet while the balance is low; never
+    // on mainnet-beta
+    airdrop_lamports: Option<u64>,
+    client: Client,
+    latest: Mutex<Option<PayerBalance>>,
+}
+
+impl PayerMonitor {
+    // Only with SAFEX_REPORT_PAYER_KEYPAIR; a throwaway payer has nothing worth watching
+    pub fn from_env() -> Result<Option<Self>> {
+        if env::var("SAFEX_REPORT_PAYER_KEYPAIR").is_err() {
+            return Ok(None);
+        }
+        let cluster = Cluster::registry()?;
+        let lamports = |name: &str| env::var(name).ok()
+            .map(|value| value.trim().parse::<u64>().map_err(|_| anyhow!("Invalid {}: {}", name, value)))
+            .transpose();
+        let min_lamports = lamports("SAFEX_PAYER_MIN_LAMPORTS")?.unwrap_or(DEFAULT_MIN_LAMPORTS);
+        let airdrop_lamports = lamports("SAFEX_PAYER_AIRDROP_LAMPORTS")?.filter(|lamports| *lamports > 0);
+        if airdrop_lamports.is_some() && cluster == Cluster::MainnetBeta {
+            return Err(anyhow!("SAFEX_PAYER_AIRDROP_LAMPORTS can't be used on mainnet-beta"));
+        }
+        let alert_webhook = env::var("SAFEX_PAYER_ALERT_WEBHOOK_URL").ok().filter(|url| !url.trim().is_empty());
+        if let Some(url) = &alert_webhook {
+            if !url.starts_with("https://") && !url.starts_with("http://") {
+                return Err(anyhow!("SAFEX_PAYER_ALERT_WEBHOOK_URL must be http or https: {}", url));
+            }
+        }
+        let client = Client::builder()
+            .timeout(Duration::from_secs(10))
+            .build()?;
+        Ok(Some(Self { cluster, min_lamports, alert_webhook, airdrop_lamports, client, latest: Mutex::new(None) }))
+    }
+
+    // The last balance read, without asking the RPC node
+    pub fn latest(&self) -> Option<PayerBalance> {
+        self.latest.lock().unwrap().clone()
+    }
+
+    // Read the balance, alerting when it crossed the threshold since the last check
+    pub async fn check(&self) -> Result<PayerBalance> {
+        let (pubkey, lamports) = tokio::task::spawn_blocking(|| ReportLogger::new()?.payer_balance()).await??;
+        let balance = PayerBalance {
+            cluster: self.cluster.as_str().to_string(),
+            pubkey: pubkey.to_string(),
+            lamports,
+            sol: lamports as f64 / LAMPORTS_PER_SOL as f64,
+            min_lamports: self.min_lamports,
+            low: lamports < self.min_lamports,
+            checked_at: now_unix(),
+        };
+        let was_low = self.latest.lock().unwrap().replace(balance.clone()).map(|previous| previous.low);
+
+        match (was_low, balance.low) {
+            (Some(false) | None, true) => self.alert(format!(
+                "Safex report payer {} on {} is low: {} SOL left, below the {} SOL threshold. Report logging fails once it can't cover rent and the registry fee.",
+                balance.pubkey, balance.cluster, balance.sol, self.min_lamports as f64 / LAMPORTS_PER_SOL as f64,
+            )).await,
+            (Some(true), false) => self.alert(format!(
+                "Safex report payer {} on {} is back above the threshold with {} SOL",
+                balance.pubkey, balance.cluster, balance.sol,
+            )).await,
+            _ => {},
+        }
+        if balance.low {
+            if let Some(lamports) = self.airdrop_lamports {
+                match tokio::task::spawn_blocking(move || ReportLogger::new()?.request_airdrop(lamports)).await? {
+                    Ok(signature) => println!("Requested an airdrop of {} lamports for the report payer: {}", lamports, signature),
+                    Err(e) => println!("Warning: Payer airdrop failed: {}", e),
+                }
+            }
+        }
+        Ok(balance)
+    }
+
+    async fn alert(&self, message: String) {
+        println!("Warning: {}", message);
+        let Some(url) = &self.alert_webhook else {
+            return;
+        };
+        let sent = self.client.post(url)
+            .header("User-Agent", "Safex-App")
+            .json(&json!({ "text": message }))
+            .send()
+            .await
+            .and_then(|response| response.error_for_status());
+        if let Err(e) = sent {
+            println!("Warning: Failed to send payer alert: {}", e);
+        }
+    }
+}
+
+// Checks the balance every SAFEX_PAYER_CHECK_INTERVAL_SECS (5 minutes by default; 0 turns
+// periodic checks off, leaving only the ones the admin dashboard makes)
+pub fn spawn_payer_monitor(monitor: Arc<PayerMonitor>) -> Result<()> {
+    let secs: u64 = match env::var("SAFEX_PAYER_CHECK_INTERVAL_SECS") {
+        Ok(value) => value.trim().parse().map_err(|_| anyhow!("Invalid SAFEX_PAYER_CHECK_INTERVAL_SECS: {}", value))?,
+        Err(_) => DEFAULT_INTERVAL_SECS,
+    };
+    if secs == 0 {
+        println!("Periodic payer balance checks disabled");
+        return Ok(());
+    }
+    println!("Checking the report payer balance every {}s against {} lamports", secs, monitor.min_lamports);
+    tokio::spawn(async move {
+        let mut interval = tokio::time::interval(Duration::from_secs(secs));
+        loop {
+            interval.tick().await;
+            if let Err(e) = monitor.check().await {
+                println!("Warning: Failed to check payer balance: {}", e);
+            }
+        }
+    });
+    Ok(())
+}
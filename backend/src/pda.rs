@@ -0,0 +1,228 @@
+use anyhow::{anyhow, Result};
+use regex::Regex;
+use solana_sdk::pubkey::{Pubkey, MAX_SEEDS, MAX_SEED_LEN};
+use std::collections::{BTreeMap, HashMap};
+use std::path::Path;
+use std::str::FromStr;
+
+use crate::account_graph::build_account_graph;
+use crate::hashing::decode_hex;
+use crate::instructions::program_sources;
+use crate::models::{DerivedPda, SeedValue};
+use crate::rent::top_level_split;
+
+pub fn seed_bytes(value: &SeedValue) -> Result<Vec<u8>> {
+    Ok(match value {
+        SeedValue::String(text) => text.as_bytes().to_vec(),
+        SeedValue::Bytes(text) => decode_hex(text.trim().trim_start_matches("0x")).ok_or_else(|| anyhow!("{} is not hex", text))?,
+        SeedValue::Pubkey(key) => Pubkey::from_str(key).map_err(|_| anyhow!("{} is not a base58 public key", key))?.to_bytes().to_vec(),
+        SeedValue::U8(n) => vec![*n],
+        SeedValue::U16(n) => n.to_le_bytes().to_vec(),
+        SeedValue::U32(n) => n.to_le_bytes().to_vec(),
+        SeedValue::U64(n) => n.to_le_bytes().to_vec(),
+    })
+}
+
+fn describe_seed(value: &SeedValue) -> String {
+    match value {
+        SeedValue::String(text) => format!("{:?}", text),
+        SeedValue::Bytes(hex) => format!("0x{}", hex.trim_start_matches("0x")),
+        SeedValue::Pubkey(key) => key.clone(),
+        SeedValue::U8(n) => format!("{}u8", n),
+        SeedValue::U16(n) => format!("{}u16", n),
+        SeedValue::U32(n) => format!("{}u32", n),
+        SeedValue::U64(n) => format!("{}u64", n),
+    }
+}
+
+// The canonical bump's address, as find_program_address returns it
+pub fn derive(program_id: &Pubkey, seeds: &[Vec<u8>]) -> Result<(Pubkey, u8)> {
+    // The bump takes the last of the seeds a derivation allows
+    if seeds.len() >= MAX_SEEDS {
+        return Err(anyhow!("{} seeds given, at most {} are allowed besides the bump", seeds.len(), MAX_SEEDS - 1));
+    }
+    if let Some((index, seed)) = seeds.iter().enumerate().find(|(_, seed)| seed.len() > MAX_SEED_LEN) {
+        return Err(anyhow!("Seed {} is {} bytes, more than the {} allowed", index, seed.len(), MAX_SEED_LEN));
+    }
+    let seeds: Vec<&[u8]> = seeds.iter().map(Vec::as_slice).collect();
+    Pubkey::try_find_program_address(&seeds, program_id).ok_or_else(|| anyhow!("No bump yields an address off the curve"))
+}
+
+pub fn derive_pda(program_id: &str, seeds: &[SeedValue]) -> Result<DerivedPda> {
+    let program_key = Pubkey::from_str(program_id).map_err(|_| anyhow!("Invalid program ID: {}", program_id))?;
+    let bytes = seeds.iter().enumerate()
+        .map(|(index, seed)| seed_bytes(seed).map_err(|e| anyhow!("Seed {}: {}", index, e)))
+        .collect::<Result<Vec<_>>>()?;
+    let (address, bump) = derive(&program_key, &bytes)?;
+    Ok(DerivedPda {
+        program: None,
+        account: None,
+        program_id: Some(program_id.to_string()),
+        seeds: seeds.iter().map(describe_seed).collect(),
+        address: Some(address.to_string()),
+        bump: Some(bump),
+        unresolved_seeds: Vec::new(),
+        error: None,
+    })
+}
+
+// Every PDA the repository's programs declare: Anchor accounts with `seeds = [...]` and
+// find_program_address / create_program_address calls. Literal and constant seeds resolve
+// themselves; the rest come from `values`, and PDAs missing any are listed with what's missing.
+pub fn discover_pdas(repo_path: &Path, program_id: Option<&str>, values: &BTreeMap<String, SeedValue>) -> Result<Vec<DerivedPda>> {
+    let program_key = program_id
+        .map(|id| Pubkey::from_str(id).map_err(|_| anyhow!("Invalid program ID: {}", id)))
+        .transpose()?;
+    let re_declare_id = Regex::new(r#"declare_id!\s*\(\s*"([1-9A-HJ-NP-Za-km-z]{32,44})"\s*\)"#).unwrap();
+    let re_constant = Regex::new(r#"const\s+([A-Z_][A-Z0-9_]*)\s*:\s*&(?:'static\s+)?(?:\[u8\]|str)\s*=\s*(b?"(?:[^"\\]|\\.)*")"#).unwrap();
+    let re_derivation = Regex::new(r"(?:find|create)_program_address\s*\(\s*&\s*\[").unwrap();
+
+    let graph = build_account_graph(repo_path)?;
+    let mut pdas = Vec::new();
+    for program in program_sources(repo_path)? {
+        let declared = program.sources.iter()
+            .find_map(|(_, source)| re_declare_id.captures(source))
+            .and_then(|id| Pubkey::from_str(&id[1]).ok());
+        let program_key = program_key.or(declared);
+        let constants: HashMap<String, Vec<u8>> = program.sources.iter()
+            .flat_map(|(_, source)| re_constant.captures_iter(source))
+            .filter_map(|c| Some((c[1].to_string(), literal_bytes(&c[2])?)))
+            .collect();
+        let resolver = SeedResolver { constants: &constants, values };
+
+        for account in graph.accounts.iter().filter(|a| a.program == program.name) {
+            let Some(seeds) = &account.seeds else { continue };
+            pdas.push(resolver.derive(&program.name, account.name.clone(), program_key.as_ref(), seeds.clone()));
+        }
+        for (path, source) in &program.sources {
+            for found in re_derivation.find_iter(source) {
+                let Some(seeds) = bracket_body(source, found.end() - 1) else { continue };
+                let seeds: Vec<String> = top_level_split(seeds, ',').into_iter()
+                    .map(|seed| seed.split_whitespace().collect::<Vec<_>>().join(" "))
+                    .collect();
+                let line = source[..found.start()].matches('\n').count() + 1;
+                pdas.push(resolver.derive(&program.name, format!("{}:{}", path, line), program_key.as_ref(), seeds));
+            }
+        }
+    }
+    Ok(pdas)
+}
+
+struct SeedResolver<'a> {
+    constants: &'a HashMap<String, Vec<u8>>,
+    values: &'a BTreeMap<String, SeedValue>,
+}
+
+impl SeedResolver<'_> {
+    fn derive(&self, program: &str, account: String, program_id: Option<&Pubkey>, seeds: Vec<String>) -> DerivedPda {
+        let mut pda = DerivedPda {
+            program: Some(program.to_string()),
+            account: Some(account),
+            program_id: program_id.map(Pubkey::to_string),
+            seeds,
+            address: None,
+            bump: None,
+            unresolved_seeds: Vec::new(),
+            error: None,
+        };
+        let mut bytes = Vec::new();
+        for seed in &pda.seeds {
+            match self.resolve(seed) {
+                Ok(Some(seed_bytes)) => bytes.push(seed_bytes),
+                Ok(None) => pda.unresolved_seeds.push(seed.clone()),
+                Err(e) => pda.error = Some(format!("{}: {}", seed, e)),
+            }
+        }
+        if pda.error.is_some() || !pda.unresolved_seeds.is_empty() {
+            return pda;
+        }
+        let Some(program_id) = program_id else {
+            pda.error = Some("No program ID: pass program_id, or declare one with declare_id!".to_string());
+            return pda;
+        };
+        match derive(program_id, &bytes) {
+            Ok((address, bump)) => {
+                pda.address = Some(address.to_string());
+                pda.bump = Some(bump);
+            },
+            Err(e) => pda.error = Some(e.to_string()),
+        }
+        pda
+    }
+
+    // A seed expression's bytes, or None when neither the source nor `values` gives them
+    fn resolve(&self, seed: &str) -> Result<Option<Vec<u8>>> {
+        if let Some(value) = self.values.get(seed) {
+            return seed_bytes(value).map(Some);
+        }
+        let name = seed_name(seed);
+        if let Some(bytes) = literal_bytes(name) {
+            return Ok(Some(bytes));
+        }
+        if let Some(bytes) = self.constants.get(name) {
+            return Ok(Some(bytes.clone()));
+        }
+        let field = name.rsplit('.').next().unwrap_or(name);
+        match self.values.get(name).or_else(|| self.values.get(field)) {
+            Some(value) => seed_bytes(value).map(Some),
+            None => Ok(None),
+        }
+    }
+}
+
+// What a seed is taken from: `&args.id.to_le_bytes().as_ref()` is `args.id`
+fn seed_name(seed: &str) -> &str {
+    const CONVERSIONS: &[&str] = &[".as_ref()", ".as_bytes()", ".to_le_bytes()", ".to_bytes()", ".key()", ".as_slice()", "[..]"];
+    let mut name = seed.trim();
+    loop {
+        let stripped = name.trim_start_matches(['&', ' ']);
+        let stripped = CONVERSIONS.iter().find_map(|c| stripped.strip_suffix(c)).unwrap_or(stripped);
+        if stripped == name {
+            return name;
+        }
+        name = stripped;
+    }
+}
+
+// The bytes of a `b"..."` or `"..."` literal
+fn literal_bytes(literal: &str) -> Option<Vec<u8>> {
+    let body = literal.strip_prefix('b').unwrap_or(literal).strip_prefix('"')?.strip_suffix('"')?;
+    let mut bytes = Vec::with_capacity(body.len());
+    let mut chars = body.chars();
+    while let Some(c) = chars.next() {
+        if c != '\\' {
+            let mut buffer = [0; 4];
+            bytes.extend_from_slice(c.encode_utf8(&mut buffer).as_bytes());
+            continue;
+        }
+        bytes.push(match chars.next()? {
+            'n' => b'\n',
+            'r' => b'\r',
+            't' => b'\t',
+            '0' => 0,
+            'x' => u8::from_str_radix(&chars.by_ref().take(2).collect::<String>(), 16).ok()?,
+            c @ ('\\' | '"' | '\'') => c as u8,
+            _ => return None,
+        });
+    }
+    Some(bytes)
+}
+
+// Contents of the `[ ... ]` group opening at `open`
+fn bracket_body(source: &str, open: usize) -> Option<&str> {
+    let mut depth = 0;
+    for (i, c) in source[open..].char_indices() {
+        match c {
+            '[' => depth += 1,
+            ']' => {
+                depth -= 1;
+                if depth == 0 {
+                    return Some(&source[open + 1..open + i]);
+                }
+            },
+            _ => {},
+        }
+    }
+    None
+}
+
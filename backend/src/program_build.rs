@@ -0,0 +1,116 @@
+use anyhow::{anyhow, Context, Result};
+use sha2::{Digest, Sha256};
+use std::env;
+use std::fs::{self, File};
+use std::path::{Path, PathBuf};
+use std::process::Stdio;
+use std::time::{Duration, Instant};
+use tempfile::TempDir;
+
+use crate::sandbox::Sandbox;
+
+// Keep the end of the log, where the compiler errors are
+const MAX_LOG_BYTES: usize = 64 * 1024;
+
+// The outcome of building a repository's on-chain programs
+pub struct ProgramBuild {
+    pub success: bool,
+    pub log: String,
+    // The LLVM backend's "Stack offset of N exceeded max offset" lines from the whole log,
+    // which the tail above may have cut
+    pub stack_warnings: Vec<String>,
+    pub programs: Vec<CompiledProgram>,
+}
+
+// A `.so` from target/deploy, as it would be deployed
+pub struct CompiledProgram {
+    pub name: String,
+    pub path: PathBuf,
+    pub sha256: String,
+    pub size: u64,
+}
+
+// `anchor build` for Anchor workspaces, `cargo build-sbf` for native programs, under the
+// sandbox's network policy. A failed build is a result, not an error; errors are for builds
+// that couldn't run at all.
+pub fn build_programs(repo_path: &Path, sandbox: &Sandbox) -> Result<ProgramBuild> {
+    if !repo_path.join("Cargo.toml").exists() {
+        return Err(anyhow!("No Cargo.toml at the repository root"));
+    }
+    sandbox.prepare(repo_path, None);
+
+    let (program, args) = if repo_path.join("Anchor.toml").exists() {
+        ("anchor", vec!["build"])
+    } else {
+        ("cargo", vec!["build-sbf"])
+    };
+
+    // Output goes to a file so a chatty build can't fill a pipe and stall
+    let log_dir = TempDir::new()?;
+    let log_path = log_dir.path().join("build.log");
+    let log_file = File::create(&log_path)?;
+    let mut child = sandbox.command(program)
+        .args(&args)
+        .current_dir(repo_path)
+        .stdin(Stdio::null())
+        .stdout(log_file.try_clone()?)
+        .stderr(log_file)
+        .spawn()
+        .with_context(|| format!("Failed to start {}", program))?;
+
+    let timeout = build_timeout();
+    let deadline = Instant::now() + timeout;
+    let status = loop {
+        if let Some(status) = child.try_wait()? {
+            break status;
+        }
+        if Instant::now() > deadline {
+            let _ = child.kill();
+            let _ = child.wait();
+            return Err(anyhow!("{} {} timed out after {}s", program, args.join(" "), timeout.as_secs()));
+        }
+        std::thread::sleep(Duration::from_millis(200));
+    };
+
+    let log = fs::read(&log_path).unwrap_or_default();
+    let mut stack_warnings: Vec<String> = Vec::new();
+    for line in String::from_utf8_lossy(&log).lines().filter(|l| l.contains("Stack offset of")) {
+        if !stack_warnings.iter().any(|w| w == line.trim()) {
+            stack_warnings.push(line.trim().to_string());
+        }
+    }
+    let log = String::from_utf8_lossy(&log[log.len().saturating_sub(MAX_LOG_BYTES)..]).into_owned();
+    let programs = if status.success() { compiled_programs(repo_path)? } else { Vec::new() };
+    Ok(ProgramBuild { success: status.success(), log, stack_warnings, programs })
+}
+
+fn build_timeout() -> Duration {
+    let secs = env::var("SAFEX_BUILD_TIMEOUT_SECS").ok().and_then(|s| s.parse().ok()).unwrap_or(900);
+    Duration::from_secs(secs)
+}
+
+fn compiled_programs(repo_path: &Path) -> Result<Vec<CompiledProgram>> {
+    let deploy_dir = repo_path.join("target").join("deploy");
+    let Ok(entries) = fs::read_dir(&deploy_dir) else {
+        return Ok(Vec::new());
+    };
+    let mut programs = Vec::new();
+    for entry in entries.flatten() {
+        let path = entry.path();
+        if path.extension().and_then(|e| e.to_str()) != Some("so") {
+            continue;
+        }
+        let Some(name) = path.file_stem().and_then(|s| s.to_str()).map(str::to_string) else {
+            continue;
+        };
+        let data = fs::read(&path)?;
+        programs.push(CompiledProgram {
+            name,
+            sha256: format!("{:x}", Sha256::digest(&data)),
+            size: data.len() as u64,
+            path,
+        });
+    }
+    programs.sort_by(|a, b| a.name.cmp(&b.name));
+    Ok(programs)
+}
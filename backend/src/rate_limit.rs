@@ -0,0 +1,202 @@
+use actix_web::body::{EitherBody, MessageBody};
+use actix_web::dev::{ServiceRequest, ServiceResponse};
+use actix_web::http::{header, Method};
+use actix_web::middleware::Next;
+use actix_web::{web, HttpResponse};
+use anyhow::{anyhow, Result};
+use governor::clock::{Clock, DefaultClock};
+use governor::{DefaultKeyedRateLimiter, Quota, RateLimiter};
+use serde_json::json;
+use std::net::{IpAddr, Ipv4Addr};
+use std::num::NonZeroU32;
+use std::sync::Arc;
+use std::time::Duration;
+
+use crate::auth::{presented_secret, ApiKeys};
+use crate::config;
+use crate::github::RequestToken;
+
+// Endpoints that read from a clone cache checkout. A request with its own GitHub token gets a
+// fresh private clone instead of the cached one (see CloneCache::checkout).
+const CHECKOUT_PATHS: &[&str] = &[
+    "/api/repo-files", "/api/repo-stats", "/api/instructions", "/api/account-graph", "/api/estimate-rent",
+    "/api/decode-account", "/api/derive-pda", "/api/autofix-preview",
+];
+
+// How much an endpoint costs us to serve; each class has its own budgets
+#[derive(Debug, Clone, Copy)]
+enum Cost {
+    // Reads that mostly proxy GitHub or the database
+    Cheap,
+    Standard,
+    // Clones, builds and fuzz runs
+    Expensive,
+}
+
+impl Cost {
+    fn of(req: &ServiceRequest) -> Self {
+        match req.path() {
+            "/api/fuzz-test" | "/api/analyze-code" | "/api/jobs/analyze" | "/api/jobs/fuzz" | "/api/jobs/regression-fuzz"
+            | "/api/reverify-reports" | "/api/build-program" | "/api/benchmark" | "/api/self-test"
+            | "/api/decode-account" | "/api/estimate" => Cost::Expensive,
+            path if CHECKOUT_PATHS.contains(&path) && req.headers().contains_key(RequestToken::HEADER) => Cost::Expensive,
+            "/api/repo-contents" | "/api/repo-files" => Cost::Cheap,
+            _ if req.method() == Method::GET => Cost::Cheap,
+            _ => Cost::Standard,
+        }
+    }
+
+    fn as_str(self) -> &'static str {
+        match self {
+            Cost::Cheap => "cheap",
+            Cost::Standard => "standard",
+            Cost::Expensive => "expensive",
+        }
+    }
+}
+
+struct Budget {
+    ip_quota: Quota,
+    key_quota: Quota,
+    per_ip: Arc<DefaultKeyedRateLimiter<IpAddr>>,
+    per_key: Arc<DefaultKeyedRateLimiter<String>>,
+}
+
+impl Budget {
+    // SAFEX_RATE_LIMIT_<CLASS>_IP / _KEY = "<requests per minute>[:<burst>]". A limiter whose
+    // quota is the same as in `previous` is kept, so a reload doesn't refill its clients' buckets.
+    fn load(var: &dyn Fn(&str) -> Option<String>, cost: Cost, ip_default: (u32, u32), key_default: (u32, u32), previous: Option<&Budget>) -> Result<Self> {
+        let prefix = format!("SAFEX_RATE_LIMIT_{}", cost.as_str().to_uppercase());
+        let ip_quota = quota(var, &format!("{}_IP", prefix), ip_default)?;
+        let key_quota = quota(var, &format!("{}_KEY", prefix), key_default)?;
+        Ok(Self {
+            ip_quota,
+            key_quota,
+            per_ip: match previous {
+                Some(previous) if previous.ip_quota == ip_quota => previous.per_ip.clone(),
+                _ => Arc::new(RateLimiter::keyed(ip_quota)),
+            },
+            per_key: match previous {
+                Some(previous) if previous.key_quota == key_quota => previous.per_key.clone(),
+                _ => Arc::new(RateLimiter::keyed(key_quota)),
+            },
+        })
+    }
+}
+
+fn quota(var: &dyn Fn(&str) -> Option<String>, name: &str, default: (u32, u32)) -> Result<Quota> {
+    let (per_minute, burst) = match var(name) {
+        Some(value) => {
+            let (rate, burst) = value.split_once(':').unwrap_or((&value, &value));
+            let parse = |n: &str| n.trim().parse::<u32>().map_err(|_| anyhow!("Invalid {}: {}", name, value));
+            (parse(rate)?, parse(burst)?)
+        },
+        None => default,
+    };
+    let per_minute = NonZeroU32::new(per_minute).ok_or_else(|| anyhow!("{} must allow at least one request per minute", name))?;
+    let burst = NonZeroU32::new(burst).ok_or_else(|| anyhow!("{} burst must be at least 1", name))?;
+    Ok(Quota::per_minute(per_minute).allow_burst(burst))
+}
+
+// Per-client request budgets. Requests with a valid API key are counted against that key,
+// everything else against the client IP, so teams behind one NAT can still use their own keys.
+pub struct RateLimits {
+    cheap: Budget,
+    standard: Budget,
+    expensive: Budget,
+    // Take the client IP from Forwarded/X-Forwarded-For; only safe behind a trusted proxy
+    trust_proxy: bool,
+}
+
+impl RateLimits {
+    // SAFEX_RATE_LIMITS=off disables limiting, SAFEX_TRUST_PROXY=true honours forwarding headers.
+    // `previous` is the running config's limits, whose unchanged budgets carry over.
+    pub fn load(var: &dyn Fn(&str) -> Option<String>, previous: Option<&Self>) -> Result<Option<Self>> {
+        if var("SAFEX_RATE_LIMITS").is_some_and(|v| v == "off") {
+            return Ok(None);
+        }
+        let trust_proxy = var("SAFEX_TRUST_PROXY").is_some_and(|v| v == "true" || v == "1");
+        Ok(Some(Self {
+            cheap: Budget::load(var, Cost::Cheap, (120, 30), (600, 100), previous.map(|limits| &limits.cheap))?,
+            standard: Budget::load(var, Cost::Standard, (60, 10), (300, 50), previous.map(|limits| &limits.standard))?,
+            expensive: Budget::load(var, Cost::Expensive, (6, 2), (30, 5), previous.map(|limits| &limits.expensive))?,
+            trust_proxy,
+        }))
+    }
+
+    // For the log when a config is put in place
+    pub fn describe(limits: Option<&Self>) -> &'static str {
+        match limits {
+            None => "Rate limiting disabled",
+            Some(limits) if limits.trust_proxy => "Rate limiting enabled, using forwarded client IPs",
+            Some(_) => "Rate limiting enabled",
+        }
+    }
+
+    // Ok, or how long the client has to wait before retrying
+    fn check(&self, req: &ServiceRequest) -> Result<(), Duration> {
+        let budget = match Cost::of(req) {
+            Cost::Cheap => &self.cheap,
+            Cost::Standard => &self.standard,
+            Cost::Expensive => &self.expensive,
+        };
+        let api_key = req.app_data::<web::Data<ApiKeys>>()
+            .zip(presented_secret(req.request()))
+            .and_then(|(keys, secret)| keys.lookup(secret))
+            .map(|key| format!("{}:{}", key.tenant, key.name));
+        let result = match api_key {
+            Some(key) => budget.per_key.check_key(&key),
+            None => budget.per_ip.check_key(&self.client_ip(req)),
+        };
+        result.map_err(|not_until| not_until.wait_time_from(DefaultClock::default().now()))
+    }
+
+    fn client_ip(&self, req: &ServiceRequest) -> IpAddr {
+        let forwarded = if self.trust_proxy {
+            req.connection_info().realip_remote_addr().and_then(|addr| addr.parse().ok())
+        } else {
+            None
+        };
+        forwarded
+            .or_else(|| req.peer_addr().map(|addr| addr.ip()))
+            .unwrap_or(IpAddr::V4(Ipv4Addr::UNSPECIFIED))
+    }
+
+    // Forget clients whose buckets have refilled so the maps don't grow without bound. Follows
+    // the limits of the running config across reloads.
+    pub fn spawn_pruning() {
+        tokio::spawn(async move {
+            let mut interval = tokio::time::interval(Duration::from_secs(60));
+            loop {
+                interval.tick().await;
+                let Some(limits) = config::current().rate_limits.clone() else { continue };
+                for budget in [&limits.cheap, &limits.standard, &limits.expensive] {
+                    budget.per_ip.retain_recent();
+                    budget.per_ip.shrink_to_fit();
+                    budget.per_key.retain_recent();
+                    budget.per_key.shrink_to_fit();
+                }
+            }
+        });
+    }
+}
+
+pub async fn rate_limit(
+    req: ServiceRequest,
+    next: Next<impl MessageBody>,
+) -> Result<ServiceResponse<EitherBody<impl MessageBody>>, actix_web::Error> {
+    let Some(limits) = config::current().rate_limits.clone() else {
+        return next.call(req).await.map(ServiceResponse::map_into_left_body);
+    };
+    if let Err(wait) = limits.check(&req) {
+        let retry_after = wait.as_secs_f64().ceil().max(1.0) as u64;
+        let response = HttpResponse::TooManyRequests()
+            .insert_header((header::RETRY_AFTER, retry_after.to_string()))
+            .json(json!({
+                "success": false,
+                "message": format!("Rate limit exceeded, retry in {}s", retry_after),
+            }));
+        return Ok(req.into_response(response).map_into_right_body());
+    }
+    next.call(req).await.map(ServiceResponse::map_into_left_body)
+}
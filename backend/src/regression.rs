@@ -0,0 +1,55 @@
+use anyhow::{anyhow, Result};
+use git2::{ObjectType, Repository, Tree};
+use std::collections::HashMap;
+use std::path::Path;
+
+use crate::github::GitHubClient;
+use crate::instructions::{instruction_source, program_instructions, program_sources, ProgramSource};
+
+// Instructions whose code differs between two commits of a local clone, left checked out at
+// `head`. Each instruction's source (see instruction_source) is compared with the same
+// instruction's at `base`; instructions new in `head`, or in programs new in `head`, count as
+// changed. Removed instructions can't be fuzzed and are left out.
+pub fn changed_instructions(repo_path: &Path, base: &str, head: &str) -> Result<Vec<String>> {
+    GitHubClient::checkout_commit(repo_path, head)?;
+    let repo = Repository::open(repo_path)?;
+    let base_tree = repo.revparse_single(base)
+        .map_err(|e| anyhow!("Commit {} not found: {}", base, e))?
+        .peel_to_tree()?;
+
+    let mut changed = Vec::new();
+    for program in program_sources(repo_path)? {
+        let before = ProgramSource {
+            name: program.name.clone(),
+            path: program.path.clone(),
+            is_anchor: program.is_anchor,
+            sources: program.sources.iter()
+                .filter_map(|(path, _)| blob_at(&repo, &base_tree, path).map(|source| (path.clone(), source)))
+                .collect(),
+        };
+        let before_instructions: HashMap<String, String> = program_instructions(&before).into_iter()
+            .map(|instruction| {
+                let source = instruction_source(&before, &instruction);
+                (instruction.name, source)
+            })
+            .collect();
+
+        for instruction in program_instructions(&program) {
+            let source = instruction_source(&program, &instruction);
+            if before_instructions.get(&instruction.name) != Some(&source) {
+                changed.push(instruction.name);
+            }
+        }
+    }
+    Ok(changed)
+}
+
+// A text file's content in `tree`; None when it doesn't exist there
+fn blob_at(repo: &Repository, tree: &Tree, path: &str) -> Option<String> {
+    let entry = tree.get_path(Path::new(path)).ok()?;
+    if entry.kind() != Some(ObjectType::Blob) {
+        return None;
+    }
+    let blob = repo.find_blob(entry.id()).ok()?;
+    String::from_utf8(blob.content().to_vec()).ok()
+}
@@ -0,0 +1,427 @@
+use anyhow::Result;
+use futures_util::future::join_all;
+use regex::Regex;
+use solana_sdk::rent::Rent;
+use std::collections::{BTreeSet, HashMap};
+use std::fs;
+use std::path::Path;
+use tracing::Instrument;
+
+use crate::cluster::Cluster;
+use crate::models::{AccountSpaceEstimate, DeclaredSpace};
+use crate::stats::collect_files;
+
+const DISCRIMINATOR_LEN: u64 = 8;
+// Guards against recursive type definitions
+const MAX_DEPTH: u32 = 16;
+
+pub struct Field {
+    pub name: String,
+    pub ty: String,
+    // From #[max_len(..)], one entry per nesting level of String/Vec
+    pub max_len: Vec<u64>,
+}
+
+pub struct Variant {
+    pub name: String,
+    pub fields: Vec<Field>,
+}
+
+pub enum TypeDef {
+    Struct(Vec<Field>),
+    Enum(Vec<Variant>),
+}
+
+pub struct AccountDef {
+    pub name: String,
+    pub path: String,
+    pub zero_copy: bool,
+}
+
+// Anchor `#[account]` types and the types and constants they use, parsed from a repository's
+// sources. Parsing is regex based like the analyzer's lints, so types it can't see (macros,
+// foreign crates) are missing.
+pub struct AccountSchemas {
+    pub types: HashMap<String, TypeDef>,
+    pub consts: HashMap<String, u64>,
+    pub accounts: Vec<AccountDef>,
+    sources: Vec<(String, String)>,
+}
+
+// Sizes accounts from their schemas: Borsh encoding for regular accounts, repr(C) layout for
+// zero-copy ones. Types that couldn't be parsed make the estimate a lower bound.
+impl AccountSchemas {
+    // (bytes, exact) of the Borsh encoding of `ty`
+    fn borsh(&self, ty: &str, max_len: &[u64], depth: u32) -> (u64, bool) {
+        if depth > MAX_DEPTH {
+            return (0, false);
+        }
+        if let Some((element, len)) = parse_array(ty) {
+            let (size, exact) = self.borsh(element, max_len, depth + 1);
+            return match self.array_len(len) {
+                Some(len) => (size * len, exact),
+                None => (0, false),
+            };
+        }
+
+        let (name, args) = split_generic(ty);
+        if let (Some(size), []) = (primitive_size(name), args.as_slice()) {
+            return (size, true);
+        }
+        match (name, args.as_slice()) {
+            ("String", []) => match max_len.first() {
+                Some(len) => (4 + len, true),
+                None => (4, false),
+            },
+            ("Vec", [element]) => match max_len.first() {
+                Some(len) => {
+                    let (size, exact) = self.borsh(element, &max_len[1..], depth + 1);
+                    (4 + len * size, exact)
+                },
+                None => (4, false),
+            },
+            ("Option", [inner]) => {
+                let (size, exact) = self.borsh(inner, max_len, depth + 1);
+                (1 + size, exact)
+            },
+            ("Box", [inner]) => self.borsh(inner, max_len, depth + 1),
+            _ => match self.types.get(name) {
+                Some(TypeDef::Struct(fields)) => self.borsh_fields(fields, depth + 1),
+                // Variant index plus the largest variant
+                Some(TypeDef::Enum(variants)) => variants.iter()
+                    .map(|variant| self.borsh_fields(&variant.fields, depth + 1))
+                    .fold((1, true), |(max, exact), (size, variant_exact)| (max.max(1 + size), exact && variant_exact)),
+                None => (0, false),
+            },
+        }
+    }
+
+    fn borsh_fields(&self, fields: &[Field], depth: u32) -> (u64, bool) {
+        fields.iter()
+            .map(|f| self.borsh(&f.ty, &f.max_len, depth))
+            .fold((0, true), |(total, exact), (size, field_exact)| (total + size, exact && field_exact))
+    }
+
+    // (size, align) under repr(C), or None for types that can't be zero-copy
+    pub fn layout(&self, ty: &str, depth: u32) -> Option<(u64, u64)> {
+        if depth > MAX_DEPTH {
+            return None;
+        }
+        if let Some((element, len)) = parse_array(ty) {
+            let (size, align) = self.layout(element, depth + 1)?;
+            return Some((size * self.array_len(len)?, align));
+        }
+        let name = split_generic(ty).0;
+        if name == "Pubkey" {
+            return Some((32, 1));
+        }
+        if let Some(size) = primitive_size(name) {
+            // SBF aligns 128-bit integers to 8 bytes
+            return Some((size, size.min(8)));
+        }
+        let Some(TypeDef::Struct(fields)) = self.types.get(name) else {
+            return None;
+        };
+        let (mut offset, mut struct_align) = (0u64, 1u64);
+        for field in fields {
+            let (size, align) = self.layout(&field.ty, depth + 1)?;
+            offset = offset.next_multiple_of(align) + size;
+            struct_align = struct_align.max(align);
+        }
+        Some((offset.next_multiple_of(struct_align), struct_align))
+    }
+
+    pub fn array_len(&self, len: &str) -> Option<u64> {
+        parse_int(len).or_else(|| self.consts.get(len.rsplit("::").next()?).copied())
+    }
+
+    // Size of an account, including its discriminator, and the fields that kept it from being exact
+    fn account_size(&self, account: &AccountDef) -> (u64, Vec<String>) {
+        let Some(TypeDef::Struct(fields)) = self.types.get(&account.name) else {
+            return (DISCRIMINATOR_LEN, Vec::new());
+        };
+        if account.zero_copy {
+            return match self.layout(&account.name, 0) {
+                Some((size, _)) => (DISCRIMINATOR_LEN + size, Vec::new()),
+                None => (DISCRIMINATOR_LEN, fields.iter().map(|f| f.name.clone()).collect()),
+            };
+        }
+
+        let mut total = DISCRIMINATOR_LEN;
+        let mut unbounded = Vec::new();
+        for field in fields {
+            let (size, exact) = self.borsh(&field.ty, &field.max_len, 0);
+            total += size;
+            if !exact {
+                unbounded.push(field.name.clone());
+            }
+        }
+        (total, unbounded)
+    }
+
+    // Evaluate sums/products of integers, constants, `T::INIT_SPACE` and `size_of::<T>()`
+    fn evaluate(&self, expression: &str) -> Option<u64> {
+        let expression: String = expression.chars().filter(|c| !c.is_whitespace()).collect();
+        expression.split('+')
+            .map(|term| term.split('*').map(|factor| self.evaluate_factor(factor)).product::<Option<u64>>())
+            .sum()
+    }
+
+    fn evaluate_factor(&self, factor: &str) -> Option<u64> {
+        if let Some(value) = parse_int(factor) {
+            return Some(value);
+        }
+        if let Some(ty) = factor.strip_suffix("::INIT_SPACE") {
+            let (size, exact) = self.borsh(ty, &[], 0);
+            return exact.then_some(size);
+        }
+        if let Some(ty) = factor.strip_suffix(">()").and_then(|f| f.rsplit_once("size_of::<")).map(|(_, ty)| ty) {
+            return self.layout(ty, 0).map(|(size, _)| size);
+        }
+        self.consts.get(factor.rsplit("::").next()?).copied()
+    }
+}
+
+fn primitive_size(ty: &str) -> Option<u64> {
+    match ty {
+        "bool" | "u8" | "i8" => Some(1),
+        "u16" | "i16" => Some(2),
+        "u32" | "i32" | "f32" => Some(4),
+        "u64" | "i64" | "f64" => Some(8),
+        "u128" | "i128" => Some(16),
+        "Pubkey" => Some(32),
+        _ => None,
+    }
+}
+
+fn parse_int(value: &str) -> Option<u64> {
+    let digits: String = value.trim_end_matches("usize").trim_end_matches("u64").chars().filter(|c| *c != '_').collect();
+    digits.parse().ok()
+}
+
+// "[T;N]" -> (T, N)
+pub fn parse_array(ty: &str) -> Option<(&str, &str)> {
+    let inner = ty.strip_prefix('[')?.strip_suffix(']')?;
+    let split = top_level_split(inner, ';');
+    match split.as_slice() {
+        [element, len] => Some((element, len)),
+        _ => None,
+    }
+}
+
+// "a::b::Vec<T>" -> ("Vec", ["T"])
+pub fn split_generic(ty: &str) -> (&str, Vec<&str>) {
+    let (path, args) = match ty.find('<') {
+        Some(open) if ty.ends_with('>') => (&ty[..open], top_level_split(&ty[open + 1..ty.len() - 1], ',')),
+        _ => (ty, Vec::new()),
+    };
+    (path.rsplit("::").next().unwrap_or(path), args.into_iter().filter(|a| !a.starts_with('\'')).collect())
+}
+
+// Split on `separator` outside of <>, [], () and {}
+pub fn top_level_split(text: &str, separator: char) -> Vec<&str> {
+    let mut parts = Vec::new();
+    let (mut depth, mut start) = (0i32, 0);
+    for (i, c) in text.char_indices() {
+        match c {
+            '<' | '[' | '(' | '{' => depth += 1,
+            '>' | ']' | ')' | '}' => depth -= 1,
+            c if c == separator && depth == 0 => {
+                parts.push(text[start..i].trim());
+                start = i + 1;
+            },
+            _ => {},
+        }
+    }
+    parts.push(text[start..].trim());
+    parts.into_iter().filter(|p| !p.is_empty()).collect()
+}
+
+// Contents of the `{ ... }` block opening at `open`
+pub fn block_body(source: &str, open: usize) -> Option<&str> {
+    let mut depth = 0;
+    for (i, c) in source[open..].char_indices() {
+        match c {
+            '{' => depth += 1,
+            '}' => {
+                depth -= 1;
+                if depth == 0 {
+                    return Some(&source[open + 1..open + i]);
+                }
+            },
+            _ => {},
+        }
+    }
+    None
+}
+
+struct Parser {
+    item: Regex,
+    comment: Regex,
+    attribute: Regex,
+    max_len: Regex,
+    field: Regex,
+    constant: Regex,
+    init_space: Regex,
+}
+
+impl Parser {
+    fn new() -> Self {
+        Self {
+            item: Regex::new(r"(?m)((?:^[ \t]*#\[[^\n]*\][ \t]*\n)*)^[ \t]*pub(?:\([^)]*\))?\s+(struct|enum)\s+(\w+)(?:<[^>{]*>)?\s*\{").unwrap(),
+            comment: Regex::new(r"//[^\n]*").unwrap(),
+            attribute: Regex::new(r"#\[[^\]]*\]").unwrap(),
+            max_len: Regex::new(r"#\[max_len\(([^)]*)\)\]").unwrap(),
+            field: Regex::new(r"(?s)^(?:pub(?:\([^)]*\))?\s+)?(\w+)\s*:\s*(.+)$").unwrap(),
+            constant: Regex::new(r"const\s+(\w+)\s*:\s*\w+\s*=\s*([0-9_]+)\s*;").unwrap(),
+            // `#[account(init, ..., space = ...)] pub name: Account<'info, Type>`
+            init_space: Regex::new(r"(?s)#\[account\(((?:[^()]|\([^()]*\))*)\)\]\s*pub\s+\w+\s*:\s*(?:Box<\s*)?(?:Account|AccountLoader)<\s*'info\s*,\s*(?:\w+::)*(\w+)\s*>").unwrap(),
+        }
+    }
+
+    fn fields(&self, body: &str) -> Vec<Field> {
+        let body = self.comment.replace_all(body, "");
+        top_level_split(&body, ',').into_iter().filter_map(|chunk| {
+            let max_len = self.max_len.captures(chunk)
+                .map(|c| c[1].split(',').filter_map(|n| parse_int(n.trim())).collect())
+                .unwrap_or_default();
+            let declaration = self.attribute.replace_all(chunk, "");
+            let captures = self.field.captures(declaration.trim())?;
+            Some(Field {
+                name: captures[1].to_string(),
+                ty: captures[2].chars().filter(|c| !c.is_whitespace()).collect(),
+                max_len,
+            })
+        }).collect()
+    }
+
+    fn variants(&self, body: &str) -> Vec<Variant> {
+        let body = self.comment.replace_all(body, "");
+        top_level_split(&body, ',').into_iter().map(|chunk| {
+            let variant = self.attribute.replace_all(chunk, "");
+            let variant = variant.trim();
+            let name_end = variant.find(['{', '(', '=']).unwrap_or(variant.len());
+            let fields = if let Some(open) = variant.find('{') {
+                block_body(variant, open).map(|fields| self.fields(fields)).unwrap_or_default()
+            } else if let (Some(open), Some(close)) = (variant.find('('), variant.rfind(')')) {
+                top_level_split(&variant[open + 1..close], ',').into_iter().enumerate().map(|(i, ty)| Field {
+                    name: i.to_string(),
+                    ty: ty.chars().filter(|c| !c.is_whitespace()).collect(),
+                    max_len: Vec::new(),
+                }).collect()
+            } else {
+                Vec::new()
+            };
+            Variant { name: variant[..name_end].trim().to_string(), fields }
+        }).collect()
+    }
+}
+
+pub fn parse_account_schemas(repo_path: &Path) -> Result<AccountSchemas> {
+    let parser = Parser::new();
+    let mut files = Vec::new();
+    collect_files(repo_path, &mut files)?;
+
+    let mut schemas = AccountSchemas { types: HashMap::new(), consts: HashMap::new(), accounts: Vec::new(), sources: Vec::new() };
+    for file in files.iter().filter(|f| f.extension().is_some_and(|e| e == "rs")) {
+        let Ok(source) = fs::read_to_string(file) else {
+            continue;
+        };
+        let path = file.strip_prefix(repo_path).unwrap_or(file).display().to_string();
+
+        for captures in parser.constant.captures_iter(&source) {
+            if let Some(value) = parse_int(&captures[2]) {
+                schemas.consts.insert(captures[1].to_string(), value);
+            }
+        }
+        for captures in parser.item.captures_iter(&source) {
+            let open = captures.get(0).unwrap().end() - 1;
+            let Some(body) = block_body(&source, open) else {
+                continue;
+            };
+            let (attributes, name) = (&captures[1], captures[3].to_string());
+            if &captures[2] == "enum" {
+                schemas.types.insert(name, TypeDef::Enum(parser.variants(body)));
+                continue;
+            }
+            schemas.types.insert(name.clone(), TypeDef::Struct(parser.fields(body)));
+            if let Some(account) = attributes.lines().map(str::trim).find(|a| a.starts_with("#[account")) {
+                schemas.accounts.push(AccountDef { name, path: path.clone(), zero_copy: account.contains("zero_copy") });
+            }
+        }
+        schemas.sources.push((path, source));
+    }
+    Ok(schemas)
+}
+
+pub fn estimate_account_sizes(repo_path: &Path) -> Result<Vec<AccountSpaceEstimate>> {
+    let mut schemas = parse_account_schemas(repo_path)?;
+    let parser = Parser::new();
+
+    // Where each account type is initialized with an explicit `space`
+    let mut declared: HashMap<String, Vec<(String, String)>> = HashMap::new();
+    let space = Regex::new(r"\bspace\s*=\s*([^,]+)").unwrap();
+    let init = Regex::new(r"\binit(_if_needed)?\b").unwrap();
+    for (path, source) in &schemas.sources {
+        for captures in parser.init_space.captures_iter(source) {
+            if !init.is_match(&captures[1]) {
+                continue;
+            }
+            if let Some(expression) = space.captures(&captures[1]) {
+                declared.entry(captures[2].to_string())
+                    .or_default()
+                    .push((path.clone(), expression[1].trim().to_string()));
+            }
+        }
+    }
+
+    let accounts = std::mem::take(&mut schemas.accounts);
+    Ok(accounts.into_iter().map(|account| {
+        let (size, unbounded_fields) = schemas.account_size(&account);
+        let exact = unbounded_fields.is_empty();
+        let declared_space = declared.remove(&account.name).unwrap_or_default().into_iter().map(|(path, expression)| {
+            let bytes = schemas.evaluate(&expression);
+            let warning = match bytes {
+                Some(bytes) if bytes < size => Some(format!(
+                    "Declared space of {} bytes is smaller than the {}{} bytes the account needs",
+                    bytes, if exact { "" } else { "at least " }, size
+                )),
+                Some(bytes) if exact && bytes > size => Some(format!(
+                    "Declared space over-allocates by {} bytes", bytes - size
+                )),
+                _ => None,
+            };
+            DeclaredSpace { path, expression, bytes, warning }
+        }).collect();
+
+        AccountSpaceEstimate {
+            name: account.name,
+            path: account.path,
+            zero_copy: account.zero_copy,
+            size,
+            exact,
+            unbounded_fields,
+            rent_exempt_lamports: None,
+            declared_space,
+        }
+    }).collect())
+}
+
+// Rent-exempt minimum for each size as reported by the cluster, or None if it couldn't be reached
+pub async fn rent_exempt_minimums(cluster: Cluster, sizes: &BTreeSet<u64>) -> Option<HashMap<u64, u64>> {
+    let client = cluster.rpc_client();
+    let lamports = join_all(sizes.iter().map(|size| client.get_minimum_balance_for_rent_exemption(*size as usize)))
+        .instrument(tracing::info_span!("solana.rpc", rpc.method = "getMinimumBalanceForRentExemption", cluster = cluster.as_str()))
+        .await;
+    match lamports.into_iter().collect::<Result<Vec<u64>, _>>() {
+        Ok(lamports) => Some(sizes.iter().copied().zip(lamports).collect()),
+        Err(e) => {
+            println!("Warning: Failed to query rent from {}: {}", cluster.as_str(), e);
+            None
+        }
+    }
+}
+
+pub fn default_rent_exempt_minimum(size: u64) -> u64 {
+    Rent::default().minimum_balance(size as usize)
+}
@@ -0,0 +1,152 @@
+use anyhow::{anyhow, Result};
+use serde::{Deserialize, Serialize};
+use std::fmt;
+
+// A parsed and normalized git repository URL. Accepts https://, http://, ssh:// and
+// scp-like (git@host:owner/repo) URLs, plus scheme-less `github.com/owner/repo`.
+// Deserializing validates, so request models holding one reject bad URLs up front.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(try_from = "String", into = "String")]
+pub struct RepoUrl {
+    clone_url: String,
+    scheme: String,
+    host: String,
+    port: Option<u16>,
+    segments: Vec<String>,
+}
+
+impl RepoUrl {
+    pub fn parse(input: &str) -> Result<Self> {
+        let url = input.trim();
+        if url.is_empty() {
+            return Err(anyhow!("Repository URL is empty"));
+        }
+
+        let first_slash = url.find('/').unwrap_or(url.len());
+        let (scheme, rest, scp_like) = match url.split_once("://") {
+            Some((scheme, rest)) => match scheme.to_lowercase().as_str() {
+                "https" | "http" | "ssh" => (scheme.to_lowercase(), rest, false),
+                _ => return Err(anyhow!("Unsupported repository URL scheme '{}' (expected https:// or ssh://)", scheme)),
+            },
+            None if url[..first_slash].contains('@') && url[..first_slash].contains(':') => ("ssh".to_string(), url, true),
+            None if url[..first_slash].contains('.') => ("https".to_string(), url, false),
+            None => return Err(anyhow!("Not a repository URL: {}", input)),
+        };
+
+        let (authority, path) = if scp_like {
+            rest.split_once(':').unwrap_or((rest, ""))
+        } else {
+            rest.split_once('/').unwrap_or((rest, ""))
+        };
+        // Query strings and fragments (?tab=readme, #readme) never change the repository
+        let path = path.split(['?', '#']).next().unwrap_or("");
+
+        let host_port = authority.rsplit('@').next().unwrap_or(authority);
+        let (host, port) = match host_port.split_once(':') {
+            Some((host, port)) if !scp_like => {
+                let port = port.parse().map_err(|_| anyhow!("Invalid port in repository URL: {}", input))?;
+                (host, Some(port))
+            },
+            _ => (host_port, None),
+        };
+        let host = host.to_lowercase();
+        let host = host.strip_prefix("www.").unwrap_or(&host).to_string();
+        if host.is_empty() || !host.chars().all(|c| c.is_ascii_alphanumeric() || c == '.' || c == '-') {
+            return Err(anyhow!("Invalid host in repository URL: {}", input));
+        }
+
+        let mut segments: Vec<String> = path.split('/')
+            .filter(|s| !s.is_empty())
+            .map(str::to_string)
+            .collect();
+        if let Some(last) = segments.last_mut() {
+            *last = last.trim_end_matches(".git").to_string();
+        }
+        if segments.iter().any(|s| s.is_empty() || s == "." || s == ".." || !s.chars().all(|c| c.is_ascii_alphanumeric() || "-_.~".contains(c))) {
+            return Err(anyhow!("Invalid repository path in URL: {}", input));
+        }
+
+        match host.as_str() {
+            "github.com" if segments.len() != 2 => {
+                return Err(anyhow!("Not a GitHub repository URL (expected github.com/<owner>/<repo>): {}", input));
+            },
+            // GitLab allows nested groups, but routes like /-/issues are not repositories
+            "gitlab.com" if segments.len() < 2 || segments.iter().any(|s| s == "-") => {
+                return Err(anyhow!("Not a GitLab repository URL (expected gitlab.com/<group>/<repo>): {}", input));
+            },
+            _ if segments.is_empty() => {
+                return Err(anyhow!("Repository URL has no repository path: {}", input));
+            },
+            _ => {},
+        }
+
+        // Scheme-less input is cloned over https; everything else is cloned exactly as given
+        let clone_url = if url.contains("://") || scp_like {
+            url.to_string()
+        } else {
+            format!("https://{}", url)
+        };
+
+        Ok(Self { clone_url, scheme, host, port, segments })
+    }
+
+    // URL handed to git, preserving the caller's transport (ssh keys, ports, .git suffix)
+    pub fn clone_url(&self) -> &str {
+        &self.clone_url
+    }
+
+    // scp-like ssh form (git@host:owner/repo.git), for cloning with an ssh key
+    pub fn ssh_url(&self) -> String {
+        format!("git@{}:{}.git", self.host, self.segments.join("/"))
+    }
+
+    // Normalized form used as the key for stored history: https://host/owner/repo
+    pub fn canonical(&self) -> String {
+        let port = match (self.scheme.as_str(), self.port) {
+            ("https" | "http", Some(port)) => format!(":{}", port),
+            _ => String::new(),
+        };
+        let scheme = if self.scheme == "http" { "http" } else { "https" };
+        format!("{}://{}{}/{}", scheme, self.host, port, self.segments.join("/"))
+    }
+
+    pub fn host(&self) -> &str {
+        &self.host
+    }
+
+    pub fn is_github(&self) -> bool {
+        self.host == "github.com"
+    }
+
+    // Owner and repository name, for hosts with a metadata API
+    pub fn owner_repo(&self) -> Option<(&str, &str)> {
+        match self.segments.as_slice() {
+            [owner, repo] if self.is_github() => Some((owner, repo)),
+            _ => None,
+        }
+    }
+
+    pub fn name(&self) -> &str {
+        self.segments.last().map(String::as_str).unwrap_or_default()
+    }
+}
+
+impl fmt::Display for RepoUrl {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.canonical())
+    }
+}
+
+impl TryFrom<String> for RepoUrl {
+    type Error = anyhow::Error;
+
+    fn try_from(value: String) -> Result<Self> {
+        Self::parse(&value)
+    }
+}
+
+impl From<RepoUrl> for String {
+    fn from(url: RepoUrl) -> String {
+        url.clone_url
+    }
+}
@@ -0,0 +1,338 @@
+use anyhow::{anyhow, Result};
+use handlebars::Handlebars;
+use serde_json::{json, Value};
+
+use crate::cluster::Cluster;
+use crate::db::Database;
+use crate::ecosystem::project_score;
+use crate::models::{AnchorBackend, BugSeverity, CodeBug, CpiLocation, CpiSurface, CpiTarget, DeploymentInfo, FindingComment, FindingThread, ReportAnchor, Taxonomy, TextEdit, TriageState};
+use crate::rules::taxonomy_counts;
+
+const MAX_TEMPLATE_BYTES: usize = 256 * 1024;
+
+// Everything needed to render a finished analysis as a deliverable
+pub struct ReportContext {
+    pub repo_url: String,
+    pub commit_sha: Option<String>,
+    pub run_id: Option<String>,
+    pub bugs: Vec<CodeBug>,
+    pub deployment: Option<DeploymentInfo>,
+    pub cpi_surface: Option<CpiSurface>,
+    // The team's comment threads; those on findings of this run are rendered
+    pub discussion: Vec<FindingThread>,
+    // On-chain logs of a report for the same commit
+    pub anchors: Vec<ReportAnchor>,
+}
+
+impl ReportContext {
+    pub fn count(&self, severity: BugSeverity) -> usize {
+        self.bugs.iter().filter(|b| b.severity == severity).count()
+    }
+}
+
+pub fn escape_html(value: &str) -> String {
+    value
+        .replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+        .replace('\'', "&#39;")
+}
+
+// Render a self-contained HTML report (inline styles so it survives email clients)
+pub fn render_html_report(context: &ReportContext) -> String {
+    let mut rows = String::new();
+    for bug in &context.bugs {
+        let location = match &bug.file {
+            Some(file) => format!("{}:{}", escape_html(file), bug.line),
+            None => format!("line {}", bug.line),
+        };
+        rows.push_str(&format!(
+            "<tr><td style=\"padding:4px 8px;text-transform:uppercase\">{}</td><td style=\"padding:4px 8px\">{}</td><td style=\"padding:4px 8px\"><code>{}</code></td><td style=\"padding:4px 8px;white-space:pre-wrap\">{}</td></tr>\n",
+            bug.severity.as_str(),
+            escape_html(&bug.bug),
+            location,
+            escape_html(&bug.fix),
+        ));
+    }
+
+    let deployment = match &context.deployment {
+        Some(deployment) => {
+            let notes: String = deployment.notes.iter().map(|n| format!("<li>{}</li>", escape_html(n))).collect();
+            format!(
+                "<h2>Deployment</h2>\n<p><strong>Program:</strong> <code>{}</code> on {}<br>\n<strong>Upgrade authority:</strong> {} ({})<br>\n<strong>Last deployed at slot:</strong> {}<br>\n<strong>Risk:</strong> <span style=\"text-transform:uppercase\">{}</span></p>\n<ul>{}</ul>\n",
+                escape_html(&deployment.program_id),
+                deployment.cluster.as_str(),
+                escape_html(deployment.upgrade_authority.as_deref().unwrap_or("none")),
+                escape_html(&deployment.authority_kind),
+                deployment.last_deploy_slot.map(|s| s.to_string()).unwrap_or_else(|| "unknown".to_string()),
+                deployment.risk.as_str(),
+                notes,
+            )
+        },
+        None => String::new(),
+    };
+
+    let cpi_surface = match context.cpi_surface.as_ref().filter(|surface| !surface.targets.is_empty() || !surface.unused_entries.is_empty()) {
+        Some(surface) => {
+            let mut rows = String::new();
+            for target in &surface.targets {
+                let status = match (target.allowed, surface.declared) {
+                    (Some(true), _) => "allowed",
+                    (Some(false), _) => "not allowed",
+                    (None, true) => "unresolved",
+                    (None, false) => "not reviewed",
+                };
+                let locations: Vec<String> = target.locations.iter().map(|l| format!("{}:{}", escape_html(&l.file), l.line)).collect();
+                rows.push_str(&format!(
+                    "<tr><td style=\"padding:4px 8px\">{}</td><td style=\"padding:4px 8px\"><code>{}</code></td><td style=\"padding:4px 8px\">{}</td><td style=\"padding:4px 8px\"><code>{}</code></td></tr>\n",
+                    escape_html(&target.name),
+                    escape_html(target.program_id.as_deref().unwrap_or("unknown")),
+                    status,
+                    locations.join("<br>"),
+                ));
+            }
+            for program_id in &surface.unused_entries {
+                rows.push_str(&format!(
+                    "<tr><td style=\"padding:4px 8px\">-</td><td style=\"padding:4px 8px\"><code>{}</code></td><td style=\"padding:4px 8px\">allowed, never invoked</td><td style=\"padding:4px 8px\"></td></tr>\n",
+                    escape_html(program_id),
+                ));
+            }
+            format!(
+                "<h2>CPI surface</h2>\n<p>{}</p>\n<table style=\"border-collapse:collapse\" border=\"1\">\n<tr><th>Program</th><th>ID</th><th>Status</th><th>Invoked at</th></tr>\n{}</table>\n",
+                if surface.declared { "Programs invoked, checked against the allowlist in .safex.toml." } else { "Programs invoked. The repository declares no CPI allowlist in .safex.toml." },
+                rows,
+            )
+        },
+        None => String::new(),
+    };
+
+    let mut threads = String::new();
+    for thread in &context.discussion {
+        let Some(bug) = context.bugs.iter().find(|bug| bug.fingerprint == thread.fingerprint) else {
+            continue;
+        };
+        let status = match (&thread.resolved_by, thread.resolved_at) {
+            (Some(by), Some(at)) => format!("Resolved by {} on {}", escape_html(by), format_time(at)),
+            _ => "Open".to_string(),
+        };
+        let comments: String = thread.comments.iter().map(|comment| format!(
+            "<li><strong>{}</strong> <span style=\"color:#666\">{}</span><div style=\"white-space:pre-wrap\">{}</div></li>",
+            escape_html(&comment.author),
+            format_time(comment.created_at),
+            escape_html(&comment.body),
+        )).collect();
+        threads.push_str(&format!(
+            "<h3><span style=\"text-transform:uppercase\">{}</span> {}</h3>\n<p>{}</p>\n<ul>{}</ul>\n",
+            bug.severity.as_str(),
+            escape_html(&bug.bug),
+            status,
+            comments,
+        ));
+    }
+    let discussion = if threads.is_empty() { String::new() } else { format!("<h2>Discussion</h2>\n{}", threads) };
+
+    format!(
+        r#"<!DOCTYPE html>
+<html>
+<head><meta charset="utf-8"><title>Safex security report</title></head>
+<body style="font-family:sans-serif">
+<h1>Safex security report</h1>
+<p><strong>Repository:</strong> {repo}<br>
+<strong>Commit:</strong> {commit}<br>
+<strong>Run:</strong> {run}</p>
+<p><strong>{total}</strong> findings: {critical} critical, {high} high, {medium} medium, {low} low</p>
+<table style="border-collapse:collapse" border="1">
+<tr><th>Severity</th><th>Finding</th><th>Location</th><th>Fix</th></tr>
+{rows}</table>
+{deployment}{cpi_surface}{discussion}</body>
+</html>
+"#,
+        repo = escape_html(&context.repo_url),
+        commit = escape_html(context.commit_sha.as_deref().unwrap_or("unknown")),
+        run = escape_html(context.run_id.as_deref().unwrap_or("unsaved")),
+        total = context.bugs.len(),
+        critical = context.count(BugSeverity::Critical),
+        high = context.count(BugSeverity::High),
+        medium = context.count(BugSeverity::Medium),
+        low = context.count(BugSeverity::Low),
+        rows = rows,
+        deployment = deployment,
+        cpi_surface = cpi_surface,
+        discussion = discussion,
+    )
+}
+
+fn format_time(unix: i64) -> String {
+    chrono::DateTime::from_timestamp(unix, 0)
+        .map(|time| time.format("%Y-%m-%d %H:%M UTC").to_string())
+        .unwrap_or_else(|| unix.to_string())
+}
+
+// What a tenant's report template renders against:
+//   repo_url, commit_sha, run_id, generated_at   strings; commit_sha and run_id may be null
+//   score                                         0-100, as the ecosystem index scores projects
+//   counts.total/critical/high/medium/low         findings by severity
+//   categories                                    findings per vulnerability class: category, title, total and by severity
+//   findings                                      CodeBug objects (bug, severity, file, line, fix, rule_id, ...)
+//   anchors                                       report logs: backend, transaction_signature, hash, status, logged_at
+//   deployment, cpi_surface                       as the analysis response has them, or null
+//   discussion                                    threads on this run's findings, each with its `finding`
+pub fn template_data(context: &ReportContext) -> Value {
+    let discussion: Vec<Value> = context.discussion.iter().filter_map(|thread| {
+        let bug = context.bugs.iter().find(|bug| bug.fingerprint == thread.fingerprint)?;
+        let comments: Vec<Value> = thread.comments.iter().map(|comment| json!({
+            "author": comment.author,
+            "body": comment.body,
+            "created_at": format_time(comment.created_at),
+        })).collect();
+        Some(json!({
+            "finding": bug,
+            "comments": comments,
+            "resolved": thread.resolved,
+            "resolved_by": thread.resolved_by,
+            "resolved_at": thread.resolved_at.map(format_time),
+        }))
+    }).collect();
+    let anchors: Vec<Value> = context.anchors.iter().map(|anchor| json!({
+        "backend": anchor.backend,
+        "transaction_signature": anchor.transaction_signature,
+        "hash": anchor.hash,
+        "status": anchor.status,
+        "logged_at": format_time(anchor.logged_at),
+    })).collect();
+    let (categories, _) = taxonomy_counts(&context.bugs);
+    json!({
+        "repo_url": context.repo_url,
+        "commit_sha": context.commit_sha,
+        "run_id": context.run_id,
+        "generated_at": format_time(chrono::Utc::now().timestamp()),
+        "score": project_score(&context.bugs),
+        "counts": {
+            "total": context.bugs.len(),
+            "critical": context.count(BugSeverity::Critical),
+            "high": context.count(BugSeverity::High),
+            "medium": context.count(BugSeverity::Medium),
+            "low": context.count(BugSeverity::Low),
+        },
+        "categories": categories,
+        "findings": context.bugs,
+        "anchors": anchors,
+        "deployment": context.deployment,
+        "cpi_surface": context.cpi_surface,
+        "discussion": discussion,
+    })
+}
+
+// The tenant's template when it has one, else the built-in report. A template that fails to
+// render falls back to the built-in one rather than losing the report.
+pub fn render_report(context: &ReportContext, template: Option<&str>) -> String {
+    let Some(template) = template else {
+        return render_html_report(context);
+    };
+    match Handlebars::new().render_template(template, &template_data(context)) {
+        Ok(html) => html,
+        Err(e) => {
+            println!("Warning: Report template failed to render, using the built-in report: {}", e);
+            render_html_report(context)
+        }
+    }
+}
+
+// The tenant's uploaded template, if any; a failed lookup falls back to the built-in report
+pub fn tenant_template(db: &Database, tenant: &str) -> Option<String> {
+    match db.get_report_template(tenant) {
+        Ok(template) => template.map(|t| t.template),
+        Err(e) => {
+            println!("Warning: Failed to load report template: {}", e);
+            None
+        }
+    }
+}
+
+// Parse the template and render it strictly against a report with every section filled in,
+// so misspelled placeholders are caught on upload rather than rendering as blanks
+pub fn check_template(template: &str) -> Result<()> {
+    if template.len() > MAX_TEMPLATE_BYTES {
+        return Err(anyhow!("must be at most {} bytes", MAX_TEMPLATE_BYTES));
+    }
+    let mut handlebars = Handlebars::new();
+    handlebars.set_strict_mode(true);
+    handlebars.render_template(template, &template_data(&sample_context()))
+        .map(|_| ())
+        .map_err(|e| anyhow!("{}", e))
+}
+
+fn sample_context() -> ReportContext {
+    let bug = CodeBug {
+        bug: "Missing signer check".to_string(),
+        line: 42,
+        file: Some("programs/vault/src/lib.rs".to_string()),
+        severity: BugSeverity::High,
+        fix: "Require the authority to sign".to_string(),
+        fingerprint: "0".repeat(64),
+        rule_id: Some("anchor-missing-signer".to_string()),
+        taxonomy: Some(Taxonomy::MissingSignerCheck),
+        autofix: Some(vec![TextEdit {
+            byte_start: 0,
+            byte_end: 0,
+            original: String::new(),
+            replacement: "#[account(signer)]\n".to_string(),
+        }]),
+        triage_state: Some(TriageState::Open),
+        triage_comment: Some("Confirmed".to_string()),
+        issue_url: Some("https://github.com/example/vault/issues/1".to_string()),
+        assignee: Some("auditor".to_string()),
+    };
+    ReportContext {
+        repo_url: "https://github.com/example/vault".to_string(),
+        commit_sha: Some("0".repeat(40)),
+        run_id: Some("00000000-0000-0000-0000-000000000000".to_string()),
+        deployment: Some(DeploymentInfo {
+            program_id: "11111111111111111111111111111111".to_string(),
+            cluster: Cluster::default(),
+            loader: "BPFLoaderUpgradeab1e11111111111111111111111".to_string(),
+            upgradeable: true,
+            upgrade_authority: Some("11111111111111111111111111111111".to_string()),
+            authority_kind: "wallet".to_string(),
+            multisig_program: Some("11111111111111111111111111111111".to_string()),
+            last_deploy_slot: Some(1),
+            risk: BugSeverity::High,
+            notes: vec!["Upgradeable by a single wallet".to_string()],
+        }),
+        cpi_surface: Some(CpiSurface {
+            declared: true,
+            targets: vec![CpiTarget {
+                program_id: Some("11111111111111111111111111111111".to_string()),
+                name: "system_program".to_string(),
+                allowed: Some(true),
+                locations: vec![CpiLocation { file: "programs/vault/src/lib.rs".to_string(), line: 50 }],
+            }],
+            unused_entries: vec!["11111111111111111111111111111111".to_string()],
+        }),
+        discussion: vec![FindingThread {
+            repo_url: "https://github.com/example/vault".to_string(),
+            fingerprint: bug.fingerprint.clone(),
+            comments: vec![FindingComment {
+                id: "0".to_string(),
+                author: "reviewer".to_string(),
+                body: "Fixed in the next release".to_string(),
+                created_at: 0,
+            }],
+            resolved: true,
+            resolved_by: Some("reviewer".to_string()),
+            resolved_at: Some(0),
+        }],
+        anchors: vec![ReportAnchor {
+            backend: AnchorBackend::Solana,
+            transaction_signature: "1".repeat(88),
+            hash: "0".repeat(64),
+            status: "finalized".to_string(),
+            receipt: None,
+            logged_at: 0,
+            verified: None,
+            verification_error: None,
+        }],
+        bugs: vec![bug],
+    }
+}
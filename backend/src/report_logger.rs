@@ -1,40 +1,84 @@
-use anyhow::Result;
+use anyhow::{anyhow, Result};
 use sha2::{Sha256, Digest};
 use solana_client::rpc_client::RpcClient;
+use solana_client::rpc_config::RpcProgramAccountsConfig;
+use solana_client::rpc_filter::{Memcmp, RpcFilterType};
 use solana_sdk::{
     instruction::{AccountMeta, Instruction},
     message::Message,
     pubkey::Pubkey,
-    signature::{Keypair, Signer},
+    signature::{read_keypair_file, Keypair, Signer},
     transaction::Transaction,
 };
+use std::env;
 use std::str::FromStr;
 
+use crate::cluster::Cluster;
+use crate::hashing::hex;
+use crate::models::{LoggedReport, ReportStatus};
+use crate::repo_url::RepoUrl;
+
 // Program ID of the report-logger Anchor program
-const PROGRAM_ID: &str = "4L6BwTs3J5deHpTLSHGPZKQKn9uhLFMKnKjhjqeobQ26";
+pub const PROGRAM_ID: &str = "4L6BwTs3J5deHpTLSHGPZKQKn9uhLFMKnKjhjqeobQ26";
+
+// Report account: 8-byte discriminator, authority, hash, timestamp, repo_hash, commit, status
+const REPORT_LEN: usize = 8 + 32 + 32 + 8 + 32 + 20 + 1;
+const HASH_OFFSET: usize = 8 + 32;
+
+// Config account: 8-byte discriminator, admin, arbiter, treasury, fee_lamports, bump
+const CONFIG_LEN: usize = 8 + 32 + 32 + 32 + 8 + 1;
+
+// The registry settings log_report needs: where the fee goes and how much it is
+pub struct RegistryConfig {
+    pub treasury: Pubkey,
+    pub fee_lamports: u64,
+}
 
 pub struct ReportLogger {
     client: RpcClient,
     payer: Keypair,
+    // Refuse to log when the registry charges more than this (SAFEX_REPORT_MAX_FEE_LAMPORTS)
+    max_fee_lamports: Option<u64>,
 }
 
 impl ReportLogger {
     pub fn new() -> Result<Self> {
-        // Connect to Solana devnet
-        let client = RpcClient::new("https://api.devnet.solana.com".to_string());
+        // Connect to the registry's cluster (devnet unless SAFEX_REPORT_CLUSTER says otherwise)
+        let client = RpcClient::new(Cluster::registry()?.rpc_url());
         
-        // For development, generate a new keypair
-        // In production, this should be loaded from a secure location
-        let payer = Keypair::new();
+        // Without a configured payer a throwaway keypair is generated, which only works against
+        // a funded test validator
+        let payer = configured_payer()?.unwrap_or_else(Keypair::new);
+        let max_fee_lamports = env::var("SAFEX_REPORT_MAX_FEE_LAMPORTS").ok().and_then(|v| v.parse().ok());
         
-        Ok(Self { client, payer })
+        Ok(Self { client, payer, max_fee_lamports })
     }
     
-    pub fn log_report(&self, report_content: &str) -> Result<String> {
-        // Generate SHA256 hash of the report content
-        let mut hasher = Sha256::new();
-        hasher.update(report_content.as_bytes());
-        let hash = hasher.finalize();
+    // The registry's config PDA, created by the program's `initialize`
+    pub fn registry_config(&self) -> Result<RegistryConfig> {
+        let program_id = Pubkey::from_str(PROGRAM_ID)?;
+        let address = config_address(&program_id);
+        let data = tracing::info_span!("solana.rpc", rpc.method = "getAccountInfo")
+            .in_scope(|| self.client.get_account_data(&address))
+            .map_err(|e| anyhow!("Report registry config {} is unavailable (is the program initialized?): {}", address, e))?;
+        if data.len() < CONFIG_LEN || data[..8] != account_discriminator("Config") {
+            return Err(anyhow!("Account {} is not a report registry config", address));
+        }
+        Ok(RegistryConfig {
+            treasury: Pubkey::try_from(&data[72..104])?,
+            fee_lamports: u64::from_le_bytes(data[104..112].try_into()?),
+        })
+    }
+    
+    // A signed transaction recording the report hash together with the repository hash and
+    // commit it attests to. Sending is separate so confirmation tracking can subscribe first.
+    #[tracing::instrument(name = "report_logger.build_transaction", skip_all)]
+    pub fn build_transaction(&self, hash: &[u8; 32], repo_hash: &[u8; 32], commit: &[u8; 20]) -> Result<Transaction> {
+        // The fee, if any, goes to the treasury named in the registry config
+        let config = self.registry_config()?;
+        if let Some(max_fee) = self.max_fee_lamports.filter(|max_fee| config.fee_lamports > *max_fee) {
+            return Err(anyhow!("Registry fee of {} lamports exceeds SAFEX_REPORT_MAX_FEE_LAMPORTS ({})", config.fee_lamports, max_fee));
+        }
         
         // Create a new account for storing the report
         let report_account = Keypair::new();
@@ -42,10 +86,12 @@ impl ReportLogger {
         // Get program ID
         let program_id = Pubkey::from_str(PROGRAM_ID)?;
         
-        // Create instruction data: [0, hash[0], hash[1], ..., hash[31]]
-        // 0 is the instruction discriminator for log_report
-        let mut instruction_data = vec![0];
-        instruction_data.extend_from_slice(&hash);
+        // Anchor instruction data: 8-byte discriminator, then the borsh-encoded arguments
+        // (fixed-size arrays are written as raw bytes)
+        let mut instruction_data = instruction_discriminator("log_report").to_vec();
+        instruction_data.extend_from_slice(hash);
+        instruction_data.extend_from_slice(repo_hash);
+        instruction_data.extend_from_slice(commit);
         
         // Create the instruction
         let instruction = Instruction {
@@ -53,6 +99,8 @@ impl ReportLogger {
             accounts: vec![
                 AccountMeta::new(report_account.pubkey(), true),
                 AccountMeta::new(self.payer.pubkey(), true),
+                AccountMeta::new_readonly(config_address(&program_id), false),
+                AccountMeta::new(config.treasury, false),
                 AccountMeta::new_readonly(Pubkey::from_str("11111111111111111111111111111111").unwrap(), false),
             ],
             data: instruction_data,
@@ -62,13 +110,212 @@ impl ReportLogger {
         let message = Message::new(&[instruction], Some(&self.payer.pubkey()));
         let mut transaction = Transaction::new_unsigned(message);
         
-        let recent_blockhash = self.client.get_latest_blockhash()?;
+        let recent_blockhash = tracing::info_span!("solana.rpc", rpc.method = "getLatestBlockhash")
+            .in_scope(|| self.client.get_latest_blockhash())?;
         transaction.sign(&[&self.payer, &report_account], recent_blockhash);
-        
-        // Send transaction
-        let signature = self.client.send_and_confirm_transaction(&transaction)?;
-        
-        // Return the transaction signature
+        Ok(transaction)
+    }
+    
+    // Send without waiting; preflight still rejects transactions that would fail outright
+    #[tracing::instrument(name = "report_logger.send", skip_all)]
+    pub fn send(&self, transaction: &Transaction) -> Result<String> {
+        let signature = tracing::info_span!("solana.rpc", rpc.method = "sendTransaction")
+            .in_scope(|| self.client.send_transaction(transaction))?;
+        Ok(signature.to_string())
+    }
+    
+    // Send and block until the transaction is confirmed, for when it can't be tracked
+    #[tracing::instrument(name = "report_logger.send_and_confirm", skip_all)]
+    pub fn send_and_confirm(&self, transaction: &Transaction) -> Result<String> {
+        let signature = tracing::info_span!("solana.rpc", rpc.method = "sendTransaction")
+            .in_scope(|| self.client.send_and_confirm_transaction(transaction))?;
         Ok(signature.to_string())
     }
-}
\ No newline at end of file
+
+    // The payer's address and balance, so operators can top it up before logging stalls
+    pub fn payer_balance(&self) -> Result<(Pubkey, u64)> {
+        let pubkey = self.payer.pubkey();
+        let lamports = tracing::info_span!("solana.rpc", rpc.method = "getBalance")
+            .in_scope(|| self.client.get_balance(&pubkey))?;
+        Ok((pubkey, lamports))
+    }
+
+    // Ask the cluster's faucet to fund the payer; only devnet, testnet and test validators have one
+    pub fn request_airdrop(&self, lamports: u64) -> Result<String> {
+        let signature = tracing::info_span!("solana.rpc", rpc.method = "requestAirdrop")
+            .in_scope(|| self.client.request_airdrop(&self.payer.pubkey(), lamports))?;
+        Ok(signature.to_string())
+    }
+
+    // Every report logged with this content hash, oldest first
+    pub fn reports_with_hash(&self, hash: &[u8; 32]) -> Result<Vec<LoggedReport>> {
+        self.find_reports(HASH_OFFSET, hash)
+    }
+
+    #[tracing::instrument(name = "report_logger.find_reports", skip_all)]
+    fn find_reports(&self, offset: usize, bytes: &[u8]) -> Result<Vec<LoggedReport>> {
+        let mut filters = report_filters();
+        filters.push(RpcFilterType::Memcmp(Memcmp::new_raw_bytes(offset, bytes.to_vec())));
+        let config = RpcProgramAccountsConfig {
+            filters: Some(filters),
+            ..Default::default()
+        };
+        let program_id = Pubkey::from_str(PROGRAM_ID)?;
+        let accounts = tracing::info_span!("solana.rpc", rpc.method = "getProgramAccounts")
+            .in_scope(|| self.client.get_program_accounts_with_config(&program_id, config))?;
+
+        let mut reports: Vec<LoggedReport> = accounts.iter()
+            .filter_map(|(address, account)| parse_report(address, &account.data))
+            .collect();
+        reports.sort_by_key(|r| r.timestamp);
+        Ok(reports)
+    }
+}
+
+// getProgramAccounts filters matching every report account
+pub fn report_filters() -> Vec<RpcFilterType> {
+    vec![
+        RpcFilterType::DataSize(REPORT_LEN as u64),
+        RpcFilterType::Memcmp(Memcmp::new_raw_bytes(0, account_discriminator("Report").to_vec())),
+    ]
+}
+
+pub fn parse_report(address: &Pubkey, data: &[u8]) -> Option<LoggedReport> {
+    if data.len() < REPORT_LEN {
+        return None;
+    }
+    Some(LoggedReport {
+        address: address.to_string(),
+        authority: Pubkey::try_from(&data[8..40]).ok()?.to_string(),
+        hash: hex(&data[40..72]),
+        timestamp: i64::from_le_bytes(data[72..80].try_into().ok()?),
+        repo_hash: hex(&data[80..112]),
+        commit_sha: hex(&data[112..132]),
+        status: report_status(data[132])?,
+        discrepancy: None,
+    })
+}
+
+// Events the program emits, as they appear base64-encoded in "Program data:" log lines
+pub enum RegistryEvent {
+    ReportLogged(LoggedReport),
+    ReportStatusChanged { report: String, status: ReportStatus },
+}
+
+// Event data is the 8-byte discriminator followed by the borsh-encoded fields;
+// anything else (including other programs' events) is None
+pub fn parse_event(data: &[u8]) -> Option<RegistryEvent> {
+    let discriminator = data.get(..8)?;
+    if discriminator == event_discriminator("ReportLogged") && data.len() >= 8 + 32 + 32 + 32 + 32 + 20 + 8 {
+        Some(RegistryEvent::ReportLogged(LoggedReport {
+            address: Pubkey::try_from(&data[8..40]).ok()?.to_string(),
+            authority: Pubkey::try_from(&data[40..72]).ok()?.to_string(),
+            hash: hex(&data[72..104]),
+            repo_hash: hex(&data[104..136]),
+            commit_sha: hex(&data[136..156]),
+            timestamp: i64::from_le_bytes(data[156..164].try_into().ok()?),
+            status: ReportStatus::Active,
+            discrepancy: None,
+        }))
+    } else if discriminator == event_discriminator("ReportStatusChanged") && data.len() > 8 + 32 {
+        Some(RegistryEvent::ReportStatusChanged {
+            report: Pubkey::try_from(&data[8..40]).ok()?.to_string(),
+            status: report_status(data[40])?,
+        })
+    } else {
+        None
+    }
+}
+
+// Borsh encodes unit enum variants as their index
+fn report_status(byte: u8) -> Option<ReportStatus> {
+    match byte {
+        0 => Some(ReportStatus::Active),
+        1 => Some(ReportStatus::Disputed),
+        2 => Some(ReportStatus::Superseded),
+        3 => Some(ReportStatus::Revoked),
+        _ => None,
+    }
+}
+
+// SAFEX_REPORT_PAYER_KEYPAIR is a keypair file (as written by `solana-keygen`) that pays rent,
+// the registry fee and certificate mints
+pub fn configured_payer() -> Result<Option<Keypair>> {
+    match env::var("SAFEX_REPORT_PAYER_KEYPAIR") {
+        Ok(path) => read_keypair_file(&path).map(Some).map_err(|e| anyhow!("Failed to read payer keypair {}: {}", path, e)),
+        Err(_) => Ok(None),
+    }
+}
+
+// The program's assert_report, which fails the transaction it's in unless `report` is an
+// active report with this hash
+pub fn assert_report_instruction(report: &Pubkey, hash: &[u8; 32]) -> Result<Instruction> {
+    let mut data = instruction_discriminator("assert_report").to_vec();
+    data.extend_from_slice(hash);
+    Ok(Instruction {
+        program_id: Pubkey::from_str(PROGRAM_ID)?,
+        accounts: vec![AccountMeta::new_readonly(*report, false)],
+        data,
+    })
+}
+
+fn config_address(program_id: &Pubkey) -> Pubkey {
+    Pubkey::find_program_address(&[b"config"], program_id).0
+}
+
+// First 8 bytes of sha256("global:<name>"), how Anchor identifies instructions
+pub fn instruction_discriminator(name: &str) -> [u8; 8] {
+    let digest = Sha256::digest(format!("global:{}", name).as_bytes());
+    let mut discriminator = [0u8; 8];
+    discriminator.copy_from_slice(&digest[..8]);
+    discriminator
+}
+
+// First 8 bytes of sha256("account:<name>"), which Anchor prefixes account data with
+pub fn account_discriminator(name: &str) -> [u8; 8] {
+    let digest = Sha256::digest(format!("account:{}", name).as_bytes());
+    let mut discriminator = [0u8; 8];
+    discriminator.copy_from_slice(&digest[..8]);
+    discriminator
+}
+
+// First 8 bytes of sha256("event:<Name>"), which Anchor prefixes event data with
+pub fn event_discriminator(name: &str) -> [u8; 8] {
+    let digest = Sha256::digest(format!("event:{}", name).as_bytes());
+    let mut discriminator = [0u8; 8];
+    discriminator.copy_from_slice(&digest[..8]);
+    discriminator
+}
+
+// SHA256 of the canonical URL, so the same repository hashes the same however it was written
+pub fn repo_url_hash(repo_url: &RepoUrl) -> [u8; 32] {
+    Sha256::digest(repo_url.canonical().as_bytes()).into()
+}
+
+// A full 40-character hex git commit SHA as its 20 raw bytes
+pub fn parse_commit_sha(commit_sha: &str) -> Result<[u8; 20]> {
+    parse_hex(commit_sha.trim()).ok_or_else(|| anyhow!("Commit SHA must be 40 hex characters: {}", commit_sha.trim()))
+}
+
+// A 64-character hex SHA256 report hash as its 32 raw bytes
+pub fn parse_report_hash(hash: &str) -> Result<[u8; 32]> {
+    parse_hex(hash.trim()).ok_or_else(|| anyhow!("Report hash must be 64 hex characters: {}", hash.trim()))
+}
+
+fn parse_hex<const N: usize>(value: &str) -> Option<[u8; N]> {
+    if value.len() != N * 2 || !value.is_ascii() {
+        return None;
+    }
+    let mut bytes = [0u8; N];
+    for (i, byte) in bytes.iter_mut().enumerate() {
+        *byte = u8::from_str_radix(&value[i * 2..i * 2 + 2], 16).ok()?;
+    }
+    Some(bytes)
+}
+
+// SAFEX_MAX_REPORT_BYTES caps the size of a report submitted for logging or verification
+// (default 10 MB). JSON bodies are also held to actix's JSON size limit; larger reports go
+// through the streaming upload.
+pub fn max_report_bytes() -> usize {
+    env::var("SAFEX_MAX_REPORT_BYTES").ok().and_then(|v| v.parse().ok()).unwrap_or(10 * 1024 * 1024)
+}
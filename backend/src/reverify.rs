@@ -0,0 +1,150 @@
+use anyhow::{anyhow, Result};
+use solana_client::nonblocking::rpc_client::RpcClient;
+use solana_sdk::pubkey::Pubkey;
+use std::env;
+use std::str::FromStr;
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::time::Instant;
+
+use crate::cluster::Cluster;
+use crate::db::{now_unix, Database};
+use crate::models::{Discrepancy, DiscrepancyKind, LoggedReport};
+use crate::report_logger::{parse_report, PROGRAM_ID};
+
+const BPF_LOADER_UPGRADEABLE_ID: &str = "BPFLoaderUpgradeab1e11111111111111111111111";
+
+// getMultipleAccounts takes at most 100 addresses
+const BATCH_SIZE: usize = 100;
+
+// Periodic runs start shortly after boot, so the program's deployment is on record early
+const STARTUP_DELAY: Duration = Duration::from_secs(60);
+
+pub struct Reverification {
+    pub checked: usize,
+    pub program_upgraded: bool,
+    pub flagged: Vec<LoggedReport>,
+}
+
+// Runs re-verification every SAFEX_REVERIFY_INTERVAL_SECS (daily by default; 0 turns it off)
+pub fn spawn_reverification(db: Arc<Database>) -> Result<()> {
+    let secs: u64 = match env::var("SAFEX_REVERIFY_INTERVAL_SECS") {
+        Ok(value) => value.trim().parse().map_err(|_| anyhow!("Invalid SAFEX_REVERIFY_INTERVAL_SECS: {}", value))?,
+        Err(_) => 24 * 60 * 60,
+    };
+    if secs == 0 {
+        println!("Periodic report re-verification disabled");
+        return Ok(());
+    }
+    let period = Duration::from_secs(secs);
+    tokio::spawn(async move {
+        let mut interval = tokio::time::interval_at(Instant::now() + STARTUP_DELAY.min(period), period);
+        loop {
+            interval.tick().await;
+            match reverify_reports(&db).await {
+                Ok(run) => println!("Re-verified {} reports, {} newly flagged", run.checked, run.flagged.len()),
+                Err(e) => println!("Warning: report re-verification failed: {}", e),
+            }
+        }
+    });
+    Ok(())
+}
+
+// Re-fetch the account behind every indexed report and flag the ones whose on-chain trail
+// no longer matches: closed accounts, changed contents, and (for reports logged before it)
+// a redeployment of the program
+pub async fn reverify_reports(db: &Database) -> Result<Reverification> {
+    let cluster = Cluster::registry()?;
+    let program_id = Pubkey::from_str(PROGRAM_ID)?;
+    let client = cluster.rpc_client();
+
+    let program_upgraded = check_program(db, &client, cluster, &program_id).await?;
+
+    let reports = db.all_indexed_reports(cluster.as_str())?;
+    let mut flagged = Vec::new();
+    for batch in reports.chunks(BATCH_SIZE) {
+        let addresses = batch.iter()
+            .map(|report| Pubkey::from_str(&report.address))
+            .collect::<Result<Vec<_>, _>>()?;
+        let accounts = client.get_multiple_accounts(&addresses).await
+            .map_err(|e| anyhow!("Failed to load report accounts: {}", e))?;
+
+        for ((report, address), account) in batch.iter().zip(&addresses).zip(accounts) {
+            let (kind, detail) = match account {
+                None => (DiscrepancyKind::AccountClosed, "Report account was closed".to_string()),
+                Some(account) => match parse_report(address, &account.data).filter(|_| account.owner == program_id) {
+                    None => (DiscrepancyKind::AccountClosed, "Account no longer holds a report".to_string()),
+                    Some(current) => {
+                        let changed = changed_fields(report, &current);
+                        if changed.is_empty() {
+                            continue;
+                        }
+                        (DiscrepancyKind::HashMismatch, format!("Changed on-chain since it was indexed: {}", changed.join(", ")))
+                    }
+                },
+            };
+            if db.flag_indexed_report(cluster.as_str(), &report.address, kind, &detail)? {
+                println!("Warning: report {} flagged as {}: {}", report.address, kind.as_str(), detail);
+                flagged.push(LoggedReport {
+                    discrepancy: Some(Discrepancy { kind, detail, detected_at: now_unix() }),
+                    ..report.clone()
+                });
+            }
+        }
+    }
+
+    Ok(Reverification {
+        checked: reports.len(),
+        program_upgraded,
+        flagged,
+    })
+}
+
+// Compare the program's deployment slot with the one seen by the previous run (the first run
+// only records it). Programs that aren't upgradeable never change.
+async fn check_program(db: &Database, client: &RpcClient, cluster: Cluster, program_id: &Pubkey) -> Result<bool> {
+    let program = client.get_account(program_id).await
+        .map_err(|e| anyhow!("Failed to load program {}: {}", program_id, e))?;
+    if program.owner != Pubkey::from_str(BPF_LOADER_UPGRADEABLE_ID)? {
+        return Ok(false);
+    }
+    // UpgradeableLoaderState::Program is a 4-byte variant tag, then the ProgramData address
+    let programdata = program.data.get(4..36)
+        .and_then(|bytes| Pubkey::try_from(bytes).ok())
+        .ok_or_else(|| anyhow!("Program {} has no program data account", program_id))?;
+    // UpgradeableLoaderState::ProgramData is a 4-byte variant tag, then the deployment slot
+    let data = client.get_account_data(&programdata).await
+        .map_err(|e| anyhow!("Failed to load program data {}: {}", programdata, e))?;
+    let slot = data.get(4..12)
+        .and_then(|bytes| bytes.try_into().ok())
+        .map(u64::from_le_bytes)
+        .ok_or_else(|| anyhow!("Program data {} is malformed", programdata))?;
+
+    let previous = db.program_deploy_slot(cluster.as_str())?;
+    let upgraded = matches!(previous, Some(previous) if previous != slot);
+    if upgraded {
+        // Reports logged after the upgrade ran under the new code; without a block time
+        // for the slot every report is flagged
+        let upgraded_at = client.get_block_time(slot).await.ok();
+        let detail = format!("Program was redeployed at slot {} (previously {})", slot, previous.unwrap_or_default());
+        let flagged = db.flag_unflagged_indexed_reports(cluster.as_str(), upgraded_at, DiscrepancyKind::ProgramUpgraded, &detail)?;
+        println!("Warning: report-logger program on {} was redeployed at slot {}; flagged {} reports", cluster.as_str(), slot, flagged);
+    }
+    db.set_program_deploy_slot(cluster.as_str(), slot)?;
+    Ok(upgraded)
+}
+
+// Names of the fields that differ between the indexed report and its account
+fn changed_fields(indexed: &LoggedReport, current: &LoggedReport) -> Vec<&'static str> {
+    [
+        ("authority", indexed.authority != current.authority),
+        ("hash", indexed.hash != current.hash),
+        ("repo_hash", indexed.repo_hash != current.repo_hash),
+        ("commit_sha", indexed.commit_sha != current.commit_sha),
+        ("timestamp", indexed.timestamp != current.timestamp),
+    ]
+    .into_iter()
+    .filter(|(_, changed)| *changed)
+    .map(|(field, _)| field)
+    .collect()
+}
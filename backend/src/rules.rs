@@ -0,0 +1,785 @@
+use anyhow::{anyhow, Result};
+use std::collections::BTreeMap;
+
+use crate::models::{BugSeverity, CodeBug, Rule, RuleReference, RuleSetInfo, Taxonomy, TaxonomyCount};
+
+// Rule IDs attached to findings; the frontend looks them up in the catalog below
+pub const ANCHOR_MISSING_SIGNER: &str = "anchor-missing-signer";
+pub const NATIVE_MISSING_SIGNER: &str = "native-missing-signer";
+pub const NATIVE_MISSING_OWNER_CHECK: &str = "native-missing-owner-check";
+pub const NATIVE_UNCHECKED_DESERIALIZATION: &str = "native-unchecked-deserialization";
+pub const TS_HARDCODED_SECRET_KEY: &str = "ts-hardcoded-secret-key";
+pub const TS_INLINE_SECRET_KEY_ARRAY: &str = "ts-inline-secret-key-array";
+pub const TS_SKIP_PREFLIGHT: &str = "ts-skip-preflight";
+pub const TS_UNAWAITED_CONFIRMATION: &str = "ts-unawaited-confirmation";
+pub const CONFIG_OVERFLOW_CHECKS_DISABLED: &str = "config-overflow-checks-disabled";
+pub const CONFIG_PANIC_STRATEGY_MISMATCH: &str = "config-panic-strategy-mismatch";
+pub const CONFIG_DEBUG_ASSERTIONS_STRIPPED: &str = "config-debug-assertions-stripped";
+pub const CONFIG_FEATURE_GATED_CHECK: &str = "config-feature-gated-check";
+pub const WORKSPACE_DUPLICATE_PROGRAM_ID: &str = "workspace-duplicate-program-id";
+pub const WORKSPACE_RESERVED_PROGRAM_ID: &str = "workspace-reserved-program-id";
+pub const ANCHOR_TOML_PROGRAM_MAPPING: &str = "anchor-toml-program-mapping";
+pub const CPI_TARGET_NOT_ALLOWED: &str = "cpi-target-not-allowed";
+pub const CPI_TARGET_UNRESOLVED: &str = "cpi-target-unresolved";
+pub const CPI_ALLOWLIST_UNUSED: &str = "cpi-allowlist-unused";
+pub const TAINT_UNCHECKED_ARITHMETIC: &str = "taint-unchecked-arithmetic";
+pub const TAINT_UNCHECKED_INDEX: &str = "taint-unchecked-index";
+pub const TAINT_TRUNCATING_CAST: &str = "taint-truncating-cast";
+pub const TOOLCHAIN_PINNED_TOO_OLD: &str = "toolchain-pinned-too-old";
+pub const TOOLCHAIN_NIGHTLY_FEATURE: &str = "toolchain-nightly-feature";
+pub const CLIPPY: &str = "clippy";
+
+const SEALEVEL_SIGNER: (&str, &str) = ("Sealevel attacks: signer authorization", "https://github.com/coral-xyz/sealevel-attacks/tree/master/programs/0-signer-authorization");
+const SEALEVEL_OWNER: (&str, &str) = ("Sealevel attacks: owner checks", "https://github.com/coral-xyz/sealevel-attacks/tree/master/programs/2-owner-checks");
+const SEALEVEL_INITIALIZATION: (&str, &str) = ("Sealevel attacks: initialization", "https://github.com/coral-xyz/sealevel-attacks/tree/master/programs/4-initialization");
+const NEODYME_PITFALLS: (&str, &str) = ("Neodyme: Solana smart contracts, common pitfalls", "https://neodyme.io/en/blog/solana_common_pitfalls/");
+
+struct RuleDef {
+    id: &'static str,
+    // Bumped whenever what the rule detects changes, so benchmark runs of different
+    // versions can be told apart
+    version: u32,
+    title: &'static str,
+    // Audit finding class, as used in public audit reports
+    category: &'static str,
+    // Vulnerability class findings are charted by
+    taxonomy: Taxonomy,
+    severity: BugSeverity,
+    // "anchor", "native" or "all"
+    applies_to: &'static str,
+    description: &'static str,
+    vulnerable_example: &'static str,
+    fixed_example: &'static str,
+    references: &'static [(&'static str, &'static str)],
+}
+
+const RULES: &[RuleDef] = &[
+    RuleDef {
+        id: ANCHOR_MISSING_SIGNER,
+        version: 1,
+        title: "Missing signer constraint",
+        category: "Access control",
+        taxonomy: Taxonomy::MissingSignerCheck,
+        severity: BugSeverity::High,
+        applies_to: "anchor",
+        description: "An account that authorizes the instruction is not required to sign it. Anyone can pass the \
+            authority's public key and act on its behalf, e.g. withdrawing from a vault they don't own.",
+        vulnerable_example: r#"#[derive(Accounts)]
+pub struct Withdraw<'info> {
+    #[account(mut, has_one = authority)]
+    pub vault: Account<'info, Vault>,
+    /// CHECK: only compared against vault.authority
+    pub authority: AccountInfo<'info>,
+}"#,
+        fixed_example: r#"#[derive(Accounts)]
+pub struct Withdraw<'info> {
+    #[account(mut, has_one = authority)]
+    pub vault: Account<'info, Vault>,
+    pub authority: Signer<'info>,
+}"#,
+        references: &[
+            SEALEVEL_SIGNER,
+            ("Anchor Signer account type", "https://docs.rs/anchor-lang/latest/anchor_lang/accounts/signer/struct.Signer.html"),
+            NEODYME_PITFALLS,
+        ],
+    },
+    RuleDef {
+        id: NATIVE_MISSING_SIGNER,
+        version: 1,
+        title: "Missing is_signer check",
+        category: "Access control",
+        taxonomy: Taxonomy::MissingSignerCheck,
+        severity: BugSeverity::High,
+        applies_to: "native",
+        description: "A privileged account (authority, admin, owner, payer) is read from the account list but its \
+            is_signer flag is never checked, so the instruction can be invoked without that key's signature.",
+        vulnerable_example: r#"let authority = next_account_info(accounts_iter)?;
+let vault = next_account_info(accounts_iter)?;
+let mut state = Vault::try_from_slice(&vault.data.borrow())?;
+if state.authority != *authority.key {
+    return Err(ProgramError::InvalidAccountData);
+}"#,
+        fixed_example: r#"let authority = next_account_info(accounts_iter)?;
+if !authority.is_signer {
+    return Err(ProgramError::MissingRequiredSignature);
+}
+let vault = next_account_info(accounts_iter)?;
+let mut state = Vault::try_from_slice(&vault.data.borrow())?;
+if state.authority != *authority.key {
+    return Err(ProgramError::InvalidAccountData);
+}"#,
+        references: &[SEALEVEL_SIGNER, NEODYME_PITFALLS],
+    },
+    RuleDef {
+        id: NATIVE_MISSING_OWNER_CHECK,
+        version: 1,
+        title: "Missing owner check",
+        category: "Account validation",
+        taxonomy: Taxonomy::MissingOwnerCheck,
+        severity: BugSeverity::High,
+        applies_to: "native",
+        description: "Account data is deserialized without verifying the account is owned by the expected program. \
+            An attacker can create an account with identical layout under their own program and fill it with \
+            arbitrary values.",
+        vulnerable_example: r#"let config = next_account_info(accounts_iter)?;
+let config_data = Config::try_from_slice(&config.data.borrow())?;"#,
+        fixed_example: r#"let config = next_account_info(accounts_iter)?;
+if config.owner != program_id {
+    return Err(ProgramError::IncorrectProgramId);
+}
+let config_data = Config::try_from_slice(&config.data.borrow())?;"#,
+        references: &[SEALEVEL_OWNER, NEODYME_PITFALLS],
+    },
+    RuleDef {
+        id: NATIVE_UNCHECKED_DESERIALIZATION,
+        version: 1,
+        title: "Unchecked deserialization",
+        category: "Data validation",
+        taxonomy: Taxonomy::Reinitialization,
+        severity: BugSeverity::Medium,
+        applies_to: "native",
+        description: "Account data is decoded with an *_unchecked variant, which skips length and initialization \
+            validation. Uninitialized or differently typed accounts can then be read as valid state.",
+        vulnerable_example: r#"let state = State::unpack_unchecked(&account.data.borrow())?;"#,
+        fixed_example: r#"let state = State::unpack(&account.data.borrow())?;
+if !state.is_initialized() {
+    return Err(ProgramError::UninitializedAccount);
+}"#,
+        references: &[SEALEVEL_INITIALIZATION, NEODYME_PITFALLS],
+    },
+    RuleDef {
+        id: TS_HARDCODED_SECRET_KEY,
+        version: 1,
+        title: "Hard-coded private key",
+        category: "Key management",
+        taxonomy: Taxonomy::SecretExposure,
+        severity: BugSeverity::High,
+        applies_to: "all",
+        description: "A base58-encoded 64-byte secret key is committed in client or test code. Anyone with read \
+            access to the repository controls that keypair, and keys copied from tests tend to end up funded on \
+            devnet or mainnet.",
+        vulnerable_example: r#"const payer = Keypair.fromSecretKey(
+  bs58.decode("4Z7cXSyeFR8wNGMVXUE1TwtKn5D5Vu7FzEv69dokLv7KrQk7h6pu4LF8ZRR9yQBhc7uSM6RTTZtU1fmaxiNrxXrs")
+);"#,
+        fixed_example: r#"const payer = Keypair.fromSecretKey(
+  bs58.decode(process.env.PAYER_SECRET_KEY!)
+);"#,
+        references: &[("Solana cookbook: keypairs and wallets", "https://solana.com/developers/cookbook/wallets/restore-keypair")],
+    },
+    RuleDef {
+        id: TS_INLINE_SECRET_KEY_ARRAY,
+        version: 1,
+        title: "Keypair from inline secret key bytes",
+        category: "Key management",
+        taxonomy: Taxonomy::SecretExposure,
+        severity: BugSeverity::High,
+        applies_to: "all",
+        description: "Keypair.fromSecretKey is called with a literal byte array, i.e. the contents of a keypair \
+            file pasted into the source. The key is exposed to everyone who can read the repository.",
+        vulnerable_example: r#"const authority = Keypair.fromSecretKey(
+  Uint8Array.from([174, 47, 154, 16, 202, 193, 206, 113, /* ... */])
+);"#,
+        fixed_example: r#"const authority = Keypair.fromSecretKey(
+  Uint8Array.from(JSON.parse(fs.readFileSync(process.env.AUTHORITY_KEYPAIR!, "utf8")))
+);"#,
+        references: &[("Solana cookbook: keypairs and wallets", "https://solana.com/developers/cookbook/wallets/restore-keypair")],
+    },
+    RuleDef {
+        id: TS_SKIP_PREFLIGHT,
+        version: 1,
+        title: "Preflight checks disabled",
+        category: "Transaction handling",
+        taxonomy: Taxonomy::TransactionHandling,
+        severity: BugSeverity::Medium,
+        applies_to: "all",
+        description: "Transactions are sent with skipPreflight: true, so simulation errors are never reported and \
+            failing transactions land on chain and pay fees. In tests this also hides the program logs that \
+            explain why an instruction failed.",
+        vulnerable_example: r#"await program.methods.withdraw(amount)
+  .accounts({ vault, authority: wallet.publicKey })
+  .rpc({ skipPreflight: true });"#,
+        fixed_example: r#"await program.methods.withdraw(amount)
+  .accounts({ vault, authority: wallet.publicKey })
+  .rpc();"#,
+        references: &[("Solana docs: transaction confirmation and preflight", "https://solana.com/developers/guides/advanced/confirmation")],
+    },
+    RuleDef {
+        id: TS_UNAWAITED_CONFIRMATION,
+        version: 1,
+        title: "Confirmation not awaited",
+        category: "Transaction handling",
+        taxonomy: Taxonomy::TransactionHandling,
+        severity: BugSeverity::Medium,
+        applies_to: "all",
+        description: "A call that sends or confirms a transaction returns a promise that is neither awaited nor \
+            returned. Execution continues before the transaction lands, so state is read too early, failures \
+            surface as unhandled rejections, and tests pass without checking anything.",
+        vulnerable_example: r#"connection.confirmTransaction(signature, "confirmed");
+const vault = await program.account.vault.fetch(vaultPda);"#,
+        fixed_example: r#"await connection.confirmTransaction(signature, "confirmed");
+const vault = await program.account.vault.fetch(vaultPda);"#,
+        references: &[("Solana docs: transaction confirmation and preflight", "https://solana.com/developers/guides/advanced/confirmation")],
+    },
+    RuleDef {
+        id: CONFIG_OVERFLOW_CHECKS_DISABLED,
+        version: 1,
+        title: "Overflow checks disabled in release builds",
+        category: "Arithmetic",
+        taxonomy: Taxonomy::IntegerOverflow,
+        severity: BugSeverity::High,
+        applies_to: "all",
+        description: "On-chain programs are built with the release profile, which wraps on integer overflow unless \
+            overflow-checks is enabled. Unchecked arithmetic on balances and amounts then silently wraps instead \
+            of aborting the transaction.",
+        vulnerable_example: r#"[profile.release]
+overflow-checks = false
+lto = "fat""#,
+        fixed_example: r#"[profile.release]
+overflow-checks = true
+lto = "fat""#,
+        references: &[
+            ("The Cargo Book: profiles", "https://doc.rust-lang.org/cargo/reference/profiles.html#overflow-checks"),
+            NEODYME_PITFALLS,
+        ],
+    },
+    RuleDef {
+        id: CONFIG_PANIC_STRATEGY_MISMATCH,
+        version: 1,
+        title: "Panic strategy differs between dev and release",
+        category: "Build configuration",
+        taxonomy: Taxonomy::BuildConfiguration,
+        severity: BugSeverity::Medium,
+        applies_to: "all",
+        description: "The dev and release profiles use different panic strategies, so code that relies on \
+            unwinding (catch_unwind, drop-based cleanup) behaves differently in local tests than in the \
+            build that is deployed.",
+        vulnerable_example: r#"[profile.dev]
+panic = "unwind"
+
+[profile.release]
+panic = "abort""#,
+        fixed_example: r#"[profile.dev]
+panic = "abort"
+
+[profile.release]
+panic = "abort""#,
+        references: &[("The Cargo Book: profiles", "https://doc.rust-lang.org/cargo/reference/profiles.html#panic")],
+    },
+    RuleDef {
+        id: CONFIG_DEBUG_ASSERTIONS_STRIPPED,
+        version: 1,
+        title: "debug_assert! checks stripped from on-chain builds",
+        category: "Data validation",
+        taxonomy: Taxonomy::BuildConfiguration,
+        severity: BugSeverity::Medium,
+        applies_to: "all",
+        description: "The program validates state with debug_assert!, but debug-assertions is off in the release \
+            profile used for on-chain builds. The checks pass in tests and are compiled out of the deployed \
+            program.",
+        vulnerable_example: r#"debug_assert!(vault.amount >= amount, "insufficient funds");
+vault.amount -= amount;"#,
+        fixed_example: r#"require!(vault.amount >= amount, ErrorCode::InsufficientFunds);
+vault.amount -= amount;"#,
+        references: &[("The Cargo Book: profiles", "https://doc.rust-lang.org/cargo/reference/profiles.html#debug-assertions")],
+    },
+    RuleDef {
+        id: CONFIG_FEATURE_GATED_CHECK,
+        version: 1,
+        title: "Security check gated behind a feature flag",
+        category: "Build configuration",
+        taxonomy: Taxonomy::BuildConfiguration,
+        severity: BugSeverity::Medium,
+        applies_to: "all",
+        description: "A Cargo feature switches validation off, either by name (no-*, skip-*, unchecked, ...) or \
+            because a check is compiled under cfg(feature). One wrong --features flag, or the feature being on by \
+            default, ships a program without the check. Findings are High when the feature is a default.",
+        vulnerable_example: r#"#[cfg(not(feature = "skip-owner-check"))]
+require_keys_eq!(*config.owner, crate::ID);"#,
+        fixed_example: r#"require_keys_eq!(*config.owner, crate::ID);"#,
+        references: &[("The Cargo Book: features", "https://doc.rust-lang.org/cargo/reference/features.html")],
+    },
+    RuleDef {
+        id: WORKSPACE_DUPLICATE_PROGRAM_ID,
+        version: 1,
+        title: "Duplicate program ID in workspace",
+        category: "Deployment",
+        taxonomy: Taxonomy::DeploymentConfiguration,
+        severity: BugSeverity::Medium,
+        applies_to: "all",
+        description: "Two programs in the workspace declare the same ID, usually because a crate was copied \
+            without generating a new keypair. Only one of them can be deployed at that address, and CPI \
+            callers and clients built against the other end up talking to the wrong program.",
+        vulnerable_example: r#"// programs/vault/src/lib.rs
+declare_id!("7xKXtg2CW87d97TXJSDpbD5jBkheTqA83TZRuJosgAsU");
+// programs/staking/src/lib.rs
+declare_id!("7xKXtg2CW87d97TXJSDpbD5jBkheTqA83TZRuJosgAsU");"#,
+        fixed_example: r#"// programs/staking/src/lib.rs, after `anchor keys sync`
+declare_id!("5k7iBwTHyd6AUDgqsHXNxpq8ExwhuwM3R1qTXEBqWfyH");"#,
+        references: &[("Anchor CLI: anchor keys", "https://www.anchor-lang.com/docs/references/cli#keys")],
+    },
+    RuleDef {
+        id: WORKSPACE_RESERVED_PROGRAM_ID,
+        version: 1,
+        title: "Program ID collides with a well-known program",
+        category: "Deployment",
+        taxonomy: Taxonomy::DeploymentConfiguration,
+        severity: BugSeverity::Medium,
+        applies_to: "all",
+        description: "declare_id! uses the address of a native or widely deployed program, or Anchor's \
+            placeholder ID from `anchor init`. The program can't be deployed there, and ID checks against \
+            crate::ID accept accounts owned by the other program.",
+        vulnerable_example: r#"declare_id!("Fg6PaFpoGXkYsidMpWTK6W2BeZ7FEfcYkg476zPFsLnS");"#,
+        fixed_example: r#"// The program's own keypair, from target/deploy/<name>-keypair.json
+declare_id!("5k7iBwTHyd6AUDgqsHXNxpq8ExwhuwM3R1qTXEBqWfyH");"#,
+        references: &[("Anchor CLI: anchor keys", "https://www.anchor-lang.com/docs/references/cli#keys")],
+    },
+    RuleDef {
+        id: ANCHOR_TOML_PROGRAM_MAPPING,
+        version: 1,
+        title: "Anchor.toml program mapping incomplete or stale",
+        category: "Deployment",
+        taxonomy: Taxonomy::DeploymentConfiguration,
+        severity: BugSeverity::Medium,
+        applies_to: "anchor",
+        description: "A [programs.<cluster>] section of Anchor.toml is missing a workspace program, or maps it to \
+            an address other than its declare_id!. Deploys and tests on that cluster then skip the program or \
+            target an outdated address.",
+        vulnerable_example: r#"[programs.localnet]
+vault = "5k7iBwTHyd6AUDgqsHXNxpq8ExwhuwM3R1qTXEBqWfyH"
+staking = "9mNvJtrBH1Vx5aU9DaAaS1THdCbpr7oXXNUjJHG1Pf9F"
+
+[programs.devnet]
+vault = "5k7iBwTHyd6AUDgqsHXNxpq8ExwhuwM3R1qTXEBqWfyH""#,
+        fixed_example: r#"[programs.localnet]
+vault = "5k7iBwTHyd6AUDgqsHXNxpq8ExwhuwM3R1qTXEBqWfyH"
+staking = "9mNvJtrBH1Vx5aU9DaAaS1THdCbpr7oXXNUjJHG1Pf9F"
+
+[programs.devnet]
+vault = "5k7iBwTHyd6AUDgqsHXNxpq8ExwhuwM3R1qTXEBqWfyH"
+staking = "9mNvJtrBH1Vx5aU9DaAaS1THdCbpr7oXXNUjJHG1Pf9F""#,
+        references: &[("Anchor.toml reference", "https://www.anchor-lang.com/docs/references/anchor-toml")],
+    },
+    RuleDef {
+        id: CPI_TARGET_NOT_ALLOWED,
+        version: 1,
+        title: "CPI into a program outside the declared allowlist",
+        category: "External calls",
+        taxonomy: Taxonomy::ArbitraryCpi,
+        severity: BugSeverity::Medium,
+        applies_to: "all",
+        description: "The program invokes a program that `[cpi] allowed_programs` in .safex.toml doesn't list. \
+            The allowlist is the reviewed set of programs the code may call into; a new target means a \
+            dependency on another program's behavior and upgrade authority that nobody signed off on.",
+        vulnerable_example: r#"# .safex.toml
+[cpi]
+allowed_programs = ["11111111111111111111111111111111"]
+
+// lib.rs
+pub token_program: Program<'info, Token>,"#,
+        fixed_example: r#"# .safex.toml, after reviewing the SPL Token calls
+[cpi]
+allowed_programs = [
+    "11111111111111111111111111111111",
+    "TokenkegQfeZyiNwAJbNbGKPFXCWuBvf9Ss623VQ5DA",
+]"#,
+        references: &[("Solana docs: cross program invocation", "https://solana.com/docs/core/cpi")],
+    },
+    RuleDef {
+        id: CPI_TARGET_UNRESOLVED,
+        version: 1,
+        title: "CPI target couldn't be checked against the allowlist",
+        category: "External calls",
+        taxonomy: Taxonomy::ArbitraryCpi,
+        severity: BugSeverity::Low,
+        applies_to: "all",
+        description: "The program takes a program account of a type defined outside the repository, so its \
+            address isn't known and the CPI allowlist can't vouch for it. Check which program the type's ID \
+            refers to and that it is on the allowlist.",
+        vulnerable_example: r#"pub oracle_program: Program<'info, PriceOracle>,"#,
+        fixed_example: r#"// PriceOracle::id() is FsJ3A3u2vn5cTVofAjvy6y5kwABJAqYWpe4975bi2epH, listed in .safex.toml
+pub oracle_program: Program<'info, PriceOracle>,"#,
+        references: &[("Solana docs: cross program invocation", "https://solana.com/docs/core/cpi")],
+    },
+    RuleDef {
+        id: CPI_ALLOWLIST_UNUSED,
+        version: 1,
+        title: "CPI allowlist entry never used",
+        category: "External calls",
+        taxonomy: Taxonomy::ArbitraryCpi,
+        severity: BugSeverity::Low,
+        applies_to: "all",
+        description: "`[cpi] allowed_programs` in .safex.toml lists a program the code doesn't invoke. Stale \
+            entries pre-approve a call nobody reviewed; remove them so the list stays the reviewed CPI surface.",
+        vulnerable_example: r#"[cpi]
+allowed_programs = [
+    "11111111111111111111111111111111",
+    "metaqbxxUerdq28cj1RbAWkYQm3ybzjb6a8bt518x1s",
+]"#,
+        fixed_example: r#"[cpi]
+allowed_programs = ["11111111111111111111111111111111"]"#,
+        references: &[],
+    },
+    RuleDef {
+        id: TAINT_UNCHECKED_ARITHMETIC,
+        version: 1,
+        title: "Instruction argument in unchecked arithmetic",
+        category: "Arithmetic",
+        taxonomy: Taxonomy::IntegerOverflow,
+        severity: BugSeverity::Medium,
+        applies_to: "all",
+        description: "A value the caller of the instruction chooses reaches +, - or * without a bounds check or \
+            checked operation. Release builds wrap on overflow unless overflow-checks is set, so a large enough \
+            argument turns a balance or fee calculation into a small or huge number.",
+        vulnerable_example: r#"pub fn deposit(ctx: Context<Deposit>, amount: u64) -> Result<()> {
+    ctx.accounts.vault.total = ctx.accounts.vault.total + amount;
+    Ok(())
+}"#,
+        fixed_example: r#"pub fn deposit(ctx: Context<Deposit>, amount: u64) -> Result<()> {
+    ctx.accounts.vault.total = ctx.accounts.vault.total
+        .checked_add(amount)
+        .ok_or(ErrorCode::Overflow)?;
+    Ok(())
+}"#,
+        references: &[NEODYME_PITFALLS],
+    },
+    RuleDef {
+        id: TAINT_UNCHECKED_INDEX,
+        version: 1,
+        title: "Instruction argument used as an unchecked index",
+        category: "Input validation",
+        taxonomy: Taxonomy::UncheckedInput,
+        severity: BugSeverity::Low,
+        applies_to: "all",
+        description: "Instruction data, or a value decoded from it, indexes or slices a buffer without a length \
+            check. Out-of-range input aborts the transaction with a panic instead of a meaningful error, and \
+            hides which input was malformed.",
+        vulnerable_example: r#"pub fn process_instruction(program_id: &Pubkey, accounts: &[AccountInfo], instruction_data: &[u8]) -> ProgramResult {
+    let amount = u64::from_le_bytes(instruction_data[1..9].try_into().unwrap());
+    // ...
+}"#,
+        fixed_example: r#"pub fn process_instruction(program_id: &Pubkey, accounts: &[AccountInfo], instruction_data: &[u8]) -> ProgramResult {
+    let amount = instruction_data.get(1..9)
+        .and_then(|bytes| bytes.try_into().ok())
+        .map(u64::from_le_bytes)
+        .ok_or(ProgramError::InvalidInstructionData)?;
+    // ...
+}"#,
+        references: &[],
+    },
+    RuleDef {
+        id: TAINT_TRUNCATING_CAST,
+        version: 1,
+        title: "Instruction argument truncated by an `as` cast",
+        category: "Arithmetic",
+        taxonomy: Taxonomy::IntegerOverflow,
+        severity: BugSeverity::Medium,
+        applies_to: "all",
+        description: "A value the caller chooses is cast with `as` into a narrower integer type, which keeps the low \
+            bits and drops the rest without an error. An argument that passed a check in its wide form can come \
+            out as a different, small number.",
+        vulnerable_example: r#"pub fn set_fee(ctx: Context<SetFee>, fee_bps: u64) -> Result<()> {
+    ctx.accounts.config.fee_bps = fee_bps as u16;
+    Ok(())
+}"#,
+        fixed_example: r#"pub fn set_fee(ctx: Context<SetFee>, fee_bps: u64) -> Result<()> {
+    ctx.accounts.config.fee_bps = u16::try_from(fee_bps).map_err(|_| ErrorCode::FeeTooHigh)?;
+    Ok(())
+}"#,
+        references: &[NEODYME_PITFALLS],
+    },
+    RuleDef {
+        id: TOOLCHAIN_PINNED_TOO_OLD,
+        // 2: dependencies' declared rust-version counts
+        version: 2,
+        title: "Pinned Rust toolchain older than the code requires",
+        category: "Toolchain",
+        taxonomy: Taxonomy::Toolchain,
+        severity: BugSeverity::Low,
+        applies_to: "all",
+        description: "rust-toolchain.toml pins a Rust release older than the one the code needs: a language or \
+            standard library feature stabilized later, a newer edition or declared rust-version, a locked \
+            dependency's rust-version, or an Anchor release built against a newer toolchain. Builds with the pinned toolchain fail, so the program that \
+            is deployed was built with something other than what the repository records.",
+        vulnerable_example: r#"# rust-toolchain.toml
+[toolchain]
+channel = "1.68.0"
+
+// lib.rs
+let Some(vault) = ctx.accounts.vault.as_ref() else { return err!(ErrorCode::MissingVault) };
+static FEES: LazyLock<FeeTable> = LazyLock::new(FeeTable::load);"#,
+        fixed_example: r#"# rust-toolchain.toml
+[toolchain]
+channel = "1.80.0""#,
+        references: &[("rustup: the toolchain file", "https://rust-lang.github.io/rustup/overrides.html#the-toolchain-file")],
+    },
+    RuleDef {
+        id: TOOLCHAIN_NIGHTLY_FEATURE,
+        version: 1,
+        title: "Nightly-only feature in on-chain code",
+        category: "Toolchain",
+        taxonomy: Taxonomy::Toolchain,
+        severity: BugSeverity::Medium,
+        applies_to: "all",
+        description: "A program crate enables an unstable feature with #![feature]. It only builds on a nightly \
+            compiler, while cargo build-sbf uses the rustc of the Solana platform tools, and the feature's \
+            behavior can change between nightlies, so the deployed bytecode can't be reproduced or verified \
+            from the source.",
+        vulnerable_example: r#"#![feature(let_chains)]
+
+use anchor_lang::prelude::*;"#,
+        fixed_example: r#"use anchor_lang::prelude::*;
+
+// Nested `if let`s instead of let chains"#,
+        references: &[("The Unstable Book", "https://doc.rust-lang.org/unstable-book/")],
+    },
+    RuleDef {
+        id: CLIPPY,
+        version: 1,
+        title: "Compiler and Clippy warnings",
+        category: "Code quality",
+        taxonomy: Taxonomy::CodeQuality,
+        severity: BugSeverity::Medium,
+        applies_to: "all",
+        description: "Warnings reported by rustc and Clippy. Severity is raised for anything involving unsafe code \
+            and lowered for unused items; the finding text carries the specific lint.",
+        vulnerable_example: r#"let amount = ctx.accounts.vault.amount;
+let fee = amount * FEE_BPS / 10_000;"#,
+        fixed_example: r#"let amount = ctx.accounts.vault.amount;
+let fee = amount.checked_mul(FEE_BPS).ok_or(ErrorCode::Overflow)? / 10_000;"#,
+        references: &[("Clippy lint list", "https://rust-lang.github.io/rust-clippy/master/index.html")],
+    },
+];
+
+pub fn rule_catalog() -> Vec<Rule> {
+    RULES.iter().map(|rule| Rule {
+        id: rule.id.to_string(),
+        version: rule.version,
+        title: rule.title.to_string(),
+        category: rule.category.to_string(),
+        taxonomy: rule.taxonomy,
+        severity: rule.severity,
+        applies_to: rule.applies_to.to_string(),
+        description: rule.description.to_string(),
+        vulnerable_example: rule.vulnerable_example.to_string(),
+        fixed_example: rule.fixed_example.to_string(),
+        references: rule.references.iter().map(|(title, url)| RuleReference {
+            title: title.to_string(),
+            url: url.to_string(),
+        }).collect(),
+    }).collect()
+}
+
+// A released rule set: which rules run, at which version. Analyses run the newest set unless
+// the request pins one, so CI keeps getting the same findings when the server is upgraded.
+// Bumping a rule's version means releasing a new set, and keeping the old behavior reachable
+// for as long as a supported set still names it: passes branch on the pinned set's
+// rule_version.
+struct RuleSetDef {
+    // Semver: major for rules changed or removed, minor for rules added
+    version: &'static str,
+    released: &'static str,
+    // Last day the set can be pinned; set when a newer one is released
+    supported_until: Option<&'static str>,
+    rules: &'static [(&'static str, u32)],
+}
+
+// Oldest first
+const RULE_SETS: &[RuleSetDef] = &[
+    RuleSetDef {
+        version: "1.0.0",
+        released: "2026-10-16",
+        supported_until: Some("2027-04-16"),
+        rules: &[
+            (ANCHOR_MISSING_SIGNER, 1),
+            (NATIVE_MISSING_SIGNER, 1),
+            (NATIVE_MISSING_OWNER_CHECK, 1),
+            (NATIVE_UNCHECKED_DESERIALIZATION, 1),
+            (TS_HARDCODED_SECRET_KEY, 1),
+            (TS_INLINE_SECRET_KEY_ARRAY, 1),
+            (TS_SKIP_PREFLIGHT, 1),
+            (TS_UNAWAITED_CONFIRMATION, 1),
+            (CONFIG_OVERFLOW_CHECKS_DISABLED, 1),
+            (CONFIG_PANIC_STRATEGY_MISMATCH, 1),
+            (CONFIG_DEBUG_ASSERTIONS_STRIPPED, 1),
+            (CONFIG_FEATURE_GATED_CHECK, 1),
+            (WORKSPACE_DUPLICATE_PROGRAM_ID, 1),
+            (WORKSPACE_RESERVED_PROGRAM_ID, 1),
+            (ANCHOR_TOML_PROGRAM_MAPPING, 1),
+            (CPI_TARGET_NOT_ALLOWED, 1),
+            (CPI_TARGET_UNRESOLVED, 1),
+            (CPI_ALLOWLIST_UNUSED, 1),
+            (TAINT_UNCHECKED_ARITHMETIC, 1),
+            (TAINT_UNCHECKED_INDEX, 1),
+            (TAINT_TRUNCATING_CAST, 1),
+            (TOOLCHAIN_PINNED_TOO_OLD, 1),
+            (TOOLCHAIN_NIGHTLY_FEATURE, 1),
+            (CLIPPY, 1),
+        ],
+    },
+    RuleSetDef {
+        version: "2.0.0",
+        released: "2026-10-16",
+        supported_until: None,
+        rules: &[
+            (ANCHOR_MISSING_SIGNER, 1),
+            (NATIVE_MISSING_SIGNER, 1),
+            (NATIVE_MISSING_OWNER_CHECK, 1),
+            (NATIVE_UNCHECKED_DESERIALIZATION, 1),
+            (TS_HARDCODED_SECRET_KEY, 1),
+            (TS_INLINE_SECRET_KEY_ARRAY, 1),
+            (TS_SKIP_PREFLIGHT, 1),
+            (TS_UNAWAITED_CONFIRMATION, 1),
+            (CONFIG_OVERFLOW_CHECKS_DISABLED, 1),
+            (CONFIG_PANIC_STRATEGY_MISMATCH, 1),
+            (CONFIG_DEBUG_ASSERTIONS_STRIPPED, 1),
+            (CONFIG_FEATURE_GATED_CHECK, 1),
+            (WORKSPACE_DUPLICATE_PROGRAM_ID, 1),
+            (WORKSPACE_RESERVED_PROGRAM_ID, 1),
+            (ANCHOR_TOML_PROGRAM_MAPPING, 1),
+            (CPI_TARGET_NOT_ALLOWED, 1),
+            (CPI_TARGET_UNRESOLVED, 1),
+            (CPI_ALLOWLIST_UNUSED, 1),
+            (TAINT_UNCHECKED_ARITHMETIC, 1),
+            (TAINT_UNCHECKED_INDEX, 1),
+            (TAINT_TRUNCATING_CAST, 1),
+            (TOOLCHAIN_PINNED_TOO_OLD, 2),
+            (TOOLCHAIN_NIGHTLY_FEATURE, 1),
+            (CLIPPY, 1),
+        ],
+    },
+];
+
+// The rule set an analysis runs
+#[derive(Debug, Clone)]
+pub struct RuleSet {
+    pub version: String,
+    // Set on deprecated sets, i.e. once a newer one has been released
+    pub supported_until: Option<String>,
+    rules: BTreeMap<String, u32>,
+}
+
+impl RuleSet {
+    fn from_def(def: &RuleSetDef) -> Self {
+        Self {
+            version: def.version.to_string(),
+            supported_until: def.supported_until.map(str::to_string),
+            rules: def.rules.iter().map(|(id, version)| (id.to_string(), *version)).collect(),
+        }
+    }
+
+    // None when the rule isn't part of the set
+    pub fn rule_version(&self, rule_id: &str) -> Option<u32> {
+        self.rules.get(rule_id).copied()
+    }
+
+    // Whether the set runs `rule_id` at `version` or later, for passes that keep an older
+    // version's behavior for the sets that pin it
+    pub fn runs_at_least(&self, rule_id: &str, version: u32) -> bool {
+        self.rule_version(rule_id).is_some_and(|pinned| pinned >= version)
+    }
+
+    // Findings of rules newer than the set are dropped; findings without a catalog rule
+    // (external analyzers, failed passes) are kept
+    pub fn includes(&self, bug: &CodeBug) -> bool {
+        match bug.rule_id.as_deref() {
+            Some(rule_id) if RULES.iter().any(|rule| rule.id == rule_id) => self.rule_version(rule_id).is_some(),
+            _ => true,
+        }
+    }
+}
+
+pub fn current_rule_set() -> RuleSet {
+    RuleSet::from_def(RULE_SETS.last().expect("at least one rule set"))
+}
+
+// "1.0.0" pins that set; "1" or "1.0" the newest set in that series. Sets past their support
+// window can't be pinned.
+pub fn resolve_rule_set(requested: &str) -> Result<RuleSet> {
+    let requested = requested.trim().trim_start_matches('v');
+    let parts: Vec<&str> = requested.split('.').collect();
+    if parts.len() > 3 || parts.iter().any(|part| part.is_empty() || !part.chars().all(|c| c.is_ascii_digit())) {
+        return Err(anyhow!("{} is not a rule set version; use MAJOR[.MINOR[.PATCH]]", requested));
+    }
+    let def = RULE_SETS.iter().rev()
+        .find(|def| def.version.split('.').zip(&parts).all(|(a, b)| a.parse::<u64>().ok() == b.parse::<u64>().ok()))
+        .ok_or_else(|| anyhow!("Unknown rule set {}; available: {}", requested, RULE_SETS.iter().map(|def| def.version).collect::<Vec<_>>().join(", ")))?;
+    let today = chrono::Utc::now().format("%Y-%m-%d").to_string();
+    if let Some(until) = def.supported_until.filter(|until| *until < today.as_str()) {
+        return Err(anyhow!("Rule set {} was supported until {}; pin {} or later", def.version, until, current_rule_set().version));
+    }
+    Ok(RuleSet::from_def(def))
+}
+
+pub fn rule_sets() -> Vec<RuleSetInfo> {
+    RULE_SETS.iter().rev().map(|def| RuleSetInfo {
+        version: def.version.to_string(),
+        released: def.released.to_string(),
+        supported_until: def.supported_until.map(str::to_string),
+        rules: def.rules.iter().map(|(id, version)| (id.to_string(), *version)).collect(),
+    }).collect()
+}
+
+pub fn rule_taxonomy(rule_id: &str) -> Option<Taxonomy> {
+    RULES.iter().find(|rule| rule.id == rule_id).map(|rule| rule.taxonomy)
+}
+
+// Findings per vulnerability class, every class included; the second value counts the findings
+// that have none
+pub fn taxonomy_counts(bugs: &[CodeBug]) -> (Vec<TaxonomyCount>, u32) {
+    let mut counts: Vec<TaxonomyCount> = Taxonomy::ALL.iter().map(|&category| TaxonomyCount {
+        category,
+        title: category.title().to_string(),
+        total: 0,
+        critical: 0,
+        high: 0,
+        medium: 0,
+        low: 0,
+    }).collect();
+    let mut uncategorized = 0;
+    for bug in bugs.iter().filter(|bug| !bug.triage_state.is_some_and(|state| state.is_suppressed())) {
+        let Some(count) = bug.taxonomy.and_then(|taxonomy| counts.iter_mut().find(|c| c.category == taxonomy)) else {
+            uncategorized += 1;
+            continue;
+        };
+        count.total += 1;
+        match bug.severity {
+            BugSeverity::Critical => count.critical += 1,
+            BugSeverity::High => count.high += 1,
+            BugSeverity::Medium => count.medium += 1,
+            BugSeverity::Low => count.low += 1,
+        }
+    }
+    (counts, uncategorized)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn newest_rule_set_runs_every_rule_at_its_current_version() {
+        let newest = RULE_SETS.last().expect("at least one rule set");
+        let mut released: Vec<(&str, u32)> = newest.rules.to_vec();
+        let mut current: Vec<(&str, u32)> = RULES.iter().map(|rule| (rule.id, rule.version)).collect();
+        released.sort();
+        current.sort();
+        assert_eq!(released, current, "release a new rule set when RULES changes");
+    }
+
+    #[test]
+    fn older_rule_sets_only_name_known_rule_versions() {
+        for def in RULE_SETS {
+            for (id, version) in def.rules {
+                let rule = RULES.iter().find(|rule| rule.id == *id).unwrap_or_else(|| panic!("{} names unknown rule {}", def.version, id));
+                assert!(*version >= 1 && *version <= rule.version, "{} names {} at version {}", def.version, id, version);
+            }
+        }
+    }
+
+    #[test]
+    fn pinned_set_keeps_its_rule_versions() {
+        let pinned = resolve_rule_set("1.0.0").unwrap();
+        assert!(!pinned.runs_at_least(TOOLCHAIN_PINNED_TOO_OLD, 2));
+        assert!(current_rule_set().runs_at_least(TOOLCHAIN_PINNED_TOO_OLD, 2));
+    }
+}
@@ -0,0 +1,409 @@
+use anyhow::{anyhow, Result};
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::env;
+use std::ffi::OsStr;
+use std::io::{BufRead, BufReader, Write};
+use std::net::{Shutdown, SocketAddr, TcpListener, TcpStream};
+use std::path::Path;
+use std::process::{Command, Stdio};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex, OnceLock};
+use std::thread;
+use std::time::Duration;
+use tempfile::TempDir;
+
+use crate::models::{BlockedConnection, NetworkReport, SandboxCapability};
+use crate::toolchain::ProvisionedToolchains;
+use crate::vendor::{use_vendored_sources, uses_vendored_sources};
+
+// Hosts cargo needs for crates.io dependencies (sparse index, API and downloads)
+const CRATES_IO_HOSTS: &[&str] = &["crates.io", "index.crates.io", "static.crates.io"];
+
+// Runs vendoring into SAFEX_VENDOR_DIR one at a time
+static SHARED_VENDOR_LOCK: Mutex<()> = Mutex::new(());
+
+// Distinct destinations remembered per run; a build script retrying in a loop shouldn't grow the report
+const MAX_BLOCKED: usize = 100;
+
+// What a job's builds may reach over the network
+#[derive(Debug, Clone, Copy, PartialEq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum NetworkPolicy {
+    // No network at all; dependencies must already be in the host's cargo cache
+    Offline,
+    // `cargo fetch` may download from crates.io before anything from the repository runs;
+    // builds, build scripts and harnesses then run without network
+    #[default]
+    CratesIo,
+    // Unrestricted, for programs with git dependencies or builds that download tools
+    Full,
+}
+
+// Runs cargo and the analyzed code under a network policy. Builds go through a local proxy
+// that refuses everything it isn't told to allow and records what it refused, and, where
+// unprivileged network namespaces are available, run with no network interfaces at all so
+// code ignoring the proxy settings can't get out either. Without them the proxy can be bypassed,
+// so the restricted policies are refused unless SAFEX_ALLOW_UNISOLATED_BUILDS=true.
+pub struct Sandbox {
+    policy: NetworkPolicy,
+    proxy: Option<EgressProxy>,
+    // Vendor dependencies before building instead of fetching them into the cargo cache
+    vendor: bool,
+    vendored: AtomicBool,
+    toolchains: ProvisionedToolchains,
+    // Whose SAFEX_VENDOR_DIR crates the builds may use; without one, crates are vendored into the checkout
+    tenant: Option<String>,
+}
+
+impl Sandbox {
+    pub fn new(policy: NetworkPolicy, vendor: bool) -> Result<Self> {
+        let proxy = match policy {
+            NetworkPolicy::Full => None,
+            NetworkPolicy::Offline | NetworkPolicy::CratesIo if !restricted_policies_available() => {
+                return Err(anyhow!("Network namespaces are unavailable on this host, so builds can't be kept off the network; set SAFEX_ALLOW_UNISOLATED_BUILDS=true to rely on the egress proxy alone"));
+            },
+            NetworkPolicy::Offline | NetworkPolicy::CratesIo => Some(EgressProxy::start()?),
+        };
+        Ok(Self { policy, proxy, vendor, vendored: AtomicBool::new(false), toolchains: ProvisionedToolchains::default(), tenant: None })
+    }
+
+    // Vendor into the tenant's own part of SAFEX_VENDOR_DIR
+    pub fn for_tenant(mut self, tenant: &str) -> Self {
+        self.tenant = Some(tenant.to_string());
+        self
+    }
+
+    // Run commands with the job's provisioned toolchains
+    pub fn with_toolchains(mut self, toolchains: ProvisionedToolchains) -> Self {
+        self.toolchains = toolchains;
+        self
+    }
+
+    // Get the dependencies of the manifest in `dir` in place for an offline build. Repositories
+    // that vendor their dependencies already are; otherwise they are vendored when asked, or
+    // fetched into the cargo cache. Both run with network access, so they run outside the
+    // checkout (see `cargo`) and crates.io is only reachable while nothing untrusted runs.
+    pub fn prepare(&self, dir: &Path, toolchain: Option<&str>) {
+        if !dir.join("Cargo.toml").exists() {
+            return;
+        }
+        if uses_vendored_sources(dir) {
+            println!("Building {} from its vendored dependencies", dir.display());
+            self.vendored.store(true, Ordering::SeqCst);
+            return;
+        }
+        if self.vendor {
+            match self.vendor_dependencies(dir, toolchain) {
+                Ok(()) => {
+                    self.vendored.store(true, Ordering::SeqCst);
+                    return;
+                },
+                Err(e) => println!("Warning: Failed to vendor dependencies, fetching them instead: {}", e),
+            }
+        }
+        let Some(proxy) = self.proxy.as_ref().filter(|_| self.policy == NetworkPolicy::CratesIo) else {
+            return;
+        };
+
+        let output = self.cargo("fetch", toolchain, dir).and_then(|(mut command, _outside)| {
+            proxy.allow(CRATES_IO_HOSTS);
+            let output = command.envs(proxy.env()).stdin(Stdio::null()).output();
+            proxy.allow(&[]);
+            Ok(output?)
+        });
+
+        // The offline build that follows reports whatever is missing
+        match output {
+            Ok(output) if !output.status.success() => {
+                let stderr = String::from_utf8_lossy(&output.stderr);
+                println!("Warning: cargo fetch failed: {}", stderr.lines().rev().find(|l| l.starts_with("error")).unwrap_or("unknown error"));
+            },
+            Err(e) => println!("Warning: Failed to run cargo fetch: {}", e),
+            Ok(_) => {},
+        }
+    }
+
+    // `cargo vendor` into the tenant's directory under SAFEX_VENDOR_DIR when set, so crates
+    // accumulate there across runs and deployments without crates.io access can be seeded ahead
+    // of time; otherwise into the checkout itself. Tenants don't share crates: whatever one
+    // tenant's build leaves in its directory is only ever built by that tenant.
+    fn vendor_dependencies(&self, dir: &Path, toolchain: Option<&str>) -> Result<()> {
+        let shared = env::var("SAFEX_VENDOR_DIR").ok().filter(|d| !d.is_empty())
+            .zip(self.tenant.as_ref())
+            .map(|(vendor_dir, tenant)| Path::new(&vendor_dir).join(&format!("{:x}", Sha256::digest(tenant.as_bytes()))[..16]));
+        // Absolute, as cargo runs elsewhere
+        let vendor_dir = std::path::absolute(shared.clone().unwrap_or_else(|| dir.join(".safex-vendor")))?;
+        let _guard = shared.as_ref().map(|_| SHARED_VENDOR_LOCK.lock().unwrap_or_else(|e| e.into_inner()));
+
+        let (mut command, _outside) = self.cargo("vendor", toolchain, dir)?;
+        command.arg("--versioned-dirs");
+        if shared.is_some() {
+            command.arg("--no-delete");
+        }
+        command.arg(&vendor_dir).stdin(Stdio::null());
+        if let Some(proxy) = &self.proxy {
+            command.envs(proxy.env());
+            match self.policy {
+                NetworkPolicy::CratesIo => proxy.allow(CRATES_IO_HOSTS),
+                _ => {
+                    command.env("CARGO_NET_OFFLINE", "true");
+                },
+            }
+        }
+        let output = command.output();
+        if let Some(proxy) = &self.proxy {
+            proxy.allow(&[]);
+        }
+        let output = output.map_err(|e| anyhow!("Failed to run cargo vendor: {}", e))?;
+        if !output.status.success() {
+            let stderr = String::from_utf8_lossy(&output.stderr);
+            return Err(anyhow!("{}", stderr.lines().rev().find(|l| l.starts_with("error")).unwrap_or("cargo vendor failed")));
+        }
+
+        // cargo vendor prints the source replacement that builds from the vendored copy
+        use_vendored_sources(dir, &String::from_utf8_lossy(&output.stdout))?;
+        println!("Vendored dependencies of {} into {}", dir.display(), vendor_dir.display());
+        Ok(())
+    }
+
+    // A command that builds or runs code from the repository under the policy
+    pub fn command(&self, program: impl AsRef<OsStr>) -> Command {
+        let Some(proxy) = &self.proxy else {
+            let mut command = Command::new(program);
+            self.toolchains.apply(&mut command);
+            return command;
+        };
+        let mut command = if isolation_available() {
+            let mut command = Command::new("unshare");
+            command.args(["--net", "--map-root-user", "--"]).arg(program);
+            command
+        } else {
+            Command::new(program)
+        };
+        command.envs(proxy.env()).env("CARGO_NET_OFFLINE", "true");
+        self.toolchains.apply(&mut command);
+        command
+    }
+
+    // `cargo <subcommand>` on the manifest in `dir`, run from an empty directory that lives as long as the
+    // returned one. Cargo reads `.cargo/config.toml` from where it runs rather than from the
+    // manifest's directory, and a checkout's can name a rustc or wrapper that cargo runs even
+    // for `fetch --offline`; rustup likewise reads the checkout's `rust-toolchain.toml`.
+    fn cargo(&self, subcommand: &str, toolchain: Option<&str>, dir: &Path) -> Result<(Command, TempDir)> {
+        let outside = TempDir::new()?;
+        let mut command = Command::new("cargo");
+        command.arg(subcommand).arg("--manifest-path").arg(dir.canonicalize()?.join("Cargo.toml")).current_dir(outside.path());
+        if let Some(channel) = toolchain {
+            command.env("RUSTUP_TOOLCHAIN", channel);
+        }
+        self.toolchains.apply(&mut command);
+        Ok((command, outside))
+    }
+
+    pub fn report(&self) -> NetworkReport {
+        NetworkReport {
+            policy: self.policy,
+            isolated: self.proxy.is_some() && isolation_available(),
+            vendored: self.vendored.load(Ordering::SeqCst),
+            blocked: self.proxy.as_ref().map(|proxy| proxy.state.blocked.lock().unwrap().clone()).unwrap_or_default(),
+        }
+    }
+}
+
+// The network policies builds can run under here. Without network namespaces, the restricted
+// ones are only listed when the operator accepts the egress proxy alone.
+pub fn sandbox_levels() -> Vec<SandboxCapability> {
+    [NetworkPolicy::Offline, NetworkPolicy::CratesIo, NetworkPolicy::Full].into_iter()
+        .filter(|policy| *policy == NetworkPolicy::Full || restricted_policies_available())
+        .map(|network_policy| SandboxCapability { network_policy, isolated: network_policy != NetworkPolicy::Full && isolation_available() })
+        .collect()
+}
+
+// Offline and crates-io builds need network namespaces, unless SAFEX_ALLOW_UNISOLATED_BUILDS
+// says the proxy will do
+fn restricted_policies_available() -> bool {
+    isolation_available() || env::var("SAFEX_ALLOW_UNISOLATED_BUILDS").is_ok_and(|value| value == "true" || value == "1")
+}
+
+// Whether `unshare --net` works here; containers often forbid unprivileged user namespaces
+fn isolation_available() -> bool {
+    static AVAILABLE: OnceLock<bool> = OnceLock::new();
+    *AVAILABLE.get_or_init(|| {
+        let available = Command::new("unshare")
+            .args(["--net", "--map-root-user", "--", "true"])
+            .stdin(Stdio::null())
+            .stdout(Stdio::null())
+            .stderr(Stdio::null())
+            .status()
+            .is_ok_and(|status| status.success());
+        if !available {
+            println!("Warning: unshare --net is unavailable, so offline and crates-io builds are refused unless SAFEX_ALLOW_UNISOLATED_BUILDS=true");
+        }
+        available
+    })
+}
+
+#[derive(Default)]
+struct ProxyState {
+    allowed: Mutex<Vec<String>>,
+    blocked: Mutex<Vec<BlockedConnection>>,
+    stopped: AtomicBool,
+}
+
+impl ProxyState {
+    fn block(&self, host: &str, port: u16) {
+        println!("Warning: Sandbox blocked a connection to {}:{}", host, port);
+        let mut blocked = self.blocked.lock().unwrap();
+        if let Some(existing) = blocked.iter_mut().find(|b| b.host == host && b.port == port) {
+            existing.attempts += 1;
+        } else if blocked.len() < MAX_BLOCKED {
+            blocked.push(BlockedConnection { host: host.to_string(), port, attempts: 1 });
+        }
+    }
+}
+
+// HTTP proxy on localhost, tunnelling CONNECT and forwarding plain HTTP to allowed hosts only
+struct EgressProxy {
+    addr: SocketAddr,
+    state: Arc<ProxyState>,
+}
+
+impl EgressProxy {
+    fn start() -> Result<Self> {
+        let listener = TcpListener::bind("127.0.0.1:0")?;
+        let addr = listener.local_addr()?;
+        let state = Arc::new(ProxyState::default());
+        let accept_state = state.clone();
+        thread::spawn(move || {
+            for stream in listener.incoming() {
+                if accept_state.stopped.load(Ordering::SeqCst) {
+                    break;
+                }
+                let Ok(stream) = stream else { continue };
+                let state = accept_state.clone();
+                thread::spawn(move || {
+                    let _ = proxy_connection(stream, &state);
+                });
+            }
+        });
+        Ok(Self { addr, state })
+    }
+
+    fn allow(&self, hosts: &[&str]) {
+        *self.state.allowed.lock().unwrap() = hosts.iter().map(|h| h.to_string()).collect();
+    }
+
+    // Proxy settings for cargo, libgit2, curl and most HTTP clients; NO_PROXY is cleared so
+    // the host's exemptions don't bypass it
+    fn env(&self) -> Vec<(&'static str, String)> {
+        let url = format!("http://{}", self.addr);
+        let mut env: Vec<(&'static str, String)> = ["CARGO_HTTP_PROXY", "HTTP_PROXY", "HTTPS_PROXY", "ALL_PROXY", "http_proxy", "https_proxy", "all_proxy"]
+            .into_iter()
+            .map(|name| (name, url.clone()))
+            .collect();
+        env.extend([("NO_PROXY", String::new()), ("no_proxy", String::new())]);
+        env
+    }
+}
+
+impl Drop for EgressProxy {
+    fn drop(&mut self) {
+        // Wake the accept loop so it sees the flag and exits
+        self.state.stopped.store(true, Ordering::SeqCst);
+        let _ = TcpStream::connect(self.addr);
+    }
+}
+
+fn proxy_connection(client: TcpStream, state: &ProxyState) -> Result<()> {
+    client.set_read_timeout(Some(Duration::from_secs(30)))?;
+    let mut reader = BufReader::new(client.try_clone()?);
+    let mut head = String::new();
+    loop {
+        let mut line = String::new();
+        if reader.read_line(&mut line)? == 0 || line == "\r\n" || line == "\n" {
+            head.push_str(&line);
+            break;
+        }
+        head.push_str(&line);
+        if head.len() > 16 * 1024 {
+            return Err(anyhow!("Request head too large"));
+        }
+    }
+
+    let mut parts = head.split_whitespace();
+    let (method, target) = (parts.next().unwrap_or_default(), parts.next().unwrap_or_default());
+    let connect = method.eq_ignore_ascii_case("CONNECT");
+    let destination = if connect {
+        split_host_port(target, 443)
+    } else {
+        target.strip_prefix("http://").and_then(|rest| rest.split('/').next()).and_then(|authority| split_host_port(authority, 80))
+    };
+    let mut client = client;
+    let Some((host, port)) = destination else {
+        client.write_all(b"HTTP/1.1 400 Bad Request\r\nContent-Length: 0\r\nConnection: close\r\n\r\n")?;
+        return Ok(());
+    };
+    if !state.allowed.lock().unwrap().contains(&host) {
+        state.block(&host, port);
+        client.write_all(b"HTTP/1.1 403 Forbidden\r\nContent-Length: 0\r\nConnection: close\r\n\r\n")?;
+        return Ok(());
+    }
+
+    let mut upstream = TcpStream::connect((host.as_str(), port))?;
+    client.set_read_timeout(None)?;
+    if connect {
+        client.write_all(b"HTTP/1.1 200 Connection Established\r\n\r\n")?;
+    } else {
+        upstream.write_all(head.as_bytes())?;
+    }
+
+    // The reader still holds anything the client sent after the request head
+    let mut upstream_writer = upstream.try_clone()?;
+    let outbound = thread::spawn(move || {
+        let _ = std::io::copy(&mut reader, &mut upstream_writer);
+        let _ = upstream_writer.shutdown(Shutdown::Write);
+    });
+    let _ = std::io::copy(&mut upstream, &mut client);
+    let _ = client.shutdown(Shutdown::Write);
+    let _ = outbound.join();
+    Ok(())
+}
+
+fn split_host_port(authority: &str, default_port: u16) -> Option<(String, u16)> {
+    let (host, port) = match authority.rsplit_once(':') {
+        Some((host, port)) => (host, port.parse().ok()?),
+        None => (authority, default_port),
+    };
+    let host = host.trim_start_matches('[').trim_end_matches(']').to_lowercase();
+    (!host.is_empty()).then_some((host, port))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs;
+    use std::os::unix::fs::PermissionsExt;
+
+    #[test]
+    fn preparing_never_runs_the_checkouts_cargo_config() {
+        env::set_var("SAFEX_ALLOW_UNISOLATED_BUILDS", "true");
+        for key in ["rustc-wrapper", "rustc"] {
+            let checkout = TempDir::new().unwrap();
+            let dir = checkout.path();
+            let marker = dir.join("ran");
+            fs::create_dir_all(dir.join("src")).unwrap();
+            fs::create_dir_all(dir.join(".cargo")).unwrap();
+            fs::write(dir.join("Cargo.toml"), "[package]\nname = \"victim\"\nversion = \"0.1.0\"\nedition = \"2021\"\n").unwrap();
+            fs::write(dir.join("src/lib.rs"), "").unwrap();
+            fs::write(dir.join("evil.sh"), format!("#!/bin/sh\ntouch {}\nexec \"$@\"\n", marker.display())).unwrap();
+            fs::set_permissions(dir.join("evil.sh"), fs::Permissions::from_mode(0o755)).unwrap();
+            fs::write(dir.join(".cargo/config.toml"), format!("[build]\n{} = \"{}\"\n", key, dir.join("evil.sh").display())).unwrap();
+
+            Sandbox::new(NetworkPolicy::CratesIo, false).unwrap().prepare(dir, None);
+            assert!(!marker.exists(), "cargo fetch ran build.{}", key);
+            Sandbox::new(NetworkPolicy::Full, true).unwrap().prepare(dir, None);
+            assert!(!marker.exists(), "cargo vendor ran build.{}", key);
+        }
+    }
+}
@@ -0,0 +1,77 @@
+use anyhow::{anyhow, Result};
+use serde::Serialize;
+use serde_json::Value;
+use solana_sdk::pubkey::Pubkey;
+use solana_sdk::signature::{read_keypair_file, Keypair, Signer};
+use std::env;
+
+use crate::db::now_unix;
+use crate::models::ResponseSignature;
+
+pub const ALGORITHM: &str = "ed25519";
+
+// Signs what the verification endpoints answer, so a consumer holding a response can prove
+// later what this instance attested and when
+pub struct ResponseSigner {
+    keypair: Keypair,
+}
+
+impl ResponseSigner {
+    // SAFEX_RESPONSE_SIGNING_KEYPAIR is an ed25519 keypair file (as written by `solana-keygen`).
+    // Without it responses go out unsigned.
+    pub fn from_env() -> Result<Option<Self>> {
+        let Ok(path) = env::var("SAFEX_RESPONSE_SIGNING_KEYPAIR") else {
+            return Ok(None);
+        };
+        let keypair = read_keypair_file(&path).map_err(|e| anyhow!("Failed to read response signing keypair {}: {}", path, e))?;
+        Ok(Some(Self { keypair }))
+    }
+
+    pub fn public_key(&self) -> Pubkey {
+        self.keypair.pubkey()
+    }
+
+    // The signature covers the canonical JSON of the whole response with the signature's own
+    // `value` left out: verifiers remove `signature.value`, canonicalize the rest and check it
+    // against `signature.public_key`. The response's `signature` field is replaced.
+    pub fn sign<T: Serialize>(&self, response: &T) -> Result<ResponseSignature> {
+        let mut signature = ResponseSignature {
+            algorithm: ALGORITHM.to_string(),
+            public_key: self.public_key().to_string(),
+            signed_at: now_unix(),
+            value: String::new(),
+        };
+        let mut payload = serde_json::to_value(response)?;
+        let Value::Object(fields) = &mut payload else {
+            return Err(anyhow!("Only JSON objects can be signed"));
+        };
+        let mut unsigned = serde_json::to_value(&signature)?;
+        if let Value::Object(signature_fields) = &mut unsigned {
+            signature_fields.remove("value");
+        }
+        fields.insert("signature".to_string(), unsigned);
+
+        signature.value = self.keypair.sign_message(canonical_json(&payload).as_bytes()).to_string();
+        Ok(signature)
+    }
+}
+
+// Compact JSON with object keys sorted at every level, so the signed bytes don't depend on
+// field order
+pub fn canonical_json(value: &Value) -> String {
+    match value {
+        Value::Object(fields) => {
+            let mut entries: Vec<(&String, &Value)> = fields.iter().collect();
+            entries.sort_by(|a, b| a.0.cmp(b.0));
+            let entries: Vec<String> = entries.into_iter()
+                .map(|(key, value)| format!("{}:{}", Value::String(key.clone()), canonical_json(value)))
+                .collect();
+            format!("{{{}}}", entries.join(","))
+        },
+        Value::Array(items) => {
+            let items: Vec<String> = items.iter().map(canonical_json).collect();
+            format!("[{}]", items.join(","))
+        },
+        scalar => scalar.to_string(),
+    }
+}
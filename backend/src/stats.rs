@@ -0,0 +1,227 @@
+use anyhow::Result;
+use regex::Regex;
+use std::collections::{BTreeMap, BTreeSet};
+use std::fs;
+use std::path::{Path, PathBuf};
+use toml::Table;
+
+use crate::models::{ProgramStats, RepoStats};
+use crate::vendor::is_vendored_crate;
+
+// Extensions counted towards the language breakdown; everything else is ignored
+const LANGUAGES: &[(&str, &str)] = &[
+    ("rs", "Rust"),
+    ("ts", "TypeScript"),
+    ("tsx", "TypeScript"),
+    ("js", "JavaScript"),
+    ("jsx", "JavaScript"),
+    ("mjs", "JavaScript"),
+    ("cjs", "JavaScript"),
+    ("toml", "TOML"),
+    ("json", "JSON"),
+    ("md", "Markdown"),
+    ("sh", "Shell"),
+    ("py", "Python"),
+    ("c", "C"),
+    ("h", "C"),
+    ("cpp", "C++"),
+    ("go", "Go"),
+    ("sol", "Solidity"),
+    ("yml", "YAML"),
+    ("yaml", "YAML"),
+];
+
+const TEST_EXTENSIONS: &[&str] = &["rs", "ts", "js"];
+
+// Size/complexity estimate of a checked-out repository, used to scope audits
+pub fn compute_repo_stats(repo_path: &Path) -> Result<RepoStats> {
+    let mut files = Vec::new();
+    collect_files(repo_path, &mut files)?;
+
+    let mut languages: BTreeMap<String, u64> = BTreeMap::new();
+    let mut test_lines = 0;
+    for file in &files {
+        let Some(extension) = file.extension().and_then(|e| e.to_str()) else {
+            continue;
+        };
+        if let Some((_, language)) = LANGUAGES.iter().find(|(ext, _)| *ext == extension) {
+            *languages.entry(language.to_string()).or_default() += fs::metadata(file)?.len();
+        }
+        if TEST_EXTENSIONS.contains(&extension) && is_test_path(repo_path, file) {
+            test_lines += count_lines(file);
+        }
+    }
+
+    let counter = InstructionCounter::new();
+    let mut dependencies = BTreeSet::new();
+    let mut programs = Vec::new();
+    let mut program_rust_lines = 0;
+    for manifest in files.iter().filter(|f| f.file_name().is_some_and(|n| n == "Cargo.toml")) {
+        let Ok(cargo_toml) = fs::read_to_string(manifest).unwrap_or_default().parse::<Table>() else {
+            continue;
+        };
+        for section in ["dependencies", "dev-dependencies", "build-dependencies"] {
+            if let Some(deps) = cargo_toml.get(section).and_then(|d| d.as_table()) {
+                dependencies.extend(deps.keys().cloned());
+            }
+        }
+        if let Some(deps) = cargo_toml.get("workspace")
+            .and_then(|w| w.get("dependencies"))
+            .and_then(|d| d.as_table()) {
+            dependencies.extend(deps.keys().cloned());
+        }
+
+        let Some(program_dir) = manifest.parent().filter(|_| is_program_manifest(&cargo_toml)) else {
+            continue;
+        };
+
+        let mut instructions = 0;
+        for file in files.iter().filter(|f| f.starts_with(program_dir) && f.extension().is_some_and(|e| e == "rs")) {
+            if is_test_path(repo_path, file) {
+                continue;
+            }
+            program_rust_lines += count_lines(file);
+            instructions += counter.count(&fs::read_to_string(file).unwrap_or_default());
+        }
+        programs.push(ProgramStats {
+            name: package_name(&cargo_toml),
+            path: program_dir.strip_prefix(repo_path).unwrap_or(program_dir).display().to_string(),
+            instructions,
+        });
+    }
+
+    Ok(RepoStats {
+        languages,
+        program_count: programs.len() as u32,
+        instruction_count: programs.iter().map(|p| p.instructions).sum(),
+        programs,
+        program_rust_lines,
+        test_lines,
+        dependency_count: dependencies.len() as u32,
+    })
+}
+
+// A program is any on-chain crate: it depends on anchor-lang or solana-program
+pub fn is_program_manifest(cargo_toml: &Table) -> bool {
+    let deps = cargo_toml.get("dependencies").and_then(|d| d.as_table());
+    cargo_toml.contains_key("package")
+        && deps.is_some_and(|d| d.contains_key("anchor-lang") || d.contains_key("solana-program"))
+}
+
+pub fn package_name(cargo_toml: &Table) -> String {
+    cargo_toml.get("package")
+        .and_then(|p| p.get("name"))
+        .and_then(|n| n.as_str())
+        .unwrap_or("unknown")
+        .to_string()
+}
+
+pub fn collect_files(dir_path: &Path, files: &mut Vec<PathBuf>) -> Result<()> {
+    for entry in fs::read_dir(dir_path)? {
+        let entry = entry?;
+        let path = entry.path();
+        if path.file_name()
+            .and_then(|name| name.to_str())
+            .is_some_and(|name| name.starts_with('.') || name == "target" || name == "node_modules") {
+            continue;
+        }
+        // Don't follow symlinks out of the clone
+        let file_type = entry.file_type()?;
+        if file_type.is_dir() {
+            if !is_vendored_crate(&path) {
+                collect_files(&path, files)?;
+            }
+        } else if file_type.is_file() {
+            files.push(path);
+        }
+    }
+    Ok(())
+}
+
+// 1-based line of a byte offset. Counting newlines rather than `lines()` keeps a match in the
+// middle of a line on that line, and a match at the very start on line 1.
+pub fn line_at(source: &str, offset: usize) -> u32 {
+    source[..offset].matches('\n').count() as u32 + 1
+}
+
+pub fn is_test_path(repo_path: &Path, file: &Path) -> bool {
+    let relative = file.strip_prefix(repo_path).unwrap_or(file);
+    relative.components().any(|c| c.as_os_str() == "tests" || c.as_os_str() == "test")
+        || relative.file_stem().and_then(|s| s.to_str()).is_some_and(|s| s.ends_with("_test") || s.ends_with(".test") || s.ends_with(".spec"))
+}
+
+// Non-blank lines
+fn count_lines(file: &Path) -> u64 {
+    fs::read_to_string(file)
+        .map(|content| content.lines().filter(|l| !l.trim().is_empty()).count() as u64)
+        .unwrap_or(0)
+}
+
+// Anchor: `pub fn` handlers directly inside the #[program] module.
+// Native: variants of `*Instruction` enums, which is how dispatch is conventionally written.
+struct InstructionCounter {
+    handler: Regex,
+    instruction_enum: Regex,
+    variant: Regex,
+}
+
+impl InstructionCounter {
+    fn new() -> Self {
+        Self {
+            handler: Regex::new(r"^\s*pub\s+fn\s+\w+").unwrap(),
+            instruction_enum: Regex::new(r"pub\s+enum\s+\w*Instruction\w*\s*\{").unwrap(),
+            variant: Regex::new(r"^\s*[A-Z]\w*\s*(\{|\(|,|$)").unwrap(),
+        }
+    }
+
+    fn count(&self, source: &str) -> u32 {
+        let mut count = 0;
+        if let Some(idx) = source.find("#[program]") {
+            count += top_level_lines(&source[idx..]).iter().filter(|l| self.handler.is_match(l)).count() as u32;
+        }
+        for found in self.instruction_enum.find_iter(source) {
+            count += top_level_lines(&source[found.start()..]).iter().filter(|l| self.variant.is_match(l)).count() as u32;
+        }
+        count
+    }
+}
+
+// Lines sitting directly inside the first `{ ... }` block of `source`
+fn top_level_lines(source: &str) -> Vec<&str> {
+    let Some(open) = source.find('{') else {
+        return Vec::new();
+    };
+    let mut lines = Vec::new();
+    let mut depth = 1;
+    for line in source[open + 1..].lines() {
+        if depth == 1 {
+            lines.push(line);
+        }
+        for c in line.chars() {
+            match c {
+                '{' => depth += 1,
+                '}' => depth -= 1,
+                _ => {},
+            }
+        }
+        if depth <= 0 {
+            break;
+        }
+    }
+    lines
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn line_at_counts_the_line_an_offset_is_on() {
+        let source = "use a;\nfn f() { g(); }\n";
+        assert_eq!(line_at(source, 0), 1);
+        assert_eq!(line_at(source, source.find("fn").unwrap()), 2);
+        // Mid-line, where counting `lines()` of the prefix gave 3
+        assert_eq!(line_at(source, source.find("g()").unwrap()), 2);
+        assert_eq!(line_at(source, source.len()), 3);
+    }
+}
@@ -0,0 +1,225 @@
+use anyhow::{anyhow, Result};
+use async_trait::async_trait;
+use hmac::{Hmac, Mac};
+use reqwest::{Client, StatusCode};
+use sha2::{Digest, Sha256};
+use std::env;
+use std::path::{Component, Path, PathBuf};
+use std::sync::Arc;
+use std::time::Duration;
+
+// Where reports, fuzz harnesses and logs are kept so any instance can serve them
+#[async_trait]
+pub trait Storage: Send + Sync {
+    async fn put(&self, key: &str, data: Vec<u8>, content_type: &str) -> Result<()>;
+    async fn get(&self, key: &str) -> Result<Option<Vec<u8>>>;
+}
+
+// SAFEX_STORAGE selects the backend: "local" (default) or "s3"
+pub fn storage_from_env() -> Result<Arc<dyn Storage>> {
+    match env::var("SAFEX_STORAGE").unwrap_or_else(|_| "local".to_string()).as_str() {
+        "local" => {
+            let root = env::var("SAFEX_STORAGE_DIR").unwrap_or_else(|_| "artifacts".to_string());
+            println!("Storing artifacts on local disk at: {}", root);
+            Ok(Arc::new(LocalStorage::new(PathBuf::from(root))))
+        },
+        "s3" => {
+            let storage = S3Storage::from_env()?;
+            println!("Storing artifacts in S3 bucket: {}", storage.bucket);
+            Ok(Arc::new(storage))
+        },
+        other => Err(anyhow!("Unknown SAFEX_STORAGE backend: {}", other)),
+    }
+}
+
+// Keys are relative, slash-separated paths; anything that could escape the root is rejected
+pub fn validate_key(key: &str) -> Result<()> {
+    let valid = !key.is_empty()
+        && !key.starts_with('/')
+        && !key.contains('\\')
+        && Path::new(key).components().all(|c| matches!(c, Component::Normal(_)));
+    if valid {
+        Ok(())
+    } else {
+        Err(anyhow!("Invalid artifact key: {}", key))
+    }
+}
+
+// The key `key` is stored under for `tenant`. Artifacts live under their tenant so only it is
+// served them.
+pub fn tenant_key(tenant: &str, key: &str) -> String {
+    format!("{}{}", tenant_prefix(tenant), key)
+}
+
+pub fn owned_by(key: &str, tenant: &str) -> bool {
+    key.starts_with(&tenant_prefix(tenant))
+}
+
+fn tenant_prefix(tenant: &str) -> String {
+    format!("tenants/{}/", &format!("{:x}", Sha256::digest(tenant.as_bytes()))[..16])
+}
+
+// Content type for serving a stored artifact, based on its extension
+pub fn content_type_for_key(key: &str) -> &'static str {
+    match Path::new(key).extension().and_then(|e| e.to_str()) {
+        Some("html") => "text/html; charset=utf-8",
+        Some("json") => "application/json",
+        Some("rs") | Some("log") | Some("txt") | Some("toml") => "text/plain; charset=utf-8",
+        _ => "application/octet-stream",
+    }
+}
+
+pub struct LocalStorage {
+    root: PathBuf,
+}
+
+impl LocalStorage {
+    pub fn new(root: PathBuf) -> Self {
+        Self { root }
+    }
+}
+
+#[async_trait]
+impl Storage for LocalStorage {
+    async fn put(&self, key: &str, data: Vec<u8>, _content_type: &str) -> Result<()> {
+        validate_key(key)?;
+        let path = self.root.join(key);
+        if let Some(parent) = path.parent() {
+            tokio::fs::create_dir_all(parent).await?;
+        }
+        tokio::fs::write(path, data).await?;
+        Ok(())
+    }
+
+    async fn get(&self, key: &str) -> Result<Option<Vec<u8>>> {
+        validate_key(key)?;
+        match tokio::fs::read(self.root.join(key)).await {
+            Ok(data) => Ok(Some(data)),
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(None),
+            Err(e) => Err(e.into()),
+        }
+    }
+}
+
+// S3-compatible object storage (AWS, MinIO, R2...) using path-style requests signed with SigV4
+pub struct S3Storage {
+    client: Client,
+    endpoint: String,
+    bucket: String,
+    region: String,
+    access_key: String,
+    secret_key: String,
+}
+
+impl S3Storage {
+    pub fn from_env() -> Result<Self> {
+        let required = |name: &str| env::var(name).map_err(|_| anyhow!("{} must be set for S3 storage", name));
+        let client = Client::builder()
+            .timeout(Duration::from_secs(60))
+            .build()?;
+
+        Ok(Self {
+            client,
+            endpoint: required("SAFEX_S3_ENDPOINT")?.trim_end_matches('/').to_string(),
+            bucket: required("SAFEX_S3_BUCKET")?,
+            region: env::var("SAFEX_S3_REGION").unwrap_or_else(|_| "us-east-1".to_string()),
+            access_key: required("SAFEX_S3_ACCESS_KEY")?,
+            secret_key: required("SAFEX_S3_SECRET_KEY")?,
+        })
+    }
+
+    fn object_path(&self, key: &str) -> String {
+        format!("/{}/{}", uri_encode(&self.bucket), key.split('/').map(uri_encode).collect::<Vec<_>>().join("/"))
+    }
+
+    // Build a SigV4-signed request for a single object
+    fn signed_request(&self, method: reqwest::Method, key: &str, body: &[u8]) -> Result<reqwest::RequestBuilder> {
+        let now = chrono::Utc::now();
+        let amz_date = now.format("%Y%m%dT%H%M%SZ").to_string();
+        let date = now.format("%Y%m%d").to_string();
+        let host = self.endpoint
+            .split("://")
+            .nth(1)
+            .unwrap_or(&self.endpoint)
+            .to_string();
+        let path = self.object_path(key);
+        let payload_hash = hex_sha256(body);
+
+        let canonical_request = format!(
+            "{}\n{}\n\nhost:{}\nx-amz-content-sha256:{}\nx-amz-date:{}\n\nhost;x-amz-content-sha256;x-amz-date\n{}",
+            method, path, host, payload_hash, amz_date, payload_hash
+        );
+        let scope = format!("{}/{}/s3/aws4_request", date, self.region);
+        let string_to_sign = format!("AWS4-HMAC-SHA256\n{}\n{}\n{}", amz_date, scope, hex_sha256(canonical_request.as_bytes()));
+
+        let signing_key = [date.as_str(), self.region.as_str(), "s3", "aws4_request"]
+            .iter()
+            .try_fold(format!("AWS4{}", self.secret_key).into_bytes(), |key, part| hmac_sha256(&key, part.as_bytes()))?;
+        let signature: String = hmac_sha256(&signing_key, string_to_sign.as_bytes())?
+            .iter()
+            .map(|b| format!("{:02x}", b))
+            .collect();
+
+        let authorization = format!(
+            "AWS4-HMAC-SHA256 Credential={}/{}, SignedHeaders=host;x-amz-content-sha256;x-amz-date, Signature={}",
+            self.access_key, scope, signature
+        );
+
+        Ok(self.client
+            .request(method, format!("{}{}", self.endpoint, path))
+            .header("x-amz-date", amz_date)
+            .header("x-amz-content-sha256", payload_hash)
+            .header("Authorization", authorization))
+    }
+}
+
+#[async_trait]
+impl Storage for S3Storage {
+    async fn put(&self, key: &str, data: Vec<u8>, content_type: &str) -> Result<()> {
+        validate_key(key)?;
+        let response = self.signed_request(reqwest::Method::PUT, key, &data)?
+            .header("Content-Type", content_type)
+            .body(data)
+            .send()
+            .await?;
+        if !response.status().is_success() {
+            let status = response.status();
+            let error_text = response.text().await.unwrap_or_default();
+            return Err(anyhow!("S3 upload failed: {} - {}", status, error_text));
+        }
+        Ok(())
+    }
+
+    async fn get(&self, key: &str) -> Result<Option<Vec<u8>>> {
+        validate_key(key)?;
+        let response = self.signed_request(reqwest::Method::GET, key, b"")?.send().await?;
+        match response.status() {
+            StatusCode::NOT_FOUND => Ok(None),
+            status if status.is_success() => Ok(Some(response.bytes().await?.to_vec())),
+            status => {
+                let error_text = response.text().await.unwrap_or_default();
+                Err(anyhow!("S3 download failed: {} - {}", status, error_text))
+            }
+        }
+    }
+}
+
+fn hex_sha256(data: &[u8]) -> String {
+    let mut hasher = Sha256::new();
+    hasher.update(data);
+    format!("{:x}", hasher.finalize())
+}
+
+fn hmac_sha256(key: &[u8], data: &[u8]) -> Result<Vec<u8>> {
+    let mut mac = Hmac::<Sha256>::new_from_slice(key).map_err(|e| anyhow!("Invalid HMAC key: {}", e))?;
+    mac.update(data);
+    Ok(mac.finalize().into_bytes().to_vec())
+}
+
+// RFC 3986 encoding as required by SigV4 (everything but unreserved characters)
+fn uri_encode(segment: &str) -> String {
+    segment.bytes().map(|b| match b {
+        b'A'..=b'Z' | b'a'..=b'z' | b'0'..=b'9' | b'-' | b'.' | b'_' | b'~' => (b as char).to_string(),
+        _ => format!("%{:02X}", b),
+    }).collect()
+}
@@ -0,0 +1,199 @@
+use anyhow::Result;
+use regex::Regex;
+use std::collections::{HashMap, HashSet};
+use std::fs;
+use std::path::Path;
+
+use crate::rent::{block_body, top_level_split};
+use crate::stats::{collect_files, is_test_path};
+
+// Where an instruction argument ends up without a check in between
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Sink {
+    // +, - or * that can overflow
+    Arithmetic,
+    // Indexing or slicing that panics out of bounds
+    Index,
+    // `as` into a narrower integer, which silently truncates
+    Cast,
+}
+
+pub struct TaintFinding {
+    pub file: String,
+    pub line: u32,
+    pub function: String,
+    pub variable: String,
+    pub sink: Sink,
+}
+
+// Integer types an unchecked `as` can truncate a u64 argument into
+const NARROW_INTS: &str = "u8|u16|u32|i8|i16|i32";
+
+// Intra-procedural dataflow from what the caller of an instruction controls to sinks that
+// misbehave on hostile values. Sources are the arguments of Anchor handlers after the
+// Context, and the raw instruction data of native processors; taint follows `let` bindings
+// and assignments in source order. A variable compared in a require!, assert! or if before
+// the sink counts as bounded. Calls into other functions aren't followed.
+pub fn find_tainted_sinks(repo_path: &Path) -> Result<Vec<TaintFinding>> {
+    let mut files = Vec::new();
+    collect_files(repo_path, &mut files)?;
+    let mut findings = Vec::new();
+    for file in files.iter().filter(|f| f.extension().is_some_and(|e| e == "rs") && !is_test_path(repo_path, f)) {
+        let Ok(source) = fs::read_to_string(file) else { continue };
+        let path = file.strip_prefix(repo_path).unwrap_or(file).display().to_string();
+        findings.extend(tainted_sinks_in(&path, &source));
+    }
+    Ok(findings)
+}
+
+// The same analysis over one file's source, reported against `path`
+pub fn tainted_sinks_in(path: &str, source: &str) -> Vec<TaintFinding> {
+    let re_fn = Regex::new(r"\bfn\s+(\w+)\s*(?:<[^{(]*>)?\s*\(").unwrap();
+    let re_param = Regex::new(r"^(?:mut\s+)?(\w+)\s*:\s*(.+)$").unwrap();
+
+    let mut findings = Vec::new();
+    for captures in re_fn.captures_iter(source) {
+        let whole = captures.get(0).unwrap();
+        let Some(params_end) = closing_paren(source, whole.end() - 1) else { continue };
+        let params = &source[whole.end()..params_end];
+        // Trait methods and extern declarations have no body
+        let Some(open) = source[params_end..].find(['{', ';']).map(|i| params_end + i) else { continue };
+        if source.as_bytes()[open] != b'{' {
+            continue;
+        }
+        let Some(body) = block_body(source, open) else { continue };
+
+        let params: Vec<(&str, &str)> = top_level_split(params, ',').into_iter()
+            .filter_map(|param| re_param.captures(param))
+            .map(|c| (c.get(1).unwrap().as_str(), c.get(2).unwrap().as_str()))
+            .collect();
+        let anchor_handler = params.iter().any(|(_, ty)| ty.contains("Context<"));
+        let tainted: HashSet<String> = params.iter()
+            .filter(|(_, ty)| if anchor_handler { !ty.contains("Context<") } else { ty.replace(' ', "") == "&[u8]" })
+            .map(|(name, _)| name.to_string())
+            .collect();
+        if tainted.is_empty() {
+            continue;
+        }
+
+        let first_line = source[..open].lines().count() as u32;
+        for (offset, variable, sink) in trace(body, tainted) {
+            findings.push(TaintFinding {
+                file: path.to_string(),
+                line: first_line + offset,
+                function: captures[1].to_string(),
+                variable,
+                sink,
+            });
+        }
+    }
+    findings
+}
+
+// Walk a function body line by line, growing and narrowing the tainted set and reporting
+// sinks as (line offset from the opening brace, variable, sink)
+fn trace(body: &str, mut tainted: HashSet<String>) -> Vec<(u32, String, Sink)> {
+    let re_let = Regex::new(r"\blet\s+(?:mut\s+)?(\w+)\b[^=]*=([^=].*)$").unwrap();
+    let re_let_tuple = Regex::new(r"\blet\s+\(([^)]*)\)\s*(?::[^=]*)?=([^=].*)$").unwrap();
+    let re_assign = Regex::new(r"^\s*(\w+)\s*=([^=].*)$").unwrap();
+    let re_guard = Regex::new(r"\b(?:require\w*!|assert\w*!|if)").unwrap();
+
+    let mut patterns: HashMap<String, Patterns> = HashMap::new();
+    let mut sinks = Vec::new();
+    for (index, line) in body.lines().enumerate() {
+        let line = line.split("//").next().unwrap_or_default();
+        if line.trim().is_empty() {
+            continue;
+        }
+        // Arrows aren't subtraction or comparison
+        let code = line.replace("->", "  ").replace("=>", "  ");
+        let checked = ["checked_", "saturating_", "wrapping_", "overflowing_"].iter().any(|op| code.contains(op));
+
+        let mut variables: Vec<&String> = tainted.iter().collect();
+        variables.sort();
+        let mut reported = Vec::new();
+        for variable in variables {
+            let p = patterns.entry(variable.clone()).or_insert_with(|| Patterns::new(variable));
+            for (sink, re) in [(Sink::Arithmetic, &p.arithmetic), (Sink::Index, &p.index), (Sink::Cast, &p.cast)] {
+                if (sink == Sink::Arithmetic && checked) || reported.contains(&sink) {
+                    continue;
+                }
+                if re.is_match(&code) {
+                    reported.push(sink);
+                    sinks.push((index as u32, variable.clone(), sink));
+                }
+            }
+        }
+
+        let mentions = |expr: &str, patterns: &mut HashMap<String, Patterns>, variable: &String| {
+            patterns.entry(variable.clone()).or_insert_with(|| Patterns::new(variable)).mention.is_match(expr)
+        };
+
+        // A comparison in a guard bounds the variables in it from here on
+        if re_guard.is_match(&code) && code.contains(['<', '>']) {
+            tainted.retain(|v| !mentions(&code, &mut patterns, v));
+            continue;
+        }
+
+        let (names, expr): (Vec<String>, &str) = if let Some(c) = re_let_tuple.captures(&code) {
+            let names = c.get(1).unwrap().as_str().split(',')
+                .map(|n| n.trim().trim_start_matches("mut ").trim().to_string())
+                .filter(|n| !n.is_empty() && n != "_")
+                .collect();
+            (names, c.get(2).unwrap().as_str())
+        } else if let Some(c) = re_let.captures(&code).or_else(|| re_assign.captures(&code)) {
+            (vec![c[1].to_string()], c.get(2).unwrap().as_str())
+        } else {
+            continue;
+        };
+        if tainted.iter().any(|v| mentions(expr, &mut patterns, v)) {
+            tainted.extend(names);
+        } else if code.trim_start().starts_with("let") {
+            // Shadowed by an untainted value
+            for name in names {
+                tainted.remove(&name);
+            }
+        }
+    }
+    sinks
+}
+
+// Per-variable patterns, compiled once per function
+struct Patterns {
+    mention: Regex,
+    arithmetic: Regex,
+    index: Regex,
+    cast: Regex,
+}
+
+impl Patterns {
+    fn new(variable: &str) -> Self {
+        let v = regex::escape(variable);
+        Self {
+            mention: Regex::new(&format!(r"\b{v}\b")).unwrap(),
+            // An operand of +, -, * or their compound assignments; a * without a left operand is a deref
+            arithmetic: Regex::new(&format!(r"\b{v}\s*[-+*]=?\s*[\w(]|[\w)\]]\s*[-+*]=?\s*\b{v}\b")).unwrap(),
+            // Indexing into the variable, or with it
+            index: Regex::new(&format!(r"\b{v}\s*\[|\w\s*\[[^\]]*\b{v}\b[^\]]*\]")).unwrap(),
+            cast: Regex::new(&format!(r"\b{v}\s+as\s+(?:{NARROW_INTS})\b")).unwrap(),
+        }
+    }
+}
+
+// Index of the `)` matching the `(` at `open`
+fn closing_paren(source: &str, open: usize) -> Option<usize> {
+    let mut depth = 0;
+    for (i, c) in source[open..].char_indices() {
+        match c {
+            '(' => depth += 1,
+            ')' => {
+                depth -= 1;
+                if depth == 0 {
+                    return Some(open + i);
+                }
+            },
+            _ => {},
+        }
+    }
+    None
+}
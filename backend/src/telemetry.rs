@@ -0,0 +1,54 @@
+use anyhow::Result;
+use opentelemetry::trace::TracerProvider as _;
+use opentelemetry::KeyValue;
+use opentelemetry_otlp::SpanExporter;
+use opentelemetry_sdk::trace::SdkTracerProvider;
+use opentelemetry_sdk::Resource;
+use std::env;
+use tracing_subscriber::layer::SubscriberExt;
+use tracing_subscriber::util::SubscriberInitExt;
+use tracing_subscriber::EnvFilter;
+
+// Holds the tracer provider so buffered spans can be flushed on shutdown
+pub struct Telemetry {
+    provider: Option<SdkTracerProvider>,
+}
+
+impl Telemetry {
+    // Spans are exported over OTLP/HTTP when OTEL_EXPORTER_OTLP_ENDPOINT is set
+    // (standard OTEL_* variables apply); otherwise they are no-ops
+    pub fn init() -> Result<Self> {
+        if env::var("OTEL_EXPORTER_OTLP_ENDPOINT").is_err() && env::var("OTEL_EXPORTER_OTLP_TRACES_ENDPOINT").is_err() {
+            println!("No OTEL_EXPORTER_OTLP_ENDPOINT configured, tracing disabled");
+            return Ok(Self { provider: None });
+        }
+
+        let service_name = env::var("OTEL_SERVICE_NAME").unwrap_or_else(|_| "safex-backend".to_string());
+        let exporter = SpanExporter::builder().with_http().build()?;
+        let provider = SdkTracerProvider::builder()
+            .with_batch_exporter(exporter)
+            .with_resource(Resource::builder()
+                .with_service_name(service_name.clone())
+                .with_attribute(KeyValue::new("service.version", env!("CARGO_PKG_VERSION")))
+                .build())
+            .build();
+        opentelemetry::global::set_tracer_provider(provider.clone());
+
+        let filter = EnvFilter::try_from_env("SAFEX_TRACE_FILTER").unwrap_or_else(|_| EnvFilter::new("info"));
+        tracing_subscriber::registry()
+            .with(filter)
+            .with(tracing_opentelemetry::layer().with_tracer(provider.tracer(service_name.clone())))
+            .try_init()?;
+
+        println!("Exporting traces over OTLP as {}", service_name);
+        Ok(Self { provider: Some(provider) })
+    }
+
+    pub fn shutdown(&self) {
+        if let Some(provider) = &self.provider {
+            if let Err(e) = provider.shutdown() {
+                println!("Warning: Failed to flush traces: {}", e);
+            }
+        }
+    }
+}
@@ -0,0 +1,94 @@
+use anyhow::{anyhow, Result};
+use futures_util::StreamExt;
+use rustls::ServerConfig;
+use rustls_acme::acme::ACME_TLS_ALPN_NAME;
+use rustls_acme::caches::DirCache;
+use rustls_acme::AcmeConfig;
+use std::env;
+use std::fs::File;
+use std::io::BufReader;
+
+// Offered to clients in order of preference; HTTP/2 is negotiated through ALPN
+const ALPN_PROTOCOLS: &[&[u8]] = &[b"h2", b"http/1.1"];
+
+// TLS for deployments that serve the API without a reverse proxy in front. Either a
+// certificate and key from disk (SAFEX_TLS_CERT and SAFEX_TLS_KEY, PEM) or certificates
+// obtained and renewed from Let's Encrypt for SAFEX_TLS_ACME_DOMAINS. Without either the
+// server speaks plain HTTP.
+pub fn server_config_from_env() -> Result<Option<ServerConfig>> {
+    let cert = env::var("SAFEX_TLS_CERT").ok();
+    let key = env::var("SAFEX_TLS_KEY").ok();
+    let acme_domains = env::var("SAFEX_TLS_ACME_DOMAINS").ok();
+    match (cert, key, acme_domains) {
+        (None, None, None) => Ok(None),
+        (Some(_), _, Some(_)) | (_, Some(_), Some(_)) => {
+            Err(anyhow!("Set either SAFEX_TLS_CERT and SAFEX_TLS_KEY or SAFEX_TLS_ACME_DOMAINS, not both"))
+        },
+        (Some(cert), Some(key), None) => file_config(&cert, &key).map(Some),
+        (None, None, Some(domains)) => acme_config(&domains).map(Some),
+        _ => Err(anyhow!("SAFEX_TLS_CERT and SAFEX_TLS_KEY must be set together")),
+    }
+}
+
+fn file_config(cert_path: &str, key_path: &str) -> Result<ServerConfig> {
+    let file = File::open(cert_path).map_err(|e| anyhow!("Failed to open TLS certificate {}: {}", cert_path, e))?;
+    let certs = rustls_pemfile::certs(&mut BufReader::new(file))
+        .collect::<Result<Vec<_>, _>>()
+        .map_err(|e| anyhow!("Failed to read TLS certificate {}: {}", cert_path, e))?;
+    if certs.is_empty() {
+        return Err(anyhow!("No certificates found in {}", cert_path));
+    }
+    let file = File::open(key_path).map_err(|e| anyhow!("Failed to open TLS key {}: {}", key_path, e))?;
+    let key = rustls_pemfile::private_key(&mut BufReader::new(file))
+        .map_err(|e| anyhow!("Failed to read TLS key {}: {}", key_path, e))?
+        .ok_or_else(|| anyhow!("No private key found in {}", key_path))?;
+
+    let mut config = ServerConfig::builder()
+        .with_no_client_auth()
+        .with_single_cert(certs, key)
+        .map_err(|e| anyhow!("Invalid TLS certificate or key: {}", e))?;
+    config.alpn_protocols = ALPN_PROTOCOLS.iter().map(|p| p.to_vec()).collect();
+    Ok(config)
+}
+
+// Certificates are validated through the TLS-ALPN-01 challenge, so the server must be reachable
+// on port 443 for every domain. Issued certificates and the account key are kept in
+// SAFEX_TLS_ACME_CACHE_DIR and renewed in the background before they expire. Let's Encrypt's
+// staging directory is used unless SAFEX_TLS_ACME_PRODUCTION is set, as its rate limits are
+// easy to hit while setting up.
+fn acme_config(domains: &str) -> Result<ServerConfig> {
+    let domains: Vec<String> = domains.split(',')
+        .map(str::trim)
+        .filter(|domain| !domain.is_empty())
+        .map(str::to_string)
+        .collect();
+    if domains.is_empty() {
+        return Err(anyhow!("SAFEX_TLS_ACME_DOMAINS lists no domains"));
+    }
+    let contact = env::var("SAFEX_TLS_ACME_CONTACT").ok().filter(|c| !c.is_empty());
+    let cache_dir = env::var("SAFEX_TLS_ACME_CACHE_DIR").unwrap_or_else(|_| "acme-cache".to_string());
+    let production = env::var("SAFEX_TLS_ACME_PRODUCTION").is_ok_and(|v| v == "true" || v == "1");
+
+    let mut state = AcmeConfig::new(&domains)
+        .contact(contact.iter().map(|email| format!("mailto:{}", email)))
+        .cache(DirCache::new(cache_dir))
+        .directory_lets_encrypt(production)
+        .state();
+    let mut config = ServerConfig::builder()
+        .with_no_client_auth()
+        .with_cert_resolver(state.resolver());
+    config.alpn_protocols = ALPN_PROTOCOLS.iter().map(|p| p.to_vec()).collect();
+    config.alpn_protocols.push(ACME_TLS_ALPN_NAME.to_vec());
+
+    // Drives issuance and renewal; the resolver serves whatever certificate it last deployed
+    tokio::spawn(async move {
+        while let Some(event) = state.next().await {
+            match event {
+                Ok(event) => println!("ACME: {:?}", event),
+                Err(e) => println!("Warning: ACME certificate management failed: {}", e),
+            }
+        }
+    });
+    println!("Managing TLS certificates for {} through ACME", domains.join(", "));
+    Ok(config)
+}
@@ -0,0 +1,253 @@
+use actix_web::dev::Payload;
+use actix_web::http::StatusCode;
+use actix_web::{error, web, FromRequest, HttpRequest, HttpResponse};
+use futures_util::future::LocalBoxFuture;
+use regex::Regex;
+use serde::de::DeserializeOwned;
+use serde_json::{json, Value};
+use solana_sdk::pubkey::Pubkey;
+use std::borrow::Cow;
+use std::collections::BTreeMap;
+use std::ops::Deref;
+use std::sync::LazyLock;
+use validator::{Validate, ValidateUrl, ValidationError, ValidationErrors, ValidationErrorsKind};
+
+use crate::certificate::MAX_METADATA_URI_LEN;
+use crate::cors::{normalize_origin, ANY_ORIGIN};
+use crate::exclusions::{glob_regex, MAX_EXCLUDE_PATTERNS};
+use crate::fuzzer::MAX_SEED_ACCOUNTS;
+use crate::hashing::{decode_hex, SALT_LEN};
+use crate::models::FieldError;
+use crate::report_logger::{max_report_bytes, parse_commit_sha, parse_report_hash};
+use crate::rules::resolve_rule_set;
+
+const MAX_COMMENT_BYTES: usize = 10_000;
+// A single file; the snippet rules run inline and are linear in its size
+const MAX_SNIPPET_BYTES: usize = 512 * 1024;
+
+static IDENTIFIER: LazyLock<Regex> = LazyLock::new(|| Regex::new(r"^[A-Za-z_][A-Za-z0-9_]{0,63}$").unwrap());
+static JIRA_PROJECT_KEY: LazyLock<Regex> = LazyLock::new(|| Regex::new(r"^[A-Z][A-Z0-9_]{1,254}$").unwrap());
+
+// A JSON body that deserialized and passed its model's constraints. Either failure is a 422
+// listing each offending field; a body that isn't JSON at all stays a 400.
+pub struct Valid<T>(pub T);
+
+impl<T> Valid<T> {
+    pub fn into_inner(self) -> T {
+        self.0
+    }
+}
+
+impl<T> Deref for Valid<T> {
+    type Target = T;
+
+    fn deref(&self) -> &T {
+        &self.0
+    }
+}
+
+impl<T: DeserializeOwned + Validate + 'static> FromRequest for Valid<T> {
+    type Error = actix_web::Error;
+    type Future = LocalBoxFuture<'static, Result<Self, Self::Error>>;
+
+    fn from_request(req: &HttpRequest, payload: &mut Payload) -> Self::Future {
+        // Parsing into a Value first keeps the content type, size limit and syntax errors of
+        // the JsonConfig
+        let json = web::Json::<Value>::from_request(req, payload);
+        Box::pin(async move {
+            parse_valid(json.await?.into_inner()).map(Valid)
+        })
+    }
+}
+
+// Deserialize a JSON value into a request model and check its constraints
+pub fn parse_valid<T: DeserializeOwned + Validate>(value: Value) -> Result<T, actix_web::Error> {
+    let body: T = serde_path_to_error::deserialize(value).map_err(|e| {
+        let message = e.inner().to_string();
+        let field = match e.path().to_string() {
+            // Missing fields are reported against the object that lacks them
+            path if path == "." => message.split('`').nth(1).unwrap_or_default().to_string(),
+            path => path,
+        };
+        invalid(vec![FieldError { field, message }])
+    })?;
+    if let Err(errors) = body.validate() {
+        let mut fields = Vec::new();
+        flatten("", &errors, &mut fields);
+        fields.sort_by(|a, b| a.field.cmp(&b.field));
+        return Err(invalid(fields));
+    }
+    Ok(body)
+}
+
+// The same 422 for a field a handler finds invalid, where the limit depends on the caller
+pub fn invalid_field(field: &str, message: String) -> actix_web::Error {
+    invalid(vec![FieldError { field: field.to_string(), message }])
+}
+
+fn invalid(errors: Vec<FieldError>) -> actix_web::Error {
+    let summary: Vec<String> = errors.iter().map(|e| format!("{}: {}", e.field, e.message)).collect();
+    let message = format!("Invalid request body: {}", summary.join("; "));
+    let response = HttpResponse::build(StatusCode::UNPROCESSABLE_ENTITY)
+        .json(json!({ "success": false, "message": message, "errors": errors }));
+    error::InternalError::from_response(message, response).into()
+}
+
+// One entry per failed constraint, with nested structs and lists as dotted paths
+fn flatten(prefix: &str, errors: &ValidationErrors, out: &mut Vec<FieldError>) {
+    for (field, kind) in &errors.0 {
+        let path = if prefix.is_empty() { field.to_string() } else { format!("{}.{}", prefix, field) };
+        match kind {
+            ValidationErrorsKind::Field(failures) => out.extend(failures.iter().map(|failure| FieldError {
+                field: path.clone(),
+                message: failure.message.as_deref().map(str::to_string).unwrap_or_else(|| format!("failed the {} check", failure.code)),
+            })),
+            ValidationErrorsKind::Struct(nested) => flatten(&path, nested, out),
+            ValidationErrorsKind::List(items) => {
+                for (index, nested) in items {
+                    flatten(&format!("{}[{}]", path, index), nested, out);
+                }
+            },
+        }
+    }
+}
+
+fn failure(code: &'static str, message: String) -> ValidationError {
+    ValidationError::new(code).with_message(Cow::Owned(message))
+}
+
+// Instruction and account type names as Rust declares them
+pub fn identifier(value: &str) -> Result<(), ValidationError> {
+    if IDENTIFIER.is_match(value) {
+        Ok(())
+    } else {
+        Err(failure("identifier", "must be a Rust identifier of at most 64 characters (letters, digits and _)".to_string()))
+    }
+}
+
+// Hex SHA256, as reports are identified by
+pub fn hex_hash(value: &str) -> Result<(), ValidationError> {
+    parse_report_hash(value).map(|_| ()).map_err(|_| failure("hex_hash", "must be a 64-character hex SHA256 hash".to_string()))
+}
+
+// The hex salt a confidential report was logged with
+pub fn salt(value: &str) -> Result<(), ValidationError> {
+    match decode_hex(value) {
+        Some(salt) if salt.len() == SALT_LEN => Ok(()),
+        _ => Err(failure("salt", format!("must be {} hex characters", SALT_LEN * 2))),
+    }
+}
+
+// Sized in bytes, as it's hashed and stored, rather than in characters
+pub fn report_content(value: &str) -> Result<(), ValidationError> {
+    let max_bytes = max_report_bytes();
+    if value.is_empty() {
+        Err(failure("report_content", "must not be empty".to_string()))
+    } else if value.len() > max_bytes {
+        Err(failure("report_content", format!("is {} bytes, more than the {} allowed", value.len(), max_bytes)))
+    } else {
+        Ok(())
+    }
+}
+
+pub fn commit_sha(value: &str) -> Result<(), ValidationError> {
+    parse_commit_sha(value).map(|_| ()).map_err(|_| failure("commit_sha", "must be 40 hex characters".to_string()))
+}
+
+// A rule set that exists and is still supported
+pub fn ruleset_version(value: &str) -> Result<(), ValidationError> {
+    resolve_rule_set(value).map(|_| ()).map_err(|e| failure("ruleset_version", e.to_string()))
+}
+
+// Globs of paths to leave out of an analysis
+pub fn exclude_paths(value: &[String]) -> Result<(), ValidationError> {
+    if value.len() > MAX_EXCLUDE_PATTERNS {
+        return Err(failure("exclude_paths", format!("must list at most {} globs", MAX_EXCLUDE_PATTERNS)));
+    }
+    value.iter()
+        .try_for_each(|pattern| glob_regex(pattern).map(|_| ()))
+        .map_err(|e| failure("exclude_paths", e.to_string()))
+}
+
+// Instruction account names and the addresses to seed them from
+pub fn seed_accounts(value: &BTreeMap<String, String>) -> Result<(), ValidationError> {
+    if value.len() > MAX_SEED_ACCOUNTS {
+        return Err(failure("seed_accounts", format!("must name at most {} accounts", MAX_SEED_ACCOUNTS)));
+    }
+    for (name, address) in value {
+        if name.trim().is_empty() {
+            return Err(failure("seed_accounts", "account names must not be empty".to_string()));
+        }
+        if address.parse::<Pubkey>().is_err() {
+            return Err(failure("seed_accounts", format!("{} is not a valid address: {}", name, address)));
+        }
+    }
+    Ok(())
+}
+
+// A CORS origin, scheme://host[:port]. Allowing any origin is only possible through
+// SAFEX_CORS_ORIGINS.
+pub fn origin(value: &str) -> Result<(), ValidationError> {
+    if value.trim() == ANY_ORIGIN {
+        return Err(failure("origin", "Any origin (*) can only be allowed through SAFEX_CORS_ORIGINS".to_string()));
+    }
+    normalize_origin(value).map(|_| ()).map_err(|e| failure("origin", e.to_string()))
+}
+
+// Where a certificate's metadata JSON is hosted, short enough for the on-chain metadata
+pub fn metadata_uri(value: &str) -> Result<(), ValidationError> {
+    if value.len() > MAX_METADATA_URI_LEN {
+        Err(failure("metadata_uri", format!("must be at most {} bytes", MAX_METADATA_URI_LEN)))
+    } else if !value.validate_url() {
+        Err(failure("metadata_uri", "must be a URL".to_string()))
+    } else {
+        Ok(())
+    }
+}
+
+// The API token is sent to it, so only over TLS
+pub fn jira_base_url(value: &str) -> Result<(), ValidationError> {
+    if !value.validate_url() {
+        Err(failure("jira_base_url", "must be a URL".to_string()))
+    } else if !value.trim().starts_with("https://") {
+        Err(failure("jira_base_url", "must be an https URL".to_string()))
+    } else {
+        Ok(())
+    }
+}
+
+// Project keys as Jira requires them: an uppercase letter followed by uppercase letters, digits or _
+pub fn jira_project_key(value: &str) -> Result<(), ValidationError> {
+    if JIRA_PROJECT_KEY.is_match(value) {
+        Ok(())
+    } else {
+        Err(failure("jira_project_key", "must be a Jira project key such as SEC".to_string()))
+    }
+}
+
+// A tenant's report template must parse and render against a sample report
+pub fn report_template(value: &str) -> Result<(), ValidationError> {
+    crate::report::check_template(value).map_err(|e| failure("report_template", e.to_string()))
+}
+
+// Pasted source for the snippet analyzer
+pub fn snippet_source(value: &str) -> Result<(), ValidationError> {
+    if value.trim().is_empty() {
+        Err(failure("snippet_source", "must not be empty".to_string()))
+    } else if value.len() > MAX_SNIPPET_BYTES {
+        Err(failure("snippet_source", format!("must be at most {} bytes", MAX_SNIPPET_BYTES)))
+    } else {
+        Ok(())
+    }
+}
+
+// Comments on findings are plain text, kept to what fits in a report
+pub fn comment_body(value: &str) -> Result<(), ValidationError> {
+    if value.trim().is_empty() {
+        Err(failure("comment_body", "must not be empty".to_string()))
+    } else if value.len() > MAX_COMMENT_BYTES {
+        Err(failure("comment_body", format!("must be at most {} bytes", MAX_COMMENT_BYTES)))
+    } else {
+        Ok(())
+    }
+}
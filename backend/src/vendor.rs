@@ -0,0 +1,68 @@
+use anyhow::{anyhow, Result};
+use std::fs;
+use std::path::{Path, PathBuf};
+use toml::Table;
+
+// `cargo vendor` writes this into every crate it vendors
+const CHECKSUM_FILE: &str = ".cargo-checksum.json";
+
+// A crate copied in by `cargo vendor`: third-party code that file walks and lints skip
+pub fn is_vendored_crate(dir: &Path) -> bool {
+    dir.join(CHECKSUM_FILE).is_file()
+}
+
+// Same check for a repository-relative path in a tree listing
+pub fn is_vendored_checksum(path: &str) -> bool {
+    path == CHECKSUM_FILE || path.ends_with(&format!("/{}", CHECKSUM_FILE))
+}
+
+// Whether cargo in `dir` builds from vendored sources: its config replaces crates-io with a
+// `directory` source, as `[source.vendored-sources]` does in the config `cargo vendor` prints
+pub fn uses_vendored_sources(dir: &Path) -> bool {
+    let Some(config) = fs::read_to_string(config_path(dir)).ok().and_then(|c| c.parse::<Table>().ok()) else {
+        return false;
+    };
+    let sources = config.get("source").and_then(|s| s.as_table());
+    let replacement = sources
+        .and_then(|s| s.get("crates-io"))
+        .and_then(|c| c.get("replace-with"))
+        .and_then(|r| r.as_str());
+    match (sources, replacement) {
+        (Some(sources), Some(replacement)) => sources.get(replacement).and_then(|s| s.get("directory")).is_some(),
+        _ => false,
+    }
+}
+
+// Merge the source replacement `cargo vendor` printed into the cargo config in `dir`,
+// keeping everything else the repository configures
+pub fn use_vendored_sources(dir: &Path, vendor_config: &str) -> Result<()> {
+    let vendor_config: Table = vendor_config.parse().map_err(|e| anyhow!("Unexpected cargo vendor output: {}", e))?;
+    let vendor_sources = vendor_config.get("source").and_then(|s| s.as_table())
+        .ok_or_else(|| anyhow!("cargo vendor printed no source replacement"))?;
+
+    let path = config_path(dir);
+    let mut config: Table = match fs::read_to_string(&path) {
+        Ok(content) => content.parse().map_err(|e| anyhow!("Failed to parse {}: {}", path.display(), e))?,
+        Err(_) => Table::new(),
+    };
+    let sources = config.entry("source").or_insert_with(|| Table::new().into())
+        .as_table_mut()
+        .ok_or_else(|| anyhow!("`source` in {} is not a table", path.display()))?;
+    for (name, source) in vendor_sources {
+        sources.insert(name.clone(), source.clone());
+    }
+
+    fs::create_dir_all(dir.join(".cargo"))?;
+    fs::write(&path, toml::to_string(&config)?)?;
+    Ok(())
+}
+
+// Cargo reads the legacy extensionless `.cargo/config` when present
+fn config_path(dir: &Path) -> PathBuf {
+    let legacy = dir.join(".cargo").join("config");
+    if legacy.is_file() {
+        legacy
+    } else {
+        dir.join(".cargo").join("config.toml")
+    }
+}
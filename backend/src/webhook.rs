@@ -0,0 +1,30 @@
+use hmac::{Hmac, Mac};
+use sha2::Sha256;
+use std::env;
+
+use crate::hashing::decode_hex;
+
+// Who jobs queued by webhook deliveries are audited as
+pub const WEBHOOK_ACTOR: &str = "github-webhook";
+
+// Verifies GitHub webhook deliveries against the secret configured on the webhook
+pub struct WebhookSecret(String);
+
+impl WebhookSecret {
+    // GITHUB_WEBHOOK_SECRET; without it the webhook endpoint refuses every delivery
+    pub fn from_env() -> Option<Self> {
+        env::var("GITHUB_WEBHOOK_SECRET").ok().filter(|s| !s.is_empty()).map(Self)
+    }
+
+    // `signature` is the X-Hub-Signature-256 header, "sha256=<hex hmac of the body>"
+    pub fn verify(&self, body: &[u8], signature: &str) -> bool {
+        let Some(expected) = signature.strip_prefix("sha256=").and_then(decode_hex) else {
+            return false;
+        };
+        let Ok(mut mac) = Hmac::<Sha256>::new_from_slice(self.0.as_bytes()) else {
+            return false;
+        };
+        mac.update(body);
+        mac.verify_slice(&expected).is_ok()
+    }
+}
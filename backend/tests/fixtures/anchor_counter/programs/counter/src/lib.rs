@@ -0,0 +1,37 @@
+use anchor_lang::prelude::*;
+
+declare_id!("9mNvJtrBH1Vx5aU9DaAaS1THdCbpr7oXXNUjJHG1Pf9F");
+
+// No findings expected: every rule should stay quiet on this program
+#[program]
+pub mod counter {
+    use super::*;
+
+    pub fn increment(ctx: Context<Increment>, by: u64) -> Result<()> {
+        require!(by <= 100, CounterError::StepTooLarge);
+        let counter = &mut ctx.accounts.counter;
+        counter.count = counter.count.checked_add(by).ok_or(CounterError::Overflow)?;
+        Ok(())
+    }
+}
+
+#[derive(Accounts)]
+pub struct Increment<'info> {
+    #[account(mut, has_one = authority)]
+    pub counter: Account<'info, Counter>,
+    pub authority: Signer<'info>,
+}
+
+#[account]
+pub struct Counter {
+    pub authority: Pubkey,
+    pub count: u64,
+}
+
+#[error_code]
+pub enum CounterError {
+    #[msg("Step is larger than 100")]
+    StepTooLarge,
+    #[msg("Counter overflowed")]
+    Overflow,
+}
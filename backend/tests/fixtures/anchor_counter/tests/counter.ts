@@ -0,0 +1,11 @@
+import * as anchor from "@coral-xyz/anchor";
+import { Keypair } from "@solana/web3.js";
+
+describe("counter", () => {
+  const program = anchor.workspace.Counter;
+  const authority = Keypair.generate();
+
+  it("increments", async () => {
+    await program.methods.increment(new anchor.BN(1)).accounts({ authority: authority.publicKey }).signers([authority]).rpc();
+  });
+});
@@ -0,0 +1,46 @@
+use anchor_lang::prelude::*;
+use anchor_spl::token::Token;
+
+declare_id!("Fg6PaFpoGXkYsidMpWTK6W2BeZ7FEfcYkg476zPFsLnS"); // safex-expect: workspace-reserved-program-id
+
+#[program]
+pub mod vault {
+    use super::*;
+
+    pub fn deposit(ctx: Context<Deposit>, amount: u64) -> Result<()> {
+        let vault = &mut ctx.accounts.vault;
+        vault.total = vault.total + amount; // safex-expect: taint-unchecked-arithmetic
+        Ok(())
+    }
+
+    pub fn set_fee(ctx: Context<Withdraw>, fee_bps: u64) -> Result<()> {
+        ctx.accounts.vault.fee_bps = fee_bps as u16; // safex-expect: taint-truncating-cast
+        Ok(())
+    }
+}
+
+#[derive(Accounts)]
+pub struct Deposit<'info> {
+    #[account(mut)]
+    pub vault: Account<'info, Vault>,
+    pub depositor: Signer<'info>,
+    pub system_program: Program<'info, System>,
+    pub token_program: Program<'info, Token>, // safex-expect: cpi-target-not-allowed
+    pub oracle_program: Program<'info, PriceOracle>, // safex-expect: cpi-target-unresolved
+}
+
+// Anyone can pass the vault's authority without its signature
+#[derive(Accounts)]
+pub struct Withdraw<'info> { // safex-expect: anchor-missing-signer
+    #[account(mut, has_one = authority)]
+    pub vault: Account<'info, Vault>,
+    /// CHECK: only compared against vault.authority
+    pub authority: AccountInfo<'info>,
+}
+
+#[account]
+pub struct Vault {
+    pub authority: Pubkey,
+    pub total: u64,
+    pub fee_bps: u16,
+}
@@ -0,0 +1,14 @@
+import * as anchor from "@coral-xyz/anchor";
+import { Keypair } from "@solana/web3.js";
+
+const payer = Keypair.fromSecretKey(Uint8Array.from([174, 47, 154, 16, 202, 193, 206, 113])); // safex-expect: ts-inline-secret-key-array
+const treasury = "MASi45ub7Qe4ZE36UT5G6cU4ud8Fhhe4deS4F3cw9KTAb8dLcukC7edhDQ7cn5d4gEYkbUrMWeWQLGsCmrG6dLaY"; // safex-expect: ts-hardcoded-secret-key
+
+describe("vault", () => {
+  const program = anchor.workspace.Vault;
+
+  it("deposits", async () => {
+    program.methods.deposit(new anchor.BN(1)).accounts({ payer: payer.publicKey }).rpc(); // safex-expect: ts-unawaited-confirmation
+    await program.methods.setFee(new anchor.BN(30)).rpc({ skipPreflight: true }); // safex-expect: ts-skip-preflight
+  });
+});
@@ -0,0 +1,41 @@
+use borsh::{BorshDeserialize, BorshSerialize};
+use solana_program::{
+    account_info::{next_account_info, AccountInfo},
+    entrypoint,
+    entrypoint::ProgramResult,
+    program_error::ProgramError,
+    pubkey::Pubkey,
+};
+
+solana_program::declare_id!("4uQeVj5tqViQh7yWWGStvkEG1Zmhx6uasJtWCJziofM");
+
+entrypoint!(process_instruction);
+
+#[derive(BorshSerialize, BorshDeserialize)]
+pub struct Escrow {
+    pub maker: Pubkey,
+    pub amount: u64,
+}
+
+pub fn process_instruction(program_id: &Pubkey, accounts: &[AccountInfo], instruction_data: &[u8]) -> ProgramResult {
+    let accounts_iter = &mut accounts.iter();
+    let authority = next_account_info(accounts_iter)?; // safex-expect: native-missing-signer
+    let escrow = next_account_info(accounts_iter)?;
+    let vault = next_account_info(accounts_iter)?;
+
+    #[cfg(not(feature = "skip-owner-check"))] // safex-expect: config-feature-gated-check
+    if escrow.owner != program_id {
+        return Err(ProgramError::IncorrectProgramId);
+    }
+
+    let amount = u64::from_le_bytes(instruction_data[1..9].try_into().unwrap()); // safex-expect: taint-unchecked-index
+    let mut state = Escrow::try_from_slice(&escrow.data.borrow())?;
+    let vault_state = Escrow::try_from_slice(&vault.data.borrow())?; // safex-expect: native-missing-owner-check
+    let legacy = Escrow::try_from_slice_unchecked(&vault.data.borrow())?; // safex-expect: native-unchecked-deserialization
+    debug_assert!(legacy.amount == vault_state.amount);
+
+    state.maker = *authority.key;
+    state.amount = state.amount + amount; // safex-expect: taint-unchecked-arithmetic
+    state.serialize(&mut &mut escrow.data.borrow_mut()[..])?;
+    Ok(())
+}
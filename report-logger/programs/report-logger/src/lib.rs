@@ -1,4 +1,5 @@
 use anchor_lang::prelude::*;
+use anchor_lang::system_program;
 
 declare_id!("4L6BwTs3J5deHpTLSHGPZKQKn9uhLFMKnKjhjqeobQ26");
 
@@ -6,41 +7,299 @@ declare_id!("4L6BwTs3J5deHpTLSHGPZKQKn9uhLFMKnKjhjqeobQ26");
 pub mod report_logger {
     use super::*;
 
-    pub fn initialize(ctx: Context<Initialize>) -> Result<()> {
-        msg!("Report Logger initialized");
+    // Creates the registry config; the signer becomes its admin. Only the program's upgrade
+    // authority may, so nobody can take the config PDA first, and it can only be created
+    // once. A zero fee makes logging free.
+    pub fn initialize(ctx: Context<Initialize>, arbiter: Pubkey, fee_lamports: u64, treasury: Pubkey) -> Result<()> {
+        let config = &mut ctx.accounts.config;
+        config.admin = ctx.accounts.admin.key();
+        config.arbiter = arbiter;
+        config.treasury = treasury;
+        config.fee_lamports = fee_lamports;
+        config.bump = ctx.bumps.config;
+
+        msg!("Report Logger initialized by {} with fee {} lamports", config.admin, fee_lamports);
+        Ok(())
+    }
+
+    // The admin changes the registry settings, including handing over the admin role
+    pub fn update_config(ctx: Context<UpdateConfig>, admin: Pubkey, arbiter: Pubkey, fee_lamports: u64, treasury: Pubkey) -> Result<()> {
+        let config = &mut ctx.accounts.config;
+        config.admin = admin;
+        config.arbiter = arbiter;
+        config.treasury = treasury;
+        config.fee_lamports = fee_lamports;
+
+        msg!("Report Logger config updated, fee {} lamports", fee_lamports);
         Ok(())
     }
 
-    pub fn log_report(ctx: Context<LogReport>, hash: [u8; 32]) -> Result<()> {
+    pub fn log_report(ctx: Context<LogReport>, hash: [u8; 32], repo_hash: [u8; 32], commit: [u8; 20]) -> Result<()> {
+        let fee_lamports = ctx.accounts.config.fee_lamports;
+        if fee_lamports > 0 {
+            let transfer = system_program::Transfer {
+                from: ctx.accounts.authority.to_account_info(),
+                to: ctx.accounts.treasury.to_account_info(),
+            };
+            system_program::transfer(CpiContext::new(ctx.accounts.system_program.to_account_info(), transfer), fee_lamports)?;
+        }
+
         let report = &mut ctx.accounts.report;
         report.authority = ctx.accounts.authority.key();
         report.hash = hash;
         report.timestamp = Clock::get()?.unix_timestamp;
+        report.repo_hash = repo_hash;
+        report.commit = commit;
+        report.status = ReportStatus::Active;
         
-        msg!("Report logged with hash: {:?}", hash);
+        msg!("Report logged with hash: {:?}, commit: {:?}", hash, commit);
+        emit!(ReportLogged {
+            report: report.key(),
+            authority: report.authority,
+            hash,
+            repo_hash,
+            commit,
+            timestamp: report.timestamp,
+        });
+        Ok(())
+    }
+
+    // Fails unless `report` is an active report with this hash, so a transaction can depend on
+    // the report having been logged; certificate mints start with it
+    pub fn assert_report(ctx: Context<AssertReport>, hash: [u8; 32]) -> Result<()> {
+        let report = &ctx.accounts.report;
+        require!(report.hash == hash, ReportLoggerError::HashMismatch);
+        require!(report.status == ReportStatus::Active, ReportLoggerError::ReportNotActive);
+        Ok(())
+    }
+
+    // A reviewer other than the report's authority endorses (or rejects) a logged report.
+    // One attestation per reviewer and report, at a PDA derived from both.
+    pub fn attest_report(ctx: Context<AttestReport>, verdict: Verdict) -> Result<()> {
+        let attestation = &mut ctx.accounts.attestation;
+        attestation.report = ctx.accounts.report.key();
+        attestation.attester = ctx.accounts.attester.key();
+        attestation.verdict = verdict;
+        attestation.timestamp = Clock::get()?.unix_timestamp;
+        attestation.bump = ctx.bumps.attestation;
+
+        msg!("Report {} attested by {}", attestation.report, attestation.attester);
+        Ok(())
+    }
+
+    // A reviewer who has attested to an active report flags it for the arbiter
+    pub fn dispute_report(ctx: Context<DisputeReport>) -> Result<()> {
+        let report = &mut ctx.accounts.report;
+        require!(report.status == ReportStatus::Active, ReportLoggerError::ReportNotActive);
+        report.status = ReportStatus::Disputed;
+
+        msg!("Report {} disputed by {}", report.key(), ctx.accounts.disputer.key());
+        emit!(ReportStatusChanged { report: report.key(), status: report.status });
+        Ok(())
+    }
+
+    // The arbiter settles a dispute: the report stands (Active), was replaced by a later
+    // report (Superseded), or is withdrawn (Revoked)
+    pub fn resolve_dispute(ctx: Context<ResolveDispute>, outcome: ReportStatus) -> Result<()> {
+        let report = &mut ctx.accounts.report;
+        require!(report.status == ReportStatus::Disputed, ReportLoggerError::ReportNotDisputed);
+        require!(outcome != ReportStatus::Disputed, ReportLoggerError::InvalidResolution);
+        report.status = outcome;
+
+        msg!("Dispute on report {} resolved as {:?}", report.key(), outcome);
+        emit!(ReportStatusChanged { report: report.key(), status: outcome });
         Ok(())
     }
 }
 
 #[derive(Accounts)]
-pub struct Initialize {}
+pub struct Initialize<'info> {
+    #[account(
+        init,
+        payer = admin,
+        space = 8 + Config::SIZE,
+        seeds = [b"config"],
+        bump
+    )]
+    pub config: Account<'info, Config>,
+    #[account(mut)]
+    pub admin: Signer<'info>,
+    #[account(constraint = program.programdata_address()? == Some(program_data.key()))]
+    pub program: Program<'info, crate::program::ReportLogger>,
+    #[account(constraint = program_data.upgrade_authority_address == Some(admin.key()) @ ReportLoggerError::NotUpgradeAuthority)]
+    pub program_data: Account<'info, ProgramData>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct UpdateConfig<'info> {
+    #[account(
+        mut,
+        seeds = [b"config"],
+        bump = config.bump,
+        has_one = admin @ ReportLoggerError::NotAdmin
+    )]
+    pub config: Account<'info, Config>,
+    pub admin: Signer<'info>,
+}
 
 #[derive(Accounts)]
 pub struct LogReport<'info> {
     #[account(
         init,
         payer = authority,
-        space = 8 + 32 + 32 + 8
+        space = 8 + Report::SIZE
     )]
     pub report: Account<'info, Report>,
     #[account(mut)]
     pub authority: Signer<'info>,
+    #[account(seeds = [b"config"], bump = config.bump)]
+    pub config: Account<'info, Config>,
+    /// CHECK: only receives the fee; must be the configured treasury
+    #[account(mut, address = config.treasury @ ReportLoggerError::WrongTreasury)]
+    pub treasury: UncheckedAccount<'info>,
     pub system_program: Program<'info, System>,
 }
 
+#[derive(Accounts)]
+pub struct AssertReport<'info> {
+    pub report: Account<'info, Report>,
+}
+
+#[derive(Accounts)]
+pub struct AttestReport<'info> {
+    #[account(constraint = report.authority != attester.key() @ ReportLoggerError::SelfAttestation)]
+    pub report: Account<'info, Report>,
+    #[account(
+        init,
+        payer = attester,
+        space = 8 + Attestation::SIZE,
+        seeds = [b"attestation", report.key().as_ref(), attester.key().as_ref()],
+        bump
+    )]
+    pub attestation: Account<'info, Attestation>,
+    #[account(mut)]
+    pub attester: Signer<'info>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct DisputeReport<'info> {
+    #[account(mut)]
+    pub report: Account<'info, Report>,
+    // Only reviewers with an attestation of this report may dispute it
+    #[account(
+        seeds = [b"attestation", report.key().as_ref(), disputer.key().as_ref()],
+        bump = attestation.bump
+    )]
+    pub attestation: Account<'info, Attestation>,
+    pub disputer: Signer<'info>,
+}
+
+#[derive(Accounts)]
+pub struct ResolveDispute<'info> {
+    #[account(mut)]
+    pub report: Account<'info, Report>,
+    #[account(
+        seeds = [b"config"],
+        bump = config.bump,
+        has_one = arbiter @ ReportLoggerError::NotArbiter
+    )]
+    pub config: Account<'info, Config>,
+    pub arbiter: Signer<'info>,
+}
+
+#[account]
+pub struct Config {
+    pub admin: Pubkey,        // 32 bytes
+    pub arbiter: Pubkey,      // 32 bytes
+    pub treasury: Pubkey,     // 32 bytes, receives the per-report fee
+    pub fee_lamports: u64,    // 8 bytes, 0 for free logging
+    pub bump: u8,             // 1 byte
+}
+
+impl Config {
+    pub const SIZE: usize = 32 + 32 + 32 + 8 + 1;
+}
+
 #[account]
 pub struct Report {
     pub authority: Pubkey,    // 32 bytes
     pub hash: [u8; 32],       // 32 bytes
     pub timestamp: i64,       // 8 bytes
+    pub repo_hash: [u8; 32],  // 32 bytes, SHA256 of the canonical repository URL
+    pub commit: [u8; 20],     // 20 bytes, git commit SHA-1 the report covers
+    pub status: ReportStatus, // 1 byte
+}
+
+impl Report {
+    pub const SIZE: usize = 32 + 32 + 8 + 32 + 20 + 1;
+}
+
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, PartialEq, Eq, Debug)]
+pub enum ReportStatus {
+    Active,
+    Disputed,
+    Superseded,
+    Revoked,
+}
+
+// Events let indexers follow the registry from transaction logs instead of polling accounts
+
+#[event]
+pub struct ReportLogged {
+    pub report: Pubkey,
+    pub authority: Pubkey,
+    pub hash: [u8; 32],
+    pub repo_hash: [u8; 32],
+    pub commit: [u8; 20],
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct ReportStatusChanged {
+    pub report: Pubkey,
+    pub status: ReportStatus,
+}
+
+#[account]
+pub struct Attestation {
+    pub report: Pubkey,       // 32 bytes
+    pub attester: Pubkey,     // 32 bytes
+    pub verdict: Verdict,     // 1 byte
+    pub timestamp: i64,       // 8 bytes
+    pub bump: u8,             // 1 byte
+}
+
+impl Attestation {
+    pub const SIZE: usize = 32 + 32 + 1 + 8 + 1;
+}
+
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, PartialEq, Eq)]
+pub enum Verdict {
+    Endorse,
+    EndorseWithConcerns,
+    Reject,
+}
+
+#[error_code]
+pub enum ReportLoggerError {
+    #[msg("A report's authority cannot attest to their own report")]
+    SelfAttestation,
+    #[msg("Only active reports can be disputed")]
+    ReportNotActive,
+    #[msg("The report is not under dispute")]
+    ReportNotDisputed,
+    #[msg("A dispute must resolve to Active, Superseded or Revoked")]
+    InvalidResolution,
+    #[msg("Only the configured arbiter can resolve disputes")]
+    NotArbiter,
+    #[msg("Only the admin can update the config")]
+    NotAdmin,
+    #[msg("Only the program's upgrade authority can initialize the config")]
+    NotUpgradeAuthority,
+    #[msg("The report has a different hash")]
+    HashMismatch,
+    #[msg("The fee must be paid to the configured treasury")]
+    WrongTreasury,
 }
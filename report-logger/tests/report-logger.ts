@@ -10,7 +10,15 @@ describe("report-logger", () => {
 
   it("Is initialized!", async () => {
     // Add your test here.
-    const tx = await program.methods.initialize().rpc();
+    // The deploying wallet is the upgrade authority, the only signer allowed to initialize
+    const [programData] = anchor.web3.PublicKey.findProgramAddressSync(
+      [program.programId.toBuffer()],
+      new anchor.web3.PublicKey("BPFLoaderUpgradeab1e11111111111111111111111")
+    );
+    const tx = await program.methods
+      .initialize(anchor.getProvider().publicKey, new anchor.BN(0), anchor.getProvider().publicKey)
+      .accounts({ programData })
+      .rpc();
     console.log("Your transaction signature", tx);
   });
 });
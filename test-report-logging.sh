@@ -9,6 +9,6 @@ REPORT_CONTENT="{\"scan_id\":\"test-123\",\"repo\":\"test-repo\",\"findings\":[{
 # Send request to log the report
 curl -X POST http://127.0.0.1:8080/api/log-report \
   -H "Content-Type: application/json" \
-  -d "{\"report_content\":\"$REPORT_CONTENT\"}"
+  -d "{\"report_content\":\"$REPORT_CONTENT\",\"repo_url\":\"https://github.com/test/test-repo\",\"commit_sha\":\"0123456789abcdef0123456789abcdef01234567\"}"
 
 echo -e "\n\nDone!"
\ No newline at end of file